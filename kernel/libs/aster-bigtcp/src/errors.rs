@@ -9,6 +9,25 @@ pub enum BindError {
     InUse,
 }
 
+pub mod iface {
+    /// An error returned when configuring an [`Iface`].
+    ///
+    /// [`Iface`]: crate::iface::Iface
+    #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+    pub enum ConfigError {
+        /// The entry to add already exists.
+        AlreadyExists,
+        /// The entry to remove does not exist.
+        NotFound,
+        /// There is no room for more entries.
+        NoSpace,
+        /// The value is invalid.
+        InvalidValue,
+        /// The iface does not support the operation.
+        Unsupported,
+    }
+}
+
 pub mod tcp {
     /// An error returned by [`TcpListener::new_listen`].
     ///
@@ -112,3 +131,74 @@ pub mod udp {
         }
     }
 }
+
+pub mod icmp {
+    pub use smoltcp::socket::icmp::RecvError;
+
+    /// An error returned by [`IcmpSocket::send`].
+    ///
+    /// [`IcmpSocket::send`]: crate::socket::IcmpSocket::send
+    #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+    pub enum SendError {
+        Unaddressable,
+        BufferFull,
+        /// The packet is too large.
+        TooLarge,
+    }
+
+    impl From<smoltcp::socket::icmp::SendError> for SendError {
+        fn from(value: smoltcp::socket::icmp::SendError) -> Self {
+            match value {
+                smoltcp::socket::icmp::SendError::Unaddressable => Self::Unaddressable,
+                smoltcp::socket::icmp::SendError::BufferFull => Self::BufferFull,
+            }
+        }
+    }
+}
+
+pub mod raw {
+    pub use smoltcp::socket::raw::RecvError;
+
+    /// An error returned by [`RawIpSocket::send`].
+    ///
+    /// [`RawIpSocket::send`]: crate::socket::RawIpSocket::send
+    #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+    pub enum SendError {
+        /// There is no source address to send the packet from.
+        Unaddressable,
+        BufferFull,
+        /// The packet is too large.
+        TooLarge,
+    }
+
+    impl From<smoltcp::socket::raw::SendError> for SendError {
+        fn from(value: smoltcp::socket::raw::SendError) -> Self {
+            match value {
+                smoltcp::socket::raw::SendError::BufferFull => Self::BufferFull,
+            }
+        }
+    }
+}
+
+pub mod packet {
+    /// An error returned by [`PacketSocket::recv`].
+    ///
+    /// [`PacketSocket::recv`]: crate::socket::PacketSocket::recv
+    #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+    pub enum RecvError {
+        /// The receive queue is empty.
+        Exhausted,
+    }
+
+    /// An error returned by [`PacketSocket::send`].
+    ///
+    /// [`PacketSocket::send`]: crate::socket::PacketSocket::send
+    #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+    pub enum SendError {
+        BufferFull,
+        /// The frame is too large.
+        TooLarge,
+        /// The frame is too short to include the link-layer header.
+        TooShort,
+    }
+}
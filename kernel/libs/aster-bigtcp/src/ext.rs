@@ -18,5 +18,7 @@ pub trait Ext {
     type TcpEventObserver: SocketEventObserver + Clone;
 
     /// The type for UDP sockets to observe events.
+    ///
+    /// ICMP sockets and raw IP sockets, which are also datagram sockets, use the same type.
     type UdpEventObserver: SocketEventObserver;
 }
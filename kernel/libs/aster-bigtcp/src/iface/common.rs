@@ -6,7 +6,7 @@ use alloc::{
     sync::Arc,
     vec::Vec,
 };
-use core::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use core::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicUsize, Ordering};
 
 use aster_softirq::BottomHalfDisabled;
 use bitflags::bitflags;
@@ -15,20 +15,23 @@ use ostd::sync::{SpinLock, SpinLockGuard};
 use smoltcp::{
     iface::{packet::Packet, Context},
     phy::Device,
-    wire::{IpAddress, IpEndpoint, Ipv4Address, Ipv4Packet},
+    wire::{IpAddress, IpCidr, IpEndpoint, Ipv4Address, Ipv4Cidr, Ipv6Cidr, ETHERNET_HEADER_LEN},
 };
 
 use super::{
-    poll::{FnHelper, PollContext, SocketTableAction},
+    poll::{FnHelper, IpPacket, PollContext, SocketTableAction},
     poll_iface::PollableIface,
     port::BindPortConfig,
     time::get_network_timestamp,
     Iface,
 };
 use crate::{
-    errors::BindError,
+    errors::{iface::ConfigError, BindError},
     ext::Ext,
-    socket::{TcpListenerBg, UdpSocketBg},
+    socket::{
+        DhcpSocketBg, IcmpSocketBg, PacketSocketBg, PacketType, RawIpSocketBg, SocketInfo,
+        TcpListenerBg, UdpSocketBg,
+    },
     socket_table::SocketTable,
 };
 
@@ -36,12 +39,18 @@ pub struct IfaceCommon<E: Ext> {
     index: u32,
     name: String,
     type_: InterfaceType,
-    flags: InterfaceFlags,
+    flags: AtomicU32,
+    /// The number of users that put the iface into promiscuous mode.
+    promisc_count: AtomicUsize,
+    mtu: AtomicUsize,
+    max_mtu: usize,
 
     interface: SpinLock<PollableIface<E>, BottomHalfDisabled>,
-    used_ports: SpinLock<BTreeMap<u16, PortState>, BottomHalfDisabled>,
+    used_ports: SpinLock<BTreeMap<(IpAddress, u16), PortState>, BottomHalfDisabled>,
     sockets: SpinLock<SocketTable<E>, BottomHalfDisabled>,
+    packet_sockets: SpinLock<Vec<Arc<PacketSocketBg<E>>>, BottomHalfDisabled>,
     sched_poll: E::ScheduleNextPoll,
+    stats: AtomicIfaceStats,
 }
 
 impl<E: Ext> IfaceCommon<E> {
@@ -49,6 +58,7 @@ impl<E: Ext> IfaceCommon<E> {
         name: String,
         type_: InterfaceType,
         flags: InterfaceFlags,
+        mtu: usize,
         interface: smoltcp::iface::Interface,
         sched_poll: E::ScheduleNextPoll,
     ) -> Self {
@@ -58,11 +68,16 @@ impl<E: Ext> IfaceCommon<E> {
             index,
             name,
             type_,
-            flags,
+            flags: AtomicU32::new(flags.bits()),
+            promisc_count: AtomicUsize::new(0),
+            mtu: AtomicUsize::new(mtu),
+            max_mtu: mtu,
             interface: SpinLock::new(PollableIface::new(interface)),
             used_ports: SpinLock::new(BTreeMap::new()),
             sockets: SpinLock::new(SocketTable::new()),
+            packet_sockets: SpinLock::new(Vec::new()),
             sched_poll,
+            stats: AtomicIfaceStats::new(),
         }
     }
 
@@ -79,7 +94,51 @@ impl<E: Ext> IfaceCommon<E> {
     }
 
     pub(super) fn flags(&self) -> InterfaceFlags {
-        self.flags
+        let mut flags = InterfaceFlags::from_bits_truncate(self.flags.load(Ordering::Relaxed));
+        if self.promisc_count.load(Ordering::Relaxed) > 0 {
+            flags |= InterfaceFlags::PROMISC;
+        }
+        flags
+    }
+
+    pub(super) fn set_flags(&self, flags: InterfaceFlags) {
+        let changeable = InterfaceFlags::changeable();
+        let old_flags = InterfaceFlags::from_bits_truncate(self.flags.load(Ordering::Relaxed));
+
+        let mut new_flags = (old_flags - changeable) | (flags & changeable);
+        // There is no carrier detection, so the iface is running as long as it is up.
+        new_flags.set(
+            InterfaceFlags::RUNNING,
+            new_flags.contains(InterfaceFlags::UP),
+        );
+
+        self.flags.store(new_flags.bits(), Ordering::Relaxed);
+    }
+
+    pub(super) fn inc_promiscuity(&self) {
+        self.promisc_count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(super) fn dec_promiscuity(&self) {
+        let old_count = self.promisc_count.fetch_sub(1, Ordering::Relaxed);
+        debug_assert!(old_count > 0);
+    }
+
+    pub(super) fn mtu(&self) -> usize {
+        self.mtu.load(Ordering::Relaxed)
+    }
+
+    pub(super) fn max_mtu(&self) -> usize {
+        self.max_mtu
+    }
+
+    pub(super) fn set_mtu(&self, mtu: usize) -> Result<(), ConfigError> {
+        if !(MIN_MTU..=self.max_mtu).contains(&mtu) {
+            return Err(ConfigError::InvalidValue);
+        }
+
+        self.mtu.store(mtu, Ordering::Relaxed);
+        Ok(())
     }
 
     pub(super) fn ipv4_addr(&self) -> Option<Ipv4Address> {
@@ -93,6 +152,124 @@ impl<E: Ext> IfaceCommon<E> {
     pub(super) fn sched_poll(&self) -> &E::ScheduleNextPoll {
         &self.sched_poll
     }
+
+    pub(super) fn ipv4_gateway(&self) -> Option<Ipv4Address> {
+        self.interface.lock().ipv4_gateway()
+    }
+
+    pub(super) fn ipv4_cidrs(&self) -> Vec<Ipv4Cidr> {
+        self.interface.lock().ipv4_cidrs()
+    }
+
+    pub(super) fn add_ipv4_cidr(&self, cidr: Ipv4Cidr) -> Result<(), ConfigError> {
+        self.interface.lock().add_ip_cidr(IpCidr::Ipv4(cidr))
+    }
+
+    pub(super) fn del_ipv4_cidr(&self, cidr: Ipv4Cidr) -> Result<(), ConfigError> {
+        self.interface.lock().del_ip_cidr(IpCidr::Ipv4(cidr))
+    }
+
+    pub(super) fn ipv6_cidrs(&self) -> Vec<Ipv6Cidr> {
+        self.interface.lock().ipv6_cidrs()
+    }
+
+    pub(super) fn add_ipv6_cidr(&self, cidr: Ipv6Cidr) -> Result<(), ConfigError> {
+        self.interface.lock().add_ip_cidr(IpCidr::Ipv6(cidr))
+    }
+
+    pub(super) fn del_ipv6_cidr(&self, cidr: Ipv6Cidr) -> Result<(), ConfigError> {
+        self.interface.lock().del_ip_cidr(IpCidr::Ipv6(cidr))
+    }
+
+    pub(super) fn ipv4_routes(&self) -> Vec<Ipv4Route> {
+        self.interface.lock().ipv4_routes()
+    }
+
+    pub(super) fn add_ipv4_route(&self, route: Ipv4Route) -> Result<(), ConfigError> {
+        self.interface.lock().add_ipv4_route(route)
+    }
+
+    pub(super) fn del_ipv4_route(
+        &self,
+        cidr: Ipv4Cidr,
+        gateway: Option<Ipv4Address>,
+    ) -> Result<(), ConfigError> {
+        self.interface.lock().del_ipv4_route(cidr, gateway)
+    }
+
+    pub(super) fn join_ipv4_multicast_group(&self, group: Ipv4Address) {
+        self.interface.lock().join_ipv4_multicast_group(group)
+    }
+
+    pub(super) fn leave_ipv4_multicast_group(&self, group: Ipv4Address) {
+        self.interface.lock().leave_ipv4_multicast_group(group)
+    }
+
+    pub(super) fn stats(&self) -> IfaceStats {
+        self.stats.load()
+    }
+
+    /// Records a packet of `len` bytes received by the physical device.
+    pub(super) fn record_rx(&self, len: usize) {
+        self.stats.rx_packets.fetch_add(1, Ordering::Relaxed);
+        self.stats.rx_bytes.fetch_add(len as u64, Ordering::Relaxed);
+    }
+
+    /// Records a packet of `len` bytes transmitted by the physical device.
+    pub(super) fn record_tx(&self, len: usize) {
+        self.stats.tx_packets.fetch_add(1, Ordering::Relaxed);
+        self.stats.tx_bytes.fetch_add(len as u64, Ordering::Relaxed);
+    }
+}
+
+/// The minimum MTU of IPv4.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.0.18/source/include/uapi/linux/if_ether.h#L36>
+const MIN_MTU: usize = 68;
+
+/// An IPv4 route via a gateway.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ipv4Route {
+    /// The destination network.
+    pub cidr: Ipv4Cidr,
+    /// The gateway through which the destination network is reachable.
+    pub gateway: Ipv4Address,
+}
+
+/// Statistics of the packets that an iface has transmitted and received.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct IfaceStats {
+    pub rx_packets: u64,
+    pub rx_bytes: u64,
+    pub tx_packets: u64,
+    pub tx_bytes: u64,
+}
+
+struct AtomicIfaceStats {
+    rx_packets: AtomicU64,
+    rx_bytes: AtomicU64,
+    tx_packets: AtomicU64,
+    tx_bytes: AtomicU64,
+}
+
+impl AtomicIfaceStats {
+    const fn new() -> Self {
+        Self {
+            rx_packets: AtomicU64::new(0),
+            rx_bytes: AtomicU64::new(0),
+            tx_packets: AtomicU64::new(0),
+            tx_bytes: AtomicU64::new(0),
+        }
+    }
+
+    fn load(&self) -> IfaceStats {
+        IfaceStats {
+            rx_packets: self.rx_packets.load(Ordering::Relaxed),
+            rx_bytes: self.rx_bytes.load(Ordering::Relaxed),
+            tx_packets: self.tx_packets.load(Ordering::Relaxed),
+            tx_bytes: self.tx_bytes.load(Ordering::Relaxed),
+        }
+    }
 }
 
 /// An allocator that allocates a unique index for each interface.
@@ -100,7 +277,7 @@ impl<E: Ext> IfaceCommon<E> {
 // FIXME: This allocator is specific to each network namespace.
 pub static INTERFACE_INDEX_ALLOCATOR: AtomicU32 = AtomicU32::new(1);
 
-// Lock order: `interface` -> `sockets`
+// Lock order: `interface` -> `sockets` -> `packet_sockets`
 impl<E: Ext> IfaceCommon<E> {
     /// Acquires the lock to the interface.
     pub(crate) fn interface(&self) -> SpinLockGuard<'_, PollableIface<E>, BottomHalfDisabled> {
@@ -120,11 +297,13 @@ impl<E: Ext> IfaceCommon<E> {
     pub(super) fn bind(
         &self,
         iface: Arc<dyn Iface<E>>,
+        addr: IpAddress,
         config: BindPortConfig,
     ) -> core::result::Result<BoundPort<E>, BindError> {
-        let (port, can_reuse) = self.bind_port(config)?;
+        let (port, can_reuse) = self.bind_port(addr, config)?;
         Ok(BoundPort {
             iface,
+            addr,
             port,
             can_reuse: AtomicBool::new(can_reuse),
         })
@@ -136,11 +315,12 @@ impl<E: Ext> IfaceCommon<E> {
     ///
     /// See <https://en.wikipedia.org/wiki/Ephemeral_port>.
     fn alloc_ephemeral_port(
-        used_ports: &mut BTreeMap<u16, PortState>,
+        used_ports: &mut BTreeMap<(IpAddress, u16), PortState>,
+        addr: IpAddress,
         _can_reuse: bool,
     ) -> Option<u16> {
         for port in IP_LOCAL_PORT_START..=IP_LOCAL_PORT_END {
-            if let Entry::Vacant(..) = used_ports.entry(port) {
+            if let Entry::Vacant(..) = used_ports.entry((addr, port)) {
                 return Some(port);
             }
         }
@@ -151,20 +331,20 @@ impl<E: Ext> IfaceCommon<E> {
         None
     }
 
-    fn bind_port(&self, config: BindPortConfig) -> Result<(u16, bool), BindError> {
+    fn bind_port(&self, addr: IpAddress, config: BindPortConfig) -> Result<(u16, bool), BindError> {
         let mut used_ports = self.used_ports.lock();
         let config_can_reuse = config.can_reuse();
 
         let port = if let Some(port) = config.port() {
             port
         } else {
-            match Self::alloc_ephemeral_port(&mut used_ports, config_can_reuse) {
+            match Self::alloc_ephemeral_port(&mut used_ports, addr, config_can_reuse) {
                 Some(port) => port,
                 None => return Err(BindError::Exhausted),
             }
         };
 
-        if let Some(port_state) = used_ports.get_mut(&port) {
+        if let Some(port_state) = used_ports.get_mut(&(addr, port)) {
             // FIXME: If the socket is not a backlog socket,
             // we should check whether there is a listening socket on the port.
             // If there is, the socket cannot be bound to that port.
@@ -180,16 +360,16 @@ impl<E: Ext> IfaceCommon<E> {
             }
         } else {
             let port_state = PortState::new(config_can_reuse);
-            used_ports.insert(port, port_state);
+            used_ports.insert((addr, port), port_state);
         };
 
         Ok((port, config_can_reuse))
     }
 
     /// Releases the port so that it can be used again.
-    fn release_port(&self, port: u16, can_reuse: bool) {
+    fn release_port(&self, addr: IpAddress, port: u16, can_reuse: bool) {
         let mut used_ports = self.used_ports.lock();
-        if let Entry::Occupied(mut entry) = used_ports.entry(port) {
+        if let Entry::Occupied(mut entry) = used_ports.entry((addr, port)) {
             let port_state = entry.get_mut();
             port_state.nsocket -= 1;
             if can_reuse {
@@ -208,6 +388,24 @@ impl<E: Ext> IfaceCommon<E> {
         sockets.insert_udp_socket(socket);
     }
 
+    pub(crate) fn register_icmp_socket(&self, socket: Arc<IcmpSocketBg<E>>) {
+        let mut sockets = self.sockets.lock();
+        sockets.insert_icmp_socket(socket);
+    }
+
+    pub(crate) fn register_raw_ip_socket(&self, socket: Arc<RawIpSocketBg<E>>) {
+        let mut sockets = self.sockets.lock();
+        sockets.insert_raw_ip_socket(socket);
+    }
+
+    pub(crate) fn register_dhcp_socket(
+        &self,
+        socket: Arc<DhcpSocketBg<E>>,
+    ) -> Result<(), Arc<DhcpSocketBg<E>>> {
+        let mut sockets = self.sockets.lock();
+        sockets.insert_dhcp_socket(socket)
+    }
+
     pub(crate) fn remove_tcp_listener(&self, socket: &Arc<TcpListenerBg<E>>) {
         let mut sockets = self.sockets.lock();
         let removed = sockets.remove_listener(socket.listener_key());
@@ -219,6 +417,55 @@ impl<E: Ext> IfaceCommon<E> {
         let removed = sockets.remove_udp_socket(socket);
         debug_assert!(removed.is_some());
     }
+
+    pub(crate) fn remove_icmp_socket(&self, socket: &Arc<IcmpSocketBg<E>>) {
+        let mut sockets = self.sockets.lock();
+        let removed = sockets.remove_icmp_socket(socket);
+        debug_assert!(removed.is_some());
+    }
+
+    pub(crate) fn remove_raw_ip_socket(&self, socket: &Arc<RawIpSocketBg<E>>) {
+        let mut sockets = self.sockets.lock();
+        let removed = sockets.remove_raw_ip_socket(socket);
+        debug_assert!(removed.is_some());
+    }
+
+    pub(crate) fn remove_dhcp_socket(&self, socket: &Arc<DhcpSocketBg<E>>) {
+        let mut sockets = self.sockets.lock();
+        let removed = sockets.remove_dhcp_socket(socket);
+        debug_assert!(removed.is_some());
+    }
+
+    pub(crate) fn register_packet_socket(&self, socket: Arc<PacketSocketBg<E>>) {
+        let mut packet_sockets = self.packet_sockets.lock();
+        debug_assert!(!packet_sockets
+            .iter()
+            .any(|packet_socket| Arc::ptr_eq(packet_socket, &socket)));
+        packet_sockets.push(socket);
+    }
+
+    pub(crate) fn remove_packet_socket(&self, socket: &Arc<PacketSocketBg<E>>) {
+        let mut packet_sockets = self.packet_sockets.lock();
+        let index = packet_sockets
+            .iter()
+            .position(|packet_socket| Arc::ptr_eq(packet_socket, socket));
+        debug_assert!(index.is_some());
+        if let Some(index) = index {
+            packet_sockets.swap_remove(index);
+        }
+    }
+
+    pub(super) fn socket_infos(&self) -> Vec<SocketInfo> {
+        let sockets = self.sockets.lock();
+
+        let listeners = sockets.listener_iter().map(|listener| listener.info());
+        let connections = sockets
+            .connection_iter()
+            .map(|connection| connection.info());
+        let udp_sockets = sockets.udp_socket_iter().map(|socket| socket.info());
+
+        listeners.chain(connections).chain(udp_sockets).collect()
+    }
 }
 
 impl<E: Ext> IfaceCommon<E> {
@@ -233,11 +480,17 @@ impl<E: Ext> IfaceCommon<E> {
         P: for<'pkt, 'cx, 'tx> FnHelper<
             &'pkt [u8],
             &'cx mut Context,
+            &'cx [IpCidr],
             D::TxToken<'tx>,
-            Option<(Ipv4Packet<&'pkt [u8]>, D::TxToken<'tx>)>,
+            Option<(IpPacket<'pkt>, D::TxToken<'tx>)>,
         >,
         Q: FnMut(&Packet, &mut Context, D::TxToken<'_>),
     {
+        // An iface that is down neither transmits nor receives packets.
+        if !self.flags().contains(InterfaceFlags::UP) {
+            return None;
+        }
+
         let mut interface = self.interface();
         interface.context_mut().now = get_network_timestamp();
 
@@ -261,9 +514,74 @@ impl<E: Ext> IfaceCommon<E> {
             }
         }
 
-        // Note that only TCP connections can have timers set, so as far as the time to poll is
-        // concerned, we only need to consider TCP connections.
-        interface.next_poll_at_ms()
+        // Note that only TCP connections and the DHCP socket can have timers set, so as far as the
+        // time to poll is concerned, we only need to consider them.
+        let dhcp_poll_at_ms = sockets
+            .dhcp_socket()
+            .and_then(|socket| socket.next_poll_at_ms(interface.context_mut()));
+        match (interface.next_poll_at_ms(), dhcp_poll_at_ms) {
+            (Some(tcp_poll_at_ms), Some(dhcp_poll_at_ms)) => {
+                Some(tcp_poll_at_ms.min(dhcp_poll_at_ms))
+            }
+            (tcp_poll_at_ms, dhcp_poll_at_ms) => tcp_poll_at_ms.or(dhcp_poll_at_ms),
+        }
+    }
+
+    /// Returns whether there are packet sockets attached to the iface.
+    pub(super) fn has_packet_sockets(&self) -> bool {
+        !self.packet_sockets.lock().is_empty()
+    }
+
+    /// Delivers a copy of a link-layer frame that passes through the iface to packet sockets.
+    ///
+    /// Frames shorter than the Ethernet header are ignored.
+    pub(super) fn tap_frame(&self, frame: &[u8], packet_type: PacketType) {
+        if frame.len() < ETHERNET_HEADER_LEN {
+            return;
+        }
+
+        for socket in self.packet_sockets.lock().iter() {
+            socket.process(frame, packet_type);
+        }
+    }
+
+    /// Transmits the link-layer frames queued in packet sockets.
+    ///
+    /// `transmit` is called for each frame and returns `false` if the frame cannot be transmitted
+    /// now. Each transmitted frame is also delivered to other packet sockets as an outgoing frame.
+    pub(super) fn dispatch_frames<T>(&self, mut transmit: T)
+    where
+        T: FnMut(&[u8]) -> bool,
+    {
+        // An iface that is down transmits no frames.
+        if !self.flags().contains(InterfaceFlags::UP) {
+            return;
+        }
+
+        let sockets: Vec<_> = self
+            .packet_sockets
+            .lock()
+            .iter()
+            .filter(|socket| socket.need_dispatch())
+            .cloned()
+            .collect();
+
+        for socket in sockets.iter() {
+            socket.dispatch(|frame| {
+                if !transmit(frame) {
+                    return false;
+                }
+
+                self.record_tx(frame.len());
+                for other in self.packet_sockets.lock().iter() {
+                    if !Arc::ptr_eq(other, socket) {
+                        other.process(frame, PacketType::Outgoing);
+                    }
+                }
+
+                true
+            });
+        }
     }
 }
 
@@ -274,6 +592,7 @@ impl<E: Ext> IfaceCommon<E> {
 // FIXME: TCP and UDP ports are independent. Find a way to track the protocol here.
 pub struct BoundPort<E: Ext> {
     iface: Arc<dyn Iface<E>>,
+    addr: IpAddress,
     port: u16,
     can_reuse: AtomicBool,
 }
@@ -289,13 +608,14 @@ impl<E: Ext> BoundPort<E> {
         self.port
     }
 
+    /// Returns the bound address.
+    pub fn addr(&self) -> IpAddress {
+        self.addr
+    }
+
     /// Returns the bound endpoint.
-    pub fn endpoint(&self) -> Option<IpEndpoint> {
-        let ip_addr = {
-            let ipv4_addr = self.iface().ipv4_addr()?;
-            IpAddress::Ipv4(ipv4_addr)
-        };
-        Some(IpEndpoint::new(ip_addr, self.port))
+    pub fn endpoint(&self) -> IpEndpoint {
+        IpEndpoint::new(self.addr, self.port)
     }
 
     /// Sets whether the port can be reused.
@@ -307,7 +627,7 @@ impl<E: Ext> BoundPort<E> {
             return;
         }
 
-        if let Some(port_state) = used_ports.get_mut(&self.port) {
+        if let Some(port_state) = used_ports.get_mut(&(self.addr, self.port)) {
             if can_reuse {
                 port_state.nreuse += 1;
             } else {
@@ -323,7 +643,7 @@ impl<E: Ext> Drop for BoundPort<E> {
     fn drop(&mut self) {
         self.iface
             .common()
-            .release_port(self.port, *self.can_reuse.get_mut());
+            .release_port(self.addr, self.port, *self.can_reuse.get_mut());
     }
 }
 
@@ -419,3 +739,23 @@ bitflags! {
         const ECHO			    = 1<<18;
     }
 }
+
+impl InterfaceFlags {
+    /// Returns the flags that can be changed by the user space.
+    ///
+    /// Reference: <https://elixir.bootlin.com/linux/v6.0.18/source/net/core/dev.c#L8489>
+    pub const fn changeable() -> Self {
+        Self::from_bits_truncate(
+            Self::UP.bits()
+                | Self::DEBUG.bits()
+                | Self::NOTRAILERS.bits()
+                | Self::NOARP.bits()
+                | Self::PROMISC.bits()
+                | Self::ALLMULTI.bits()
+                | Self::MULTICAST.bits()
+                | Self::PORTSEL.bits()
+                | Self::AUTOMEDIA.bits()
+                | Self::DYNAMIC.bits(),
+        )
+    }
+}
@@ -1,11 +1,17 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use alloc::sync::Arc;
+use alloc::{sync::Arc, vec::Vec};
 
-use smoltcp::wire::Ipv4Address;
+use smoltcp::wire::{EthernetAddress, IpAddress, Ipv4Address, Ipv4Cidr, Ipv6Cidr};
 
-use super::{port::BindPortConfig, BoundPort, InterfaceFlags, InterfaceType};
-use crate::{errors::BindError, ext::Ext};
+use super::{
+    port::BindPortConfig, BoundPort, IfaceStats, InterfaceFlags, InterfaceType, Ipv4Route,
+};
+use crate::{
+    errors::{iface::ConfigError, BindError},
+    ext::Ext,
+    socket::SocketInfo,
+};
 
 /// A network interface.
 ///
@@ -17,15 +23,36 @@ pub trait Iface<E>: internal::IfaceInternal<E> + Send + Sync {
     /// Transmits or receives packets queued in the iface, and updates socket status accordingly.
     fn poll(&self);
 
-    /// Returns the maximum transmission unit.
-    fn mtu(&self) -> usize;
+    /// Returns the Ethernet address of the iface.
+    ///
+    /// Ifaces that have no link-layer addresses return `None`.
+    fn ether_addr(&self) -> Option<EthernetAddress> {
+        None
+    }
+
+    /// Returns the entries in the neighbor table.
+    ///
+    /// Each entry maps an IPv4 address to the Ethernet address of the neighbor. Ifaces that do
+    /// not resolve link-layer addresses have no neighbor table.
+    fn neighbors(&self) -> Vec<(Ipv4Address, EthernetAddress)> {
+        Vec::new()
+    }
+
+    /// Adds an entry to the neighbor table, or replaces the existing entry of the IPv4 address.
+    fn set_neighbor(
+        &self,
+        _ip_addr: Ipv4Address,
+        _ether_addr: EthernetAddress,
+    ) -> core::result::Result<(), ConfigError> {
+        Err(ConfigError::Unsupported)
+    }
 }
 
 impl<E: Ext> dyn Iface<E> {
-    /// Binds a socket to the iface.
+    /// Binds a socket to an address of the iface.
     ///
     /// After binding the socket to the iface, the iface will handle all packets to and from the
-    /// socket.
+    /// socket. Ports are allocated separately for each address of the iface.
     ///
     /// If [`BindPortConfig::Ephemeral`] is specified, the iface will pick up an ephemeral port for
     /// the socket.
@@ -35,10 +62,11 @@ impl<E: Ext> dyn Iface<E> {
     /// <https://github.com/smoltcp-rs/smoltcp/issues/779>.
     pub fn bind(
         self: &Arc<Self>,
+        addr: IpAddress,
         config: BindPortConfig,
     ) -> core::result::Result<BoundPort<E>, BindError> {
         let common = self.common();
-        common.bind(self.clone(), config)
+        common.bind(self.clone(), addr, config)
     }
 
     /// Returns the interface index.
@@ -63,6 +91,44 @@ impl<E: Ext> dyn Iface<E> {
         self.common().flags()
     }
 
+    /// Sets the interface flags.
+    ///
+    /// Only the flags in [`InterfaceFlags::changeable`] are changed. The others are ignored.
+    pub fn set_flags(&self, flags: InterfaceFlags) {
+        self.common().set_flags(flags)
+    }
+
+    /// Puts the iface into promiscuous mode on behalf of one more user.
+    ///
+    /// The iface stays in promiscuous mode until all users call [`Self::dec_promiscuity`]. The
+    /// [`InterfaceFlags::PROMISC`] flag is reported as long as there are such users.
+    pub fn inc_promiscuity(&self) {
+        self.common().inc_promiscuity()
+    }
+
+    /// Takes the iface out of promiscuous mode on behalf of one user.
+    pub fn dec_promiscuity(&self) {
+        self.common().dec_promiscuity()
+    }
+
+    /// Returns the maximum transmission unit.
+    pub fn mtu(&self) -> usize {
+        self.common().mtu()
+    }
+
+    /// Returns the largest maximum transmission unit that the underlying device supports.
+    pub fn max_mtu(&self) -> usize {
+        self.common().max_mtu()
+    }
+
+    /// Sets the maximum transmission unit.
+    ///
+    /// FIXME: smoltcp still uses the MTU of the underlying device to build packets, so a
+    /// smaller MTU is only reported to the user space.
+    pub fn set_mtu(&self, mtu: usize) -> core::result::Result<(), ConfigError> {
+        self.common().set_mtu(mtu)
+    }
+
     /// Gets the IPv4 address of the iface, if any.
     ///
     /// FIXME: One iface may have multiple IPv4 addresses.
@@ -78,10 +144,100 @@ impl<E: Ext> dyn Iface<E> {
         self.common().prefix_len()
     }
 
+    /// Gets the IPv4 address of the default gateway, if any.
+    pub fn ipv4_gateway(&self) -> Option<Ipv4Address> {
+        self.common().ipv4_gateway()
+    }
+
+    /// Returns all IPv4 addresses of the iface, along with their prefix lengths.
+    pub fn ipv4_cidrs(&self) -> Vec<Ipv4Cidr> {
+        self.common().ipv4_cidrs()
+    }
+
+    /// Adds an IPv4 address to the iface.
+    pub fn add_ipv4_cidr(&self, cidr: Ipv4Cidr) -> core::result::Result<(), ConfigError> {
+        self.common().add_ipv4_cidr(cidr)
+    }
+
+    /// Removes an IPv4 address from the iface.
+    pub fn del_ipv4_cidr(&self, cidr: Ipv4Cidr) -> core::result::Result<(), ConfigError> {
+        self.common().del_ipv4_cidr(cidr)
+    }
+
+    /// Returns all IPv6 addresses of the iface, along with their prefix lengths.
+    ///
+    /// This includes the link-local address if the iface has one.
+    pub fn ipv6_cidrs(&self) -> Vec<Ipv6Cidr> {
+        self.common().ipv6_cidrs()
+    }
+
+    /// Adds an IPv6 address to the iface.
+    pub fn add_ipv6_cidr(&self, cidr: Ipv6Cidr) -> core::result::Result<(), ConfigError> {
+        self.common().add_ipv6_cidr(cidr)
+    }
+
+    /// Removes an IPv6 address from the iface.
+    pub fn del_ipv6_cidr(&self, cidr: Ipv6Cidr) -> core::result::Result<(), ConfigError> {
+        self.common().del_ipv6_cidr(cidr)
+    }
+
+    /// Returns the IPv4 routes via gateways, including the default route.
+    ///
+    /// The routes to the networks that the iface is directly attached to are implied by the
+    /// IPv4 addresses of the iface and are not returned.
+    pub fn ipv4_routes(&self) -> Vec<Ipv4Route> {
+        self.common().ipv4_routes()
+    }
+
+    /// Adds an IPv4 route via a gateway.
+    pub fn add_ipv4_route(&self, route: Ipv4Route) -> core::result::Result<(), ConfigError> {
+        self.common().add_ipv4_route(route)
+    }
+
+    /// Removes the IPv4 route to `cidr`.
+    ///
+    /// If `gateway` is specified, the route is removed only if it is via the gateway.
+    pub fn del_ipv4_route(
+        &self,
+        cidr: Ipv4Cidr,
+        gateway: Option<Ipv4Address>,
+    ) -> core::result::Result<(), ConfigError> {
+        self.common().del_ipv4_route(cidr, gateway)
+    }
+
+    /// Joins the IPv4 multicast group on behalf of one more user.
+    ///
+    /// The iface reports the membership to the multicast routers when the first user joins the
+    /// group. Polling the iface is _always_ required after this method returns.
+    pub fn join_ipv4_multicast_group(&self, group: Ipv4Address) {
+        self.common().join_ipv4_multicast_group(group)
+    }
+
+    /// Leaves the IPv4 multicast group on behalf of one user.
+    ///
+    /// The iface stays in the group until all users leave it. Polling the iface is _always_
+    /// required after this method returns.
+    pub fn leave_ipv4_multicast_group(&self, group: Ipv4Address) {
+        self.common().leave_ipv4_multicast_group(group)
+    }
+
     /// Returns a reference to the associated [`ScheduleNextPoll`].
     pub fn sched_poll(&self) -> &E::ScheduleNextPoll {
         self.common().sched_poll()
     }
+
+    /// Returns the statistics of the packets transmitted and received by the iface.
+    ///
+    /// Packets that are delivered locally without going through the underlying device are not
+    /// counted.
+    pub fn stats(&self) -> IfaceStats {
+        self.common().stats()
+    }
+
+    /// Takes snapshots of all the TCP and UDP sockets bound to the iface.
+    pub fn socket_infos(&self) -> Vec<SocketInfo> {
+        self.common().socket_infos()
+    }
 }
 
 pub(super) mod internal {
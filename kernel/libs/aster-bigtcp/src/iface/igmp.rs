@@ -0,0 +1,171 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Host support for the Internet Group Management Protocol, version 2 (IGMPv2).
+//!
+//! An iface reports its IPv4 multicast group memberships to the multicast routers on the link,
+//! so that the multicast packets sent to the groups can be forwarded to the iface.
+//!
+//! Reference: <https://datatracker.ietf.org/doc/html/rfc2236>.
+
+use alloc::collections::{btree_map::BTreeMap, vec_deque::VecDeque};
+
+use smoltcp::wire::Ipv4Address;
+
+/// The length of IGMPv2 messages.
+pub(super) const IGMP_MESSAGE_LEN: usize = 8;
+
+/// The all-systems group, which every multicast-capable iface joins implicitly.
+const ALL_SYSTEMS_GROUP: Ipv4Address = Ipv4Address::new(224, 0, 0, 1);
+/// The all-routers group, to which the leave messages are sent.
+const ALL_ROUTERS_GROUP: Ipv4Address = Ipv4Address::new(224, 0, 0, 2);
+
+const TYPE_MEMBERSHIP_QUERY: u8 = 0x11;
+const TYPE_V2_MEMBERSHIP_REPORT: u8 = 0x16;
+const TYPE_LEAVE_GROUP: u8 = 0x17;
+
+/// The IGMP state of an iface.
+pub(crate) struct IgmpState {
+    /// The joined groups, along with the number of their users.
+    groups: BTreeMap<Ipv4Address, usize>,
+    /// The messages that are waiting to be sent.
+    pending: VecDeque<IgmpMessage>,
+}
+
+/// An IGMP message that is waiting to be sent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum IgmpMessage {
+    Report(Ipv4Address),
+    Leave(Ipv4Address),
+}
+
+impl IgmpState {
+    pub(super) const fn new() -> Self {
+        Self {
+            groups: BTreeMap::new(),
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Joins `group` on behalf of one more user.
+    ///
+    /// A membership report is sent when the first user joins the group.
+    pub(super) fn join(&mut self, group: Ipv4Address) {
+        let users = self.groups.entry(group).or_insert(0);
+        *users += 1;
+
+        if *users == 1 && group != ALL_SYSTEMS_GROUP {
+            self.queue(IgmpMessage::Report(group));
+        }
+    }
+
+    /// Leaves `group` on behalf of one user.
+    ///
+    /// A leave message is sent when the last user leaves the group.
+    pub(super) fn leave(&mut self, group: Ipv4Address) {
+        let Some(users) = self.groups.get_mut(&group) else {
+            return;
+        };
+
+        *users -= 1;
+        if *users > 0 {
+            return;
+        }
+
+        self.groups.remove(&group);
+        if group != ALL_SYSTEMS_GROUP {
+            self.queue(IgmpMessage::Leave(group));
+        }
+    }
+
+    /// Returns whether the iface belongs to `group`.
+    pub(super) fn has_group(&self, group: Ipv4Address) -> bool {
+        group == ALL_SYSTEMS_GROUP || self.groups.contains_key(&group)
+    }
+
+    /// Processes an incoming IGMP message.
+    ///
+    /// Only membership queries are processed. The reports of other hosts are ignored, so every
+    /// report is answered even if another member of the group has answered it.
+    pub(super) fn process(&mut self, payload: &[u8]) {
+        if payload.len() < IGMP_MESSAGE_LEN || checksum(payload) != 0 {
+            return;
+        }
+        if payload[0] != TYPE_MEMBERSHIP_QUERY {
+            return;
+        }
+
+        // TODO: Delay the reports by a random time up to the maximum response time, as RFC 2236
+        // requires. For now, the reports are sent immediately.
+        let group = Ipv4Address::new(payload[4], payload[5], payload[6], payload[7]);
+        if group.is_unspecified() {
+            let groups = self
+                .groups
+                .keys()
+                .copied()
+                .filter(|group| *group != ALL_SYSTEMS_GROUP);
+            self.pending.extend(groups.map(IgmpMessage::Report));
+        } else if self.groups.contains_key(&group) && group != ALL_SYSTEMS_GROUP {
+            self.queue(IgmpMessage::Report(group));
+        }
+    }
+
+    /// Takes the next message that is waiting to be sent.
+    pub(super) fn pop_pending(&mut self) -> Option<IgmpMessage> {
+        self.pending.pop_front()
+    }
+
+    fn queue(&mut self, message: IgmpMessage) {
+        if !self.pending.contains(&message) {
+            self.pending.push_back(message);
+        }
+    }
+}
+
+impl IgmpMessage {
+    /// Returns the destination address of the message.
+    pub(super) fn dst_addr(&self) -> Ipv4Address {
+        match self {
+            Self::Report(group) => *group,
+            Self::Leave(_) => ALL_ROUTERS_GROUP,
+        }
+    }
+
+    /// Encodes the message, including its checksum.
+    pub(super) fn to_bytes(self) -> [u8; IGMP_MESSAGE_LEN] {
+        let (type_, group) = match self {
+            Self::Report(group) => (TYPE_V2_MEMBERSHIP_REPORT, group),
+            Self::Leave(group) => (TYPE_LEAVE_GROUP, group),
+        };
+
+        let mut bytes = [0; IGMP_MESSAGE_LEN];
+        bytes[0] = type_;
+        bytes[4..8].copy_from_slice(&group.octets());
+
+        let checksum = checksum(&bytes);
+        bytes[2..4].copy_from_slice(&checksum.to_be_bytes());
+
+        bytes
+    }
+}
+
+/// Computes the Internet checksum of `data`.
+///
+/// The checksum of data that already contains a valid checksum is zero.
+///
+/// Reference: <https://datatracker.ietf.org/doc/html/rfc1071>.
+fn checksum(data: &[u8]) -> u16 {
+    let mut sum = data
+        .chunks(2)
+        .map(|chunk| match chunk {
+            [high, low] => u16::from_be_bytes([*high, *low]) as u32,
+            [high] => u16::from_be_bytes([*high, 0]) as u32,
+            _ => unreachable!(),
+        })
+        .sum::<u32>();
+
+    while sum > 0xffff {
+        sum = (sum >> 16) + (sum & 0xffff);
+    }
+
+    !(sum as u16)
+}
@@ -3,6 +3,7 @@
 mod common;
 #[expect(clippy::module_inception)]
 mod iface;
+mod igmp;
 mod phy;
 mod poll;
 mod poll_iface;
@@ -10,7 +11,7 @@ mod port;
 mod sched;
 mod time;
 
-pub use common::{BoundPort, InterfaceFlags, InterfaceType};
+pub use common::{BoundPort, IfaceStats, InterfaceFlags, InterfaceType, Ipv4Route};
 pub use iface::Iface;
 pub use phy::{EtherIface, IpIface};
 pub(crate) use poll_iface::{PollKey, PollableIfaceMut};
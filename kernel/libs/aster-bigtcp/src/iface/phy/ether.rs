@@ -1,27 +1,37 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use alloc::{collections::btree_map::BTreeMap, string::String, sync::Arc};
+use alloc::{collections::btree_map::BTreeMap, string::String, sync::Arc, vec::Vec};
 
 use aster_softirq::BottomHalfDisabled;
 use ostd::sync::SpinLock;
 use smoltcp::{
-    iface::{packet::Packet, Config, Context},
+    iface::{
+        packet::{IpPayload, Packet},
+        Config, Context,
+    },
     phy::{Device, DeviceCapabilities, TxToken},
     wire::{
         self, ArpOperation, ArpPacket, ArpRepr, EthernetAddress, EthernetFrame, EthernetProtocol,
-        EthernetRepr, IpAddress, Ipv4Address, Ipv4AddressExt, Ipv4Cidr, Ipv4Packet,
+        EthernetRepr, Icmpv6Message, Icmpv6Packet, Icmpv6Repr, IpAddress, IpCidr, IpProtocol,
+        IpRepr, Ipv4Address, Ipv4AddressExt, Ipv4Cidr, Ipv4Packet, Ipv6Address, Ipv6Cidr,
+        Ipv6Packet, Ipv6Repr, NdiscNeighborFlags, NdiscRepr, RawHardwareAddress,
+        IPV6_LINK_LOCAL_ALL_NODES,
     },
 };
 
 use crate::{
     device::{NotifyDevice, WithDevice},
+    errors::iface::ConfigError,
     ext::Ext,
     iface::{
         common::{IfaceCommon, InterfaceType},
         iface::internal::IfaceInternal,
+        poll::IpPacket,
+        poll_iface::solicited_node_addr,
         time::get_network_timestamp,
         Iface, InterfaceFlags, ScheduleNextPoll,
     },
+    socket::PacketType,
 };
 
 pub struct EtherIface<D, E: Ext> {
@@ -29,41 +39,68 @@ pub struct EtherIface<D, E: Ext> {
     common: IfaceCommon<E>,
     ether_addr: EthernetAddress,
     arp_table: SpinLock<BTreeMap<Ipv4Address, EthernetAddress>, BottomHalfDisabled>,
+    ndp_table: SpinLock<BTreeMap<Ipv6Address, EthernetAddress>, BottomHalfDisabled>,
+}
+
+/// A packet that resolves the link-layer addresses of neighbors.
+enum NeighborPacket {
+    Arp(ArpRepr),
+    /// A neighbor discovery message, along with its Ethernet destination and its IPv6 header.
+    Ndisc(EthernetAddress, Ipv6Repr, NdiscRepr<'static>),
 }
 
 impl<D: WithDevice, E: Ext> EtherIface<D, E> {
+    /// Creates a new Ethernet iface.
+    ///
+    /// The IPv4 address and the default gateway can be left unconfigured (e.g., if they will be
+    /// obtained via DHCP later).
     pub fn new(
         driver: D,
         ether_addr: EthernetAddress,
-        ip_cidr: Ipv4Cidr,
-        gateway: Ipv4Address,
+        ip_cidr: Option<Ipv4Cidr>,
+        gateway: Option<Ipv4Address>,
         name: String,
         sched_poll: E::ScheduleNextPoll,
         flags: InterfaceFlags,
     ) -> Arc<Self> {
-        let interface = driver.with(|device| {
+        let (interface, mtu) = driver.with(|device| {
             let config = Config::new(wire::HardwareAddress::Ethernet(ether_addr));
             let now = get_network_timestamp();
 
             let mut interface = smoltcp::iface::Interface::new(config, device, now);
             interface.update_ip_addrs(|ip_addrs| {
                 debug_assert!(ip_addrs.is_empty());
-                ip_addrs.push(wire::IpCidr::Ipv4(ip_cidr)).unwrap();
+                if let Some(ip_cidr) = ip_cidr {
+                    ip_addrs.push(wire::IpCidr::Ipv4(ip_cidr)).unwrap();
+                }
+                ip_addrs
+                    .push(wire::IpCidr::Ipv6(link_local_cidr(ether_addr)))
+                    .unwrap();
             });
-            interface
-                .routes_mut()
-                .add_default_ipv4_route(gateway)
-                .unwrap();
-            interface
+            if let Some(gateway) = gateway {
+                interface
+                    .routes_mut()
+                    .add_default_ipv4_route(gateway)
+                    .unwrap();
+            }
+            (interface, device.capabilities().max_transmission_unit)
         });
 
-        let common = IfaceCommon::new(name, InterfaceType::ETHER, flags, interface, sched_poll);
+        let common = IfaceCommon::new(
+            name,
+            InterfaceType::ETHER,
+            flags,
+            mtu,
+            interface,
+            sched_poll,
+        );
 
         Arc::new(Self {
             driver,
             common,
             ether_addr,
             arp_table: SpinLock::new(BTreeMap::new()),
+            ndp_table: SpinLock::new(BTreeMap::new()),
         })
     }
 }
@@ -82,17 +119,46 @@ where
         self.driver.with(|device| {
             let next_poll = self.common.poll(
                 &mut *device,
-                |data, iface_cx, tx_token| self.process(data, iface_cx, tx_token),
+                |data, iface_cx, ip_addrs, tx_token| {
+                    self.process(data, iface_cx, ip_addrs, tx_token)
+                },
                 |pkt, iface_cx, tx_token| self.dispatch(pkt, iface_cx, tx_token),
             );
+            self.common.dispatch_frames(|frame| {
+                let Some(tx_token) = device.transmit(get_network_timestamp()) else {
+                    return false;
+                };
+                tx_token.consume(frame.len(), |buffer| buffer.copy_from_slice(frame));
+                true
+            });
             device.notify_poll_end();
             self.common.sched_poll().schedule_next_poll(next_poll);
         });
     }
 
-    fn mtu(&self) -> usize {
-        self.driver
-            .with(|device| device.capabilities().max_transmission_unit)
+    fn ether_addr(&self) -> Option<EthernetAddress> {
+        Some(self.ether_addr)
+    }
+
+    fn neighbors(&self) -> Vec<(Ipv4Address, EthernetAddress)> {
+        self.arp_table
+            .lock()
+            .iter()
+            .map(|(ip_addr, ether_addr)| (*ip_addr, *ether_addr))
+            .collect()
+    }
+
+    fn set_neighbor(
+        &self,
+        ip_addr: Ipv4Address,
+        ether_addr: EthernetAddress,
+    ) -> Result<(), ConfigError> {
+        if !ether_addr.is_unicast() {
+            return Err(ConfigError::InvalidValue);
+        }
+
+        self.arp_table.lock().insert(ip_addr, ether_addr);
+        Ok(())
     }
 }
 
@@ -101,41 +167,77 @@ impl<D, E: Ext> EtherIface<D, E> {
         &self,
         data: &'pkt [u8],
         iface_cx: &mut Context,
+        ip_addrs: &[IpCidr],
         tx_token: T,
-    ) -> Option<(Ipv4Packet<&'pkt [u8]>, T)> {
-        match self.parse_ip_or_process_arp(data, iface_cx) {
+    ) -> Option<(IpPacket<'pkt>, T)> {
+        self.common.record_rx(data.len());
+        self.tap_received_frame(data);
+
+        match self.parse_ip_or_process_neighbor(data, iface_cx, ip_addrs) {
             Ok(pkt) => Some((pkt, tx_token)),
-            Err(Some(arp)) => {
-                Self::emit_arp(&arp, tx_token);
+            Err(Some(neighbor)) => {
+                self.emit_neighbor(&neighbor, &iface_cx.caps, tx_token);
                 None
             }
             Err(None) => None,
         }
     }
 
-    fn parse_ip_or_process_arp<'pkt>(
+    /// Delivers a copy of a received Ethernet frame to packet sockets.
+    fn tap_received_frame(&self, data: &[u8]) {
+        let Ok(frame) = EthernetFrame::new_checked(data) else {
+            return;
+        };
+
+        let dst_addr = frame.dst_addr();
+        let packet_type = if dst_addr.is_broadcast() {
+            PacketType::Broadcast
+        } else if dst_addr.is_multicast() {
+            PacketType::Multicast
+        } else if dst_addr == self.ether_addr {
+            PacketType::Host
+        } else if self.common.flags().contains(InterfaceFlags::PROMISC) {
+            // The frame will be dropped later, but packet sockets can still see it.
+            PacketType::OtherHost
+        } else {
+            return;
+        };
+
+        self.common.tap_frame(data, packet_type);
+    }
+
+    fn parse_ip_or_process_neighbor<'pkt>(
         &self,
         data: &'pkt [u8],
         iface_cx: &mut Context,
-    ) -> Result<Ipv4Packet<&'pkt [u8]>, Option<ArpRepr>> {
+        ip_addrs: &[IpCidr],
+    ) -> Result<IpPacket<'pkt>, Option<NeighborPacket>> {
         // Parse the Ethernet header. Ignore the packet if the header is ill-formed.
         let frame = EthernetFrame::new_checked(data).map_err(|_| None)?;
         let repr = EthernetRepr::parse(&frame).map_err(|_| None)?;
 
-        // Ignore the Ethernet frame if it is not sent to us.
-        if !repr.dst_addr.is_broadcast() && repr.dst_addr != self.ether_addr {
+        // Ignore the Ethernet frame if it is not sent to us. Multicast frames are accepted here
+        // and filtered later by their IP destination addresses.
+        if repr.dst_addr.is_unicast() && repr.dst_addr != self.ether_addr {
             return Err(None);
         }
 
         // Ignore the Ethernet frame if the protocol is not supported.
         match repr.ethertype {
-            EthernetProtocol::Ipv4 => {
-                Ok(Ipv4Packet::new_checked(frame.payload()).map_err(|_| None)?)
+            EthernetProtocol::Ipv4 => Ok(IpPacket::Ipv4(
+                Ipv4Packet::new_checked(frame.payload()).map_err(|_| None)?,
+            )),
+            EthernetProtocol::Ipv6 => {
+                let pkt = Ipv6Packet::new_checked(frame.payload()).map_err(|_| None)?;
+                if !is_ndisc_neighbor_packet(&pkt) {
+                    return Ok(IpPacket::Ipv6(pkt));
+                }
+                Err(self.process_ndisc(&pkt, repr.src_addr, iface_cx, ip_addrs))
             }
             EthernetProtocol::Arp => {
                 let pkt = ArpPacket::new_checked(frame.payload()).map_err(|_| None)?;
                 let arp = ArpRepr::parse(&pkt).map_err(|_| None)?;
-                Err(self.process_arp(&arp, iface_cx))
+                Err(self.process_arp(&arp, iface_cx).map(NeighborPacket::Arp))
             }
             _ => Err(None),
         }
@@ -197,76 +299,278 @@ impl<D, E: Ext> EtherIface<D, E> {
         }
     }
 
+    fn process_ndisc(
+        &self,
+        pkt: &Ipv6Packet<&[u8]>,
+        ether_src_addr: EthernetAddress,
+        iface_cx: &mut Context,
+        ip_addrs: &[IpCidr],
+    ) -> Option<NeighborPacket> {
+        let ip_repr = Ipv6Repr::parse(pkt).ok()?;
+
+        // Ignore the packet if it may have been forwarded by a router. See
+        // <https://datatracker.ietf.org/doc/html/rfc4861#section-7.1>.
+        if ip_repr.hop_limit != 255 {
+            return None;
+        }
+
+        let icmp_pkt = Icmpv6Packet::new_checked(pkt.payload()).ok()?;
+        let icmp_repr = Icmpv6Repr::parse(
+            &ip_repr.src_addr,
+            &ip_repr.dst_addr,
+            &icmp_pkt,
+            &iface_cx.checksum_caps(),
+        )
+        .ok()?;
+        let Icmpv6Repr::Ndisc(ndisc_repr) = icmp_repr else {
+            return None;
+        };
+
+        match ndisc_repr {
+            NdiscRepr::NeighborAdvert {
+                target_addr,
+                lladdr: Some(lladdr),
+                ..
+            } => {
+                // Ignore the advertisement if the addresses are not unicast or not local.
+                let ether_addr = parse_ether_addr(&lladdr)?;
+                if target_addr.is_multicast()
+                    || !iface_cx.in_same_network(&IpAddress::Ipv6(target_addr))
+                {
+                    return None;
+                }
+
+                // Insert the mapping between the Ethernet address and the IP address.
+                //
+                // TODO: Remove the mapping if it expires.
+                self.ndp_table.lock().insert(target_addr, ether_addr);
+
+                None
+            }
+            NdiscRepr::NeighborSolicit {
+                target_addr,
+                lladdr,
+            } => {
+                // Ignore the solicitation if we do not own the target address.
+                if !ip_addrs
+                    .iter()
+                    .any(|cidr| cidr.address() == IpAddress::Ipv6(target_addr))
+                {
+                    return None;
+                }
+
+                let lladdr = match lladdr {
+                    Some(lladdr) => Some(parse_ether_addr(&lladdr)?),
+                    None => None,
+                };
+
+                // Duplicate address detection uses the unspecified source address, in which case
+                // the advertisement is sent to all nodes. See
+                // <https://datatracker.ietf.org/doc/html/rfc4861#section-7.2.4>.
+                let (ether_dst_addr, ip_dst_addr, flags) = if ip_repr.src_addr.is_unspecified() {
+                    (
+                        ipv6_multicast_ether_addr(IPV6_LINK_LOCAL_ALL_NODES),
+                        IPV6_LINK_LOCAL_ALL_NODES,
+                        NdiscNeighborFlags::OVERRIDE,
+                    )
+                } else {
+                    // The solicitation can also update our neighbor cache. See
+                    // <https://datatracker.ietf.org/doc/html/rfc4861#section-7.2.3>.
+                    if let Some(lladdr) = lladdr {
+                        self.ndp_table.lock().insert(ip_repr.src_addr, lladdr);
+                    }
+                    (
+                        lladdr.unwrap_or(ether_src_addr),
+                        ip_repr.src_addr,
+                        NdiscNeighborFlags::SOLICITED | NdiscNeighborFlags::OVERRIDE,
+                    )
+                };
+
+                let ndisc_repr = NdiscRepr::NeighborAdvert {
+                    flags,
+                    target_addr,
+                    lladdr: Some(RawHardwareAddress::from(self.ether_addr)),
+                };
+                let ip_repr = Ipv6Repr {
+                    src_addr: target_addr,
+                    dst_addr: ip_dst_addr,
+                    next_header: IpProtocol::Icmpv6,
+                    payload_len: Icmpv6Repr::Ndisc(ndisc_repr).buffer_len(),
+                    hop_limit: 255,
+                };
+
+                Some(NeighborPacket::Ndisc(ether_dst_addr, ip_repr, ndisc_repr))
+            }
+            _ => None,
+        }
+    }
+
     fn dispatch<T: TxToken>(&self, pkt: &Packet, iface_cx: &mut Context, tx_token: T) {
-        match self.resolve_ether_or_generate_arp(pkt, iface_cx) {
-            Ok(ether) => Self::emit_ip(&ether, pkt, &iface_cx.caps, tx_token),
-            Err(Some(arp)) => Self::emit_arp(&arp, tx_token),
+        match self.resolve_ether_or_generate_neighbor(pkt, iface_cx) {
+            Ok(ether) => self.emit_ip(&ether, pkt, &iface_cx.caps, tx_token),
+            Err(Some(neighbor)) => self.emit_neighbor(&neighbor, &iface_cx.caps, tx_token),
             Err(None) => (),
         }
     }
 
-    fn resolve_ether_or_generate_arp(
+    fn resolve_ether_or_generate_neighbor(
         &self,
         pkt: &Packet,
         iface_cx: &mut Context,
-    ) -> Result<EthernetRepr, Option<ArpRepr>> {
+    ) -> Result<EthernetRepr, Option<NeighborPacket>> {
+        let ip_repr = pkt.ip_repr();
+
+        let (next_hop_ether, ethertype) = match ip_repr {
+            IpRepr::Ipv4(_) => (
+                self.resolve_ipv4_next_hop(&ip_repr, iface_cx)?,
+                EthernetProtocol::Ipv4,
+            ),
+            IpRepr::Ipv6(ipv6_repr) => (
+                self.resolve_ipv6_next_hop(&ipv6_repr, iface_cx)?,
+                EthernetProtocol::Ipv6,
+            ),
+        };
+
+        Ok(EthernetRepr {
+            src_addr: self.ether_addr,
+            dst_addr: next_hop_ether,
+            ethertype,
+        })
+    }
+
+    fn resolve_ipv4_next_hop(
+        &self,
+        ip_repr: &IpRepr,
+        iface_cx: &mut Context,
+    ) -> Result<EthernetAddress, Option<NeighborPacket>> {
+        // Multicast addresses are mapped to Ethernet addresses directly.
+        if let IpAddress::Ipv4(dst_addr) = ip_repr.dst_addr() {
+            if dst_addr.is_multicast() {
+                return Ok(ipv4_multicast_ether_addr(dst_addr));
+            }
+        }
+
         // Resolve the next-hop IP address.
-        let next_hop_ip = match iface_cx.route(&pkt.ip_repr().dst_addr(), iface_cx.now()) {
+        let next_hop_ip = match iface_cx.route(&ip_repr.dst_addr(), iface_cx.now()) {
             Some(IpAddress::Ipv4(next_hop_ip)) => next_hop_ip,
-            None => return Err(None),
+            _ => return Err(None),
         };
 
         // Resolve the next-hop Ethernet address.
-        let next_hop_ether = if next_hop_ip.is_broadcast() {
-            EthernetAddress::BROADCAST
+        if next_hop_ip.is_broadcast() {
+            Ok(EthernetAddress::BROADCAST)
         } else if let Some(next_hop_ether) = self.arp_table.lock().get(&next_hop_ip) {
-            *next_hop_ether
+            Ok(*next_hop_ether)
         } else {
             // If the next-hop Ethernet address cannot be resolved, we drop the original packet and
             // send an ARP packet instead. The upper layer should be responsible for detecting the
             // packet loss and retrying later to see if the Ethernet address is ready.
-            return Err(Some(ArpRepr::EthernetIpv4 {
+            Err(Some(NeighborPacket::Arp(ArpRepr::EthernetIpv4 {
                 operation: ArpOperation::Request,
                 source_hardware_addr: self.ether_addr,
                 source_protocol_addr: iface_cx.ipv4_addr().unwrap_or(Ipv4Address::UNSPECIFIED),
                 target_hardware_addr: EthernetAddress::BROADCAST,
                 target_protocol_addr: next_hop_ip,
-            }));
+            })))
+        }
+    }
+
+    fn resolve_ipv6_next_hop(
+        &self,
+        ip_repr: &Ipv6Repr,
+        iface_cx: &mut Context,
+    ) -> Result<EthernetAddress, Option<NeighborPacket>> {
+        // Multicast addresses are mapped to Ethernet addresses directly.
+        if ip_repr.dst_addr.is_multicast() {
+            return Ok(ipv6_multicast_ether_addr(ip_repr.dst_addr));
+        }
+
+        // Resolve the next-hop IP address.
+        let next_hop_ip = match iface_cx.route(&IpAddress::Ipv6(ip_repr.dst_addr), iface_cx.now()) {
+            Some(IpAddress::Ipv6(next_hop_ip)) => next_hop_ip,
+            _ => return Err(None),
         };
 
-        Ok(EthernetRepr {
-            src_addr: self.ether_addr,
-            dst_addr: next_hop_ether,
-            ethertype: EthernetProtocol::Ipv4,
-        })
+        // Resolve the next-hop Ethernet address.
+        if let Some(next_hop_ether) = self.ndp_table.lock().get(&next_hop_ip) {
+            return Ok(*next_hop_ether);
+        }
+
+        // Like ARP, we drop the original packet and send a neighbor solicitation instead. See
+        // <https://datatracker.ietf.org/doc/html/rfc4861#section-7.2.2>.
+        let dst_addr = solicited_node_addr(next_hop_ip);
+        let ndisc_repr = NdiscRepr::NeighborSolicit {
+            target_addr: next_hop_ip,
+            lladdr: Some(RawHardwareAddress::from(self.ether_addr)),
+        };
+        let solicit_repr = Ipv6Repr {
+            src_addr: ip_repr.src_addr,
+            dst_addr,
+            next_header: IpProtocol::Icmpv6,
+            payload_len: Icmpv6Repr::Ndisc(ndisc_repr).buffer_len(),
+            hop_limit: 255,
+        };
+
+        Err(Some(NeighborPacket::Ndisc(
+            ipv6_multicast_ether_addr(dst_addr),
+            solicit_repr,
+            ndisc_repr,
+        )))
     }
 
     /// Consumes the token and emits an IP packet.
     fn emit_ip<T: TxToken>(
+        &self,
         ether_repr: &EthernetRepr,
         ip_pkt: &Packet,
         caps: &DeviceCapabilities,
         tx_token: T,
     ) {
-        tx_token.consume(
-            ether_repr.buffer_len() + ip_pkt.ip_repr().buffer_len(),
-            |buffer| {
-                let mut frame = EthernetFrame::new_unchecked(buffer);
-                ether_repr.emit(&mut frame);
-
-                let ip_repr = ip_pkt.ip_repr();
-                ip_repr.emit(frame.payload_mut(), &caps.checksum);
-                ip_pkt.emit_payload(
-                    &ip_repr,
-                    &mut frame.payload_mut()[ip_repr.header_len()..],
-                    caps,
+        let len = ether_repr.buffer_len() + ip_pkt.ip_repr().buffer_len();
+        self.common.record_tx(len);
+
+        tx_token.consume(len, |buffer| {
+            let mut frame = EthernetFrame::new_unchecked(&mut *buffer);
+            ether_repr.emit(&mut frame);
+
+            let ip_repr = ip_pkt.ip_repr();
+            ip_repr.emit(frame.payload_mut(), &caps.checksum);
+            ip_pkt.emit_payload(
+                &ip_repr,
+                &mut frame.payload_mut()[ip_repr.header_len()..],
+                caps,
+            );
+
+            self.common.tap_frame(buffer, PacketType::Outgoing);
+        });
+    }
+
+    /// Consumes the token and emits an ARP packet or a neighbor discovery message.
+    fn emit_neighbor<T: TxToken>(
+        &self,
+        neighbor: &NeighborPacket,
+        caps: &DeviceCapabilities,
+        tx_token: T,
+    ) {
+        match neighbor {
+            NeighborPacket::Arp(arp_repr) => self.emit_arp(arp_repr, tx_token),
+            NeighborPacket::Ndisc(ether_dst_addr, ip_repr, ndisc_repr) => {
+                let ether_repr = EthernetRepr {
+                    src_addr: self.ether_addr,
+                    dst_addr: *ether_dst_addr,
+                    ethertype: EthernetProtocol::Ipv6,
+                };
+                let ip_pkt = Packet::new(
+                    IpRepr::Ipv6(*ip_repr),
+                    IpPayload::Icmpv6(Icmpv6Repr::Ndisc(*ndisc_repr)),
                 );
-            },
-        );
+                self.emit_ip(&ether_repr, &ip_pkt, caps, tx_token);
+            }
+        }
     }
 
     /// Consumes the token and emits an ARP packet.
-    fn emit_arp<T: TxToken>(arp_repr: &ArpRepr, tx_token: T) {
+    fn emit_arp<T: TxToken>(&self, arp_repr: &ArpRepr, tx_token: T) {
         let ether_repr = match arp_repr {
             ArpRepr::EthernetIpv4 {
                 source_hardware_addr,
@@ -280,12 +584,88 @@ impl<D, E: Ext> EtherIface<D, E> {
             _ => return,
         };
 
-        tx_token.consume(ether_repr.buffer_len() + arp_repr.buffer_len(), |buffer| {
-            let mut frame = EthernetFrame::new_unchecked(buffer);
+        let len = ether_repr.buffer_len() + arp_repr.buffer_len();
+        self.common.record_tx(len);
+
+        tx_token.consume(len, |buffer| {
+            let mut frame = EthernetFrame::new_unchecked(&mut *buffer);
             ether_repr.emit(&mut frame);
 
             let mut pkt = ArpPacket::new_unchecked(frame.payload_mut());
             arp_repr.emit(&mut pkt);
+
+            self.common.tap_frame(buffer, PacketType::Outgoing);
         });
     }
 }
+
+/// Returns whether `pkt` is a neighbor solicitation or a neighbor advertisement.
+///
+/// These messages are processed by the Ethernet iface, in the same way as ARP packets.
+fn is_ndisc_neighbor_packet(pkt: &Ipv6Packet<&[u8]>) -> bool {
+    if pkt.next_header() != IpProtocol::Icmpv6 {
+        return false;
+    }
+
+    Icmpv6Packet::new_checked(pkt.payload()).is_ok_and(|icmp_pkt| {
+        matches!(
+            icmp_pkt.msg_type(),
+            Icmpv6Message::NeighborSolicit | Icmpv6Message::NeighborAdvert
+        )
+    })
+}
+
+/// Parses the link-layer address option of a neighbor discovery message.
+///
+/// Returns `None` if the address is not a unicast Ethernet address.
+fn parse_ether_addr(lladdr: &RawHardwareAddress) -> Option<EthernetAddress> {
+    if lladdr.len() != 6 {
+        return None;
+    }
+
+    let ether_addr = EthernetAddress::from_bytes(lladdr.as_bytes());
+    ether_addr.is_unicast().then_some(ether_addr)
+}
+
+/// Returns the link-local address of the iface with the Ethernet address `ether_addr`.
+///
+/// The interface identifier is the modified EUI-64 identifier. See
+/// <https://datatracker.ietf.org/doc/html/rfc4291#appendix-A>.
+fn link_local_cidr(ether_addr: EthernetAddress) -> Ipv6Cidr {
+    let [a, b, c, d, e, f] = ether_addr.0;
+    let addr = Ipv6Address::from([
+        0xfe,
+        0x80,
+        0,
+        0,
+        0,
+        0,
+        0,
+        0,
+        a ^ 0x02,
+        b,
+        c,
+        0xff,
+        0xfe,
+        d,
+        e,
+        f,
+    ]);
+    Ipv6Cidr::new(addr, 64)
+}
+
+/// Returns the Ethernet address to which the IPv4 multicast address `addr` is mapped.
+///
+/// Reference: <https://datatracker.ietf.org/doc/html/rfc1112#section-6.4>.
+fn ipv4_multicast_ether_addr(addr: Ipv4Address) -> EthernetAddress {
+    let octets = addr.octets();
+    EthernetAddress([0x01, 0x00, 0x5e, octets[1] & 0x7f, octets[2], octets[3]])
+}
+
+/// Returns the Ethernet address to which the IPv6 multicast address `addr` is mapped.
+///
+/// Reference: <https://datatracker.ietf.org/doc/html/rfc2464#section-7>.
+fn ipv6_multicast_ether_addr(addr: Ipv6Address) -> EthernetAddress {
+    let octets = addr.octets();
+    EthernetAddress([0x33, 0x33, octets[12], octets[13], octets[14], octets[15]])
+}
@@ -1,11 +1,11 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use alloc::{string::String, sync::Arc};
+use alloc::{string::String, sync::Arc, vec};
 
 use smoltcp::{
     iface::Config,
     phy::{Device, TxToken},
-    wire::{self, Ipv4Cidr, Ipv4Packet},
+    wire::{self, EthernetFrame, EthernetProtocol, Ipv4Cidr, ETHERNET_HEADER_LEN},
 };
 
 use crate::{
@@ -14,9 +14,11 @@ use crate::{
     iface::{
         common::{IfaceCommon, InterfaceFlags, InterfaceType},
         iface::internal::IfaceInternal,
+        poll::IpPacket,
         time::get_network_timestamp,
         Iface, ScheduleNextPoll,
     },
+    socket::PacketType,
 };
 
 pub struct IpIface<D, E: Ext> {
@@ -33,7 +35,7 @@ impl<D: WithDevice, E: Ext> IpIface<D, E> {
         type_: InterfaceType,
         flags: InterfaceFlags,
     ) -> Arc<Self> {
-        let interface = driver.with(|device| {
+        let (interface, mtu) = driver.with(|device| {
             let config = Config::new(smoltcp::wire::HardwareAddress::Ip);
             let now = get_network_timestamp();
 
@@ -42,10 +44,10 @@ impl<D: WithDevice, E: Ext> IpIface<D, E> {
                 debug_assert!(ip_addrs.is_empty());
                 ip_addrs.push(wire::IpCidr::Ipv4(ip_cidr)).unwrap();
             });
-            interface
+            (interface, device.capabilities().max_transmission_unit)
         });
 
-        let common = IfaceCommon::new(name, type_, flags, interface, sched_poll);
+        let common = IfaceCommon::new(name, type_, flags, mtu, interface, sched_poll);
 
         Arc::new(Self { driver, common })
     }
@@ -60,11 +62,26 @@ impl<D, E: Ext> IfaceInternal<E> for IpIface<D, E> {
 impl<D: WithDevice + 'static, E: Ext> Iface<E> for IpIface<D, E> {
     fn poll(&self) {
         self.driver.with(|device| {
+            // The frames sent by packet sockets are dispatched first, so that they are received
+            // in the same poll. Their fake Ethernet headers are stripped.
+            self.common.dispatch_frames(|frame| {
+                let Some(tx_token) = device.transmit(get_network_timestamp()) else {
+                    return false;
+                };
+                let ip_packet = &frame[ETHERNET_HEADER_LEN..];
+                tx_token.consume(ip_packet.len(), |buffer| buffer.copy_from_slice(ip_packet));
+                true
+            });
             let next_poll = self.common.poll(
-                device,
-                |data, _iface_cx, tx_token| Some((Ipv4Packet::new_checked(data).ok()?, tx_token)),
+                &mut *device,
+                |data, _iface_cx, _ip_addrs, tx_token| {
+                    self.common.record_rx(data.len());
+                    self.tap_packet(data, PacketType::Host);
+                    Some((IpPacket::new_checked(data)?, tx_token))
+                },
                 |pkt, iface_cx, tx_token| {
                     let ip_repr = pkt.ip_repr();
+                    self.common.record_tx(ip_repr.buffer_len());
                     tx_token.consume(ip_repr.buffer_len(), |buffer| {
                         ip_repr.emit(&mut buffer[..], &iface_cx.checksum_caps());
                         pkt.emit_payload(
@@ -72,15 +89,34 @@ impl<D: WithDevice + 'static, E: Ext> Iface<E> for IpIface<D, E> {
                             &mut buffer[ip_repr.header_len()..],
                             &iface_cx.caps,
                         );
+                        self.tap_packet(buffer, PacketType::Outgoing);
                     });
                 },
             );
             self.common.sched_poll().schedule_next_poll(next_poll);
         });
     }
+}
+
+impl<D, E: Ext> IpIface<D, E> {
+    /// Delivers a copy of an IP packet to packet sockets.
+    ///
+    /// Like Linux, a fake Ethernet header with zero addresses is prepended to the IP packet.
+    fn tap_packet(&self, ip_packet: &[u8], packet_type: PacketType) {
+        if !self.common.has_packet_sockets() {
+            return;
+        }
+
+        let ethertype = match ip_packet.first().map(|byte| byte >> 4) {
+            Some(4) => EthernetProtocol::Ipv4,
+            Some(6) => EthernetProtocol::Ipv6,
+            _ => return,
+        };
+
+        let mut frame = vec![0u8; ETHERNET_HEADER_LEN + ip_packet.len()];
+        EthernetFrame::new_unchecked(&mut frame[..]).set_ethertype(ethertype);
+        frame[ETHERNET_HEADER_LEN..].copy_from_slice(ip_packet);
 
-    fn mtu(&self) -> usize {
-        self.driver
-            .with(|device| device.capabilities().max_transmission_unit)
+        self.common.tap_frame(&frame, packet_type);
     }
 }
@@ -9,9 +9,10 @@ use smoltcp::{
     },
     phy::{ChecksumCapabilities, Device, RxToken, TxToken},
     wire::{
-        Icmpv4DstUnreachable, Icmpv4Repr, IpAddress, IpProtocol, IpRepr, Ipv4Address, Ipv4Packet,
-        Ipv4Repr, TcpControl, TcpPacket, TcpRepr, UdpPacket, UdpRepr, IPV4_HEADER_LEN,
-        IPV4_MIN_MTU,
+        IcmpRepr, Icmpv4DstUnreachable, Icmpv4Packet, Icmpv4Repr, Icmpv6DstUnreachable,
+        Icmpv6Packet, Icmpv6Repr, IpAddress, IpCidr, IpProtocol, IpRepr, Ipv4Address, Ipv4Packet,
+        Ipv4Repr, Ipv6Packet, Ipv6Repr, TcpControl, TcpPacket, TcpRepr, UdpPacket, UdpRepr,
+        IPV4_HEADER_LEN, IPV4_MIN_MTU, IPV6_HEADER_LEN, IPV6_MIN_MTU,
     },
 };
 
@@ -37,6 +38,32 @@ pub(super) enum SocketTableAction<E: Ext> {
     DelTcpConn(ConnectionKey),
 }
 
+/// An IP packet received by the physical layer.
+pub(super) enum IpPacket<'pkt> {
+    Ipv4(Ipv4Packet<&'pkt [u8]>),
+    Ipv6(Ipv6Packet<&'pkt [u8]>),
+}
+
+impl<'pkt> IpPacket<'pkt> {
+    /// Checks the IP version and the IP header of `data`.
+    ///
+    /// Returns `None` if the IP version is not supported or the header is ill-formed.
+    pub(super) fn new_checked(data: &'pkt [u8]) -> Option<Self> {
+        match data.first()? >> 4 {
+            4 => Ipv4Packet::new_checked(data).ok().map(Self::Ipv4),
+            6 => Ipv6Packet::new_checked(data).ok().map(Self::Ipv6),
+            _ => None,
+        }
+    }
+}
+
+/// The reason why an ICMP "destination unreachable" message is generated.
+#[derive(Debug, Clone, Copy)]
+enum DstUnreachable {
+    HostUnreachable,
+    PortUnreachable,
+}
+
 impl<'a, E: Ext> PollContext<'a, E> {
     pub(super) fn new(
         iface: PollableIfaceMut<'a, E>,
@@ -53,8 +80,8 @@ impl<'a, E: Ext> PollContext<'a, E> {
 
 // This works around <https://github.com/rust-lang/rust/issues/49601>.
 // See the issue above for details.
-pub(super) trait FnHelper<A, B, C, O>: FnMut(A, B, C) -> O {}
-impl<A, B, C, O, F> FnHelper<A, B, C, O> for F where F: FnMut(A, B, C) -> O {}
+pub(super) trait FnHelper<A, B, C, D, O>: FnMut(A, B, C, D) -> O {}
+impl<A, B, C, D, O, F> FnHelper<A, B, C, D, O> for F where F: FnMut(A, B, C, D) -> O {}
 
 impl<E: Ext> PollContext<'_, E> {
     pub(super) fn poll_ingress<D, P, Q>(
@@ -67,19 +94,24 @@ impl<E: Ext> PollContext<'_, E> {
         P: for<'pkt, 'cx, 'tx> FnHelper<
             &'pkt [u8],
             &'cx mut Context,
+            &'cx [IpCidr],
             D::TxToken<'tx>,
-            Option<(Ipv4Packet<&'pkt [u8]>, D::TxToken<'tx>)>,
+            Option<(IpPacket<'pkt>, D::TxToken<'tx>)>,
         >,
         Q: FnMut(&Packet, &mut Context, D::TxToken<'_>),
     {
         while let Some((rx_token, tx_token)) = device.receive(self.iface.context().now()) {
             rx_token.consume(|data| {
-                let Some((pkt, tx_token)) = process_phy(data, self.iface.context_mut(), tx_token)
-                else {
+                let (iface_cx, ip_addrs) = self.iface.context_and_ip_addrs();
+                let Some((pkt, tx_token)) = process_phy(data, iface_cx, ip_addrs, tx_token) else {
                     return;
                 };
 
-                let Some(reply) = self.parse_and_process_ipv4(pkt) else {
+                let reply = match pkt {
+                    IpPacket::Ipv4(pkt) => self.parse_and_process_ipv4(pkt),
+                    IpPacket::Ipv6(pkt) => self.parse_and_process_ipv6(pkt),
+                };
+                let Some(reply) = reply else {
                     return;
                 };
 
@@ -95,14 +127,32 @@ impl<E: Ext> PollContext<'_, E> {
         // Parse the IP header. Ignore the packet if the header is ill-formed.
         let repr = Ipv4Repr::parse(&pkt, &self.iface.context().checksum_caps()).ok()?;
 
-        if !repr.dst_addr.is_broadcast() && !self.is_unicast_local(IpAddress::Ipv4(repr.dst_addr)) {
+        if self.process_dhcp(&repr, pkt.payload()) {
+            return None;
+        }
+
+        if repr.dst_addr.is_multicast() || self.iface.is_ipv4_broadcast(repr.dst_addr) {
+            // Ignore the packet if it is sent by us. It has been delivered locally when it was
+            // sent, so it must not be delivered again if the link echoes it back.
+            if self.is_unicast_local(IpAddress::Ipv4(repr.src_addr)) {
+                return None;
+            }
+
+            // Ignore the packet if it is sent to a multicast group that we have not joined.
+            if repr.dst_addr.is_multicast() && !self.iface.has_ipv4_multicast_group(repr.dst_addr) {
+                return None;
+            }
+        } else if !self.is_unicast_local(IpAddress::Ipv4(repr.dst_addr)) {
             return self.generate_icmp_unreachable(
                 &IpRepr::Ipv4(repr),
                 pkt.payload(),
-                Icmpv4DstUnreachable::HostUnreachable,
+                DstUnreachable::HostUnreachable,
             );
         }
 
+        // Raw IP sockets receive a copy of the packet, regardless of how it is processed below.
+        self.process_raw_ip(&IpRepr::Ipv4(repr), pkt.payload());
+
         let checksum_caps = self.iface.context().checksum_caps();
         match repr.next_header {
             IpProtocol::Tcp => {
@@ -111,10 +161,173 @@ impl<E: Ext> PollContext<'_, E> {
             IpProtocol::Udp => {
                 self.parse_and_process_udp(&IpRepr::Ipv4(repr), pkt.payload(), &checksum_caps)
             }
+            IpProtocol::Icmp => self.parse_and_process_icmpv4(&repr, pkt.payload(), &checksum_caps),
+            IpProtocol::Igmp => {
+                self.iface.process_igmp(pkt.payload());
+                None
+            }
             _ => None,
         }
     }
 
+    fn parse_and_process_ipv6<'pkt>(
+        &mut self,
+        pkt: Ipv6Packet<&'pkt [u8]>,
+    ) -> Option<Packet<'pkt>> {
+        // Parse the IP header. Ignore the packet if the header is ill-formed.
+        let repr = Ipv6Repr::parse(&pkt).ok()?;
+
+        // Ignore the packet if the source address is multicast. See
+        // <https://datatracker.ietf.org/doc/html/rfc4291#section-2.7>.
+        if repr.src_addr.is_multicast() {
+            return None;
+        }
+
+        // Ignore the packet if it is not sent to us. Unlike IPv4, we do not generate ICMP messages
+        // here, since forwarding is not supported and the packet may be sent to a multicast group
+        // that we have not joined.
+        let is_local = if repr.dst_addr.is_multicast() {
+            self.iface.has_ipv6_multicast_group(repr.dst_addr)
+        } else {
+            self.is_unicast_local(IpAddress::Ipv6(repr.dst_addr))
+        };
+        if !is_local {
+            return None;
+        }
+
+        // TODO: Support IPv6 extension headers.
+        let checksum_caps = self.iface.context().checksum_caps();
+        match repr.next_header {
+            IpProtocol::Tcp => {
+                self.parse_and_process_tcp(&IpRepr::Ipv6(repr), pkt.payload(), &checksum_caps)
+            }
+            IpProtocol::Udp => {
+                self.parse_and_process_udp(&IpRepr::Ipv6(repr), pkt.payload(), &checksum_caps)
+            }
+            IpProtocol::Icmpv6 => {
+                self.parse_and_process_icmpv6(&repr, pkt.payload(), &checksum_caps)
+            }
+            _ => None,
+        }
+    }
+
+    fn parse_and_process_icmpv4<'pkt>(
+        &mut self,
+        ip_repr: &Ipv4Repr,
+        ip_payload: &'pkt [u8],
+        checksum_caps: &ChecksumCapabilities,
+    ) -> Option<Packet<'pkt>> {
+        // Parse the ICMP header. Ignore the packet if the header is ill-formed.
+        let icmp_pkt = Icmpv4Packet::new_checked(ip_payload).ok()?;
+        let icmp_repr = Icmpv4Repr::parse(&icmp_pkt, checksum_caps).ok()?;
+
+        // Like Linux's ping sockets, ICMP sockets only receive echo replies. The identifiers are
+        // allocated as ports, so at most one socket can accept the reply.
+        if let Icmpv4Repr::EchoReply { .. } = icmp_repr {
+            for socket in self.sockets.icmp_socket_iter() {
+                if socket.process_v4(self.iface.context_mut(), ip_repr, &icmp_repr) {
+                    break;
+                }
+            }
+            return None;
+        }
+
+        // Other messages are ignored, except that echo requests are replied to.
+        let Icmpv4Repr::EchoRequest {
+            ident,
+            seq_no,
+            data,
+        } = icmp_repr
+        else {
+            return None;
+        };
+
+        // Like Linux, ignore echo requests sent to broadcast addresses. See
+        // `icmp_echo_ignore_broadcasts` in
+        // <https://docs.kernel.org/networking/ip-sysctl.html#icmp-echo-ignore-broadcasts>.
+        if ip_repr.dst_addr.is_broadcast() {
+            return None;
+        }
+
+        let reply_repr = Icmpv4Repr::EchoReply {
+            ident,
+            seq_no,
+            data,
+        };
+        let reply_ip_repr = IpRepr::Ipv4(Ipv4Repr {
+            src_addr: ip_repr.dst_addr,
+            dst_addr: ip_repr.src_addr,
+            next_header: IpProtocol::Icmp,
+            payload_len: reply_repr.buffer_len(),
+            hop_limit: 64,
+        });
+
+        if !self.is_unicast_local(reply_ip_repr.dst_addr()) {
+            return Some(Packet::new(reply_ip_repr, IpPayload::Icmpv4(reply_repr)));
+        }
+
+        // The reply is sent to ourselves (e.g., when pinging a local address), so process it
+        // right now. Processing an echo reply never generates a new reply.
+        let reply = emit_local_packet(&reply_ip_repr, &IcmpRepr::Ipv4(reply_repr));
+        let new_reply = self.process_local_packet(&reply);
+        debug_assert!(new_reply.is_none());
+
+        None
+    }
+
+    fn parse_and_process_icmpv6<'pkt>(
+        &self,
+        ip_repr: &Ipv6Repr,
+        ip_payload: &'pkt [u8],
+        checksum_caps: &ChecksumCapabilities,
+    ) -> Option<Packet<'pkt>> {
+        // Parse the ICMPv6 header. Ignore the packet if the header is ill-formed.
+        let icmp_pkt = Icmpv6Packet::new_checked(ip_payload).ok()?;
+        let icmp_repr = Icmpv6Repr::parse(
+            &ip_repr.src_addr,
+            &ip_repr.dst_addr,
+            &icmp_pkt,
+            checksum_caps,
+        )
+        .ok()?;
+
+        // Neighbor discovery messages are handled by the physical layer, and other messages are
+        // ignored since there are no ICMPv6 sockets.
+        let Icmpv6Repr::EchoRequest {
+            ident,
+            seq_no,
+            data,
+        } = icmp_repr
+        else {
+            return None;
+        };
+
+        // Reply to the echo request. If the request is sent to a multicast group, the reply is
+        // sent from one of our unicast addresses. See
+        // <https://datatracker.ietf.org/doc/html/rfc4443#section-4.2>.
+        let src_addr = if ip_repr.dst_addr.is_multicast() {
+            self.iface.ipv6_source_addr(&ip_repr.src_addr)?
+        } else {
+            ip_repr.dst_addr
+        };
+        let reply_repr = Icmpv6Repr::EchoReply {
+            ident,
+            seq_no,
+            data,
+        };
+
+        Some(Packet::new(
+            IpRepr::Ipv6(Ipv6Repr {
+                src_addr,
+                dst_addr: ip_repr.src_addr,
+                next_header: IpProtocol::Icmpv6,
+                payload_len: reply_repr.buffer_len(),
+                hop_limit: 64,
+            }),
+            IpPayload::Icmpv6(reply_repr),
+        ))
+    }
+
     fn parse_and_process_tcp<'pkt>(
         &mut self,
         ip_repr: &IpRepr,
@@ -268,7 +481,7 @@ impl<E: Ext> PollContext<'_, E> {
             return self.generate_icmp_unreachable(
                 ip_repr,
                 ip_payload,
-                Icmpv4DstUnreachable::PortUnreachable,
+                DstUnreachable::PortUnreachable,
             );
         }
 
@@ -284,7 +497,7 @@ impl<E: Ext> PollContext<'_, E> {
             }
 
             processed |= socket.process(self.iface.context_mut(), ip_repr, udp_repr, udp_payload);
-            if processed && ip_repr.dst_addr().is_unicast() {
+            if processed && self.is_unicast_local(ip_repr.dst_addr()) {
                 break;
             }
         }
@@ -292,11 +505,68 @@ impl<E: Ext> PollContext<'_, E> {
         processed
     }
 
+    /// Tries to process an incoming DHCP packet and returns whether the packet is processed.
+    ///
+    /// This must be done before the destination address is checked, because the DHCP server may
+    /// send packets to the address that it offers, which does not belong to us yet.
+    fn process_dhcp(&mut self, ip_repr: &Ipv4Repr, ip_payload: &[u8]) -> bool {
+        let Some(socket) = self.sockets.dhcp_socket() else {
+            return false;
+        };
+
+        if ip_repr.next_header != IpProtocol::Udp {
+            return false;
+        }
+
+        // Check the ports before parsing the UDP header, so that the checksum of non-DHCP packets
+        // is not verified twice.
+        let Ok(udp_pkt) = UdpPacket::new_checked(ip_payload) else {
+            return false;
+        };
+        if !socket.can_process(udp_pkt.src_port(), udp_pkt.dst_port()) {
+            return false;
+        }
+
+        // Drop the packet if the UDP header is ill-formed.
+        if let Ok(udp_repr) = UdpRepr::parse(
+            &udp_pkt,
+            &IpAddress::Ipv4(ip_repr.src_addr),
+            &IpAddress::Ipv4(ip_repr.dst_addr),
+            &self.iface.context().checksum_caps(),
+        ) {
+            socket.process(
+                self.iface.context_mut(),
+                ip_repr,
+                &udp_repr,
+                udp_pkt.payload(),
+            );
+        }
+
+        true
+    }
+
+    fn process_raw_ip(&mut self, ip_repr: &IpRepr, ip_payload: &[u8]) {
+        for socket in self.sockets.raw_ip_socket_iter() {
+            socket.process(self.iface.context_mut(), ip_repr, ip_payload);
+        }
+    }
+
+    /// Processes a packet that is generated locally and sent to a local address.
+    ///
+    /// The packet is processed as if it were received from the physical layer. Any reply
+    /// generated is returned.
+    fn process_local_packet<'pkt>(&mut self, data: &'pkt [u8]) -> Option<Packet<'pkt>> {
+        match IpPacket::new_checked(data)? {
+            IpPacket::Ipv4(pkt) => self.parse_and_process_ipv4(pkt),
+            IpPacket::Ipv6(pkt) => self.parse_and_process_ipv6(pkt),
+        }
+    }
+
     fn generate_icmp_unreachable<'pkt>(
         &self,
         ip_repr: &IpRepr,
         ip_payload: &'pkt [u8],
-        reason: Icmpv4DstUnreachable,
+        reason: DstUnreachable,
     ) -> Option<Packet<'pkt>> {
         if !ip_repr.src_addr().is_unicast() || !ip_repr.dst_addr().is_unicast() {
             return None;
@@ -312,29 +582,73 @@ impl<E: Ext> PollContext<'_, E> {
             return None;
         }
 
-        let IpRepr::Ipv4(ipv4_repr) = ip_repr;
+        match ip_repr {
+            IpRepr::Ipv4(ipv4_repr) => {
+                let reply_len =
+                    icmp_reply_payload_len(ip_payload.len(), IPV4_MIN_MTU, IPV4_HEADER_LEN);
+                let icmp_repr = Icmpv4Repr::DstUnreachable {
+                    reason: match reason {
+                        DstUnreachable::HostUnreachable => Icmpv4DstUnreachable::HostUnreachable,
+                        DstUnreachable::PortUnreachable => Icmpv4DstUnreachable::PortUnreachable,
+                    },
+                    header: *ipv4_repr,
+                    data: &ip_payload[..reply_len],
+                };
 
-        let reply_len = icmp_reply_payload_len(ip_payload.len(), IPV4_MIN_MTU, IPV4_HEADER_LEN);
-        let icmp_repr = Icmpv4Repr::DstUnreachable {
-            reason,
-            header: *ipv4_repr,
-            data: &ip_payload[..reply_len],
-        };
+                Some(Packet::new_ipv4(
+                    Ipv4Repr {
+                        src_addr: self
+                            .iface
+                            .context()
+                            .ipv4_addr()
+                            .unwrap_or(Ipv4Address::UNSPECIFIED),
+                        dst_addr: ipv4_repr.src_addr,
+                        next_header: IpProtocol::Icmp,
+                        payload_len: icmp_repr.buffer_len(),
+                        hop_limit: 64,
+                    },
+                    IpPayload::Icmpv4(icmp_repr),
+                ))
+            }
+            IpRepr::Ipv6(ipv6_repr) => {
+                let reply_len =
+                    icmp_reply_payload_len(ip_payload.len(), IPV6_MIN_MTU, IPV6_HEADER_LEN);
+                let icmp_repr = Icmpv6Repr::DstUnreachable {
+                    reason: match reason {
+                        DstUnreachable::HostUnreachable => Icmpv6DstUnreachable::AddrUnreachable,
+                        DstUnreachable::PortUnreachable => Icmpv6DstUnreachable::PortUnreachable,
+                    },
+                    header: *ipv6_repr,
+                    data: &ip_payload[..reply_len],
+                };
 
-        Some(Packet::new_ipv4(
-            Ipv4Repr {
-                src_addr: self
-                    .iface
-                    .context()
-                    .ipv4_addr()
-                    .unwrap_or(Ipv4Address::UNSPECIFIED),
-                dst_addr: ipv4_repr.src_addr,
-                next_header: IpProtocol::Icmp,
-                payload_len: icmp_repr.buffer_len(),
-                hop_limit: 64,
-            },
-            IpPayload::Icmpv4(icmp_repr),
-        ))
+                Some(Packet::new(
+                    IpRepr::Ipv6(Ipv6Repr {
+                        src_addr: self.iface.ipv6_source_addr(&ipv6_repr.src_addr)?,
+                        dst_addr: ipv6_repr.src_addr,
+                        next_header: IpProtocol::Icmpv6,
+                        payload_len: icmp_repr.buffer_len(),
+                        hop_limit: 64,
+                    }),
+                    IpPayload::Icmpv6(icmp_repr),
+                ))
+            }
+        }
+    }
+
+    /// Returns whether a packet that is sent to `dst_addr` via the link should also be delivered
+    /// locally.
+    ///
+    /// This is the case for broadcast packets. Multicast packets are delivered locally only if
+    /// the multicast loop is enabled for them and we have joined the multicast group.
+    fn is_looped_back(&self, dst_addr: IpAddress, multicast_loop: bool) -> bool {
+        match dst_addr {
+            IpAddress::Ipv4(addr) if addr.is_multicast() => {
+                multicast_loop && self.iface.has_ipv4_multicast_group(addr)
+            }
+            IpAddress::Ipv4(addr) => self.iface.is_ipv4_broadcast(addr),
+            IpAddress::Ipv6(_) => false,
+        }
     }
 
     /// Returns whether the destination address is the unicast address of a local interface.
@@ -342,13 +656,7 @@ impl<E: Ext> PollContext<'_, E> {
     /// Note: "local" means that the IP address belongs to the local interface, not to be confused
     /// with the localhost IP (127.0.0.1).
     fn is_unicast_local(&self, dst_addr: IpAddress) -> bool {
-        match dst_addr {
-            IpAddress::Ipv4(dst_addr) => self
-                .iface
-                .context()
-                .ipv4_addr()
-                .is_some_and(|addr| addr == dst_addr),
-        }
+        self.iface.has_ip_addr(dst_addr)
     }
 }
 
@@ -359,13 +667,13 @@ impl<E: Ext> PollContext<'_, E> {
         Q: FnMut(&Packet, &mut Context, D::TxToken<'_>),
     {
         while let Some(tx_token) = device.transmit(self.iface.context().now()) {
-            if !self.dispatch_ipv4(tx_token, dispatch_phy) {
+            if !self.dispatch_ip(tx_token, dispatch_phy) {
                 break;
             }
         }
     }
 
-    fn dispatch_ipv4<T, Q>(&mut self, tx_token: T, dispatch_phy: &mut Q) -> bool
+    fn dispatch_ip<T, Q>(&mut self, tx_token: T, dispatch_phy: &mut Q) -> bool
     where
         T: TxToken,
         Q: FnMut(&Packet, &mut Context, T),
@@ -376,9 +684,45 @@ impl<E: Ext> PollContext<'_, E> {
             return did_something_tcp;
         };
 
-        let (did_something_udp, _tx_token) = self.dispatch_udp(tx_token, dispatch_phy);
+        let (did_something_udp, tx_token) = self.dispatch_udp(tx_token, dispatch_phy);
 
-        did_something_tcp || did_something_udp
+        let Some(tx_token) = tx_token else {
+            return did_something_tcp || did_something_udp;
+        };
+
+        let (did_something_icmp, tx_token) = self.dispatch_icmp(tx_token, dispatch_phy);
+
+        let Some(tx_token) = tx_token else {
+            return did_something_tcp || did_something_udp || did_something_icmp;
+        };
+
+        let (did_something_raw_ip, tx_token) = self.dispatch_raw_ip(tx_token, dispatch_phy);
+
+        let Some(tx_token) = tx_token else {
+            return did_something_tcp
+                || did_something_udp
+                || did_something_icmp
+                || did_something_raw_ip;
+        };
+
+        let (did_something_igmp, tx_token) = self.dispatch_igmp(tx_token, dispatch_phy);
+
+        let Some(tx_token) = tx_token else {
+            return did_something_tcp
+                || did_something_udp
+                || did_something_icmp
+                || did_something_raw_ip
+                || did_something_igmp;
+        };
+
+        let did_something_dhcp = self.dispatch_dhcp(tx_token, dispatch_phy);
+
+        did_something_tcp
+            || did_something_udp
+            || did_something_icmp
+            || did_something_raw_ip
+            || did_something_igmp
+            || did_something_dhcp
     }
 
     fn dispatch_tcp<T, Q>(&mut self, tx_token: T, dispatch_phy: &mut Q) -> (bool, Option<T>)
@@ -499,18 +843,18 @@ impl<E: Ext> PollContext<'_, E> {
 
             let mut deferred = None;
 
-            let (cx, pending) = self.iface.inner_mut();
-            socket.dispatch(cx, |cx, ip_repr, udp_repr, udp_payload| {
-                let iface = PollableIfaceMut::new(cx, pending);
+            let (cx, ip_addrs, pending, igmp) = self.iface.inner_mut();
+            socket.dispatch(cx, |cx, ip_repr, udp_repr, udp_payload, multicast_loop| {
+                let iface = PollableIfaceMut::new(cx, ip_addrs, pending, igmp);
                 let mut this = PollContext::new(iface, self.sockets, &mut actions);
 
-                if ip_repr.dst_addr().is_broadcast() || !this.is_unicast_local(ip_repr.dst_addr()) {
+                if !this.is_unicast_local(ip_repr.dst_addr()) {
                     dispatch_phy(
                         &Packet::new(ip_repr.clone(), IpPayload::Udp(*udp_repr, udp_payload)),
                         this.iface.context_mut(),
                         tx_token.take().unwrap(),
                     );
-                    if !ip_repr.dst_addr().is_broadcast() {
+                    if !this.is_looped_back(ip_repr.dst_addr(), multicast_loop) {
                         return;
                     }
                 }
@@ -560,4 +904,204 @@ impl<E: Ext> PollContext<'_, E> {
 
         (did_something, tx_token)
     }
+
+    fn dispatch_icmp<T, Q>(&mut self, tx_token: T, dispatch_phy: &mut Q) -> (bool, Option<T>)
+    where
+        T: TxToken,
+        Q: FnMut(&Packet, &mut Context, T),
+    {
+        let mut tx_token = Some(tx_token);
+        let mut did_something = false;
+
+        for socket in self.sockets.icmp_socket_iter() {
+            if !socket.need_dispatch() {
+                continue;
+            }
+
+            // We set `did_something` even if no packets are actually generated. This is because a
+            // timer can expire, but no packets are actually generated.
+            did_something = true;
+
+            let mut deferred = None;
+
+            let (cx, ip_addrs, pending, igmp) = self.iface.inner_mut();
+            socket.dispatch(cx, |cx, ip_repr, icmp_repr| {
+                let mut iface = PollableIfaceMut::new(cx, ip_addrs, pending, igmp);
+
+                if !iface.has_ip_addr(ip_repr.dst_addr()) {
+                    let ip_payload = match icmp_repr {
+                        IcmpRepr::Ipv4(icmpv4_repr) => IpPayload::Icmpv4(icmpv4_repr.clone()),
+                        IcmpRepr::Ipv6(icmpv6_repr) => IpPayload::Icmpv6(icmpv6_repr.clone()),
+                    };
+                    dispatch_phy(
+                        &Packet::new(ip_repr.clone(), ip_payload),
+                        iface.context_mut(),
+                        tx_token.take().unwrap(),
+                    );
+                    return;
+                }
+
+                // We cannot process the packet now because it may cause deadlocks. We will copy
+                // the packet and process it after releasing the socket lock.
+                deferred = Some(emit_local_packet(ip_repr, icmp_repr));
+            });
+
+            if let Some(data) = deferred {
+                if let Some(reply) = self.process_local_packet(&data) {
+                    dispatch_phy(&reply, self.iface.context_mut(), tx_token.take().unwrap());
+                }
+            }
+
+            if tx_token.is_none() {
+                break;
+            }
+        }
+
+        (did_something, tx_token)
+    }
+
+    fn dispatch_raw_ip<T, Q>(&mut self, tx_token: T, dispatch_phy: &mut Q) -> (bool, Option<T>)
+    where
+        T: TxToken,
+        Q: FnMut(&Packet, &mut Context, T),
+    {
+        let mut tx_token = Some(tx_token);
+        let mut did_something = false;
+
+        for socket in self.sockets.raw_ip_socket_iter() {
+            if !socket.need_dispatch() {
+                continue;
+            }
+
+            // We set `did_something` even if no packets are actually generated. This is because a
+            // timer can expire, but no packets are actually generated.
+            did_something = true;
+
+            let mut deferred = None;
+
+            let (cx, ip_addrs, pending, igmp) = self.iface.inner_mut();
+            socket.dispatch(cx, |cx, ip_repr, ip_payload| {
+                let mut iface = PollableIfaceMut::new(cx, ip_addrs, pending, igmp);
+
+                if !iface.has_ip_addr(ip_repr.dst_addr()) {
+                    dispatch_phy(
+                        &Packet::new(ip_repr.clone(), IpPayload::Raw(ip_payload)),
+                        iface.context_mut(),
+                        tx_token.take().unwrap(),
+                    );
+                    return;
+                }
+
+                // We cannot process the packet now because it may cause deadlocks. We will copy
+                // the packet and process it after releasing the socket lock.
+                deferred = Some({
+                    let mut data = vec![0; ip_repr.buffer_len()];
+                    ip_repr.emit(&mut data[..], &ChecksumCapabilities::default());
+                    data[ip_repr.header_len()..].copy_from_slice(ip_payload);
+                    data
+                });
+            });
+
+            if let Some(data) = deferred {
+                if let Some(reply) = self.process_local_packet(&data) {
+                    dispatch_phy(&reply, self.iface.context_mut(), tx_token.take().unwrap());
+                }
+            }
+
+            if tx_token.is_none() {
+                break;
+            }
+        }
+
+        (did_something, tx_token)
+    }
+
+    fn dispatch_igmp<T, Q>(&mut self, tx_token: T, dispatch_phy: &mut Q) -> (bool, Option<T>)
+    where
+        T: TxToken,
+        Q: FnMut(&Packet, &mut Context, T),
+    {
+        let Some(message) = self.iface.pop_pending_igmp() else {
+            return (false, Some(tx_token));
+        };
+
+        // TODO: Add the Router Alert option, which RFC 2236 requires but is not supported by
+        // `smoltcp`'s `Ipv4Repr`.
+        let igmp_payload = message.to_bytes();
+        let ip_repr = Ipv4Repr {
+            src_addr: self
+                .iface
+                .context()
+                .ipv4_addr()
+                .unwrap_or(Ipv4Address::UNSPECIFIED),
+            dst_addr: message.dst_addr(),
+            next_header: IpProtocol::Igmp,
+            payload_len: igmp_payload.len(),
+            hop_limit: 1,
+        };
+
+        // IGMP messages are only meaningful to the multicast routers, so they never need to be
+        // processed locally.
+        dispatch_phy(
+            &Packet::new_ipv4(ip_repr, IpPayload::Raw(&igmp_payload)),
+            self.iface.context_mut(),
+            tx_token,
+        );
+
+        (true, None)
+    }
+
+    fn dispatch_dhcp<T, Q>(&mut self, tx_token: T, dispatch_phy: &mut Q) -> bool
+    where
+        T: TxToken,
+        Q: FnMut(&Packet, &mut Context, T),
+    {
+        let Some(socket) = self.sockets.dhcp_socket() else {
+            return false;
+        };
+
+        // DHCP packets are always sent to the DHCP server or broadcast, so they never need to be
+        // processed locally. Unlike other sockets, we only report that we did something if a
+        // packet is actually generated. Otherwise, the DHCP socket, which always has a timer,
+        // would keep the loop running forever.
+        socket.dispatch(
+            self.iface.context_mut(),
+            |cx, ip_repr, udp_repr, dhcp_repr| {
+                dispatch_phy(
+                    &Packet::new(
+                        IpRepr::Ipv4(*ip_repr),
+                        IpPayload::Dhcpv4(*udp_repr, dhcp_repr.clone()),
+                    ),
+                    cx,
+                    tx_token,
+                );
+            },
+        )
+    }
+}
+
+/// Emits an ICMP packet that is generated locally, so that it can be processed by
+/// [`PollContext::process_local_packet`].
+fn emit_local_packet(ip_repr: &IpRepr, icmp_repr: &IcmpRepr) -> Vec<u8> {
+    // The checksums are verified when the packet is processed, so they must be filled in.
+    let checksum_caps = ChecksumCapabilities::default();
+
+    let mut data = vec![0; ip_repr.buffer_len()];
+    ip_repr.emit(&mut data[..], &checksum_caps);
+
+    let icmp_data = &mut data[ip_repr.header_len()..];
+    match (ip_repr, icmp_repr) {
+        (IpRepr::Ipv4(_), IcmpRepr::Ipv4(icmpv4_repr)) => {
+            icmpv4_repr.emit(&mut Icmpv4Packet::new_unchecked(icmp_data), &checksum_caps)
+        }
+        (IpRepr::Ipv6(ipv6_repr), IcmpRepr::Ipv6(icmpv6_repr)) => icmpv6_repr.emit(
+            &ipv6_repr.src_addr,
+            &ipv6_repr.dst_addr,
+            &mut Icmpv6Packet::new_unchecked(icmp_data),
+            &checksum_caps,
+        ),
+        _ => unreachable!("the ICMP version does not match the IP version"),
+    }
+
+    data
 }
@@ -1,12 +1,24 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use alloc::{collections::btree_set::BTreeSet, sync::Arc};
+use alloc::{collections::btree_set::BTreeSet, sync::Arc, vec::Vec};
 use core::{
     borrow::Borrow,
     sync::atomic::{AtomicU64, Ordering},
 };
 
+use smoltcp::{
+    iface::Route,
+    wire::{
+        IpAddress, IpCidr, Ipv4Address, Ipv4Cidr, Ipv6Address, Ipv6Cidr, IPV6_LINK_LOCAL_ALL_NODES,
+    },
+};
+
+use super::{
+    igmp::{IgmpMessage, IgmpState},
+    Ipv4Route,
+};
 use crate::{
+    errors::iface::ConfigError,
     ext::Ext,
     socket::{NeedIfacePoll, TcpConnectionBg},
 };
@@ -17,21 +29,32 @@ use crate::{
 /// poll.
 pub(crate) struct PollableIface<E: Ext> {
     interface: smoltcp::iface::Interface,
+    /// A copy of the interface's addresses.
+    ///
+    /// The `smoltcp` context does not expose its addresses, so we keep a copy here to check the
+    /// destination addresses of incoming packets.
+    ip_addrs: Vec<IpCidr>,
     pending_conns: PendingConnSet<E>,
+    igmp: IgmpState,
 }
 
 impl<E: Ext> PollableIface<E> {
     pub(super) fn new(interface: smoltcp::iface::Interface) -> Self {
+        let ip_addrs = interface.ip_addrs().to_vec();
         Self {
             interface,
+            ip_addrs,
             pending_conns: PendingConnSet::new(),
+            igmp: IgmpState::new(),
         }
     }
 
     pub(super) fn as_mut(&mut self) -> PollableIfaceMut<E> {
         PollableIfaceMut {
             context: self.interface.context(),
+            ip_addrs: &self.ip_addrs,
             pending_conns: &mut self.pending_conns,
+            igmp: &mut self.igmp,
         }
     }
 
@@ -40,10 +63,133 @@ impl<E: Ext> PollableIface<E> {
     }
 
     pub(super) fn prefix_len(&self) -> Option<u8> {
-        self.interface
-            .ip_addrs()
-            .first()
-            .map(|ip_addr| ip_addr.prefix_len())
+        self.ipv4_cidrs().first().map(|cidr| cidr.prefix_len())
+    }
+
+    pub(super) fn ipv4_gateway(&mut self) -> Option<smoltcp::wire::Ipv4Address> {
+        let mut gateway = None;
+        self.interface.routes_mut().update(|routes| {
+            gateway = routes
+                .iter()
+                .find_map(|route| match (route.cidr, route.via_router) {
+                    (IpCidr::Ipv4(cidr), IpAddress::Ipv4(router)) if cidr.prefix_len() == 0 => {
+                        Some(router)
+                    }
+                    _ => None,
+                });
+        });
+        gateway
+    }
+
+    pub(super) fn ipv4_cidrs(&self) -> Vec<Ipv4Cidr> {
+        self.ip_addrs
+            .iter()
+            .filter_map(|ip_addr| match ip_addr {
+                IpCidr::Ipv4(cidr) => Some(*cidr),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub(super) fn ipv6_cidrs(&self) -> Vec<Ipv6Cidr> {
+        self.ip_addrs
+            .iter()
+            .filter_map(|ip_addr| match ip_addr {
+                IpCidr::Ipv6(cidr) => Some(*cidr),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub(super) fn add_ip_cidr(&mut self, cidr: IpCidr) -> Result<(), ConfigError> {
+        let mut result = Ok(());
+        self.interface.update_ip_addrs(|ip_addrs| {
+            if ip_addrs.contains(&cidr) {
+                result = Err(ConfigError::AlreadyExists);
+            } else if ip_addrs.push(cidr).is_err() {
+                result = Err(ConfigError::NoSpace);
+            }
+        });
+        self.ip_addrs = self.interface.ip_addrs().to_vec();
+        result
+    }
+
+    pub(super) fn del_ip_cidr(&mut self, cidr: IpCidr) -> Result<(), ConfigError> {
+        let mut result = Err(ConfigError::NotFound);
+        self.interface.update_ip_addrs(|ip_addrs| {
+            if let Some(pos) = ip_addrs.iter().position(|addr| *addr == cidr) {
+                ip_addrs.remove(pos);
+                result = Ok(());
+            }
+        });
+        self.ip_addrs = self.interface.ip_addrs().to_vec();
+        result
+    }
+
+    pub(super) fn ipv4_routes(&mut self) -> Vec<Ipv4Route> {
+        let mut ipv4_routes = Vec::new();
+        self.interface.routes_mut().update(|routes| {
+            ipv4_routes.extend(routes.iter().filter_map(|route| {
+                match (route.cidr, route.via_router) {
+                    (IpCidr::Ipv4(cidr), IpAddress::Ipv4(gateway)) => {
+                        Some(Ipv4Route { cidr, gateway })
+                    }
+                    _ => None,
+                }
+            }));
+        });
+        ipv4_routes
+    }
+
+    pub(super) fn add_ipv4_route(&mut self, route: Ipv4Route) -> Result<(), ConfigError> {
+        let mut result = Ok(());
+        self.interface.routes_mut().update(|routes| {
+            if routes
+                .iter()
+                .any(|existing| existing.cidr == IpCidr::Ipv4(route.cidr))
+            {
+                result = Err(ConfigError::AlreadyExists);
+                return;
+            }
+
+            let new_route = Route {
+                cidr: IpCidr::Ipv4(route.cidr),
+                via_router: IpAddress::Ipv4(route.gateway),
+                preferred_until: None,
+                expires_at: None,
+            };
+            if routes.push(new_route).is_err() {
+                result = Err(ConfigError::NoSpace);
+            }
+        });
+        result
+    }
+
+    pub(super) fn del_ipv4_route(
+        &mut self,
+        cidr: Ipv4Cidr,
+        gateway: Option<Ipv4Address>,
+    ) -> Result<(), ConfigError> {
+        let mut result = Err(ConfigError::NotFound);
+        self.interface.routes_mut().update(|routes| {
+            let pos = routes.iter().position(|route| {
+                route.cidr == IpCidr::Ipv4(cidr)
+                    && gateway.is_none_or(|gateway| route.via_router == IpAddress::Ipv4(gateway))
+            });
+            if let Some(pos) = pos {
+                routes.remove(pos);
+                result = Ok(());
+            }
+        });
+        result
+    }
+
+    pub(super) fn join_ipv4_multicast_group(&mut self, group: Ipv4Address) {
+        self.igmp.join(group);
+    }
+
+    pub(super) fn leave_ipv4_multicast_group(&mut self, group: Ipv4Address) {
+        self.igmp.leave(group);
     }
 
     /// Returns the next poll time.
@@ -78,7 +224,9 @@ impl<E: Ext> PollableIface<E> {
 /// [`smoltcp`] APIs.
 pub(crate) struct PollableIfaceMut<'a, E: Ext> {
     context: &'a mut smoltcp::iface::Context,
+    ip_addrs: &'a [IpCidr],
     pending_conns: &'a mut PendingConnSet<E>,
+    igmp: &'a mut IgmpState,
 }
 
 // FIXME: We provide `new()` and `inner_mut()` as `pub(crate)` methods because it's necessary to
@@ -87,16 +235,27 @@ pub(crate) struct PollableIfaceMut<'a, E: Ext> {
 impl<'a, E: Ext> PollableIfaceMut<'a, E> {
     pub(crate) fn new(
         context: &'a mut smoltcp::iface::Context,
+        ip_addrs: &'a [IpCidr],
         pending_conns: &'a mut PendingConnSet<E>,
+        igmp: &'a mut IgmpState,
     ) -> Self {
         Self {
             context,
+            ip_addrs,
             pending_conns,
+            igmp,
         }
     }
 
-    pub(crate) fn inner_mut(&mut self) -> (&mut smoltcp::iface::Context, &mut PendingConnSet<E>) {
-        (self.context, self.pending_conns)
+    pub(crate) fn inner_mut(
+        &mut self,
+    ) -> (
+        &mut smoltcp::iface::Context,
+        &'a [IpCidr],
+        &mut PendingConnSet<E>,
+        &mut IgmpState,
+    ) {
+        (self.context, self.ip_addrs, self.pending_conns, self.igmp)
     }
 }
 
@@ -105,6 +264,14 @@ impl<E: Ext> PollableIfaceMut<'_, E> {
         let now = self.context.now.total_millis() as u64;
         self.pending_conns.pop_tcp_before_now(now)
     }
+
+    pub(super) fn process_igmp(&mut self, payload: &[u8]) {
+        self.igmp.process(payload);
+    }
+
+    pub(super) fn pop_pending_igmp(&mut self) -> Option<IgmpMessage> {
+        self.igmp.pop_pending()
+    }
 }
 
 impl<E: Ext> PollableIfaceMut<'_, E> {
@@ -118,6 +285,70 @@ impl<E: Ext> PollableIfaceMut<'_, E> {
         self.context
     }
 
+    /// Returns the `smoltcp` context together with the addresses of the interface.
+    pub(super) fn context_and_ip_addrs(&mut self) -> (&mut smoltcp::iface::Context, &[IpCidr]) {
+        (self.context, self.ip_addrs)
+    }
+
+    /// Returns whether `addr` is one of the addresses of the interface.
+    pub(super) fn has_ip_addr(&self, addr: IpAddress) -> bool {
+        self.ip_addrs.iter().any(|cidr| cidr.address() == addr)
+    }
+
+    /// Returns whether `addr` is the limited broadcast address or the directed broadcast address
+    /// of one of the IPv4 networks that the interface is attached to.
+    pub(super) fn is_ipv4_broadcast(&self, addr: Ipv4Address) -> bool {
+        addr.is_broadcast()
+            || self.ip_addrs.iter().any(|cidr| match cidr {
+                IpCidr::Ipv4(cidr) => cidr.broadcast() == Some(addr),
+                _ => false,
+            })
+    }
+
+    /// Returns whether the interface belongs to the IPv4 multicast group `addr`.
+    ///
+    /// Every interface belongs to the all-systems group. Other groups must be joined first.
+    pub(super) fn has_ipv4_multicast_group(&self, addr: Ipv4Address) -> bool {
+        self.igmp.has_group(addr)
+    }
+
+    /// Returns whether the interface belongs to the IPv6 multicast group `addr`.
+    ///
+    /// Every interface with IPv6 addresses joins the all-nodes group and the solicited-node
+    /// groups of its addresses.
+    ///
+    /// Reference: <https://datatracker.ietf.org/doc/html/rfc4291#section-2.8>.
+    pub(super) fn has_ipv6_multicast_group(&self, addr: Ipv6Address) -> bool {
+        let mut ipv6_addrs = self.ip_addrs.iter().filter_map(|cidr| match cidr {
+            IpCidr::Ipv6(cidr) => Some(cidr.address()),
+            _ => None,
+        });
+
+        if addr == IPV6_LINK_LOCAL_ALL_NODES {
+            ipv6_addrs.next().is_some()
+        } else {
+            ipv6_addrs.any(|ipv6_addr| solicited_node_addr(ipv6_addr) == addr)
+        }
+    }
+
+    /// Selects the IPv6 source address for packets sent to `dst_addr`.
+    ///
+    /// An address in the same network as `dst_addr` is preferred. Otherwise, the first IPv6
+    /// address of the interface is used.
+    pub(super) fn ipv6_source_addr(&self, dst_addr: &Ipv6Address) -> Option<Ipv6Address> {
+        let ipv6_cidrs = || {
+            self.ip_addrs.iter().filter_map(|cidr| match cidr {
+                IpCidr::Ipv6(cidr) => Some(*cidr),
+                _ => None,
+            })
+        };
+
+        ipv6_cidrs()
+            .find(|cidr| cidr.contains_addr(dst_addr))
+            .or_else(|| ipv6_cidrs().next())
+            .map(|cidr| cidr.address())
+    }
+
     /// Updates the next poll time of `socket` to `poll_at`.
     ///
     /// This method (or [`PollableIface::update_next_poll_at_ms`]) should be called after network
@@ -131,6 +362,23 @@ impl<E: Ext> PollableIfaceMut<'_, E> {
     }
 }
 
+/// Returns the solicited-node multicast address of `addr`.
+///
+/// Reference: <https://datatracker.ietf.org/doc/html/rfc4291#section-2.7.1>.
+pub(super) fn solicited_node_addr(addr: Ipv6Address) -> Ipv6Address {
+    let octets = addr.octets();
+    Ipv6Address::new(
+        0xff02,
+        0,
+        0,
+        0,
+        0,
+        1,
+        0xff00 | octets[13] as u16,
+        ((octets[14] as u16) << 8) | octets[15] as u16,
+    )
+}
+
 /// A key to sort sockets by their next poll time.
 pub(crate) struct PollKey {
     next_poll_at_ms: AtomicU64,
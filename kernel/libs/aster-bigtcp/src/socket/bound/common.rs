@@ -15,11 +15,12 @@ use crate::{
 
 pub struct Socket<T: Inner<E>, E: Ext>(pub(super) Takeable<Arc<SocketBg<T, E>>>);
 
-/// [`TcpConnectionInner`], [`TcpListenerInner`], or [`UdpSocketInner`].
+/// [`TcpConnectionInner`], [`TcpListenerInner`], [`UdpSocketInner`], or [`IcmpSocketInner`].
 ///
 /// [`TcpConnectionInner`]: super::tcp_conn::TcpConnectionInner
 /// [`TcpListenerInner`]: super::tcp_listen::TcpListenerInner
 /// [`UdpSocketInner`]: super::udp::UdpSocketInner
+/// [`IcmpSocketInner`]: super::icmp::IcmpSocketInner
 pub trait Inner<E: Ext> {
     type Observer: SocketEventObserver;
 
@@ -30,7 +31,8 @@ pub trait Inner<E: Ext> {
         Self: Sized;
 }
 
-/// Common states shared by [`TcpConnectionBg`], [`TcpListenerBg`], and [`UdpSocketBg`].
+/// Common states shared by [`TcpConnectionBg`], [`TcpListenerBg`], [`UdpSocketBg`], and
+/// [`IcmpSocketBg`].
 ///
 /// In the type name, `Bg` means "background". Its meaning is described below:
 /// - A foreground socket (e.g., [`TcpConnection`]) handles system calls from the user program.
@@ -39,6 +41,7 @@ pub trait Inner<E: Ext> {
 /// [`TcpConnectionBg`]: super::tcp_conn::TcpConnectionBg
 /// [`TcpListenerBg`]: super::tcp_listen::TcpListenerBg
 /// [`UdpSocketBg`]: super::udp::UdpSocketBg
+/// [`IcmpSocketBg`]: super::icmp::IcmpSocketBg
 /// [`TcpConnection`]: super::tcp_conn::TcpConnection
 pub struct SocketBg<T: Inner<E>, E: Ext> {
     pub(super) bound: BoundPort<E>,
@@ -91,7 +94,7 @@ impl<T: Inner<E>, E: Ext> Socket<T, E> {
         self.0.observer.call_once(|| new_observer);
     }
 
-    pub fn local_endpoint(&self) -> Option<IpEndpoint> {
+    pub fn local_endpoint(&self) -> IpEndpoint {
         self.0.bound.endpoint()
     }
 
@@ -0,0 +1,214 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use alloc::{boxed::Box, sync::Arc, vec::Vec};
+
+use aster_softirq::BottomHalfDisabled;
+use ostd::sync::SpinLock;
+use smoltcp::{
+    iface::Context,
+    socket::{dhcpv4::Event, PollAt},
+    wire::{
+        DhcpRepr, Ipv4Address, Ipv4Cidr, Ipv4Repr, UdpRepr, DHCP_CLIENT_PORT, DHCP_SERVER_PORT,
+    },
+};
+use spin::once::Once;
+
+use crate::{
+    errors::BindError,
+    ext::Ext,
+    iface::Iface,
+    socket::{
+        event::{SocketEventObserver, SocketEvents},
+        unbound::{new_dhcp_socket, RawDhcpSocket},
+    },
+};
+
+/// A DHCPv4 client socket.
+///
+/// The socket runs the DHCP protocol on the iface as the iface is polled. It does _not_ configure
+/// the iface by itself. Instead, it reports the lease as [`DhcpEvent`]s, and the user is
+/// responsible for updating the addresses and the routes of the iface.
+///
+/// Like other sockets that do not occupy a port, a DHCP socket is attached to an iface. There can
+/// be at most one DHCP socket on each iface.
+pub struct DhcpSocket<E: Ext>(Arc<DhcpSocketBg<E>>);
+
+/// The background part of a [`DhcpSocket`].
+///
+/// See [`SocketBg`] for the meaning of "background".
+///
+/// [`SocketBg`]: super::common::SocketBg
+pub(crate) struct DhcpSocketBg<E: Ext> {
+    iface: Arc<dyn Iface<E>>,
+    socket: SpinLock<Box<RawDhcpSocket>, BottomHalfDisabled>,
+    event: SpinLock<Option<DhcpEvent>, BottomHalfDisabled>,
+    observer: Once<E::UdpEventObserver>,
+}
+
+/// A change of the lease reported by a [`DhcpSocket`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DhcpEvent {
+    /// A lease has been acquired or renewed with a different configuration.
+    Configured(DhcpConfig),
+    /// The lease has been lost (e.g., it has expired or the server has declined it).
+    Deconfigured,
+}
+
+/// The IPv4 configuration provided by a DHCP server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DhcpConfig {
+    /// The address of the DHCP server.
+    pub server: Ipv4Address,
+    /// The address of the iface, along with the prefix length of the network.
+    pub address: Ipv4Cidr,
+    /// The default gateway, if any.
+    pub router: Option<Ipv4Address>,
+    /// The DNS servers.
+    pub dns_servers: Vec<Ipv4Address>,
+}
+
+impl From<Event<'_>> for DhcpEvent {
+    fn from(event: Event<'_>) -> Self {
+        match event {
+            Event::Configured(config) => Self::Configured(DhcpConfig {
+                server: config.server.address,
+                address: config.address,
+                router: config.router,
+                dns_servers: config.dns_servers.iter().copied().collect(),
+            }),
+            Event::Deconfigured => Self::Deconfigured,
+        }
+    }
+}
+
+impl<E: Ext> DhcpSocketBg<E> {
+    /// Returns whether the socket can process a UDP packet with the ports.
+    pub(crate) fn can_process(&self, src_port: u16, dst_port: u16) -> bool {
+        src_port == DHCP_SERVER_PORT && dst_port == DHCP_CLIENT_PORT
+    }
+
+    /// Processes an incoming DHCP packet.
+    ///
+    /// The caller must have checked the ports with [`Self::can_process`].
+    pub(crate) fn process(
+        &self,
+        cx: &mut Context,
+        ip_repr: &Ipv4Repr,
+        udp_repr: &UdpRepr,
+        udp_payload: &[u8],
+    ) {
+        let mut socket = self.socket.lock();
+
+        socket.process(cx, ip_repr, udp_repr, udp_payload);
+
+        self.update_event(&mut socket);
+    }
+
+    /// Tries to generate an outgoing packet and dispatches the generated packet.
+    ///
+    /// Returns whether a packet is generated.
+    pub(crate) fn dispatch<D>(&self, cx: &mut Context, dispatch: D) -> bool
+    where
+        D: FnOnce(&mut Context, &Ipv4Repr, &UdpRepr, &DhcpRepr),
+    {
+        let mut socket = self.socket.lock();
+        let mut dispatched = false;
+
+        socket
+            .dispatch(cx, |cx, (ip_repr, udp_repr, dhcp_repr)| {
+                dispatch(cx, &ip_repr, &udp_repr, &dhcp_repr);
+                dispatched = true;
+                Ok::<(), ()>(())
+            })
+            .unwrap();
+
+        // The lease can expire when the timer fires.
+        self.update_event(&mut socket);
+
+        dispatched
+    }
+
+    /// Returns the next time (in milliseconds) at which the socket needs to be polled.
+    pub(crate) fn next_poll_at_ms(&self, cx: &mut Context) -> Option<u64> {
+        match self.socket.lock().poll_at(cx) {
+            PollAt::Now => Some(0),
+            PollAt::Time(instant) => Some(instant.total_millis() as u64),
+            PollAt::Ingress => None,
+        }
+    }
+
+    fn update_event(&self, socket: &mut RawDhcpSocket) {
+        let Some(event) = socket.poll() else {
+            return;
+        };
+
+        // Only the latest state of the lease matters, so an event that has not been received is
+        // simply overwritten.
+        *self.event.lock() = Some(DhcpEvent::from(event));
+
+        if let Some(observer) = self.observer.get() {
+            observer.on_events(SocketEvents::CAN_RECV);
+        }
+    }
+}
+
+impl<E: Ext> Drop for DhcpSocket<E> {
+    fn drop(&mut self) {
+        // A DHCP socket can be removed immediately.
+        self.0.iface.common().remove_dhcp_socket(&self.0);
+    }
+}
+
+impl<E: Ext> DhcpSocket<E> {
+    /// Attaches a new DHCP socket to the iface.
+    ///
+    /// This method fails with [`BindError::InUse`] if there is already a DHCP socket on the iface.
+    ///
+    /// Polling the iface is _always_ required after this method succeeds.
+    pub fn new_bind(
+        iface: Arc<dyn Iface<E>>,
+        observer: E::UdpEventObserver,
+    ) -> Result<Self, BindError> {
+        let socket_bg = Arc::new(DhcpSocketBg {
+            iface,
+            socket: SpinLock::new(new_dhcp_socket()),
+            event: SpinLock::new(None),
+            observer: Once::initialized(observer),
+        });
+
+        socket_bg
+            .iface
+            .common()
+            .register_dhcp_socket(socket_bg.clone())
+            .map_err(|_| BindError::InUse)?;
+
+        Ok(Self(socket_bg))
+    }
+
+    pub fn iface(&self) -> &Arc<dyn Iface<E>> {
+        &self.0.iface
+    }
+
+    /// Takes the latest change of the lease, if any.
+    ///
+    /// Polling the iface is _not_ required after this method succeeds.
+    pub fn poll_event(&self) -> Option<DhcpEvent> {
+        self.0.event.lock().take()
+    }
+
+    /// Returns whether there is a change of the lease to take.
+    pub fn has_event(&self) -> bool {
+        self.0.event.lock().is_some()
+    }
+
+    /// Drops the current lease, if any, and restarts the discovery.
+    ///
+    /// If there is a lease, a [`DhcpEvent::Deconfigured`] event will be reported.
+    ///
+    /// Polling the iface is _always_ required after this method succeeds.
+    pub fn reset(&self) {
+        let mut socket = self.0.socket.lock();
+        socket.reset();
+        self.0.update_event(&mut socket);
+    }
+}
@@ -0,0 +1,185 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use alloc::{boxed::Box, sync::Arc};
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use aster_softirq::BottomHalfDisabled;
+use ostd::sync::SpinLock;
+use smoltcp::{
+    iface::Context,
+    socket::icmp::Endpoint,
+    wire::{IcmpRepr, Icmpv4Repr, IpAddress, IpRepr, Ipv4Repr},
+};
+
+use super::common::{Inner, Socket, SocketBg};
+use crate::{
+    errors::icmp::SendError,
+    ext::Ext,
+    iface::BoundPort,
+    socket::{event::SocketEvents, unbound::new_icmp_socket, RawIcmpSocket},
+};
+
+/// An ICMP socket that sends echo requests and receives echo replies.
+///
+/// The port of the bound port is used as the identifier of the echo messages. This is how Linux
+/// implements ICMP "ping" sockets.
+pub type IcmpSocket<E> = Socket<IcmpSocketInner, E>;
+
+/// States needed by [`IcmpSocketBg`].
+pub struct IcmpSocketInner {
+    socket: SpinLock<Box<RawIcmpSocket>, BottomHalfDisabled>,
+    need_dispatch: AtomicBool,
+}
+
+impl<E: Ext> Inner<E> for IcmpSocketInner {
+    type Observer = E::UdpEventObserver;
+
+    fn on_drop(this: &Arc<SocketBg<Self, E>>) {
+        // An ICMP socket can be removed immediately.
+        this.bound.iface().common().remove_icmp_socket(this);
+    }
+}
+
+pub(crate) type IcmpSocketBg<E> = SocketBg<IcmpSocketInner, E>;
+
+impl<E: Ext> IcmpSocketBg<E> {
+    /// Tries to process an incoming ICMPv4 packet and returns whether the packet is processed.
+    pub(crate) fn process_v4(
+        &self,
+        cx: &mut Context,
+        ip_repr: &Ipv4Repr,
+        icmp_repr: &Icmpv4Repr,
+    ) -> bool {
+        let mut socket = self.inner.socket.lock();
+
+        if !socket.accepts_v4(cx, ip_repr, icmp_repr) {
+            return false;
+        }
+
+        socket.process_v4(cx, ip_repr, icmp_repr);
+
+        self.notify_events(SocketEvents::CAN_RECV);
+
+        true
+    }
+
+    /// Tries to generate an outgoing packet and dispatches the generated packet.
+    pub(crate) fn dispatch<D>(&self, cx: &mut Context, dispatch: D)
+    where
+        D: FnOnce(&mut Context, &IpRepr, &IcmpRepr),
+    {
+        let mut socket = self.inner.socket.lock();
+
+        socket
+            .dispatch(cx, |cx, (ip_repr, icmp_repr)| {
+                dispatch(cx, &ip_repr, &icmp_repr);
+                Ok::<(), ()>(())
+            })
+            .unwrap();
+
+        // For ICMP, dequeuing a packet means that we can queue more packets.
+        self.notify_events(SocketEvents::CAN_SEND);
+
+        self.inner
+            .need_dispatch
+            .store(socket.send_queue() > 0, Ordering::Relaxed);
+    }
+
+    /// Returns whether the socket _may_ generate an outgoing packet.
+    ///
+    /// The check is intended to be lock-free and fast, but may have false positives.
+    pub(crate) fn need_dispatch(&self) -> bool {
+        self.inner.need_dispatch.load(Ordering::Relaxed)
+    }
+}
+
+impl<E: Ext> IcmpSocket<E> {
+    /// Binds to a specified endpoint.
+    ///
+    /// Polling the iface is _not_ required after this method succeeds.
+    pub fn new_bind(
+        bound: BoundPort<E>,
+        observer: E::UdpEventObserver,
+    ) -> Result<Self, (BoundPort<E>, smoltcp::socket::icmp::BindError)> {
+        let socket = {
+            let mut socket = new_icmp_socket();
+
+            if let Err(err) = socket.bind(Endpoint::Ident(bound.port())) {
+                return Err((bound, err));
+            }
+
+            socket
+        };
+
+        let inner = IcmpSocketInner {
+            socket: SpinLock::new(socket),
+            need_dispatch: AtomicBool::new(false),
+        };
+
+        let socket = Self::new(bound, inner);
+        socket.init_observer(observer);
+        socket
+            .iface()
+            .common()
+            .register_icmp_socket(socket.inner().clone());
+
+        Ok(socket)
+    }
+
+    /// Sends an ICMP message.
+    ///
+    /// `f` should fill in the whole ICMP message, including the ICMP header. Note that the
+    /// checksum will be recalculated before the message is sent.
+    ///
+    /// Polling the iface is _always_ required after this method succeeds.
+    pub fn send<F, R>(&self, size: usize, remote_addr: IpAddress, f: F) -> Result<R, SendError>
+    where
+        F: FnOnce(&mut [u8]) -> R,
+    {
+        let mut socket = self.0.inner.socket.lock();
+
+        if size > socket.packet_send_capacity() {
+            return Err(SendError::TooLarge);
+        }
+
+        let buffer = match socket.send(size, remote_addr) {
+            Ok(data) => data,
+            Err(err) => return Err(err.into()),
+        };
+        let result = f(buffer);
+
+        self.0
+            .inner
+            .need_dispatch
+            .store(socket.send_queue() > 0, Ordering::Relaxed);
+
+        Ok(result)
+    }
+
+    /// Receives an ICMP message, including the ICMP header.
+    ///
+    /// Polling the iface is _not_ required after this method succeeds.
+    pub fn recv<F, R>(&self, f: F) -> Result<R, smoltcp::socket::icmp::RecvError>
+    where
+        F: FnOnce(&[u8], IpAddress) -> R,
+    {
+        let mut socket = self.0.inner.socket.lock();
+
+        let (data, remote_addr) = socket.recv()?;
+        let result = f(data, remote_addr);
+
+        Ok(result)
+    }
+
+    /// Calls `f` with an immutable reference to the associated [`RawIcmpSocket`].
+    //
+    // NOTE: If a mutable reference is required, add a method above that correctly updates the next
+    // polling time.
+    pub fn raw_with<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&RawIcmpSocket) -> R,
+    {
+        let socket = self.0.inner.socket.lock();
+        f(&socket)
+    }
+}
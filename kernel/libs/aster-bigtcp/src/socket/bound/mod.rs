@@ -1,14 +1,26 @@
 // SPDX-License-Identifier: MPL-2.0
 
 mod common;
+mod dhcp;
+mod icmp;
+mod packet;
+mod raw;
 mod tcp_conn;
 mod tcp_listen;
 mod udp;
 
 pub use common::NeedIfacePoll;
+pub(crate) use dhcp::DhcpSocketBg;
+pub use dhcp::{DhcpConfig, DhcpEvent, DhcpSocket};
+pub use icmp::IcmpSocket;
+pub(crate) use icmp::IcmpSocketBg;
+pub(crate) use packet::PacketSocketBg;
+pub use packet::{PacketFilter, PacketMeta, PacketSocket, PacketType};
+pub(crate) use raw::RawIpSocketBg;
+pub use raw::{RawIpHeader, RawIpSocket};
 pub use tcp_conn::{ConnectState, RawTcpSocketExt, TcpConnection};
 pub(crate) use tcp_conn::{TcpConnectionBg, TcpProcessResult};
 pub use tcp_listen::TcpListener;
 pub(crate) use tcp_listen::TcpListenerBg;
-pub use udp::UdpSocket;
 pub(crate) use udp::UdpSocketBg;
+pub use udp::{UdpRecvMeta, UdpSendOptions, UdpSocket};
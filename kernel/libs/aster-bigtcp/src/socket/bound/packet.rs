@@ -0,0 +1,272 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use alloc::{sync::Arc, vec};
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use aster_softirq::BottomHalfDisabled;
+use ostd::sync::SpinLock;
+use smoltcp::{
+    storage::{PacketBuffer, PacketMetadata},
+    wire::{EthernetAddress, EthernetFrame, EthernetProtocol, ETHERNET_HEADER_LEN},
+};
+use spin::once::Once;
+
+use crate::{
+    errors::packet::{RecvError, SendError},
+    ext::Ext,
+    iface::Iface,
+    socket::{
+        event::{SocketEventObserver, SocketEvents},
+        unbound::{PACKET_METADATA_LEN, PACKET_RECV_PAYLOAD_LEN, PACKET_SEND_PAYLOAD_LEN},
+    },
+};
+
+/// A packet socket.
+///
+/// A packet socket receives a copy of every link-layer frame that passes through the iface in
+/// either direction if its filter accepts the frame, and sends link-layer frames built by the user.
+/// Frames are always Ethernet frames. For ifaces that have no link-layer header (e.g., the loopback
+/// iface), a fake Ethernet header with zero addresses is used, as Linux does.
+///
+/// Unlike other sockets, a packet socket does not occupy a port. It is attached to an iface.
+pub struct PacketSocket<E: Ext>(Arc<PacketSocketBg<E>>);
+
+/// The background part of a [`PacketSocket`].
+///
+/// See [`SocketBg`] for the meaning of "background".
+///
+/// [`SocketBg`]: super::common::SocketBg
+pub(crate) struct PacketSocketBg<E: Ext> {
+    iface: Arc<dyn Iface<E>>,
+    filter: PacketFilter,
+    recv_buffer: SpinLock<PacketBuffer<'static, PacketMeta>, BottomHalfDisabled>,
+    send_buffer: SpinLock<PacketBuffer<'static, ()>, BottomHalfDisabled>,
+    need_dispatch: AtomicBool,
+    observer: Once<E::UdpEventObserver>,
+}
+
+/// The frames that a [`PacketSocket`] receives.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PacketFilter {
+    /// The socket receives no frames, so it can only send frames.
+    None,
+    /// The socket receives frames of all protocols.
+    All,
+    /// The socket receives incoming frames of the protocol.
+    Protocol(EthernetProtocol),
+}
+
+impl PacketFilter {
+    fn accepts(&self, protocol: EthernetProtocol, packet_type: PacketType) -> bool {
+        match self {
+            Self::None => false,
+            Self::All => true,
+            // Like Linux, outgoing frames are only seen by sockets receiving all protocols.
+            Self::Protocol(_) if packet_type == PacketType::Outgoing => false,
+            Self::Protocol(accepted) => *accepted == protocol,
+        }
+    }
+}
+
+/// The type of a frame received by a [`PacketSocket`].
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.0.9/source/include/uapi/linux/if_packet.h#L26>
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum PacketType {
+    /// The frame is sent to us.
+    Host = 0,
+    /// The frame is a broadcast frame.
+    Broadcast = 1,
+    /// The frame is a multicast frame.
+    Multicast = 2,
+    /// The frame is sent to another host and is received in promiscuous mode.
+    OtherHost = 3,
+    /// The frame is sent by us.
+    Outgoing = 4,
+}
+
+/// The metadata of a frame received by a [`PacketSocket`].
+#[derive(Debug, Clone, Copy)]
+pub struct PacketMeta {
+    /// The type of the frame.
+    pub packet_type: PacketType,
+    /// The protocol in the link-layer header.
+    pub protocol: EthernetProtocol,
+    /// The source address in the link-layer header.
+    pub src_addr: EthernetAddress,
+}
+
+impl<E: Ext> PacketSocketBg<E> {
+    /// Processes a frame that passes through the iface if the socket accepts it.
+    ///
+    /// The frame must be at least [`ETHERNET_HEADER_LEN`] bytes long.
+    pub(crate) fn process(&self, frame: &[u8], packet_type: PacketType) {
+        let frame = EthernetFrame::new_unchecked(frame);
+        let protocol = frame.ethertype();
+
+        if !self.filter.accepts(protocol, packet_type) {
+            return;
+        }
+
+        let meta = PacketMeta {
+            packet_type,
+            protocol,
+            src_addr: frame.src_addr(),
+        };
+        let data = frame.into_inner();
+
+        // Like Linux, drop the frame if the receive buffer is full.
+        let mut recv_buffer = self.recv_buffer.lock();
+        let Ok(buffer) = recv_buffer.enqueue(data.len(), meta) else {
+            return;
+        };
+        buffer.copy_from_slice(data);
+        drop(recv_buffer);
+
+        self.notify_events(SocketEvents::CAN_RECV);
+    }
+
+    /// Dequeues the frames to send and passes them to `dispatch` one by one.
+    ///
+    /// If `dispatch` returns `false`, the frame is kept and the dispatching stops.
+    pub(crate) fn dispatch<D>(&self, mut dispatch: D)
+    where
+        D: FnMut(&[u8]) -> bool,
+    {
+        let mut send_buffer = self.send_buffer.lock();
+
+        while let Ok(((), frame)) = send_buffer.peek() {
+            if !dispatch(frame) {
+                break;
+            }
+            send_buffer.dequeue().unwrap();
+        }
+
+        self.need_dispatch
+            .store(!send_buffer.is_empty(), Ordering::Relaxed);
+        drop(send_buffer);
+
+        // For packet sockets, dequeuing a frame means that we can queue more frames.
+        self.notify_events(SocketEvents::CAN_SEND);
+    }
+
+    /// Returns whether the socket _may_ have frames to send.
+    ///
+    /// The check is intended to be lock-free and fast, but may have false positives.
+    pub(crate) fn need_dispatch(&self) -> bool {
+        self.need_dispatch.load(Ordering::Relaxed)
+    }
+
+    fn notify_events(&self, new_events: SocketEvents) {
+        if let Some(observer) = self.observer.get() {
+            observer.on_events(new_events);
+        }
+    }
+}
+
+impl<E: Ext> Drop for PacketSocket<E> {
+    fn drop(&mut self) {
+        // A packet socket can be removed immediately.
+        self.0.iface.common().remove_packet_socket(&self.0);
+    }
+}
+
+impl<E: Ext> PacketSocket<E> {
+    /// Attaches a new packet socket to the iface.
+    ///
+    /// The socket will receive the frames accepted by `filter`.
+    ///
+    /// Polling the iface is _not_ required after this method succeeds.
+    pub fn new_bind(
+        iface: Arc<dyn Iface<E>>,
+        filter: PacketFilter,
+        observer: E::UdpEventObserver,
+    ) -> Self {
+        let socket_bg = Arc::new(PacketSocketBg {
+            iface,
+            filter,
+            recv_buffer: SpinLock::new(PacketBuffer::new(
+                vec![PacketMetadata::EMPTY; PACKET_METADATA_LEN],
+                vec![0u8; PACKET_RECV_PAYLOAD_LEN],
+            )),
+            send_buffer: SpinLock::new(PacketBuffer::new(
+                vec![PacketMetadata::EMPTY; PACKET_METADATA_LEN],
+                vec![0u8; PACKET_SEND_PAYLOAD_LEN],
+            )),
+            need_dispatch: AtomicBool::new(false),
+            observer: Once::initialized(observer),
+        });
+
+        socket_bg
+            .iface
+            .common()
+            .register_packet_socket(socket_bg.clone());
+
+        Self(socket_bg)
+    }
+
+    pub fn iface(&self) -> &Arc<dyn Iface<E>> {
+        &self.0.iface
+    }
+
+    /// Returns the filter of the frames that the socket receives.
+    pub fn filter(&self) -> PacketFilter {
+        self.0.filter
+    }
+
+    /// Sends a link-layer frame.
+    ///
+    /// `f` should fill in the frame, which is `size` bytes long and includes the Ethernet header.
+    ///
+    /// Polling the iface is _always_ required after this method succeeds.
+    pub fn send<F, R>(&self, size: usize, f: F) -> Result<R, SendError>
+    where
+        F: FnOnce(&mut [u8]) -> R,
+    {
+        if size < ETHERNET_HEADER_LEN {
+            return Err(SendError::TooShort);
+        }
+        if size > self.0.iface.mtu() + ETHERNET_HEADER_LEN {
+            return Err(SendError::TooLarge);
+        }
+
+        let mut send_buffer = self.0.send_buffer.lock();
+
+        let buffer = send_buffer
+            .enqueue(size, ())
+            .map_err(|_| SendError::BufferFull)?;
+        let result = f(buffer);
+
+        self.0.need_dispatch.store(true, Ordering::Relaxed);
+
+        Ok(result)
+    }
+
+    /// Receives a link-layer frame, including the Ethernet header.
+    ///
+    /// `f` is called with the frame and its metadata.
+    ///
+    /// Polling the iface is _not_ required after this method succeeds.
+    pub fn recv<F, R>(&self, f: F) -> Result<R, RecvError>
+    where
+        F: FnOnce(&[u8], &PacketMeta) -> R,
+    {
+        let mut recv_buffer = self.0.recv_buffer.lock();
+
+        let (meta, frame) = recv_buffer.dequeue().map_err(|_| RecvError::Exhausted)?;
+        let result = f(frame, &meta);
+
+        Ok(result)
+    }
+
+    /// Returns whether there are frames to receive.
+    pub fn can_recv(&self) -> bool {
+        !self.0.recv_buffer.lock().is_empty()
+    }
+
+    /// Returns whether more frames can be queued to send.
+    pub fn can_send(&self) -> bool {
+        !self.0.send_buffer.lock().is_full()
+    }
+}
@@ -0,0 +1,272 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use alloc::{boxed::Box, sync::Arc};
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use aster_softirq::BottomHalfDisabled;
+use ostd::sync::SpinLock;
+use smoltcp::{
+    iface::Context,
+    phy::ChecksumCapabilities,
+    wire::{
+        IpAddress, IpProtocol, IpRepr, IpVersion, Ipv4Address, Ipv4Packet, Ipv4Repr,
+        IPV4_HEADER_LEN,
+    },
+};
+use spin::once::Once;
+
+use crate::{
+    errors::raw::SendError,
+    ext::Ext,
+    iface::Iface,
+    socket::{
+        event::{SocketEventObserver, SocketEvents},
+        unbound::new_raw_ip_socket,
+        RawRawIpSocket,
+    },
+};
+
+/// A raw IPv4 socket.
+///
+/// A raw IP socket receives a copy of every incoming packet of its IP protocol, including the IP
+/// header, and sends packets of its IP protocol with either a generated IP header or one that is
+/// provided by the user.
+///
+/// Unlike other sockets, a raw IP socket does not occupy a port. It is attached to an iface and
+/// optionally to one of the iface's addresses.
+pub struct RawIpSocket<E: Ext>(Arc<RawIpSocketBg<E>>);
+
+/// The background part of a [`RawIpSocket`].
+///
+/// See [`SocketBg`] for the meaning of "background".
+///
+/// [`SocketBg`]: super::common::SocketBg
+pub(crate) struct RawIpSocketBg<E: Ext> {
+    iface: Arc<dyn Iface<E>>,
+    local_addr: Ipv4Address,
+    socket: SpinLock<Box<RawRawIpSocket>, BottomHalfDisabled>,
+    need_dispatch: AtomicBool,
+    observer: Once<E::UdpEventObserver>,
+}
+
+/// The IPv4 header of a packet sent by a [`RawIpSocket`].
+#[derive(Debug, Clone, Copy)]
+pub enum RawIpHeader {
+    /// The IPv4 header is included in the data (i.e., `IP_HDRINCL` is set).
+    ///
+    /// The total length field is always filled in, and the source address is filled in if it is
+    /// unspecified.
+    Included,
+    /// The IPv4 header should be generated with the given fields.
+    Generated {
+        dst_addr: Ipv4Address,
+        hop_limit: u8,
+    },
+}
+
+impl<E: Ext> RawIpSocketBg<E> {
+    /// Processes an incoming packet if the socket accepts it.
+    pub(crate) fn process(&self, cx: &mut Context, ip_repr: &IpRepr, ip_payload: &[u8]) {
+        if !self.local_addr.is_unspecified()
+            && ip_repr.dst_addr() != IpAddress::Ipv4(self.local_addr)
+        {
+            return;
+        }
+
+        let mut socket = self.socket.lock();
+
+        if !socket.accepts(ip_repr) {
+            return;
+        }
+
+        socket.process(cx, ip_repr, ip_payload);
+
+        self.notify_events(SocketEvents::CAN_RECV);
+    }
+
+    /// Tries to generate an outgoing packet and dispatches the generated packet.
+    pub(crate) fn dispatch<D>(&self, cx: &mut Context, dispatch: D)
+    where
+        D: FnOnce(&mut Context, &IpRepr, &[u8]),
+    {
+        let mut socket = self.socket.lock();
+
+        socket
+            .dispatch(cx, |cx, (ip_repr, ip_payload)| {
+                dispatch(cx, &ip_repr, ip_payload);
+                Ok::<(), ()>(())
+            })
+            .unwrap();
+
+        // For raw IP sockets, dequeuing a packet means that we can queue more packets.
+        self.notify_events(SocketEvents::CAN_SEND);
+
+        self.need_dispatch
+            .store(socket.send_queue() > 0, Ordering::Relaxed);
+    }
+
+    /// Returns whether the socket _may_ generate an outgoing packet.
+    ///
+    /// The check is intended to be lock-free and fast, but may have false positives.
+    pub(crate) fn need_dispatch(&self) -> bool {
+        self.need_dispatch.load(Ordering::Relaxed)
+    }
+
+    fn notify_events(&self, new_events: SocketEvents) {
+        if let Some(observer) = self.observer.get() {
+            observer.on_events(new_events);
+        }
+    }
+}
+
+impl<E: Ext> Drop for RawIpSocket<E> {
+    fn drop(&mut self) {
+        // A raw IP socket can be removed immediately.
+        self.0.iface.common().remove_raw_ip_socket(&self.0);
+    }
+}
+
+impl<E: Ext> RawIpSocket<E> {
+    /// Attaches a new raw IPv4 socket of the IP protocol to the iface.
+    ///
+    /// If `local_addr` is unspecified, the socket will receive packets sent to any address of the
+    /// iface. Otherwise, it must be one of the addresses of the iface.
+    ///
+    /// Polling the iface is _not_ required after this method succeeds.
+    pub fn new_bind(
+        iface: Arc<dyn Iface<E>>,
+        local_addr: Ipv4Address,
+        ip_protocol: IpProtocol,
+        observer: E::UdpEventObserver,
+    ) -> Self {
+        let socket_bg = Arc::new(RawIpSocketBg {
+            iface,
+            local_addr,
+            socket: SpinLock::new(new_raw_ip_socket(IpVersion::Ipv4, ip_protocol)),
+            need_dispatch: AtomicBool::new(false),
+            observer: Once::initialized(observer),
+        });
+
+        socket_bg
+            .iface
+            .common()
+            .register_raw_ip_socket(socket_bg.clone());
+
+        Self(socket_bg)
+    }
+
+    /// Returns the local address.
+    ///
+    /// The address is unspecified if the socket accepts packets sent to any address of the iface.
+    pub fn local_addr(&self) -> Ipv4Address {
+        self.0.local_addr
+    }
+
+    pub fn iface(&self) -> &Arc<dyn Iface<E>> {
+        &self.0.iface
+    }
+
+    /// Sends an IPv4 packet.
+    ///
+    /// `f` should fill in the data, which includes the IPv4 header if and only if `header` is
+    /// [`RawIpHeader::Included`].
+    ///
+    /// Polling the iface is _always_ required after this method succeeds.
+    pub fn send<F, R>(&self, size: usize, header: RawIpHeader, f: F) -> Result<R, SendError>
+    where
+        F: FnOnce(&mut [u8]) -> R,
+    {
+        // Get the source address before locking the socket, since the lock order is `interface`
+        // first and socket second.
+        let src_addr = if self.0.local_addr.is_unspecified() {
+            self.0.iface.ipv4_addr()
+        } else {
+            Some(self.0.local_addr)
+        };
+
+        let mut socket = self.0.socket.lock();
+
+        let header_len = match header {
+            RawIpHeader::Included => 0,
+            RawIpHeader::Generated { .. } => IPV4_HEADER_LEN,
+        };
+        let total_len = header_len + size;
+        if total_len > socket.payload_send_capacity() || total_len > u16::MAX as usize {
+            return Err(SendError::TooLarge);
+        }
+
+        let header_repr = match header {
+            RawIpHeader::Included => None,
+            RawIpHeader::Generated {
+                dst_addr,
+                hop_limit,
+            } => Some(Ipv4Repr {
+                src_addr: src_addr.ok_or(SendError::Unaddressable)?,
+                dst_addr,
+                next_header: socket.ip_protocol(),
+                payload_len: size,
+                hop_limit,
+            }),
+        };
+
+        let buffer = socket.send(total_len)?;
+
+        // The checksum will be filled in when the packet is dispatched.
+        let checksum_caps = ChecksumCapabilities::ignored();
+        if let Some(header_repr) = header_repr {
+            header_repr.emit(
+                &mut Ipv4Packet::new_unchecked(&mut buffer[..header_len]),
+                &checksum_caps,
+            );
+        }
+
+        let result = f(&mut buffer[header_len..]);
+
+        if header_repr.is_none() && total_len >= IPV4_HEADER_LEN {
+            let mut packet = Ipv4Packet::new_unchecked(&mut *buffer);
+            packet.set_total_len(total_len as u16);
+            if packet.src_addr().is_unspecified() {
+                if let Some(src_addr) = src_addr {
+                    packet.set_src_addr(src_addr);
+                }
+            }
+        }
+
+        self.0
+            .need_dispatch
+            .store(socket.send_queue() > 0, Ordering::Relaxed);
+
+        Ok(result)
+    }
+
+    /// Receives an IPv4 packet, including the IPv4 header.
+    ///
+    /// `f` is called with the packet and its source address.
+    ///
+    /// Polling the iface is _not_ required after this method succeeds.
+    pub fn recv<F, R>(&self, f: F) -> Result<R, smoltcp::socket::raw::RecvError>
+    where
+        F: FnOnce(&[u8], Ipv4Address) -> R,
+    {
+        let mut socket = self.0.socket.lock();
+
+        let data = socket.recv()?;
+        // The packet has passed the checks in `process`, so its header must be valid.
+        let src_addr = Ipv4Packet::new_unchecked(data).src_addr();
+        let result = f(data, src_addr);
+
+        Ok(result)
+    }
+
+    /// Calls `f` with an immutable reference to the associated [`RawRawIpSocket`].
+    //
+    // NOTE: If a mutable reference is required, add a method above that correctly updates the next
+    // polling time.
+    pub fn raw_with<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&RawRawIpSocket) -> R,
+    {
+        let socket = self.0.socket.lock();
+        f(&socket)
+    }
+}
@@ -25,6 +25,7 @@ use crate::{
     iface::{BoundPort, PollKey, PollableIfaceMut},
     socket::{
         event::SocketEvents,
+        info::{SocketInfo, SocketInfoKind},
         option::{RawTcpOption, RawTcpSetOption},
         unbound::{new_tcp_socket, RawTcpSocket},
     },
@@ -294,9 +295,7 @@ impl<E: Ext> TcpConnection<E> {
         option: &RawTcpOption,
         observer: E::TcpEventObserver,
     ) -> Result<Self, (BoundPort<E>, ConnectError)> {
-        let Some(local_endpoint) = bound.endpoint() else {
-            return Err((bound, ConnectError::Unaddressable));
-        };
+        let local_endpoint = bound.endpoint();
 
         let iface = bound.iface().clone();
         // We have to lock `interface` before locking `sockets`
@@ -315,7 +314,8 @@ impl<E: Ext> TcpConnection<E> {
 
             option.apply(&mut socket);
 
-            if let Err(err) = socket.connect(interface.context_mut(), remote_endpoint, bound.port())
+            if let Err(err) =
+                socket.connect(interface.context_mut(), remote_endpoint, local_endpoint)
             {
                 return Err((bound, err.into()));
             }
@@ -547,6 +547,20 @@ impl<E: Ext> TcpConnectionBg<E> {
     pub(crate) const fn connection_key(&self) -> &ConnectionKey {
         &self.inner.connection_key
     }
+
+    /// Takes a snapshot of the connection state.
+    pub(crate) fn info(&self) -> SocketInfo {
+        let (local_endpoint, remote_endpoint) = self.inner.connection_key.endpoints();
+        let socket = self.inner.lock();
+
+        SocketInfo {
+            kind: SocketInfoKind::Tcp(socket.state()),
+            local_endpoint,
+            remote_endpoint: Some(remote_endpoint),
+            send_queue_len: socket.send_queue(),
+            recv_queue_len: socket.recv_queue(),
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -647,10 +661,14 @@ impl<E: Ext> TcpConnectionBg<E> {
         let mut events = SocketEvents::empty();
 
         let mut reply = None;
-        let (cx, pending) = iface.inner_mut();
+        let (cx, ip_addrs, pending, igmp) = iface.inner_mut();
         socket
             .dispatch(cx, |cx, (ip_repr, tcp_repr)| {
-                reply = dispatch(PollableIfaceMut::new(cx, pending), &ip_repr, &tcp_repr);
+                reply = dispatch(
+                    PollableIfaceMut::new(cx, ip_addrs, pending, igmp),
+                    &ip_repr,
+                    &tcp_repr,
+                );
                 Ok::<(), ()>(())
             })
             .unwrap();
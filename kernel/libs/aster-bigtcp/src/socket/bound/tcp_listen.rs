@@ -19,6 +19,7 @@ use crate::{
     ext::Ext,
     iface::{BindPortConfig, BoundPort, PollableIfaceMut},
     socket::{
+        info::{SocketInfo, SocketInfoKind, TcpState},
         option::{RawTcpOption, RawTcpSetOption},
         unbound::{new_tcp_socket, RawTcpSocket},
     },
@@ -73,9 +74,7 @@ impl<E: Ext> TcpListener<E> {
         option: &RawTcpOption,
         observer: E::TcpEventObserver,
     ) -> Result<Self, (BoundPort<E>, ListenError)> {
-        let Some(local_endpoint) = bound.endpoint() else {
-            return Err((bound, ListenError::Unaddressable));
-        };
+        let local_endpoint = bound.endpoint();
 
         let iface = bound.iface().clone();
         let mut sockets = iface.common().sockets();
@@ -188,6 +187,22 @@ impl<E: Ext> TcpListenerBg<E> {
     pub(crate) const fn listener_key(&self) -> &ListenerKey {
         &self.inner.listener_key
     }
+
+    /// Takes a snapshot of the listener state.
+    ///
+    /// The receive queue length is the number of connections waiting to be accepted, and the
+    /// send queue length is the maximum length of the backlog.
+    pub(crate) fn info(&self) -> SocketInfo {
+        let backlog = self.inner.backlog.lock();
+
+        SocketInfo {
+            kind: SocketInfoKind::Tcp(TcpState::Listen),
+            local_endpoint: self.inner.listener_key.endpoint(),
+            remote_endpoint: None,
+            send_queue_len: backlog.max_conn,
+            recv_queue_len: backlog.connected.len(),
+        }
+    }
 }
 
 impl<E: Ext> TcpListenerBg<E> {
@@ -236,7 +251,10 @@ impl<E: Ext> TcpListenerBg<E> {
         let conn = TcpConnection::new_cyclic(
             self.bound
                 .iface()
-                .bind(BindPortConfig::Backlog(self.bound.port()))
+                .bind(
+                    self.bound.addr(),
+                    BindPortConfig::Backlog(self.bound.port()),
+                )
                 .unwrap(),
             |weak| {
                 TcpConnectionInner::new(
@@ -8,7 +8,7 @@ use ostd::sync::SpinLock;
 use smoltcp::{
     iface::Context,
     socket::udp::UdpMetadata,
-    wire::{IpRepr, UdpRepr},
+    wire::{IpAddress, IpEndpoint, IpListenEndpoint, IpRepr, UdpRepr},
 };
 
 use super::common::{Inner, Socket, SocketBg};
@@ -16,7 +16,12 @@ use crate::{
     errors::udp::SendError,
     ext::Ext,
     iface::BoundPort,
-    socket::{event::SocketEvents, unbound::new_udp_socket, RawUdpSocket},
+    socket::{
+        event::SocketEvents,
+        info::{SocketInfo, SocketInfoKind},
+        unbound::new_udp_socket,
+        RawUdpSocket,
+    },
 };
 
 pub type UdpSocket<E> = Socket<UdpSocketInner, E>;
@@ -40,6 +45,53 @@ impl<E: Ext> Inner<E> for UdpSocketInner {
 
 pub(crate) type UdpSocketBg<E> = SocketBg<UdpSocketInner, E>;
 
+/// The options that determine how an outgoing UDP packet is sent.
+#[derive(Debug, Clone, Copy)]
+pub struct UdpSendOptions {
+    /// The hop limit (i.e., the TTL for IPv4) of the packet.
+    pub hop_limit: u8,
+    /// Whether the packet is also delivered locally if it is sent to an IPv4 multicast group
+    /// that the iface has joined.
+    pub multicast_loop: bool,
+}
+
+/// The metadata of a received UDP packet.
+#[derive(Debug, Clone, Copy)]
+pub struct UdpRecvMeta {
+    /// The endpoint that sent the packet.
+    pub remote_endpoint: IpEndpoint,
+    /// The destination address of the packet.
+    ///
+    /// This differs from the bound address if the socket is bound to an unspecified address, or
+    /// if the packet is sent to a broadcast address or a multicast group.
+    pub local_addr: IpAddress,
+    /// The hop limit (i.e., the TTL for IPv4) of the packet.
+    pub hop_limit: u8,
+}
+
+// The per-packet information is carried in the ID of `smoltcp`'s packet metadata. The lowest byte
+// holds the hop limit, and the bit below marks whether the multicast loop is enabled.
+const META_HOP_LIMIT_MASK: u32 = 0xff;
+const META_MULTICAST_LOOP_BIT: u32 = 1 << 8;
+
+impl UdpSendOptions {
+    fn to_packet_meta(self) -> smoltcp::phy::PacketMeta {
+        let mut meta = smoltcp::phy::PacketMeta::default();
+        meta.id = self.hop_limit as u32;
+        if self.multicast_loop {
+            meta.id |= META_MULTICAST_LOOP_BIT;
+        }
+        meta
+    }
+
+    fn from_packet_meta(meta: &smoltcp::phy::PacketMeta) -> Self {
+        Self {
+            hop_limit: (meta.id & META_HOP_LIMIT_MASK) as u8,
+            multicast_loop: meta.id & META_MULTICAST_LOOP_BIT != 0,
+        }
+    }
+}
+
 impl<E: Ext> UdpSocketBg<E> {
     /// Tries to process an incoming packet and returns whether the packet is processed.
     pub(crate) fn process(
@@ -49,19 +101,24 @@ impl<E: Ext> UdpSocketBg<E> {
         udp_repr: &UdpRepr,
         udp_payload: &[u8],
     ) -> bool {
+        // Like Linux, a socket bound to a specific address only receives packets sent to that
+        // address, even if they are broadcast or multicast packets.
+        let bound_addr = self.bound.addr();
+        if !bound_addr.is_unspecified() && bound_addr != ip_repr.dst_addr() {
+            return false;
+        }
+
         let mut socket = self.inner.socket.lock();
 
         if !socket.accepts(cx, ip_repr, udp_repr) {
             return false;
         }
 
-        socket.process(
-            cx,
-            smoltcp::phy::PacketMeta::default(),
-            ip_repr,
-            udp_repr,
-            udp_payload,
-        );
+        // Only the hop limit needs to be kept. The destination address is kept by `smoltcp`.
+        let mut meta = smoltcp::phy::PacketMeta::default();
+        meta.id = ip_repr.hop_limit() as u32;
+
+        socket.process(cx, meta, ip_repr, udp_repr, udp_payload);
 
         self.notify_events(SocketEvents::CAN_RECV);
 
@@ -69,15 +126,23 @@ impl<E: Ext> UdpSocketBg<E> {
     }
 
     /// Tries to generate an outgoing packet and dispatches the generated packet.
+    ///
+    /// The last argument of `dispatch` tells whether the multicast loop is enabled for the packet.
     pub(crate) fn dispatch<D>(&self, cx: &mut Context, dispatch: D)
     where
-        D: FnOnce(&mut Context, &IpRepr, &UdpRepr, &[u8]),
+        D: FnOnce(&mut Context, &IpRepr, &UdpRepr, &[u8], bool),
     {
         let mut socket = self.inner.socket.lock();
 
         socket
-            .dispatch(cx, |cx, _meta, (ip_repr, udp_repr, udp_payload)| {
-                dispatch(cx, &ip_repr, &udp_repr, udp_payload);
+            .dispatch(cx, |cx, meta, (mut ip_repr, udp_repr, udp_payload)| {
+                let options = UdpSendOptions::from_packet_meta(&meta);
+                match &mut ip_repr {
+                    IpRepr::Ipv4(ipv4_repr) => ipv4_repr.hop_limit = options.hop_limit,
+                    IpRepr::Ipv6(ipv6_repr) => ipv6_repr.hop_limit = options.hop_limit,
+                }
+
+                dispatch(cx, &ip_repr, &udp_repr, udp_payload, options.multicast_loop);
                 Ok::<(), ()>(())
             })
             .unwrap();
@@ -96,6 +161,19 @@ impl<E: Ext> UdpSocketBg<E> {
     pub(crate) fn need_dispatch(&self) -> bool {
         self.inner.need_dispatch.load(Ordering::Relaxed)
     }
+
+    /// Takes a snapshot of the socket state.
+    pub(crate) fn info(&self) -> SocketInfo {
+        let socket = self.inner.socket.lock();
+
+        SocketInfo {
+            kind: SocketInfoKind::Udp,
+            local_endpoint: self.bound.endpoint(),
+            remote_endpoint: None,
+            send_queue_len: socket.send_queue(),
+            recv_queue_len: socket.recv_queue(),
+        }
+    }
 }
 
 impl<E: Ext> UdpSocket<E> {
@@ -106,8 +184,15 @@ impl<E: Ext> UdpSocket<E> {
         bound: BoundPort<E>,
         observer: E::UdpEventObserver,
     ) -> Result<Self, (BoundPort<E>, smoltcp::socket::udp::BindError)> {
-        let Some(local_endpoint) = bound.endpoint() else {
-            return Err((bound, smoltcp::socket::udp::BindError::Unaddressable));
+        // If the bound address is unspecified, the socket accepts packets sent to any address of
+        // the iface.
+        let local_endpoint = if bound.addr().is_unspecified() {
+            IpListenEndpoint {
+                addr: None,
+                port: bound.port(),
+            }
+        } else {
+            IpListenEndpoint::from(bound.endpoint())
         };
 
         let socket = {
@@ -141,7 +226,8 @@ impl<E: Ext> UdpSocket<E> {
     pub fn send<F, R>(
         &self,
         size: usize,
-        meta: impl Into<UdpMetadata>,
+        remote_endpoint: IpEndpoint,
+        options: UdpSendOptions,
         f: F,
     ) -> Result<R, SendError>
     where
@@ -153,6 +239,9 @@ impl<E: Ext> UdpSocket<E> {
             return Err(SendError::TooLarge);
         }
 
+        let mut meta = UdpMetadata::from(remote_endpoint);
+        meta.meta = options.to_packet_meta();
+
         let buffer = match socket.send(size, meta) {
             Ok(data) => data,
             Err(err) => return Err(err.into()),
@@ -172,12 +261,19 @@ impl<E: Ext> UdpSocket<E> {
     /// Polling the iface is _not_ required after this method succeeds.
     pub fn recv<F, R>(&self, f: F) -> Result<R, smoltcp::socket::udp::RecvError>
     where
-        F: FnOnce(&[u8], UdpMetadata) -> R,
+        F: FnOnce(&[u8], UdpRecvMeta) -> R,
     {
         let mut socket = self.0.inner.socket.lock();
 
         let (data, meta) = socket.recv()?;
-        let result = f(data, meta);
+        let recv_meta = UdpRecvMeta {
+            remote_endpoint: meta.endpoint,
+            local_addr: meta
+                .local_address
+                .unwrap_or_else(|| self.bound_port().addr()),
+            hop_limit: (meta.meta.id & META_HOP_LIMIT_MASK) as u8,
+        };
+        let result = f(data, recv_meta);
 
         Ok(result)
     }
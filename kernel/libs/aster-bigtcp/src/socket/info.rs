@@ -0,0 +1,31 @@
+// SPDX-License-Identifier: MPL-2.0
+
+pub use smoltcp::socket::tcp::State as TcpState;
+use smoltcp::wire::IpEndpoint;
+
+/// A snapshot of the state of a socket bound to an iface.
+///
+/// The snapshot is intended for diagnostic purposes (e.g., generating `/proc/net/tcp`), so it
+/// may become outdated as soon as it is taken.
+#[derive(Debug, Clone, Copy)]
+pub struct SocketInfo {
+    /// The protocol-specific state of the socket.
+    pub kind: SocketInfoKind,
+    /// The local endpoint.
+    ///
+    /// The address is unspecified if the socket accepts packets sent to any address.
+    pub local_endpoint: IpEndpoint,
+    /// The remote endpoint, if the socket is connected.
+    pub remote_endpoint: Option<IpEndpoint>,
+    /// The number of bytes (or, for listeners, connections) in the send queue.
+    pub send_queue_len: usize,
+    /// The number of bytes (or, for listeners, connections) in the receive queue.
+    pub recv_queue_len: usize,
+}
+
+/// The protocol-specific state in a [`SocketInfo`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SocketInfoKind {
+    Tcp(TcpState),
+    Udp,
+}
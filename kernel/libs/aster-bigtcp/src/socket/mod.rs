@@ -2,15 +2,24 @@
 
 mod bound;
 mod event;
+mod info;
 mod option;
 mod unbound;
 
 pub use bound::{
-    ConnectState, NeedIfacePoll, RawTcpSocketExt, TcpConnection, TcpListener, UdpSocket,
+    ConnectState, DhcpConfig, DhcpEvent, DhcpSocket, IcmpSocket, NeedIfacePoll, PacketFilter,
+    PacketMeta, PacketSocket, PacketType, RawIpHeader, RawIpSocket, RawTcpSocketExt, TcpConnection,
+    TcpListener, UdpRecvMeta, UdpSendOptions, UdpSocket,
+};
+pub(crate) use bound::{
+    DhcpSocketBg, IcmpSocketBg, PacketSocketBg, RawIpSocketBg, TcpConnectionBg, TcpListenerBg,
+    TcpProcessResult, UdpSocketBg,
 };
-pub(crate) use bound::{TcpConnectionBg, TcpListenerBg, TcpProcessResult, UdpSocketBg};
 pub use event::{SocketEventObserver, SocketEvents};
+pub use info::{SocketInfo, SocketInfoKind, TcpState};
 pub use option::{RawTcpOption, RawTcpSetOption};
 pub use unbound::{
-    RawUdpSocket, TCP_RECV_BUF_LEN, TCP_SEND_BUF_LEN, UDP_RECV_PAYLOAD_LEN, UDP_SEND_PAYLOAD_LEN,
+    RawIcmpSocket, RawRawIpSocket, RawUdpSocket, ICMP_RECV_PAYLOAD_LEN, ICMP_SEND_PAYLOAD_LEN,
+    PACKET_RECV_PAYLOAD_LEN, PACKET_SEND_PAYLOAD_LEN, RAW_RECV_PAYLOAD_LEN, RAW_SEND_PAYLOAD_LEN,
+    TCP_RECV_BUF_LEN, TCP_SEND_BUF_LEN, UDP_RECV_PAYLOAD_LEN, UDP_SEND_PAYLOAD_LEN,
 };
@@ -2,8 +2,13 @@
 
 use alloc::{boxed::Box, vec};
 
+use smoltcp::wire::{IpProtocol, IpVersion};
+
 pub(super) type RawTcpSocket = smoltcp::socket::tcp::Socket<'static>;
 pub type RawUdpSocket = smoltcp::socket::udp::Socket<'static>;
+pub type RawIcmpSocket = smoltcp::socket::icmp::Socket<'static>;
+pub type RawRawIpSocket = smoltcp::socket::raw::Socket<'static>;
+pub(super) type RawDhcpSocket = smoltcp::socket::dhcpv4::Socket<'static>;
 
 pub(super) fn new_tcp_socket() -> Box<RawTcpSocket> {
     let raw_tcp_socket = {
@@ -30,6 +35,45 @@ pub(super) fn new_udp_socket() -> Box<RawUdpSocket> {
     Box::new(raw_udp_socket)
 }
 
+pub(super) fn new_icmp_socket() -> Box<RawIcmpSocket> {
+    let raw_icmp_socket = {
+        let metadata = smoltcp::socket::icmp::PacketMetadata::EMPTY;
+        let rx_buffer = smoltcp::socket::icmp::PacketBuffer::new(
+            vec![metadata; ICMP_METADATA_LEN],
+            vec![0u8; ICMP_RECV_PAYLOAD_LEN],
+        );
+        let tx_buffer = smoltcp::socket::icmp::PacketBuffer::new(
+            vec![metadata; ICMP_METADATA_LEN],
+            vec![0u8; ICMP_SEND_PAYLOAD_LEN],
+        );
+        RawIcmpSocket::new(rx_buffer, tx_buffer)
+    };
+    Box::new(raw_icmp_socket)
+}
+
+pub(super) fn new_raw_ip_socket(
+    ip_version: IpVersion,
+    ip_protocol: IpProtocol,
+) -> Box<RawRawIpSocket> {
+    let raw_raw_ip_socket = {
+        let metadata = smoltcp::socket::raw::PacketMetadata::EMPTY;
+        let rx_buffer = smoltcp::socket::raw::PacketBuffer::new(
+            vec![metadata; RAW_METADATA_LEN],
+            vec![0u8; RAW_RECV_PAYLOAD_LEN],
+        );
+        let tx_buffer = smoltcp::socket::raw::PacketBuffer::new(
+            vec![metadata; RAW_METADATA_LEN],
+            vec![0u8; RAW_SEND_PAYLOAD_LEN],
+        );
+        RawRawIpSocket::new(ip_version, ip_protocol, rx_buffer, tx_buffer)
+    };
+    Box::new(raw_raw_ip_socket)
+}
+
+pub(super) fn new_dhcp_socket() -> Box<RawDhcpSocket> {
+    Box::new(RawDhcpSocket::new())
+}
+
 // TCP socket buffer sizes:
 //
 // According to
@@ -50,3 +94,22 @@ pub const TCP_SEND_BUF_LEN: usize = 65536 * 2;
 pub const UDP_SEND_PAYLOAD_LEN: usize = 65536;
 pub const UDP_RECV_PAYLOAD_LEN: usize = 65536;
 const UDP_METADATA_LEN: usize = 256;
+
+// ICMP socket buffer sizes:
+pub const ICMP_SEND_PAYLOAD_LEN: usize = 65536;
+pub const ICMP_RECV_PAYLOAD_LEN: usize = 65536;
+const ICMP_METADATA_LEN: usize = 256;
+
+// Raw IP socket buffer sizes:
+//
+// Note that the payload of a raw IP socket includes the IP header.
+pub const RAW_SEND_PAYLOAD_LEN: usize = 65536;
+pub const RAW_RECV_PAYLOAD_LEN: usize = 65536;
+const RAW_METADATA_LEN: usize = 256;
+
+// Packet socket buffer sizes:
+//
+// Note that the payload of a packet socket includes the link-layer header.
+pub const PACKET_SEND_PAYLOAD_LEN: usize = 65536;
+pub const PACKET_RECV_PAYLOAD_LEN: usize = 65536 * 2;
+pub(super) const PACKET_METADATA_LEN: usize = 256;
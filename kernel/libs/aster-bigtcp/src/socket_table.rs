@@ -1,7 +1,7 @@
 // SPDX-License-Identifier: MPL-2.0
 
-//! This module defines the socket table, which manages all TCP and UDP sockets,
-//! for efficiently inserting, looking up, and removing sockets.
+//! This module defines the socket table, which manages all TCP, UDP, ICMP, raw IP, and DHCP
+//! sockets, for efficiently inserting, looking up, and removing sockets.
 
 use alloc::{boxed::Box, sync::Arc, vec::Vec};
 use core::net::Ipv4Addr;
@@ -12,7 +12,9 @@ use smoltcp::wire::{IpAddress, IpEndpoint, IpListenEndpoint};
 
 use crate::{
     ext::Ext,
-    socket::{TcpConnectionBg, TcpListenerBg, UdpSocketBg},
+    socket::{
+        DhcpSocketBg, IcmpSocketBg, RawIpSocketBg, TcpConnectionBg, TcpListenerBg, UdpSocketBg,
+    },
     wire::PortNum,
 };
 
@@ -41,6 +43,10 @@ impl ListenerKey {
     pub(crate) const fn hash(&self) -> SocketHash {
         self.hash
     }
+
+    pub(crate) const fn endpoint(&self) -> IpEndpoint {
+        IpEndpoint::new(self.addr, self.port)
+    }
 }
 
 impl From<IpListenEndpoint> for ListenerKey {
@@ -83,6 +89,14 @@ impl ConnectionKey {
     pub(crate) const fn hash(&self) -> SocketHash {
         self.hash
     }
+
+    /// Returns the local endpoint and the remote endpoint.
+    pub(crate) const fn endpoints(&self) -> (IpEndpoint, IpEndpoint) {
+        (
+            IpEndpoint::new(self.local_addr, self.local_port),
+            IpEndpoint::new(self.remote_addr, self.remote_port),
+        )
+    }
 }
 
 impl From<(IpEndpoint, IpEndpoint)> for ConnectionKey {
@@ -103,26 +117,32 @@ const fn hash_local_remote(
     remote_addr: IpAddress,
     remote_port: PortNum,
 ) -> SocketHash {
-    // FIXME: Deal with IPv6 addresses once IPv6 is supported.
-    let IpAddress::Ipv4(local_ipv4) = local_addr;
-    let IpAddress::Ipv4(remote_ipv4) = remote_addr;
-
     jhash_3vals(
-        local_ipv4.to_bits(),
-        remote_ipv4.to_bits(),
+        fold_addr(local_addr),
+        fold_addr(remote_addr),
         (local_port as u32).wrapping_shl(16) | remote_port as u32,
         HASH_SECRET.wrapping_add(NET_HASHMIX),
     )
 }
 
 const fn hash_addr_port(addr: IpAddress, port: PortNum) -> SocketHash {
-    // FIXME: Deal with IPv6 addresses once IPv6 is supported.
-    let IpAddress::Ipv4(ipv4_addr) = addr;
+    jhash_1vals(fold_addr(addr), NET_HASHMIX) ^ (port as u32)
+}
 
-    jhash_1vals(ipv4_addr.to_bits(), NET_HASHMIX) ^ (port as u32)
+/// Folds an IP address into a 32-bit value for hashing.
+///
+/// Like Linux's `ipv6_addr_hash`, an IPv6 address is folded by XOR-ing its 32-bit words.
+const fn fold_addr(addr: IpAddress) -> u32 {
+    match addr {
+        IpAddress::Ipv4(ipv4_addr) => ipv4_addr.to_bits(),
+        IpAddress::Ipv6(ipv6_addr) => {
+            let bits = ipv6_addr.to_bits();
+            (bits as u32) ^ ((bits >> 32) as u32) ^ ((bits >> 64) as u32) ^ ((bits >> 96) as u32)
+        }
+    }
 }
 
-/// The socket table manages TCP and UDP sockets.
+/// The socket table manages TCP, UDP, ICMP, raw IP, and DHCP sockets.
 ///
 /// Unlike the Linux inet hashtable, which is shared across a single network namespace,
 /// this table is currently limited to a single interface.
@@ -142,6 +162,15 @@ pub(crate) struct SocketTable<E: Ext> {
     // Note that multiple UDP sockets can be bound to the same address,
     // so we cannot use (addr, port) as a _unique_ key for UDP sockets.
     udp_sockets: Vec<Arc<UdpSocketBg<E>>>,
+    // ICMP sockets are identified by the identifiers of echo messages, which are allocated as
+    // ports. Like UDP sockets, they are not hashed for simplicity.
+    icmp_sockets: Vec<Arc<IcmpSocketBg<E>>>,
+    // Every raw IP socket of the IP protocol receives a copy of each incoming packet, so there is
+    // no key to look them up.
+    raw_ip_sockets: Vec<Arc<RawIpSocketBg<E>>>,
+    // There is at most one DHCP client on each iface. It intercepts the packets from the DHCP
+    // server port to the DHCP client port, so it is not treated as a UDP socket.
+    dhcp_socket: Option<Arc<DhcpSocketBg<E>>>,
 }
 
 // On Linux, the number of buckets is determined at runtime based on the available memory.
@@ -166,11 +195,16 @@ impl<E: Ext> SocketTable<E> {
             .collect();
 
         let udp_sockets = Vec::new();
+        let icmp_sockets = Vec::new();
+        let raw_ip_sockets = Vec::new();
 
         Self {
             listener_buckets,
             connection_buckets,
             udp_sockets,
+            icmp_sockets,
+            raw_ip_sockets,
+            dhcp_socket: None,
         }
     }
 
@@ -234,6 +268,38 @@ impl<E: Ext> SocketTable<E> {
         self.udp_sockets.push(udp_socket);
     }
 
+    pub(crate) fn insert_icmp_socket(&mut self, icmp_socket: Arc<IcmpSocketBg<E>>) {
+        debug_assert!(!self
+            .icmp_sockets
+            .iter()
+            .any(|socket| Arc::ptr_eq(socket, &icmp_socket)));
+        self.icmp_sockets.push(icmp_socket);
+    }
+
+    pub(crate) fn insert_raw_ip_socket(&mut self, raw_ip_socket: Arc<RawIpSocketBg<E>>) {
+        debug_assert!(!self
+            .raw_ip_sockets
+            .iter()
+            .any(|socket| Arc::ptr_eq(socket, &raw_ip_socket)));
+        self.raw_ip_sockets.push(raw_ip_socket);
+    }
+
+    /// Inserts a DHCP socket into the table.
+    ///
+    /// If there is already a DHCP socket in the table, this method will return an error and the
+    /// socket will not be inserted.
+    pub(crate) fn insert_dhcp_socket(
+        &mut self,
+        dhcp_socket: Arc<DhcpSocketBg<E>>,
+    ) -> Result<(), Arc<DhcpSocketBg<E>>> {
+        if self.dhcp_socket.is_some() {
+            return Err(dhcp_socket);
+        }
+
+        self.dhcp_socket = Some(dhcp_socket);
+        Ok(())
+    }
+
     pub(crate) fn lookup_listener(&self, key: &ListenerKey) -> Option<&Arc<TcpListenerBg<E>>> {
         let bucket = {
             let hash = key.hash();
@@ -309,9 +375,70 @@ impl<E: Ext> SocketTable<E> {
         Some(self.udp_sockets.swap_remove(index))
     }
 
+    pub(crate) fn remove_icmp_socket(
+        &mut self,
+        socket: &Arc<IcmpSocketBg<E>>,
+    ) -> Option<Arc<IcmpSocketBg<E>>> {
+        let index = self
+            .icmp_sockets
+            .iter()
+            .position(|icmp_socket| Arc::ptr_eq(icmp_socket, socket))?;
+        Some(self.icmp_sockets.swap_remove(index))
+    }
+
+    pub(crate) fn remove_raw_ip_socket(
+        &mut self,
+        socket: &Arc<RawIpSocketBg<E>>,
+    ) -> Option<Arc<RawIpSocketBg<E>>> {
+        let index = self
+            .raw_ip_sockets
+            .iter()
+            .position(|raw_ip_socket| Arc::ptr_eq(raw_ip_socket, socket))?;
+        Some(self.raw_ip_sockets.swap_remove(index))
+    }
+
+    pub(crate) fn remove_dhcp_socket(
+        &mut self,
+        socket: &Arc<DhcpSocketBg<E>>,
+    ) -> Option<Arc<DhcpSocketBg<E>>> {
+        if !self
+            .dhcp_socket
+            .as_ref()
+            .is_some_and(|dhcp_socket| Arc::ptr_eq(dhcp_socket, socket))
+        {
+            return None;
+        }
+
+        self.dhcp_socket.take()
+    }
+
+    pub(crate) fn listener_iter(&self) -> impl Iterator<Item = &Arc<TcpListenerBg<E>>> {
+        self.listener_buckets
+            .iter()
+            .flat_map(|bucket| bucket.listeners.iter())
+    }
+
+    pub(crate) fn connection_iter(&self) -> impl Iterator<Item = &Arc<TcpConnectionBg<E>>> {
+        self.connection_buckets
+            .iter()
+            .flat_map(|bucket| bucket.connections.iter())
+    }
+
     pub(crate) fn udp_socket_iter(&self) -> impl Iterator<Item = &Arc<UdpSocketBg<E>>> {
         self.udp_sockets.iter()
     }
+
+    pub(crate) fn icmp_socket_iter(&self) -> impl Iterator<Item = &Arc<IcmpSocketBg<E>>> {
+        self.icmp_sockets.iter()
+    }
+
+    pub(crate) fn raw_ip_socket_iter(&self) -> impl Iterator<Item = &Arc<RawIpSocketBg<E>>> {
+        self.raw_ip_sockets.iter()
+    }
+
+    pub(crate) fn dhcp_socket(&self) -> Option<&Arc<DhcpSocketBg<E>>> {
+        self.dhcp_socket.as_ref()
+    }
 }
 
 impl<E: Ext> Default for SocketTable<E> {
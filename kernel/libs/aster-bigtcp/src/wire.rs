@@ -1,5 +1,8 @@
 // SPDX-License-Identifier: MPL-2.0
 
-pub use smoltcp::wire::{EthernetAddress, IpAddress, IpCidr, IpEndpoint, Ipv4Address, Ipv4Cidr};
+pub use smoltcp::wire::{
+    EthernetAddress, EthernetProtocol, IpAddress, IpCidr, IpEndpoint, IpProtocol, Ipv4Address,
+    Ipv4Cidr, Ipv6Address, Ipv6Cidr, ETHERNET_HEADER_LEN, IPV4_HEADER_LEN,
+};
 
 pub type PortNum = u16;
@@ -125,6 +125,8 @@ macro_rules! copy_gp_regs {
     };
 }
 
+pub(super) use copy_gp_regs;
+
 impl SigContext {
     pub fn copy_user_regs_to(&self, dst: &mut UserContext) {
         let gp_regs = dst.general_regs_mut();
@@ -76,6 +76,8 @@ macro_rules! copy_gp_regs {
     };
 }
 
+pub(super) use copy_gp_regs;
+
 /// Represents the context of a signal handler.
 ///
 /// This contains the context saved before a signal handler is invoked; it will be restored by
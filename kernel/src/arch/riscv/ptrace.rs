@@ -0,0 +1,111 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The architecture-specific parts of `ptrace`.
+
+use ostd::{cpu::context::UserContext, user::UserContextApi, Pod};
+
+use super::cpu::copy_gp_regs;
+use crate::prelude::*;
+
+/// The general-purpose registers exposed by the `NT_PRSTATUS` register set.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.15.7/source/arch/riscv/include/uapi/asm/ptrace.h#L20>
+#[derive(Clone, Copy, Debug, Default, Pod)]
+#[repr(C)]
+pub struct UserRegs {
+    pc: usize,
+    ra: usize,
+    sp: usize,
+    gp: usize,
+    tp: usize,
+    t0: usize,
+    t1: usize,
+    t2: usize,
+    s0: usize,
+    s1: usize,
+    a0: usize,
+    a1: usize,
+    a2: usize,
+    a3: usize,
+    a4: usize,
+    a5: usize,
+    a6: usize,
+    a7: usize,
+    s2: usize,
+    s3: usize,
+    s4: usize,
+    s5: usize,
+    s6: usize,
+    s7: usize,
+    s8: usize,
+    s9: usize,
+    s10: usize,
+    s11: usize,
+    t3: usize,
+    t4: usize,
+    t5: usize,
+    t6: usize,
+}
+
+impl UserRegs {
+    /// Saves the registers in `user_ctx`.
+    ///
+    /// The system call number is stored in `a7`, so `_syscall_num` is not used.
+    pub fn new(user_ctx: &UserContext, _syscall_num: Option<usize>) -> Self {
+        let gp_regs = user_ctx.general_regs();
+
+        let mut regs = Self {
+            pc: user_ctx.instruction_pointer(),
+            ..Default::default()
+        };
+        copy_gp_regs!(gp_regs, regs);
+
+        regs
+    }
+
+    /// Restores the registers to `user_ctx`.
+    ///
+    /// `syscall_num` is updated according to `a7`, where `-1` means that there is no system
+    /// call (i.e., the system call should be skipped).
+    pub fn copy_to(&self, user_ctx: &mut UserContext, syscall_num: &mut Option<usize>) {
+        let gp_regs = user_ctx.general_regs_mut();
+        copy_gp_regs!(self, gp_regs);
+        user_ctx.set_instruction_pointer(self.pc);
+
+        *syscall_num = (self.a7 != usize::MAX).then_some(self.a7);
+    }
+}
+
+/// Enables or disables single-stepping for the user context.
+///
+/// Single-stepping is not supported because there is no hardware support on RISC-V.
+pub fn set_single_step(_user_ctx: &mut UserContext, is_enabled: bool) -> Result<()> {
+    if is_enabled {
+        return_errno_with_message!(Errno::EIO, "single-stepping is not supported");
+    }
+
+    Ok(())
+}
+
+/// Reads a word in the user area of the tracee (i.e., `PTRACE_PEEKUSER`).
+///
+/// The user area is not available on RISC-V.
+pub fn peek_user(
+    _user_ctx: &UserContext,
+    _syscall_num: Option<usize>,
+    _offset: usize,
+) -> Result<usize> {
+    return_errno_with_message!(Errno::EIO, "the user area is not supported");
+}
+
+/// Writes a word in the user area of the tracee (i.e., `PTRACE_POKEUSER`).
+///
+/// The user area is not available on RISC-V.
+pub fn poke_user(
+    _user_ctx: &mut UserContext,
+    _syscall_num: &mut Option<usize>,
+    _offset: usize,
+    _value: usize,
+) -> Result<()> {
+    return_errno_with_message!(Errno::EIO, "the user area is not supported");
+}
@@ -0,0 +1,203 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The architecture-specific parts of `ptrace`.
+
+use ostd::{
+    cpu::context::{FpuContext, UserContext},
+    Pod,
+};
+
+use crate::prelude::*;
+
+/// The general-purpose registers exposed by `PTRACE_GETREGS` and the `NT_PRSTATUS` register set.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.15.7/source/arch/x86/include/asm/user_64.h#L69>
+#[derive(Clone, Copy, Debug, Default, Pod)]
+#[repr(C)]
+pub struct UserRegs {
+    r15: u64,
+    r14: u64,
+    r13: u64,
+    r12: u64,
+    rbp: u64,
+    rbx: u64,
+    r11: u64,
+    r10: u64,
+    r9: u64,
+    r8: u64,
+    rax: u64,
+    rcx: u64,
+    rdx: u64,
+    rsi: u64,
+    rdi: u64,
+    orig_rax: u64,
+    rip: u64,
+    cs: u64,
+    eflags: u64,
+    rsp: u64,
+    ss: u64,
+    fs_base: u64,
+    gs_base: u64,
+    ds: u64,
+    es: u64,
+    fs: u64,
+    gs: u64,
+}
+
+macro_rules! copy_gp_regs {
+    ($src: ident, $dst: ident) => {
+        $dst.rax = $src.rax as _;
+        $dst.rbx = $src.rbx as _;
+        $dst.rcx = $src.rcx as _;
+        $dst.rdx = $src.rdx as _;
+        $dst.rsi = $src.rsi as _;
+        $dst.rdi = $src.rdi as _;
+        $dst.rbp = $src.rbp as _;
+        $dst.rsp = $src.rsp as _;
+        $dst.r8 = $src.r8 as _;
+        $dst.r9 = $src.r9 as _;
+        $dst.r10 = $src.r10 as _;
+        $dst.r11 = $src.r11 as _;
+        $dst.r12 = $src.r12 as _;
+        $dst.r13 = $src.r13 as _;
+        $dst.r14 = $src.r14 as _;
+        $dst.r15 = $src.r15 as _;
+        $dst.rip = $src.rip as _;
+    };
+}
+
+// The segment selectors of the user code and the user stack.
+const USER_CS: u64 = 0x33;
+const USER_SS: u64 = 0x2b;
+
+/// The trap flag, which enables single-stepping.
+const RFLAGS_TF: u64 = 1 << 8;
+/// The flags in `RFLAGS` that can be modified by the tracer.
+///
+/// These are CF, PF, AF, ZF, SF, TF, DF, OF, RF, and AC.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.15.7/source/arch/x86/kernel/ptrace.c#L142>
+const RFLAGS_USER_MASK: u64 = 0x50dd5;
+
+impl UserRegs {
+    /// Saves the registers in `user_ctx`.
+    ///
+    /// `syscall_num` is the number of the system call that is being traced, if any.
+    pub fn new(user_ctx: &UserContext, syscall_num: Option<usize>) -> Self {
+        let gp_regs = user_ctx.general_regs();
+
+        let mut regs = Self {
+            orig_rax: syscall_num.map_or(u64::MAX, |num| num as u64),
+            cs: USER_CS,
+            eflags: gp_regs.rflags as u64,
+            ss: USER_SS,
+            fs_base: gp_regs.fsbase as u64,
+            gs_base: gp_regs.gsbase as u64,
+            ..Default::default()
+        };
+        copy_gp_regs!(gp_regs, regs);
+
+        regs
+    }
+
+    /// Restores the registers to `user_ctx`.
+    ///
+    /// The segment selectors cannot be changed and are ignored. Only the flags that are
+    /// modifiable by the user program are changed in `RFLAGS`. `syscall_num` is updated according
+    /// to `orig_rax`, where `-1` means that there is no system call (i.e., the system call should
+    /// be skipped).
+    pub fn copy_to(&self, user_ctx: &mut UserContext, syscall_num: &mut Option<usize>) {
+        let gp_regs = user_ctx.general_regs_mut();
+
+        copy_gp_regs!(self, gp_regs);
+        gp_regs.rflags = ((gp_regs.rflags as u64 & !RFLAGS_USER_MASK)
+            | (self.eflags & RFLAGS_USER_MASK)) as usize;
+        gp_regs.fsbase = self.fs_base as usize;
+        gp_regs.gsbase = self.gs_base as usize;
+
+        *syscall_num = (self.orig_rax != u64::MAX).then_some(self.orig_rax as usize);
+    }
+}
+
+/// Enables or disables single-stepping for the user context.
+pub fn set_single_step(user_ctx: &mut UserContext, is_enabled: bool) -> Result<()> {
+    let gp_regs = user_ctx.general_regs_mut();
+    if is_enabled {
+        gp_regs.rflags |= RFLAGS_TF as usize;
+    } else {
+        gp_regs.rflags &= !RFLAGS_TF as usize;
+    }
+
+    Ok(())
+}
+
+// The layout of `struct user`.
+//
+// Reference: <https://elixir.bootlin.com/linux/v6.15.7/source/arch/x86/include/asm/user_64.h#L104>
+const USER_DEBUGREG_OFFSET: usize = 848;
+const USER_SIZE: usize = 912;
+
+/// Reads a word at `offset` in the `struct user` of the tracee (i.e., `PTRACE_PEEKUSER`).
+pub fn peek_user(
+    user_ctx: &UserContext,
+    syscall_num: Option<usize>,
+    offset: usize,
+) -> Result<usize> {
+    check_user_offset(offset)?;
+
+    if offset < size_of::<UserRegs>() {
+        let regs = UserRegs::new(user_ctx, syscall_num);
+        let bytes = &regs.as_bytes()[offset..offset + size_of::<u64>()];
+        return Ok(u64::from_ne_bytes(bytes.try_into().unwrap()) as usize);
+    }
+
+    // The other fields, including the debug registers, are not supported and read as zeros.
+    Ok(0)
+}
+
+/// Writes a word at `offset` in the `struct user` of the tracee (i.e., `PTRACE_POKEUSER`).
+pub fn poke_user(
+    user_ctx: &mut UserContext,
+    syscall_num: &mut Option<usize>,
+    offset: usize,
+    value: usize,
+) -> Result<()> {
+    check_user_offset(offset)?;
+
+    if offset < size_of::<UserRegs>() {
+        let mut regs = UserRegs::new(user_ctx, *syscall_num);
+        regs.as_bytes_mut()[offset..offset + size_of::<u64>()]
+            .copy_from_slice(&(value as u64).to_ne_bytes());
+        regs.copy_to(user_ctx, syscall_num);
+        return Ok(());
+    }
+
+    // Clearing the debug registers is allowed since they are never set.
+    if offset >= USER_DEBUGREG_OFFSET && value == 0 {
+        return Ok(());
+    }
+
+    return_errno_with_message!(Errno::EIO, "the field in the user area cannot be written");
+}
+
+fn check_user_offset(offset: usize) -> Result<()> {
+    if offset % size_of::<u64>() != 0 || offset >= USER_SIZE {
+        return_errno_with_message!(Errno::EIO, "the offset in the user area is invalid");
+    }
+
+    Ok(())
+}
+
+/// The size of the floating-point registers exposed by `PTRACE_GETFPREGS` and the
+/// `NT_PRFPREG` register set, which is the size of `struct user_i387_struct`.
+pub const USER_FP_REGS_SIZE: usize = 512;
+
+/// Returns the floating-point registers in the `FXSAVE` format.
+pub fn fp_regs(fpu_ctx: &FpuContext) -> &[u8] {
+    &fpu_ctx.as_bytes()[..USER_FP_REGS_SIZE]
+}
+
+/// Returns the mutable floating-point registers in the `FXSAVE` format.
+pub fn fp_regs_mut(fpu_ctx: &mut FpuContext) -> &mut [u8] {
+    &mut fpu_ctx.as_bytes_mut()[..USER_FP_REGS_SIZE]
+}
@@ -57,6 +57,10 @@ impl FileSystem for CgroupFs {
     fn flags(&self) -> FsFlags {
         FsFlags::empty()
     }
+
+    fn name(&self) -> &'static str {
+        "cgroup2"
+    }
 }
 
 pub(super) struct CgroupFsType {
@@ -4,7 +4,7 @@
 #![expect(unused_variables)]
 
 use alloc::string::String;
-use core::{cmp::Ordering, time::Duration};
+use core::{cmp::Ordering, ops::Range, time::Duration};
 
 pub(super) use align_ext::AlignExt;
 use aster_block::{
@@ -1703,6 +1703,18 @@ impl Inode for ExfatInode {
         Ok(())
     }
 
+    fn sync_data_range(&self, range: Range<usize>) -> Result<()> {
+        let inner = self.inner.read();
+        let fs = inner.fs();
+        let _fs_guard = fs.lock();
+        let size = inner.size;
+        inner
+            .page_cache
+            .evict_range(range.start.min(size)..range.end.min(size))?;
+
+        Ok(())
+    }
+
     fn poll(&self, mask: IoEvents, _poller: Option<&mut PollHandle>) -> IoEvents {
         let events = IoEvents::IN | IoEvents::OUT;
         events & mask
@@ -5,8 +5,10 @@ use ostd::{const_assert, mm::io_util::HasVmReaderWriter};
 
 use super::{
     block_ptr::Ext2Bid,
+    csum::{crc16, crc32c},
+    extent::ExtentTree,
     fs::Ext2,
-    inode::{Inode, InodeDesc, RawInode},
+    inode::{Inode, InodeDesc, RawInode, RawInodeExtra, RAW_INODE_SIZE},
     prelude::*,
     super_block::SuperBlock,
 };
@@ -42,35 +44,82 @@ impl BlockGroup {
                 let descriptor = {
                     // Read the block group descriptor
                     // TODO: if the main is corrupted, should we load the backup?
-                    let offset = idx * core::mem::size_of::<RawGroupDescriptor>();
-                    let raw_descriptor = group_descriptors_segment
-                        .read_val::<RawGroupDescriptor>(offset)
-                        .unwrap();
-                    GroupDescriptor::from(raw_descriptor)
+                    let desc_size = super_block.desc_size();
+                    let mut raw = vec![0u8; desc_size];
+                    group_descriptors_segment.read_bytes(idx * desc_size, &mut raw)?;
+                    if let Some(checksum) = group_desc_checksum(super_block, idx, &raw) {
+                        let raw_descriptor = RawGroupDescriptor::from_bytes(&raw);
+                        if raw_descriptor.checksum != checksum {
+                            return_errno_with_message!(
+                                Errno::EBADMSG,
+                                "group descriptor checksum mismatch"
+                            );
+                        }
+                    }
+                    GroupDescriptor::try_from(raw)?
                 };
 
-                let get_bitmap = |bid: Ext2Bid, capacity: usize| -> Result<IdAlloc> {
+                let get_bitmap = |bid: Ext2Bid, capacity: usize| -> Result<Vec<u8>> {
                     if capacity > BLOCK_SIZE * 8 {
                         return_errno_with_message!(Errno::EINVAL, "bad bitmap");
                     }
                     let mut buf = vec![0u8; BLOCK_SIZE];
                     block_device.read_bytes(bid as usize * BLOCK_SIZE, &mut buf)?;
-                    Ok(IdAlloc::from_bytes_with_capacity(&buf, capacity))
+                    Ok(buf)
+                };
+
+                // The uninitialized flags are valid only if the descriptors are checksummed.
+                let is_uninit = |flag: GroupFlags| {
+                    super_block.has_uninit_groups() && descriptor.flags.contains(flag)
+                };
+
+                let blocks_per_group = super_block.blocks_per_group() as usize;
+                let block_bitmap = if is_uninit(GroupFlags::BLOCK_UNINIT) {
+                    init_block_bitmap(super_block, idx, &descriptor)
+                } else {
+                    let buf = get_bitmap(descriptor.block_bitmap_bid, blocks_per_group)?;
+                    if !bitmap_checksum_matches(
+                        super_block,
+                        &buf[..blocks_per_group / 8],
+                        descriptor.block_bitmap_csum,
+                    ) {
+                        return_errno_with_message!(
+                            Errno::EBADMSG,
+                            "block bitmap checksum mismatch"
+                        );
+                    }
+                    buf
                 };
 
-                let block_bitmap = get_bitmap(
-                    descriptor.block_bitmap_bid,
-                    super_block.blocks_per_group() as usize,
-                )?;
-                let inode_bitmap = get_bitmap(
-                    descriptor.inode_bitmap_bid,
-                    super_block.inodes_per_group() as usize,
-                )?;
+                let inodes_per_group = super_block.inodes_per_group() as usize;
+                let inode_bitmap = if is_uninit(GroupFlags::INODE_UNINIT) {
+                    vec![0u8; BLOCK_SIZE]
+                } else {
+                    let buf = get_bitmap(descriptor.inode_bitmap_bid, inodes_per_group)?;
+                    if !bitmap_checksum_matches(
+                        super_block,
+                        &buf[..inodes_per_group / 8],
+                        descriptor.inode_bitmap_csum,
+                    ) {
+                        return_errno_with_message!(
+                            Errno::EBADMSG,
+                            "inode bitmap checksum mismatch"
+                        );
+                    }
+                    buf
+                };
 
                 GroupMetadata {
                     descriptor,
-                    block_bitmap,
-                    inode_bitmap,
+                    block_bitmap: IdAlloc::from_bytes_with_capacity(
+                        &block_bitmap,
+                        blocks_per_group,
+                    ),
+                    inode_bitmap: IdAlloc::from_bytes_with_capacity(
+                        &inode_bitmap,
+                        inodes_per_group,
+                    ),
+                    inodes_per_group: super_block.inodes_per_group(),
                 }
             };
 
@@ -95,6 +144,21 @@ impl BlockGroup {
         })
     }
 
+    /// Returns the first block of the inode table of the `idx`-th group.
+    ///
+    /// The descriptor is read from the table directly, so the block groups need not
+    /// be loaded, e.g., when locating the journal inode before replaying the journal.
+    pub fn inode_table_bid(
+        group_descriptors_segment: &USegment,
+        idx: usize,
+        super_block: &SuperBlock,
+    ) -> Result<Ext2Bid> {
+        let desc_size = super_block.desc_size();
+        let mut raw = vec![0u8; desc_size];
+        group_descriptors_segment.read_bytes(idx * desc_size, &mut raw)?;
+        Ok(GroupDescriptor::try_from(raw)?.inode_table_bid)
+    }
+
     /// Finds and returns the inode.
     pub fn lookup_inode(&self, inode_idx: u32) -> Result<Arc<Inode>> {
         // The fast path
@@ -122,22 +186,76 @@ impl BlockGroup {
         Ok(inode)
     }
 
+    /// Returns the inode if it is in the inode cache.
+    pub fn cached_inode(&self, inode_idx: u32) -> Option<Arc<Inode>> {
+        self.bg_impl
+            .inner
+            .read()
+            .inode_cache
+            .get(&inode_idx)
+            .cloned()
+    }
+
     /// Loads an existing inode.
     ///
     /// This method may load the raw inode metadata from block device.
     fn load_inode(&self, inode_idx: u32) -> Result<Arc<Inode>> {
         let fs = self.fs();
-        let raw_inode = {
+        let ino = inode_idx + self.idx as u32 * fs.inodes_per_group() + 1;
+        let raw_slot = {
             let offset = (inode_idx as usize) * fs.inode_size();
+            let mut raw_slot = vec![0u8; fs.inode_size()];
             self.raw_inodes_cache
                 .pages()
-                .read_val::<RawInode>(offset)
-                .unwrap()
+                .read_bytes(offset, &mut raw_slot)?;
+            raw_slot
         };
-        let inode_desc = Dirty::new(InodeDesc::try_from(raw_inode)?);
-        let ino = inode_idx + self.idx as u32 * fs.inodes_per_group() + 1;
+        let raw_inode = RawInode::from_bytes(&raw_slot);
+        let raw_extra = RawInodeExtra::from_slot(&raw_slot);
+        let extra_isize = raw_extra.extra_isize as usize;
+        if RAW_INODE_SIZE + extra_isize > raw_slot.len() || extra_isize % 4 != 0 {
+            return_errno_with_message!(Errno::EUCLEAN, "invalid extra inode size");
+        }
 
-        Ok(Inode::new(ino, self.idx, inode_desc, Arc::downgrade(&fs)))
+        if let Some(csum_seed) = fs.inode_csum_seed(ino, raw_inode.generation) {
+            let checksum = inode_checksum(csum_seed, &raw_slot);
+            let (recorded, checksum) = if raw_extra.has_checksum_hi() {
+                (
+                    ((raw_extra.checksum_hi as u32) << 16)
+                        | raw_inode.os_dependent_2.checksum_lo as u32,
+                    checksum,
+                )
+            } else {
+                (
+                    raw_inode.os_dependent_2.checksum_lo as u32,
+                    checksum & 0xffff,
+                )
+            };
+            if recorded != checksum {
+                return_errno_with_message!(Errno::EBADMSG, "inode checksum mismatch");
+            }
+        }
+
+        let inode_desc = Dirty::new(InodeDesc::from_raw(&raw_inode, &raw_extra, fs.version())?);
+        let extent_tree = if inode_desc.is_extent_mapped() {
+            let csum_seed = fs.inode_csum_seed(ino, inode_desc.generation());
+            let read_block = |bid: Ext2Bid, buf: &mut [u8]| fs.read_block(bid, buf);
+            Some(ExtentTree::load(
+                &inode_desc.block_ptrs(),
+                &read_block,
+                csum_seed,
+            )?)
+        } else {
+            None
+        };
+
+        Ok(Inode::new(
+            ino,
+            self.idx,
+            inode_desc,
+            extent_tree,
+            Arc::downgrade(&fs),
+        ))
     }
 
     /// Inserts the inode into the inode cache.
@@ -209,40 +327,143 @@ impl BlockGroup {
         inner.metadata.free_blocks(range);
     }
 
+    /// Returns the number of free blocks in this group.
+    pub fn free_blocks_count(&self) -> u32 {
+        self.bg_impl.inner.read().metadata.free_blocks_count() as u32
+    }
+
+    /// Returns the number of free inodes in this group.
+    pub fn free_inodes_count(&self) -> u32 {
+        self.bg_impl.inner.read().metadata.free_inodes_count() as u32
+    }
+
     /// Writes back the raw inode metadata to the raw inode metadata cache.
-    pub fn sync_raw_inode(&self, inode_idx: u32, raw_inode: &RawInode) {
-        let offset = (inode_idx as usize) * self.fs().inode_size();
+    ///
+    /// The bytes of the inode slot that are not covered by `raw_inode` and `raw_extra`,
+    /// e.g., the extended attributes stored inside the inode, are kept unchanged.
+    pub fn sync_raw_inode(
+        &self,
+        inode_idx: u32,
+        raw_inode: &RawInode,
+        raw_extra: &RawInodeExtra,
+    ) -> Result<()> {
+        let fs = self.fs();
+        let offset = (inode_idx as usize) * fs.inode_size();
+        let mut raw_slot = vec![0u8; fs.inode_size()];
         self.raw_inodes_cache
             .pages()
-            .write_val(offset, raw_inode)
-            .unwrap();
+            .read_bytes(offset, &mut raw_slot)?;
+
+        raw_slot[..RAW_INODE_SIZE].copy_from_slice(raw_inode.as_bytes());
+        raw_extra.write_to_slot(&mut raw_slot);
+
+        let ino = inode_idx + self.idx as u32 * fs.inodes_per_group() + 1;
+        if let Some(csum_seed) = fs.inode_csum_seed(ino, raw_inode.generation) {
+            let checksum = inode_checksum(csum_seed, &raw_slot);
+            let mut raw_inode = *raw_inode;
+            raw_inode.os_dependent_2.checksum_lo = checksum as u16;
+            raw_slot[..RAW_INODE_SIZE].copy_from_slice(raw_inode.as_bytes());
+            if raw_extra.has_checksum_hi() {
+                let mut raw_extra = *raw_extra;
+                raw_extra.checksum_hi = (checksum >> 16) as u16;
+                raw_extra.write_to_slot(&mut raw_slot);
+            }
+        }
+
+        self.raw_inodes_cache
+            .pages()
+            .write_bytes(offset, &raw_slot)?;
+        Ok(())
+    }
+
+    /// Fills the raw inode with zeros in the raw inode metadata cache.
+    ///
+    /// This is required before using a newly-allocated inode, since the inode table
+    /// may not be initialized or may contain the stale data of a freed inode.
+    pub fn clear_raw_inode(&self, inode_idx: u32) -> Result<()> {
+        let inode_size = self.fs().inode_size();
+        let offset = (inode_idx as usize) * inode_size;
+        self.raw_inodes_cache
+            .pages()
+            .fill_zeros(offset..offset + inode_size)?;
+        Ok(())
+    }
+
+    /// Writes back the raw inode from the raw inode metadata cache.
+    pub fn flush_raw_inode(&self, inode_idx: u32) -> Result<()> {
+        let inode_size = self.fs().inode_size();
+        let offset = (inode_idx as usize) * inode_size;
+        self.raw_inodes_cache
+            .evict_range(offset..offset + inode_size)?;
+        Ok(())
+    }
+
+    /// Returns whether the metadata of this group is dirty.
+    pub fn is_dirty(&self) -> bool {
+        self.bg_impl.inner.read().metadata.is_dirty()
     }
 
     /// Writes back the metadata of this group.
-    pub fn sync_metadata(&self) -> Result<()> {
-        if !self.bg_impl.inner.read().metadata.is_dirty() {
+    pub fn sync_metadata(&self, super_block: &SuperBlock) -> Result<()> {
+        if !self.is_dirty() {
             return Ok(());
         }
 
         let mut inner = self.bg_impl.inner.write();
         let fs = self.fs();
         // Writes back the descriptor.
-        let raw_descriptor = RawGroupDescriptor::from(&inner.metadata.descriptor);
-        self.fs().sync_group_descriptor(self.idx, &raw_descriptor)?;
+        let raw_descriptor = {
+            let metadata = inner.metadata.deref_mut();
+            if let Some(csum_seed) = super_block.csum_seed() {
+                let blocks_per_group = super_block.blocks_per_group() as usize;
+                let inodes_per_group = super_block.inodes_per_group() as usize;
+                metadata.descriptor.block_bitmap_csum = crc32c(
+                    csum_seed,
+                    &metadata.block_bitmap.as_bytes()[..blocks_per_group / 8],
+                );
+                metadata.descriptor.inode_bitmap_csum = crc32c(
+                    csum_seed,
+                    &metadata.inode_bitmap.as_bytes()[..inodes_per_group / 8],
+                );
+            }
+
+            let mut raw_descriptor = metadata.descriptor.to_raw();
+            if let Some(checksum) = group_desc_checksum(super_block, self.idx, &raw_descriptor) {
+                raw_descriptor[CHECKSUM_OFFSET..CHECKSUM_OFFSET + 2]
+                    .copy_from_slice(&checksum.to_le_bytes());
+            }
+            raw_descriptor
+        };
+        fs.sync_group_descriptor(self.idx, &raw_descriptor)?;
+
+        // The bits beyond the end of a bitmap are padded with ones.
+        let padded_bitmap = |bitmap: &IdAlloc| {
+            let mut buf = vec![0xffu8; BLOCK_SIZE];
+            let bytes = bitmap.as_bytes();
+            buf[..bytes.len()].copy_from_slice(bytes);
+            buf
+        };
+
+        let bitmap_bio_segment = |bitmap: &IdAlloc| {
+            let bio_segment = BioSegment::alloc(1, BioDirection::ToDevice);
+            bio_segment
+                .writer()
+                .unwrap()
+                .write(&mut VmReader::from(padded_bitmap(bitmap).as_slice()));
+            bio_segment
+        };
 
         let mut bio_waiter = BioWaiter::new();
         // Writes back the inode bitmap.
-        let inode_bitmap_bid = Bid::new(inner.metadata.descriptor.inode_bitmap_bid as u64);
-        bio_waiter.concat(fs.block_device().write_bytes_async(
-            inode_bitmap_bid.to_offset(),
-            inner.metadata.inode_bitmap.as_bytes(),
+        bio_waiter.concat(fs.write_metadata_blocks_async(
+            inner.metadata.descriptor.inode_bitmap_bid,
+            bitmap_bio_segment(&inner.metadata.inode_bitmap),
         )?);
 
         // Writes back the block bitmap.
-        let block_bitmap_bid = Bid::new(inner.metadata.descriptor.block_bitmap_bid as u64);
-        bio_waiter.concat(fs.block_device().write_bytes_async(
-            block_bitmap_bid.to_offset(),
-            inner.metadata.block_bitmap.as_bytes(),
+        bio_waiter.concat(fs.write_metadata_blocks_async(
+            inner.metadata.descriptor.block_bitmap_bid,
+            bitmap_bio_segment(&inner.metadata.block_bitmap),
         )?);
 
         // Waits for the completion of all submitted bios.
@@ -254,6 +475,12 @@ impl BlockGroup {
         Ok(())
     }
 
+    /// Returns the cached inodes, or `None` if the inode cache is locked.
+    pub fn try_cached_inodes(&self) -> Option<Vec<Arc<Inode>>> {
+        let inner = self.bg_impl.inner.try_read()?;
+        Some(inner.inode_cache.values().cloned().collect())
+    }
+
     /// Writes back all of the cached inodes.
     ///
     /// The `sync_all` method of inode may modify the data of this block group,
@@ -344,7 +571,7 @@ impl PageCacheBackend for BlockGroupImpl {
         self.fs
             .upgrade()
             .unwrap()
-            .write_blocks_async(bid, bio_segment)
+            .write_metadata_blocks_async(bid, bio_segment)
     }
 
     fn npages(&self) -> usize {
@@ -363,6 +590,7 @@ struct GroupMetadata {
     descriptor: GroupDescriptor,
     block_bitmap: IdAlloc,
     inode_bitmap: IdAlloc,
+    inodes_per_group: u32,
 }
 
 impl GroupMetadata {
@@ -371,12 +599,19 @@ impl GroupMetadata {
     }
 
     pub fn alloc_inode(&mut self, is_dir: bool) -> Option<u32> {
-        let inode_idx = self.inode_bitmap.alloc()?;
+        let inode_idx = self.inode_bitmap.alloc()? as u32;
         self.dec_free_inodes();
         if is_dir {
             self.inc_dirs();
         }
-        Some(inode_idx as u32)
+
+        self.descriptor.flags.remove(GroupFlags::INODE_UNINIT);
+        // The inodes after the last used one are considered unused.
+        let used_inodes = self.inodes_per_group - self.descriptor.itable_unused as u32;
+        if inode_idx >= used_inodes {
+            self.descriptor.itable_unused = (self.inodes_per_group - inode_idx - 1) as u16;
+        }
+        Some(inode_idx)
     }
 
     pub fn free_inode(&mut self, inode_idx: u32, is_dir: bool) {
@@ -400,6 +635,7 @@ impl GroupMetadata {
                 continue;
             };
             self.dec_free_blocks(current_count as u16);
+            self.descriptor.flags.remove(GroupFlags::BLOCK_UNINIT);
             return Some((range.start as Ext2Bid)..(range.end as Ext2Bid));
         }
         None
@@ -458,7 +694,7 @@ impl GroupMetadata {
 ///
 /// The block group descriptor contains information regarding where important data
 /// structures for that group are located.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Debug)]
 struct GroupDescriptor {
     /// Blocks usage bitmap block
     block_bitmap_bid: Ext2Bid,
@@ -472,21 +708,213 @@ struct GroupDescriptor {
     free_inodes_count: u16,
     /// Number of directories in group
     dirs_count: u16,
+    /// Block group flags
+    flags: GroupFlags,
+    /// Number of unused inodes at the end of the inode table
+    itable_unused: u16,
+    /// Checksum of the block bitmap
+    block_bitmap_csum: u32,
+    /// Checksum of the inode bitmap
+    inode_bitmap_csum: u32,
+    /// The raw descriptor loaded from the device.
+    ///
+    /// It keeps the fields that are not interpreted by us,
+    /// so they can be written back unchanged.
+    raw: Vec<u8>,
 }
 
-impl From<RawGroupDescriptor> for GroupDescriptor {
-    fn from(desc: RawGroupDescriptor) -> Self {
-        Self {
+impl TryFrom<Vec<u8>> for GroupDescriptor {
+    type Error = crate::error::Error;
+
+    fn try_from(raw: Vec<u8>) -> Result<Self> {
+        let desc = RawGroupDescriptor::from_bytes(&raw);
+        let desc_hi = if raw.len() >= MIN_DESC_SIZE_64BIT {
+            RawGroupDescriptorHi::from_bytes(&raw[core::mem::size_of::<RawGroupDescriptor>()..])
+        } else {
+            RawGroupDescriptorHi::new_zeroed()
+        };
+        if desc_hi.block_bitmap != 0 || desc_hi.inode_bitmap != 0 || desc_hi.inode_table != 0 {
+            return_errno_with_message!(
+                Errno::EFBIG,
+                "block numbers exceeding 32 bits are not supported"
+            );
+        }
+        if desc_hi.free_blocks_count != 0
+            || desc_hi.free_inodes_count != 0
+            || desc_hi.dirs_count != 0
+            || desc_hi.itable_unused != 0
+        {
+            return_errno_with_message!(Errno::EUCLEAN, "invalid group descriptor counters");
+        }
+
+        Ok(Self {
             block_bitmap_bid: desc.block_bitmap,
             inode_bitmap_bid: desc.inode_bitmap,
             inode_table_bid: desc.inode_table,
             free_blocks_count: desc.free_blocks_count,
             free_inodes_count: desc.free_inodes_count,
             dirs_count: desc.dirs_count,
+            flags: GroupFlags::from_bits_truncate(desc.flags),
+            itable_unused: desc.itable_unused,
+            block_bitmap_csum: ((desc_hi.block_bitmap_csum as u32) << 16)
+                | desc.block_bitmap_csum as u32,
+            inode_bitmap_csum: ((desc_hi.inode_bitmap_csum as u32) << 16)
+                | desc.inode_bitmap_csum as u32,
+            raw,
+        })
+    }
+}
+
+impl GroupDescriptor {
+    /// Converts the descriptor to the raw bytes, with the checksum unchanged.
+    fn to_raw(&self) -> Vec<u8> {
+        let mut raw = self.raw.clone();
+
+        let mut desc = RawGroupDescriptor::from_bytes(&raw);
+        desc.block_bitmap = self.block_bitmap_bid;
+        desc.inode_bitmap = self.inode_bitmap_bid;
+        desc.inode_table = self.inode_table_bid;
+        desc.free_blocks_count = self.free_blocks_count;
+        desc.free_inodes_count = self.free_inodes_count;
+        desc.dirs_count = self.dirs_count;
+        desc.flags = self.flags.bits() | (desc.flags & !GroupFlags::all().bits());
+        desc.itable_unused = self.itable_unused;
+        desc.block_bitmap_csum = self.block_bitmap_csum as u16;
+        desc.inode_bitmap_csum = self.inode_bitmap_csum as u16;
+        let desc_len = core::mem::size_of::<RawGroupDescriptor>();
+        raw[..desc_len].copy_from_slice(desc.as_bytes());
+
+        if raw.len() >= MIN_DESC_SIZE_64BIT {
+            let mut desc_hi = RawGroupDescriptorHi::from_bytes(&raw[desc_len..]);
+            desc_hi.block_bitmap_csum = (self.block_bitmap_csum >> 16) as u16;
+            desc_hi.inode_bitmap_csum = (self.inode_bitmap_csum >> 16) as u16;
+            raw[desc_len..desc_len * 2].copy_from_slice(desc_hi.as_bytes());
         }
+
+        raw
+    }
+}
+
+bitflags! {
+    /// Block group flags.
+    struct GroupFlags: u16 {
+        /// The inode table and the inode bitmap are not initialized.
+        const INODE_UNINIT = 1 << 0;
+        /// The block bitmap is not initialized.
+        const BLOCK_UNINIT = 1 << 1;
+        /// The inode table is zeroed.
+        const ITABLE_ZEROED = 1 << 2;
+    }
+}
+
+/// Synthesizes the block bitmap of a block group with the `BLOCK_UNINIT` flag.
+///
+/// In such a group, only the blocks of the filesystem metadata are in use.
+fn init_block_bitmap(
+    super_block: &SuperBlock,
+    idx: usize,
+    descriptor: &GroupDescriptor,
+) -> Vec<u8> {
+    let mut bitmap = vec![0u8; BLOCK_SIZE];
+    let mut set_bit = |bit: usize| bitmap[bit / 8] |= 1 << (bit % 8);
+
+    // The superblock, the descriptor table and the reserved descriptor blocks.
+    if idx == 0 || super_block.is_backup_group(idx) {
+        let meta_blocks = 1
+            + super_block.group_descriptors_blocks() as usize
+            + super_block.reserved_gdt_blocks() as usize;
+        (0..meta_blocks).for_each(&mut set_bit);
+    }
+
+    // The bitmaps and the inode table, which may be placed in another group with `FLEX_BG`.
+    let blocks_per_group = super_block.blocks_per_group();
+    let group_range = {
+        let start = idx as Ext2Bid * blocks_per_group;
+        start..start + blocks_per_group
+    };
+    let itable_blocks = (super_block.inodes_per_group() as usize * super_block.inode_size())
+        .div_ceil(BLOCK_SIZE) as Ext2Bid;
+    let meta_bids = [descriptor.block_bitmap_bid, descriptor.inode_bitmap_bid]
+        .into_iter()
+        .chain(descriptor.inode_table_bid..descriptor.inode_table_bid + itable_blocks);
+    for bid in meta_bids {
+        if group_range.contains(&bid) {
+            set_bit((bid - group_range.start) as usize);
+        }
+    }
+
+    // The blocks beyond the end of the filesystem.
+    let nblocks = (super_block.total_blocks() - group_range.start).min(blocks_per_group);
+    (nblocks as usize..blocks_per_group as usize).for_each(set_bit);
+
+    bitmap
+}
+
+/// Computes the checksum of the raw inode slot.
+///
+/// The checksum fields themselves are treated as zeros.
+fn inode_checksum(csum_seed: u32, raw_slot: &[u8]) -> u32 {
+    let mut raw_slot = raw_slot.to_vec();
+
+    let mut raw_inode = RawInode::from_bytes(&raw_slot);
+    raw_inode.os_dependent_2.checksum_lo = 0;
+    raw_slot[..RAW_INODE_SIZE].copy_from_slice(raw_inode.as_bytes());
+
+    let mut raw_extra = RawInodeExtra::from_slot(&raw_slot);
+    if raw_extra.has_checksum_hi() {
+        raw_extra.checksum_hi = 0;
+        raw_extra.write_to_slot(&mut raw_slot);
+    }
+
+    crc32c(csum_seed, &raw_slot)
+}
+
+/// Returns whether the checksum of the bitmap matches the `recorded` one.
+fn bitmap_checksum_matches(super_block: &SuperBlock, bitmap: &[u8], recorded: u32) -> bool {
+    let Some(csum_seed) = super_block.csum_seed() else {
+        return true;
+    };
+
+    let checksum = crc32c(csum_seed, bitmap);
+    if super_block.desc_size() >= MIN_DESC_SIZE_64BIT {
+        checksum == recorded
+    } else {
+        checksum & 0xffff == recorded
+    }
+}
+
+/// Computes the checksum of the raw block group descriptor.
+///
+/// Returns `None` if the descriptors are not checksummed.
+fn group_desc_checksum(super_block: &SuperBlock, idx: usize, raw: &[u8]) -> Option<u16> {
+    let le_idx = (idx as u32).to_le_bytes();
+    let rest = &raw[CHECKSUM_OFFSET + 2..];
+
+    if let Some(csum_seed) = super_block.csum_seed() {
+        let checksum = crc32c(csum_seed, &le_idx);
+        let checksum = crc32c(checksum, &raw[..CHECKSUM_OFFSET]);
+        let checksum = crc32c(checksum, &[0u8; 2]);
+        let checksum = crc32c(checksum, rest);
+        return Some(checksum as u16);
+    }
+
+    if super_block.has_gdt_csum() {
+        let checksum = crc16(!0, super_block.uuid());
+        let checksum = crc16(checksum, &le_idx);
+        let checksum = crc16(checksum, &raw[..CHECKSUM_OFFSET]);
+        let checksum = crc16(checksum, rest);
+        return Some(checksum);
     }
+
+    None
 }
 
+/// The minimal size of the block group descriptor that contains the high 32-bit part.
+const MIN_DESC_SIZE_64BIT: usize = 64;
+
+/// The offset of the checksum in the raw block group descriptor.
+const CHECKSUM_OFFSET: usize = core::mem::offset_of!(RawGroupDescriptor, checksum);
+
 const_assert!(core::mem::size_of::<RawGroupDescriptor>() == 32);
 
 /// The raw block group descriptor.
@@ -494,28 +922,38 @@ const_assert!(core::mem::size_of::<RawGroupDescriptor>() == 32);
 /// The table starts on the first block following the superblock.
 #[repr(C)]
 #[derive(Clone, Copy, Debug, Pod)]
-pub(super) struct RawGroupDescriptor {
-    pub block_bitmap: u32,
-    pub inode_bitmap: u32,
-    pub inode_table: u32,
-    pub free_blocks_count: u16,
-    pub free_inodes_count: u16,
-    pub dirs_count: u16,
-    pad: u16,
-    reserved: [u32; 3],
+struct RawGroupDescriptor {
+    block_bitmap: u32,
+    inode_bitmap: u32,
+    inode_table: u32,
+    free_blocks_count: u16,
+    free_inodes_count: u16,
+    dirs_count: u16,
+    flags: u16,
+    exclude_bitmap: u32,
+    block_bitmap_csum: u16,
+    inode_bitmap_csum: u16,
+    itable_unused: u16,
+    checksum: u16,
 }
 
-impl From<&GroupDescriptor> for RawGroupDescriptor {
-    fn from(desc: &GroupDescriptor) -> Self {
-        Self {
-            block_bitmap: desc.block_bitmap_bid,
-            inode_bitmap: desc.inode_bitmap_bid,
-            inode_table: desc.inode_table_bid,
-            free_blocks_count: desc.free_blocks_count,
-            free_inodes_count: desc.free_inodes_count,
-            dirs_count: desc.dirs_count,
-            pad: 0u16,
-            reserved: [0u32; 3],
-        }
-    }
+const_assert!(core::mem::size_of::<RawGroupDescriptorHi>() == 32);
+
+/// The high part of the raw block group descriptor.
+///
+/// It follows `RawGroupDescriptor` if the `_64BIT` feature is set.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod)]
+struct RawGroupDescriptorHi {
+    block_bitmap: u32,
+    inode_bitmap: u32,
+    inode_table: u32,
+    free_blocks_count: u16,
+    free_inodes_count: u16,
+    dirs_count: u16,
+    itable_unused: u16,
+    exclude_bitmap: u32,
+    block_bitmap_csum: u16,
+    inode_bitmap_csum: u16,
+    reserved: u32,
 }
@@ -0,0 +1,91 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Checksum algorithms used by the metadata of Ext4.
+//!
+//! With the `METADATA_CSUM` feature, metadata blocks are protected by CRC32C.
+//! The older `GDT_CSUM` feature only protects group descriptors with CRC16.
+//!
+//! Like Linux, the CRC32C values are computed without the final inversion,
+//! so they can be chained by passing the previous value as the seed.
+
+/// Computes the CRC32C (Castagnoli) of `data`, continuing from `crc`.
+pub(super) fn crc32c(crc: u32, data: &[u8]) -> u32 {
+    data.iter().fold(crc, |crc, byte| {
+        CRC32C_TABLE[((crc ^ *byte as u32) & 0xff) as usize] ^ (crc >> 8)
+    })
+}
+
+/// Computes the CRC16 (the polynomial `0x8005`, bit-reversed) of `data`, continuing from `crc`.
+pub(super) fn crc16(crc: u16, data: &[u8]) -> u16 {
+    data.iter().fold(crc, |crc, byte| {
+        CRC16_TABLE[((crc ^ *byte as u16) & 0xff) as usize] ^ (crc >> 8)
+    })
+}
+
+const CRC32C_TABLE: [u32; 256] = make_crc32_table(0x82f6_3b78);
+
+const CRC16_TABLE: [u16; 256] = make_crc16_table(0xa001);
+
+const fn make_crc32_table(poly: u32) -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ poly
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+const fn make_crc16_table(poly: u16) -> [u16; 256] {
+    let mut table = [0u16; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u16;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ poly
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+#[cfg(ktest)]
+mod test {
+    use ostd::prelude::*;
+
+    use super::*;
+
+    #[ktest]
+    fn crc32c_check_value() {
+        assert_eq!(crc32c(!0, b"123456789") ^ !0, 0xe306_9283);
+    }
+
+    #[ktest]
+    fn crc32c_chained() {
+        let whole = crc32c(!0, b"hello, world");
+        let chained = crc32c(crc32c(!0, b"hello, "), b"world");
+        assert_eq!(whole, chained);
+    }
+
+    #[ktest]
+    fn crc16_check_value() {
+        assert_eq!(crc16(0, b"123456789"), 0xbb3d);
+    }
+}
@@ -2,7 +2,7 @@
 
 #![expect(dead_code)]
 
-use super::{inode::MAX_FNAME_LEN, prelude::*};
+use super::{csum::crc32c, inode::MAX_FNAME_LEN, prelude::*};
 
 /// The data structure in a directory's data block. It is stored in a linked list.
 ///
@@ -104,6 +104,67 @@ impl DirEntryHeader {
             inode_type: DirEntryFileType::from(inode_type) as _,
         }
     }
+
+    /// Returns whether the header is valid to be followed by the name.
+    fn is_valid(&self) -> bool {
+        let record_len = self.record_len as usize;
+        record_len % DirEntry::ALIGN == 0
+            && record_len >= DirEntry::HEADER_LEN + self.name_len as usize
+    }
+
+    /// Returns whether it is the header of a `DirEntryTail`.
+    fn is_tail(&self) -> bool {
+        self.ino == 0
+            && self.record_len as usize == DIR_ENTRY_TAIL_LEN
+            && self.name_len == 0
+            && self.inode_type == DirEntryTail::FILE_TYPE
+    }
+}
+
+/// The fake entry at the end of each directory block, which holds the checksum of the block.
+///
+/// It is present only if the metadata checksums are enabled.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod)]
+pub(super) struct DirEntryTail {
+    /// The header, whose inode number is zero to hide the tail from the old drivers.
+    header: DirEntryHeader,
+    /// The checksum of the directory block.
+    checksum: u32,
+}
+
+/// The length of the `DirEntryTail`.
+pub(super) const DIR_ENTRY_TAIL_LEN: usize = core::mem::size_of::<DirEntryTail>();
+
+impl DirEntryTail {
+    const FILE_TYPE: u8 = 0xDE;
+
+    fn new() -> Self {
+        Self {
+            header: DirEntryHeader {
+                ino: 0,
+                record_len: DIR_ENTRY_TAIL_LEN as _,
+                name_len: 0,
+                inode_type: Self::FILE_TYPE,
+            },
+            checksum: 0,
+        }
+    }
+}
+
+/// Fills the checksum in the tail of the directory block if the block has a tail.
+///
+/// The blocks without a tail, e.g., the internal nodes of the hash tree, are left untouched.
+pub(super) fn fill_dir_block_checksum(block: &mut [u8], csum_seed: u32) {
+    debug_assert_eq!(block.len(), BLOCK_SIZE);
+    let tail_offset = BLOCK_SIZE - DIR_ENTRY_TAIL_LEN;
+    let mut tail = DirEntryTail::from_bytes(&block[tail_offset..]);
+    if !tail.header.is_tail() {
+        return;
+    }
+
+    tail.checksum = crc32c(csum_seed, &block[..tail_offset]);
+    block[tail_offset..].copy_from_slice(tail.as_bytes());
 }
 
 /// The type indicator in the `DirEntry`.
@@ -154,14 +215,19 @@ impl From<DirEntryFileType> for InodeType {
 pub struct DirEntryReader<'a> {
     page_cache: &'a PageCache,
     from_offset: usize,
+    to_offset: usize,
     name_buf: Option<[u8; MAX_FNAME_LEN]>,
 }
 
 /// An iterator for iterating `DirEntryItem` from the
 /// page cache given a start offset.
+///
+/// The unused entries, whose inode numbers are zero, are skipped by default.
 pub(super) struct DirEntryIter<'a> {
     page_cache: &'a PageCache,
     offset: usize,
+    to_offset: usize,
+    skips_unused: bool,
 }
 
 impl<'a> DirEntryReader<'a> {
@@ -170,6 +236,17 @@ impl<'a> DirEntryReader<'a> {
         Self {
             page_cache,
             from_offset,
+            to_offset: usize::MAX,
+            name_buf: None,
+        }
+    }
+
+    /// Constructs a reader that only reads the entries in the given range of offsets.
+    pub(super) fn with_range(page_cache: &'a PageCache, range: Range<usize>) -> Self {
+        Self {
+            page_cache,
+            from_offset: range.start,
+            to_offset: range.end,
             name_buf: None,
         }
     }
@@ -179,17 +256,30 @@ impl<'a> DirEntryReader<'a> {
         DirEntryIter {
             page_cache: self.page_cache,
             offset: self.from_offset,
+            to_offset: self.to_offset,
+            skips_unused: true,
         }
     }
 
-    /// Returns an iterator for iterating `DirEntry`s.
-    pub fn iter_entries(&'a mut self) -> impl Iterator<Item = DirEntry> + 'a {
+    /// Returns an iterator for iterating `DirEntryItem`s, including the unused ones.
+    fn iter_with_unused(&self) -> DirEntryIter<'a> {
+        DirEntryIter {
+            skips_unused: false,
+            ..self.iter()
+        }
+    }
+
+    /// Returns an iterator for iterating `DirEntry`s with their offsets.
+    pub fn iter_entries(&'a mut self) -> impl Iterator<Item = (usize, DirEntry)> + 'a {
         let iter = self.iter();
         iter.filter_map(|entry_item| match self.read_name(&entry_item) {
-            Ok(name_buf) => Some(DirEntry {
-                header: entry_item.header,
-                name: CStr256::from(name_buf),
-            }),
+            Ok(name_buf) => Some((
+                entry_item.offset,
+                DirEntry {
+                    header: entry_item.header,
+                    name: CStr256::from(name_buf),
+                },
+            )),
             Err(_) => None,
         })
     }
@@ -248,19 +338,23 @@ impl<'a> DirEntryReader<'a> {
 impl DirEntryIter<'_> {
     /// Reads a `DirEntryItem` at the current offset.
     fn read_entry_item(&mut self) -> Result<DirEntryItem> {
-        if self.offset >= self.page_cache.pages().size() {
-            return_errno!(Errno::ENOENT);
-        }
+        loop {
+            if self.offset >= self.page_cache.pages().size().min(self.to_offset) {
+                return_errno!(Errno::ENOENT);
+            }
 
-        let header = self.read_header()?;
-        let record_len = header.record_len as usize;
-        let item = DirEntryItem {
-            header,
-            offset: self.offset,
-        };
+            let header = self.read_header()?;
+            let record_len = header.record_len as usize;
+            let item = DirEntryItem {
+                header,
+                offset: self.offset,
+            };
 
-        self.offset += record_len;
-        Ok(item)
+            self.offset += record_len;
+            if header.ino != 0 || !self.skips_unused {
+                return Ok(item);
+            }
+        }
     }
 
     /// Reads the header of the entry from the page cache.
@@ -269,8 +363,8 @@ impl DirEntryIter<'_> {
             .page_cache
             .pages()
             .read_val::<DirEntryHeader>(self.offset)?;
-        if header.ino == 0 {
-            return_errno!(Errno::ENOENT);
+        if !header.is_valid() {
+            return_errno_with_message!(Errno::EUCLEAN, "invalid directory entry");
         }
         Ok(header)
     }
@@ -344,25 +438,55 @@ impl DirEntryItem {
     pub fn gap_len(&self) -> usize {
         self.record_len() - self.actual_len()
     }
+
+    /// Returns whether the entry is unused, i.e., the whole record can hold a new entry.
+    fn is_unused(&self) -> bool {
+        self.header.ino == 0 && !self.header.is_tail()
+    }
 }
 
 /// A writer for modifying `DirEntry` of the page cache.
 pub struct DirEntryWriter<'a> {
     page_cache: &'a PageCache,
     offset: usize,
+    /// Whether each new block ends with a `DirEntryTail`.
+    has_tail: bool,
     name_buf: Option<[u8; MAX_FNAME_LEN]>,
 }
 
 impl<'a> DirEntryWriter<'a> {
     /// Constructs a writer with the given page cache and offset.
-    pub(super) fn new(page_cache: &'a PageCache, from_offset: usize) -> Self {
+    ///
+    /// If `has_tail` is true, a `DirEntryTail` is reserved at the end of each new block.
+    pub(super) fn new(page_cache: &'a PageCache, from_offset: usize, has_tail: bool) -> Self {
         Self {
             page_cache,
             offset: from_offset,
+            has_tail,
             name_buf: None,
         }
     }
 
+    /// Returns the length of the usable space in each new block.
+    fn usable_block_len(&self) -> usize {
+        if self.has_tail {
+            BLOCK_SIZE - DIR_ENTRY_TAIL_LEN
+        } else {
+            BLOCK_SIZE
+        }
+    }
+
+    /// Writes a `DirEntryTail` at the end of the block if the blocks have tails.
+    fn write_tail(&self, block_offset: usize) -> Result<()> {
+        if self.has_tail {
+            let tail_offset = block_offset + BLOCK_SIZE - DIR_ENTRY_TAIL_LEN;
+            self.page_cache
+                .pages()
+                .write_val(tail_offset, &DirEntryTail::new())?;
+        }
+        Ok(())
+    }
+
     /// Writes a `DirEntry` at the current offset. The name is written after the header.
     pub fn write_entry(&mut self, header: &DirEntryHeader, name: &str) -> Result<()> {
         self.page_cache.pages().write_val(self.offset, header)?;
@@ -392,8 +516,9 @@ impl<'a> DirEntryWriter<'a> {
         debug_assert_eq!(self.offset, DirEntry::PARENT_OFFSET);
 
         let mut parent_header = DirEntryHeader::new(parent_ino, InodeType::Dir, 2);
-        parent_header.record_len = (BLOCK_SIZE - self.offset) as _;
-        self.write_entry(&parent_header, "..")
+        parent_header.record_len = (self.usable_block_len() - self.offset) as _;
+        self.write_entry(&parent_header, "..")?;
+        self.write_tail(0)
     }
 
     /// Appends a new `DirEntry` starting from the current offset.
@@ -411,7 +536,19 @@ impl<'a> DirEntryWriter<'a> {
         debug_assert_eq!(header.name_len as usize, name_len);
         let name_bytes = name.as_bytes();
         let mut entry_item_with_enough_gap = None;
-        for entry_item in DirEntryReader::new(self.page_cache, self.offset).iter() {
+        for entry_item in DirEntryReader::new(self.page_cache, self.offset).iter_with_unused() {
+            if entry_item.is_unused() {
+                if entry_item_with_enough_gap.is_none()
+                    && entry_item.record_len() >= header.record_len as usize
+                {
+                    entry_item_with_enough_gap = Some(entry_item);
+                    if !check_existence {
+                        break;
+                    }
+                }
+                continue;
+            }
+
             if entry_item_with_enough_gap.is_none()
                 && entry_item.gap_len() >= header.record_len as usize
             {
@@ -443,6 +580,13 @@ impl<'a> DirEntryWriter<'a> {
         mut header: DirEntryHeader,
         name: &str,
     ) -> Result<()> {
+        if entry_with_enough_gap.is_unused() {
+            // Write in the place of the unused entry.
+            header.record_len = entry_with_enough_gap.record_len() as u16;
+            self.offset = entry_with_enough_gap.offset;
+            return self.write_entry(&header, name);
+        }
+
         // Write in the gap between existing entries.
         header.record_len = entry_with_enough_gap.gap_len() as u16;
         entry_with_enough_gap.set_record_len(entry_with_enough_gap.actual_len());
@@ -457,18 +601,23 @@ impl<'a> DirEntryWriter<'a> {
         let old_size = self.page_cache.pages().size();
         let new_size = old_size + BLOCK_SIZE;
         self.page_cache.resize(new_size)?;
-        header.record_len = BLOCK_SIZE as _;
+        header.record_len = self.usable_block_len() as _;
 
         self.offset = old_size;
-        self.write_entry(&header, name)
+        self.write_entry(&header, name)?;
+        self.write_tail(old_size)
     }
 
     /// Removes and returns an existing `DirEntry` indicated by `name`.
+    ///
+    /// The removed entry is merged into the previous entry in the same block. If it is the
+    /// first entry in the block, it is marked as unused instead.
     pub fn remove_entry(&mut self, name: &str) -> Result<DirEntryItem> {
         let mut pre_entry_item = None;
         let name_len = name.len();
         let name_bytes = name.as_bytes();
-        let mut iter = DirEntryReader::new(self.page_cache, DirEntry::PARENT_OFFSET).iter();
+        let block_offset = self.offset.align_down(BLOCK_SIZE);
+        let mut iter = DirEntryReader::new(self.page_cache, block_offset).iter();
         let Some(target_entry_item) = iter.find(|entry| {
             if entry.offset < self.offset {
                 pre_entry_item = Some(*entry);
@@ -482,21 +631,23 @@ impl<'a> DirEntryWriter<'a> {
         };
         let is_last_entry = iter.next().is_none();
 
-        let mut pre_entry_item = pre_entry_item.unwrap();
-        let pre_offset = pre_entry_item.offset;
-        if is_last_entry {
-            // Shrink the size.
-            let new_size = pre_offset.align_up(BLOCK_SIZE);
-            self.page_cache.resize(new_size)?;
-            pre_entry_item.set_record_len(new_size - pre_offset);
-            self.offset = pre_offset;
-            self.write_header_only(&pre_entry_item.header)?;
-        } else {
+        if let Some(mut pre_entry_item) = pre_entry_item {
             // Update the previous entry.
-            pre_entry_item
-                .set_record_len(pre_entry_item.record_len() + target_entry_item.record_len());
+            let pre_offset = pre_entry_item.offset;
+            pre_entry_item.set_record_len(
+                target_entry_item.offset + target_entry_item.record_len() - pre_offset,
+            );
             self.offset = pre_offset;
             self.write_header_only(&pre_entry_item.header)?;
+        } else if is_last_entry && block_offset > 0 {
+            // Shrink the size, since the block contains no entries anymore.
+            self.page_cache.resize(block_offset)?;
+        } else {
+            // Mark the entry as unused.
+            let mut header = target_entry_item.header;
+            header.ino = 0;
+            self.offset = target_entry_item.offset;
+            self.write_header_only(&header)?;
         }
 
         Ok(target_entry_item)
@@ -530,6 +681,33 @@ impl<'a> DirEntryWriter<'a> {
         Ok(())
     }
 
+    /// Turns the nodes of the hash tree index into plain blocks, so that the
+    /// directory can be modified linearly.
+    ///
+    /// The ".." entry in the root swallows the index, and the fake entries that cover
+    /// the other nodes become unused entries.
+    pub fn clear_index(&mut self) -> Result<()> {
+        let pages = self.page_cache.pages();
+        for block_offset in (0..pages.size()).step_by(BLOCK_SIZE) {
+            let header_offset = if block_offset == 0 {
+                DirEntry::PARENT_OFFSET
+            } else {
+                block_offset
+            };
+            let mut header = pages.read_val::<DirEntryHeader>(header_offset)?;
+            let is_node =
+                block_offset == 0 || (header.ino == 0 && header.record_len as usize == BLOCK_SIZE);
+            if !is_node {
+                continue;
+            }
+
+            header.record_len = (self.usable_block_len() - (header_offset - block_offset)) as _;
+            pages.write_val(header_offset, &header)?;
+            self.write_tail(block_offset)?;
+        }
+        Ok(())
+    }
+
     /// Reads the name of the entry from the page cache to the inner buffer.
     fn read_name(&mut self, item: &DirEntryItem) -> Result<&[u8]> {
         if self.name_buf.is_none() {
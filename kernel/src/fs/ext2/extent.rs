@@ -0,0 +1,719 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The extent tree of Ext4.
+//!
+//! An extent maps a range of consecutive logical blocks of a file to a range of
+//! consecutive blocks on the device. The extents of an inode are organized as a
+//! B+ tree, whose root lives in the `block_ptrs` of the inode and whose other
+//! nodes occupy one block each.
+//!
+//! Most files have only a few extents, so `ExtentTree` keeps all the extents of an
+//! inode in memory. The nodes on the device are read when the inode is loaded, and
+//! are rebuilt by [`ExtentTree::flush`] after the extents are changed.
+
+use core::mem::size_of;
+
+use ostd::{const_assert, mm::io_util::HasVmReaderWriter};
+
+use super::{
+    block_ptr::{BlockPtrs, Ext2Bid},
+    csum::crc32c,
+    fs::Ext2,
+    prelude::*,
+};
+
+/// An in-memory extent tree.
+#[derive(Debug)]
+pub(super) struct ExtentTree {
+    /// The extents sorted by the logical blocks, which never overlap.
+    extents: Vec<Extent>,
+    /// The device blocks of the nodes except the root.
+    node_bids: Vec<Ext2Bid>,
+    /// The number of device blocks occupied at the last flush, including the nodes.
+    flushed_nblocks: u64,
+    is_dirty: bool,
+}
+
+/// The mapping of the logical blocks at the start of a range.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(super) enum ExtentMapping {
+    /// The blocks are mapped to the device range.
+    Mapped(Range<Ext2Bid>),
+    /// The blocks are mapped to the device range, which has not been initialized
+    /// and must be read as zeros.
+    Unwritten(Range<Ext2Bid>),
+    /// The given number of blocks are not allocated.
+    Hole(Ext2Bid),
+}
+
+impl ExtentMapping {
+    /// Returns the number of logical blocks in the mapping.
+    pub fn len(&self) -> Ext2Bid {
+        match self {
+            Self::Mapped(range) | Self::Unwritten(range) => range.len() as Ext2Bid,
+            Self::Hole(len) => *len,
+        }
+    }
+}
+
+impl ExtentTree {
+    /// Creates an empty extent tree.
+    pub fn new() -> Self {
+        Self {
+            extents: Vec::new(),
+            node_bids: Vec::new(),
+            flushed_nblocks: 0,
+            is_dirty: false,
+        }
+    }
+
+    /// Returns the root of an empty extent tree.
+    pub fn empty_root() -> BlockPtrs {
+        BlockPtrs::from_bytes(&encode_node(&[], ROOT_SIZE, 0, None))
+    }
+
+    /// Loads the extent tree whose root is `root`, reading the other nodes with `read_block`.
+    ///
+    /// If `csum_seed` is not `None`, the checksums of the nodes are verified.
+    pub fn load(
+        root: &BlockPtrs,
+        read_block: &dyn Fn(Ext2Bid, &mut [u8]) -> Result<()>,
+        csum_seed: Option<u32>,
+    ) -> Result<Self> {
+        let depth = RawExtentHeader::from_bytes(root.as_bytes()).depth;
+        if depth > MAX_DEPTH {
+            return_errno_with_message!(Errno::EUCLEAN, "the extent tree is too deep");
+        }
+
+        let mut tree = Self::new();
+        tree.load_node(root.as_bytes(), depth, read_block, csum_seed)?;
+        tree.flushed_nblocks = tree.nblocks();
+        Ok(tree)
+    }
+
+    fn load_node(
+        &mut self,
+        node: &[u8],
+        depth: u16,
+        read_block: &dyn Fn(Ext2Bid, &mut [u8]) -> Result<()>,
+        csum_seed: Option<u32>,
+    ) -> Result<()> {
+        let header = RawExtentHeader::from_bytes(node);
+        if header.magic != EXTENT_MAGIC
+            || header.depth != depth
+            || header.max as usize > max_entries(node.len())
+            || header.entries > header.max
+        {
+            return_errno_with_message!(Errno::EUCLEAN, "corrupted extent tree node");
+        }
+
+        let entries = node[HEADER_SIZE..]
+            .chunks_exact(ENTRY_SIZE)
+            .take(header.entries as usize);
+        if depth == 0 {
+            for raw_extent in entries {
+                let extent = Extent::try_from(RawExtent::from_bytes(raw_extent))?;
+                if self
+                    .extents
+                    .last()
+                    .is_some_and(|last| last.end() > extent.block)
+                {
+                    return_errno_with_message!(Errno::EUCLEAN, "overlapped extents");
+                }
+                self.extents.push(extent);
+            }
+            return Ok(());
+        }
+
+        for raw_index in entries {
+            let index = RawExtentIndex::from_bytes(raw_index);
+            if index.leaf_hi != 0 {
+                return_errno_with_message!(Errno::EFBIG, "the 64-bit block is not supported");
+            }
+            if index.leaf_lo == 0 {
+                return_errno_with_message!(Errno::EUCLEAN, "invalid extent tree node");
+            }
+
+            let mut block = vec![0u8; BLOCK_SIZE];
+            read_block(index.leaf_lo, &mut block)?;
+            if let Some(csum_seed) = csum_seed {
+                let max =
+                    (RawExtentHeader::from_bytes(&block).max as usize).min(max_entries(BLOCK_SIZE));
+                let tail_offset = HEADER_SIZE + max * ENTRY_SIZE;
+                let recorded = u32::from_le_bytes(
+                    block[tail_offset..tail_offset + TAIL_SIZE]
+                        .try_into()
+                        .unwrap(),
+                );
+                if crc32c(csum_seed, &block[..tail_offset]) != recorded {
+                    return_errno_with_message!(Errno::EBADMSG, "extent block checksum mismatch");
+                }
+            }
+
+            self.node_bids.push(index.leaf_lo);
+            self.load_node(&block, depth - 1, read_block, csum_seed)?;
+        }
+        Ok(())
+    }
+
+    /// Returns whether the extents have been changed since the last flush.
+    pub fn is_dirty(&self) -> bool {
+        self.is_dirty
+    }
+
+    /// Returns the mapping of the logical blocks at the start of `range`.
+    ///
+    /// # Panics
+    ///
+    /// If the `range` is empty, this method will panic.
+    pub fn lookup(&self, range: Range<Ext2Bid>) -> ExtentMapping {
+        assert!(!range.is_empty());
+
+        let range_len = range.len() as Ext2Bid;
+        let idx = self.extents.partition_point(|e| e.end() <= range.start);
+        match self.extents.get(idx) {
+            Some(extent) if extent.block <= range.start => {
+                let offset = range.start - extent.block;
+                let start = extent.start + offset;
+                let len = (extent.len - offset).min(range_len);
+                if extent.is_unwritten {
+                    ExtentMapping::Unwritten(start..start + len)
+                } else {
+                    ExtentMapping::Mapped(start..start + len)
+                }
+            }
+            Some(extent) => ExtentMapping::Hole((extent.block - range.start).min(range_len)),
+            None => ExtentMapping::Hole(range_len),
+        }
+    }
+
+    /// Returns the ranges of the logical blocks inside `range` that are not allocated.
+    pub fn holes(&self, range: Range<Ext2Bid>) -> Vec<Range<Ext2Bid>> {
+        let mut holes = Vec::new();
+        let mut current = range.start;
+        while current < range.end {
+            let mapping = self.lookup(current..range.end);
+            if let ExtentMapping::Hole(len) = mapping {
+                holes.push(current..current + len);
+            }
+            current += mapping.len();
+        }
+        holes
+    }
+
+    /// Maps the logical blocks starting from `block` to the allocated `device_range`.
+    ///
+    /// # Panics
+    ///
+    /// If some of the logical blocks have been mapped, this method will panic.
+    pub fn insert(&mut self, mut block: Ext2Bid, device_range: Range<Ext2Bid>) {
+        let mut start = device_range.start;
+        while start < device_range.end {
+            let len = (device_range.end - start).min(MAX_INIT_LEN);
+            self.insert_extent(Extent {
+                block,
+                len,
+                start,
+                is_unwritten: false,
+            });
+            block += len;
+            start += len;
+        }
+        self.is_dirty = true;
+    }
+
+    fn insert_extent(&mut self, extent: Extent) {
+        let idx = self.extents.partition_point(|e| e.block < extent.block);
+        assert!(idx == 0 || self.extents[idx - 1].end() <= extent.block);
+        assert!(idx == self.extents.len() || extent.end() <= self.extents[idx].block);
+
+        self.extents.insert(idx, extent);
+        self.merge_around(idx);
+    }
+
+    /// Marks the unwritten logical blocks in `range` as written.
+    ///
+    /// # Panics
+    ///
+    /// If the `range` is not inside an unwritten extent, this method will panic.
+    pub fn mark_written(&mut self, range: Range<Ext2Bid>) {
+        let idx = self.extents.partition_point(|e| e.end() <= range.start);
+        let extent = self.extents[idx];
+        assert!(extent.is_unwritten);
+        assert!(extent.block <= range.start && range.end <= extent.end());
+
+        let offset = range.start - extent.block;
+        let mut pieces = Vec::with_capacity(3);
+        if offset > 0 {
+            pieces.push(Extent {
+                len: offset,
+                ..extent
+            });
+        }
+        pieces.push(Extent {
+            block: range.start,
+            len: range.len() as Ext2Bid,
+            start: extent.start + offset,
+            is_unwritten: false,
+        });
+        if range.end < extent.end() {
+            pieces.push(Extent {
+                block: range.end,
+                len: extent.end() - range.end,
+                start: extent.start + (range.end - extent.block),
+                is_unwritten: true,
+            });
+        }
+        self.extents.splice(idx..idx + 1, pieces);
+        self.merge_around(idx + (offset > 0) as usize);
+        self.is_dirty = true;
+    }
+
+    /// Merges the extent at `idx` with its neighbors if possible.
+    fn merge_around(&mut self, idx: usize) {
+        if idx + 1 < self.extents.len() && self.extents[idx].can_append(&self.extents[idx + 1]) {
+            self.extents[idx].len += self.extents[idx + 1].len;
+            self.extents.remove(idx + 1);
+        }
+        if idx > 0 && self.extents[idx - 1].can_append(&self.extents[idx]) {
+            self.extents[idx - 1].len += self.extents[idx].len;
+            self.extents.remove(idx);
+        }
+    }
+
+    /// Unmaps the logical blocks in `range`, returning the device ranges that
+    /// should be freed.
+    pub fn remove(&mut self, range: Range<Ext2Bid>) -> Vec<Range<Ext2Bid>> {
+        let mut freed_ranges = Vec::new();
+        let mut idx = self.extents.partition_point(|e| e.end() <= range.start);
+        while idx < self.extents.len() && self.extents[idx].block < range.end {
+            let extent = self.extents[idx];
+            let start = extent.block.max(range.start);
+            let end = extent.end().min(range.end);
+            freed_ranges
+                .push(extent.start + (start - extent.block)..extent.start + (end - extent.block));
+
+            // The blocks outside `range` are kept.
+            let mut pieces = Vec::with_capacity(2);
+            if extent.block < start {
+                pieces.push(Extent {
+                    len: start - extent.block,
+                    ..extent
+                });
+            }
+            if end < extent.end() {
+                pieces.push(Extent {
+                    block: end,
+                    len: extent.end() - end,
+                    start: extent.start + (end - extent.block),
+                    ..extent
+                });
+            }
+            let nr_pieces = pieces.len();
+            self.extents.splice(idx..idx + 1, pieces);
+            idx += nr_pieces;
+            self.is_dirty = true;
+        }
+        freed_ranges
+    }
+
+    /// Unmaps all the logical blocks from `nblocks`, returning the device ranges
+    /// that should be freed.
+    pub fn truncate(&mut self, nblocks: Ext2Bid) -> Vec<Range<Ext2Bid>> {
+        let mut freed_ranges = Vec::new();
+        while let Some(last) = self.extents.last_mut() {
+            if last.end() <= nblocks {
+                break;
+            }
+
+            if last.block >= nblocks {
+                freed_ranges.push(last.start..last.start + last.len);
+                self.extents.pop();
+            } else {
+                let new_len = nblocks - last.block;
+                freed_ranges.push(last.start + new_len..last.start + last.len);
+                last.len = new_len;
+            }
+            self.is_dirty = true;
+        }
+        freed_ranges
+    }
+
+    /// Returns the last device block mapped by the extents.
+    pub fn last_device_bid(&self) -> Option<Ext2Bid> {
+        self.extents
+            .last()
+            .map(|extent| extent.start + extent.len - 1)
+    }
+
+    /// Returns the number of device blocks occupied by the data and the nodes.
+    fn nblocks(&self) -> u64 {
+        let data_nblocks: u64 = self.extents.iter().map(|e| e.len as u64).sum();
+        data_nblocks + self.node_bids.len() as u64
+    }
+
+    /// Returns the number of the nodes written by [`Self::flush`].
+    pub fn nr_nodes(&self) -> usize {
+        self.level_nnodes().iter().sum()
+    }
+
+    /// Returns the number of nodes at each level, from the leaves to the children
+    /// of the root.
+    fn level_nnodes(&self) -> Vec<usize> {
+        let mut level_nnodes = Vec::new();
+        let mut nentries = self.extents.len();
+        while nentries > max_entries(ROOT_SIZE) {
+            let nnodes = nentries.div_ceil(max_entries(BLOCK_SIZE));
+            level_nnodes.push(nnodes);
+            nentries = nnodes;
+        }
+        level_nnodes
+    }
+
+    /// Writes the extents back to the nodes, and returns the new root.
+    ///
+    /// The nodes are rebuilt from scratch, reusing the blocks of the old nodes and
+    /// allocating new blocks from the `block_group_idx` group first. The second
+    /// return value is the change of the number of device blocks occupied by the
+    /// data and the nodes since the last flush.
+    pub fn flush(
+        &mut self,
+        fs: &Ext2,
+        csum_seed: Option<u32>,
+        block_group_idx: usize,
+    ) -> Result<(BlockPtrs, i64)> {
+        let level_nnodes = self.level_nnodes();
+        let nnodes: usize = level_nnodes.iter().sum();
+
+        while self.node_bids.len() < nnodes {
+            let count = (nnodes - self.node_bids.len()) as Ext2Bid;
+            let range = fs
+                .alloc_blocks(block_group_idx, count)
+                .ok_or_else(|| Error::with_message(Errno::ENOSPC, "no space for extents"))?;
+            self.node_bids.extend(range);
+        }
+        for bid in self.node_bids.drain(nnodes..) {
+            fs.free_blocks(bid..bid + 1)?;
+        }
+
+        let mut entries: Vec<(Ext2Bid, [u8; ENTRY_SIZE])> = self
+            .extents
+            .iter()
+            .map(|extent| (extent.block, to_entry(&RawExtent::from(extent))))
+            .collect();
+        let mut node_bids = self.node_bids.iter();
+        for (depth, &nnodes) in level_nnodes.iter().enumerate() {
+            let mut parent_entries = Vec::with_capacity(nnodes);
+            for node_entries in entries.chunks(max_entries(BLOCK_SIZE)) {
+                let bid = *node_bids.next().unwrap();
+                let node = encode_node(node_entries, BLOCK_SIZE, depth as u16, csum_seed);
+                let bio_segment = BioSegment::alloc(1, BioDirection::ToDevice);
+                bio_segment
+                    .writer()
+                    .unwrap()
+                    .write(&mut VmReader::from(node.as_slice()));
+                fs.write_metadata_blocks(bid, bio_segment)?;
+
+                let first_block = node_entries[0].0;
+                let index = RawExtentIndex {
+                    block: first_block,
+                    leaf_lo: bid,
+                    leaf_hi: 0,
+                    unused: 0,
+                };
+                parent_entries.push((first_block, to_entry(&index)));
+            }
+            entries = parent_entries;
+        }
+        let root = BlockPtrs::from_bytes(&encode_node(
+            &entries,
+            ROOT_SIZE,
+            level_nnodes.len() as u16,
+            None,
+        ));
+
+        let nblocks = self.nblocks();
+        let delta = nblocks as i64 - self.flushed_nblocks as i64;
+        self.flushed_nblocks = nblocks;
+        self.is_dirty = false;
+        Ok((root, delta))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Extent {
+    /// The first logical block.
+    block: Ext2Bid,
+    /// The number of blocks.
+    len: Ext2Bid,
+    /// The first device block.
+    start: Ext2Bid,
+    /// Whether the blocks are allocated but not initialized.
+    is_unwritten: bool,
+}
+
+impl Extent {
+    fn end(&self) -> Ext2Bid {
+        self.block + self.len
+    }
+
+    fn max_len(&self) -> Ext2Bid {
+        if self.is_unwritten {
+            MAX_UNWRITTEN_LEN
+        } else {
+            MAX_INIT_LEN
+        }
+    }
+
+    /// Returns whether `next` is right after `self` both logically and physically.
+    fn can_append(&self, next: &Extent) -> bool {
+        self.end() == next.block
+            && self.start + self.len == next.start
+            && self.is_unwritten == next.is_unwritten
+            && self.len + next.len <= self.max_len()
+    }
+}
+
+impl TryFrom<RawExtent> for Extent {
+    type Error = crate::error::Error;
+
+    fn try_from(raw_extent: RawExtent) -> Result<Self> {
+        if raw_extent.start_hi != 0 {
+            return_errno_with_message!(Errno::EFBIG, "the 64-bit block is not supported");
+        }
+
+        let (len, is_unwritten) = if raw_extent.len as Ext2Bid > MAX_INIT_LEN {
+            (raw_extent.len as Ext2Bid - MAX_INIT_LEN, true)
+        } else {
+            (raw_extent.len as Ext2Bid, false)
+        };
+        if len == 0
+            || raw_extent.start_lo == 0
+            || raw_extent.block.checked_add(len).is_none()
+            || raw_extent.start_lo.checked_add(len).is_none()
+        {
+            return_errno_with_message!(Errno::EUCLEAN, "invalid extent");
+        }
+
+        Ok(Self {
+            block: raw_extent.block,
+            len,
+            start: raw_extent.start_lo,
+            is_unwritten,
+        })
+    }
+}
+
+impl From<&Extent> for RawExtent {
+    fn from(extent: &Extent) -> Self {
+        let len = if extent.is_unwritten {
+            extent.len + MAX_INIT_LEN
+        } else {
+            extent.len
+        };
+        Self {
+            block: extent.block,
+            len: len as u16,
+            start_hi: 0,
+            start_lo: extent.start,
+        }
+    }
+}
+
+/// Encodes a node that consists of a header and the `entries`.
+///
+/// If `csum_seed` is not `None`, the checksum is appended to the entries.
+fn encode_node(
+    entries: &[(Ext2Bid, [u8; ENTRY_SIZE])],
+    node_size: usize,
+    depth: u16,
+    csum_seed: Option<u32>,
+) -> Vec<u8> {
+    let max = max_entries(node_size);
+    debug_assert!(entries.len() <= max);
+
+    let mut node = vec![0u8; node_size];
+    let header = RawExtentHeader {
+        magic: EXTENT_MAGIC,
+        entries: entries.len() as u16,
+        max: max as u16,
+        depth,
+        generation: 0,
+    };
+    node[..HEADER_SIZE].copy_from_slice(header.as_bytes());
+    for (i, (_, entry)) in entries.iter().enumerate() {
+        let offset = HEADER_SIZE + i * ENTRY_SIZE;
+        node[offset..offset + ENTRY_SIZE].copy_from_slice(entry);
+    }
+
+    if let Some(csum_seed) = csum_seed {
+        let tail_offset = HEADER_SIZE + max * ENTRY_SIZE;
+        let checksum = crc32c(csum_seed, &node[..tail_offset]);
+        node[tail_offset..tail_offset + TAIL_SIZE].copy_from_slice(&checksum.to_le_bytes());
+    }
+    node
+}
+
+fn to_entry<T: Pod>(raw_entry: &T) -> [u8; ENTRY_SIZE] {
+    raw_entry.as_bytes().try_into().unwrap()
+}
+
+/// Returns the maximum number of entries in a node of `node_size` bytes.
+///
+/// A node in a block reserves the room for the checksum, which occupies
+/// the remainder of the block anyway.
+const fn max_entries(node_size: usize) -> usize {
+    (node_size - HEADER_SIZE) / ENTRY_SIZE
+}
+
+const EXTENT_MAGIC: u16 = 0xF30A;
+
+/// The maximum depth of the extent tree.
+const MAX_DEPTH: u16 = 5;
+
+/// The maximum number of blocks in an initialized extent.
+const MAX_INIT_LEN: Ext2Bid = 1 << 15;
+
+/// The maximum number of blocks in an unwritten extent.
+const MAX_UNWRITTEN_LEN: Ext2Bid = MAX_INIT_LEN - 1;
+
+/// The size of the root node, which is stored in the `block_ptrs` of the inode.
+const ROOT_SIZE: usize = size_of::<BlockPtrs>();
+
+const HEADER_SIZE: usize = size_of::<RawExtentHeader>();
+const ENTRY_SIZE: usize = size_of::<RawExtent>();
+const TAIL_SIZE: usize = size_of::<u32>();
+
+const_assert!(size_of::<RawExtentIndex>() == ENTRY_SIZE);
+const_assert!(HEADER_SIZE + max_entries(BLOCK_SIZE) * ENTRY_SIZE + TAIL_SIZE <= BLOCK_SIZE);
+
+/// The header of each node.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod)]
+struct RawExtentHeader {
+    magic: u16,
+    /// Number of valid entries.
+    entries: u16,
+    /// Capacity of entries.
+    max: u16,
+    /// Depth of the node, zero for the leaves.
+    depth: u16,
+    generation: u32,
+}
+
+/// The entry in a leaf.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod)]
+struct RawExtent {
+    /// First logical block.
+    block: u32,
+    /// Number of blocks, which is larger than `MAX_INIT_LEN` if it is unwritten.
+    len: u16,
+    /// High 16 bits of the first device block.
+    start_hi: u16,
+    /// Low 32 bits of the first device block.
+    start_lo: u32,
+}
+
+/// The entry in an internal node.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod)]
+struct RawExtentIndex {
+    /// The first logical block covered by the child.
+    block: u32,
+    /// Low 32 bits of the device block of the child.
+    leaf_lo: u32,
+    /// High 16 bits of the device block of the child.
+    leaf_hi: u16,
+    unused: u16,
+}
+
+#[cfg(ktest)]
+mod test {
+    use ostd::prelude::*;
+
+    use super::*;
+
+    #[ktest]
+    fn insert_and_lookup() {
+        let mut tree = ExtentTree::new();
+        tree.insert(0, 100..104);
+        tree.insert(8, 200..202);
+        tree.insert(4, 104..106);
+
+        assert_eq!(tree.extents.len(), 2);
+        assert_eq!(tree.lookup(2..10), ExtentMapping::Mapped(102..106));
+        assert_eq!(tree.lookup(6..10), ExtentMapping::Hole(2));
+        assert_eq!(tree.lookup(9..20), ExtentMapping::Mapped(201..202));
+        assert_eq!(tree.lookup(10..20), ExtentMapping::Hole(10));
+        assert_eq!(tree.holes(0..12), vec![6..8, 10..12]);
+    }
+
+    #[ktest]
+    fn mark_written() {
+        let mut tree = ExtentTree::new();
+        tree.extents.push(Extent {
+            block: 0,
+            len: 8,
+            start: 100,
+            is_unwritten: true,
+        });
+
+        tree.mark_written(2..4);
+        assert_eq!(tree.lookup(0..8), ExtentMapping::Unwritten(100..102));
+        assert_eq!(tree.lookup(2..8), ExtentMapping::Mapped(102..104));
+        assert_eq!(tree.lookup(4..8), ExtentMapping::Unwritten(104..108));
+
+        tree.mark_written(0..2);
+        assert_eq!(tree.lookup(0..8), ExtentMapping::Mapped(100..104));
+        assert_eq!(tree.extents.len(), 2);
+    }
+
+    #[ktest]
+    fn truncate() {
+        let mut tree = ExtentTree::new();
+        tree.insert(0, 100..104);
+        tree.insert(8, 200..204);
+
+        assert_eq!(tree.truncate(10), vec![202..204]);
+        assert_eq!(tree.truncate(2), vec![200..202, 102..104]);
+        assert_eq!(tree.last_device_bid(), Some(101));
+        assert!(tree.truncate(2).is_empty());
+    }
+
+    #[ktest]
+    fn remove() {
+        let mut tree = ExtentTree::new();
+        tree.insert(0, 100..104);
+        tree.insert(4, 104..108);
+        tree.insert(10, 200..204);
+
+        assert_eq!(tree.remove(2..6), vec![102..106]);
+        assert_eq!(tree.lookup(0..8), ExtentMapping::Mapped(100..102));
+        assert_eq!(tree.lookup(2..8), ExtentMapping::Hole(4));
+        assert_eq!(tree.lookup(6..8), ExtentMapping::Mapped(106..108));
+
+        assert_eq!(tree.remove(7..12), vec![107..108, 200..202]);
+        assert_eq!(tree.holes(0..14), vec![2..6, 7..12]);
+        assert!(tree.remove(2..6).is_empty());
+    }
+
+    #[ktest]
+    fn raw_extent_conversion() {
+        let extent = Extent {
+            block: 16,
+            len: 10,
+            start: 1000,
+            is_unwritten: true,
+        };
+        let raw_extent = RawExtent::from(&extent);
+        assert_eq!(raw_extent.len as Ext2Bid, 10 + MAX_INIT_LEN);
+        assert_eq!(Extent::try_from(raw_extent).unwrap(), extent);
+
+        let root = ExtentTree::empty_root();
+        let header = RawExtentHeader::from_bytes(root.as_bytes());
+        assert_eq!(header.magic, EXTENT_MAGIC);
+        assert_eq!(header.max, 4);
+        assert_eq!(header.depth, 0);
+    }
+}
@@ -2,24 +2,43 @@
 
 #![expect(dead_code)]
 
+use ostd::mm::io_util::HasVmReaderWriter;
+
 use super::{
-    block_group::{BlockGroup, RawGroupDescriptor},
+    block_group::BlockGroup,
     block_ptr::Ext2Bid,
-    inode::{FilePerm, Inode, InodeDesc, RawInode},
+    csum::crc32c,
+    extent::ExtentTree,
+    inode::{FilePerm, Inode, InodeDesc, RawInode, RawInodeExtra, RAW_INODE_SIZE},
+    journal::{Journal, JournalHandle},
     prelude::*,
-    super_block::{RawSuperBlock, SuperBlock, SUPER_BLOCK_OFFSET},
+    super_block::{ExtVersion, RawSuperBlock, SuperBlock, SUPER_BLOCK_OFFSET},
 };
-use crate::fs::{
-    registry::{FsProperties, FsType},
-    utils::FileSystem,
+use crate::{
+    fs::{
+        registry::{FsProperties, FsType},
+        utils::FileSystem,
+    },
+    thread::kernel_thread::ThreadOptions,
+    vm::reclaim::{self, Shrinker},
 };
 
 /// The root inode number.
 const ROOT_INO: u32 = 2;
 
+/// The minimal extra size of new inodes, which holds all the extra fields we know.
+const MIN_EXTRA_ISIZE: u16 = RawInodeExtra::SIZE as u16;
+
+/// The interval of the periodic commits of the journal, which is the default of Linux.
+const COMMIT_INTERVAL: Duration = Duration::from_secs(5);
+
 /// The Ext2 filesystem.
+///
+/// It can also mount an Ext4 filesystem, see [`Ext2::open_ext4`]. If the filesystem
+/// has a journal, the updates of the metadata are journaled.
 #[derive(Debug)]
 pub struct Ext2 {
+    version: ExtVersion,
     block_device: Arc<dyn BlockDevice>,
     super_block: RwMutex<Dirty<SuperBlock>>,
     block_groups: Vec<BlockGroup>,
@@ -27,41 +46,55 @@ pub struct Ext2 {
     blocks_per_group: Ext2Bid,
     inode_size: usize,
     block_size: usize,
+    csum_seed: Option<u32>,
     group_descriptors_segment: USegment,
+    journal: Option<Journal>,
     self_ref: Weak<Self>,
 }
 
 impl Ext2 {
     /// Opens and loads an Ext2 from the `block_device`.
     pub fn open(block_device: Arc<dyn BlockDevice>) -> Result<Arc<Self>> {
-        // Load the superblock
-        // TODO: if the main superblock is corrupted, should we load the backup?
-        let super_block = {
-            let raw_super_block = block_device.read_val::<RawSuperBlock>(SUPER_BLOCK_OFFSET)?;
-            SuperBlock::try_from(raw_super_block)?
-        };
-        assert_eq!(
-            super_block.block_size(),
-            BLOCK_SIZE,
-            "currently only support 4096-byte block size"
-        );
+        Self::open_with_version(block_device, ExtVersion::Ext2)
+    }
 
-        let group_descriptors_segment: USegment = {
-            let npages = ((super_block.block_groups_count() as usize)
-                * core::mem::size_of::<RawGroupDescriptor>())
-            .div_ceil(BLOCK_SIZE);
-            let segment = FrameAllocOptions::new()
-                .zeroed(false)
-                .alloc_segment(npages)?;
-            let bio_segment =
-                BioSegment::new_from_segment(segment.clone().into(), BioDirection::FromDevice);
-            match block_device.read_blocks(super_block.group_descriptors_bid(0), bio_segment)? {
-                BioStatus::Complete => (),
-                err_status => {
-                    return Err(Error::from(err_status));
+    /// Opens and loads an Ext4 from the `block_device`.
+    ///
+    /// If the filesystem was not cleanly unmounted, the journal is replayed first.
+    pub fn open_ext4(block_device: Arc<dyn BlockDevice>) -> Result<Arc<Self>> {
+        Self::open_with_version(block_device, ExtVersion::Ext4)
+    }
+
+    fn open_with_version(
+        block_device: Arc<dyn BlockDevice>,
+        version: ExtVersion,
+    ) -> Result<Arc<Self>> {
+        let (mut super_block, mut group_descriptors_segment) =
+            Self::load_super_block(block_device.as_ref(), version)?;
+
+        // Replay the journal before loading the block groups, since the metadata
+        // is inconsistent until then.
+        let journal = match super_block.journal_ino() {
+            Some(journal_ino) => {
+                let journal = Journal::load(
+                    block_device.clone(),
+                    journal_ino,
+                    &super_block,
+                    &group_descriptors_segment,
+                )?;
+                if journal.recover()? {
+                    (super_block, group_descriptors_segment) =
+                        Self::load_super_block(block_device.as_ref(), version)?;
+                    if super_block.journal_ino() != Some(journal_ino) {
+                        return_errno_with_message!(
+                            Errno::EUCLEAN,
+                            "the journal inode is changed by the journal"
+                        );
+                    }
                 }
+                Some(journal)
             }
-            segment.into()
+            None => None,
         };
 
         // Load the block groups information
@@ -84,25 +117,121 @@ impl Ext2 {
             Ok(block_groups)
         };
 
+        // The block groups are loaded inside `Arc::new_cyclic`, which cannot fail,
+        // so the error (e.g., a checksum mismatch) is carried out of the closure.
+        let mut load_result = Ok(());
         let ext2 = Arc::new_cyclic(|weak_ref| Self {
+            version,
             inodes_per_group: super_block.inodes_per_group(),
             blocks_per_group: super_block.blocks_per_group(),
             inode_size: super_block.inode_size(),
             block_size: super_block.block_size(),
+            csum_seed: super_block.csum_seed(),
             block_groups: load_block_groups(
                 weak_ref.clone(),
                 block_device.as_ref(),
                 &group_descriptors_segment,
             )
-            .unwrap(),
+            .unwrap_or_else(|err| {
+                load_result = Err(err);
+                Vec::new()
+            }),
             block_device,
             super_block: RwMutex::new(Dirty::new(super_block)),
             group_descriptors_segment,
+            journal,
             self_ref: weak_ref.clone(),
         });
+        load_result?;
+
+        // Linux does not keep the free counts in the superblock up to date in the
+        // journal, so they are stale after replaying it, and are recomputed here.
+        if ext2.journal.is_some() {
+            let free_blocks = ext2
+                .block_groups
+                .iter()
+                .map(|block_group| block_group.free_blocks_count())
+                .sum();
+            let free_inodes = ext2
+                .block_groups
+                .iter()
+                .map(|block_group| block_group.free_inodes_count())
+                .sum();
+            let mut super_block = ext2.super_block.write();
+            if super_block.free_blocks_count() != free_blocks
+                || super_block.free_inodes_count() != free_inodes
+            {
+                super_block.set_free_counts(free_blocks, free_inodes);
+            }
+        }
+
+        reclaim::register_shrinker(Arc::downgrade(&ext2) as Weak<dyn Shrinker>);
+        if ext2.journal.is_some() {
+            ext2.spawn_commit_thread();
+        }
         Ok(ext2)
     }
 
+    /// Spawns the thread that commits the journal periodically or on request,
+    /// which exits after the filesystem is dropped.
+    fn spawn_commit_thread(&self) {
+        let commit_request = self.journal.as_ref().unwrap().commit_request();
+        let weak_self = self.self_ref.clone();
+        let task_fn = move || loop {
+            commit_request.wait(&COMMIT_INTERVAL);
+            let Some(fs) = weak_self.upgrade() else {
+                return;
+            };
+            if let Err(err) = fs.commit_journal() {
+                warn!("failed to commit the journal: {:?}", err);
+            }
+        };
+        ThreadOptions::new(task_fn).spawn();
+    }
+
+    /// Loads the superblock and the group descriptors table.
+    fn load_super_block(
+        block_device: &dyn BlockDevice,
+        version: ExtVersion,
+    ) -> Result<(SuperBlock, USegment)> {
+        // Load the superblock
+        // TODO: if the main superblock is corrupted, should we load the backup?
+        let super_block = {
+            let raw_super_block = block_device.read_val::<RawSuperBlock>(SUPER_BLOCK_OFFSET)?;
+            SuperBlock::try_from(raw_super_block)?
+        };
+        super_block.check_features(version)?;
+        if super_block.block_size() != BLOCK_SIZE {
+            return_errno_with_message!(
+                Errno::EINVAL,
+                "currently only support 4096-byte block size"
+            );
+        }
+
+        let group_descriptors_segment: USegment = {
+            let npages = super_block.group_descriptors_blocks() as usize;
+            let segment = FrameAllocOptions::new()
+                .zeroed(false)
+                .alloc_segment(npages)?;
+            let bio_segment =
+                BioSegment::new_from_segment(segment.clone().into(), BioDirection::FromDevice);
+            match block_device.read_blocks(super_block.group_descriptors_bid(0), bio_segment)? {
+                BioStatus::Complete => (),
+                err_status => {
+                    return Err(Error::from(err_status));
+                }
+            }
+            segment.into()
+        };
+
+        Ok((super_block, group_descriptors_segment))
+    }
+
+    /// Returns the version of the on-disk format.
+    pub fn version(&self) -> ExtVersion {
+        self.version
+    }
+
     /// Returns the block device.
     pub fn block_device(&self) -> &dyn BlockDevice {
         self.block_device.as_ref()
@@ -128,6 +257,23 @@ impl Ext2 {
         self.blocks_per_group
     }
 
+    /// Returns the seed of the metadata checksums.
+    ///
+    /// It is `None` if the metadata checksums are disabled.
+    pub(super) fn csum_seed(&self) -> Option<u32> {
+        self.csum_seed
+    }
+
+    /// Returns the seed of the metadata checksums of the inode.
+    ///
+    /// It is `None` if the metadata checksums are disabled.
+    pub(super) fn inode_csum_seed(&self, ino: u32, generation: u32) -> Option<u32> {
+        self.csum_seed.map(|csum_seed| {
+            let csum_seed = crc32c(csum_seed, &ino.to_le_bytes());
+            crc32c(csum_seed, &generation.to_le_bytes())
+        })
+    }
+
     /// Returns the super block.
     pub fn super_block(&self) -> RwMutexReadGuard<Dirty<SuperBlock>> {
         self.super_block.read()
@@ -154,12 +300,41 @@ impl Ext2 {
     ) -> Result<Arc<Inode>> {
         let (block_group_idx, ino) =
             self.alloc_ino(dir_block_group_idx, inode_type == InodeType::Dir)?;
+        let block_group = &self.block_groups[block_group_idx];
+        let inode_idx = self.inode_idx(ino);
+        if let Err(e) = block_group.clear_raw_inode(inode_idx) {
+            self.free_inode(ino, inode_type == InodeType::Dir)?;
+            return Err(e);
+        }
+
         let inode = {
-            let inode_desc = InodeDesc::new(inode_type, file_perm);
-            Inode::new(ino, block_group_idx, inode_desc, self.self_ref.clone())
+            let mut inode_desc = InodeDesc::new(inode_type, file_perm, self.version);
+            let extent_tree = match self.version {
+                ExtVersion::Ext4 => {
+                    let extra_isize = (self.inode_size - RAW_INODE_SIZE)
+                        .min(self.super_block().want_extra_isize().max(MIN_EXTRA_ISIZE) as usize);
+                    inode_desc.set_extra_isize(extra_isize as u16);
+                    if matches!(
+                        inode_type,
+                        InodeType::File | InodeType::Dir | InodeType::SymLink
+                    ) {
+                        inode_desc.set_extent_mapped(ExtentTree::empty_root());
+                        Some(ExtentTree::new())
+                    } else {
+                        None
+                    }
+                }
+                ExtVersion::Ext2 => None,
+            };
+            Inode::new(
+                ino,
+                block_group_idx,
+                inode_desc,
+                extent_tree,
+                self.self_ref.clone(),
+            )
         };
-        let block_group = &self.block_groups[block_group_idx];
-        block_group.insert_cache(self.inode_idx(ino), inode.clone());
+        block_group.insert_cache(inode_idx, inode.clone());
         Ok(inode)
     }
 
@@ -204,19 +379,22 @@ impl Ext2 {
     pub(super) fn sync_inode(&self, ino: u32, inode: &InodeDesc) -> Result<()> {
         let (_, block_group) = self.block_group_of_ino(ino)?;
         let inode_idx = self.inode_idx(ino);
-        block_group.sync_raw_inode(inode_idx, &RawInode::from(inode));
-        Ok(())
+        block_group.sync_raw_inode(
+            inode_idx,
+            &RawInode::from(inode),
+            &RawInodeExtra::from(inode),
+        )
     }
 
-    /// Writes back the block group descriptor to the descriptors table.
+    /// Writes back the raw block group descriptor to the descriptors table.
     pub(super) fn sync_group_descriptor(
         &self,
         block_group_idx: usize,
-        raw_descriptor: &RawGroupDescriptor,
+        raw_descriptor: &[u8],
     ) -> Result<()> {
-        let offset = block_group_idx * core::mem::size_of::<RawGroupDescriptor>();
+        let offset = block_group_idx * raw_descriptor.len();
         self.group_descriptors_segment
-            .write_val(offset, raw_descriptor)?;
+            .write_bytes(offset, raw_descriptor)?;
         Ok(())
     }
 
@@ -299,11 +477,20 @@ impl Ext2 {
             current_range.start += range_in_group.len() as Ext2Bid
         }
 
+        if let Some(journal) = self.journal.as_ref() {
+            journal.forget(range);
+        }
         Ok(())
     }
 
     /// Reads contiguous blocks starting from the `bid` synchronously.
+    ///
+    /// The metadata blocks staged in the journal are read instead of those on the device.
     pub(super) fn read_blocks(&self, bid: Ext2Bid, bio_segment: BioSegment) -> Result<()> {
+        if let Some(journal) = self.journal.as_ref() {
+            return journal.read_blocks(bid, bio_segment);
+        }
+
         let status = self
             .block_device
             .read_blocks(Bid::new(bid as u64), bio_segment)?;
@@ -314,17 +501,31 @@ impl Ext2 {
     }
 
     /// Reads contiguous blocks starting from the `bid` asynchronously.
+    ///
+    /// The metadata blocks staged in the journal are read instead of those on the device.
     pub(super) fn read_blocks_async(
         &self,
         bid: Ext2Bid,
         bio_segment: BioSegment,
     ) -> Result<BioWaiter> {
+        if let Some(journal) = self.journal.as_ref() {
+            return journal.read_blocks_async(bid, bio_segment);
+        }
+
         let waiter = self
             .block_device
             .read_blocks_async(Bid::new(bid as u64), bio_segment)?;
         Ok(waiter)
     }
 
+    /// Reads a block into `buf` synchronously.
+    pub(super) fn read_block(&self, bid: Ext2Bid, buf: &mut [u8]) -> Result<()> {
+        let bio_segment = BioSegment::alloc(1, BioDirection::FromDevice);
+        self.read_blocks(bid, bio_segment.clone())?;
+        bio_segment.reader().unwrap().read(&mut VmWriter::from(buf));
+        Ok(())
+    }
+
     /// Writes contiguous blocks starting from the `bid` synchronously.
     pub(super) fn write_blocks(&self, bid: Ext2Bid, bio_segment: BioSegment) -> Result<()> {
         let status = self
@@ -348,8 +549,101 @@ impl Ext2 {
         Ok(waiter)
     }
 
+    /// Writes contiguous metadata blocks starting from the `bid` synchronously.
+    ///
+    /// If the filesystem has a journal, the blocks are staged in the running transaction,
+    /// and written to the device when the transaction is committed.
+    pub(super) fn write_metadata_blocks(
+        &self,
+        bid: Ext2Bid,
+        bio_segment: BioSegment,
+    ) -> Result<()> {
+        self.write_metadata_blocks_async(bid, bio_segment)?
+            .wait()
+            .ok_or_else(|| Error::with_message(Errno::EIO, "failed to write the metadata"))?;
+        Ok(())
+    }
+
+    /// Writes contiguous metadata blocks starting from the `bid` asynchronously.
+    ///
+    /// If the filesystem has a journal, the blocks are staged in the running transaction,
+    /// and written to the device when the transaction is committed.
+    pub(super) fn write_metadata_blocks_async(
+        &self,
+        bid: Ext2Bid,
+        bio_segment: BioSegment,
+    ) -> Result<BioWaiter> {
+        let Some(journal) = self.journal.as_ref() else {
+            return self.write_blocks_async(bid, bio_segment);
+        };
+
+        let mut reader = bio_segment.reader().unwrap();
+        let mut block = vec![0u8; BLOCK_SIZE];
+        for idx in 0..bio_segment.nblocks() {
+            reader.read(&mut VmWriter::from(block.as_mut_slice()));
+            journal.stage(bid + idx as Ext2Bid, &block)?;
+        }
+        Ok(BioWaiter::new())
+    }
+
+    /// Opens a handle of the journal for an update that stages at most `nblocks`
+    /// metadata blocks, or returns `None` if the filesystem has no journal.
+    ///
+    /// See [`Journal::start`] for the locks that must not be held.
+    pub(super) fn start_journal(&self, nblocks: usize) -> Option<JournalHandle<'_>> {
+        self.journal.as_ref().map(|journal| journal.start(nblocks))
+    }
+
+    /// Adds the inode `ino` to the ordered inodes of the journal, whose data are written
+    /// back before the metadata pointing to its new data blocks are committed.
+    pub(super) fn add_ordered_inode(&self, ino: u32) {
+        if let Some(journal) = self.journal.as_ref() {
+            journal.add_ordered_inode(ino);
+        }
+    }
+
+    /// Commits the running transaction of the journal, if any.
+    fn commit_journal(&self) -> Result<()> {
+        let Some(journal) = self.journal.as_ref() else {
+            return Ok(());
+        };
+        journal.commit(|ino| {
+            // An inode not in the cache has been written back when it is evicted.
+            let (_, block_group) = self.block_group_of_ino(ino)?;
+            match block_group.cached_inode(self.inode_idx(ino)) {
+                Some(inode) => inode.sync_data(),
+                None => Ok(()),
+            }
+        })
+    }
+
     /// Writes back the metadata to the block device.
+    ///
+    /// If the filesystem has a journal, the metadata is committed as a transaction,
+    /// together with the other metadata blocks staged since the last commit.
     pub fn sync_metadata(&self) -> Result<()> {
+        let Some(journal) = self.journal.as_ref() else {
+            return self.write_back_metadata();
+        };
+
+        // The superblock, the group descriptors and the bitmaps of the dirty groups.
+        let nblocks = {
+            let super_block = self.super_block.read();
+            let nr_dirty_groups = self
+                .block_groups
+                .iter()
+                .filter(|block_group| block_group.is_dirty())
+                .count();
+            1 + super_block.group_descriptors_blocks() as usize + nr_dirty_groups * 2
+        };
+        // The handle is closed before the commit, which waits for the open handles.
+        let handle = journal.start(nblocks);
+        self.write_back_metadata()?;
+        drop(handle);
+        self.commit_journal()
+    }
+
+    fn write_back_metadata(&self) -> Result<()> {
         // If the superblock is clean, the block groups must be clean.
         if !self.super_block.read().is_dirty() {
             return Ok(());
@@ -358,28 +652,35 @@ impl Ext2 {
         let mut super_block = self.super_block.write();
         // Writes back the metadata of block groups
         for block_group in &self.block_groups {
-            block_group.sync_metadata()?;
+            block_group.sync_metadata(&super_block)?;
         }
 
         // Writes back the main superblock and group descriptor table.
-        let mut bio_waiter = BioWaiter::new();
         let raw_super_block = RawSuperBlock::from((*super_block).deref());
-        bio_waiter.concat(
-            self.block_device
-                .write_bytes_async(SUPER_BLOCK_OFFSET, raw_super_block.as_bytes())?,
-        );
         let group_descriptors_bio_segment = BioSegment::new_from_segment(
             self.group_descriptors_segment.clone(),
             BioDirection::ToDevice,
         );
-        bio_waiter.concat(self.block_device.write_blocks_async(
-            super_block.group_descriptors_bid(0),
-            group_descriptors_bio_segment.clone(),
-        )?);
-        bio_waiter
-            .wait()
-            .ok_or_else(|| Error::with_message(Errno::EIO, "failed to sync main metadata"))?;
-        drop(bio_waiter);
+        if let Some(journal) = self.journal.as_ref() {
+            journal.stage_super_block(&raw_super_block)?;
+            self.write_metadata_blocks(
+                super_block.group_descriptors_bid(0).to_raw() as Ext2Bid,
+                group_descriptors_bio_segment.clone(),
+            )?;
+        } else {
+            let mut bio_waiter = BioWaiter::new();
+            bio_waiter.concat(
+                self.block_device
+                    .write_bytes_async(SUPER_BLOCK_OFFSET, raw_super_block.as_bytes())?,
+            );
+            bio_waiter.concat(self.block_device.write_blocks_async(
+                super_block.group_descriptors_bid(0),
+                group_descriptors_bio_segment.clone(),
+            )?);
+            bio_waiter
+                .wait()
+                .ok_or_else(|| Error::with_message(Errno::EIO, "failed to sync main metadata"))?;
+        }
 
         // Writes back the backups of superblock and group descriptor table.
         let mut raw_super_block_backup = raw_super_block;
@@ -387,6 +688,7 @@ impl Ext2 {
             if super_block.is_backup_group(idx as usize) {
                 let mut bio_waiter = BioWaiter::new();
                 raw_super_block_backup.block_group_idx = idx as u16;
+                raw_super_block_backup.update_checksum();
                 bio_waiter.concat(self.block_device.write_bytes_async(
                     super_block.bid(idx as usize).to_offset(),
                     raw_super_block_backup.as_bytes(),
@@ -406,6 +708,24 @@ impl Ext2 {
         Ok(())
     }
 
+    /// Writes back the raw inode of `ino` to the block device.
+    ///
+    /// If the filesystem has a journal, the running transaction is committed,
+    /// so the inode is durable after the block device is flushed. The data of the
+    /// inodes are written back before the metadata pointing to them are committed.
+    pub(super) fn flush_inode(&self, ino: u32) -> Result<()> {
+        let (_, block_group) = self.block_group_of_ino(ino)?;
+        {
+            // The raw inode shares a block with the other raw inodes.
+            let _handle = self.start_journal(1);
+            block_group.flush_raw_inode(self.inode_idx(ino))?;
+        }
+        if self.journal.is_some() {
+            self.sync_metadata()?;
+        }
+        Ok(())
+    }
+
     /// Writes back all the cached inodes to the block device.
     pub fn sync_all_inodes(&self) -> Result<()> {
         for block_group in &self.block_groups {
@@ -439,6 +759,38 @@ impl Ext2 {
     }
 }
 
+/// Shrinks the caches of the indirect blocks of the cached inodes under memory pressure.
+impl Shrinker for Ext2 {
+    fn count_objects(&self) -> usize {
+        self.block_groups
+            .iter()
+            .filter_map(BlockGroup::try_cached_inodes)
+            .flatten()
+            .map(|inode| inode.num_indirect_blocks())
+            .sum()
+    }
+
+    fn scan_objects(&self, nr_to_scan: usize) -> usize {
+        let mut nr_left = nr_to_scan;
+        let mut nr_freed = 0;
+        for inodes in self
+            .block_groups
+            .iter()
+            .filter_map(BlockGroup::try_cached_inodes)
+        {
+            for inode in inodes {
+                if nr_left == 0 {
+                    return nr_freed;
+                }
+                let nr_scanned = inode.num_indirect_blocks().min(nr_left);
+                nr_freed += inode.shrink_indirect_blocks(nr_scanned);
+                nr_left -= nr_scanned;
+            }
+        }
+        nr_freed
+    }
+}
+
 pub(super) struct Ext2Type;
 
 impl FsType for Ext2Type {
@@ -463,3 +815,28 @@ impl FsType for Ext2Type {
         None
     }
 }
+
+pub(super) struct Ext4Type;
+
+impl FsType for Ext4Type {
+    fn name(&self) -> &'static str {
+        "ext4"
+    }
+
+    fn create(
+        &self,
+        _args: Option<CString>,
+        disk: Option<Arc<dyn BlockDevice>>,
+        _ctx: &Context,
+    ) -> Result<Arc<dyn FileSystem>> {
+        Ext2::open_ext4(disk.unwrap()).map(|fs| fs as _)
+    }
+
+    fn properties(&self) -> FsProperties {
+        FsProperties::NEED_DISK
+    }
+
+    fn sysnode(&self) -> Option<Arc<dyn aster_systree::SysBranchNode>> {
+        None
+    }
+}
@@ -0,0 +1,480 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The hash tree (htree) index of directories.
+//!
+//! An indexed directory keeps its entries in the leaf blocks as usual, but the
+//! first block, and possibly some other blocks, are the nodes of a tree that maps
+//! the hashes of the names to the leaf blocks. Each node hides itself from the old
+//! drivers behind a fake entry that covers the whole block.
+//!
+//! Only the lookup through the index is supported. Before a directory is modified,
+//! its index is dropped so that it becomes a plain linear directory.
+
+use core::mem::size_of;
+
+use super::{block_ptr::Ext2Bid, fs::Ext2, prelude::*, super_block::FeatureInCompatSet};
+
+/// Returns the logical blocks of the leaves that may contain `name`, in the order
+/// they should be searched.
+///
+/// Usually there is only one leaf. More leaves are returned if the names of the same
+/// hash span multiple leaves.
+pub(super) fn lookup_leaves(page_cache: &PageCache, name: &str, fs: &Ext2) -> Result<Vec<Ext2Bid>> {
+    let nblocks = (page_cache.pages().size() / BLOCK_SIZE) as Ext2Bid;
+    let read_block = |bid: Ext2Bid| -> Result<Vec<u8>> {
+        if bid >= nblocks {
+            return_errno_with_message!(Errno::EUCLEAN, "the htree block is out of bounds");
+        }
+        let mut block = vec![0u8; BLOCK_SIZE];
+        page_cache
+            .pages()
+            .read_bytes(bid as usize * BLOCK_SIZE, &mut block)?;
+        Ok(block)
+    };
+
+    let root = read_block(0)?;
+    let root_info = DxRootInfo::from_bytes(&root[DX_ROOT_INFO_OFFSET..]);
+    if root_info.reserved_zero != 0 || root_info.info_len as usize != size_of::<DxRootInfo>() {
+        return_errno_with_message!(Errno::EUCLEAN, "invalid htree root");
+    }
+
+    let (hash_info, max_levels, has_tail) = {
+        let super_block = fs.super_block();
+        let mut version = root_info.hash_version;
+        if super_block.is_unsigned_hash() && version <= DX_HASH_TEA {
+            version += DX_HASH_UNSIGNED_DELTA;
+        }
+        let max_levels = if super_block
+            .feature_incompat()
+            .contains(FeatureInCompatSet::LARGEDIR)
+        {
+            3
+        } else {
+            2
+        };
+        (
+            HashInfo {
+                version,
+                seed: super_block.hash_seed(),
+            },
+            max_levels,
+            fs.csum_seed().is_some(),
+        )
+    };
+    if root_info.indirect_levels >= max_levels {
+        return_errno_with_message!(Errno::EUCLEAN, "the htree is too deep");
+    }
+    let (hash, _) = hash_info.hash(name.as_bytes())?;
+
+    // Descends from the root to the leaf, recording the path.
+    let mut path: Vec<DxFrame> = Vec::with_capacity(root_info.indirect_levels as usize + 1);
+    let mut frame = DxFrame::new(root, DX_ROOT_ENTRIES_OFFSET, has_tail)?;
+    loop {
+        frame.search(hash);
+        let child = frame.child();
+        path.push(frame);
+        if path.len() > root_info.indirect_levels as usize {
+            break;
+        }
+        frame = DxFrame::new(read_block(child)?, DX_NODE_ENTRIES_OFFSET, has_tail)?;
+    }
+
+    let mut leaves = vec![path.last().unwrap().child()];
+    // Continues with the next leaves if they hold the names of the same hash.
+    loop {
+        let Some(level) = path.iter().rposition(|frame| frame.at + 1 < frame.count) else {
+            break;
+        };
+        path[level].at += 1;
+        if path[level].hash(path[level].at) & !1 != hash {
+            break;
+        }
+
+        path.truncate(level + 1);
+        while path.len() <= root_info.indirect_levels as usize {
+            let child = path.last().unwrap().child();
+            let mut frame = DxFrame::new(read_block(child)?, DX_NODE_ENTRIES_OFFSET, has_tail)?;
+            frame.at = 0;
+            path.push(frame);
+        }
+        leaves.push(path.last().unwrap().child());
+    }
+
+    if leaves.iter().any(|&leaf| leaf >= nblocks) {
+        return_errno_with_message!(Errno::EUCLEAN, "the htree leaf is out of bounds");
+    }
+    Ok(leaves)
+}
+
+/// A node on the lookup path.
+struct DxFrame {
+    block: Vec<u8>,
+    /// The offset of the entries in the block.
+    entries_offset: usize,
+    count: usize,
+    /// The index of the entry on the path.
+    at: usize,
+}
+
+impl DxFrame {
+    fn new(block: Vec<u8>, entries_offset: usize, has_tail: bool) -> Result<Self> {
+        let count_limit = DxCountLimit::from_bytes(&block[entries_offset..]);
+        let expected_limit = {
+            let tail_len = if has_tail { DX_TAIL_LEN } else { 0 };
+            (BLOCK_SIZE - entries_offset - tail_len) / DX_ENTRY_LEN
+        };
+        let count = count_limit.count as usize;
+        if count_limit.limit as usize != expected_limit || count == 0 || count > expected_limit {
+            return_errno_with_message!(Errno::EUCLEAN, "invalid htree node");
+        }
+
+        Ok(Self {
+            block,
+            entries_offset,
+            count,
+            at: 0,
+        })
+    }
+
+    /// Finds the last entry whose hash is not greater than `hash`.
+    fn search(&mut self, hash: u32) {
+        // The first entry has no hash, and covers all the hashes lower than the second one.
+        let mut low = 1;
+        let mut high = self.count - 1;
+        while low <= high {
+            let mid = (low + high) / 2;
+            if self.hash(mid) > hash {
+                high = mid - 1;
+            } else {
+                low = mid + 1;
+            }
+        }
+        self.at = low - 1;
+    }
+
+    fn hash(&self, idx: usize) -> u32 {
+        self.read_u32(self.entries_offset + idx * DX_ENTRY_LEN)
+    }
+
+    /// Returns the logical block of the child of the entry on the path.
+    fn child(&self) -> Ext2Bid {
+        let offset = self.entries_offset + self.at * DX_ENTRY_LEN + size_of::<u32>();
+        self.read_u32(offset) & DX_BLOCK_MASK
+    }
+
+    fn read_u32(&self, offset: usize) -> u32 {
+        u32::from_le_bytes(
+            self.block[offset..offset + size_of::<u32>()]
+                .try_into()
+                .unwrap(),
+        )
+    }
+}
+
+/// The information of the hash function.
+struct HashInfo {
+    version: u8,
+    seed: [u32; 4],
+}
+
+impl HashInfo {
+    /// Calculates the major and minor hashes of the name.
+    fn hash(&self, name: &[u8]) -> Result<(u32, u32)> {
+        let mut buf = if self.seed.iter().any(|&word| word != 0) {
+            self.seed
+        } else {
+            DEFAULT_SEED
+        };
+
+        let (hash, minor_hash) = match self.version {
+            DX_HASH_LEGACY => (dx_hack_hash(name, true), 0),
+            DX_HASH_LEGACY_UNSIGNED => (dx_hack_hash(name, false), 0),
+            DX_HASH_HALF_MD4 | DX_HASH_HALF_MD4_UNSIGNED => {
+                let is_signed = self.version == DX_HASH_HALF_MD4;
+                let mut input = [0u32; 8];
+                for (i, _) in name.iter().enumerate().step_by(32) {
+                    str_to_hash_buf(&name[i..], &mut input, is_signed);
+                    half_md4_transform(&mut buf, &input);
+                }
+                (buf[1], buf[2])
+            }
+            DX_HASH_TEA | DX_HASH_TEA_UNSIGNED => {
+                let is_signed = self.version == DX_HASH_TEA;
+                let mut input = [0u32; 4];
+                for (i, _) in name.iter().enumerate().step_by(16) {
+                    str_to_hash_buf(&name[i..], &mut input, is_signed);
+                    tea_transform(&mut buf, &input);
+                }
+                (buf[0], buf[1])
+            }
+            _ => return_errno_with_message!(Errno::EOPNOTSUPP, "unsupported htree hash"),
+        };
+
+        let mut hash = hash & !1;
+        if hash == HTREE_EOF_32BIT << 1 {
+            hash = (HTREE_EOF_32BIT - 1) << 1;
+        }
+        Ok((hash, minor_hash))
+    }
+}
+
+/// The legacy hash function.
+fn dx_hack_hash(name: &[u8], is_signed: bool) -> u32 {
+    let (mut hash0, mut hash1): (u32, u32) = (0x12a3fe2d, 0x37abe8f9);
+    for &byte in name {
+        let ch = if is_signed {
+            byte as i8 as i32 as u32
+        } else {
+            byte as u32
+        };
+        let mut hash = hash1.wrapping_add(hash0 ^ ch.wrapping_mul(7152373));
+        if hash & 0x8000_0000 != 0 {
+            hash = hash.wrapping_sub(0x7fff_ffff);
+        }
+        hash1 = hash0;
+        hash0 = hash;
+    }
+    hash0 << 1
+}
+
+/// Packs the string into the words of `buf`, padding with the length of the string.
+fn str_to_hash_buf(name: &[u8], buf: &mut [u32], is_signed: bool) {
+    let len = name.len() as u32;
+    let mut pad = len | (len << 8);
+    pad |= pad << 16;
+
+    let mut val = pad;
+    let mut words = buf.iter_mut();
+    for (i, &byte) in name.iter().take(buf.len() * 4).enumerate() {
+        let ch = if is_signed {
+            byte as i8 as i32 as u32
+        } else {
+            byte as u32
+        };
+        val = ch.wrapping_add(val << 8);
+        if i % 4 == 3 {
+            *words.next().unwrap() = val;
+            val = pad;
+        }
+    }
+    if let Some(word) = words.next() {
+        *word = val;
+    }
+    for word in words {
+        *word = pad;
+    }
+}
+
+/// The transform of the TEA (Tiny Encryption Algorithm).
+fn tea_transform(buf: &mut [u32; 4], input: &[u32; 4]) {
+    const DELTA: u32 = 0x9E3779B9;
+
+    let (mut b0, mut b1) = (buf[0], buf[1]);
+    let [a, b, c, d] = *input;
+    let mut sum: u32 = 0;
+    for _ in 0..16 {
+        sum = sum.wrapping_add(DELTA);
+        b0 = b0.wrapping_add(
+            ((b1 << 4).wrapping_add(a)) ^ (b1.wrapping_add(sum)) ^ ((b1 >> 5).wrapping_add(b)),
+        );
+        b1 = b1.wrapping_add(
+            ((b0 << 4).wrapping_add(c)) ^ (b0.wrapping_add(sum)) ^ ((b0 >> 5).wrapping_add(d)),
+        );
+    }
+    buf[0] = buf[0].wrapping_add(b0);
+    buf[1] = buf[1].wrapping_add(b1);
+}
+
+/// The transform of the MD4 with a reduced number of rounds.
+fn half_md4_transform(buf: &mut [u32; 4], input: &[u32; 8]) {
+    const K1: u32 = 0;
+    const K2: u32 = 0o13240474631;
+    const K3: u32 = 0o15666365641;
+
+    fn f(x: u32, y: u32, z: u32) -> u32 {
+        z ^ (x & (y ^ z))
+    }
+    fn g(x: u32, y: u32, z: u32) -> u32 {
+        (x & y).wrapping_add((x ^ y) & z)
+    }
+    fn h(x: u32, y: u32, z: u32) -> u32 {
+        x ^ y ^ z
+    }
+    fn round(
+        func: fn(u32, u32, u32) -> u32,
+        a: &mut u32,
+        (b, c, d): (u32, u32, u32),
+        x: u32,
+        shift: u32,
+    ) {
+        *a = a
+            .wrapping_add(func(b, c, d))
+            .wrapping_add(x)
+            .rotate_left(shift);
+    }
+
+    let [mut a, mut b, mut c, mut d] = *buf;
+    let rounds: [(fn(u32, u32, u32) -> u32, u32, [(usize, u32); 8]); 3] = [
+        (
+            f,
+            K1,
+            [
+                (0, 3),
+                (1, 7),
+                (2, 11),
+                (3, 19),
+                (4, 3),
+                (5, 7),
+                (6, 11),
+                (7, 19),
+            ],
+        ),
+        (
+            g,
+            K2,
+            [
+                (1, 3),
+                (3, 5),
+                (5, 9),
+                (7, 13),
+                (0, 3),
+                (2, 5),
+                (4, 9),
+                (6, 13),
+            ],
+        ),
+        (
+            h,
+            K3,
+            [
+                (3, 3),
+                (7, 9),
+                (2, 11),
+                (6, 15),
+                (1, 3),
+                (5, 9),
+                (0, 11),
+                (4, 15),
+            ],
+        ),
+    ];
+    for (func, k, steps) in rounds {
+        for (step, (idx, shift)) in steps.into_iter().enumerate() {
+            let x = input[idx].wrapping_add(k);
+            // The registers rotate as (a, b, c, d), (d, a, b, c), (c, d, a, b), (b, c, d, a).
+            match step % 4 {
+                0 => round(func, &mut a, (b, c, d), x, shift),
+                1 => round(func, &mut d, (a, b, c), x, shift),
+                2 => round(func, &mut c, (d, a, b), x, shift),
+                _ => round(func, &mut b, (c, d, a), x, shift),
+            }
+        }
+    }
+
+    buf[0] = buf[0].wrapping_add(a);
+    buf[1] = buf[1].wrapping_add(b);
+    buf[2] = buf[2].wrapping_add(c);
+    buf[3] = buf[3].wrapping_add(d);
+}
+
+const DX_HASH_LEGACY: u8 = 0;
+const DX_HASH_HALF_MD4: u8 = 1;
+const DX_HASH_TEA: u8 = 2;
+const DX_HASH_LEGACY_UNSIGNED: u8 = 3;
+const DX_HASH_HALF_MD4_UNSIGNED: u8 = 4;
+const DX_HASH_TEA_UNSIGNED: u8 = 5;
+/// The difference between the unsigned versions and the signed ones.
+const DX_HASH_UNSIGNED_DELTA: u8 = DX_HASH_LEGACY_UNSIGNED - DX_HASH_LEGACY;
+
+/// The seed used if the seed in the superblock is all zero.
+const DEFAULT_SEED: [u32; 4] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476];
+
+/// The largest 32-bit hash, which is reserved to mark the end of the directory.
+const HTREE_EOF_32BIT: u32 = 0x7fff_ffff;
+
+/// The mask of the logical block in an entry, whose high bits are reserved.
+const DX_BLOCK_MASK: u32 = 0x0fff_ffff;
+
+/// The offset of `DxRootInfo` in the root, which follows the "." and ".." entries.
+const DX_ROOT_INFO_OFFSET: usize = 24;
+const DX_ROOT_ENTRIES_OFFSET: usize = DX_ROOT_INFO_OFFSET + size_of::<DxRootInfo>();
+/// The offset of the entries in the other nodes, which follow a fake entry.
+const DX_NODE_ENTRIES_OFFSET: usize = 8;
+/// The length of each entry, which holds the hash and the logical block.
+const DX_ENTRY_LEN: usize = 8;
+/// The length of the tail that holds the checksum of a node.
+const DX_TAIL_LEN: usize = 8;
+
+/// The information in the root of the hash tree.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod)]
+struct DxRootInfo {
+    reserved_zero: u32,
+    hash_version: u8,
+    /// The length of the information, which is always 8.
+    info_len: u8,
+    /// The depth of the tree minus one.
+    indirect_levels: u8,
+    unused_flags: u8,
+}
+
+/// The header of the entries in a node, which takes the place of the hash of
+/// the first entry.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod)]
+struct DxCountLimit {
+    limit: u16,
+    count: u16,
+}
+
+#[cfg(ktest)]
+mod test {
+    use ostd::prelude::*;
+
+    use super::*;
+
+    #[ktest]
+    fn str_to_hash_buf_pads_with_len() {
+        let mut buf = [0u32; 4];
+        str_to_hash_buf(b"abcde", &mut buf, false);
+        assert_eq!(buf[0], 0x61626364);
+        assert_eq!(buf[1], 0x05050565);
+        assert_eq!(buf[2], 0x05050505);
+        assert_eq!(buf[3], 0x05050505);
+    }
+
+    #[ktest]
+    fn signedness_only_matters_for_high_bytes() {
+        let signed = HashInfo {
+            version: DX_HASH_TEA,
+            seed: [0; 4],
+        };
+        let unsigned = HashInfo {
+            version: DX_HASH_TEA_UNSIGNED,
+            seed: DEFAULT_SEED,
+        };
+        assert_eq!(
+            signed.hash(b"lost+found").unwrap(),
+            unsigned.hash(b"lost+found").unwrap()
+        );
+        assert_ne!(
+            signed.hash("文件".as_bytes()).unwrap(),
+            unsigned.hash("文件".as_bytes()).unwrap()
+        );
+        assert_eq!(dx_hack_hash(b"abc", true), dx_hack_hash(b"abc", false));
+    }
+
+    #[ktest]
+    fn hash_is_even() {
+        for version in [DX_HASH_LEGACY, DX_HASH_HALF_MD4, DX_HASH_TEA] {
+            let info = HashInfo {
+                version,
+                seed: [1, 2, 3, 4],
+            };
+            let (hash, _) = info
+                .hash(b"a-rather-long-file-name-that-spans-two-chunks")
+                .unwrap();
+            assert_eq!(hash & 1, 0);
+        }
+    }
+}
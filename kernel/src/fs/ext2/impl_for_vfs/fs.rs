@@ -4,7 +4,9 @@ use ostd::sync::RwMutexReadGuard;
 
 use crate::{
     fs::{
-        ext2::{utils::Dirty, Ext2, SuperBlock as Ext2SuperBlock, MAGIC_NUM as EXT2_MAGIC},
+        ext2::{
+            utils::Dirty, Ext2, ExtVersion, SuperBlock as Ext2SuperBlock, MAGIC_NUM as EXT2_MAGIC,
+        },
         utils::{FileSystem, FsFlags, Inode, SuperBlock, NAME_MAX},
     },
     prelude::*,
@@ -30,6 +32,13 @@ impl FileSystem for Ext2 {
     fn flags(&self) -> FsFlags {
         FsFlags::empty()
     }
+
+    fn name(&self) -> &'static str {
+        match self.version() {
+            ExtVersion::Ext2 => "ext2",
+            ExtVersion::Ext4 => "ext4",
+        }
+    }
 }
 
 impl From<RwMutexReadGuard<'_, Dirty<Ext2SuperBlock>>> for SuperBlock {
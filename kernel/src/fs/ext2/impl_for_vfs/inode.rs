@@ -2,7 +2,7 @@
 
 #![expect(unused_variables)]
 
-use core::time::Duration;
+use core::{ops::Range, time::Duration};
 
 use aster_rights::Full;
 
@@ -177,6 +177,7 @@ impl Inode for Ext2Inode {
 
     fn sync_all(&self) -> Result<()> {
         self.sync_all()?;
+        self.fs().flush_inode(self.ino())?;
         self.fs().block_device().sync()?;
         Ok(())
     }
@@ -187,6 +188,10 @@ impl Inode for Ext2Inode {
         Ok(())
     }
 
+    fn sync_data_range(&self, range: Range<usize>) -> Result<()> {
+        self.sync_data_range(range)
+    }
+
     fn fs(&self) -> Arc<dyn FileSystem> {
         self.fs()
     }
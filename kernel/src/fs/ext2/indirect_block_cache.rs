@@ -21,7 +21,7 @@ pub struct IndirectBlockCache {
 
 // TODO: Should we allocate the bio segments from the pool for the I/O on indirect blocks?
 impl IndirectBlockCache {
-    /// The upper bound on the size of the cache.
+    /// The size of the cache beyond which the clean blocks are evicted.
     ///
     /// Use the same value as `BH_LRU_SIZE`.
     const MAX_SIZE: usize = 16;
@@ -95,14 +95,43 @@ impl IndirectBlockCache {
         self.evict(cache_size)
     }
 
-    /// Attempts to evict some blocks from cache if it exceeds the maximum size.
+    /// Returns the number of the cached blocks.
+    pub fn num_blocks(&self) -> usize {
+        self.cache.len()
+    }
+
+    /// Evicts the clean blocks among at most `nr_to_scan` least recently used blocks,
+    /// returning the number of the blocks evicted.
+    ///
+    /// Unlike [`Self::evict_all`], no I/O is performed.
+    pub fn evict_clean(&mut self, nr_to_scan: usize) -> usize {
+        let clean_bids: Vec<Ext2Bid> = self
+            .cache
+            .iter()
+            .rev()
+            .take(nr_to_scan)
+            .filter(|(_, block)| !block.is_dirty())
+            .map(|(bid, _)| *bid)
+            .collect();
+        for bid in clean_bids.iter() {
+            self.cache.pop(bid);
+        }
+        clean_bids.len()
+    }
+
+    /// Attempts to evict some clean blocks from cache if it exceeds the maximum size.
+    ///
+    /// The dirty blocks are kept until the inode is synced, since they may point to the
+    /// data blocks that have not been written yet. If the filesystem has a journal, they
+    /// must not be journaled before the data.
     fn try_shrink(&mut self) -> Result<()> {
         if self.cache.len() < Self::MAX_SIZE {
             return Ok(());
         }
         // TODO: How to determine the number of evictions each time?
         let evict_num = Self::MAX_SIZE / 2;
-        self.evict(evict_num)
+        self.evict_clean(evict_num);
+        Ok(())
     }
 
     /// Evicts `num` blocks from cache.
@@ -117,7 +146,7 @@ impl IndirectBlockCache {
                     Segment::<()>::from(block.frame.clone()).into(),
                     BioDirection::ToDevice,
                 );
-                bio_waiter.concat(self.fs().write_blocks_async(bid, bio_segment)?);
+                bio_waiter.concat(self.fs().write_metadata_blocks_async(bid, bio_segment)?);
             }
         }
 
@@ -4,19 +4,26 @@
 #![expect(unused_variables)]
 
 use alloc::{borrow::ToOwned, rc::Rc};
-use core::sync::atomic::{AtomicUsize, Ordering};
+use core::{
+    mem::offset_of,
+    sync::atomic::{AtomicUsize, Ordering},
+};
 
+use aster_block::SECTOR_SIZE;
 use inherit_methods_macro::inherit_methods;
 use ostd::{const_assert, mm::io_util::HasVmReaderWriter};
 
 use super::{
     block_ptr::{BidPath, BlockPtrs, Ext2Bid, BID_SIZE, MAX_BLOCK_PTRS},
-    dir::{DirEntryHeader, DirEntryItem, DirEntryReader, DirEntryWriter},
+    dir::{fill_dir_block_checksum, DirEntryHeader, DirEntryItem, DirEntryReader, DirEntryWriter},
+    extent::{ExtentMapping, ExtentTree},
     fs::Ext2,
+    htree,
     indirect_block_cache::{IndirectBlock, IndirectBlockCache},
     prelude::*,
+    super_block::{ExtVersion, FeatureCompatSet},
     utils::now,
-    xattr::Xattr,
+    xattr::{Xattr, XATTR_NBLOCKS},
 };
 use crate::{
     fs::{
@@ -51,8 +58,12 @@ impl Inode {
         ino: u32,
         block_group_idx: usize,
         desc: Dirty<InodeDesc>,
+        extent_tree: Option<ExtentTree>,
         fs: Weak<Ext2>,
     ) -> Arc<Self> {
+        let csum_seed = fs
+            .upgrade()
+            .and_then(|fs| fs.inode_csum_seed(ino, desc.generation()));
         Arc::new_cyclic(|weak_self| Self {
             ino,
             type_: desc.type_,
@@ -60,7 +71,17 @@ impl Inode {
             xattr: desc
                 .acl
                 .map(|acl| Xattr::new(acl, weak_self.clone(), fs.clone())),
-            inner: RwMutex::new(InodeInner::new(desc, weak_self.clone(), fs.clone())),
+            inner: RwMutex::new(InodeInner::new(
+                desc,
+                BlockMapping {
+                    extent_tree,
+                    csum_seed,
+                    ino,
+                    block_group_idx,
+                },
+                weak_self.clone(),
+                fs.clone(),
+            )),
             fs,
             extension: Extension::new(),
         })
@@ -615,14 +636,15 @@ impl Inode {
 
             let try_readdir = |offset: &mut usize, visitor: &mut dyn DirentVisitor| -> Result<()> {
                 let mut dir_entry_reader = DirEntryReader::new(&inner.page_cache, *offset);
-                for dir_entry in dir_entry_reader.iter_entries() {
+                for (entry_offset, dir_entry) in dir_entry_reader.iter_entries() {
                     visitor.visit(
                         dir_entry.name(),
                         dir_entry.ino() as u64,
                         dir_entry.type_(),
                         dir_entry.record_len(),
                     )?;
-                    *offset += dir_entry.record_len();
+                    // The unused entries before this one are skipped.
+                    *offset = entry_offset + dir_entry.record_len();
                 }
 
                 Ok(())
@@ -750,6 +772,12 @@ impl Inode {
     }
 
     pub fn sync_all(&self) -> Result<()> {
+        // The handle is opened before locking the inode, since opening it may wait for
+        // a commit, which writes back the data of the inodes.
+        let fs = self.fs();
+        let nblocks = self.inner.read().nblocks_to_sync() + XATTR_NBLOCKS;
+        let _handle = fs.start_journal(nblocks);
+
         let mut inner = self.inner.write();
         inner.sync_data()?;
         inner.sync_metadata()?;
@@ -759,6 +787,33 @@ impl Inode {
         Ok(())
     }
 
+    /// Returns the number of the cached indirect blocks, or zero if they are locked.
+    pub(super) fn num_indirect_blocks(&self) -> usize {
+        let Some(inner) = self.inner.try_read() else {
+            return 0;
+        };
+        let Some(indirect_blocks) = inner.inode_impl.block_manager.indirect_blocks.try_read()
+        else {
+            return 0;
+        };
+        indirect_blocks.num_blocks()
+    }
+
+    /// Evicts the clean indirect blocks among at most `nr_to_scan` least recently used
+    /// ones, returning the number of the blocks evicted.
+    ///
+    /// Nothing is evicted if the blocks are locked.
+    pub(super) fn shrink_indirect_blocks(&self, nr_to_scan: usize) -> usize {
+        let Some(inner) = self.inner.try_read() else {
+            return 0;
+        };
+        let Some(mut indirect_blocks) = inner.inode_impl.block_manager.indirect_blocks.try_write()
+        else {
+            return 0;
+        };
+        indirect_blocks.evict_clean(nr_to_scan)
+    }
+
     pub fn set_file_perm(&self, perm: FilePerm) {
         let mut inner = self.inner.write();
         inner.set_file_perm(perm);
@@ -885,6 +940,7 @@ impl Inode {
     pub fn set_mtime(&self, time: Duration);
     pub fn set_ctime(&self, time: Duration);
     pub fn sync_data(&self) -> Result<()>;
+    pub fn sync_data_range(&self, range: Range<usize>) -> Result<()>;
     pub fn sync_metadata(&mut self) -> Result<()>;
 }
 
@@ -956,9 +1012,14 @@ struct InodeInner {
 }
 
 impl InodeInner {
-    pub fn new(desc: Dirty<InodeDesc>, weak_self: Weak<Inode>, fs: Weak<Ext2>) -> Self {
+    pub fn new(
+        desc: Dirty<InodeDesc>,
+        mapping: BlockMapping,
+        weak_self: Weak<Inode>,
+        fs: Weak<Ext2>,
+    ) -> Self {
         let num_page_bytes = desc.num_page_bytes();
-        let inode_impl = InodeImpl::new(desc, weak_self, fs);
+        let inode_impl = InodeImpl::new(desc, mapping, weak_self, fs);
         Self {
             page_cache: PageCache::with_capacity(
                 num_page_bytes,
@@ -1063,7 +1124,7 @@ impl InodeInner {
 
     pub fn read_link(&self) -> Result<String> {
         let file_size = self.inode_impl.file_size();
-        if file_size <= MAX_FAST_SYMLINK_LEN {
+        if file_size <= MAX_FAST_SYMLINK_LEN && !self.inode_impl.desc.is_extent_mapped() {
             return self.inode_impl.read_link();
         }
 
@@ -1077,16 +1138,37 @@ impl InodeInner {
 
     fn init_dir(&mut self, self_ino: u32, parent_ino: u32) -> Result<()> {
         debug_assert_eq!(self.inode_type(), InodeType::Dir);
-        DirEntryWriter::new(&self.page_cache, 0).init_dir(self_ino, parent_ino)?;
+        DirEntryWriter::new(&self.page_cache, 0, self.has_dir_tail())
+            .init_dir(self_ino, parent_ino)?;
+        self.inode_impl.resize(self.page_cache.pages().size())?;
         self.inc_hard_links(); // for ".."
         Ok(())
     }
 
     pub fn contains_entry(&self, name: &str) -> bool {
-        DirEntryReader::new(&self.page_cache, 0).contains_entry(name)
+        self.find_entry_item(name).is_some()
     }
 
     pub fn find_entry_item(&self, name: &str) -> Option<DirEntryItem> {
+        // The "." and ".." entries are not indexed.
+        if self.has_dir_index() && !is_dot_or_dotdot(name) {
+            match htree::lookup_leaves(&self.page_cache, name, &self.inode_impl.fs()) {
+                Ok(leaves) => {
+                    return leaves.into_iter().find_map(|leaf| {
+                        let offset = leaf as usize * BLOCK_SIZE;
+                        DirEntryReader::with_range(&self.page_cache, offset..offset + BLOCK_SIZE)
+                            .find_entry_item(name)
+                    });
+                }
+                Err(err) => {
+                    warn!(
+                        "failed to look up the htree, fall back to linear search: {:?}",
+                        err
+                    );
+                }
+            }
+        }
+
         DirEntryReader::new(&self.page_cache, 0).find_entry_item(name)
     }
 
@@ -1101,8 +1183,9 @@ impl InodeInner {
         name: &str,
         check_existence: bool,
     ) -> Result<()> {
+        self.clear_dir_index()?;
         let entry_header = DirEntryHeader::new(ino, inode_type, name.len());
-        DirEntryWriter::new(&self.page_cache, 0).append_new_entry(
+        DirEntryWriter::new(&self.page_cache, 0, self.has_dir_tail()).append_new_entry(
             entry_header,
             name,
             check_existence,
@@ -1123,7 +1206,9 @@ impl InodeInner {
     }
 
     pub fn remove_entry_at(&mut self, name: &str, offset: usize) -> Result<()> {
-        let removed_entry = DirEntryWriter::new(&self.page_cache, offset).remove_entry(name)?;
+        self.clear_dir_index()?;
+        let removed_entry = DirEntryWriter::new(&self.page_cache, offset, self.has_dir_tail())
+            .remove_entry(name)?;
         let file_size = self.file_size();
         let page_cache_size = self.page_cache.pages().size();
         if page_cache_size < file_size {
@@ -1136,7 +1221,9 @@ impl InodeInner {
     }
 
     pub fn rename_entry_at(&mut self, old_name: &str, new_name: &str, offset: usize) -> Result<()> {
-        DirEntryWriter::new(&self.page_cache, offset).rename_entry(old_name, new_name)?;
+        self.clear_dir_index()?;
+        DirEntryWriter::new(&self.page_cache, offset, self.has_dir_tail())
+            .rename_entry(old_name, new_name)?;
         let file_size = self.file_size();
         let page_cache_size = self.page_cache.pages().size();
         if page_cache_size != file_size {
@@ -1146,19 +1233,70 @@ impl InodeInner {
     }
 
     pub fn set_parent_ino(&mut self, parent_ino: u32) -> Result<()> {
+        self.clear_dir_index()?;
         let mut entry_item = self.find_entry_item("..").unwrap();
         entry_item.set_ino(parent_ino);
-        DirEntryWriter::new(&self.page_cache, entry_item.offset())
+        DirEntryWriter::new(&self.page_cache, entry_item.offset(), self.has_dir_tail())
             .write_header_only(entry_item.header())?;
         Ok(())
     }
 
+    /// Returns whether the directory blocks end with a `DirEntryTail`.
+    fn has_dir_tail(&self) -> bool {
+        self.inode_impl.block_manager.csum_seed.is_some()
+    }
+
+    /// Returns whether the directory is indexed by a hash tree.
+    fn has_dir_index(&self) -> bool {
+        self.file_flags().contains(FileFlags::INDEX_DIR)
+            && self
+                .inode_impl
+                .fs()
+                .super_block()
+                .feature_compat()
+                .contains(FeatureCompatSet::DIR_INDEX)
+    }
+
+    /// Drops the hash tree index of the directory.
+    ///
+    /// The index is not maintained on modifications, so it is dropped before the
+    /// directory is modified for the first time.
+    fn clear_dir_index(&mut self) -> Result<()> {
+        if !self.file_flags().contains(FileFlags::INDEX_DIR) {
+            return Ok(());
+        }
+
+        DirEntryWriter::new(&self.page_cache, 0, self.has_dir_tail()).clear_index()?;
+        self.inode_impl.clear_file_flags(FileFlags::INDEX_DIR);
+        Ok(())
+    }
+
+    /// Returns the maximal number of the metadata blocks written back by
+    /// `sync_data` and `sync_metadata`.
+    pub fn nblocks_to_sync(&self) -> usize {
+        // The blocks of directories are metadata.
+        let nr_dir_blocks = if self.inode_type() == InodeType::Dir {
+            self.page_cache.nr_dirty_pages()
+        } else {
+            0
+        };
+        nr_dir_blocks + self.inode_impl.nblocks_to_sync()
+    }
+
     pub fn sync_data(&self) -> Result<()> {
         // Writes back the data in page cache.
         let file_size = self.file_size();
         self.page_cache.evict_range(0..file_size)?;
         Ok(())
     }
+
+    pub fn sync_data_range(&self, range: Range<usize>) -> Result<()> {
+        // Writes back the data in page cache within the file.
+        let file_size = self.file_size();
+        self.page_cache
+            .evict_range(range.start.min(file_size)..range.end.min(file_size))?;
+        Ok(())
+    }
 }
 
 #[inherit_methods(from = "self.inode_impl")]
@@ -1198,11 +1336,21 @@ struct InodeImpl {
 }
 
 impl InodeImpl {
-    pub fn new(desc: Dirty<InodeDesc>, weak_self: Weak<Inode>, fs: Weak<Ext2>) -> Self {
+    pub fn new(
+        desc: Dirty<InodeDesc>,
+        mapping: BlockMapping,
+        weak_self: Weak<Inode>,
+        fs: Weak<Ext2>,
+    ) -> Self {
         let block_manager = InodeBlockManager {
             nblocks: AtomicUsize::new(desc.blocks_count() as _),
             block_ptrs: RwMutex::new(desc.block_ptrs),
             indirect_blocks: RwMutex::new(IndirectBlockCache::new(fs.clone())),
+            extent_tree: RwMutex::new(mapping.extent_tree),
+            csum_seed: mapping.csum_seed,
+            is_dir: desc.type_ == InodeType::Dir,
+            ino: mapping.ino,
+            block_group_idx: mapping.block_group_idx,
             fs,
         };
         Self {
@@ -1258,6 +1406,10 @@ impl InodeImpl {
         self.desc.flags
     }
 
+    pub fn clear_file_flags(&mut self, flags: FileFlags) {
+        self.desc.flags.remove(flags);
+    }
+
     pub fn hard_links(&self) -> u16 {
         self.desc.hard_links
     }
@@ -1328,6 +1480,16 @@ impl InodeImpl {
     }
 
     pub fn write_link(&mut self, target: &str) -> Result<()> {
+        if self.desc.is_extent_mapped() {
+            // The target of a fast symlink takes the place of the extent tree root.
+            self.resize(0)?;
+            self.flush_extent_tree()?;
+            *self.block_manager.extent_tree.write() = None;
+            self.desc.flags.remove(FileFlags::EXTENTS);
+            self.desc.block_ptrs = BlockPtrs::default();
+            *self.block_manager.block_ptrs.write() = BlockPtrs::default();
+        }
+
         let target_len = target.len();
         self.desc.block_ptrs.as_bytes_mut()[..target_len].copy_from_slice(target.as_bytes());
         self.block_manager.block_ptrs.write().as_bytes_mut()[..target_len]
@@ -1338,8 +1500,28 @@ impl InodeImpl {
         Ok(())
     }
 
+    /// Returns the maximal number of the metadata blocks written back by `sync_metadata`,
+    /// besides the raw inode.
+    pub fn nblocks_to_sync(&self) -> usize {
+        let nr_nodes = self
+            .block_manager
+            .extent_tree
+            .read()
+            .as_ref()
+            .filter(|extent_tree| extent_tree.is_dirty())
+            .map_or(0, |extent_tree| extent_tree.nr_nodes());
+        let nr_indirect_blocks = self.block_manager.indirect_blocks.read().num_blocks();
+        nr_nodes + nr_indirect_blocks
+    }
+
     pub fn sync_metadata(&mut self) -> Result<()> {
-        if !self.desc.is_dirty() {
+        let is_extent_tree_dirty = self
+            .block_manager
+            .extent_tree
+            .read()
+            .as_ref()
+            .is_some_and(|extent_tree| extent_tree.is_dirty());
+        if !self.desc.is_dirty() && !is_extent_tree_dirty {
             return Ok(());
         }
 
@@ -1358,11 +1540,37 @@ impl InodeImpl {
             }
         }
 
+        self.flush_extent_tree()?;
         self.block_manager.indirect_blocks.write().evict_all()?;
         inode.fs().sync_inode(inode.ino(), &self.desc)?;
         self.desc.clear_dirty();
         Ok(())
     }
+
+    /// Writes the nodes of the extent tree back if the extents have been changed,
+    /// updating the root and the number of sectors in the descriptor.
+    fn flush_extent_tree(&mut self) -> Result<()> {
+        let fs = self.fs();
+        let mut extent_tree = self.block_manager.extent_tree.write();
+        let Some(extent_tree) = extent_tree.as_mut().filter(|tree| tree.is_dirty()) else {
+            return Ok(());
+        };
+
+        let (root, nblocks_delta) = extent_tree.flush(
+            &fs,
+            self.block_manager.csum_seed,
+            self.block_manager.block_group_idx,
+        )?;
+        self.desc.block_ptrs = root;
+        *self.block_manager.block_ptrs.write() = root;
+        let sectors_delta = nblocks_delta * (BLOCK_SIZE / SECTOR_SIZE) as i64;
+        self.desc.sectors_count = self
+            .desc
+            .sectors_count
+            .checked_add_signed(sectors_delta)
+            .unwrap();
+        Ok(())
+    }
 }
 
 // Heavy implementation for inode resizing.
@@ -1390,7 +1598,12 @@ impl InodeImpl {
             if new_blocks - old_blocks > self.fs().super_block().free_blocks_count() {
                 return_errno_with_message!(Errno::ENOSPC, "not enough free blocks");
             }
-            self.expand_blocks(old_blocks..new_blocks)?;
+            if self.desc.is_extent_mapped() {
+                self.expand_extents(old_blocks..new_blocks)?;
+            } else {
+                self.expand_blocks(old_blocks..new_blocks)?;
+            }
+            self.block_manager.order_data();
         }
 
         // Expands the size
@@ -1398,6 +1611,44 @@ impl InodeImpl {
         Ok(())
     }
 
+    /// Expands the blocks of an extent-mapped inode.
+    ///
+    /// The blocks in `range` that are not mapped yet are allocated. After a successful
+    /// expansion, the block count will be enlarged to `range.end`.
+    fn expand_extents(&mut self, range: Range<Ext2Bid>) -> Result<()> {
+        let fs = self.fs();
+        let mut extent_tree = self.block_manager.extent_tree.write();
+        let extent_tree = extent_tree.as_mut().unwrap();
+        // The logical blocks mapped by this call, which are unmapped if the expansion fails.
+        let mut inserted = Vec::new();
+        for hole in extent_tree.holes(range.clone()) {
+            let mut current = hole.start;
+            while current < hole.end {
+                let block_group_idx = extent_tree
+                    .last_device_bid()
+                    .map_or(self.block_manager.block_group_idx, |bid| {
+                        ((bid + 1) / fs.blocks_per_group()) as usize
+                    });
+                let Some(device_range) = fs.alloc_blocks(block_group_idx, hole.end - current)
+                else {
+                    for block_range in inserted {
+                        for device_range in extent_tree.remove(block_range) {
+                            fs.free_blocks(device_range).unwrap();
+                        }
+                    }
+                    return_errno_with_message!(Errno::ENOSPC, "can not allocate blocks");
+                };
+                let len = device_range.len() as Ext2Bid;
+                extent_tree.insert(current, device_range);
+                inserted.push(current..current + len);
+                current += len;
+            }
+        }
+
+        self.desc.blocks_count = range.end;
+        Ok(())
+    }
+
     /// Expands inode blocks.
     ///
     /// After a successful expansion, the block count will be enlarged to `range.end`.
@@ -1644,7 +1895,11 @@ impl InodeImpl {
 
         // Shrinks block count if necessary
         if new_blocks < old_blocks {
-            self.shrink_blocks(new_blocks..old_blocks);
+            if self.desc.is_extent_mapped() {
+                self.shrink_extents(new_blocks);
+            } else {
+                self.shrink_blocks(new_blocks..old_blocks);
+            }
         }
 
         // Shrinks the size
@@ -1658,6 +1913,25 @@ impl InodeImpl {
             .store(self.blocks_count() as _, Ordering::Release);
     }
 
+    /// Shrinks the blocks of an extent-mapped inode.
+    ///
+    /// After the reduction, the block count will be decreased to `nblocks`.
+    fn shrink_extents(&mut self, nblocks: Ext2Bid) {
+        let fs = self.fs();
+        let freed_ranges = self
+            .block_manager
+            .extent_tree
+            .write()
+            .as_mut()
+            .unwrap()
+            .truncate(nblocks);
+        for device_range in freed_ranges {
+            fs.free_blocks(device_range).unwrap();
+        }
+
+        self.desc.blocks_count = nblocks;
+    }
+
     /// Shrinks inode blocks.
     ///
     /// After the reduction, the block count will be decreased to `range.start`.
@@ -1832,9 +2106,34 @@ struct InodeBlockManager {
     /// frequent reads access the `InodeDesc` copy without locking.
     block_ptrs: RwMutex<BlockPtrs>,
     indirect_blocks: RwMutex<IndirectBlockCache>,
+    /// The extent tree, which is `Some` if the inode is extent-mapped.
+    ///
+    /// The holes are allocated when they are written back from the page cache.
+    extent_tree: RwMutex<Option<ExtentTree>>,
+    /// The checksum seed of the inode, which is `Some` if the metadata checksums are enabled.
+    csum_seed: Option<u32>,
+    is_dir: bool,
+    ino: u32,
+    block_group_idx: usize,
     fs: Weak<Ext2>,
 }
 
+/// The information to map the inode blocks to the device blocks.
+struct BlockMapping {
+    extent_tree: Option<ExtentTree>,
+    csum_seed: Option<u32>,
+    ino: u32,
+    block_group_idx: usize,
+}
+
+/// A range of blocks on which the I/O is performed.
+enum IoRange {
+    /// The consecutive blocks on the device.
+    Device(Range<Ext2Bid>),
+    /// The given number of blocks that have no device blocks and are read as zeros.
+    Zeros(usize),
+}
+
 impl InodeBlockManager {
     /// Reads one or multiple blocks to the segment start from `bid` asynchronously.
     pub fn read_blocks_async(
@@ -1846,7 +2145,14 @@ impl InodeBlockManager {
         debug_assert!(nblocks * BLOCK_SIZE <= writer.avail());
         let mut bio_waiter = BioWaiter::new();
 
-        for dev_range in DeviceRangeReader::new(self, bid..bid + nblocks as Ext2Bid)? {
+        for io_range in self.read_io_ranges(bid..bid + nblocks as Ext2Bid)? {
+            let dev_range = match io_range {
+                IoRange::Device(dev_range) => dev_range,
+                IoRange::Zeros(range_nblocks) => {
+                    writer.fill_zeros(range_nblocks * BLOCK_SIZE)?;
+                    continue;
+                }
+            };
             let start_bid = dev_range.start as Ext2Bid;
             let range_nblocks = dev_range.len();
 
@@ -1870,7 +2176,14 @@ impl InodeBlockManager {
     pub fn read_block_async(&self, bid: Ext2Bid, frame: &CachePage) -> Result<BioWaiter> {
         let mut bio_waiter = BioWaiter::new();
 
-        for dev_range in DeviceRangeReader::new(self, bid..bid + 1 as Ext2Bid)? {
+        for io_range in self.read_io_ranges(bid..bid + 1 as Ext2Bid)? {
+            let dev_range = match io_range {
+                IoRange::Device(dev_range) => dev_range,
+                IoRange::Zeros(_) => {
+                    frame.writer().fill_zeros(BLOCK_SIZE);
+                    continue;
+                }
+            };
             let start_bid = dev_range.start as Ext2Bid;
             // TODO: Should we allocate the bio segment from the pool on reads?
             // This may require an additional copy to the requested frame in the completion callback.
@@ -1895,7 +2208,7 @@ impl InodeBlockManager {
         debug_assert_eq!(nblocks * BLOCK_SIZE, reader.remain());
         let mut bio_waiter = BioWaiter::new();
 
-        for dev_range in DeviceRangeReader::new(self, bid..bid + nblocks as Ext2Bid)? {
+        for dev_range in self.write_device_ranges(bid..bid + nblocks as Ext2Bid)? {
             let start_bid = dev_range.start as Ext2Bid;
             let range_nblocks = dev_range.len();
 
@@ -1919,21 +2232,116 @@ impl InodeBlockManager {
     pub fn write_block_async(&self, bid: Ext2Bid, frame: &CachePage) -> Result<BioWaiter> {
         let mut bio_waiter = BioWaiter::new();
 
-        for dev_range in DeviceRangeReader::new(self, bid..bid + 1 as Ext2Bid)? {
+        for dev_range in self.write_device_ranges(bid..bid + 1 as Ext2Bid)? {
             let start_bid = dev_range.start as Ext2Bid;
             let bio_segment = BioSegment::alloc(1, BioDirection::ToDevice);
             // This requires an additional copy to the pooled bio segment.
-            bio_segment
-                .writer()
-                .unwrap()
-                .write_fallible(&mut frame.reader().to_fallible())?;
-            let waiter = self.fs().write_blocks_async(start_bid, bio_segment)?;
+            match self.csum_seed {
+                Some(csum_seed) if self.is_dir => {
+                    let mut block = vec![0u8; BLOCK_SIZE];
+                    frame
+                        .reader()
+                        .read(&mut VmWriter::from(block.as_mut_slice()));
+                    fill_dir_block_checksum(&mut block, csum_seed);
+                    bio_segment
+                        .writer()
+                        .unwrap()
+                        .write(&mut VmReader::from(block.as_slice()));
+                }
+                _ => {
+                    bio_segment
+                        .writer()
+                        .unwrap()
+                        .write_fallible(&mut frame.reader().to_fallible())?;
+                }
+            }
+            // The blocks of directories are metadata, which are journaled.
+            let waiter = if self.is_dir {
+                self.fs()
+                    .write_metadata_blocks_async(start_bid, bio_segment)?
+            } else {
+                self.fs().write_blocks_async(start_bid, bio_segment)?
+            };
             bio_waiter.concat(waiter);
         }
 
         Ok(bio_waiter)
     }
 
+    /// Returns the ranges to read the blocks in `range` from.
+    fn read_io_ranges(&self, range: Range<Ext2Bid>) -> Result<Vec<IoRange>> {
+        let extent_tree = self.extent_tree.read();
+        let Some(extent_tree) = extent_tree.as_ref() else {
+            return Ok(DeviceRangeReader::new(self, range)?
+                .map(IoRange::Device)
+                .collect());
+        };
+
+        let mut io_ranges = Vec::new();
+        let mut current = range.start;
+        while current < range.end {
+            let mapping = extent_tree.lookup(current..range.end);
+            current += mapping.len();
+            io_ranges.push(match mapping {
+                ExtentMapping::Mapped(dev_range) => IoRange::Device(dev_range),
+                ExtentMapping::Unwritten(dev_range) => IoRange::Zeros(dev_range.len()),
+                ExtentMapping::Hole(len) => IoRange::Zeros(len as usize),
+            });
+        }
+        Ok(io_ranges)
+    }
+
+    /// Returns the device ranges to write the blocks in `range` to.
+    ///
+    /// For the extent-mapped inodes, the holes are allocated and the unwritten extents
+    /// are marked as written.
+    fn write_device_ranges(&self, range: Range<Ext2Bid>) -> Result<Vec<Range<Ext2Bid>>> {
+        let mut extent_tree = self.extent_tree.write();
+        let Some(extent_tree) = extent_tree.as_mut() else {
+            return Ok(DeviceRangeReader::new(self, range)?.collect());
+        };
+
+        let fs = self.fs();
+        let mut dev_ranges = Vec::new();
+        let mut current = range.start;
+        while current < range.end {
+            let dev_range = match extent_tree.lookup(current..range.end) {
+                ExtentMapping::Mapped(dev_range) => dev_range,
+                ExtentMapping::Unwritten(dev_range) => {
+                    extent_tree.mark_written(current..current + dev_range.len() as Ext2Bid);
+                    self.order_data();
+                    dev_range
+                }
+                ExtentMapping::Hole(len) => {
+                    let block_group_idx = extent_tree
+                        .last_device_bid()
+                        .map_or(self.block_group_idx, |bid| {
+                            ((bid + 1) / fs.blocks_per_group()) as usize
+                        });
+                    let dev_range = fs
+                        .alloc_blocks(block_group_idx, len)
+                        .ok_or_else(|| Error::new(Errno::ENOSPC))?;
+                    extent_tree.insert(current, dev_range.clone());
+                    self.order_data();
+                    dev_range
+                }
+            };
+            current += dev_range.len() as Ext2Bid;
+            dev_ranges.push(dev_range);
+        }
+        Ok(dev_ranges)
+    }
+
+    /// Adds the inode to the ordered inodes of the journal, since the data in its newly
+    /// mapped blocks must be written before the metadata mapping them are committed.
+    ///
+    /// The blocks of directories are metadata, which are journaled instead.
+    fn order_data(&self) {
+        if !self.is_dir {
+            self.fs().add_ordered_inode(self.ino);
+        }
+    }
+
     pub fn nblocks(&self) -> usize {
         self.nblocks.load(Ordering::Acquire)
     }
@@ -2095,6 +2503,8 @@ impl Iterator for DeviceRangeReader<'_> {
 /// Each block group has an inode table it is responsible for.
 #[derive(Clone, Copy, Debug)]
 pub(super) struct InodeDesc {
+    /// The version of the on-disk format.
+    version: ExtVersion,
     /// Type.
     type_: InodeType,
     /// Permission.
@@ -2113,56 +2523,123 @@ pub(super) struct InodeDesc {
     mtime: Duration,
     /// Deletion time.
     dtime: Duration,
+    /// Creation time.
+    crtime: Duration,
     /// Hard links count.
     hard_links: u16,
     /// Number of blocks.
     blocks_count: Ext2Bid,
+    /// Number of 512-byte sectors occupied by the data and the extent tree.
+    ///
+    /// It is only maintained for the extent-mapped inodes.
+    sectors_count: u64,
     /// File flags.
     flags: FileFlags,
-    /// Pointers to blocks.
+    /// Pointers to blocks, or the root of the extent tree if the inode is extent-mapped.
     block_ptrs: BlockPtrs,
+    /// File version (for NFS), which is also a part of the checksum seed.
+    generation: u32,
     /// File or directory acl block.
     acl: Option<Bid>,
+    /// Size of the extra inode fields beyond the 128-byte base inode.
+    extra_isize: u16,
+    /// Project Id.
+    projid: u32,
 }
 
-impl TryFrom<RawInode> for InodeDesc {
-    type Error = crate::error::Error;
-
-    fn try_from(inode: RawInode) -> Result<Self> {
+impl InodeDesc {
+    /// Creates the descriptor from the raw inode and its extra fields.
+    pub fn from_raw(inode: &RawInode, extra: &RawInodeExtra, version: ExtVersion) -> Result<Self> {
         let inode_type = InodeType::from_raw_mode(inode.mode)?;
-        Ok(Self {
+        let flags = FileFlags::from_bits(inode.flags)
+            .ok_or(Error::with_message(Errno::EINVAL, "invalid file flags"))?;
+        let size = if inode_type == InodeType::File || version == ExtVersion::Ext4 {
+            ((inode.size_high as usize) << 32) | inode.size_low as usize
+        } else {
+            inode.size_low as usize
+        };
+        let acl = match (inode_type, version) {
+            (InodeType::File | InodeType::Dir, ExtVersion::Ext4) => Some(Bid::new(
+                ((inode.os_dependent_2.file_acl_high as u64) << 32) | inode.file_acl as u64,
+            )),
+            (InodeType::File, ExtVersion::Ext2) => Some(Bid::new(inode.file_acl as _)),
+            (InodeType::Dir, ExtVersion::Ext2) => Some(Bid::new(inode.size_high as _)),
+            _ => None,
+        };
+        let sectors_count = {
+            let raw_count =
+                ((inode.os_dependent_2.blocks_high as u64) << 32) | inode.blocks_count as u64;
+            if flags.contains(FileFlags::HUGE_FILE) {
+                raw_count * (BLOCK_SIZE / SECTOR_SIZE) as u64
+            } else {
+                raw_count
+            }
+        };
+        let has_field = |offset: usize| (extra.extra_isize as usize) >= offset + size_of::<u32>();
+        let decode_time = |time: UnixTime, offset: usize, extra_time: u32| {
+            let time = Duration::from(time);
+            if has_field(offset) {
+                decode_extra_time(time.as_secs() as u32, extra_time)
+            } else {
+                time
+            }
+        };
+
+        let mut desc = Self {
+            version,
             type_: inode_type,
             perm: FilePerm::from_raw_mode(inode.mode)?,
             uid: ((inode.os_dependent_2.uid_high as u32) << 16) | inode.uid as u32,
             gid: ((inode.os_dependent_2.gid_high as u32) << 16) | inode.gid as u32,
-            size: if inode_type == InodeType::File {
-                ((inode.size_high as usize) << 32) | inode.size_low as usize
+            size,
+            atime: decode_time(
+                inode.atime,
+                offset_of!(RawInodeExtra, atime_extra),
+                extra.atime_extra,
+            ),
+            ctime: decode_time(
+                inode.ctime,
+                offset_of!(RawInodeExtra, ctime_extra),
+                extra.ctime_extra,
+            ),
+            mtime: decode_time(
+                inode.mtime,
+                offset_of!(RawInodeExtra, mtime_extra),
+                extra.mtime_extra,
+            ),
+            dtime: Duration::from(inode.dtime),
+            crtime: if has_field(offset_of!(RawInodeExtra, crtime_extra)) {
+                decode_extra_time(extra.crtime, extra.crtime_extra)
             } else {
-                inode.size_low as usize
+                Duration::ZERO
             },
-            atime: Duration::from(inode.atime),
-            ctime: Duration::from(inode.ctime),
-            mtime: Duration::from(inode.mtime),
-            dtime: Duration::from(inode.dtime),
             hard_links: inode.hard_links,
             blocks_count: inode.blocks_count,
-            flags: FileFlags::from_bits(inode.flags)
-                .ok_or(Error::with_message(Errno::EINVAL, "invalid file flags"))?,
+            sectors_count,
+            flags,
             block_ptrs: inode.block_ptrs,
-            acl: match inode_type {
-                InodeType::File => Some(Bid::new(inode.file_acl as _)),
-                InodeType::Dir => Some(Bid::new(inode.size_high as _)),
-                _ => None,
+            generation: inode.generation,
+            acl,
+            extra_isize: extra.extra_isize,
+            projid: if has_field(offset_of!(RawInodeExtra, projid)) {
+                extra.projid
+            } else {
+                0
             },
-        })
+        };
+        if desc.is_extent_mapped() {
+            // The `blocks_count` of extent-mapped inodes is the number of blocks
+            // in the page cache, see `InodeDesc::blocks_count`.
+            desc.blocks_count = desc.size_to_blocks(desc.size);
+        }
+        Ok(desc)
     }
-}
 
-impl InodeDesc {
-    pub fn new(type_: InodeType, perm: FilePerm) -> Dirty<Self> {
+    pub fn new(type_: InodeType, perm: FilePerm, version: ExtVersion) -> Dirty<Self> {
         let now = now();
         let credentials = current_thread!().as_posix_thread().unwrap().credentials();
         Dirty::new_dirty(Self {
+            version,
             type_,
             perm,
             uid: credentials.fsuid().into(),
@@ -2172,14 +2649,19 @@ impl InodeDesc {
             ctime: now,
             mtime: now,
             dtime: Duration::ZERO,
+            crtime: now,
             hard_links: 1,
             blocks_count: 0,
+            sectors_count: 0,
             flags: FileFlags::empty(),
             block_ptrs: BlockPtrs::default(),
+            generation: 0,
             acl: match type_ {
                 InodeType::File | InodeType::Dir => Some(Bid::new(0)),
                 _ => None,
             },
+            extra_isize: 0,
+            projid: 0,
         })
     }
 
@@ -2196,8 +2678,34 @@ impl InodeDesc {
         blocks
     }
 
+    /// Returns whether the blocks are mapped by an extent tree rooted at `block_ptrs`.
+    pub fn is_extent_mapped(&self) -> bool {
+        self.flags.contains(FileFlags::EXTENTS)
+    }
+
+    /// Makes the blocks mapped by the extent tree whose root is `root`.
+    pub fn set_extent_mapped(&mut self, root: BlockPtrs) {
+        self.flags.insert(FileFlags::EXTENTS);
+        self.block_ptrs = root;
+    }
+
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+
+    pub fn block_ptrs(&self) -> BlockPtrs {
+        self.block_ptrs
+    }
+
+    pub fn set_extra_isize(&mut self, extra_isize: u16) {
+        self.extra_isize = extra_isize;
+    }
+
     fn size_to_blocks(&self, size: usize) -> Ext2Bid {
-        if self.type_ == InodeType::SymLink && size <= MAX_FAST_SYMLINK_LEN {
+        if self.type_ == InodeType::SymLink
+            && size <= MAX_FAST_SYMLINK_LEN
+            && !self.is_extent_mapped()
+        {
             return 0;
         }
         size.div_ceil(BLOCK_SIZE) as Ext2Bid
@@ -2279,12 +2787,31 @@ bitflags! {
         const DIR_SYNC = 1 << 16;
         /// Top of directory hierarchies.
         const TOP_DIR = 1 << 17;
+        /// Set to each huge file, whose `blocks_count` is in the unit of filesystem blocks.
+        const HUGE_FILE = 1 << 18;
+        /// Inode uses extents.
+        const EXTENTS = 1 << 19;
+        /// Verity protected inode.
+        const VERITY = 1 << 20;
+        /// Inode used for large extended attribute value.
+        const EA_INODE = 1 << 21;
+        /// Inode is DAX.
+        const DAX = 1 << 25;
+        /// Inode has inline data.
+        const INLINE_DATA = 1 << 28;
+        /// Create with parents projid.
+        const PROJINHERIT = 1 << 29;
+        /// Casefolded directory.
+        const CASEFOLD = 1 << 30;
         /// Reserved for ext2 lib.
         const RESERVED = 1 << 31;
     }
 }
 
-const_assert!(core::mem::size_of::<RawInode>() == 128);
+/// The size of the raw inode, excluding the extra fields.
+pub(super) const RAW_INODE_SIZE: usize = 128;
+
+const_assert!(core::mem::size_of::<RawInode>() == RAW_INODE_SIZE);
 
 /// The raw inode on device.
 #[repr(C)]
@@ -2322,6 +2849,8 @@ pub(super) struct RawInode {
     /// In revision 0, this field is reserved.
     /// In revision 1, Upper 32 bits of file size (if feature bit set)
     /// if it's a file, Directory ACL if it's a directory.
+    ///
+    /// In Ext4, it is always the upper 32 bits of the size.
     pub size_high: u32,
     /// Fragment address.
     pub frag_addr: u32,
@@ -2331,6 +2860,22 @@ pub(super) struct RawInode {
 
 impl From<&InodeDesc> for RawInode {
     fn from(inode: &InodeDesc) -> Self {
+        let is_ext4 = inode.version == ExtVersion::Ext4;
+        let mut flags = inode.flags;
+        let (blocks_count, blocks_high) = if inode.is_extent_mapped() {
+            let sectors_count = if inode.sectors_count >= 1 << 48 {
+                flags.insert(FileFlags::HUGE_FILE);
+                inode.sectors_count / (BLOCK_SIZE / SECTOR_SIZE) as u64
+            } else {
+                flags.remove(FileFlags::HUGE_FILE);
+                inode.sectors_count
+            };
+            (sectors_count as u32, (sectors_count >> 32) as u16)
+        } else {
+            (inode.blocks_count, 0)
+        };
+        let acl = inode.acl.map_or(0, |acl| acl.to_raw());
+
         Self {
             mode: inode.type_ as u16 | inode.perm.bits(),
             uid: inode.uid as u16,
@@ -2341,18 +2886,24 @@ impl From<&InodeDesc> for RawInode {
             dtime: UnixTime::from(inode.dtime),
             gid: inode.gid as u16,
             hard_links: inode.hard_links,
-            blocks_count: inode.blocks_count,
-            flags: inode.flags.bits(),
+            blocks_count,
+            flags: flags.bits(),
             block_ptrs: inode.block_ptrs,
-            file_acl: match inode.acl {
-                Some(acl) if inode.type_ == InodeType::File => acl.to_raw() as u32,
+            generation: inode.generation,
+            file_acl: match inode.type_ {
+                InodeType::File => acl as u32,
+                InodeType::Dir if is_ext4 => acl as u32,
                 _ => Default::default(),
             },
-            size_high: match inode.acl {
-                Some(acl) if inode.type_ == InodeType::Dir => acl.to_raw() as u32,
+            size_high: match inode.type_ {
+                InodeType::Dir if !is_ext4 => acl as u32,
+                InodeType::File => (inode.size >> 32) as u32,
+                _ if is_ext4 => (inode.size >> 32) as u32,
                 _ => Default::default(),
             },
             os_dependent_2: Osd2 {
+                blocks_high,
+                file_acl_high: if is_ext4 { (acl >> 32) as u16 } else { 0 },
                 uid_high: (inode.uid >> 16) as u16,
                 gid_high: (inode.gid >> 16) as u16,
                 ..Default::default()
@@ -2366,18 +2917,129 @@ impl From<&InodeDesc> for RawInode {
 #[repr(C)]
 #[derive(Clone, Copy, Default, Debug, Pod)]
 pub(super) struct Osd2 {
-    /// Fragment number.
-    pub frag_num: u8,
-    /// Fragment size.
-    pub frag_size: u8,
-    pad1: u16,
+    /// High 16 bits of the blocks count.
+    ///
+    /// In Ext2, it is the fragment number and the fragment size, which are always zero.
+    pub blocks_high: u16,
+    /// High 16 bits of File ACL.
+    pub file_acl_high: u16,
     /// High 16 bits of User Id.
     pub uid_high: u16,
     /// High 16 bits of Group Id.
     pub gid_high: u16,
-    reserved2: u32,
+    /// Low 16 bits of the inode checksum.
+    pub checksum_lo: u16,
+    reserved2: u16,
+}
+
+/// The extra fields of the raw inode on device, which follow the base inode.
+///
+/// Only the first `extra_isize` bytes are stored in the inode slot, the others are zero.
+#[repr(C)]
+#[derive(Clone, Copy, Default, Debug, Pod)]
+pub(super) struct RawInodeExtra {
+    /// Size of the extra fields.
+    pub extra_isize: u16,
+    /// High 16 bits of the inode checksum.
+    pub checksum_hi: u16,
+    /// Extra change time (nsec << 2 | epoch).
+    pub ctime_extra: u32,
+    /// Extra modification time (nsec << 2 | epoch).
+    pub mtime_extra: u32,
+    /// Extra access time (nsec << 2 | epoch).
+    pub atime_extra: u32,
+    /// Creation time.
+    pub crtime: u32,
+    /// Extra creation time (nsec << 2 | epoch).
+    pub crtime_extra: u32,
+    /// High 32 bits of the version.
+    pub version_hi: u32,
+    /// Project Id.
+    pub projid: u32,
+}
+
+impl RawInodeExtra {
+    /// The size of all the extra fields that we know.
+    pub const SIZE: usize = size_of::<Self>();
+
+    /// Reads the extra fields from the raw inode slot.
+    pub fn from_slot(raw_slot: &[u8]) -> Self {
+        let mut extra = Self::new_zeroed();
+        let Some(extra_bytes) = raw_slot.get(RAW_INODE_SIZE..) else {
+            return extra;
+        };
+        if extra_bytes.len() < size_of::<u16>() {
+            return extra;
+        }
+        let extra_isize = u16::from_le_bytes([extra_bytes[0], extra_bytes[1]]);
+        let len = (extra_isize as usize)
+            .min(Self::SIZE)
+            .min(extra_bytes.len());
+        extra.as_bytes_mut()[..len].copy_from_slice(&extra_bytes[..len]);
+        extra.extra_isize = extra_isize;
+        extra
+    }
+
+    /// Writes the valid extra fields into the raw inode slot.
+    pub fn write_to_slot(&self, raw_slot: &mut [u8]) {
+        let Some(extra_bytes) = raw_slot.get_mut(RAW_INODE_SIZE..) else {
+            return;
+        };
+        let len = (self.extra_isize as usize)
+            .min(Self::SIZE)
+            .min(extra_bytes.len());
+        extra_bytes[..len].copy_from_slice(&self.as_bytes()[..len]);
+    }
+
+    /// Returns whether the high 16 bits of the inode checksum are stored.
+    pub fn has_checksum_hi(&self) -> bool {
+        self.extra_isize as usize >= offset_of!(RawInodeExtra, ctime_extra)
+    }
+}
+
+impl From<&InodeDesc> for RawInodeExtra {
+    fn from(inode: &InodeDesc) -> Self {
+        let (crtime, crtime_extra) = encode_extra_time(inode.crtime);
+        Self {
+            extra_isize: inode.extra_isize,
+            ctime_extra: encode_extra_time(inode.ctime).1,
+            mtime_extra: encode_extra_time(inode.mtime).1,
+            atime_extra: encode_extra_time(inode.atime).1,
+            crtime,
+            crtime_extra,
+            projid: inode.projid,
+            ..Default::default()
+        }
+    }
 }
 
+/// Decodes the time from the seconds stored in the base inode and the extra time.
+///
+/// The extra time holds the nanoseconds and extends the signed 32-bit seconds with two
+/// more bits, so that the time can range from 1901 to 2446.
+fn decode_extra_time(sec: u32, extra: u32) -> Duration {
+    let sec = (sec as i32 as i64) + (((extra & EXTRA_TIME_EPOCH_MASK) as i64) << 32);
+    let nsec = (extra >> EXTRA_TIME_NSEC_SHIFT).min(999_999_999);
+    if sec < 0 {
+        // The time before the Unix epoch is not representable.
+        return Duration::ZERO;
+    }
+    Duration::new(sec as u64, nsec)
+}
+
+/// Encodes the time as the seconds stored in the base inode and the extra time.
+fn encode_extra_time(time: Duration) -> (u32, u32) {
+    let sec = time.as_secs() as i64;
+    let epoch = ((sec - (sec as i32 as i64)) >> 32) as u32 & EXTRA_TIME_EPOCH_MASK;
+    (
+        sec as u32,
+        (time.subsec_nanos() << EXTRA_TIME_NSEC_SHIFT) | epoch,
+    )
+}
+
+const EXTRA_TIME_EPOCH_MASK: u32 = 0b11;
+const EXTRA_TIME_NSEC_SHIFT: u32 = 2;
+
 fn is_block_aligned(offset: usize) -> bool {
     offset % BLOCK_SIZE == 0
 }
@@ -0,0 +1,606 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The on-disk format of the JBD2 journal.
+//!
+//! Unlike the rest of the filesystem, all the fields of the journal are big-endian.
+//! The blocks of the journal other than the logged copies of the metadata start with
+//! a 12-byte header: the magic number, the block type and the transaction sequence.
+
+use crate::fs::ext2::{csum::crc32c, prelude::*};
+
+/// The magic number in the header of the journal blocks.
+pub(super) const JOURNAL_MAGIC: u32 = 0xC03B_3998;
+
+/// The size of the journal superblock, which is covered by its checksum.
+pub(super) const SUPER_BLOCK_SIZE: usize = 1024;
+
+const HEADER_SIZE: usize = 12;
+const UUID_SIZE: usize = 16;
+/// The size of the checksum at the end of the descriptor and revoke blocks.
+const TAIL_SIZE: usize = 4;
+/// The size of the revoke header, including the number of bytes used in the block.
+const REVOKE_HEADER_SIZE: usize = HEADER_SIZE + 4;
+
+// The offsets of the fields in the commit block.
+const COMMIT_CHECKSUM_OFFSET: usize = 16;
+const COMMIT_SEC_OFFSET: usize = 48;
+const COMMIT_NSEC_OFFSET: usize = 56;
+
+// The offsets of the fields in the journal superblock.
+const SB_BLOCK_SIZE_OFFSET: usize = 12;
+const SB_MAXLEN_OFFSET: usize = 16;
+const SB_FIRST_OFFSET: usize = 20;
+const SB_SEQUENCE_OFFSET: usize = 24;
+const SB_START_OFFSET: usize = 28;
+const SB_ERRNO_OFFSET: usize = 32;
+const SB_FEATURE_COMPAT_OFFSET: usize = 36;
+const SB_FEATURE_INCOMPAT_OFFSET: usize = 40;
+const SB_FEATURE_RO_COMPAT_OFFSET: usize = 44;
+const SB_UUID_OFFSET: usize = 48;
+const SB_CHECKSUM_TYPE_OFFSET: usize = 80;
+const SB_NUM_FC_BLOCKS_OFFSET: usize = 84;
+const SB_CHECKSUM_OFFSET: usize = 252;
+
+/// The checksum type of the journal superblock for CRC32C.
+const CRC32C_CHECKSUM_TYPE: u8 = 4;
+/// The default number of fast commit blocks if it is not recorded in the superblock.
+const DEFAULT_NUM_FC_BLOCKS: u32 = 256;
+
+/// The type of a journal block.
+#[repr(u32)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, TryFromInt)]
+pub(super) enum BlockType {
+    /// Describes the home locations of the metadata blocks following it.
+    Descriptor = 1,
+    /// Marks the end of a complete transaction.
+    Commit = 2,
+    /// The superblock of version 1, which supports no features.
+    SuperBlockV1 = 3,
+    /// The superblock of version 2.
+    SuperBlockV2 = 4,
+    /// Lists the blocks whose earlier logged copies must not be replayed.
+    Revoke = 5,
+}
+
+bitflags! {
+    /// Compatible features of the journal.
+    pub(super) struct JournalFeatureCompat: u32 {
+        /// Commit blocks have the legacy CRC32 checksums of the transactions
+        const CHECKSUM = 1 << 0;
+    }
+}
+
+bitflags! {
+    /// Incompatible features of the journal.
+    pub(super) struct JournalFeatureIncompat: u32 {
+        /// The journal has revoke blocks
+        const REVOKE = 1 << 0;
+        /// The block numbers are 64 bits
+        const _64BIT = 1 << 1;
+        /// Commit blocks can be written without waiting for the logged blocks
+        const ASYNC_COMMIT = 1 << 2;
+        /// The metadata of the journal has checksums of version 2
+        const CSUM_V2 = 1 << 3;
+        /// The metadata of the journal has checksums of version 3
+        const CSUM_V3 = 1 << 4;
+        /// The journal has a fast commit area
+        const FAST_COMMIT = 1 << 5;
+    }
+}
+
+bitflags! {
+    /// The flags of a block tag in descriptor blocks.
+    pub(super) struct TagFlags: u32 {
+        /// The first four bytes of the logged block were the magic number and were zeroed
+        const ESCAPE = 1 << 0;
+        /// The tag has the same UUID as the previous one, so the UUID is omitted
+        const SAME_UUID = 1 << 1;
+        /// The block is deleted by this transaction (unused)
+        const DELETED = 1 << 2;
+        /// The tag is the last one in the descriptor block
+        const LAST_TAG = 1 << 3;
+    }
+}
+
+/// A block tag, which describes the home location of a logged block.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) struct Tag {
+    /// The home location of the block.
+    pub bid: u64,
+    pub flags: TagFlags,
+    /// The checksum of the logged block, which is valid with the metadata checksums.
+    pub checksum: u32,
+}
+
+/// The in-memory journal superblock.
+///
+/// Only the fields that are changed by us are interpreted, the others are
+/// written back unchanged.
+#[derive(Clone, Debug)]
+pub(super) struct JournalSuperBlock {
+    /// The number of blocks in the journal.
+    pub maxlen: u32,
+    /// The first block of the log.
+    pub first: u32,
+    /// The sequence of the first transaction in the log.
+    pub sequence: u32,
+    /// The block of the first transaction in the log, or zero if the log is empty.
+    pub start: u32,
+    pub feature_compat: JournalFeatureCompat,
+    pub feature_incompat: JournalFeatureIncompat,
+    raw: Box<[u8]>,
+}
+
+impl JournalSuperBlock {
+    /// Parses the journal superblock from the first block of the journal.
+    pub fn parse(block: &[u8]) -> Result<Self> {
+        let Some((block_type, _)) = parse_header(block) else {
+            return_errno_with_message!(Errno::EINVAL, "bad journal magic number");
+        };
+        let raw: Box<[u8]> = block[..SUPER_BLOCK_SIZE].into();
+
+        let (feature_compat, feature_incompat) = match block_type {
+            BlockType::SuperBlockV1 => (
+                JournalFeatureCompat::empty(),
+                JournalFeatureIncompat::empty(),
+            ),
+            BlockType::SuperBlockV2 => {
+                if be32(&raw, SB_FEATURE_RO_COMPAT_OFFSET) != 0 {
+                    return_errno_with_message!(
+                        Errno::EINVAL,
+                        "unsupported read-only compatible journal features"
+                    );
+                }
+                let feature_incompat =
+                    JournalFeatureIncompat::from_bits(be32(&raw, SB_FEATURE_INCOMPAT_OFFSET))
+                        .ok_or(Error::with_message(
+                            Errno::EINVAL,
+                            "unsupported incompatible journal features",
+                        ))?;
+                (
+                    JournalFeatureCompat::from_bits_truncate(be32(&raw, SB_FEATURE_COMPAT_OFFSET)),
+                    feature_incompat,
+                )
+            }
+            _ => return_errno_with_message!(Errno::EINVAL, "bad journal superblock type"),
+        };
+
+        let journal_sb = Self {
+            maxlen: be32(&raw, SB_MAXLEN_OFFSET),
+            first: be32(&raw, SB_FIRST_OFFSET),
+            sequence: be32(&raw, SB_SEQUENCE_OFFSET),
+            start: be32(&raw, SB_START_OFFSET),
+            feature_compat,
+            feature_incompat,
+            raw,
+        };
+        if be32(&journal_sb.raw, SB_BLOCK_SIZE_OFFSET) as usize != BLOCK_SIZE {
+            return_errno_with_message!(Errno::EINVAL, "unsupported journal block size");
+        }
+        if journal_sb.first == 0 || journal_sb.first >= journal_sb.log_end() {
+            return_errno_with_message!(Errno::EUCLEAN, "invalid journal log area");
+        }
+        if journal_sb.has_csum() {
+            if journal_sb.raw[SB_CHECKSUM_TYPE_OFFSET] != CRC32C_CHECKSUM_TYPE {
+                return_errno_with_message!(Errno::EINVAL, "unsupported journal checksum type");
+            }
+            if journal_sb.checksum() != be32(&journal_sb.raw, SB_CHECKSUM_OFFSET) {
+                return_errno_with_message!(Errno::EBADMSG, "journal superblock checksum mismatch");
+            }
+        }
+        Ok(journal_sb)
+    }
+
+    /// Returns the UUID of the journal.
+    pub fn uuid(&self) -> &[u8] {
+        &self.raw[SB_UUID_OFFSET..SB_UUID_OFFSET + UUID_SIZE]
+    }
+
+    /// Returns the error recorded in the journal, which is non-zero if the journal is aborted.
+    pub fn errno(&self) -> i32 {
+        be32(&self.raw, SB_ERRNO_OFFSET) as i32
+    }
+
+    /// Returns the end of the log area, which excludes the fast commit area.
+    pub fn log_end(&self) -> u32 {
+        if !self
+            .feature_incompat
+            .contains(JournalFeatureIncompat::FAST_COMMIT)
+        {
+            return self.maxlen;
+        }
+        let num_fc_blocks = match be32(&self.raw, SB_NUM_FC_BLOCKS_OFFSET) {
+            0 => DEFAULT_NUM_FC_BLOCKS,
+            num => num,
+        };
+        self.maxlen.saturating_sub(num_fc_blocks)
+    }
+
+    /// Returns the format of the journal blocks.
+    pub fn format(&self) -> JournalFormat {
+        JournalFormat {
+            feature_incompat: self.feature_incompat,
+            csum_seed: self.has_csum().then(|| crc32c(!0, self.uuid())),
+        }
+    }
+
+    /// Converts the superblock to the raw bytes, updating the checksum.
+    pub fn to_raw(&self) -> Box<[u8]> {
+        let mut raw = self.raw.clone();
+        set_be32(&mut raw, SB_SEQUENCE_OFFSET, self.sequence);
+        set_be32(&mut raw, SB_START_OFFSET, self.start);
+        set_be32(
+            &mut raw,
+            SB_FEATURE_COMPAT_OFFSET,
+            self.feature_compat.bits(),
+        );
+        set_be32(
+            &mut raw,
+            SB_FEATURE_INCOMPAT_OFFSET,
+            self.feature_incompat.bits(),
+        );
+        if self.has_csum() {
+            set_be32(&mut raw, SB_CHECKSUM_OFFSET, 0);
+            let checksum = crc32c(!0, &raw);
+            set_be32(&mut raw, SB_CHECKSUM_OFFSET, checksum);
+        }
+        raw
+    }
+
+    fn has_csum(&self) -> bool {
+        self.feature_incompat
+            .intersects(JournalFeatureIncompat::CSUM_V2 | JournalFeatureIncompat::CSUM_V3)
+    }
+
+    fn checksum(&self) -> u32 {
+        let mut raw = self.raw.clone();
+        set_be32(&mut raw, SB_CHECKSUM_OFFSET, 0);
+        crc32c(!0, &raw)
+    }
+}
+
+/// The format of the descriptor, commit and revoke blocks.
+#[derive(Clone, Copy, Debug)]
+pub(super) struct JournalFormat {
+    feature_incompat: JournalFeatureIncompat,
+    /// The seed of the checksums, which is `Some` with the metadata checksums.
+    csum_seed: Option<u32>,
+}
+
+impl JournalFormat {
+    /// Returns the number of tags that always fit in a descriptor block.
+    pub fn tags_per_descriptor(&self) -> usize {
+        (BLOCK_SIZE - HEADER_SIZE - self.tail_size() - UUID_SIZE) / self.tag_size()
+    }
+
+    /// Computes the checksum of a logged block in the transaction of `sequence`.
+    pub fn block_checksum(&self, sequence: u32, block: &[u8]) -> Option<u32> {
+        let csum_seed = self.csum_seed?;
+        let checksum = crc32c(crc32c(csum_seed, &sequence.to_be_bytes()), block);
+        if self.is_csum_v3() {
+            Some(checksum)
+        } else {
+            Some(checksum & 0xffff)
+        }
+    }
+
+    /// Encodes a descriptor block of the transaction of `sequence`.
+    ///
+    /// The tags must fit in the block, and the `SAME_UUID` and `LAST_TAG` flags are set here.
+    pub fn encode_descriptor(&self, sequence: u32, tags: &[Tag], uuid: &[u8]) -> Vec<u8> {
+        debug_assert!(!tags.is_empty() && tags.len() <= self.tags_per_descriptor());
+
+        let mut block = vec![0u8; BLOCK_SIZE];
+        write_header(&mut block, BlockType::Descriptor, sequence);
+        let mut offset = HEADER_SIZE;
+        for (idx, tag) in tags.iter().enumerate() {
+            let mut flags = tag.flags;
+            flags.set(TagFlags::SAME_UUID, idx > 0);
+            flags.set(TagFlags::LAST_TAG, idx == tags.len() - 1);
+            self.encode_tag(
+                &mut block[offset..offset + self.tag_size()],
+                &Tag { flags, ..*tag },
+            );
+            offset += self.tag_size();
+            if idx == 0 {
+                block[offset..offset + UUID_SIZE].copy_from_slice(uuid);
+                offset += UUID_SIZE;
+            }
+        }
+        self.fill_tail(&mut block);
+        block
+    }
+
+    /// Decodes the tags of a descriptor block.
+    ///
+    /// Returns `None` if the checksum of the block mismatches.
+    pub fn decode_descriptor(&self, block: &[u8]) -> Option<Vec<Tag>> {
+        if !self.verify_tail(block) {
+            return None;
+        }
+
+        let end = BLOCK_SIZE - self.tail_size();
+        let mut offset = HEADER_SIZE;
+        let mut tags = Vec::new();
+        while offset + self.tag_size() <= end {
+            let tag = self.decode_tag(&block[offset..offset + self.tag_size()]);
+            offset += self.tag_size();
+            if !tag.flags.contains(TagFlags::SAME_UUID) {
+                offset += UUID_SIZE;
+            }
+            tags.push(tag);
+            if tag.flags.contains(TagFlags::LAST_TAG) {
+                break;
+            }
+        }
+        Some(tags)
+    }
+
+    /// Encodes the commit block of the transaction of `sequence`.
+    pub fn encode_commit(&self, sequence: u32, commit_time: Duration) -> Vec<u8> {
+        let mut block = vec![0u8; BLOCK_SIZE];
+        write_header(&mut block, BlockType::Commit, sequence);
+        block[COMMIT_SEC_OFFSET..COMMIT_SEC_OFFSET + 8]
+            .copy_from_slice(&commit_time.as_secs().to_be_bytes());
+        set_be32(&mut block, COMMIT_NSEC_OFFSET, commit_time.subsec_nanos());
+        if let Some(csum_seed) = self.csum_seed {
+            let checksum = crc32c(csum_seed, &block);
+            set_be32(&mut block, COMMIT_CHECKSUM_OFFSET, checksum);
+        }
+        block
+    }
+
+    /// Verifies the checksum of a commit block.
+    pub fn verify_commit(&self, block: &[u8]) -> bool {
+        let Some(csum_seed) = self.csum_seed else {
+            return true;
+        };
+        let mut block = block.to_vec();
+        let recorded = be32(&block, COMMIT_CHECKSUM_OFFSET);
+        set_be32(&mut block, COMMIT_CHECKSUM_OFFSET, 0);
+        crc32c(csum_seed, &block) == recorded
+    }
+
+    /// Decodes the revoked blocks of a revoke block.
+    ///
+    /// Returns `None` if the block is corrupted.
+    pub fn decode_revoke(&self, block: &[u8]) -> Option<Vec<u64>> {
+        if !self.verify_tail(block) {
+            return None;
+        }
+
+        let count = be32(block, HEADER_SIZE) as usize;
+        if !(REVOKE_HEADER_SIZE..=BLOCK_SIZE - self.tail_size()).contains(&count) {
+            return None;
+        }
+        let record_size = if self.is_64bit() { 8 } else { 4 };
+        let revoked = block[REVOKE_HEADER_SIZE..count]
+            .chunks_exact(record_size)
+            .map(|record| {
+                if self.is_64bit() {
+                    u64::from_be_bytes(record.try_into().unwrap())
+                } else {
+                    be32(record, 0) as u64
+                }
+            })
+            .collect();
+        Some(revoked)
+    }
+
+    fn tag_size(&self) -> usize {
+        if self.is_csum_v3() {
+            return 16;
+        }
+        let mut size = 8;
+        if self.is_64bit() {
+            size += 4;
+        }
+        if self
+            .feature_incompat
+            .contains(JournalFeatureIncompat::CSUM_V2)
+        {
+            size += 2;
+        }
+        size
+    }
+
+    fn encode_tag(&self, buf: &mut [u8], tag: &Tag) {
+        set_be32(buf, 0, tag.bid as u32);
+        if self.is_csum_v3() {
+            set_be32(buf, 4, tag.flags.bits());
+            set_be32(buf, 8, (tag.bid >> 32) as u32);
+            set_be32(buf, 12, tag.checksum);
+            return;
+        }
+        buf[4..6].copy_from_slice(&(tag.checksum as u16).to_be_bytes());
+        buf[6..8].copy_from_slice(&(tag.flags.bits() as u16).to_be_bytes());
+        if self.is_64bit() {
+            set_be32(buf, 8, (tag.bid >> 32) as u32);
+        }
+    }
+
+    fn decode_tag(&self, buf: &[u8]) -> Tag {
+        let bid_lo = be32(buf, 0) as u64;
+        if self.is_csum_v3() {
+            return Tag {
+                bid: ((be32(buf, 8) as u64) << 32) | bid_lo,
+                flags: TagFlags::from_bits_truncate(be32(buf, 4)),
+                checksum: be32(buf, 12),
+            };
+        }
+        let bid_hi = if self.is_64bit() {
+            be32(buf, 8) as u64
+        } else {
+            0
+        };
+        Tag {
+            bid: (bid_hi << 32) | bid_lo,
+            flags: TagFlags::from_bits_truncate(u16::from_be_bytes([buf[6], buf[7]]) as u32),
+            checksum: u16::from_be_bytes([buf[4], buf[5]]) as u32,
+        }
+    }
+
+    fn tail_size(&self) -> usize {
+        if self.csum_seed.is_some() {
+            TAIL_SIZE
+        } else {
+            0
+        }
+    }
+
+    fn fill_tail(&self, block: &mut [u8]) {
+        if let Some(csum_seed) = self.csum_seed {
+            let checksum = crc32c(csum_seed, block);
+            set_be32(block, BLOCK_SIZE - TAIL_SIZE, checksum);
+        }
+    }
+
+    fn verify_tail(&self, block: &[u8]) -> bool {
+        let Some(csum_seed) = self.csum_seed else {
+            return true;
+        };
+        let mut block = block.to_vec();
+        let recorded = be32(&block, BLOCK_SIZE - TAIL_SIZE);
+        set_be32(&mut block, BLOCK_SIZE - TAIL_SIZE, 0);
+        crc32c(csum_seed, &block) == recorded
+    }
+
+    fn is_64bit(&self) -> bool {
+        self.feature_incompat
+            .contains(JournalFeatureIncompat::_64BIT)
+    }
+
+    fn is_csum_v3(&self) -> bool {
+        self.feature_incompat
+            .contains(JournalFeatureIncompat::CSUM_V3)
+    }
+}
+
+/// Parses the header of a journal block.
+///
+/// Returns `None` if the block does not start with a valid header.
+pub(super) fn parse_header(block: &[u8]) -> Option<(BlockType, u32)> {
+    if be32(block, 0) != JOURNAL_MAGIC {
+        return None;
+    }
+    let block_type = BlockType::try_from(be32(block, 4)).ok()?;
+    Some((block_type, be32(block, 8)))
+}
+
+/// Returns whether a logged block must be escaped, since it looks like a journal block.
+pub(super) fn needs_escape(block: &[u8]) -> bool {
+    be32(block, 0) == JOURNAL_MAGIC
+}
+
+/// Escapes or unescapes a logged block by zeroing or restoring the magic number.
+pub(super) fn set_escaped(block: &mut [u8], is_escaped: bool) {
+    let magic = if is_escaped { 0 } else { JOURNAL_MAGIC };
+    set_be32(block, 0, magic);
+}
+
+fn write_header(block: &mut [u8], block_type: BlockType, sequence: u32) {
+    set_be32(block, 0, JOURNAL_MAGIC);
+    set_be32(block, 4, block_type as u32);
+    set_be32(block, 8, sequence);
+}
+
+fn be32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_be_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+fn set_be32(bytes: &mut [u8], offset: usize, val: u32) {
+    bytes[offset..offset + 4].copy_from_slice(&val.to_be_bytes());
+}
+
+#[cfg(ktest)]
+mod test {
+    use ostd::prelude::*;
+
+    use super::*;
+
+    fn new_format(
+        feature_incompat: JournalFeatureIncompat,
+        csum_seed: Option<u32>,
+    ) -> JournalFormat {
+        JournalFormat {
+            feature_incompat,
+            csum_seed,
+        }
+    }
+
+    #[ktest]
+    fn tag_sizes() {
+        use JournalFeatureIncompat as F;
+
+        assert_eq!(new_format(F::empty(), None).tag_size(), 8);
+        assert_eq!(new_format(F::_64BIT, None).tag_size(), 12);
+        assert_eq!(new_format(F::CSUM_V2, Some(0)).tag_size(), 10);
+        assert_eq!(new_format(F::_64BIT | F::CSUM_V2, Some(0)).tag_size(), 14);
+        assert_eq!(new_format(F::CSUM_V3, Some(0)).tag_size(), 16);
+    }
+
+    #[ktest]
+    fn descriptor_round_trip() {
+        let uuid = [0x5au8; UUID_SIZE];
+        for format in [
+            new_format(JournalFeatureIncompat::empty(), None),
+            new_format(JournalFeatureIncompat::CSUM_V2, Some(0x1234_5678)),
+            new_format(
+                JournalFeatureIncompat::CSUM_V3 | JournalFeatureIncompat::_64BIT,
+                Some(0x8765_4321),
+            ),
+        ] {
+            let tags: Vec<Tag> = (0..format.tags_per_descriptor())
+                .map(|idx| Tag {
+                    bid: 100 + idx as u64,
+                    flags: if idx % 2 == 0 {
+                        TagFlags::ESCAPE
+                    } else {
+                        TagFlags::empty()
+                    },
+                    checksum: format.block_checksum(7, &[idx as u8; 16]).unwrap_or(0),
+                })
+                .collect();
+
+            let mut block = format.encode_descriptor(7, &tags, &uuid);
+            assert_eq!(parse_header(&block), Some((BlockType::Descriptor, 7)));
+            let decoded = format.decode_descriptor(&block).unwrap();
+            assert_eq!(decoded.len(), tags.len());
+            for (decoded, tag) in decoded.iter().zip(tags.iter()) {
+                assert_eq!(decoded.bid, tag.bid);
+                assert_eq!(decoded.checksum, tag.checksum);
+                assert_eq!(
+                    decoded.flags & TagFlags::ESCAPE,
+                    tag.flags & TagFlags::ESCAPE
+                );
+            }
+            assert!(decoded.last().unwrap().flags.contains(TagFlags::LAST_TAG));
+
+            if format.csum_seed.is_some() {
+                block[HEADER_SIZE] ^= 1;
+                assert!(format.decode_descriptor(&block).is_none());
+            }
+        }
+    }
+
+    #[ktest]
+    fn commit_checksum() {
+        let format = new_format(JournalFeatureIncompat::CSUM_V3, Some(0xdead_beef));
+        let mut block = format.encode_commit(42, Duration::from_secs(1));
+        assert_eq!(parse_header(&block), Some((BlockType::Commit, 42)));
+        assert!(format.verify_commit(&block));
+        block[COMMIT_SEC_OFFSET] ^= 1;
+        assert!(!format.verify_commit(&block));
+    }
+
+    #[ktest]
+    fn escape() {
+        let mut block = vec![0u8; BLOCK_SIZE];
+        block[..4].copy_from_slice(&JOURNAL_MAGIC.to_be_bytes());
+        assert!(needs_escape(&block));
+        set_escaped(&mut block, true);
+        assert!(!needs_escape(&block));
+        set_escaped(&mut block, false);
+        assert!(needs_escape(&block));
+    }
+}
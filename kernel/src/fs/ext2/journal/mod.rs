@@ -0,0 +1,641 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The journal of Ext3 and Ext4, in the on-disk format of JBD2.
+//!
+//! The journal makes the updates of the metadata atomic. The metadata blocks written
+//! by the filesystem are staged in the running transaction instead of being written
+//! in place. When the filesystem is synced, the transaction is committed: the staged
+//! blocks are logged to the journal, followed by a commit block, and then they are
+//! written to their home locations, which is known as the checkpoint. If the system
+//! crashes after the commit block is written, the transaction is replayed at the next
+//! mount, either by us or by Linux and e2fsck.
+//!
+//! The journal works in the ordered mode: the file data is written in place before the
+//! metadata referring to it is committed, so the metadata never points to stale data.
+//! The inodes whose data blocks are newly allocated are recorded as the ordered inodes,
+//! whose data are written back right before the commit.
+//!
+//! An update of the metadata that stages several blocks, e.g., the syncing of an inode,
+//! is done under a [`JournalHandle`], which reserves the space for the blocks in the
+//! running transaction. The transaction is never committed while a handle is open, so
+//! the update is committed atomically. If there is no space to reserve, the handle
+//! waits for the commit thread of the filesystem to commit the running transaction.
+//!
+//! The log is checkpointed and emptied right after each commit, so it never holds more
+//! than one transaction of ours, and no revoke records are needed. The journals written
+//! by Linux may hold many transactions and revoke records, which are handled by
+//! [`Journal::recover`].
+
+use core::{
+    mem::size_of,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use ostd::{mm::io_util::HasVmReaderWriter, sync::WaitQueue};
+
+use self::format::{
+    needs_escape, set_escaped, JournalFeatureCompat, JournalFormat, JournalSuperBlock, Tag,
+    TagFlags,
+};
+use crate::fs::ext2::{
+    block_group::BlockGroup,
+    block_ptr::{BlockPtrs, Ext2Bid, BID_SIZE, DIRECT_RANGE},
+    extent::{ExtentMapping, ExtentTree},
+    inode::{FileFlags, RawInode},
+    prelude::*,
+    super_block::{RawSuperBlock, SuperBlock, SUPER_BLOCK_OFFSET},
+    utils::now,
+};
+
+mod format;
+mod recovery;
+
+/// The block containing the main superblock of the filesystem.
+const SUPER_BLOCK_BID: Ext2Bid = (SUPER_BLOCK_OFFSET / BLOCK_SIZE) as Ext2Bid;
+
+/// The journal stored in an inode of the filesystem.
+pub(super) struct Journal {
+    block_device: Arc<dyn BlockDevice>,
+    /// The device blocks of the journal, indexed by the journal block number.
+    blocks: Vec<Ext2Bid>,
+    format: JournalFormat,
+    /// The maximal number of metadata blocks in a transaction.
+    max_transaction_blocks: usize,
+    /// The number of blocks in the filesystem.
+    fs_nblocks: Ext2Bid,
+    inner: RwMutex<Inner>,
+    /// The inodes whose data must be written back before the next commit.
+    ordered: Mutex<BTreeSet<u32>>,
+    /// The mutex that serializes the commits.
+    commit_mutex: Mutex<()>,
+    /// The queue of the handles waiting for the space and of the commits waiting
+    /// for the handles to be closed.
+    wait_queue: WaitQueue,
+    commit_request: Arc<CommitRequest>,
+}
+
+struct Inner {
+    superblock: JournalSuperBlock,
+    /// The metadata blocks staged in the running transaction, keyed by the home locations.
+    running: BTreeMap<Ext2Bid, Box<[u8]>>,
+    /// The number of blocks reserved by the open handles.
+    nr_reserved: usize,
+    /// The number of the open handles.
+    nr_handles: usize,
+    /// Whether the running transaction is being committed, during which no handles
+    /// can be opened.
+    is_committing: bool,
+}
+
+/// A handle of an update of the metadata, which is closed when dropped.
+///
+/// See the [module-level documentation](self) for details.
+pub(super) struct JournalHandle<'a> {
+    journal: &'a Journal,
+    nblocks: usize,
+}
+
+impl Drop for JournalHandle<'_> {
+    fn drop(&mut self) {
+        let mut inner = self.journal.inner.write();
+        inner.nr_reserved -= self.nblocks;
+        inner.nr_handles -= 1;
+        drop(inner);
+        self.journal.wait_queue.wake_all();
+    }
+}
+
+/// The requests to commit the running transaction, which are served by the commit thread.
+pub(super) struct CommitRequest {
+    is_requested: AtomicBool,
+    wait_queue: WaitQueue,
+}
+
+impl CommitRequest {
+    fn new() -> Self {
+        Self {
+            is_requested: AtomicBool::new(false),
+            wait_queue: WaitQueue::new(),
+        }
+    }
+
+    /// Waits until a commit is requested, or `timeout` elapses.
+    pub fn wait(&self, timeout: &Duration) {
+        // The commit is done at the timeout anyway, so the error is ignored.
+        let _ = self.wait_queue.wait_until_or_timeout(
+            || {
+                self.is_requested
+                    .swap(false, Ordering::AcqRel)
+                    .then_some(())
+            },
+            timeout,
+        );
+    }
+
+    fn request(&self) {
+        self.is_requested.store(true, Ordering::Release);
+        self.wait_queue.wake_all();
+    }
+}
+
+impl Journal {
+    /// Loads the journal stored in the `journal_ino` inode.
+    ///
+    /// The block groups may be inconsistent before the journal is replayed, so only
+    /// the group descriptors table is used to locate the inode.
+    pub fn load(
+        block_device: Arc<dyn BlockDevice>,
+        journal_ino: u32,
+        super_block: &SuperBlock,
+        group_descriptors_segment: &USegment,
+    ) -> Result<Self> {
+        let raw_inode = {
+            let inodes_per_group = super_block.inodes_per_group();
+            let group_idx = ((journal_ino - 1) / inodes_per_group) as usize;
+            let inode_idx = ((journal_ino - 1) % inodes_per_group) as usize;
+            if group_idx >= super_block.block_groups_count() as usize {
+                return_errno_with_message!(Errno::EUCLEAN, "invalid journal inode");
+            }
+            let inode_table_bid =
+                BlockGroup::inode_table_bid(group_descriptors_segment, group_idx, super_block)?;
+            let offset =
+                inode_table_bid as usize * BLOCK_SIZE + inode_idx * super_block.inode_size();
+            block_device.read_val::<RawInode>(offset)?
+        };
+
+        let read_block = |bid: Ext2Bid, buf: &mut [u8]| -> Result<()> {
+            if bid == 0 || bid >= super_block.total_blocks() {
+                return_errno_with_message!(Errno::EUCLEAN, "invalid journal block");
+            }
+            block_device.read_bytes(bid as usize * BLOCK_SIZE, buf)?;
+            Ok(())
+        };
+        let nblocks = (((raw_inode.size_high as u64) << 32 | raw_inode.size_low as u64)
+            / BLOCK_SIZE as u64) as usize;
+        let blocks = if raw_inode.flags & FileFlags::EXTENTS.bits() != 0 {
+            map_extents(&raw_inode.block_ptrs, nblocks, &read_block)?
+        } else {
+            map_block_ptrs(&raw_inode.block_ptrs, nblocks, &read_block)?
+        };
+        if blocks.is_empty() {
+            return_errno_with_message!(Errno::EUCLEAN, "the journal is empty");
+        }
+
+        let mut superblock = {
+            let mut block = vec![0u8; BLOCK_SIZE];
+            read_block(blocks[0], &mut block)?;
+            JournalSuperBlock::parse(&block)?
+        };
+        if superblock.maxlen as usize > blocks.len() {
+            return_errno_with_message!(Errno::EUCLEAN, "the journal is beyond its inode");
+        }
+        if superblock.errno() != 0 {
+            warn!("the journal was aborted with error {}", superblock.errno());
+        }
+        // The legacy checksums of the commit blocks are neither verified nor written.
+        superblock
+            .feature_compat
+            .remove(JournalFeatureCompat::CHECKSUM);
+
+        let format = superblock.format();
+        let max_transaction_blocks = {
+            // Each transaction needs a commit block, and a descriptor block for every
+            // `tags_per_descriptor` metadata blocks.
+            let log_len = (superblock.log_end() - superblock.first) as usize;
+            let tags_per_descriptor = format.tags_per_descriptor();
+            log_len.saturating_sub(2) * tags_per_descriptor / (tags_per_descriptor + 1)
+        };
+        if max_transaction_blocks == 0 {
+            return_errno_with_message!(Errno::EINVAL, "the journal is too small");
+        }
+
+        Ok(Self {
+            block_device,
+            blocks,
+            format,
+            max_transaction_blocks,
+            fs_nblocks: super_block.total_blocks(),
+            inner: RwMutex::new(Inner {
+                superblock,
+                running: BTreeMap::new(),
+                nr_reserved: 0,
+                nr_handles: 0,
+                is_committing: false,
+            }),
+            ordered: Mutex::new(BTreeSet::new()),
+            commit_mutex: Mutex::new(()),
+            wait_queue: WaitQueue::new(),
+            commit_request: Arc::new(CommitRequest::new()),
+        })
+    }
+
+    /// Returns the requests to the commit thread.
+    pub fn commit_request(&self) -> Arc<CommitRequest> {
+        self.commit_request.clone()
+    }
+
+    /// Opens a handle of an update that stages at most `nblocks` blocks.
+    ///
+    /// If the running transaction does not have enough space, this method requests a
+    /// commit and waits for it. So the caller must not hold the locks that the commit
+    /// needs, e.g., the locks of the inodes, whose data are written back at the commit.
+    pub fn start(&self, nblocks: usize) -> JournalHandle<'_> {
+        let nblocks = nblocks.min(self.max_transaction_blocks);
+        self.wait_queue.wait_until(|| {
+            let mut inner = self.inner.write();
+            if !inner.is_committing
+                && inner.running.len() + inner.nr_reserved + nblocks <= self.max_transaction_blocks
+            {
+                inner.nr_reserved += nblocks;
+                inner.nr_handles += 1;
+                return Some(());
+            }
+            drop(inner);
+            self.commit_request.request();
+            None
+        });
+        JournalHandle {
+            journal: self,
+            nblocks,
+        }
+    }
+
+    /// Adds the inode `ino` to the ordered inodes, whose data are written back
+    /// before the next commit.
+    pub fn add_ordered_inode(&self, ino: u32) {
+        self.ordered.lock().insert(ino);
+    }
+
+    /// Stages a metadata block in the running transaction.
+    ///
+    /// The transaction is never committed here, since the other blocks of the update
+    /// may not have been staged yet. If the transaction is full, the block is not staged
+    /// and a commit is requested. The blocks staged under handles never fill up the
+    /// transaction unless the other blocks, e.g., those of the directories written back
+    /// in the background, have taken the reserved space.
+    pub fn stage(&self, bid: Ext2Bid, block: &[u8]) -> Result<()> {
+        let mut inner = self.inner.write();
+        self.stage_locked(&mut inner, bid, block.into())
+    }
+
+    /// Stages the main superblock of the filesystem in the running transaction.
+    ///
+    /// The superblock shares its block with the boot sector, which is kept unchanged.
+    pub fn stage_super_block(&self, raw_super_block: &RawSuperBlock) -> Result<()> {
+        let mut inner = self.inner.write();
+        let mut block = match inner.running.get(&SUPER_BLOCK_BID) {
+            Some(block) => block.clone(),
+            None => {
+                let mut block = vec![0u8; BLOCK_SIZE].into_boxed_slice();
+                self.block_device
+                    .read_bytes(SUPER_BLOCK_BID as usize * BLOCK_SIZE, &mut block)?;
+                block
+            }
+        };
+        let offset = SUPER_BLOCK_OFFSET % BLOCK_SIZE;
+        block[offset..offset + size_of::<RawSuperBlock>()]
+            .copy_from_slice(raw_super_block.as_bytes());
+        self.stage_locked(&mut inner, SUPER_BLOCK_BID, block)
+    }
+
+    fn stage_locked(&self, inner: &mut Inner, bid: Ext2Bid, block: Box<[u8]>) -> Result<()> {
+        debug_assert_eq!(block.len(), BLOCK_SIZE);
+        if !inner.running.contains_key(&bid) && inner.running.len() >= self.max_transaction_blocks {
+            self.commit_request.request();
+            return_errno_with_message!(Errno::ENOSPC, "the running transaction is full");
+        }
+        inner.running.insert(bid, block);
+
+        // Commits early, so that the handles rarely wait for the space.
+        if inner.running.len() + inner.nr_reserved >= self.max_transaction_blocks / 2 {
+            self.commit_request.request();
+        }
+        Ok(())
+    }
+
+    /// Drops the staged blocks in `range`, which have been freed.
+    ///
+    /// The freed blocks may be reused for the file data, which must not be overwritten
+    /// by the stale metadata at the checkpoint.
+    pub fn forget(&self, range: Range<Ext2Bid>) {
+        let mut inner = self.inner.write();
+        let staged: Vec<Ext2Bid> = inner.running.range(range).map(|(bid, _)| *bid).collect();
+        for bid in staged {
+            inner.running.remove(&bid);
+        }
+    }
+
+    /// Reads contiguous blocks starting from the `bid` synchronously,
+    /// with the staged blocks taking the place of the blocks on the device.
+    pub fn read_blocks(&self, bid: Ext2Bid, bio_segment: BioSegment) -> Result<()> {
+        // Holds the lock, so that the staged blocks cannot be checkpointed
+        // while reading the stale blocks on the device.
+        let inner = self.inner.read();
+        match self
+            .block_device
+            .read_blocks(Bid::new(bid as u64), bio_segment.clone())?
+        {
+            BioStatus::Complete => (),
+            err_status => return Err(Error::from(err_status)),
+        }
+
+        let range = bid..bid + bio_segment.nblocks() as Ext2Bid;
+        for (staged_bid, block) in inner.running.range(range) {
+            let mut writer = bio_segment.writer()?;
+            writer.skip((staged_bid - bid) as usize * BLOCK_SIZE);
+            writer.write(&mut VmReader::from(&block[..]));
+        }
+        Ok(())
+    }
+
+    /// Reads contiguous blocks starting from the `bid` asynchronously.
+    ///
+    /// If some of the blocks are staged, they are read synchronously instead.
+    pub fn read_blocks_async(&self, bid: Ext2Bid, bio_segment: BioSegment) -> Result<BioWaiter> {
+        let range = bid..bid + bio_segment.nblocks() as Ext2Bid;
+        let inner = self.inner.read();
+        if inner.running.range(range).next().is_some() {
+            drop(inner);
+            self.read_blocks(bid, bio_segment)?;
+            return Ok(BioWaiter::new());
+        }
+
+        let waiter = self
+            .block_device
+            .read_blocks_async(Bid::new(bid as u64), bio_segment)?;
+        Ok(waiter)
+    }
+
+    /// Commits the running transaction, and checkpoints it.
+    ///
+    /// The data of the ordered inodes are written back by `write_back_data` first.
+    /// This method waits for the open handles to be closed, so the caller must not
+    /// hold a handle.
+    pub fn commit(&self, write_back_data: impl Fn(u32) -> Result<()>) -> Result<()> {
+        let _guard = self.commit_mutex.lock();
+
+        // Stops opening new handles, and waits for the open ones to stage their blocks.
+        self.wait_queue.wait_until(|| {
+            let mut inner = self.inner.write();
+            inner.is_committing = true;
+            (inner.nr_handles == 0).then_some(())
+        });
+
+        let result = self.commit_ordered(write_back_data);
+
+        self.inner.write().is_committing = false;
+        self.wait_queue.wake_all();
+        result
+    }
+
+    fn commit_ordered(&self, write_back_data: impl Fn(u32) -> Result<()>) -> Result<()> {
+        if self.inner.read().running.is_empty() {
+            return Ok(());
+        }
+
+        // The inodes are added to the ordered ones before the metadata pointing to their
+        // new data blocks are staged. So the data of all the inodes whose metadata are in
+        // the transaction are written back here. The inodes added later are written back
+        // before the next commit, since no handle can stage their metadata until then.
+        //
+        // The data are written back without holding the lock, since the writeback
+        // may read the staged blocks, e.g., the indirect blocks.
+        let ordered = core::mem::take(&mut *self.ordered.lock());
+        for ino in ordered.iter() {
+            if let Err(err) = write_back_data(*ino) {
+                self.ordered.lock().extend(ordered);
+                return Err(err);
+            }
+        }
+
+        let mut inner = self.inner.write();
+        self.commit_locked(&mut inner)
+    }
+
+    fn commit_locked(&self, inner: &mut Inner) -> Result<()> {
+        if inner.running.is_empty() {
+            return Ok(());
+        }
+
+        // The superblock in the transaction tells that the journal needs recovery,
+        // until the log is emptied after the checkpoint.
+        if let Some(block) = inner.running.get_mut(&SUPER_BLOCK_BID) {
+            let offset = SUPER_BLOCK_OFFSET % BLOCK_SIZE;
+            let raw_super_block = &mut block[offset..offset + size_of::<RawSuperBlock>()];
+            let mut super_block = RawSuperBlock::from_bytes(raw_super_block);
+            super_block.set_needs_recovery(true);
+            raw_super_block.copy_from_slice(super_block.as_bytes());
+        }
+
+        let sequence = inner.superblock.sequence;
+        let first = inner.superblock.first;
+        let uuid = inner.superblock.uuid().to_vec();
+
+        // Logs the metadata blocks, each group of which follows a descriptor block.
+        let mut bio_waiter = BioWaiter::new();
+        let mut pos = first;
+        let staged: Vec<(Ext2Bid, &[u8])> = inner
+            .running
+            .iter()
+            .map(|(bid, block)| (*bid, &block[..]))
+            .collect();
+        for group in staged.chunks(self.format.tags_per_descriptor()) {
+            let mut logged_blocks = Vec::with_capacity(group.len());
+            let mut tags = Vec::with_capacity(group.len());
+            for (bid, block) in group {
+                let mut logged = block.to_vec();
+                let mut flags = TagFlags::empty();
+                if needs_escape(&logged) {
+                    set_escaped(&mut logged, true);
+                    flags |= TagFlags::ESCAPE;
+                }
+                tags.push(Tag {
+                    bid: *bid as u64,
+                    flags,
+                    checksum: self.format.block_checksum(sequence, &logged).unwrap_or(0),
+                });
+                logged_blocks.push(logged);
+            }
+
+            let descriptor = self.format.encode_descriptor(sequence, &tags, &uuid);
+            bio_waiter.concat(self.write_log_block_async(pos, &descriptor)?);
+            pos += 1;
+            for logged in logged_blocks {
+                bio_waiter.concat(self.write_log_block_async(pos, &logged)?);
+                pos += 1;
+            }
+        }
+        debug_assert!(pos < inner.superblock.log_end());
+
+        // Points the journal to the transaction. The filesystem must tell that the
+        // journal needs recovery, or Linux will discard the journal without replaying it.
+        inner.superblock.start = first;
+        bio_waiter.concat(self.write_superblock_async(&inner.superblock)?);
+        bio_waiter.concat(self.set_fs_needs_recovery_async(true)?);
+        wait(bio_waiter)?;
+        self.flush()?;
+
+        // Writes the commit block after all the other blocks are durable.
+        let commit = self.format.encode_commit(sequence, now());
+        wait(self.write_log_block_async(pos, &commit)?)?;
+        self.flush()?;
+
+        // Checkpoints the transaction.
+        let mut bio_waiter = BioWaiter::new();
+        for (bid, block) in inner.running.iter() {
+            bio_waiter.concat(
+                self.block_device
+                    .write_bytes_async(*bid as usize * BLOCK_SIZE, block)?,
+            );
+        }
+        wait(bio_waiter)?;
+        self.flush()?;
+
+        // Empties the log.
+        inner.superblock.start = 0;
+        inner.superblock.sequence = sequence.wrapping_add(1);
+        let mut bio_waiter = self.write_superblock_async(&inner.superblock)?;
+        bio_waiter.concat(self.set_fs_needs_recovery_async(false)?);
+        wait(bio_waiter)?;
+        self.flush()?;
+
+        inner.running.clear();
+        Ok(())
+    }
+
+    fn read_log_block(&self, pos: u32, buf: &mut [u8]) -> Result<()> {
+        let bid = self.blocks[pos as usize];
+        self.block_device
+            .read_bytes(bid as usize * BLOCK_SIZE, buf)?;
+        Ok(())
+    }
+
+    fn write_log_block_async(&self, pos: u32, block: &[u8]) -> Result<BioWaiter> {
+        let bid = self.blocks[pos as usize];
+        let waiter = self
+            .block_device
+            .write_bytes_async(bid as usize * BLOCK_SIZE, block)?;
+        Ok(waiter)
+    }
+
+    fn write_superblock_async(&self, superblock: &JournalSuperBlock) -> Result<BioWaiter> {
+        self.write_log_block_async(0, &superblock.to_raw())
+    }
+
+    /// Returns whether the filesystem tells that the journal needs recovery.
+    fn fs_needs_recovery(&self) -> Result<bool> {
+        let raw_super_block = self
+            .block_device
+            .read_val::<RawSuperBlock>(SUPER_BLOCK_OFFSET)?;
+        Ok(raw_super_block.needs_recovery())
+    }
+
+    /// Sets or clears the flag of the filesystem telling that the journal needs recovery.
+    ///
+    /// The flag is changed in place, so the other fields of the superblock are unchanged.
+    fn set_fs_needs_recovery_async(&self, needs_recovery: bool) -> Result<BioWaiter> {
+        let mut raw_super_block = self
+            .block_device
+            .read_val::<RawSuperBlock>(SUPER_BLOCK_OFFSET)?;
+        raw_super_block.set_needs_recovery(needs_recovery);
+        let waiter = self
+            .block_device
+            .write_bytes_async(SUPER_BLOCK_OFFSET, raw_super_block.as_bytes())?;
+        Ok(waiter)
+    }
+
+    fn flush(&self) -> Result<()> {
+        match self.block_device.sync()? {
+            BioStatus::Complete => Ok(()),
+            err_status => Err(Error::from(err_status)),
+        }
+    }
+}
+
+impl Debug for Journal {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_struct("Journal")
+            .field("nblocks", &self.blocks.len())
+            .field("max_transaction_blocks", &self.max_transaction_blocks)
+            .field("running", &self.inner.read().running.len())
+            .field("ordered", &self.ordered.lock().len())
+            .finish()
+    }
+}
+
+fn wait(bio_waiter: BioWaiter) -> Result<()> {
+    bio_waiter
+        .wait()
+        .ok_or_else(|| Error::with_message(Errno::EIO, "failed to write the journal"))?;
+    Ok(())
+}
+
+/// Maps the blocks of an extent-mapped journal inode to the device blocks.
+fn map_extents(
+    root: &BlockPtrs,
+    nblocks: usize,
+    read_block: &dyn Fn(Ext2Bid, &mut [u8]) -> Result<()>,
+) -> Result<Vec<Ext2Bid>> {
+    let extent_tree = ExtentTree::load(root, read_block, None)?;
+    let mut blocks = Vec::with_capacity(nblocks);
+    while blocks.len() < nblocks {
+        let start = blocks.len() as Ext2Bid;
+        match extent_tree.lookup(start..nblocks as Ext2Bid) {
+            ExtentMapping::Mapped(range) => blocks.extend(range),
+            _ => return_errno_with_message!(Errno::EUCLEAN, "the journal has holes"),
+        }
+    }
+    Ok(blocks)
+}
+
+/// Maps the blocks of a block-mapped journal inode, which is created by Ext3,
+/// to the device blocks.
+fn map_block_ptrs(
+    block_ptrs: &BlockPtrs,
+    nblocks: usize,
+    read_block: &dyn Fn(Ext2Bid, &mut [u8]) -> Result<()>,
+) -> Result<Vec<Ext2Bid>> {
+    let mut blocks: Vec<Ext2Bid> = DIRECT_RANGE
+        .map(|idx| block_ptrs.direct(idx))
+        .take(nblocks)
+        .collect();
+    for (bid, level) in [
+        (block_ptrs.indirect(), 1),
+        (block_ptrs.db_indirect(), 2),
+        (block_ptrs.tb_indirect(), 3),
+    ] {
+        map_indirect_block(bid, level, nblocks, &mut blocks, read_block)?;
+    }
+
+    if blocks.len() < nblocks || blocks.contains(&0) {
+        return_errno_with_message!(Errno::EUCLEAN, "the journal has holes");
+    }
+    Ok(blocks)
+}
+
+fn map_indirect_block(
+    bid: Ext2Bid,
+    level: usize,
+    nblocks: usize,
+    blocks: &mut Vec<Ext2Bid>,
+    read_block: &dyn Fn(Ext2Bid, &mut [u8]) -> Result<()>,
+) -> Result<()> {
+    if blocks.len() >= nblocks {
+        return Ok(());
+    }
+
+    let mut block = vec![0u8; BLOCK_SIZE];
+    read_block(bid, &mut block)?;
+    for ptr in block.chunks_exact(BID_SIZE) {
+        if blocks.len() >= nblocks {
+            break;
+        }
+        let ptr = Ext2Bid::from_le_bytes(ptr.try_into().unwrap());
+        if level == 1 {
+            blocks.push(ptr);
+        } else {
+            map_indirect_block(ptr, level - 1, nblocks, blocks, read_block)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,223 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The recovery of the journal.
+//!
+//! The log is scanned from its start to find the committed transactions. A transaction
+//! is committed if its commit block is found with the expected sequence and a valid
+//! checksum. Then the latest logged copy of each block is written to its home location,
+//! unless the block is revoked by the same or a later transaction.
+
+use super::{
+    format::{
+        parse_header, set_escaped, BlockType, JournalFeatureIncompat, JournalSuperBlock, Tag,
+        TagFlags,
+    },
+    wait, Journal,
+};
+use crate::fs::ext2::{block_ptr::Ext2Bid, prelude::*};
+
+/// A committed transaction found in the log.
+struct Transaction {
+    sequence: u32,
+    /// The tags of the logged blocks, with the positions of the blocks in the log.
+    blocks: Vec<(Tag, u32)>,
+    /// The blocks whose logged copies in this and the earlier transactions are revoked.
+    revoked: Vec<u64>,
+}
+
+impl Transaction {
+    fn new(sequence: u32) -> Self {
+        Self {
+            sequence,
+            blocks: Vec::new(),
+            revoked: Vec::new(),
+        }
+    }
+}
+
+impl Journal {
+    /// Replays the committed transactions in the journal, and empties the log.
+    ///
+    /// Returns whether the filesystem has been changed on the device, in which case
+    /// the metadata loaded before must be loaded again.
+    pub fn recover(&self) -> Result<bool> {
+        let mut inner = self.inner.write();
+        let fs_needs_recovery = self.fs_needs_recovery()?;
+        if inner.superblock.start == 0 {
+            if !fs_needs_recovery {
+                return Ok(false);
+            }
+            wait(self.set_fs_needs_recovery_async(false)?)?;
+            self.flush()?;
+            return Ok(true);
+        }
+
+        let next_sequence = if fs_needs_recovery {
+            if inner
+                .superblock
+                .feature_incompat
+                .contains(JournalFeatureIncompat::FAST_COMMIT)
+            {
+                return_errno_with_message!(
+                    Errno::EINVAL,
+                    "the journal with fast commits cannot be replayed"
+                );
+            }
+
+            let (transactions, next_sequence) = self.scan(&inner.superblock)?;
+            self.replay(&transactions)?;
+            next_sequence
+        } else {
+            // Like Linux, the log is discarded if the filesystem does not need recovery.
+            warn!("the journal is not empty, but the filesystem does not need recovery");
+            inner.superblock.sequence
+        };
+
+        // Skips a sequence, so that the stale blocks of a partial transaction
+        // are never mistaken for the next transaction.
+        inner.superblock.start = 0;
+        inner.superblock.sequence = next_sequence.wrapping_add(1);
+        let mut bio_waiter = self.write_superblock_async(&inner.superblock)?;
+        bio_waiter.concat(self.set_fs_needs_recovery_async(false)?);
+        wait(bio_waiter)?;
+        self.flush()?;
+        Ok(true)
+    }
+
+    /// Scans the log from its start, returning the committed transactions
+    /// and the sequence of the first transaction that is not committed.
+    fn scan(&self, superblock: &JournalSuperBlock) -> Result<(Vec<Transaction>, u32)> {
+        let start = superblock.start;
+        let log_len = superblock.log_end() - superblock.first;
+        if !(superblock.first..superblock.log_end()).contains(&start) {
+            return_errno_with_message!(Errno::EUCLEAN, "invalid start of the journal");
+        }
+        let next_pos = |pos: u32| {
+            if pos + 1 == superblock.log_end() {
+                superblock.first
+            } else {
+                pos + 1
+            }
+        };
+
+        let mut committed = Vec::new();
+        let mut running = Transaction::new(superblock.sequence);
+        let mut block = vec![0u8; BLOCK_SIZE];
+        let mut pos = start;
+        // A valid log never wraps around itself, which also bounds a corrupted log.
+        let mut nscanned = 0;
+        while nscanned < log_len {
+            self.read_log_block(pos, &mut block)?;
+            let Some((block_type, sequence)) = parse_header(&block) else {
+                break;
+            };
+            if sequence != running.sequence {
+                break;
+            }
+
+            match block_type {
+                BlockType::Descriptor => {
+                    let Some(tags) = self.format.decode_descriptor(&block) else {
+                        break;
+                    };
+                    for tag in tags {
+                        pos = next_pos(pos);
+                        nscanned += 1;
+                        running.blocks.push((tag, pos));
+                    }
+                }
+                BlockType::Commit => {
+                    if !self.format.verify_commit(&block) {
+                        break;
+                    }
+                    let next = Transaction::new(running.sequence.wrapping_add(1));
+                    committed.push(core::mem::replace(&mut running, next));
+                }
+                BlockType::Revoke => {
+                    let Some(revoked) = self.format.decode_revoke(&block) else {
+                        break;
+                    };
+                    running.revoked.extend(revoked);
+                }
+                BlockType::SuperBlockV1 | BlockType::SuperBlockV2 => break,
+            }
+            pos = next_pos(pos);
+            nscanned += 1;
+        }
+
+        Ok((committed, running.sequence))
+    }
+
+    /// Writes the latest logged copies of the blocks in the committed transactions
+    /// to their home locations.
+    fn replay(&self, transactions: &[Transaction]) -> Result<()> {
+        // The sequence of the latest transaction revoking each block.
+        let mut revoked = BTreeMap::new();
+        for transaction in transactions {
+            for bid in transaction.revoked.iter() {
+                revoked.insert(*bid, transaction.sequence);
+            }
+        }
+
+        let mut latest = BTreeMap::new();
+        for transaction in transactions {
+            for (tag, pos) in transaction.blocks.iter() {
+                let is_revoked = revoked.get(&tag.bid).is_some_and(|revoke_sequence| {
+                    !is_sequence_after(transaction.sequence, *revoke_sequence)
+                });
+                if !is_revoked {
+                    latest.insert(tag.bid, (transaction.sequence, *tag, *pos));
+                }
+            }
+        }
+
+        let mut bio_waiter = BioWaiter::new();
+        for (bid, (sequence, tag, pos)) in latest {
+            let bid = Ext2Bid::try_from(bid)
+                .ok()
+                .filter(|bid| *bid < self.fs_nblocks)
+                .ok_or_else(|| Error::with_message(Errno::EUCLEAN, "invalid logged block"))?;
+
+            let mut block = vec![0u8; BLOCK_SIZE];
+            self.read_log_block(pos, &mut block)?;
+            if self
+                .format
+                .block_checksum(sequence, &block)
+                .is_some_and(|checksum| checksum != tag.checksum)
+            {
+                warn!("the logged block {} has a bad checksum, skipping it", bid);
+                continue;
+            }
+            if tag.flags.contains(TagFlags::ESCAPE) {
+                set_escaped(&mut block, false);
+            }
+            bio_waiter.concat(
+                self.block_device
+                    .write_bytes_async(bid as usize * BLOCK_SIZE, &block)?,
+            );
+        }
+        wait(bio_waiter)?;
+        self.flush()
+    }
+}
+
+/// Returns whether the transaction of sequence `a` is after that of `b`,
+/// taking the wrapping around into account.
+fn is_sequence_after(a: u32, b: u32) -> bool {
+    (a.wrapping_sub(b) as i32) > 0
+}
+
+#[cfg(ktest)]
+mod test {
+    use ostd::prelude::*;
+
+    use super::*;
+
+    #[ktest]
+    fn sequence_order() {
+        assert!(is_sequence_after(2, 1));
+        assert!(!is_sequence_after(1, 1));
+        assert!(!is_sequence_after(1, 2));
+        assert!(is_sequence_after(0, u32::MAX));
+    }
+}
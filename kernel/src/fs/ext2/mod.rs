@@ -7,6 +7,14 @@
 //! The structures of Ext3 and Ext4 are based on Ext2 and add some additional options
 //! such as journaling.
 //!
+//! The same implementation also mounts Ext4 filesystems (see `Ext2::open_ext4`),
+//! supporting the extent trees, flexible block groups, 64-bit group descriptors,
+//! huge files, the hash tree lookup of directories and the metadata checksums.
+//!
+//! If the filesystem has a journal (e.g., Ext3 and Ext4), the updates of the metadata
+//! are journaled in the ordered mode, and the journal is replayed at mount time.
+//! The journal is in the JBD2 format, so it can also be replayed by Linux and e2fsck.
+//!
 //! The features of this version of Ext2 are as follows:
 //! 1. No unsafe Rust. The filesystem is written is Rust without any unsafe code,
 //!    ensuring that there are no memory safety issues in the code.
@@ -35,22 +43,29 @@
 //! Here we summarizes the features that need to be implemented in the future.
 //! 1. Supports merging small read/write operations.
 //! 2. Handles the intermediate failure status correctly.
+//! 3. Supports the external journal devices, the fast commits, the block numbers beyond
+//!    32 bits and the block sizes other than 4096 bytes.
+//! 4. Maintains the hash tree index of directories, which is dropped on modifications now.
 
 use alloc::sync::Arc;
 
 pub use fs::Ext2;
 pub use inode::{FilePerm, Inode};
-pub use super_block::{SuperBlock, MAGIC_NUM};
+pub use super_block::{ExtVersion, SuperBlock, MAGIC_NUM};
 
-use crate::fs::ext2::fs::Ext2Type;
+use crate::fs::ext2::fs::{Ext2Type, Ext4Type};
 
 mod block_group;
 mod block_ptr;
+mod csum;
 mod dir;
+mod extent;
 mod fs;
+mod htree;
 mod impl_for_vfs;
 mod indirect_block_cache;
 mod inode;
+mod journal;
 mod prelude;
 mod super_block;
 mod utils;
@@ -59,4 +74,6 @@ mod xattr;
 pub(super) fn init() {
     let ext2_type = Arc::new(Ext2Type);
     super::registry::register(ext2_type).unwrap();
+    let ext4_type = Arc::new(Ext4Type);
+    super::registry::register(ext4_type).unwrap();
 }
@@ -2,7 +2,7 @@
 
 use ostd::const_assert;
 
-use super::{inode::RawInode, prelude::*};
+use super::{csum::crc32c, inode::RawInode, prelude::*};
 
 /// The magic number of Ext2.
 pub const MAGIC_NUM: u16 = 0xef53;
@@ -93,12 +93,81 @@ pub struct SuperBlock {
     prealloc_file_blocks: u8,
     /// Number of blocks to preallocate for directories.
     prealloc_dir_blocks: u8,
+    //
+    // These fields are introduced by Ext3 and Ext4.
+    //
+    /// Number of reserved group descriptor blocks for online growth.
+    reserved_gdt_blocks: u16,
+    /// Size of the group descriptor.
+    desc_size: usize,
+    /// Desired extra size of new inodes.
+    want_extra_isize: u16,
+    /// HTREE hash seed.
+    hash_seed: [u32; 4],
+    /// Default hash version to use.
+    def_hash_version: u8,
+    /// Miscellaneous flags.
+    flags: SuperBlockFlags,
+    /// Block groups containing the superblock backups if `SPARSE_SUPER2` is set.
+    backup_bgs: [u32; 2],
+    /// The seed of the metadata checksums if `METADATA_CSUM` is set.
+    csum_seed: Option<u32>,
+    /// The raw superblock loaded from the device.
+    ///
+    /// It keeps the fields that are not interpreted by us,
+    /// so they can be written back unchanged.
+    raw: RawSuperBlock,
 }
 
 impl TryFrom<RawSuperBlock> for SuperBlock {
     type Error = crate::error::Error;
 
     fn try_from(sb: RawSuperBlock) -> Result<Self> {
+        if sb.magic != MAGIC_NUM {
+            return_errno_with_message!(Errno::EINVAL, "bad ext2 magic number");
+        }
+        let feature_ro_compat = FeatureRoCompatSet::from_bits(sb.feature_ro_compat).ok_or(
+            Error::with_message(Errno::EINVAL, "invalid feature ro compat set"),
+        )?;
+        let feature_incompat = FeatureInCompatSet::from_bits(sb.feature_incompat).ok_or(
+            Error::with_message(Errno::EINVAL, "invalid feature incompat set"),
+        )?;
+
+        let csum_seed = if feature_ro_compat.contains(FeatureRoCompatSet::METADATA_CSUM) {
+            if sb.checksum_type != CHECKSUM_TYPE_CRC32C {
+                return_errno_with_message!(Errno::EINVAL, "unknown checksum type");
+            }
+            if sb.checksum != sb.compute_checksum() {
+                return_errno_with_message!(Errno::EBADMSG, "superblock checksum mismatch");
+            }
+            if feature_incompat.contains(FeatureInCompatSet::CSUM_SEED) {
+                Some(sb.checksum_seed)
+            } else {
+                Some(crc32c(!0, &sb.uuid))
+            }
+        } else {
+            None
+        };
+
+        if sb.blocks_count_hi != 0 {
+            return_errno_with_message!(
+                Errno::EFBIG,
+                "block numbers exceeding 32 bits are not supported"
+            );
+        }
+
+        let desc_size = if feature_incompat.contains(FeatureInCompatSet::_64BIT) {
+            let desc_size = sb.desc_size as usize;
+            if !(MIN_DESC_SIZE_64BIT..=MAX_DESC_SIZE).contains(&desc_size)
+                || !desc_size.is_power_of_two()
+            {
+                return_errno_with_message!(Errno::EINVAL, "invalid group descriptor size");
+            }
+            desc_size
+        } else {
+            MIN_DESC_SIZE
+        };
+
         Ok(Self {
             inodes_count: sb.inodes_count,
             blocks_count: sb.blocks_count,
@@ -115,12 +184,7 @@ impl TryFrom<RawSuperBlock> for SuperBlock {
             wtime: sb.wtime,
             mnt_count: sb.mnt_count,
             max_mnt_count: sb.max_mnt_count,
-            magic: {
-                if sb.magic != MAGIC_NUM {
-                    return_errno_with_message!(Errno::EINVAL, "bad ext2 magic number");
-                }
-                MAGIC_NUM
-            },
+            magic: MAGIC_NUM,
             state: {
                 let state = FsState::try_from(sb.state)
                     .map_err(|_| Error::with_message(Errno::EINVAL, "invalid fs state"))?;
@@ -163,17 +227,22 @@ impl TryFrom<RawSuperBlock> for SuperBlock {
             feature_compat: FeatureCompatSet::from_bits(sb.feature_compat).ok_or(
                 Error::with_message(Errno::EINVAL, "invalid feature compat set"),
             )?,
-            feature_incompat: FeatureInCompatSet::from_bits(sb.feature_incompat).ok_or(
-                Error::with_message(Errno::EINVAL, "invalid feature incompat set"),
-            )?,
-            feature_ro_compat: FeatureRoCompatSet::from_bits(sb.feature_ro_compat).ok_or(
-                Error::with_message(Errno::EINVAL, "invalid feature ro compat set"),
-            )?,
+            feature_incompat,
+            feature_ro_compat,
             uuid: sb.uuid,
             volume_name: sb.volume_name,
             last_mounted_dir: sb.last_mounted_dir,
             prealloc_file_blocks: sb.prealloc_file_blocks,
             prealloc_dir_blocks: sb.prealloc_dir_blocks,
+            reserved_gdt_blocks: sb.reserved_gdt_blocks,
+            desc_size,
+            want_extra_isize: sb.want_extra_isize,
+            hash_seed: sb.hash_seed,
+            def_hash_version: sb.def_hash_version,
+            flags: SuperBlockFlags::from_bits_truncate(sb.flags),
+            backup_bgs: sb.backup_bgs,
+            csum_seed,
+            raw: sb,
         })
     }
 }
@@ -215,8 +284,121 @@ impl SuperBlock {
     }
 
     /// Returns the number of block groups.
+    ///
+    /// The last block group may contain fewer blocks than the others.
     pub fn block_groups_count(&self) -> u32 {
-        self.blocks_count / self.blocks_per_group
+        (self.blocks_count - self.first_data_block.to_raw() as u32).div_ceil(self.blocks_per_group)
+    }
+
+    /// Returns the size of the block group descriptor.
+    pub fn desc_size(&self) -> usize {
+        self.desc_size
+    }
+
+    /// Returns the number of blocks occupied by the block group descriptor table.
+    pub fn group_descriptors_blocks(&self) -> u32 {
+        ((self.block_groups_count() as usize) * self.desc_size).div_ceil(self.block_size) as u32
+    }
+
+    /// Returns the number of reserved group descriptor blocks following the descriptor table.
+    pub fn reserved_gdt_blocks(&self) -> u32 {
+        if self.feature_compat.contains(FeatureCompatSet::RESIZE_INO) {
+            self.reserved_gdt_blocks as u32
+        } else {
+            0
+        }
+    }
+
+    /// Returns the desired extra size of new inodes.
+    pub fn want_extra_isize(&self) -> u16 {
+        self.want_extra_isize
+    }
+
+    /// Returns the seed of the metadata checksums.
+    ///
+    /// It is `None` if the metadata checksums are disabled.
+    pub fn csum_seed(&self) -> Option<u32> {
+        self.csum_seed
+    }
+
+    /// Returns whether the group descriptors are protected by the CRC16 checksums.
+    pub fn has_gdt_csum(&self) -> bool {
+        self.csum_seed.is_none()
+            && self
+                .feature_ro_compat
+                .contains(FeatureRoCompatSet::GDT_CSUM)
+    }
+
+    /// Returns whether some block groups may have uninitialized bitmaps or inode tables.
+    pub fn has_uninit_groups(&self) -> bool {
+        self.csum_seed.is_some() || self.has_gdt_csum()
+    }
+
+    /// Returns the HTREE hash seed.
+    pub fn hash_seed(&self) -> [u32; 4] {
+        self.hash_seed
+    }
+
+    /// Returns the default HTREE hash version.
+    pub fn def_hash_version(&self) -> u8 {
+        self.def_hash_version
+    }
+
+    /// Returns whether the HTREE hashes of the legacy versions treat `char` as unsigned.
+    pub fn is_unsigned_hash(&self) -> bool {
+        self.flags.contains(SuperBlockFlags::UNSIGNED_HASH)
+    }
+
+    /// Returns the UUID of the filesystem.
+    pub fn uuid(&self) -> &[u8; 16] {
+        &self.uuid
+    }
+
+    /// Returns the inode number of the journal.
+    ///
+    /// Returns `None` if there is no journal or the journal is on an external device.
+    pub fn journal_ino(&self) -> Option<u32> {
+        if !self.feature_compat.contains(FeatureCompatSet::HAS_JOURNAL)
+            || self
+                .feature_incompat
+                .contains(FeatureInCompatSet::JOURNAL_DEV)
+            || self.raw.journal_ino == 0
+        {
+            return None;
+        }
+        Some(self.raw.journal_ino)
+    }
+
+    /// Checks whether the features of the filesystem are supported by `version`.
+    pub fn check_features(&self, version: ExtVersion) -> Result<()> {
+        let (incompat_supported, ro_compat_supported) = match version {
+            ExtVersion::Ext2 => (
+                FeatureInCompatSet::EXT2_SUPPORTED,
+                FeatureRoCompatSet::EXT2_SUPPORTED,
+            ),
+            ExtVersion::Ext4 => (
+                FeatureInCompatSet::EXT4_SUPPORTED,
+                FeatureRoCompatSet::EXT4_SUPPORTED,
+            ),
+        };
+
+        if !incompat_supported.contains(self.feature_incompat) {
+            return_errno_with_message!(Errno::EINVAL, "unsupported incompatible features");
+        }
+        if !ro_compat_supported.contains(self.feature_ro_compat) {
+            // TODO: Mount the filesystem as read-only instead of failing.
+            return_errno_with_message!(Errno::EINVAL, "unsupported read-only compatible features");
+        }
+        if self.feature_incompat.contains(FeatureInCompatSet::RECOVER)
+            && self.journal_ino().is_none()
+        {
+            return_errno_with_message!(
+                Errno::EINVAL,
+                "the external journal needs recovery, which is not supported"
+            );
+        }
+
+        Ok(())
     }
 
     /// Returns the filesystem state.
@@ -249,6 +431,15 @@ impl SuperBlock {
         self.free_blocks_count
     }
 
+    /// Sets the numbers of free blocks and free inodes.
+    ///
+    /// The numbers are recomputed from the block groups after the journal is replayed,
+    /// since the journals written by Linux may not contain the latest superblock.
+    pub(super) fn set_free_counts(&mut self, free_blocks: u32, free_inodes: u32) {
+        self.free_blocks_count = free_blocks;
+        self.free_inodes_count = free_inodes;
+    }
+
     /// Increase the number of free blocks.
     pub(super) fn inc_free_blocks(&mut self, count: u32) {
         self.free_blocks_count = self.free_blocks_count.checked_add(count).unwrap();
@@ -279,6 +470,14 @@ impl SuperBlock {
     pub(super) fn is_backup_group(&self, block_group_idx: usize) -> bool {
         if block_group_idx == 0 {
             false
+        } else if self
+            .feature_compat
+            .contains(FeatureCompatSet::SPARSE_SUPER2)
+        {
+            // The backup groups are recorded in the superblock.
+            self.backup_bgs
+                .iter()
+                .any(|&idx| idx != 0 && idx as usize == block_group_idx)
         } else if self
             .feature_ro_compat
             .contains(FeatureRoCompatSet::SPARSE_SUPER)
@@ -324,6 +523,15 @@ impl SuperBlock {
     }
 }
 
+/// The versions of the on-disk format that can be mounted.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ExtVersion {
+    /// The Second Extended File System.
+    Ext2,
+    /// The Fourth Extended File System.
+    Ext4,
+}
+
 bitflags! {
     /// Compatible feature set.
     pub struct FeatureCompatSet: u32 {
@@ -339,6 +547,20 @@ bitflags! {
         const RESIZE_INO = 1 << 4;
         /// Directories use hash index
         const DIR_INDEX = 1 << 5;
+        /// Lazy block group
+        const LAZY_BG = 1 << 6;
+        /// Exclude inode
+        const EXCLUDE_INODE = 1 << 7;
+        /// Exclude bitmap
+        const EXCLUDE_BITMAP = 1 << 8;
+        /// Superblock backups are only in the groups recorded in the superblock
+        const SPARSE_SUPER2 = 1 << 9;
+        /// File system has a fast commit area
+        const FAST_COMMIT = 1 << 10;
+        /// Inode numbers are stable
+        const STABLE_INODES = 1 << 11;
+        /// Orphan inodes are tracked by an orphan file
+        const ORPHAN_FILE = 1 << 12;
     }
 }
 
@@ -355,9 +577,48 @@ bitflags! {
         const JOURNAL_DEV = 1 << 3;
         /// Metablock block group
         const META_BG = 1 << 4;
+        /// Files use extent trees
+        const EXTENTS = 1 << 6;
+        /// File system can have more than 2^32 blocks
+        const _64BIT = 1 << 7;
+        /// Multiple mount protection
+        const MMP = 1 << 8;
+        /// The metadata of block groups can be placed in other groups
+        const FLEX_BG = 1 << 9;
+        /// Inodes can store large extended attribute values
+        const EA_INODE = 1 << 10;
+        /// Data in directory entries
+        const DIRDATA = 1 << 12;
+        /// The metadata checksum seed is stored in the superblock
+        const CSUM_SEED = 1 << 13;
+        /// Large directories and 3-level HTREE
+        const LARGEDIR = 1 << 14;
+        /// Data in inodes
+        const INLINE_DATA = 1 << 15;
+        /// Encrypted inodes
+        const ENCRYPT = 1 << 16;
+        /// Case-insensitive directories
+        const CASEFOLD = 1 << 17;
     }
 }
 
+impl FeatureInCompatSet {
+    /// The incompatible features that can be mounted as Ext2.
+    const EXT2_SUPPORTED: Self =
+        Self::from_bits_truncate(Self::FILETYPE.bits() | Self::RECOVER.bits());
+
+    /// The incompatible features that can be mounted as Ext4.
+    const EXT4_SUPPORTED: Self = Self::from_bits_truncate(
+        Self::FILETYPE.bits()
+            | Self::RECOVER.bits()
+            | Self::EXTENTS.bits()
+            | Self::_64BIT.bits()
+            | Self::FLEX_BG.bits()
+            | Self::CSUM_SEED.bits()
+            | Self::LARGEDIR.bits(),
+    );
+}
+
 bitflags! {
     /// Readonly-compatible feature set.
     pub struct FeatureRoCompatSet: u32 {
@@ -367,6 +628,65 @@ bitflags! {
         const LARGE_FILE = 1 << 1;
         /// Directory contents are stored in the form of a Binary Tree
         const BTREE_DIR = 1 << 2;
+        /// File sizes can be represented in units of blocks
+        const HUGE_FILE = 1 << 3;
+        /// Group descriptors have checksums
+        const GDT_CSUM = 1 << 4;
+        /// Directories can have more than 65000 subdirectories
+        const DIR_NLINK = 1 << 5;
+        /// Inodes have extra space
+        const EXTRA_ISIZE = 1 << 6;
+        /// File system has a snapshot
+        const HAS_SNAPSHOT = 1 << 7;
+        /// Quota is tracked by hidden inodes
+        const QUOTA = 1 << 8;
+        /// Blocks are allocated in clusters
+        const BIGALLOC = 1 << 9;
+        /// Metadata is protected by checksums
+        const METADATA_CSUM = 1 << 10;
+        /// Replicas
+        const REPLICA = 1 << 11;
+        /// File system can only be mounted as read-only
+        const READONLY = 1 << 12;
+        /// Project quota is tracked
+        const PROJECT = 1 << 13;
+        /// Blocks can be shared between files
+        const SHARED_BLOCKS = 1 << 14;
+        /// Files can be protected by fs-verity
+        const VERITY = 1 << 15;
+        /// The orphan file may contain orphan inodes
+        const ORPHAN_PRESENT = 1 << 16;
+    }
+}
+
+impl FeatureRoCompatSet {
+    /// The readonly-compatible features that can be mounted as Ext2.
+    const EXT2_SUPPORTED: Self = Self::from_bits_truncate(
+        Self::SPARSE_SUPER.bits() | Self::LARGE_FILE.bits() | Self::BTREE_DIR.bits(),
+    );
+
+    /// The readonly-compatible features that can be mounted as Ext4.
+    const EXT4_SUPPORTED: Self = Self::from_bits_truncate(
+        Self::SPARSE_SUPER.bits()
+            | Self::LARGE_FILE.bits()
+            | Self::BTREE_DIR.bits()
+            | Self::HUGE_FILE.bits()
+            | Self::GDT_CSUM.bits()
+            | Self::DIR_NLINK.bits()
+            | Self::EXTRA_ISIZE.bits()
+            | Self::METADATA_CSUM.bits(),
+    );
+}
+
+bitflags! {
+    /// Miscellaneous flags of the superblock.
+    struct SuperBlockFlags: u32 {
+        /// The legacy HTREE hashes treat `char` as signed
+        const SIGNED_HASH = 1 << 0;
+        /// The legacy HTREE hashes treat `char` as unsigned
+        const UNSIGNED_HASH = 1 << 1;
+        /// The filesystem is for testing development code
+        const TEST_FILESYS = 1 << 2;
     }
 }
 
@@ -417,11 +737,21 @@ pub enum RevLevel {
     Dynamic = 1,
 }
 
+/// The minimal size of the block group descriptor.
+const MIN_DESC_SIZE: usize = 32;
+/// The minimal size of the block group descriptor if the `_64BIT` feature is set.
+const MIN_DESC_SIZE_64BIT: usize = 64;
+/// The maximal size of the block group descriptor.
+const MAX_DESC_SIZE: usize = 1024;
+
+/// The checksum type of CRC32C, the only checksum type defined.
+const CHECKSUM_TYPE_CRC32C: u8 = 1;
+
 const_assert!(core::mem::size_of::<RawSuperBlock>() == SUPER_BLOCK_SIZE);
 
 /// The raw superblock, it must be exactly 1024 bytes in length.
 #[repr(C)]
-#[derive(Clone, Copy, Debug, Pod, Default)]
+#[derive(Clone, Copy, Debug, Pod)]
 pub(super) struct RawSuperBlock {
     pub inodes_count: u32,
     pub blocks_count: u32,
@@ -465,7 +795,8 @@ pub(super) struct RawSuperBlock {
     pub algorithm_usage_bitmap: u32,
     pub prealloc_file_blocks: u8,
     pub prealloc_dir_blocks: u8,
-    padding1: u16,
+    /// Number of reserved group descriptor blocks for online growth.
+    pub reserved_gdt_blocks: u16,
     ///
     /// This fields are for journaling support in Ext3.
     ///
@@ -481,18 +812,115 @@ pub(super) struct RawSuperBlock {
     pub hash_seed: [u32; 4],
     /// Default hash version to use
     pub def_hash_version: u8,
-    reserved_char_pad: u8,
-    reserved_word_pad: u16,
+    /// Whether `journal_blocks` is a backup of the block pointers of the journal inode.
+    pub journal_backup_type: u8,
+    /// Size of the group descriptor if the `_64BIT` feature is set.
+    pub desc_size: u16,
     /// Default mount options.
     pub default_mount_opts: u32,
     /// First metablock block group.
     pub first_meta_bg: u32,
-    reserved: Reserved,
+    ///
+    /// This fields are introduced by Ext4.
+    ///
+    /// When the filesystem was created.
+    pub mkfs_time: UnixTime,
+    /// Backup of the block pointers and the size of the journal inode.
+    pub journal_blocks: [u32; 17],
+    /// High 32 bits of the total number of blocks.
+    pub blocks_count_hi: u32,
+    /// High 32 bits of the number of reserved blocks.
+    pub reserved_blocks_count_hi: u32,
+    /// High 32 bits of the number of free blocks.
+    pub free_blocks_count_hi: u32,
+    /// All inodes have at least this number of extra bytes.
+    pub min_extra_isize: u16,
+    /// New inodes should reserve this number of extra bytes.
+    pub want_extra_isize: u16,
+    /// Miscellaneous flags.
+    pub flags: u32,
+    /// RAID stride.
+    pub raid_stride: u16,
+    /// Seconds to wait in multiple mount protection checking.
+    pub mmp_interval: u16,
+    /// Block for multiple mount protection.
+    pub mmp_block: [u32; 2],
+    /// RAID stripe width.
+    pub raid_stripe_width: u32,
+    /// The number to left-shift 1 to obtain the number of block groups in a flexible group.
+    pub log_groups_per_flex: u8,
+    /// Metadata checksum algorithm type.
+    pub checksum_type: u8,
+    pub encryption_level: u8,
+    reserved_pad: u8,
+    /// Number of KiB written to the filesystem over its lifetime.
+    pub kbytes_written: [u32; 2],
+    /// Snapshot and error information.
+    snapshot_and_error_info: [u32; 32],
+    /// Mount options.
+    pub mount_opts: [u8; 64],
+    /// Inode number of the user quota file.
+    pub usr_quota_inum: u32,
+    /// Inode number of the group quota file.
+    pub grp_quota_inum: u32,
+    /// Overhead blocks in the filesystem.
+    pub overhead_clusters: u32,
+    /// Block groups containing the superblock backups if `SPARSE_SUPER2` is set.
+    pub backup_bgs: [u32; 2],
+    /// Encryption information.
+    encryption_info: [u8; 20],
+    /// Inode number of `lost+found`.
+    pub lpf_ino: u32,
+    /// Inode number of the project quota file.
+    pub prj_quota_inum: u32,
+    /// Seed of the metadata checksums if the `CSUM_SEED` feature is set.
+    pub checksum_seed: u32,
+    /// High 8 bits of the timestamps and the error codes.
+    time_hi_and_error_codes: [u8; 8],
+    /// Filename charset encoding.
+    pub encoding: u16,
+    /// Filename charset encoding flags.
+    pub encoding_flags: u16,
+    /// Inode number of the orphan file.
+    pub orphan_file_inum: u32,
+    reserved: [u32; 94],
+    /// Checksum of the superblock.
+    pub checksum: u32,
+}
+
+impl RawSuperBlock {
+    /// Computes the checksum of the superblock.
+    fn compute_checksum(&self) -> u32 {
+        let offset = core::mem::offset_of!(RawSuperBlock, checksum);
+        crc32c(!0, &self.as_bytes()[..offset])
+    }
+
+    /// Returns whether the journal needs recovery.
+    pub fn needs_recovery(&self) -> bool {
+        self.feature_incompat & FeatureInCompatSet::RECOVER.bits() != 0
+    }
+
+    /// Sets or clears the flag telling that the journal needs recovery.
+    pub fn set_needs_recovery(&mut self, needs_recovery: bool) {
+        if needs_recovery {
+            self.feature_incompat |= FeatureInCompatSet::RECOVER.bits();
+        } else {
+            self.feature_incompat &= !FeatureInCompatSet::RECOVER.bits();
+        }
+        self.update_checksum();
+    }
+
+    /// Updates the checksum of the superblock if the `METADATA_CSUM` feature is set.
+    pub fn update_checksum(&mut self) {
+        if self.feature_ro_compat & FeatureRoCompatSet::METADATA_CSUM.bits() != 0 {
+            self.checksum = self.compute_checksum();
+        }
+    }
 }
 
 impl From<&SuperBlock> for RawSuperBlock {
     fn from(sb: &SuperBlock) -> Self {
-        Self {
+        let mut raw = Self {
             inodes_count: sb.inodes_count,
             blocks_count: sb.blocks_count,
             reserved_blocks_count: sb.reserved_blocks_count,
@@ -528,17 +956,9 @@ impl From<&SuperBlock> for RawSuperBlock {
             last_mounted_dir: sb.last_mounted_dir,
             prealloc_file_blocks: sb.prealloc_file_blocks,
             prealloc_dir_blocks: sb.prealloc_dir_blocks,
-            ..Default::default()
-        }
-    }
-}
-
-#[repr(C)]
-#[derive(Clone, Copy, Debug, Pod)]
-struct Reserved([u32; 190]);
-
-impl Default for Reserved {
-    fn default() -> Self {
-        Self([0u32; 190])
+            ..sb.raw
+        };
+        raw.update_checksum();
+        raw
     }
 }
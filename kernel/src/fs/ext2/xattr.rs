@@ -1,8 +1,10 @@
 // SPDX-License-Identifier: MPL-2.0
 
+use core::mem::offset_of;
+
 use ostd::mm::io_util::HasVmReaderWriter;
 
-use super::{block_ptr::Ext2Bid, prelude::*, Ext2, Inode};
+use super::{block_ptr::Ext2Bid, csum::crc32c, prelude::*, Ext2, Inode};
 use crate::fs::utils::{XattrName, XattrNamespace, XattrSetFlags, XATTR_NAME_MAX_LEN};
 
 const EXT2_XATTR_MAGIC: u32 = 0xEA020000;
@@ -16,7 +18,9 @@ struct XattrHeader {
     ref_count: u32,
     nblocks: u32,
     hash: u32,
-    reserved: [u32; 4],
+    /// The checksum of the block, which is used only if the metadata checksums are enabled.
+    checksum: u32,
+    reserved: [u32; 3],
 }
 
 const XATTR_HEADER_SIZE: usize = size_of::<XattrHeader>();
@@ -132,8 +136,8 @@ impl Xattr {
             self.inode().set_acl(new_bid);
         // Need to load the xattr block from device
         } else if cache.header.is_none() {
-            fs.block_device().read_blocks(
-                cache.bid,
+            fs.read_blocks(
+                cache.bid.to_raw() as Ext2Bid,
                 BioSegment::new_from_segment(self.blocks_buf.clone(), BioDirection::FromDevice),
             )?;
 
@@ -141,6 +145,11 @@ impl Xattr {
             if header.magic != EXT2_XATTR_MAGIC {
                 return_errno_with_message!(Errno::EINVAL, "invalid xattr magic");
             }
+            if let Some(csum_seed) = fs.csum_seed() {
+                if self.checksum(cache.bid, csum_seed)? != header.checksum {
+                    return_errno_with_message!(Errno::EBADMSG, "xattr block checksum mismatch");
+                }
+            }
 
             let mut cache = cache.upgrade();
             cache.header = Some(header);
@@ -328,8 +337,13 @@ impl Xattr {
     pub fn flush(&self) -> Result<()> {
         let cache = self.cache.upread();
         if cache.is_dirty() {
-            self.fs().block_device().write_blocks(
-                cache.bid,
+            if let Some(csum_seed) = self.fs().csum_seed() {
+                let checksum = self.checksum(cache.bid, csum_seed)?;
+                self.blocks_buf
+                    .write_val(offset_of!(XattrHeader, checksum), &checksum)?;
+            }
+            self.fs().write_metadata_blocks(
+                cache.bid.to_raw() as Ext2Bid,
                 BioSegment::new_from_segment(self.blocks_buf.clone(), BioDirection::ToDevice),
             )?;
             cache.upgrade().clear_dirty();
@@ -348,6 +362,18 @@ impl Xattr {
         Ok(())
     }
 
+    /// Calculates the checksum of the block, which covers the block number and the
+    /// block with the checksum field zeroed.
+    fn checksum(&self, bid: Bid, csum_seed: u32) -> Result<u32> {
+        let mut block = vec![0u8; XATTR_NBLOCKS * BLOCK_SIZE];
+        self.blocks_buf.read_bytes(0, &mut block)?;
+        let checksum_offset = offset_of!(XattrHeader, checksum);
+        block[checksum_offset..checksum_offset + size_of::<u32>()].fill(0);
+
+        let csum_seed = crc32c(csum_seed, &bid.to_raw().to_le_bytes());
+        Ok(crc32c(csum_seed, &block))
+    }
+
     fn fs(&self) -> Arc<Ext2> {
         self.fs.upgrade().unwrap()
     }
@@ -482,6 +508,7 @@ impl Default for XattrHeader {
             nblocks: XATTR_NBLOCKS as _,
             ref_count: Default::default(),
             hash: Default::default(),
+            checksum: Default::default(),
             reserved: Default::default(),
         }
     }
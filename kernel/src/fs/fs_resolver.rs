@@ -6,7 +6,8 @@ use ostd::task::Task;
 
 use super::{
     file_table::{get_file_fast, FileDesc},
-    inode_handle::InodeHandle,
+    inode_handle::{check_not_swap_file, InodeHandle},
+    notify::FsEvents,
     path::Path,
     rootfs::root_mount,
     utils::{AccessMode, CreationFlags, InodeMode, InodeType, StatusFlags, PATH_MAX, SYMLINKS_MAX},
@@ -73,6 +74,10 @@ impl FsResolver {
             Err(e) => return Err(e),
         };
 
+        if !open_args.status_flags.contains(StatusFlags::O_PATH) {
+            inode_handle.path().notify(FsEvents::OPEN);
+        }
+
         Ok(inode_handle)
     }
 
@@ -108,7 +113,12 @@ impl FsResolver {
             );
         }
 
+        if !open_args.status_flags.contains(StatusFlags::O_PATH) {
+            target_path.notify_perm(FsEvents::OPEN_PERM)?;
+        }
+
         if inode_type.is_regular_file() && creation_flags.contains(CreationFlags::O_TRUNC) {
+            check_not_swap_file(target_path.inode())?;
             target_path.resize(0)?;
         }
         InodeHandle::new(target_path, open_args.access_mode, open_args.status_flags)
@@ -136,6 +146,7 @@ impl FsResolver {
         let tail_file_name = lookup_ctx.tail_file_name().unwrap();
         let new_path =
             parent.new_fs_child(&tail_file_name, InodeType::File, open_args.inode_mode)?;
+        new_path.notify_perm(FsEvents::OPEN_PERM)?;
         // Don't check access mode for newly created file
         InodeHandle::new_unchecked_access(new_path, open_args.access_mode, open_args.status_flags)
     }
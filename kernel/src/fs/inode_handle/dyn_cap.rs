@@ -17,6 +17,26 @@ impl InodeHandle<Rights> {
         path: Path,
         access_mode: AccessMode,
         status_flags: StatusFlags,
+    ) -> Result<Self> {
+        Self::new_inner(path, access_mode, status_flags, false)
+    }
+
+    /// Creates a handle whose accesses do not produce file system events.
+    ///
+    /// This is used to open the files for the listeners of file system events.
+    pub fn new_without_notify(
+        path: Path,
+        access_mode: AccessMode,
+        status_flags: StatusFlags,
+    ) -> Result<Self> {
+        Self::new_inner(path, access_mode, status_flags, true)
+    }
+
+    fn new_inner(
+        path: Path,
+        access_mode: AccessMode,
+        status_flags: StatusFlags,
+        is_notify_suppressed: bool,
     ) -> Result<Self> {
         let inode = path.inode();
         if inode.type_() == InodeType::Dir && access_mode.is_writable() {
@@ -28,6 +48,7 @@ impl InodeHandle<Rights> {
         } else {
             None
         };
+        sysctl::alloc_file()?;
 
         let inner = Arc::new(InodeHandle_ {
             path,
@@ -35,6 +56,7 @@ impl InodeHandle<Rights> {
             offset: Mutex::new(0),
             access_mode,
             status_flags: AtomicU32::new(status_flags.bits()),
+            is_notify_suppressed,
         });
         Ok(Self(inner, Rights::from(access_mode)))
     }
@@ -53,6 +75,18 @@ impl InodeHandle<Rights> {
         }
         self.0.readdir(visitor)
     }
+
+    pub fn read_pages_at(
+        &self,
+        offset: usize,
+        max_len: usize,
+        max_pages: usize,
+    ) -> Result<Option<Vec<(UFrame, Range<usize>)>>> {
+        if !self.1.contains(Rights::READ) {
+            return_errno_with_message!(Errno::EBADF, "file is not readable");
+        }
+        self.0.read_pages_at(offset, max_len, max_pages)
+    }
 }
 
 impl Clone for InodeHandle<Rights> {
@@ -7,16 +7,22 @@
 mod dyn_cap;
 mod static_cap;
 
-use core::sync::atomic::{AtomicU32, Ordering};
+use core::{
+    ops::Range,
+    sync::atomic::{AtomicU32, Ordering},
+};
 
 use aster_rights::Rights;
 use inherit_methods_macro::inherit_methods;
+use ostd::mm::UFrame;
 
 use crate::{
     events::IoEvents,
     fs::{
         file_handle::FileLike,
+        notify::{self, FsEvents},
         path::Path,
+        sysctl,
         utils::{
             AccessMode, DirentVisitor, FallocMode, FileRange, FlockItem, FlockList, Inode,
             InodeMode, InodeType, IoctlCmd, Metadata, RangeLockItem, RangeLockItemBuilder,
@@ -28,6 +34,7 @@ use crate::{
         signal::{PollHandle, Pollable},
         Gid, Uid,
     },
+    vm::{swap, vmo::CommitFlags},
 };
 
 #[derive(Debug)]
@@ -42,6 +49,11 @@ struct InodeHandle_ {
     offset: Mutex<usize>,
     access_mode: AccessMode,
     status_flags: AtomicU32,
+    /// Whether the file system events on the file are suppressed.
+    ///
+    /// This is the case for the files opened by fanotify for its listeners,
+    /// whose accesses must not produce new events.
+    is_notify_suppressed: bool,
 }
 
 impl InodeHandle_ {
@@ -88,11 +100,18 @@ impl InodeHandle_ {
             todo!("support read_at for FileIo");
         }
 
-        if self.status_flags().contains(StatusFlags::O_DIRECT) {
-            self.path.inode().read_direct_at(offset, writer)
+        self.notify_perm(FsEvents::ACCESS_PERM)?;
+
+        let len = if self.status_flags().contains(StatusFlags::O_DIRECT) {
+            self.path.inode().read_direct_at(offset, writer)?
         } else {
-            self.path.inode().read_at(offset, writer)
+            self.path.inode().read_at(offset, writer)?
+        };
+
+        if len > 0 {
+            self.notify(FsEvents::ACCESS);
         }
+        Ok(len)
     }
 
     pub fn write_at(&self, mut offset: usize, reader: &mut VmReader) -> Result<usize> {
@@ -100,17 +119,71 @@ impl InodeHandle_ {
             todo!("support write_at for FileIo");
         }
 
+        check_not_swap_file(self.path.inode())?;
+
         let status_flags = self.status_flags();
         if status_flags.contains(StatusFlags::O_APPEND) {
             // If the file has the O_APPEND flag, the offset is ignored
             offset = self.path.size();
         }
 
-        if status_flags.contains(StatusFlags::O_DIRECT) {
-            self.path.inode().write_direct_at(offset, reader)
+        let len = if status_flags.contains(StatusFlags::O_DIRECT) {
+            self.path.inode().write_direct_at(offset, reader)?
         } else {
-            self.path.inode().write_at(offset, reader)
+            self.path.inode().write_at(offset, reader)?
+        };
+
+        if len > 0 {
+            self.notify(FsEvents::MODIFY);
         }
+        Ok(len)
+    }
+
+    /// Reads the data at `offset` as the pages in the page cache.
+    ///
+    /// At most `max_len` bytes in at most `max_pages` pages are read. The pages are returned with
+    /// the ranges of the data in them, so the data can be used without being copied. Note that
+    /// later modifications of the file will be visible in the returned pages.
+    ///
+    /// Returns `None` if the file is not backed by the page cache.
+    pub fn read_pages_at(
+        &self,
+        offset: usize,
+        max_len: usize,
+        max_pages: usize,
+    ) -> Result<Option<Vec<(UFrame, Range<usize>)>>> {
+        let inode = self.path.inode();
+        if self.file_io.is_some()
+            || self.status_flags().contains(StatusFlags::O_DIRECT)
+            || inode.type_() != InodeType::File
+        {
+            return Ok(None);
+        }
+        let Some(page_cache) = inode.page_cache() else {
+            return Ok(None);
+        };
+
+        self.notify_perm(FsEvents::ACCESS_PERM)?;
+
+        let end = offset.saturating_add(max_len).min(inode.size());
+        let mut pages = Vec::new();
+        let mut pos = offset;
+        while pos < end && pages.len() < max_pages {
+            let page_offset = pos % PAGE_SIZE;
+            let len = (PAGE_SIZE - page_offset).min(end - pos);
+            let page = match page_cache.commit_on(pos / PAGE_SIZE, CommitFlags::empty()) {
+                Ok(page) => page,
+                Err(_) if !pages.is_empty() => break,
+                Err(err) => return Err(err),
+            };
+            pages.push((page, page_offset..page_offset + len));
+            pos += len;
+        }
+
+        if !pages.is_empty() {
+            self.notify(FsEvents::ACCESS);
+        }
+        Ok(Some(pages))
     }
 
     pub fn seek(&self, pos: SeekFrom) -> Result<usize> {
@@ -123,7 +196,9 @@ impl InodeHandle_ {
     }
 
     pub fn resize(&self, new_size: usize) -> Result<()> {
-        do_resize_util(self.path.inode(), self.status_flags(), new_size)
+        do_resize_util(self.path.inode(), self.status_flags(), new_size)?;
+        self.notify(FsEvents::MODIFY);
+        Ok(())
     }
 
     pub fn access_mode(&self) -> AccessMode {
@@ -147,6 +222,19 @@ impl InodeHandle_ {
         Ok(read_cnt)
     }
 
+    fn notify(&self, events: FsEvents) {
+        if !self.is_notify_suppressed {
+            self.path.notify(events);
+        }
+    }
+
+    fn notify_perm(&self, events: FsEvents) -> Result<()> {
+        if self.is_notify_suppressed {
+            return Ok(());
+        }
+        self.path.notify_perm(events)
+    }
+
     fn poll(&self, mask: IoEvents, poller: Option<&mut PollHandle>) -> IoEvents {
         if let Some(ref file_io) = self.file_io {
             return file_io.poll(mask, poller);
@@ -282,6 +370,24 @@ impl InodeHandle_ {
     pub fn set_group(&self, gid: Gid) -> Result<()>;
 }
 
+impl Drop for InodeHandle_ {
+    fn drop(&mut self) {
+        sysctl::free_file();
+
+        if self.is_notify_suppressed || self.status_flags().contains(StatusFlags::O_PATH) {
+            return;
+        }
+
+        let events = if self.access_mode.is_writable() {
+            FsEvents::CLOSE_WRITE
+        } else {
+            FsEvents::CLOSE_NOWRITE
+        };
+        // The file may be released with spin locks held, so the events are delivered later.
+        notify::defer_close_events(self.path.clone(), events);
+    }
+}
+
 impl Debug for InodeHandle_ {
     fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
         f.debug_struct("InodeHandle_")
@@ -393,6 +499,7 @@ pub fn do_fallocate_util(
             "currently fallocate file with O_DIRECT or O_PATH is not supported"
         );
     }
+    check_not_swap_file(inode)?;
 
     inode.fallocate(mode, offset, len)
 }
@@ -406,5 +513,15 @@ pub fn do_resize_util(
         // FIXME: It's allowed to `ftruncate` an append-only file on Linux.
         return_errno_with_message!(Errno::EPERM, "can not resize append-only file");
     }
+    check_not_swap_file(inode)?;
     inode.resize(new_size)
 }
+
+/// Checks that `inode` is not the file of an enabled swap area, which cannot be
+/// modified through the VFS.
+pub fn check_not_swap_file(inode: &Arc<dyn Inode>) -> Result<()> {
+    if swap::is_swap_file(inode) {
+        return_errno_with_message!(Errno::ETXTBSY, "the file is an active swap file");
+    }
+    Ok(())
+}
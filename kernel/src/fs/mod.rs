@@ -10,7 +10,9 @@ pub mod file_handle;
 pub mod file_table;
 pub mod fs_resolver;
 pub mod inode_handle;
+pub mod mqueue;
 pub mod named_pipe;
+pub mod notify;
 pub mod overlayfs;
 pub mod path;
 pub mod pipe;
@@ -22,6 +24,8 @@ pub mod sysfs;
 pub mod thread_info;
 pub mod utils;
 
+mod sysctl;
+
 use aster_block::BlockDevice;
 use aster_virtio::device::block::device::BlockDevice as VirtIoBlockDevice;
 
@@ -56,9 +60,13 @@ pub fn lazy_init() {
 
     sysfs::init();
     procfs::init();
+    sysctl::init();
+    utils::init();
+    path::init();
     cgroupfs::init();
     ramfs::init();
     devpts::init();
+    mqueue::init();
 
     ext2::init();
     exfat::init();
@@ -0,0 +1,422 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! POSIX message queues.
+//!
+//! Each message queue is a file in the mqueue file system. The queues are
+//! created and opened by `mq_open` through an internal mount of the file
+//! system, so a queue descriptor is an ordinary opened file that can be polled
+//! or closed. The file system can also be mounted (normally at `/dev/mqueue`)
+//! to list, inspect or remove the queues with file operations.
+
+#![expect(unused_variables)]
+
+use core::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+use spin::Once;
+
+pub use self::queue::{MqAttr, MqueueInode, NotifyMethod, NOTIFY_COOKIE_LEN};
+use crate::{
+    fs::{
+        path::{MountNode, Path},
+        registry::{FsProperties, FsType},
+        utils::{
+            DirentVisitor, FileSystem, FsFlags, Inode, InodeMode, InodeType, Metadata, MknodType,
+            SuperBlock, NAME_MAX,
+        },
+    },
+    prelude::*,
+    process::{posix_thread::AsPosixThread, Gid, Uid},
+};
+
+mod queue;
+
+const MQUEUE_MAGIC: u64 = 0x19800202;
+const BLOCK_SIZE: usize = PAGE_SIZE;
+
+const ROOT_INO: u64 = 1;
+
+// The following constant values are derived from the default values in Linux.
+
+/// Maximum priority of messages (exclusive).
+pub const MQ_PRIO_MAX: u32 = 32768;
+/// Default maximum number of messages in a queue.
+pub const DFLT_MSGMAX: usize = 10;
+/// Default maximum size in bytes of a message.
+pub const DFLT_MSGSIZEMAX: usize = 8192;
+/// Maximum number of messages in a queue, which can only be exceeded by
+/// privileged callers up to [`HARD_MSGMAX`].
+pub const MSG_MAX: usize = DFLT_MSGMAX;
+/// Maximum size in bytes of a message, which can only be exceeded by
+/// privileged callers up to [`HARD_MSGSIZEMAX`].
+pub const MSGSIZE_MAX: usize = DFLT_MSGSIZEMAX;
+/// Hard limit of the number of messages in a queue.
+pub const HARD_MSGMAX: usize = 65536;
+/// Hard limit of the size in bytes of a message.
+pub const HARD_MSGSIZEMAX: usize = 16 * 1024 * 1024;
+
+/// The mqueue file system, which holds all the POSIX message queues.
+pub struct MqueueFs {
+    sb: SuperBlock,
+    root: Arc<RootInode>,
+    next_ino: AtomicU64,
+}
+
+impl MqueueFs {
+    fn new() -> Arc<Self> {
+        Arc::new_cyclic(|weak_self| Self {
+            sb: SuperBlock::new(MQUEUE_MAGIC, BLOCK_SIZE, NAME_MAX),
+            root: RootInode::new(weak_self.clone()),
+            next_ino: AtomicU64::new(ROOT_INO + 1),
+        })
+    }
+
+    /// Returns the mqueue file system.
+    ///
+    /// Like Linux, all the mounts of the file system share the same instance,
+    /// so the queues created by `mq_open` are visible in every mount.
+    pub fn singleton() -> &'static Arc<MqueueFs> {
+        MQUEUE_FS.get().unwrap()
+    }
+
+    /// Returns the root path of the internal mount, which is used by the
+    /// `mq_*` system calls to look up the queues.
+    pub fn root_path() -> Path {
+        Path::new_fs_root(MQUEUE_MOUNT.get().unwrap().clone())
+    }
+
+    /// Looks up the message queue with `name`, or creates it if it does not exist.
+    ///
+    /// The lookup and the creation are done atomically under the lock of the root
+    /// directory, so only one of the concurrent callers can create the queue. The
+    /// attributes of the new queue are given by `attr`, which is only called on
+    /// creation and must not sleep. If `is_exclusive` is true, the queue must not exist.
+    ///
+    /// Returns the queue and whether it is newly created.
+    pub fn lookup_or_create_queue<F>(
+        &self,
+        name: &str,
+        is_exclusive: bool,
+        mode: InodeMode,
+        uid: Uid,
+        gid: Gid,
+        attr: F,
+    ) -> Result<(Arc<MqueueInode>, bool)>
+    where
+        F: FnOnce() -> Result<MqAttr>,
+    {
+        self.root
+            .lookup_or_create_queue(name, is_exclusive, mode, uid, gid, attr)
+    }
+
+    fn alloc_ino(&self) -> u64 {
+        self.next_ino.fetch_add(1, Ordering::Relaxed)
+    }
+}
+
+impl FileSystem for MqueueFs {
+    fn sync(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn root_inode(&self) -> Arc<dyn Inode> {
+        self.root.clone()
+    }
+
+    fn sb(&self) -> SuperBlock {
+        self.sb.clone()
+    }
+
+    fn flags(&self) -> FsFlags {
+        FsFlags::empty()
+    }
+
+    fn name(&self) -> &'static str {
+        "mqueue"
+    }
+}
+
+struct MqueueFsType;
+
+impl FsType for MqueueFsType {
+    fn name(&self) -> &'static str {
+        "mqueue"
+    }
+
+    fn create(
+        &self,
+        _args: Option<CString>,
+        _disk: Option<Arc<dyn aster_block::BlockDevice>>,
+        _ctx: &Context,
+    ) -> Result<Arc<dyn FileSystem>> {
+        Ok(MqueueFs::singleton().clone())
+    }
+
+    fn properties(&self) -> FsProperties {
+        FsProperties::empty()
+    }
+
+    fn sysnode(&self) -> Option<Arc<dyn aster_systree::SysBranchNode>> {
+        None
+    }
+}
+
+static MQUEUE_FS: Once<Arc<MqueueFs>> = Once::new();
+static MQUEUE_MOUNT: Once<Arc<MountNode>> = Once::new();
+
+pub(super) fn init() {
+    let mqueue_fs = MQUEUE_FS.call_once(MqueueFs::new);
+    MQUEUE_MOUNT.call_once(|| MountNode::new_root(mqueue_fs.clone()));
+
+    let mqueue_type = Arc::new(MqueueFsType);
+    super::registry::register(mqueue_type).unwrap();
+}
+
+struct RootInode {
+    queues: RwLock<BTreeMap<String, Arc<MqueueInode>>>,
+    metadata: RwLock<Metadata>,
+    fs: Weak<MqueueFs>,
+}
+
+impl RootInode {
+    fn new(fs: Weak<MqueueFs>) -> Arc<Self> {
+        Arc::new(Self {
+            queues: RwLock::new(BTreeMap::new()),
+            metadata: RwLock::new(Metadata::new_dir(
+                ROOT_INO,
+                InodeMode::from_bits_truncate(0o1777),
+                BLOCK_SIZE,
+            )),
+            fs,
+        })
+    }
+
+    fn create_queue(
+        &self,
+        name: &str,
+        mode: InodeMode,
+        attr: MqAttr,
+        uid: Uid,
+        gid: Gid,
+    ) -> Result<Arc<MqueueInode>> {
+        if name.len() > NAME_MAX {
+            return_errno!(Errno::ENAMETOOLONG);
+        }
+
+        let mut queues = self.queues.write();
+        if queues.contains_key(name) {
+            return_errno!(Errno::EEXIST);
+        }
+
+        let fs = self.fs.upgrade().unwrap();
+        let queue = MqueueInode::new(fs.alloc_ino(), mode, attr, uid, gid, self.fs.clone());
+        queues.insert(name.to_string(), queue.clone());
+
+        Ok(queue)
+    }
+
+    fn lookup_or_create_queue<F>(
+        &self,
+        name: &str,
+        is_exclusive: bool,
+        mode: InodeMode,
+        uid: Uid,
+        gid: Gid,
+        attr: F,
+    ) -> Result<(Arc<MqueueInode>, bool)>
+    where
+        F: FnOnce() -> Result<MqAttr>,
+    {
+        if name.len() > NAME_MAX {
+            return_errno!(Errno::ENAMETOOLONG);
+        }
+
+        let mut queues = self.queues.write();
+        if let Some(queue) = queues.get(name) {
+            if is_exclusive {
+                return_errno_with_message!(Errno::EEXIST, "the queue already exists");
+            }
+            return Ok((queue.clone(), false));
+        }
+
+        let attr = attr()?;
+        let fs = self.fs.upgrade().unwrap();
+        let queue = MqueueInode::new(fs.alloc_ino(), mode, attr, uid, gid, self.fs.clone());
+        queues.insert(name.to_string(), queue.clone());
+
+        Ok((queue, true))
+    }
+}
+
+impl Inode for RootInode {
+    fn size(&self) -> usize {
+        self.metadata.read().size
+    }
+
+    fn resize(&self, new_size: usize) -> Result<()> {
+        Err(Error::new(Errno::EISDIR))
+    }
+
+    fn metadata(&self) -> Metadata {
+        *self.metadata.read()
+    }
+
+    fn ino(&self) -> u64 {
+        self.metadata.read().ino as _
+    }
+
+    fn type_(&self) -> InodeType {
+        self.metadata.read().type_
+    }
+
+    fn mode(&self) -> Result<InodeMode> {
+        Ok(self.metadata.read().mode)
+    }
+
+    fn set_mode(&self, mode: InodeMode) -> Result<()> {
+        self.metadata.write().mode = mode;
+        Ok(())
+    }
+
+    fn owner(&self) -> Result<Uid> {
+        Ok(self.metadata.read().uid)
+    }
+
+    fn set_owner(&self, uid: Uid) -> Result<()> {
+        self.metadata.write().uid = uid;
+        Ok(())
+    }
+
+    fn group(&self) -> Result<Gid> {
+        Ok(self.metadata.read().gid)
+    }
+
+    fn set_group(&self, gid: Gid) -> Result<()> {
+        self.metadata.write().gid = gid;
+        Ok(())
+    }
+
+    fn atime(&self) -> Duration {
+        self.metadata.read().atime
+    }
+
+    fn set_atime(&self, time: Duration) {
+        self.metadata.write().atime = time;
+    }
+
+    fn mtime(&self) -> Duration {
+        self.metadata.read().mtime
+    }
+
+    fn set_mtime(&self, time: Duration) {
+        self.metadata.write().mtime = time;
+    }
+
+    fn ctime(&self) -> Duration {
+        self.metadata.read().ctime
+    }
+
+    fn set_ctime(&self, time: Duration) {
+        self.metadata.write().ctime = time;
+    }
+
+    fn create(&self, name: &str, type_: InodeType, mode: InodeMode) -> Result<Arc<dyn Inode>> {
+        if type_ != InodeType::File {
+            return_errno_with_message!(Errno::EPERM, "only message queues can be created");
+        }
+
+        // A queue created with `open` has the default attributes.
+        let (uid, gid) = match current_thread!().as_posix_thread() {
+            Some(posix_thread) => {
+                let credentials = posix_thread.credentials();
+                (credentials.fsuid(), credentials.fsgid())
+            }
+            None => (Uid::new_root(), Gid::new_root()),
+        };
+        let queue = self.create_queue(name, mode, MqAttr::default(), uid, gid)?;
+
+        Ok(queue)
+    }
+
+    fn mknod(&self, name: &str, mode: InodeMode, type_: MknodType) -> Result<Arc<dyn Inode>> {
+        Err(Error::new(Errno::EPERM))
+    }
+
+    fn readdir_at(&self, offset: usize, visitor: &mut dyn DirentVisitor) -> Result<usize> {
+        let try_readdir = |offset: &mut usize, visitor: &mut dyn DirentVisitor| -> Result<()> {
+            // Read the 2 special entries.
+            if *offset == 0 {
+                visitor.visit(".", self.ino(), self.type_(), *offset)?;
+                *offset += 1;
+            }
+            if *offset == 1 {
+                visitor.visit("..", self.ino(), self.type_(), *offset)?;
+                *offset += 1;
+            }
+
+            // Read the queues.
+            let queues = self.queues.read();
+            let start_offset = *offset;
+            for (idx, (name, queue)) in queues
+                .iter()
+                .enumerate()
+                .map(|(idx, entry)| (idx + 2, entry))
+                .skip_while(|(idx, _)| idx < &start_offset)
+            {
+                visitor.visit(name.as_ref(), queue.ino(), queue.type_(), idx)?;
+                *offset = idx + 1;
+            }
+            Ok(())
+        };
+
+        let mut iterate_offset = offset;
+        match try_readdir(&mut iterate_offset, visitor) {
+            Err(e) if offset == iterate_offset => Err(e),
+            _ => Ok(iterate_offset - offset),
+        }
+    }
+
+    fn link(&self, old: &Arc<dyn Inode>, name: &str) -> Result<()> {
+        Err(Error::new(Errno::EPERM))
+    }
+
+    fn unlink(&self, name: &str) -> Result<()> {
+        // The queue is destroyed once all the descriptors referring to it are closed.
+        self.queues
+            .write()
+            .remove(name)
+            .ok_or(Error::new(Errno::ENOENT))?;
+        Ok(())
+    }
+
+    fn rmdir(&self, name: &str) -> Result<()> {
+        Err(Error::new(Errno::EPERM))
+    }
+
+    fn lookup(&self, name: &str) -> Result<Arc<dyn Inode>> {
+        let inode: Arc<dyn Inode> = match name {
+            "." | ".." => self.fs().root_inode(),
+            name => self
+                .queues
+                .read()
+                .get(name)
+                .cloned()
+                .ok_or(Error::new(Errno::ENOENT))?,
+        };
+        Ok(inode)
+    }
+
+    fn rename(&self, old_name: &str, target: &Arc<dyn Inode>, new_name: &str) -> Result<()> {
+        Err(Error::new(Errno::EPERM))
+    }
+
+    fn fs(&self) -> Arc<dyn FileSystem> {
+        self.fs.upgrade().unwrap()
+    }
+
+    fn is_dentry_cacheable(&self) -> bool {
+        false
+    }
+}
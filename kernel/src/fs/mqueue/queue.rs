@@ -0,0 +1,575 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#![expect(unused_variables)]
+
+use alloc::format;
+use core::time::Duration;
+
+use super::{MqueueFs, BLOCK_SIZE, DFLT_MSGMAX, DFLT_MSGSIZEMAX};
+use crate::{
+    events::{IoEvents, Observer},
+    fs::{
+        file_handle::FileLike,
+        file_table::{FdEvents, FileTableEntry},
+        utils::{FileSystem, Inode, InodeMode, InodeType, Metadata},
+    },
+    net::socket::netlink::NetlinkRouteSocket,
+    prelude::*,
+    process::{
+        signal::{
+            c_types::{siginfo_t, sigval_t, SigNotify},
+            constants::SI_MESGQ,
+            sig_num::SigNum,
+            signals::Signal,
+            PollHandle, Pollable, Pollee,
+        },
+        Gid, Pid, Process, Uid,
+    },
+    time::clocks::RealTimeCoarseClock,
+};
+
+/// The attributes of a message queue, which are fixed at creation.
+#[derive(Debug, Clone, Copy)]
+pub struct MqAttr {
+    /// Maximum number of messages in the queue
+    pub maxmsg: usize,
+    /// Maximum size in bytes of a message
+    pub msgsize: usize,
+}
+
+impl Default for MqAttr {
+    fn default() -> Self {
+        Self {
+            maxmsg: DFLT_MSGMAX,
+            msgsize: DFLT_MSGSIZEMAX,
+        }
+    }
+}
+
+/// A POSIX message queue, which is an inode in the mqueue file system.
+pub struct MqueueInode {
+    this: Weak<Self>,
+    attr: MqAttr,
+    inner: SpinLock<MqueueInner>,
+    pollee: Pollee,
+    metadata: RwLock<Metadata>,
+    fs: Weak<MqueueFs>,
+}
+
+struct MqueueInner {
+    /// Messages grouped by priority, each group in the order of arrival
+    messages: BTreeMap<u32, VecDeque<Box<[u8]>>>,
+    /// Number of messages in the queue
+    num_messages: usize,
+    /// Total size in bytes of the messages in the queue
+    num_bytes: usize,
+    /// Number of receivers that may be blocked on the queue
+    num_receivers: usize,
+    /// Process registered by `mq_notify`
+    notification: Option<Notification>,
+}
+
+/// A registration of `mq_notify`.
+struct Notification {
+    process: Weak<Process>,
+    pid: Pid,
+    method: NotifyMethod,
+    /// The observer of the file descriptor used to register, which removes the
+    /// registration when the file descriptor is closed.
+    fd_observer: Arc<NotificationFdObserver>,
+}
+
+/// The way to notify the registered process when a message arrives.
+pub enum NotifyMethod {
+    /// Do nothing (`SIGEV_NONE`).
+    None,
+    /// Send the signal with the value (`SIGEV_SIGNAL`).
+    Signal(SigNum, sigval_t),
+    /// Send the cookie to the netlink socket (`SIGEV_THREAD`).
+    ///
+    /// The C library implements `SIGEV_THREAD` with a helper thread, which
+    /// receives the cookie from the socket and starts the notification thread.
+    Cookie {
+        socket: Arc<dyn FileLike>,
+        cookie: [u8; NOTIFY_COOKIE_LEN],
+    },
+}
+
+/// The length of the cookie sent to the netlink socket for `SIGEV_THREAD`.
+pub const NOTIFY_COOKIE_LEN: usize = 32;
+
+/// The values of the last byte of the cookie.
+const NOTIFY_WOKENUP: u8 = 1;
+const NOTIFY_REMOVED: u8 = 2;
+
+impl Notification {
+    fn is_alive(&self) -> bool {
+        self.process.strong_count() > 0
+    }
+
+    /// Tells the C library that the registration is removed, so that it can
+    /// release the resources of its helper thread.
+    fn notify_removed(self) {
+        if let NotifyMethod::Cookie { socket, cookie } = self.method {
+            send_cookie(&socket, cookie, NOTIFY_REMOVED);
+        }
+    }
+}
+
+fn send_cookie(socket: &Arc<dyn FileLike>, mut cookie: [u8; NOTIFY_COOKIE_LEN], status: u8) {
+    cookie[NOTIFY_COOKIE_LEN - 1] = status;
+    if let Some(socket) = socket.downcast_ref::<NetlinkRouteSocket>() {
+        socket.send_raw_from_kernel(Box::new(cookie));
+    }
+}
+
+/// An observer that removes the registration of `mq_notify` when the file
+/// descriptor used to register is closed.
+struct NotificationFdObserver {
+    queue: Weak<MqueueInode>,
+}
+
+impl Observer<FdEvents> for NotificationFdObserver {
+    fn on_events(&self, events: &FdEvents) {
+        if !matches!(events, FdEvents::Close(_)) {
+            return;
+        }
+        let Some(queue) = self.queue.upgrade() else {
+            return;
+        };
+
+        let notification = {
+            let mut inner = queue.inner.lock();
+            if inner
+                .notification
+                .as_ref()
+                .is_some_and(|notification| core::ptr::eq(&*notification.fd_observer, self))
+            {
+                inner.notification.take()
+            } else {
+                None
+            }
+        };
+        if let Some(notification) = notification {
+            notification.notify_removed();
+        }
+    }
+}
+
+impl MqueueInode {
+    pub(super) fn new(
+        ino: u64,
+        mode: InodeMode,
+        attr: MqAttr,
+        uid: Uid,
+        gid: Gid,
+        fs: Weak<MqueueFs>,
+    ) -> Arc<Self> {
+        let mut metadata = Metadata::new_file(ino, mode, BLOCK_SIZE);
+        metadata.uid = uid;
+        metadata.gid = gid;
+
+        Arc::new_cyclic(|this| Self {
+            this: this.clone(),
+            attr,
+            inner: SpinLock::new(MqueueInner {
+                messages: BTreeMap::new(),
+                num_messages: 0,
+                num_bytes: 0,
+                num_receivers: 0,
+                notification: None,
+            }),
+            pollee: Pollee::new(),
+            metadata: RwLock::new(metadata),
+            fs,
+        })
+    }
+
+    /// Returns the message queue that the file refers to.
+    pub fn from_file(file: &Arc<dyn FileLike>) -> Result<&Self> {
+        file.inode()
+            .and_then(|inode| inode.downcast_ref::<Self>())
+            .ok_or_else(|| Error::with_message(Errno::EBADF, "the file is not a message queue"))
+    }
+
+    pub fn attr(&self) -> MqAttr {
+        self.attr
+    }
+
+    /// Returns the number of messages in the queue.
+    pub fn num_messages(&self) -> usize {
+        self.inner.lock().num_messages
+    }
+
+    /// Sends a message with the priority to the queue.
+    ///
+    /// If the queue is full, the caller is blocked until there is free space
+    /// or the timeout expires, unless `is_nonblocking` is true.
+    pub fn send(
+        &self,
+        data: Box<[u8]>,
+        priority: u32,
+        is_nonblocking: bool,
+        timeout: Option<&Duration>,
+        sender: (Pid, Uid),
+    ) -> Result<()> {
+        debug_assert!(data.len() <= self.attr.msgsize);
+
+        let mut data = Some(data);
+        if is_nonblocking {
+            self.try_send(&mut data, priority, sender)
+        } else {
+            self.wait_events(IoEvents::OUT, timeout, || {
+                self.try_send(&mut data, priority, sender)
+            })
+        }
+    }
+
+    fn try_send(
+        &self,
+        data: &mut Option<Box<[u8]>>,
+        priority: u32,
+        sender: (Pid, Uid),
+    ) -> Result<()> {
+        let notification = {
+            let mut inner = self.inner.lock();
+            if inner.num_messages >= self.attr.maxmsg {
+                return_errno_with_message!(Errno::EAGAIN, "the message queue is full");
+            }
+
+            let data = data.take().unwrap();
+            inner.num_bytes += data.len();
+            inner.num_messages += 1;
+            inner.messages.entry(priority).or_default().push_back(data);
+
+            // The notification is only sent when a message arrives on an empty queue
+            // and no other receiver is waiting for it.
+            if inner.num_messages == 1 && inner.num_receivers == 0 {
+                inner.notification.take()
+            } else {
+                None
+            }
+        };
+
+        self.pollee.notify(IoEvents::IN);
+        self.touch();
+
+        if let Some(Notification {
+            process, method, ..
+        }) = notification
+        {
+            match method {
+                NotifyMethod::None => (),
+                NotifyMethod::Signal(num, value) => {
+                    if let Some(process) = process.upgrade() {
+                        process.enqueue_signal(MqueueSignal {
+                            num,
+                            value,
+                            pid: sender.0,
+                            uid: sender.1,
+                        });
+                    }
+                }
+                NotifyMethod::Cookie { socket, cookie } => {
+                    send_cookie(&socket, cookie, NOTIFY_WOKENUP)
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Receives the oldest message with the highest priority from the queue.
+    ///
+    /// If the queue is empty, the caller is blocked until a message arrives or
+    /// the timeout expires, unless `is_nonblocking` is true.
+    pub fn receive(
+        &self,
+        is_nonblocking: bool,
+        timeout: Option<&Duration>,
+    ) -> Result<(Box<[u8]>, u32)> {
+        if is_nonblocking {
+            return self.try_receive();
+        }
+
+        self.inner.lock().num_receivers += 1;
+        let res = self.wait_events(IoEvents::IN, timeout, || self.try_receive());
+        self.inner.lock().num_receivers -= 1;
+
+        res
+    }
+
+    fn try_receive(&self) -> Result<(Box<[u8]>, u32)> {
+        let (data, priority) = {
+            let mut inner = self.inner.lock();
+            let Some(mut entry) = inner.messages.last_entry() else {
+                return_errno_with_message!(Errno::EAGAIN, "the message queue is empty");
+            };
+
+            let priority = *entry.key();
+            let data = entry.get_mut().pop_front().unwrap();
+            if entry.get().is_empty() {
+                entry.remove();
+            }
+
+            inner.num_bytes -= data.len();
+            inner.num_messages -= 1;
+            (data, priority)
+        };
+
+        self.pollee.notify(IoEvents::OUT);
+        self.touch();
+
+        Ok((data, priority))
+    }
+
+    /// Registers the process to be notified when a message arrives on the
+    /// empty queue, as is done by `mq_notify`.
+    ///
+    /// The registration is removed when the file descriptor of `fd_entry` is
+    /// closed.
+    pub fn register_notification(
+        &self,
+        process: Weak<Process>,
+        pid: Pid,
+        method: NotifyMethod,
+        fd_entry: &FileTableEntry,
+    ) -> Result<()> {
+        let fd_observer = Arc::new(NotificationFdObserver {
+            queue: self.this.clone(),
+        });
+
+        let old_notification = {
+            let mut inner = self.inner.lock();
+            if inner
+                .notification
+                .as_ref()
+                .is_some_and(|notification| notification.is_alive())
+            {
+                return_errno_with_message!(Errno::EBUSY, "another process has been registered");
+            }
+
+            inner.notification.replace(Notification {
+                process,
+                pid,
+                method,
+                fd_observer: fd_observer.clone(),
+            })
+        };
+        drop(old_notification);
+
+        fd_entry.register_observer(Arc::downgrade(&fd_observer) as _);
+
+        Ok(())
+    }
+
+    /// Removes the registration of the process, if any.
+    pub fn unregister_notification(&self, pid: Pid) {
+        let notification = {
+            let mut inner = self.inner.lock();
+            if inner
+                .notification
+                .as_ref()
+                .is_some_and(|notification| notification.pid == pid)
+            {
+                inner.notification.take()
+            } else {
+                None
+            }
+        };
+
+        if let Some(notification) = notification {
+            notification.notify_removed();
+        }
+    }
+
+    fn check_io_events(&self) -> IoEvents {
+        let inner = self.inner.lock();
+
+        let mut events = IoEvents::empty();
+        if inner.num_messages > 0 {
+            events |= IoEvents::IN;
+        }
+        if inner.num_messages < self.attr.maxmsg {
+            events |= IoEvents::OUT;
+        }
+
+        events
+    }
+
+    /// Returns the status of the queue, which is the content of the queue file.
+    ///
+    /// Reference: <https://man7.org/linux/man-pages/man7/mq_overview.7.html>
+    fn status(&self) -> String {
+        let inner = self.inner.lock();
+
+        let (notify, signo, notify_pid) = match inner.notification.as_ref() {
+            Some(notification) if notification.is_alive() => match notification.method {
+                NotifyMethod::None => (SigNotify::SIGEV_NONE as i32, 0, notification.pid),
+                NotifyMethod::Signal(num, _) => (
+                    SigNotify::SIGEV_SIGNAL as i32,
+                    num.as_u8() as i32,
+                    notification.pid,
+                ),
+                NotifyMethod::Cookie { .. } => {
+                    (SigNotify::SIGEV_THREAD as i32, 0, notification.pid)
+                }
+            },
+            _ => (0, 0, 0),
+        };
+
+        format!(
+            "QSIZE:{:<10} NOTIFY:{:<5} SIGNO:{:<5} NOTIFY_PID:{:<6}\n",
+            inner.num_bytes, notify, signo, notify_pid
+        )
+    }
+
+    fn touch(&self) {
+        let now = RealTimeCoarseClock::get().read_time();
+        let mut metadata = self.metadata.write();
+        metadata.atime = now;
+        metadata.mtime = now;
+        metadata.ctime = now;
+    }
+}
+
+impl Pollable for MqueueInode {
+    fn poll(&self, mask: IoEvents, poller: Option<&mut PollHandle>) -> IoEvents {
+        self.pollee
+            .poll_with(mask, poller, || self.check_io_events())
+    }
+}
+
+impl Inode for MqueueInode {
+    fn size(&self) -> usize {
+        self.metadata.read().size
+    }
+
+    fn resize(&self, new_size: usize) -> Result<()> {
+        Err(Error::new(Errno::EPERM))
+    }
+
+    fn metadata(&self) -> Metadata {
+        *self.metadata.read()
+    }
+
+    fn ino(&self) -> u64 {
+        self.metadata.read().ino as _
+    }
+
+    fn type_(&self) -> InodeType {
+        self.metadata.read().type_
+    }
+
+    fn mode(&self) -> Result<InodeMode> {
+        Ok(self.metadata.read().mode)
+    }
+
+    fn set_mode(&self, mode: InodeMode) -> Result<()> {
+        self.metadata.write().mode = mode;
+        Ok(())
+    }
+
+    fn owner(&self) -> Result<Uid> {
+        Ok(self.metadata.read().uid)
+    }
+
+    fn set_owner(&self, uid: Uid) -> Result<()> {
+        self.metadata.write().uid = uid;
+        Ok(())
+    }
+
+    fn group(&self) -> Result<Gid> {
+        Ok(self.metadata.read().gid)
+    }
+
+    fn set_group(&self, gid: Gid) -> Result<()> {
+        self.metadata.write().gid = gid;
+        Ok(())
+    }
+
+    fn atime(&self) -> Duration {
+        self.metadata.read().atime
+    }
+
+    fn set_atime(&self, time: Duration) {
+        self.metadata.write().atime = time;
+    }
+
+    fn mtime(&self) -> Duration {
+        self.metadata.read().mtime
+    }
+
+    fn set_mtime(&self, time: Duration) {
+        self.metadata.write().mtime = time;
+    }
+
+    fn ctime(&self) -> Duration {
+        self.metadata.read().ctime
+    }
+
+    fn set_ctime(&self, time: Duration) {
+        self.metadata.write().ctime = time;
+    }
+
+    fn read_at(&self, offset: usize, writer: &mut VmWriter) -> Result<usize> {
+        let status = self.status();
+        let data = status.as_bytes();
+        let start = data.len().min(offset);
+        let end = data.len().min(offset + writer.avail());
+        let len = end - start;
+        writer.write_fallible(&mut (&data[start..end]).into())?;
+        Ok(len)
+    }
+
+    fn read_direct_at(&self, offset: usize, writer: &mut VmWriter) -> Result<usize> {
+        self.read_at(offset, writer)
+    }
+
+    fn poll(&self, mask: IoEvents, poller: Option<&mut PollHandle>) -> IoEvents {
+        Pollable::poll(self, mask, poller)
+    }
+
+    fn fs(&self) -> Arc<dyn FileSystem> {
+        self.fs.upgrade().unwrap()
+    }
+
+    fn is_dentry_cacheable(&self) -> bool {
+        false
+    }
+}
+
+/// The signal sent by the notification of a message queue.
+#[derive(Clone, Copy)]
+struct MqueueSignal {
+    num: SigNum,
+    value: sigval_t,
+    /// PID of the sender of the message
+    pid: Pid,
+    /// Real UID of the sender of the message
+    uid: Uid,
+}
+
+impl Debug for MqueueSignal {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_struct("MqueueSignal")
+            .field("num", &self.num)
+            .field("value", &self.value.read_ptr())
+            .field("pid", &self.pid)
+            .field("uid", &self.uid)
+            .finish()
+    }
+}
+
+impl Signal for MqueueSignal {
+    fn num(&self) -> SigNum {
+        self.num
+    }
+
+    fn to_info(&self) -> siginfo_t {
+        let mut info = siginfo_t::new(self.num, SI_MESGQ);
+        info.set_pid_uid(self.pid, self.uid);
+        info.set_si_value(self.value);
+        info
+    }
+}
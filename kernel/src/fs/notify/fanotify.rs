@@ -0,0 +1,796 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use core::{
+    mem::offset_of,
+    sync::atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicU8, Ordering},
+};
+
+use ostd::{sync::WaitQueue, task::Task};
+
+use super::{FsEvent, FsEvents, FsNotifyMark, FsNotifyMarks};
+use crate::{
+    current_userspace,
+    events::IoEvents,
+    fs::{
+        file_handle::FileLike,
+        file_table::{FdFlags, FileDesc},
+        inode_handle::InodeHandle,
+        path::{MountNode, Path},
+        utils::{AccessMode, CreationFlags, Inode, InodeMode, IoctlCmd, Metadata, StatusFlags},
+    },
+    prelude::*,
+    process::{
+        posix_thread::{AsPosixThread, ThreadLocal},
+        signal::{PollHandle, Pollable, Pollee},
+        Pid,
+    },
+    thread::Thread,
+};
+
+bitflags! {
+    /// The flags of `fanotify_init`.
+    pub struct FanotifyInitFlags: u32 {
+        /// Sets the close-on-exec flag of the fanotify file.
+        const FAN_CLOEXEC           = 0x00000001;
+        /// Makes the fanotify file nonblocking.
+        const FAN_NONBLOCK          = 0x00000002;
+        /// Allows the permission events, which are reported after the file
+        /// content is ready.
+        const FAN_CLASS_CONTENT     = 0x00000004;
+        /// Allows the permission events, which are reported before the file
+        /// content is ready.
+        const FAN_CLASS_PRE_CONTENT = 0x00000008;
+        /// Removes the limit on the number of queued events.
+        const FAN_UNLIMITED_QUEUE   = 0x00000010;
+        /// Removes the limit on the number of marks.
+        const FAN_UNLIMITED_MARKS   = 0x00000020;
+        /// Reports the thread IDs instead of the process IDs.
+        const FAN_REPORT_TID        = 0x00000100;
+    }
+}
+
+bitflags! {
+    /// The flags of `fanotify_mark`.
+    pub struct FanotifyMarkFlags: u32 {
+        /// Adds the events to the mark.
+        const FAN_MARK_ADD         = 0x00000001;
+        /// Removes the events from the mark.
+        const FAN_MARK_REMOVE      = 0x00000002;
+        /// Does not follow the path if it is a symbolic link.
+        const FAN_MARK_DONT_FOLLOW = 0x00000004;
+        /// Fails if the path is not a directory.
+        const FAN_MARK_ONLYDIR     = 0x00000008;
+        /// Marks the mount of the path instead of the inode.
+        const FAN_MARK_MOUNT       = 0x00000010;
+        /// Removes all the inode marks or all the mount marks.
+        const FAN_MARK_FLUSH       = 0x00000080;
+    }
+}
+
+/// The object marked by a fanotify instance.
+pub enum FanotifyTarget {
+    Inode(Arc<dyn Inode>),
+    Mount(Arc<MountNode>),
+}
+
+/// The events that can be reported by fanotify.
+const FANOTIFY_EVENTS: FsEvents = FsEvents::from_bits_truncate(
+    FsEvents::ACCESS.bits()
+        | FsEvents::MODIFY.bits()
+        | FsEvents::CLOSE.bits()
+        | FsEvents::OPEN.bits()
+        | FsEvents::PERM_EVENTS.bits(),
+);
+
+/// The events and the flags that can be specified in the mask of a fanotify mark.
+///
+/// `FsEvents::ISDIR` is `FAN_ONDIR` in fanotify, which makes the mark report
+/// the events on directories.
+const MARK_MASK: FsEvents = FsEvents::from_bits_truncate(
+    FANOTIFY_EVENTS.bits() | FsEvents::ISDIR.bits() | FsEvents::EVENT_ON_CHILD.bits(),
+);
+
+/// The maximum number of queued events of a fanotify instance.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.15.7/source/fs/notify/fanotify/fanotify_user.c#L30>
+const MAX_QUEUED_EVENTS: usize = 16384;
+
+/// The maximum number of marks of a fanotify instance.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.15.7/source/fs/notify/fanotify/fanotify_user.c#L31>
+const MAX_MARKS: usize = 8192;
+
+/// The file descriptor reported by the events that are not associated with files.
+const FAN_NOFD: FileDesc = -1;
+
+/// A fanotify instance, which monitors the accesses to the files of the marked
+/// inodes and mounts, and optionally decides whether the accesses are permitted.
+pub struct FanotifyFile {
+    marks: Mutex<Vec<(FanotifyTarget, Arc<FanotifyMark>)>>,
+    queue: Mutex<VecDeque<FanotifyEvent>>,
+    /// The permission requests that have been read but not responded.
+    ///
+    /// The requests are keyed by unique IDs in the order of reading, since the
+    /// file descriptors reported with them may be closed and reused.
+    pending_requests: Mutex<BTreeMap<u64, PendingRequest>>,
+    next_request_id: AtomicU64,
+    pollee: Pollee,
+    is_nonblocking: AtomicBool,
+    flags: FanotifyInitFlags,
+    event_access_mode: AccessMode,
+    event_status_flags: StatusFlags,
+    event_fd_flags: FdFlags,
+    this: Weak<FanotifyFile>,
+}
+
+impl FanotifyFile {
+    /// Creates a new fanotify instance.
+    ///
+    /// The files reported with the events are opened with `event_flags`.
+    pub fn new(flags: FanotifyInitFlags, event_flags: u32) -> Result<Arc<Self>> {
+        if flags.contains(
+            FanotifyInitFlags::FAN_CLASS_CONTENT | FanotifyInitFlags::FAN_CLASS_PRE_CONTENT,
+        ) {
+            return_errno_with_message!(Errno::EINVAL, "the notification class is invalid");
+        }
+
+        let event_access_mode = AccessMode::from_u32(event_flags)?;
+        let event_creation_flags = CreationFlags::from_bits_truncate(event_flags);
+        let event_status_flags = StatusFlags::from_bits_truncate(event_flags);
+        let supported_creation_flags = CreationFlags::O_CLOEXEC;
+        let supported_status_flags = StatusFlags::O_APPEND
+            | StatusFlags::O_NONBLOCK
+            | StatusFlags::O_DSYNC
+            | StatusFlags::O_NOATIME
+            | StatusFlags::O_SYNC;
+        let supported_flags = (event_creation_flags & supported_creation_flags).bits()
+            | (event_status_flags & supported_status_flags).bits();
+        if (event_flags & !0b11) != supported_flags {
+            return_errno_with_message!(Errno::EINVAL, "the event file flags are invalid");
+        }
+
+        let event_fd_flags = if event_creation_flags.contains(CreationFlags::O_CLOEXEC) {
+            FdFlags::CLOEXEC
+        } else {
+            FdFlags::empty()
+        };
+
+        Ok(Arc::new_cyclic(|weak_self| Self {
+            marks: Mutex::new(Vec::new()),
+            queue: Mutex::new(VecDeque::new()),
+            pending_requests: Mutex::new(BTreeMap::new()),
+            next_request_id: AtomicU64::new(0),
+            pollee: Pollee::new(),
+            is_nonblocking: AtomicBool::new(flags.contains(FanotifyInitFlags::FAN_NONBLOCK)),
+            flags,
+            event_access_mode,
+            event_status_flags,
+            event_fd_flags,
+            this: weak_self.clone(),
+        }))
+    }
+
+    /// Adds `mask` to the mark of the target, or creates a new mark.
+    pub fn add_mark(&self, target: FanotifyTarget, mask: FsEvents) -> Result<()> {
+        self.check_mask(mask)?;
+        if mask.is_empty() {
+            return_errno_with_message!(Errno::EINVAL, "no events are specified");
+        }
+
+        let mut marks = self.marks.lock();
+
+        if let Some((_, mark)) = marks.iter().find(|(marked, _)| marked.is_same(&target)) {
+            mark.mask.fetch_or(mask.bits(), Ordering::Relaxed);
+            return Ok(());
+        }
+
+        if marks.len() >= MAX_MARKS && !self.flags.contains(FanotifyInitFlags::FAN_UNLIMITED_MARKS)
+        {
+            return_errno_with_message!(Errno::ENOSPC, "too many marks");
+        }
+
+        let mark = Arc::new(FanotifyMark {
+            mask: AtomicU32::new(mask.bits()),
+            file: self.this.clone(),
+        });
+        target.attach(mark.clone())?;
+        marks.push((target, mark));
+
+        Ok(())
+    }
+
+    /// Removes `mask` from the mark of the target.
+    ///
+    /// The mark is removed if it no longer has any events.
+    pub fn remove_mark(&self, target: &FanotifyTarget, mask: FsEvents) -> Result<()> {
+        self.check_mask(mask)?;
+
+        let mut marks = self.marks.lock();
+
+        let Some(index) = marks.iter().position(|(marked, _)| marked.is_same(target)) else {
+            return_errno_with_message!(Errno::ENOENT, "the target is not marked");
+        };
+        let mark = &marks[index].1;
+        let old_mask = mark.mask.fetch_and(!mask.bits(), Ordering::Relaxed);
+        if old_mask & !mask.bits() & FANOTIFY_EVENTS.bits() == 0 {
+            let (target, mark) = marks.swap_remove(index);
+            target.detach(&(mark as Arc<dyn FsNotifyMark>));
+        }
+
+        Ok(())
+    }
+
+    /// Removes all the mount marks if `is_mount` is true, or all the inode
+    /// marks otherwise.
+    pub fn flush_marks(&self, is_mount: bool) {
+        self.marks.lock().retain(|(target, mark)| {
+            if target.is_mount() != is_mount {
+                return true;
+            }
+            target.detach(&(mark.clone() as Arc<dyn FsNotifyMark>));
+            false
+        });
+    }
+
+    fn check_mask(&self, mask: FsEvents) -> Result<()> {
+        if !MARK_MASK.contains(mask) {
+            return_errno_with_message!(Errno::EINVAL, "the events are not supported");
+        }
+        if mask.intersects(FsEvents::PERM_EVENTS) && !self.allows_perm_events() {
+            return_errno_with_message!(
+                Errno::EINVAL,
+                "permission events are not allowed in the notification class"
+            );
+        }
+        Ok(())
+    }
+
+    fn allows_perm_events(&self) -> bool {
+        self.flags.intersects(
+            FanotifyInitFlags::FAN_CLASS_CONTENT | FanotifyInitFlags::FAN_CLASS_PRE_CONTENT,
+        )
+    }
+
+    /// Returns the events of `event` that are interesting to a mark of `mask`.
+    fn interesting_events(mask: FsEvents, event: &FsEvent) -> FsEvents {
+        // Only the events on opened files are reported.
+        if event.path.is_none() {
+            return FsEvents::empty();
+        }
+        if event.name.is_some() && !mask.contains(FsEvents::EVENT_ON_CHILD) {
+            return FsEvents::empty();
+        }
+        if event.events.contains(FsEvents::ISDIR) && !mask.contains(FsEvents::ISDIR) {
+            return FsEvents::empty();
+        }
+        event.events & mask & FANOTIFY_EVENTS
+    }
+
+    fn handle_event(&self, mask: FsEvents, event: &FsEvent) {
+        let events = Self::interesting_events(mask, event) - FsEvents::PERM_EVENTS;
+        if events.is_empty() {
+            return;
+        }
+
+        self.queue_event(FanotifyEvent {
+            mask: events | (event.events & FsEvents::ISDIR),
+            path: event.path.cloned(),
+            pid: self.current_pid(),
+            request: None,
+        });
+    }
+
+    /// Queues a permission request for `event`, if it is interesting.
+    ///
+    /// Returns the queued request, whose response should be waited for.
+    fn handle_perm_event(&self, mask: FsEvents, event: &FsEvent) -> Option<Arc<PermRequest>> {
+        let events = Self::interesting_events(mask, event) & FsEvents::PERM_EVENTS;
+        if events.is_empty() {
+            return None;
+        }
+
+        let request = Arc::new(PermRequest::new());
+        let is_queued = self.queue_event(FanotifyEvent {
+            mask: events | (event.events & FsEvents::ISDIR),
+            path: event.path.cloned(),
+            pid: self.current_pid(),
+            request: Some(request.clone()),
+        });
+        // The access is permitted if the request cannot be queued.
+        is_queued.then_some(request)
+    }
+
+    /// Queues an event.
+    ///
+    /// Returns false if the event is dropped because the queue overflows.
+    fn queue_event(&self, event: FanotifyEvent) -> bool {
+        let mut queue = self.queue.lock();
+
+        // Merges the event with the last one if they are on the same file.
+        if let Some(last) = queue.back_mut()
+            && last.can_merge(&event)
+        {
+            last.mask |= event.mask;
+            return true;
+        }
+
+        let is_queued = if queue.len() < MAX_QUEUED_EVENTS
+            || self.flags.contains(FanotifyInitFlags::FAN_UNLIMITED_QUEUE)
+        {
+            queue.push_back(event);
+            true
+        } else {
+            if queue
+                .back()
+                .is_none_or(|last| last.mask != FsEvents::Q_OVERFLOW)
+            {
+                queue.push_back(FanotifyEvent {
+                    mask: FsEvents::Q_OVERFLOW,
+                    path: None,
+                    pid: 0,
+                    request: None,
+                });
+            }
+            false
+        };
+
+        self.pollee.notify(IoEvents::IN);
+        is_queued
+    }
+
+    fn current_pid(&self) -> Pid {
+        let Some(thread) = Thread::current() else {
+            return 0;
+        };
+        let Some(posix_thread) = thread.as_posix_thread() else {
+            return 0;
+        };
+        if self.flags.contains(FanotifyInitFlags::FAN_REPORT_TID) {
+            posix_thread.tid()
+        } else {
+            posix_thread.process().pid()
+        }
+    }
+
+    fn try_read(&self, writer: &mut VmWriter) -> Result<usize> {
+        let mut queue = self.queue.lock();
+        if queue.is_empty() {
+            return_errno_with_message!(Errno::EAGAIN, "no events are available");
+        }
+
+        let mut read_len = 0;
+        let mut event_files = Vec::new();
+        while !queue.is_empty() {
+            if writer.avail() < size_of::<CFanotifyEventMetadata>() {
+                if read_len == 0 {
+                    return_errno_with_message!(Errno::EINVAL, "the buffer is too small");
+                }
+                break;
+            }
+
+            let event = queue.pop_front().unwrap();
+            if let Err(err) = self.read_event(&event, writer, &mut event_files) {
+                // The access is denied if the request cannot be read.
+                if let Some(request) = &event.request {
+                    request.respond(false);
+                }
+                if read_len == 0 {
+                    return Err(err);
+                }
+                break;
+            }
+            read_len += size_of::<CFanotifyEventMetadata>();
+        }
+
+        self.pollee.invalidate();
+        drop(queue);
+
+        // The file table may be borrowed by the system call that reads the events, so the files
+        // are installed after the system call completes.
+        if !event_files.is_empty() {
+            let this = self.this.upgrade().unwrap();
+            let current = Task::current().unwrap();
+            current
+                .as_thread_local()
+                .unwrap()
+                .add_task_work(Box::new(move |thread_local| {
+                    this.install_event_files(event_files, thread_local)
+                }));
+        }
+
+        Ok(read_len)
+    }
+
+    /// Writes the event to `writer`.
+    ///
+    /// The file of the event is opened and pushed to `event_files`, whose file
+    /// descriptor is written to the event once the file is installed.
+    fn read_event(
+        &self,
+        event: &FanotifyEvent,
+        writer: &mut VmWriter,
+        event_files: &mut Vec<EventFile>,
+    ) -> Result<()> {
+        let file: Option<Arc<dyn FileLike>> = match &event.path {
+            Some(path) => Some(Arc::new(InodeHandle::new_without_notify(
+                path.clone(),
+                self.event_access_mode,
+                self.event_status_flags,
+            )?)),
+            None => None,
+        };
+
+        let fd_addr = writer.cursor() as Vaddr + offset_of!(CFanotifyEventMetadata, fd);
+        let metadata = CFanotifyEventMetadata {
+            event_len: size_of::<CFanotifyEventMetadata>() as u32,
+            vers: FANOTIFY_METADATA_VERSION,
+            reserved: 0,
+            metadata_len: size_of::<CFanotifyEventMetadata>() as u16,
+            mask: event.mask.bits() as u64,
+            fd: FAN_NOFD,
+            pid: event.pid as i32,
+        };
+        writer.write_val(&metadata)?;
+
+        let request_id = event.request.as_ref().map(|request| {
+            let request_id = self.next_request_id.fetch_add(1, Ordering::Relaxed);
+            self.pending_requests.lock().insert(
+                request_id,
+                PendingRequest {
+                    fd: FAN_NOFD,
+                    request: request.clone(),
+                },
+            );
+            request_id
+        });
+
+        if let Some(file) = file {
+            event_files.push(EventFile {
+                file,
+                fd_addr,
+                request_id,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Installs the files of the read events into the file table, and writes
+    /// their file descriptors to the events.
+    fn install_event_files(&self, event_files: Vec<EventFile>, thread_local: &ThreadLocal) {
+        let file_table = thread_local.borrow_file_table();
+        let file_table = file_table.unwrap();
+
+        let fds: Vec<FileDesc> = {
+            let mut file_table_locked = file_table.write();
+            event_files
+                .iter()
+                .map(|event_file| {
+                    file_table_locked.insert(event_file.file.clone(), self.event_fd_flags)
+                })
+                .collect()
+        };
+
+        let user_space = current_userspace!();
+        // The closed files are dropped after the file table lock is released.
+        let mut closed_files = Vec::new();
+        for (event_file, fd) in event_files.iter().zip(fds) {
+            let is_reported = user_space.write_val(event_file.fd_addr, &fd).is_ok();
+            if !is_reported {
+                closed_files.extend(file_table.write().close_file(fd));
+            }
+
+            let Some(request_id) = event_file.request_id else {
+                continue;
+            };
+            let mut pending_requests = self.pending_requests.lock();
+            if is_reported {
+                if let Some(pending_request) = pending_requests.get_mut(&request_id) {
+                    pending_request.fd = fd;
+                }
+            } else if let Some(pending_request) = pending_requests.remove(&request_id) {
+                // The access is denied if the request cannot be reported.
+                pending_request.request.respond(false);
+            }
+        }
+    }
+
+    fn write_response(&self, reader: &mut VmReader) -> Result<usize> {
+        if reader.remain() < size_of::<CFanotifyResponse>() {
+            return_errno_with_message!(Errno::EINVAL, "the buffer is too small");
+        }
+        let response = reader.read_val::<CFanotifyResponse>()?;
+
+        let is_allowed = match response.response {
+            FAN_ALLOW => true,
+            FAN_DENY => false,
+            _ => return_errno_with_message!(Errno::EINVAL, "the response is invalid"),
+        };
+        if response.fd < 0 {
+            return_errno_with_message!(Errno::EINVAL, "the file descriptor is invalid");
+        }
+
+        // Like Linux, the earliest request reported with the file descriptor is responded.
+        let request = {
+            let mut pending_requests = self.pending_requests.lock();
+            let Some(request_id) = pending_requests
+                .iter()
+                .find(|(_, pending_request)| pending_request.fd == response.fd)
+                .map(|(request_id, _)| *request_id)
+            else {
+                return_errno_with_message!(Errno::ENOENT, "no permission request is pending");
+            };
+            pending_requests.remove(&request_id).unwrap().request
+        };
+        request.respond(is_allowed);
+
+        Ok(size_of::<CFanotifyResponse>())
+    }
+
+    fn check_io_events(&self) -> IoEvents {
+        if self.queue.lock().is_empty() {
+            IoEvents::empty()
+        } else {
+            IoEvents::IN
+        }
+    }
+}
+
+impl Drop for FanotifyFile {
+    fn drop(&mut self) {
+        for (target, mark) in core::mem::take(self.marks.get_mut()) {
+            target.detach(&(mark as Arc<dyn FsNotifyMark>));
+        }
+
+        // The accesses waiting for the responses are permitted.
+        for event in self.queue.get_mut().drain(..) {
+            if let Some(request) = event.request {
+                request.respond(true);
+            }
+        }
+        for pending_request in core::mem::take(self.pending_requests.get_mut()).into_values() {
+            pending_request.request.respond(true);
+        }
+    }
+}
+
+impl Pollable for FanotifyFile {
+    fn poll(&self, mask: IoEvents, poller: Option<&mut PollHandle>) -> IoEvents {
+        self.pollee
+            .poll_with(mask, poller, || self.check_io_events())
+    }
+}
+
+impl FileLike for FanotifyFile {
+    fn read(&self, writer: &mut VmWriter) -> Result<usize> {
+        if self.is_nonblocking.load(Ordering::Relaxed) {
+            self.try_read(writer)
+        } else {
+            self.wait_events(IoEvents::IN, None, || self.try_read(writer))
+        }
+    }
+
+    fn write(&self, reader: &mut VmReader) -> Result<usize> {
+        self.write_response(reader)
+    }
+
+    fn ioctl(&self, cmd: IoctlCmd, arg: usize) -> Result<i32> {
+        match cmd {
+            IoctlCmd::FIONREAD => {
+                let len = self.queue.lock().len() * size_of::<CFanotifyEventMetadata>();
+                current_userspace!().write_val(arg, &(len as i32))?;
+                Ok(0)
+            }
+            _ => return_errno_with_message!(Errno::EINVAL, "the ioctl command is not supported"),
+        }
+    }
+
+    fn status_flags(&self) -> StatusFlags {
+        if self.is_nonblocking.load(Ordering::Relaxed) {
+            StatusFlags::O_NONBLOCK
+        } else {
+            StatusFlags::empty()
+        }
+    }
+
+    fn set_status_flags(&self, new_flags: StatusFlags) -> Result<()> {
+        self.is_nonblocking.store(
+            new_flags.contains(StatusFlags::O_NONBLOCK),
+            Ordering::Relaxed,
+        );
+        Ok(())
+    }
+
+    fn metadata(&self) -> Metadata {
+        // This is a dummy implementation.
+        // TODO: Add "anonymous inode fs" and link `FanotifyFile` to it.
+        Metadata::new_file(
+            0,
+            InodeMode::from_bits_truncate(0o600),
+            aster_block::BLOCK_SIZE,
+        )
+    }
+}
+
+impl FanotifyTarget {
+    fn attach(&self, mark: Arc<dyn FsNotifyMark>) -> Result<()> {
+        match self {
+            Self::Inode(inode) => FsNotifyMarks::of_inode(inode)?.add(mark),
+            Self::Mount(mount) => mount.notify_marks().add(mark),
+        }
+        Ok(())
+    }
+
+    fn detach(&self, mark: &Arc<dyn FsNotifyMark>) {
+        match self {
+            Self::Inode(inode) => {
+                if let Some(marks) = FsNotifyMarks::get(inode) {
+                    marks.remove(mark);
+                }
+            }
+            Self::Mount(mount) => mount.notify_marks().remove(mark),
+        }
+    }
+
+    fn is_same(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Inode(this), Self::Inode(other)) => {
+                core::ptr::addr_eq(Arc::as_ptr(this), Arc::as_ptr(other))
+            }
+            (Self::Mount(this), Self::Mount(other)) => Arc::ptr_eq(this, other),
+            _ => false,
+        }
+    }
+
+    fn is_mount(&self) -> bool {
+        matches!(self, Self::Mount(_))
+    }
+}
+
+/// A mark of a fanotify instance, which is attached to the marked inode or mount.
+struct FanotifyMark {
+    mask: AtomicU32,
+    file: Weak<FanotifyFile>,
+}
+
+impl FanotifyMark {
+    fn mask(&self) -> FsEvents {
+        FsEvents::from_bits_truncate(self.mask.load(Ordering::Relaxed))
+    }
+}
+
+impl FsNotifyMark for FanotifyMark {
+    fn handle_event(&self, event: &FsEvent) {
+        if let Some(file) = self.file.upgrade() {
+            file.handle_event(self.mask(), event);
+        }
+    }
+
+    fn handle_perm_event(&self, event: &FsEvent) -> Result<()> {
+        let Some(request) = self
+            .file
+            .upgrade()
+            .and_then(|file| file.handle_perm_event(self.mask(), event))
+        else {
+            return Ok(());
+        };
+
+        // The fanotify instance is not kept alive while waiting, so that the
+        // access is permitted once the instance is closed.
+        request.wait()
+    }
+}
+
+/// A request for the permission of an access, which is responded by the listener.
+struct PermRequest {
+    response: AtomicU8,
+    wait_queue: WaitQueue,
+}
+
+const RESPONSE_NONE: u8 = 0;
+const RESPONSE_ALLOW: u8 = 1;
+const RESPONSE_DENY: u8 = 2;
+
+impl PermRequest {
+    fn new() -> Self {
+        Self {
+            response: AtomicU8::new(RESPONSE_NONE),
+            wait_queue: WaitQueue::new(),
+        }
+    }
+
+    fn respond(&self, is_allowed: bool) {
+        let response = if is_allowed {
+            RESPONSE_ALLOW
+        } else {
+            RESPONSE_DENY
+        };
+        self.response.store(response, Ordering::Release);
+        self.wait_queue.wake_all();
+    }
+
+    /// Waits for the response, and returns an error if the access is denied.
+    fn wait(&self) -> Result<()> {
+        let response = self.wait_queue.pause_until(|| {
+            let response = self.response.load(Ordering::Acquire);
+            (response != RESPONSE_NONE).then_some(response)
+        })?;
+        if response == RESPONSE_DENY {
+            return_errno_with_message!(Errno::EPERM, "the access is denied by fanotify");
+        }
+        Ok(())
+    }
+}
+
+/// A permission request that has been read.
+struct PendingRequest {
+    /// The file descriptor reported with the request, or [`FAN_NOFD`] if the
+    /// file is not installed yet.
+    fd: FileDesc,
+    request: Arc<PermRequest>,
+}
+
+/// The file of a read event, which is to be installed into the file table.
+struct EventFile {
+    file: Arc<dyn FileLike>,
+    /// The user address of the file descriptor in the read event.
+    fd_addr: Vaddr,
+    /// The ID of the permission request of the event, if any.
+    request_id: Option<u64>,
+}
+
+struct FanotifyEvent {
+    mask: FsEvents,
+    /// The path of the accessed file, or `None` for an overflow event.
+    path: Option<Path>,
+    pid: Pid,
+    request: Option<Arc<PermRequest>>,
+}
+
+impl FanotifyEvent {
+    /// Returns whether `other` can be merged into this event.
+    ///
+    /// Permission events are never merged, since each of them needs a response.
+    fn can_merge(&self, other: &Self) -> bool {
+        let (Some(path), Some(other_path)) = (&self.path, &other.path) else {
+            return false;
+        };
+        self.request.is_none()
+            && other.request.is_none()
+            && self.pid == other.pid
+            && core::ptr::addr_eq(Arc::as_ptr(path.inode()), Arc::as_ptr(other_path.inode()))
+    }
+}
+
+/// The version of [`CFanotifyEventMetadata`].
+const FANOTIFY_METADATA_VERSION: u8 = 3;
+
+/// The metadata of an event read from a fanotify instance, which is
+/// `struct fanotify_event_metadata` in Linux.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.15.7/source/include/uapi/linux/fanotify.h#L137>
+#[derive(Debug, Clone, Copy, Pod)]
+#[repr(C)]
+struct CFanotifyEventMetadata {
+    event_len: u32,
+    vers: u8,
+    reserved: u8,
+    metadata_len: u16,
+    mask: u64,
+    fd: i32,
+    pid: i32,
+}
+
+const FAN_ALLOW: u32 = 0x01;
+const FAN_DENY: u32 = 0x02;
+
+/// The response to a permission event written to a fanotify instance, which
+/// is `struct fanotify_response` in Linux.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.15.7/source/include/uapi/linux/fanotify.h#L222>
+#[derive(Debug, Clone, Copy, Pod)]
+#[repr(C)]
+struct CFanotifyResponse {
+    fd: i32,
+    response: u32,
+}
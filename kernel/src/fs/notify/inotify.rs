@@ -0,0 +1,395 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use core::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+use super::{FsEvent, FsEvents, FsNotifyMark, FsNotifyMarks};
+use crate::{
+    current_userspace,
+    events::IoEvents,
+    fs::{
+        file_handle::FileLike,
+        utils::{Inode, InodeMode, IoctlCmd, Metadata, StatusFlags},
+    },
+    prelude::*,
+    process::signal::{PollHandle, Pollable, Pollee},
+};
+
+bitflags! {
+    /// The flags that control how an inotify watch is added.
+    ///
+    /// The flags share the same mask with the watched [`FsEvents`].
+    pub struct WatchFlags: u32 {
+        /// Only watches the path if it is a directory.
+        const IN_ONLYDIR     = 0x01000000;
+        /// Does not follow the path if it is a symbolic link.
+        const IN_DONT_FOLLOW = 0x02000000;
+        /// Excludes the events on the unlinked children.
+        const IN_EXCL_UNLINK = 0x04000000;
+        /// Only creates a new watch, and fails if the path is already watched.
+        const IN_MASK_CREATE = 0x10000000;
+        /// Adds the events to the existing watch instead of replacing them.
+        const IN_MASK_ADD    = 0x20000000;
+        /// Removes the watch after the first event.
+        const IN_ONESHOT     = 0x80000000;
+    }
+}
+
+/// The maximum number of queued events of an inotify instance.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.15.7/source/fs/notify/inotify/inotify_user.c#L864>
+const MAX_QUEUED_EVENTS: usize = 16384;
+
+/// An inotify instance, which monitors the file system events of the watched files.
+pub struct InotifyFile {
+    watches: Mutex<Watches>,
+    queue: Mutex<VecDeque<InotifyEvent>>,
+    pollee: Pollee,
+    is_nonblocking: AtomicBool,
+    this: Weak<InotifyFile>,
+}
+
+struct Watches {
+    watches: BTreeMap<i32, (Arc<dyn Inode>, Arc<Watch>)>,
+    next_wd: i32,
+}
+
+impl InotifyFile {
+    /// Creates a new inotify instance.
+    pub fn new(is_nonblocking: bool) -> Arc<Self> {
+        Arc::new_cyclic(|weak_self| Self {
+            watches: Mutex::new(Watches {
+                watches: BTreeMap::new(),
+                next_wd: 1,
+            }),
+            queue: Mutex::new(VecDeque::new()),
+            pollee: Pollee::new(),
+            is_nonblocking: AtomicBool::new(is_nonblocking),
+            this: weak_self.clone(),
+        })
+    }
+
+    /// Adds a watch of `events` on the inode, or modifies the existing one.
+    ///
+    /// Returns the watch descriptor.
+    pub fn add_watch(
+        &self,
+        inode: &Arc<dyn Inode>,
+        events: FsEvents,
+        flags: WatchFlags,
+    ) -> Result<i32> {
+        if flags.contains(WatchFlags::IN_MASK_ADD | WatchFlags::IN_MASK_CREATE) {
+            return_errno_with_message!(
+                Errno::EINVAL,
+                "IN_MASK_ADD and IN_MASK_CREATE cannot be both specified"
+            );
+        }
+        let events = events & FsEvents::ALL_EVENTS;
+        if events.is_empty() {
+            return_errno_with_message!(Errno::EINVAL, "no events are specified");
+        }
+
+        let mut watches = self.watches.lock();
+
+        let existing = watches
+            .watches
+            .values()
+            .find(|(watched, _)| core::ptr::addr_eq(Arc::as_ptr(watched), Arc::as_ptr(inode)));
+        if let Some((_, watch)) = existing {
+            if flags.contains(WatchFlags::IN_MASK_CREATE) {
+                return_errno_with_message!(Errno::EEXIST, "the inode is already watched");
+            }
+            watch.update(events, flags);
+            return Ok(watch.wd);
+        }
+
+        let wd = watches.next_wd;
+        let watch = Arc::new(Watch {
+            wd,
+            events: AtomicU32::new(0),
+            is_oneshot: AtomicBool::new(false),
+            file: self.this.clone(),
+        });
+        watch.update(events, flags);
+        FsNotifyMarks::of_inode(inode)?.add(watch.clone());
+
+        watches.watches.insert(wd, (inode.clone(), watch));
+        watches.next_wd = wd.checked_add(1).unwrap_or(1);
+
+        Ok(wd)
+    }
+
+    /// Removes the watch of the watch descriptor.
+    pub fn remove_watch(&self, wd: i32) -> Result<()> {
+        let mut watches = self.watches.lock();
+        if !watches.watches.contains_key(&wd) {
+            return_errno_with_message!(Errno::EINVAL, "the watch does not exist");
+        }
+        self.remove_watch_locked(&mut watches, wd);
+        Ok(())
+    }
+
+    /// Removes a watch and queues an `IN_IGNORED` event for it.
+    fn remove_watch_locked(&self, watches: &mut Watches, wd: i32) {
+        let Some((inode, watch)) = watches.watches.remove(&wd) else {
+            return;
+        };
+        if let Some(marks) = FsNotifyMarks::get(&inode) {
+            marks.remove(&(watch as Arc<dyn FsNotifyMark>));
+        }
+
+        self.queue_event(InotifyEvent {
+            wd,
+            mask: FsEvents::IGNORED,
+            cookie: 0,
+            name: None,
+        });
+    }
+
+    fn handle_event(&self, watch: &Watch, event: &FsEvent) {
+        let events = event.events;
+        let interesting = events & watch.events();
+        // The watch is removed once the inode is deleted, whether or not the
+        // deletion is watched.
+        let is_deleted = events.contains(FsEvents::DELETE_SELF);
+        if interesting.is_empty() && !is_deleted {
+            return;
+        }
+
+        let mut watches = self.watches.lock();
+        // The watch may have been removed concurrently.
+        match watches.watches.get(&watch.wd) {
+            Some((_, current)) if core::ptr::eq(Arc::as_ptr(current), watch) => (),
+            _ => return,
+        }
+
+        if !interesting.is_empty() {
+            self.queue_event(InotifyEvent {
+                wd: watch.wd,
+                mask: interesting | (events & FsEvents::ISDIR),
+                cookie: event.cookie,
+                name: event.name.map(String::from),
+            });
+        }
+
+        if is_deleted || (!interesting.is_empty() && watch.is_oneshot()) {
+            self.remove_watch_locked(&mut watches, watch.wd);
+        }
+    }
+
+    fn queue_event(&self, event: InotifyEvent) {
+        let mut queue = self.queue.lock();
+
+        // Merges the event with the last one if they are the same.
+        if queue.back() == Some(&event) {
+            return;
+        }
+
+        if queue.len() < MAX_QUEUED_EVENTS {
+            queue.push_back(event);
+        } else if queue
+            .back()
+            .is_none_or(|last| last.mask != FsEvents::Q_OVERFLOW)
+        {
+            queue.push_back(InotifyEvent {
+                wd: -1,
+                mask: FsEvents::Q_OVERFLOW,
+                cookie: 0,
+                name: None,
+            });
+        }
+
+        self.pollee.notify(IoEvents::IN);
+    }
+
+    fn try_read(&self, writer: &mut VmWriter) -> Result<usize> {
+        let mut queue = self.queue.lock();
+        if queue.is_empty() {
+            return_errno_with_message!(Errno::EAGAIN, "no events are available");
+        }
+
+        let mut read_len = 0;
+        while let Some(event) = queue.front() {
+            let event_len = event.len();
+            if writer.avail() < event_len {
+                if read_len == 0 {
+                    return_errno_with_message!(Errno::EINVAL, "the buffer is too small");
+                }
+                break;
+            }
+
+            event.write_to(writer)?;
+            read_len += event_len;
+            queue.pop_front();
+        }
+
+        self.pollee.invalidate();
+        Ok(read_len)
+    }
+
+    fn check_io_events(&self) -> IoEvents {
+        if self.queue.lock().is_empty() {
+            IoEvents::empty()
+        } else {
+            IoEvents::IN
+        }
+    }
+}
+
+impl Drop for InotifyFile {
+    fn drop(&mut self) {
+        let watches = core::mem::take(&mut self.watches.get_mut().watches);
+        for (inode, watch) in watches.into_values() {
+            if let Some(marks) = FsNotifyMarks::get(&inode) {
+                marks.remove(&(watch as Arc<dyn FsNotifyMark>));
+            }
+        }
+    }
+}
+
+impl Pollable for InotifyFile {
+    fn poll(&self, mask: IoEvents, poller: Option<&mut PollHandle>) -> IoEvents {
+        self.pollee
+            .poll_with(mask, poller, || self.check_io_events())
+    }
+}
+
+impl FileLike for InotifyFile {
+    fn read(&self, writer: &mut VmWriter) -> Result<usize> {
+        if self.is_nonblocking.load(Ordering::Relaxed) {
+            self.try_read(writer)
+        } else {
+            self.wait_events(IoEvents::IN, None, || self.try_read(writer))
+        }
+    }
+
+    fn write(&self, _reader: &mut VmReader) -> Result<usize> {
+        return_errno_with_message!(Errno::EINVAL, "inotify files do not support write");
+    }
+
+    fn ioctl(&self, cmd: IoctlCmd, arg: usize) -> Result<i32> {
+        match cmd {
+            IoctlCmd::FIONREAD => {
+                let len: usize = self.queue.lock().iter().map(InotifyEvent::len).sum();
+                current_userspace!().write_val(arg, &(len as i32))?;
+                Ok(0)
+            }
+            _ => return_errno_with_message!(Errno::EINVAL, "the ioctl command is not supported"),
+        }
+    }
+
+    fn status_flags(&self) -> StatusFlags {
+        if self.is_nonblocking.load(Ordering::Relaxed) {
+            StatusFlags::O_NONBLOCK
+        } else {
+            StatusFlags::empty()
+        }
+    }
+
+    fn set_status_flags(&self, new_flags: StatusFlags) -> Result<()> {
+        self.is_nonblocking.store(
+            new_flags.contains(StatusFlags::O_NONBLOCK),
+            Ordering::Relaxed,
+        );
+        Ok(())
+    }
+
+    fn metadata(&self) -> Metadata {
+        // This is a dummy implementation.
+        // TODO: Add "anonymous inode fs" and link `InotifyFile` to it.
+        Metadata::new_file(
+            0,
+            InodeMode::from_bits_truncate(0o600),
+            aster_block::BLOCK_SIZE,
+        )
+    }
+}
+
+/// A watch of an inotify instance, which is attached to the watched inode as a mark.
+struct Watch {
+    wd: i32,
+    events: AtomicU32,
+    is_oneshot: AtomicBool,
+    file: Weak<InotifyFile>,
+}
+
+impl Watch {
+    fn events(&self) -> FsEvents {
+        FsEvents::from_bits_truncate(self.events.load(Ordering::Relaxed))
+    }
+
+    fn is_oneshot(&self) -> bool {
+        self.is_oneshot.load(Ordering::Relaxed)
+    }
+
+    fn update(&self, events: FsEvents, flags: WatchFlags) {
+        if flags.contains(WatchFlags::IN_MASK_ADD) {
+            self.events.fetch_or(events.bits(), Ordering::Relaxed);
+        } else {
+            self.events.store(events.bits(), Ordering::Relaxed);
+        }
+        self.is_oneshot
+            .store(flags.contains(WatchFlags::IN_ONESHOT), Ordering::Relaxed);
+    }
+}
+
+impl FsNotifyMark for Watch {
+    fn handle_event(&self, event: &FsEvent) {
+        if let Some(file) = self.file.upgrade() {
+            file.handle_event(self, event);
+        }
+    }
+}
+
+#[derive(PartialEq, Eq)]
+struct InotifyEvent {
+    wd: i32,
+    mask: FsEvents,
+    cookie: u32,
+    name: Option<String>,
+}
+
+impl InotifyEvent {
+    /// Returns the length of the padded name, which includes the terminating null byte.
+    fn name_len(&self) -> usize {
+        match &self.name {
+            Some(name) => (name.len() + 1).next_multiple_of(size_of::<CInotifyEvent>()),
+            None => 0,
+        }
+    }
+
+    /// Returns the length of the event when it is read.
+    fn len(&self) -> usize {
+        size_of::<CInotifyEvent>() + self.name_len()
+    }
+
+    fn write_to(&self, writer: &mut VmWriter) -> Result<()> {
+        let name_len = self.name_len();
+        let c_event = CInotifyEvent {
+            wd: self.wd,
+            mask: self.mask.bits(),
+            cookie: self.cookie,
+            len: name_len as u32,
+        };
+        writer.write_val(&c_event)?;
+
+        if let Some(name) = &self.name {
+            writer.write_fallible(&mut VmReader::from(name.as_bytes()))?;
+            writer.fill_zeros(name_len - name.len())?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The header of an event read from an inotify instance, which is
+/// `struct inotify_event` in Linux.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.15.7/source/include/uapi/linux/inotify.h#L21>
+#[derive(Debug, Clone, Copy, Pod)]
+#[repr(C)]
+struct CInotifyEvent {
+    wd: i32,
+    mask: u32,
+    cookie: u32,
+    len: u32,
+}
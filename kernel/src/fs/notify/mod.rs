@@ -0,0 +1,360 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! File system notifications.
+//!
+//! The VFS reports the changes of files (e.g., creation, modification and
+//! deletion) through the functions in this module. An event on an inode is
+//! delivered to the marks attached to the inode, and an event on a directory
+//! entry is also delivered to the marks attached to its parent directory with
+//! the name of the entry. An event on an opened file is also delivered to the
+//! marks attached to the mount of the file.
+//!
+//! Some accesses to the opened files (e.g., opening and reading) also produce
+//! permission events, which are delivered before the accesses happen, so that
+//! the marks can deny the accesses.
+//!
+//! Marks are attached to inodes through the inode [`Extension`], so the inode
+//! marks are only available in the file systems that support it.
+//!
+//! [`Extension`]: crate::fs::utils::Extension
+
+use core::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+pub use self::{
+    fanotify::{FanotifyFile, FanotifyInitFlags, FanotifyMarkFlags, FanotifyTarget},
+    inotify::{InotifyFile, WatchFlags},
+};
+use crate::{
+    fs::{
+        path::Path,
+        utils::{Inode, InodeType},
+    },
+    prelude::*,
+};
+
+mod fanotify;
+mod inotify;
+
+bitflags! {
+    /// The file system events.
+    ///
+    /// The values are the same as the `FS_*` constants in Linux, which are
+    /// shared by the `IN_*` constants of inotify and the `FAN_*` constants of
+    /// fanotify.
+    pub struct FsEvents: u32 {
+        /// The file was accessed.
+        const ACCESS        = 0x00000001;
+        /// The file was modified.
+        const MODIFY        = 0x00000002;
+        /// The metadata of the file was changed.
+        const ATTRIB        = 0x00000004;
+        /// A writable file was closed.
+        const CLOSE_WRITE   = 0x00000008;
+        /// An unwritable file was closed.
+        const CLOSE_NOWRITE = 0x00000010;
+        /// The file was opened.
+        const OPEN          = 0x00000020;
+        /// A file was moved from the directory.
+        const MOVED_FROM    = 0x00000040;
+        /// A file was moved to the directory.
+        const MOVED_TO      = 0x00000080;
+        /// A file was created in the directory.
+        const CREATE        = 0x00000100;
+        /// A file was deleted from the directory.
+        const DELETE        = 0x00000200;
+        /// The file itself was deleted.
+        const DELETE_SELF   = 0x00000400;
+        /// The file itself was moved.
+        const MOVE_SELF     = 0x00000800;
+        /// The file system containing the file was unmounted.
+        const UNMOUNT       = 0x00002000;
+        /// The event queue overflowed.
+        const Q_OVERFLOW    = 0x00004000;
+        /// The mark was removed.
+        const IGNORED       = 0x00008000;
+        /// The file is being opened, which needs to be permitted.
+        const OPEN_PERM     = 0x00010000;
+        /// The file is being accessed, which needs to be permitted.
+        const ACCESS_PERM   = 0x00020000;
+        /// The events on the children of the marked directory are watched,
+        /// which is only used in the masks of the marks.
+        const EVENT_ON_CHILD = 0x08000000;
+        /// The subject of the event is a directory.
+        const ISDIR         = 0x40000000;
+
+        const CLOSE = Self::CLOSE_WRITE.bits | Self::CLOSE_NOWRITE.bits;
+        const MOVE = Self::MOVED_FROM.bits | Self::MOVED_TO.bits;
+        /// All the events that can be watched by inotify.
+        const ALL_EVENTS = 0x00000fff;
+        /// All the permission events.
+        const PERM_EVENTS = Self::OPEN_PERM.bits | Self::ACCESS_PERM.bits;
+    }
+}
+
+/// A file system event.
+pub struct FsEvent<'a> {
+    /// The events that happened.
+    pub events: FsEvents,
+    /// The cookie that associates the two events of the same move, or zero.
+    pub cookie: u32,
+    /// The name of the entry, if the event happens on an entry of the marked directory.
+    pub name: Option<&'a str>,
+    /// The path of the file, if the event happens on an opened file.
+    pub path: Option<&'a Path>,
+}
+
+/// A mark attached to an inode or a mount, which receives the events on it.
+pub trait FsNotifyMark: Send + Sync {
+    /// Handles an event on the marked object.
+    fn handle_event(&self, event: &FsEvent);
+
+    /// Handles a permission event on the marked object.
+    ///
+    /// This method may block until the access is granted or denied. If the
+    /// access is denied, an error is returned.
+    fn handle_perm_event(&self, event: &FsEvent) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// The marks attached to an inode or a mount.
+#[derive(Default)]
+pub struct FsNotifyMarks {
+    marks: RwLock<Vec<Arc<dyn FsNotifyMark>>>,
+}
+
+impl FsNotifyMarks {
+    /// Returns the marks attached to the inode.
+    ///
+    /// Returns an error if the file system does not support notifications.
+    pub fn of_inode(inode: &Arc<dyn Inode>) -> Result<Arc<FsNotifyMarks>> {
+        let Some(extension) = inode.extension() else {
+            return_errno_with_message!(
+                Errno::EOPNOTSUPP,
+                "the file system does not support notifications"
+            );
+        };
+        Ok(extension.get_or_put_default::<FsNotifyMarks>())
+    }
+
+    /// Attaches a mark.
+    pub fn add(&self, mark: Arc<dyn FsNotifyMark>) {
+        self.marks.write().push(mark);
+    }
+
+    /// Detaches a mark.
+    pub fn remove(&self, mark: &Arc<dyn FsNotifyMark>) {
+        self.marks
+            .write()
+            .retain(|m| !core::ptr::addr_eq(Arc::as_ptr(m), Arc::as_ptr(mark)));
+    }
+
+    fn get(inode: &Arc<dyn Inode>) -> Option<Arc<FsNotifyMarks>> {
+        inode.extension()?.get::<FsNotifyMarks>()
+    }
+
+    fn send(&self, event: &FsEvent) {
+        // Deliver the events without holding the lock, since the marks may be
+        // detached when handling the events.
+        let marks = self.marks.read().clone();
+        for mark in marks {
+            mark.handle_event(event);
+        }
+    }
+
+    fn send_perm(&self, event: &FsEvent) -> Result<()> {
+        let marks = self.marks.read().clone();
+        for mark in marks {
+            mark.handle_perm_event(event)?;
+        }
+        Ok(())
+    }
+}
+
+/// Notifies the marks of `inode` of `events`.
+pub fn notify_inode(inode: &Arc<dyn Inode>, events: FsEvents) {
+    if let Some(marks) = FsNotifyMarks::get(inode) {
+        marks.send(&FsEvent {
+            events,
+            cookie: 0,
+            name: None,
+            path: None,
+        });
+    }
+}
+
+/// Notifies the marks of the directory `dir` of `events` on its entry `name`.
+pub fn notify_entry(dir: &Arc<dyn Inode>, name: &str, events: FsEvents, cookie: u32) {
+    if let Some(marks) = FsNotifyMarks::get(dir) {
+        marks.send(&FsEvent {
+            events,
+            cookie,
+            name: Some(name),
+            path: None,
+        });
+    }
+}
+
+/// Notifies the marks of an opened file of `events`.
+///
+/// The events are delivered to the marks of the file, those of its parent
+/// directory and those of the mount. The name of the file is only computed if
+/// the parent directory has marks.
+pub fn notify_file(
+    path: &Path,
+    parent: Option<&Arc<dyn Inode>>,
+    name: impl FnOnce() -> String,
+    events: FsEvents,
+) {
+    let mut event = FsEvent {
+        events: events | type_flag(path.inode()),
+        cookie: 0,
+        name: None,
+        path: Some(path),
+    };
+
+    path.mount_node().notify_marks().send(&event);
+    if let Some(marks) = FsNotifyMarks::get(path.inode()) {
+        marks.send(&event);
+    }
+    if let Some(marks) = parent.and_then(FsNotifyMarks::get) {
+        let name = name();
+        event.name = Some(&name);
+        marks.send(&event);
+    }
+}
+
+/// Notifies the marks of an opened file of the permission `events`.
+///
+/// Returns an error if any of the marks denies the access.
+pub fn notify_file_perm(
+    path: &Path,
+    parent: Option<&Arc<dyn Inode>>,
+    name: impl FnOnce() -> String,
+    events: FsEvents,
+) -> Result<()> {
+    let mut event = FsEvent {
+        events: events | type_flag(path.inode()),
+        cookie: 0,
+        name: None,
+        path: Some(path),
+    };
+
+    path.mount_node().notify_marks().send_perm(&event)?;
+    if let Some(marks) = FsNotifyMarks::get(path.inode()) {
+        marks.send_perm(&event)?;
+    }
+    if let Some(marks) = parent.and_then(FsNotifyMarks::get) {
+        let name = name();
+        event.name = Some(&name);
+        marks.send_perm(&event)?;
+    }
+    Ok(())
+}
+
+/// The close events of the released files, which are not delivered yet.
+///
+/// A file may be released with spin locks held (e.g., when a file table is dropped), where the
+/// marks cannot be notified since they may sleep. So the close events are deferred until
+/// [`flush_close_events`] is called in the close path, like the delayed `fput` in Linux.
+static PENDING_CLOSE_EVENTS: SpinLock<VecDeque<(Path, FsEvents)>> = SpinLock::new(VecDeque::new());
+
+/// Whether [`PENDING_CLOSE_EVENTS`] may be non-empty, which avoids taking the lock on every
+/// system call.
+static HAS_PENDING_CLOSE_EVENTS: AtomicBool = AtomicBool::new(false);
+
+/// Defers the close `events` of the released file at `path`.
+pub fn defer_close_events(path: Path, events: FsEvents) {
+    let mut pending_events = PENDING_CLOSE_EVENTS.lock();
+    pending_events.push_back((path, events));
+    HAS_PENDING_CLOSE_EVENTS.store(true, Ordering::Relaxed);
+}
+
+/// Delivers the deferred close events.
+///
+/// This method must be called in a context where the marks can sleep.
+pub fn flush_close_events() {
+    if !HAS_PENDING_CLOSE_EVENTS.load(Ordering::Relaxed) {
+        return;
+    }
+
+    loop {
+        let pending_event = {
+            let mut pending_events = PENDING_CLOSE_EVENTS.lock();
+            let pending_event = pending_events.pop_front();
+            if pending_event.is_none() {
+                HAS_PENDING_CLOSE_EVENTS.store(false, Ordering::Relaxed);
+            }
+            pending_event
+        };
+        let Some((path, events)) = pending_event else {
+            return;
+        };
+        path.notify(events);
+    }
+}
+
+/// Notifies the marks of the file system events of creating `name` in `dir`.
+pub fn notify_create(dir: &Arc<dyn Inode>, name: &str, child: &Arc<dyn Inode>) {
+    notify_entry(dir, name, FsEvents::CREATE | type_flag(child), 0);
+}
+
+/// Notifies the marks of the file system events of deleting `name`, which is
+/// `child`, from `dir`.
+pub fn notify_delete(dir: &Arc<dyn Inode>, name: &str, child: &Arc<dyn Inode>) {
+    notify_entry(dir, name, FsEvents::DELETE | type_flag(child), 0);
+    notify_delete_self(child);
+}
+
+/// Notifies the marks of the file system events of moving `old_name`, which is
+/// `child`, in `old_dir` to `new_name` in `new_dir`.
+///
+/// `target` is the inode that was at `new_name` and is replaced by the move, if any.
+pub fn notify_move(
+    old_dir: &Arc<dyn Inode>,
+    old_name: &str,
+    new_dir: &Arc<dyn Inode>,
+    new_name: &str,
+    child: &Arc<dyn Inode>,
+    target: Option<&Arc<dyn Inode>>,
+) {
+    let type_flag = type_flag(child);
+    // The cookie associates the two events of the same move.
+    let cookie = next_cookie();
+    notify_entry(old_dir, old_name, FsEvents::MOVED_FROM | type_flag, cookie);
+    notify_entry(new_dir, new_name, FsEvents::MOVED_TO | type_flag, cookie);
+    notify_inode(child, FsEvents::MOVE_SELF | type_flag);
+
+    if let Some(target) = target {
+        notify_delete_self(target);
+    }
+}
+
+/// Notifies the marks of `inode` that it is deleted, if it is a directory or
+/// it has no links left.
+fn notify_delete_self(inode: &Arc<dyn Inode>) {
+    let type_flag = type_flag(inode);
+    if type_flag.contains(FsEvents::ISDIR) || inode.metadata().nlinks == 0 {
+        notify_inode(inode, FsEvents::DELETE_SELF | type_flag);
+    }
+}
+
+fn type_flag(inode: &Arc<dyn Inode>) -> FsEvents {
+    if inode.type_() == InodeType::Dir {
+        FsEvents::ISDIR
+    } else {
+        FsEvents::empty()
+    }
+}
+
+fn next_cookie() -> u32 {
+    static NEXT_COOKIE: AtomicU32 = AtomicU32::new(1);
+
+    loop {
+        let cookie = NEXT_COOKIE.fetch_add(1, Ordering::Relaxed);
+        // Zero means that the event is not a part of a move.
+        if cookie != 0 {
+            return cookie;
+        }
+    }
+}
@@ -188,6 +188,10 @@ impl FileSystem for OverlayFS {
     fn flags(&self) -> FsFlags {
         FsFlags::empty()
     }
+
+    fn name(&self) -> &'static str {
+        "overlay"
+    }
 }
 
 impl OverlayFS {
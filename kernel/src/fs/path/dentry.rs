@@ -2,32 +2,56 @@
 
 #![expect(dead_code)]
 
+use alloc::collections::btree_map::BTreeMap;
 use core::{
-    sync::atomic::{AtomicU32, Ordering},
+    sync::atomic::{AtomicU32, AtomicUsize, Ordering},
     time::Duration,
 };
 
 use hashbrown::HashMap;
 use inherit_methods_macro::inherit_methods;
 use ostd::sync::RwMutexWriteGuard;
+use spin::Once;
 
 use super::is_dot_or_dotdot;
 use crate::{
-    fs::utils::{
-        FileSystem, Inode, InodeMode, InodeType, Metadata, MknodType, XattrName, XattrNamespace,
-        XattrSetFlags,
+    fs::{
+        notify,
+        utils::{
+            FileSystem, Inode, InodeMode, InodeType, Metadata, MknodType, XattrName,
+            XattrNamespace, XattrSetFlags,
+        },
     },
     prelude::*,
     process::{Gid, Uid},
+    vm::reclaim::{self, Shrinker},
 };
 
+/// The directory dentries, keyed by their addresses, whose children are dropped
+/// by [`DentryShrinker`] under memory pressure.
+static DIR_DENTRIES: Mutex<BTreeMap<usize, Weak<Dentry>>> = Mutex::new(BTreeMap::new());
+
+/// The number of the child dentries in the cache, including the negative ones.
+static NR_CHILD_DENTRIES: AtomicUsize = AtomicUsize::new(0);
+
+static DENTRY_SHRINKER: Once<Arc<DentryShrinker>> = Once::new();
+
+pub(super) fn init() {
+    let shrinker = DENTRY_SHRINKER.call_once(|| Arc::new(DentryShrinker));
+    reclaim::register_shrinker(Arc::downgrade(shrinker) as Weak<dyn Shrinker>);
+}
+
 /// A `Dentry` represents a cached filesystem node in the VFS tree.
 pub(super) struct Dentry {
     inode: Arc<dyn Inode>,
     type_: InodeType,
     name_and_parent: RwLock<Option<(String, Arc<Dentry>)>>,
     children: RwMutex<DentryChildren>,
-    flags: AtomicU32,
+    /// The number of mounts that are mounted on the dentry.
+    ///
+    /// A dentry can be the mountpoint of several mounts at the same time, since the same dentry
+    /// is visible in all the mounts that are bound from the same file system.
+    mount_count: AtomicU32,
     this: Weak<Dentry>,
 }
 
@@ -41,7 +65,7 @@ impl Dentry {
     }
 
     fn new(inode: Arc<dyn Inode>, options: DentryOptions) -> Arc<Self> {
-        Arc::new_cyclic(|weak_self| Self {
+        let dentry = Arc::new_cyclic(|weak_self| Self {
             type_: inode.type_(),
             inode,
             name_and_parent: match options {
@@ -49,9 +73,16 @@ impl Dentry {
                 _ => RwLock::new(None),
             },
             children: RwMutex::new(DentryChildren::new()),
-            flags: AtomicU32::new(DentryFlags::empty().bits()),
+            mount_count: AtomicU32::new(0),
             this: weak_self.clone(),
-        })
+        });
+
+        if dentry.type_ == InodeType::Dir {
+            DIR_DENTRIES
+                .lock()
+                .insert(Arc::as_ptr(&dentry) as usize, Arc::downgrade(&dentry));
+        }
+        dentry
     }
 
     /// Gets the type of the `Dentry`.
@@ -98,11 +129,6 @@ impl Dentry {
         &self.inode
     }
 
-    fn flags(&self) -> DentryFlags {
-        let flags = self.flags.load(Ordering::Relaxed);
-        DentryFlags::from_bits(flags).unwrap()
-    }
-
     /// Checks if this dentry is a descendant (child, grandchild, or
     /// great-grandchild, etc.) of another dentry.
     pub fn is_descendant_of(&self, ancestor: &Arc<Self>) -> bool {
@@ -117,17 +143,18 @@ impl Dentry {
     }
 
     pub fn is_mountpoint(&self) -> bool {
-        self.flags().contains(DentryFlags::MOUNTED)
+        self.mount_count.load(Ordering::Acquire) > 0
     }
 
-    pub fn set_mounted_bit(&self) {
-        self.flags
-            .fetch_or(DentryFlags::MOUNTED.bits(), Ordering::Release);
+    /// Records that a mount is mounted on the dentry.
+    pub fn inc_mount_count(&self) {
+        self.mount_count.fetch_add(1, Ordering::Release);
     }
 
-    pub fn clear_mounted_bit(&self) {
-        self.flags
-            .fetch_and(!(DentryFlags::MOUNTED.bits()), Ordering::Release);
+    /// Records that a mount is no longer mounted on the dentry.
+    pub fn dec_mount_count(&self) {
+        let old_count = self.mount_count.fetch_sub(1, Ordering::Release);
+        debug_assert!(old_count > 0);
     }
 
     /// Currently, the root `Dentry` of a fs is the root of a mount.
@@ -147,6 +174,7 @@ impl Dentry {
         }
 
         let new_inode = self.inode.create(name, type_, mode)?;
+        notify::notify_create(&self.inode, name, &new_inode);
         let name = String::from(name);
         let new_child = Dentry::new(new_inode, DentryOptions::Leaf((name.clone(), self.this())));
 
@@ -198,6 +226,7 @@ impl Dentry {
         }
 
         let inode = self.inode.mknod(name, mode, type_)?;
+        notify::notify_create(&self.inode, name, &inode);
         let name = String::from(name);
         let new_child = Dentry::new(inode, DentryOptions::Leaf((name.clone(), self.this())));
 
@@ -221,6 +250,7 @@ impl Dentry {
 
         let old_inode = old.inode();
         self.inode.link(old_inode, name)?;
+        notify::notify_create(&self.inode, name, old_inode);
         let name = String::from(name);
         let dentry = Dentry::new(
             old_inode.clone(),
@@ -240,9 +270,11 @@ impl Dentry {
         }
 
         let children = self.children.upread();
-        children.check_mountpoint(name)?;
+        let child = children.check_mountpoint_then_find(name)?;
+        let child_inode = self.child_inode(child.as_ref(), name)?;
 
         self.inode.unlink(name)?;
+        notify::notify_delete(&self.inode, name, &child_inode);
 
         let mut children = children.upgrade();
         children.delete(name);
@@ -256,9 +288,11 @@ impl Dentry {
         }
 
         let children = self.children.upread();
-        children.check_mountpoint(name)?;
+        let child = children.check_mountpoint_then_find(name)?;
+        let child_inode = self.child_inode(child.as_ref(), name)?;
 
         self.inode.rmdir(name)?;
+        notify::notify_delete(&self.inode, name, &child_inode);
 
         let mut children = children.upgrade();
         children.delete(name);
@@ -283,8 +317,18 @@ impl Dentry {
             let children = self.children.upread();
             let old_dentry = children.check_mountpoint_then_find(old_name)?;
             children.check_mountpoint(new_name)?;
+            let old_inode = self.child_inode(old_dentry.as_ref(), old_name)?;
+            let target_inode = self.inode.lookup(new_name).ok();
 
             self.inode.rename(old_name, &self.inode, new_name)?;
+            notify::notify_move(
+                &self.inode,
+                old_name,
+                &self.inode,
+                new_name,
+                &old_inode,
+                replaced_inode(&old_inode, target_inode.as_ref()),
+            );
 
             let mut children = children.upgrade();
             match old_dentry.as_ref() {
@@ -305,8 +349,18 @@ impl Dentry {
                 write_lock_children_on_two_dentries(self, new_dir);
             let old_dentry = self_children.check_mountpoint_then_find(old_name)?;
             new_dir_children.check_mountpoint(new_name)?;
+            let old_inode = self.child_inode(old_dentry.as_ref(), old_name)?;
+            let target_inode = new_dir.inode.lookup(new_name).ok();
 
             self.inode.rename(old_name, &new_dir.inode, new_name)?;
+            notify::notify_move(
+                &self.inode,
+                old_name,
+                &new_dir.inode,
+                new_name,
+                &old_inode,
+                replaced_inode(&old_inode, target_inode.as_ref()),
+            );
             match old_dentry.as_ref() {
                 Some(dentry) => {
                     self_children.delete(old_name);
@@ -322,6 +376,27 @@ impl Dentry {
         }
         Ok(())
     }
+
+    /// Returns the inode of the child `name`, whose dentry is `child` if it is cached.
+    ///
+    /// The inode is looked up from the file system if the dentry is not cached, so that
+    /// the file system events on the inode can be reported.
+    fn child_inode(&self, child: Option<&Arc<Dentry>>, name: &str) -> Result<Arc<dyn Inode>> {
+        match child {
+            Some(child) => Ok(child.inode().clone()),
+            None => self.inode.lookup(name),
+        }
+    }
+}
+
+/// Returns `target`, the inode at the new name of a move, if it is replaced by `old`.
+///
+/// Moving a file to one of its other links does nothing.
+fn replaced_inode<'a>(
+    old: &Arc<dyn Inode>,
+    target: Option<&'a Arc<dyn Inode>>,
+) -> Option<&'a Arc<dyn Inode>> {
+    target.filter(|target| target.ino() != old.ino())
 }
 
 #[inherit_methods(from = "self.inode")]
@@ -360,11 +435,19 @@ impl Dentry {
     pub fn remove_xattr(&self, name: XattrName) -> Result<()>;
 }
 
+impl Drop for Dentry {
+    fn drop(&mut self) {
+        if self.type_ == InodeType::Dir {
+            DIR_DENTRIES.lock().remove(&(self as *const Self as usize));
+        }
+    }
+}
+
 impl Debug for Dentry {
     fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
         f.debug_struct("Dentry")
             .field("inode", &self.inode)
-            .field("flags", &self.flags())
+            .field("mount_count", &self.mount_count.load(Ordering::Relaxed))
             .finish()
     }
 }
@@ -393,12 +476,6 @@ impl DentryKey {
     }
 }
 
-bitflags! {
-    struct DentryFlags: u32 {
-        const MOUNTED = 1 << 0;
-    }
-}
-
 enum DentryOptions {
     Root,
     Leaf((String, Arc<Dentry>)),
@@ -407,9 +484,8 @@ enum DentryOptions {
 /// Manages child dentries, including both valid and negative entries.
 ///
 /// A _negative_ dentry reflects a failed filename lookup, saving potential
-/// repeated and costly lookups in the future.
-// TODO: Address the issue of negative dentry bloating. See the reference
-// https://lwn.net/Articles/894098/ for more details.
+/// repeated and costly lookups in the future. The negative dentries are dropped
+/// under memory pressure, along with the unused valid ones, by [`DentryShrinker`].
 struct DentryChildren {
     dentries: HashMap<String, Option<Arc<Dentry>>>,
 }
@@ -446,12 +522,16 @@ impl DentryChildren {
         // Assume the caller has checked that the dentry is cacheable
         // and will be newly created if looked up from the parent.
         debug_assert!(dentry.is_dentry_cacheable());
-        let _ = self.dentries.insert(name, Some(dentry));
+        if self.dentries.insert(name, Some(dentry)).is_none() {
+            NR_CHILD_DENTRIES.fetch_add(1, Ordering::Relaxed);
+        }
     }
 
     /// Inserts a negative dentry.
     pub fn insert_negative(&mut self, name: String) {
-        let _ = self.dentries.insert(name, None);
+        if self.dentries.insert(name, None).is_none() {
+            NR_CHILD_DENTRIES.fetch_add(1, Ordering::Relaxed);
+        }
     }
 
     /// Deletes a dentry by name, turning it into a negative entry if exists.
@@ -482,6 +562,71 @@ impl DentryChildren {
             None => Ok(None),
         }
     }
+
+    /// Drops the negative dentries and the unused valid dentries among at most
+    /// `nr_to_scan` ones.
+    ///
+    /// A valid dentry is unused if it is only referenced by `self` and is not a
+    /// mountpoint. Note that a dentry with cached children is referenced by them,
+    /// so the dentries are dropped from the leaves upward.
+    ///
+    /// Returns the number of the dentries scanned and that of those dropped.
+    pub fn shrink(&mut self, nr_to_scan: usize) -> (usize, usize) {
+        let old_len = self.dentries.len();
+        let mut nr_scanned = 0;
+        self.dentries.retain(|_, child| {
+            if nr_scanned == nr_to_scan {
+                return true;
+            }
+            nr_scanned += 1;
+            child
+                .as_ref()
+                .is_some_and(|child| Arc::strong_count(child) > 1 || child.is_mountpoint())
+        });
+
+        let nr_dropped = old_len - self.dentries.len();
+        NR_CHILD_DENTRIES.fetch_sub(nr_dropped, Ordering::Relaxed);
+        (nr_scanned, nr_dropped)
+    }
+}
+
+impl Drop for DentryChildren {
+    fn drop(&mut self) {
+        NR_CHILD_DENTRIES.fetch_sub(self.dentries.len(), Ordering::Relaxed);
+    }
+}
+
+/// The shrinker that drops the negative dentries and the unused dentries.
+struct DentryShrinker;
+
+impl Shrinker for DentryShrinker {
+    fn count_objects(&self) -> usize {
+        NR_CHILD_DENTRIES.load(Ordering::Relaxed)
+    }
+
+    fn scan_objects(&self, nr_to_scan: usize) -> usize {
+        let dirs: Vec<Weak<Dentry>> = DIR_DENTRIES.lock().values().cloned().collect();
+
+        let mut nr_left = nr_to_scan;
+        let mut nr_freed = 0;
+        for dir in dirs {
+            if nr_left == 0 {
+                break;
+            }
+            // The directories are upgraded one at a time, so that holding one does not
+            // prevent it from being dropped from its parent.
+            let Some(dir) = dir.upgrade() else {
+                continue;
+            };
+            let Some(mut children) = dir.children.try_write() else {
+                continue;
+            };
+            let (nr_scanned, nr_dropped) = children.shrink(nr_left);
+            nr_left -= nr_scanned;
+            nr_freed += nr_dropped;
+        }
+        nr_freed
+    }
 }
 
 fn write_lock_children_on_two_dentries<'a>(
@@ -5,11 +5,16 @@
 use core::time::Duration;
 
 use inherit_methods_macro::inherit_methods;
-pub use mount::MountNode;
+pub use mount::{MountNode, PropagationType};
+pub use mount_namespace::MountNamespace;
 
 use crate::{
     fs::{
-        path::dentry::{Dentry, DentryKey},
+        notify::{self, FsEvents},
+        path::{
+            dentry::{Dentry, DentryKey},
+            mount::CopyFlags,
+        },
         utils::{
             FileSystem, Inode, InodeMode, InodeType, Metadata, MknodType, Permission, XattrName,
             XattrNamespace, XattrSetFlags, NAME_MAX,
@@ -21,6 +26,11 @@ use crate::{
 
 mod dentry;
 mod mount;
+mod mount_namespace;
+
+pub(super) fn init() {
+    dentry::init();
+}
 
 /// A `Path` is used to represent an exact location in the VFS tree.
 ///
@@ -173,7 +183,6 @@ impl Path {
     /// sets it as the mountpoint of the child mount.
     pub(super) fn set_mountpoint(&self, child_mount: Arc<MountNode>) {
         child_mount.set_mountpoint(&self.dentry);
-        self.dentry.set_mounted_bit();
     }
 
     /// Mounts the fs on current `Dentry` as a mountpoint.
@@ -182,6 +191,9 @@ impl Path {
     /// its mounted child mount will be updated.
     /// The root Dentry cannot be mounted.
     ///
+    /// The new mount is propagated to the mounts that receive mount events from
+    /// the mount of current `Dentry`.
+    ///
     /// Returns the mounted child mount.
     pub fn mount(&self, fs: Arc<dyn FileSystem>) -> Result<Arc<MountNode>> {
         if self.type_() != InodeType::Dir {
@@ -193,6 +205,7 @@ impl Path {
 
         let child_mount = self.mount_node.mount(fs, &self.this())?;
         self.set_mountpoint(child_mount.clone());
+        child_mount.propagate_attach();
         Ok(child_mount)
     }
 
@@ -212,7 +225,6 @@ impl Path {
         let parent_mount_path = Self::new(mountpoint_mount_node.clone(), mountpoint.clone());
 
         let child_mount = mountpoint_mount_node.unmount(&parent_mount_path)?;
-        mountpoint.clear_mounted_bit();
         Ok(child_mount)
     }
 
@@ -255,14 +267,44 @@ impl Path {
     /// If `recursive` is true, it will bind mount the whole mount tree
     /// to the destination `Path`. Otherwise, it will only bind mount
     /// the root mount node.
+    ///
+    /// Returns an error if the mount of the `Path` is unbindable.
     pub fn bind_mount_to(&self, dst_path: &Self, recursive: bool) -> Result<()> {
-        let new_mount = self
-            .mount_node
-            .clone_mount_node_tree(&self.dentry, recursive);
+        if self.mount_node.is_unbindable() {
+            return_errno_with_message!(Errno::EINVAL, "the mount is unbindable");
+        }
+
+        let new_mount =
+            self.mount_node
+                .clone_mount_node_tree(&self.dentry, recursive, CopyFlags::empty());
         new_mount.graft_mount_node_tree(dst_path)?;
         Ok(())
     }
 
+    /// Notifies the marks of the `Path` of `events`.
+    pub fn notify(&self, events: FsEvents) {
+        let parent = self.dentry.parent();
+        notify::notify_file(
+            self,
+            parent.as_ref().map(|parent| parent.inode()),
+            || self.dentry.name(),
+            events,
+        );
+    }
+
+    /// Notifies the marks of the `Path` of the permission `events`.
+    ///
+    /// Returns an error if the access is denied.
+    pub fn notify_perm(&self, events: FsEvents) -> Result<()> {
+        let parent = self.dentry.parent();
+        notify::notify_file_perm(
+            self,
+            parent.as_ref().map(|parent| parent.inode()),
+            || self.dentry.name(),
+            events,
+        )
+    }
+
     fn this(&self) -> Self {
         self.clone()
     }
@@ -271,8 +313,24 @@ impl Path {
     pub fn mount_node(&self) -> &Arc<MountNode> {
         &self.mount_node
     }
+
+    /// Returns whether current `Path` is the root of its mount node.
+    ///
+    /// Unlike [`Self::is_mount_root`], this also holds for the root of a bind mount
+    /// whose root `Dentry` is not the root of the fs.
+    pub fn is_root_of_mount(&self) -> bool {
+        Arc::ptr_eq(&self.dentry, self.mount_node.root_dentry())
+    }
 }
 
+impl PartialEq for Path {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.mount_node, &other.mount_node) && Arc::ptr_eq(&self.dentry, &other.dentry)
+    }
+}
+
+impl Eq for Path {}
+
 #[inherit_methods(from = "self.dentry")]
 impl Path {
     pub fn fs(&self) -> Arc<dyn FileSystem>;
@@ -1,9 +1,12 @@
 // SPDX-License-Identifier: MPL-2.0
 
+use core::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+
 use hashbrown::HashMap;
 
 use crate::{
     fs::{
+        notify::FsNotifyMarks,
         path::{
             dentry::{Dentry, DentryKey},
             Path,
@@ -15,6 +18,8 @@ use crate::{
 
 /// The `MountNode` is used to form a mount tree to maintain the mount information.
 pub struct MountNode {
+    /// The unique ID of the mount.
+    id: usize,
     /// Root dentry.
     root_dentry: Arc<Dentry>,
     /// Mountpoint dentry. A mount node can be mounted on one dentry of another mount node,
@@ -26,10 +31,16 @@ pub struct MountNode {
     parent: RwLock<Option<Weak<MountNode>>>,
     /// Child mount nodes which are mounted on one dentry of self.
     children: RwLock<HashMap<DentryKey, Arc<Self>>>,
+    /// The marks that receive the file system events in the mount.
+    notify_marks: FsNotifyMarks,
+    /// The propagation properties, which decide how mount events are shared with other mounts.
+    propagation: RwLock<Propagation>,
     /// Reference to self.
     this: Weak<Self>,
 }
 
+static NEXT_MOUNT_ID: AtomicUsize = AtomicUsize::new(1);
+
 impl MountNode {
     /// Creates a root mount node with an associated FS.
     ///
@@ -52,10 +63,13 @@ impl MountNode {
     /// mount nodes must be explicitly assigned a mountpoint to maintain structural integrity.
     fn new(fs: Arc<dyn FileSystem>, parent_mount: Option<Weak<MountNode>>) -> Arc<Self> {
         Arc::new_cyclic(|weak_self| Self {
+            id: NEXT_MOUNT_ID.fetch_add(1, Ordering::Relaxed),
             root_dentry: Dentry::new_root(fs.root_inode()),
             mountpoint: RwLock::new(None),
             parent: RwLock::new(parent_mount),
             children: RwLock::new(HashMap::new()),
+            notify_marks: FsNotifyMarks::default(),
+            propagation: RwLock::new(Propagation::default()),
             fs,
             this: weak_self.clone(),
         })
@@ -88,6 +102,8 @@ impl MountNode {
 
     /// Unmounts a child mount node from the mountpoint and returns it.
     ///
+    /// The unmount is propagated to the mounts that receive mount events from this mount node.
+    ///
     /// The mountpoint should belong to this mount node, or an error is returned.
     pub fn unmount(&self, mountpoint: &Path) -> Result<Arc<Self>> {
         if !Arc::ptr_eq(mountpoint.mount_node(), &self.this()) {
@@ -95,10 +111,10 @@ impl MountNode {
         }
 
         let child_mount = self
-            .children
-            .write()
-            .remove(&mountpoint.key())
+            .get(mountpoint)
             .ok_or_else(|| Error::with_message(Errno::ENOENT, "can not find child mount"))?;
+        child_mount.detach_as_root();
+        propagate_umount(&self.this(), &mountpoint.dentry, &mut Vec::new());
         Ok(child_mount)
     }
 
@@ -106,15 +122,56 @@ impl MountNode {
     ///
     /// The new mount node will have the same fs as the original one and
     /// have no parent and children. We should set the parent and children manually.
-    fn clone_mount_node(&self, root_dentry: &Arc<Dentry>) -> Arc<Self> {
-        Arc::new_cyclic(|weak_self| Self {
+    ///
+    /// The propagation properties of the new mount node are decided by `flags`.
+    fn clone_mount_node(&self, root_dentry: &Arc<Dentry>, flags: CopyFlags) -> Arc<Self> {
+        let new_mount = Arc::new_cyclic(|weak_self| Self {
+            id: NEXT_MOUNT_ID.fetch_add(1, Ordering::Relaxed),
             root_dentry: root_dentry.clone(),
             mountpoint: RwLock::new(None),
             parent: RwLock::new(None),
             children: RwLock::new(HashMap::new()),
+            notify_marks: FsNotifyMarks::default(),
+            propagation: RwLock::new(Propagation::default()),
             fs: self.fs.clone(),
             this: weak_self.clone(),
-        })
+        });
+        new_mount.inherit_propagation(self, flags);
+        new_mount
+    }
+
+    /// Sets up the propagation properties of a new copy of `old`.
+    ///
+    /// Reference: <https://elixir.bootlin.com/linux/v6.15/source/fs/namespace.c#L1317>
+    fn inherit_propagation(&self, old: &Self, flags: CopyFlags) {
+        let old_propagation = old.propagation.read();
+        let mut propagation = self.propagation.write();
+
+        if flags.contains(CopyFlags::SLAVE)
+            || (flags.contains(CopyFlags::SHARED_TO_SLAVE) && old_propagation.peer_group.is_some())
+        {
+            let master = old_propagation
+                .peer_group
+                .as_ref()
+                .or(old_propagation.master.as_ref());
+            if let Some(master) = master {
+                master.add_slave(&self.this());
+                propagation.master = Some(master.clone());
+            }
+        } else {
+            if let Some(peer_group) = old_propagation.peer_group.as_ref() {
+                peer_group.add_member(&self.this());
+                propagation.peer_group = Some(peer_group.clone());
+            }
+            if let Some(master) = old_propagation.master.as_ref() {
+                master.add_slave(&self.this());
+                propagation.master = Some(master.clone());
+            }
+        }
+
+        if flags.contains(CopyFlags::COPY_UNBINDABLE) {
+            propagation.is_unbindable = old_propagation.is_unbindable;
+        }
     }
 
     /// Clones a mount tree starting from the specified root `Dentry`.
@@ -124,13 +181,28 @@ impl MountNode {
     /// and the original tree remains unchanged.
     ///
     /// If `recursive` is set to `true`, the entire tree will be copied.
-    /// Otherwise, only the root mount node will be copied.
+    /// Otherwise, only the root mount node will be copied. Unbindable mount
+    /// nodes in the tree are skipped unless [`CopyFlags::COPY_UNBINDABLE`] is set.
     pub(super) fn clone_mount_node_tree(
         &self,
         root_dentry: &Arc<Dentry>,
         recursive: bool,
+        flags: CopyFlags,
     ) -> Arc<Self> {
-        let new_root_mount = self.clone_mount_node(root_dentry);
+        self.clone_mount_node_tree_with(root_dentry, recursive, flags, &mut |_, _| {})
+    }
+
+    /// Clones a mount tree like [`Self::clone_mount_node_tree`], calling `on_clone` with each
+    /// original mount node and its copy.
+    pub(super) fn clone_mount_node_tree_with(
+        &self,
+        root_dentry: &Arc<Dentry>,
+        recursive: bool,
+        flags: CopyFlags,
+        on_clone: &mut dyn FnMut(&Arc<Self>, &Arc<Self>),
+    ) -> Arc<Self> {
+        let new_root_mount = self.clone_mount_node(root_dentry, flags);
+        on_clone(&self.this(), &new_root_mount);
         if !recursive {
             return new_root_mount;
         }
@@ -145,8 +217,11 @@ impl MountNode {
                 if !mountpoint.is_descendant_of(old_mount.root_dentry()) {
                     continue;
                 }
+                if !flags.contains(CopyFlags::COPY_UNBINDABLE) && old_child_mount.is_unbindable() {
+                    continue;
+                }
                 let new_child_mount =
-                    old_child_mount.clone_mount_node(old_child_mount.root_dentry());
+                    old_child_mount.clone_mount_node(old_child_mount.root_dentry(), flags);
                 let key = mountpoint.key();
                 new_parent_mount
                     .children
@@ -154,6 +229,7 @@ impl MountNode {
                     .insert(key, new_child_mount.clone());
                 new_child_mount.set_parent(&new_parent_mount);
                 new_child_mount.set_mountpoint(&old_child_mount.mountpoint().unwrap());
+                on_clone(old_child_mount, &new_child_mount);
                 stack.push(old_child_mount.clone());
                 new_stack.push(new_child_mount);
             }
@@ -164,8 +240,7 @@ impl MountNode {
 
     /// Detaches the mount node from the parent mount node.
     fn detach_mount_node(&self) {
-        if let Some(parent) = self.parent() {
-            let parent = parent.upgrade().unwrap();
+        if let Some(parent) = self.parent().and_then(|parent| parent.upgrade()) {
             parent
                 .children
                 .write()
@@ -173,8 +248,17 @@ impl MountNode {
         }
     }
 
+    /// Detaches the mount node from its mountpoint, making it a root mount node.
+    pub(super) fn detach_as_root(&self) {
+        self.detach_mount_node();
+        if let Some(mountpoint) = self.mountpoint.write().take() {
+            mountpoint.dec_mount_count();
+        }
+        *self.parent.write() = None;
+    }
+
     /// Attaches the mount node to the mountpoint.
-    fn attach_mount_node(&self, target_path: &Path) {
+    pub(super) fn attach_mount_node(&self, target_path: &Path) {
         let key = target_path.key();
         target_path
             .mount_node()
@@ -186,15 +270,51 @@ impl MountNode {
     }
 
     /// Grafts the mount node tree to the mountpoint.
+    ///
+    /// The mount node tree is propagated to the mounts that receive mount events from the
+    /// new parent mount node.
     pub fn graft_mount_node_tree(&self, target_path: &Path) -> Result<()> {
         if target_path.type_() != InodeType::Dir {
             return_errno!(Errno::ENOTDIR);
         }
-        self.detach_mount_node();
+        self.detach_as_root();
         self.attach_mount_node(target_path);
+        self.propagate_attach();
         Ok(())
     }
 
+    /// Propagates the mount node tree, which has just been attached to its parent, to the
+    /// mounts that receive mount events from the parent.
+    ///
+    /// If the parent is shared, the whole tree becomes shared as well.
+    ///
+    /// Reference: <https://elixir.bootlin.com/linux/v6.15/source/fs/namespace.c#L2583>
+    pub(super) fn propagate_attach(&self) {
+        let Some(parent) = self.parent().and_then(|parent| parent.upgrade()) else {
+            return;
+        };
+        let Some(mountpoint) = self.mountpoint() else {
+            return;
+        };
+        if !parent.is_shared() {
+            return;
+        }
+
+        self.set_propagation(PropagationType::Shared, true);
+        propagate_mount(&parent, &mountpoint, &self.this(), &mut Vec::new());
+    }
+
+    /// Returns the path where the mount events at `mountpoint` are propagated to in this mount
+    /// node, or `None` if the mountpoint is not visible in this mount node.
+    fn propagation_target(&self, mountpoint: &Arc<Dentry>) -> Option<Path> {
+        if !Arc::ptr_eq(mountpoint, &self.root_dentry)
+            && !mountpoint.is_descendant_of(&self.root_dentry)
+        {
+            return None;
+        }
+        Some(Path::new(self.this(), mountpoint.clone()).get_top_path())
+    }
+
     /// Gets a child mount node from the mountpoint if any.
     pub(super) fn get(&self, mountpoint: &Path) -> Option<Arc<Self>> {
         if !Arc::ptr_eq(mountpoint.mount_node(), &self.this()) {
@@ -218,8 +338,34 @@ impl MountNode {
     /// In some cases we may need to reset the mountpoint of
     /// the created `MountNode`, such as move mount.
     pub(super) fn set_mountpoint(&self, inner: &Arc<Dentry>) {
+        inner.inc_mount_count();
         let mut mountpoint = self.mountpoint.write();
-        *mountpoint = Some(inner.clone());
+        if let Some(old_mountpoint) = mountpoint.replace(inner.clone()) {
+            old_mountpoint.dec_mount_count();
+        }
+    }
+
+    /// Gets the child mount nodes.
+    pub fn children(&self) -> Vec<Arc<Self>> {
+        self.children.read().values().cloned().collect()
+    }
+
+    /// Returns the path of the root `Dentry` relative to the root of the fs.
+    pub fn root_path_in_fs(&self) -> String {
+        let mut names = Vec::new();
+        let mut dentry = self.root_dentry.clone();
+        while let Some(parent) = dentry.parent() {
+            names.push(dentry.name());
+            dentry = parent;
+        }
+
+        if names.is_empty() {
+            return String::from("/");
+        }
+        names
+            .iter()
+            .rev()
+            .fold(String::new(), |path, name| path + "/" + name)
     }
 
     /// Flushes all pending filesystem metadata and cached file data to the device.
@@ -254,10 +400,115 @@ impl MountNode {
         self.this.upgrade().unwrap()
     }
 
+    /// Gets the unique ID of the mount.
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
     /// Gets the associated fs.
     pub fn fs(&self) -> &Arc<dyn FileSystem> {
         &self.fs
     }
+
+    /// Gets the marks that receive the file system events in the mount.
+    pub fn notify_marks(&self) -> &FsNotifyMarks {
+        &self.notify_marks
+    }
+
+    /// Gets the ID of the peer group if the mount is shared.
+    pub fn peer_group_id(&self) -> Option<u32> {
+        self.propagation
+            .read()
+            .peer_group
+            .as_ref()
+            .map(|peer_group| peer_group.id)
+    }
+
+    /// Gets the ID of the peer group that propagates mount events to the mount if the mount
+    /// is a slave.
+    pub fn master_id(&self) -> Option<u32> {
+        self.propagation
+            .read()
+            .master
+            .as_ref()
+            .map(|master| master.id)
+    }
+
+    /// Returns whether the mount is unbindable.
+    pub fn is_unbindable(&self) -> bool {
+        self.propagation.read().is_unbindable
+    }
+
+    fn is_shared(&self) -> bool {
+        self.propagation.read().peer_group.is_some()
+    }
+
+    fn peer_group(&self) -> Option<Arc<PeerGroup>> {
+        self.propagation.read().peer_group.clone()
+    }
+
+    /// Changes the propagation type of the mount.
+    ///
+    /// If `recursive` is true, the propagation type of all the mounts in the mount tree will be
+    /// changed.
+    ///
+    /// Reference: <https://elixir.bootlin.com/linux/v6.15/source/fs/pnode.c#L113>
+    pub fn set_propagation(&self, type_: PropagationType, recursive: bool) {
+        if recursive {
+            for child in self.children() {
+                child.set_propagation(type_, true);
+            }
+        }
+
+        let mut propagation = self.propagation.write();
+
+        if type_ == PropagationType::Shared {
+            if propagation.peer_group.is_none() {
+                let peer_group = PeerGroup::new();
+                peer_group.add_member(&self.this());
+                propagation.peer_group = Some(peer_group);
+            }
+            propagation.is_unbindable = false;
+            return;
+        }
+
+        // Leave the peer group. The mount becomes a slave of its former peers, if any.
+        if let Some(peer_group) = propagation.peer_group.take() {
+            if peer_group.remove_member(self) {
+                peer_group.transfer_slaves(propagation.master.as_ref());
+            } else {
+                peer_group.add_slave(&self.this());
+                if let Some(old_master) = propagation.master.replace(peer_group) {
+                    old_master.remove_slave(self);
+                }
+            }
+        }
+
+        if type_ != PropagationType::Slave {
+            if let Some(master) = propagation.master.take() {
+                master.remove_slave(self);
+            }
+            propagation.is_unbindable = type_ == PropagationType::Unbindable;
+        }
+    }
+}
+
+impl Drop for MountNode {
+    fn drop(&mut self) {
+        let propagation = core::mem::take(self.propagation.get_mut());
+        if let Some(peer_group) = propagation.peer_group {
+            if peer_group.remove_member(self) {
+                peer_group.transfer_slaves(propagation.master.as_ref());
+            }
+        }
+        if let Some(master) = propagation.master {
+            master.remove_slave(self);
+        }
+
+        if let Some(mountpoint) = self.mountpoint.get_mut().take() {
+            mountpoint.dec_mount_count();
+        }
+    }
 }
 
 impl Debug for MountNode {
@@ -269,3 +520,194 @@ impl Debug for MountNode {
             .finish()
     }
 }
+
+/// The propagation type of a mount.
+///
+/// Reference: <https://docs.kernel.org/filesystems/sharedsubtree.html>
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PropagationType {
+    /// The mount shares mount events with its peers.
+    Shared,
+    /// The mount receives mount events from its master, but does not send any.
+    Slave,
+    /// The mount neither sends nor receives mount events.
+    Private,
+    /// The mount is private and cannot be bind mounted.
+    Unbindable,
+}
+
+/// The propagation properties of a mount.
+#[derive(Default)]
+struct Propagation {
+    /// The peer group of the mount if the mount is shared.
+    peer_group: Option<Arc<PeerGroup>>,
+    /// The peer group that propagates mount events to the mount if the mount is a slave.
+    master: Option<Arc<PeerGroup>>,
+    /// Whether the mount is unbindable.
+    is_unbindable: bool,
+}
+
+/// A group of shared mounts, which propagate mount events to each other and to their slaves.
+struct PeerGroup {
+    id: u32,
+    members: SpinLock<Vec<Weak<MountNode>>>,
+    slaves: SpinLock<Vec<Weak<MountNode>>>,
+}
+
+static NEXT_PEER_GROUP_ID: AtomicU32 = AtomicU32::new(1);
+
+impl PeerGroup {
+    fn new() -> Arc<Self> {
+        Arc::new(Self {
+            id: NEXT_PEER_GROUP_ID.fetch_add(1, Ordering::Relaxed),
+            members: SpinLock::new(Vec::new()),
+            slaves: SpinLock::new(Vec::new()),
+        })
+    }
+
+    fn members(&self) -> Vec<Arc<MountNode>> {
+        self.members
+            .lock()
+            .iter()
+            .filter_map(Weak::upgrade)
+            .collect()
+    }
+
+    fn add_member(&self, mount: &Arc<MountNode>) {
+        self.members.lock().push(Arc::downgrade(mount));
+    }
+
+    /// Removes the member and returns whether the peer group becomes empty.
+    fn remove_member(&self, mount: &MountNode) -> bool {
+        let mut members = self.members.lock();
+        members
+            .retain(|member| !core::ptr::eq(member.as_ptr(), mount) && member.strong_count() > 0);
+        members.is_empty()
+    }
+
+    fn slaves(&self) -> Vec<Arc<MountNode>> {
+        self.slaves
+            .lock()
+            .iter()
+            .filter_map(Weak::upgrade)
+            .collect()
+    }
+
+    fn add_slave(&self, mount: &Arc<MountNode>) {
+        self.slaves.lock().push(Arc::downgrade(mount));
+    }
+
+    fn remove_slave(&self, mount: &MountNode) {
+        self.slaves
+            .lock()
+            .retain(|slave| !core::ptr::eq(slave.as_ptr(), mount) && slave.strong_count() > 0);
+    }
+
+    /// Makes the slaves of the peer group slaves of `new_master`, or private if `new_master` is
+    /// `None`.
+    ///
+    /// This is done when the last member leaves the peer group.
+    fn transfer_slaves(&self, new_master: Option<&Arc<PeerGroup>>) {
+        let slaves: Vec<_> = core::mem::take(&mut *self.slaves.lock())
+            .into_iter()
+            .filter_map(|slave| slave.upgrade())
+            .collect();
+        for slave in slaves {
+            if let Some(new_master) = new_master {
+                new_master.add_slave(&slave);
+            }
+            slave.propagation.write().master = new_master.cloned();
+        }
+    }
+}
+
+bitflags! {
+    /// The flags that decide the propagation properties of cloned mounts.
+    pub(super) struct CopyFlags: u32 {
+        /// Makes the copies slaves of the originals.
+        const SLAVE           = 1 << 0;
+        /// Makes the copies of shared mounts slaves of the originals.
+        const SHARED_TO_SLAVE = 1 << 1;
+        /// Copies unbindable mounts instead of skipping them.
+        const COPY_UNBINDABLE = 1 << 2;
+    }
+}
+
+/// Propagates the mount tree of `source`, which has been attached to `parent` at
+/// `mountpoint`, to the peers and the slaves of `parent`.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.15/source/fs/pnode.c#L336>
+fn propagate_mount(
+    parent: &Arc<MountNode>,
+    mountpoint: &Arc<Dentry>,
+    source: &Arc<MountNode>,
+    visited: &mut Vec<Arc<PeerGroup>>,
+) {
+    let Some(peer_group) = parent.peer_group() else {
+        return;
+    };
+    if visited.iter().any(|group| Arc::ptr_eq(group, &peer_group)) {
+        return;
+    }
+    visited.push(peer_group.clone());
+
+    for peer in peer_group.members() {
+        if Arc::ptr_eq(&peer, parent) {
+            continue;
+        }
+        let Some(target_path) = peer.propagation_target(mountpoint) else {
+            continue;
+        };
+        let copy = source.clone_mount_node_tree(source.root_dentry(), true, CopyFlags::empty());
+        copy.attach_mount_node(&target_path);
+    }
+
+    for slave in peer_group.slaves() {
+        let Some(target_path) = slave.propagation_target(mountpoint) else {
+            continue;
+        };
+        let copy = source.clone_mount_node_tree(source.root_dentry(), true, CopyFlags::SLAVE);
+        copy.attach_mount_node(&target_path);
+
+        // A slave that is also shared passes the mount events on.
+        let target_mount = target_path.mount_node();
+        if target_mount.is_shared() {
+            copy.set_propagation(PropagationType::Shared, true);
+            propagate_mount(target_mount, &target_path.dentry, &copy, visited);
+        }
+    }
+}
+
+/// Propagates the unmount at `mountpoint` of `parent` to the peers and the slaves of `parent`.
+///
+/// Only the mounts that have no child mounts are unmounted.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.15/source/fs/pnode.c#L599>
+fn propagate_umount(
+    parent: &Arc<MountNode>,
+    mountpoint: &Arc<Dentry>,
+    visited: &mut Vec<Arc<PeerGroup>>,
+) {
+    let Some(peer_group) = parent.peer_group() else {
+        return;
+    };
+    if visited.iter().any(|group| Arc::ptr_eq(group, &peer_group)) {
+        return;
+    }
+    visited.push(peer_group.clone());
+
+    let receivers = peer_group
+        .members()
+        .into_iter()
+        .filter(|peer| !Arc::ptr_eq(peer, parent))
+        .chain(peer_group.slaves());
+    for receiver in receivers {
+        let child_mount = receiver.children.read().get(&mountpoint.key()).cloned();
+        if let Some(child_mount) = child_mount {
+            if child_mount.children.read().is_empty() {
+                child_mount.detach_as_root();
+            }
+        }
+        propagate_umount(&receiver, mountpoint, visited);
+    }
+}
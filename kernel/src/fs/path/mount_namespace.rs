@@ -0,0 +1,191 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use spin::Once;
+
+use super::{mount::CopyFlags, MountNode, Path};
+use crate::{
+    fs::{fs_resolver::FsResolver, rootfs::root_mount, utils::InodeType},
+    prelude::*,
+    process::namespace::{alloc_ns_id, UserNamespace},
+};
+
+/// A mount namespace, which isolates the mount tree.
+pub struct MountNamespace {
+    id: u64,
+    root: RwLock<Arc<MountNode>>,
+    owner: Arc<UserNamespace>,
+}
+
+static INIT_MNT_NS: Once<Arc<MountNamespace>> = Once::new();
+
+impl MountNamespace {
+    /// Returns the initial mount namespace, whose root is the root mount of the rootfs.
+    pub fn get_init_singleton() -> &'static Arc<MountNamespace> {
+        INIT_MNT_NS.call_once(|| {
+            Arc::new(Self {
+                id: alloc_ns_id(),
+                root: RwLock::new(root_mount().clone()),
+                owner: UserNamespace::get_init_singleton().clone(),
+            })
+        })
+    }
+
+    /// Creates a copy of the namespace, which will be owned by `owner`.
+    ///
+    /// The whole mount tree is copied. The root and the current working directory of
+    /// `fs_resolver` are moved to the corresponding mounts in the new tree.
+    ///
+    /// The copies of shared mounts are peers of the originals, unless the new namespace is
+    /// owned by another user namespace, in which case they become slaves of the originals.
+    pub fn new_copy(&self, owner: Arc<UserNamespace>, fs_resolver: &mut FsResolver) -> Arc<Self> {
+        // Reference: <https://elixir.bootlin.com/linux/v6.15/source/fs/namespace.c#L4094>
+        let mut copy_flags = CopyFlags::COPY_UNBINDABLE;
+        if !Arc::ptr_eq(&owner, &self.owner) {
+            copy_flags |= CopyFlags::SHARED_TO_SLAVE;
+        }
+
+        let mut mount_pairs = Vec::new();
+        let old_root = self.root();
+        let root = old_root.clone_mount_node_tree_with(
+            old_root.root_dentry(),
+            true,
+            copy_flags,
+            &mut |old, new| mount_pairs.push((old.clone(), new.clone())),
+        );
+
+        let rebase = |path: &Path| -> Option<Path> {
+            let (_, new_mount) = mount_pairs
+                .iter()
+                .find(|(old_mount, _)| Arc::ptr_eq(old_mount, path.mount_node()))?;
+            Some(Path::new(new_mount.clone(), path.dentry.clone()))
+        };
+        if let Some(new_root) = rebase(fs_resolver.root()) {
+            fs_resolver.set_root(new_root);
+        }
+        if let Some(new_cwd) = rebase(fs_resolver.cwd()) {
+            fs_resolver.set_cwd(new_cwd);
+        }
+
+        Arc::new(Self {
+            id: alloc_ns_id(),
+            root: RwLock::new(root),
+            owner,
+        })
+    }
+
+    /// Returns the ID of the namespace.
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Returns the user namespace that owns the namespace.
+    pub fn owner(&self) -> &Arc<UserNamespace> {
+        &self.owner
+    }
+
+    /// Returns the root mount of the namespace.
+    pub fn root(&self) -> Arc<MountNode> {
+        self.root.read().clone()
+    }
+
+    /// Returns the root directory of the namespace.
+    pub fn root_path(&self) -> Path {
+        Path::new_fs_root(self.root())
+    }
+
+    /// Moves the mount of `root` to `put_old` and puts the mount of `new_root` in its place, as
+    /// is done by `pivot_root`.
+    ///
+    /// The caller is responsible for updating the root and the current working directory of the
+    /// threads that are using `root`.
+    ///
+    /// Reference: <https://elixir.bootlin.com/linux/v6.15/source/fs/namespace.c#L4458>
+    pub fn pivot_root(&self, root: &Path, new_root: &Path, put_old: &Path) -> Result<()> {
+        if new_root.type_() != InodeType::Dir || put_old.type_() != InodeType::Dir {
+            return_errno_with_message!(
+                Errno::ENOTDIR,
+                "the new root or the old root is not a directory"
+            );
+        }
+
+        let mut ns_root = self.root.write();
+
+        let root_mount = root.mount_node();
+        let new_mount = new_root.mount_node();
+        if [root, new_root, put_old]
+            .iter()
+            .any(|path| !is_in_tree(path.mount_node(), &ns_root))
+        {
+            return_errno_with_message!(Errno::EINVAL, "the mount is not in the mount namespace");
+        }
+        if Arc::ptr_eq(new_mount, root_mount) || Arc::ptr_eq(put_old.mount_node(), root_mount) {
+            return_errno_with_message!(Errno::EBUSY, "the mount is the current root mount");
+        }
+        let is_parent_shared = |mount: &Arc<MountNode>| {
+            mount
+                .parent()
+                .and_then(|parent| parent.upgrade())
+                .is_some_and(|parent| parent.peer_group_id().is_some())
+        };
+        if put_old.mount_node().peer_group_id().is_some()
+            || is_parent_shared(new_mount)
+            || is_parent_shared(root_mount)
+        {
+            return_errno_with_message!(Errno::EINVAL, "the mounts are shared");
+        }
+        if !root.is_root_of_mount() || !new_root.is_root_of_mount() {
+            return_errno_with_message!(Errno::EINVAL, "the path is not the root of a mount");
+        }
+        if new_mount.parent().is_none() {
+            return_errno_with_message!(Errno::EINVAL, "the new root mount is not attached");
+        }
+        if !is_reachable(new_root, root) || !is_reachable(put_old, new_root) {
+            return_errno_with_message!(Errno::EINVAL, "the path is not underneath the root");
+        }
+
+        let root_mountpoint = root_mount
+            .parent()
+            .and_then(|parent| parent.upgrade())
+            .zip(root_mount.mountpoint());
+
+        new_mount.detach_as_root();
+        root_mount.detach_as_root();
+        root_mount.attach_mount_node(put_old);
+        match root_mountpoint {
+            Some((parent, mountpoint)) => {
+                new_mount.attach_mount_node(&Path::new(parent, mountpoint));
+            }
+            None => *ns_root = new_mount.clone(),
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns whether `mount` is in the mount tree rooted at `root`.
+fn is_in_tree(mount: &Arc<MountNode>, root: &Arc<MountNode>) -> bool {
+    let mut mount = mount.clone();
+    loop {
+        if Arc::ptr_eq(&mount, root) {
+            return true;
+        }
+        match mount.parent().and_then(|parent| parent.upgrade()) {
+            Some(parent) => mount = parent,
+            None => return false,
+        }
+    }
+}
+
+/// Returns whether `path` is `ancestor` or can be reached from `ancestor`.
+fn is_reachable(path: &Path, ancestor: &Path) -> bool {
+    let mut path = path.clone();
+    loop {
+        if path == *ancestor {
+            return true;
+        }
+        match path.effective_parent() {
+            Some(parent) => path = parent,
+            None => return false,
+        }
+    }
+}
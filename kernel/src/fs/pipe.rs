@@ -1,6 +1,11 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use core::sync::atomic::{AtomicU32, Ordering};
+use core::{
+    ops::Range,
+    sync::atomic::{AtomicU32, Ordering},
+};
+
+use ostd::mm::{io_util::HasVmReaderWriter, FrameAllocOptions, Infallible, UFrame};
 
 use super::{
     file_handle::FileLike,
@@ -14,7 +19,7 @@ use crate::{
         Gid, Uid,
     },
     time::clocks::RealTimeCoarseClock,
-    util::ring_buffer::{RbConsumer, RbProducer, RingBuffer},
+    util::{MultiRead, MultiWrite},
 };
 
 const DEFAULT_PIPE_BUF_SIZE: usize = 65536;
@@ -37,52 +42,404 @@ pub fn new_pair() -> Result<(Arc<PipeReader>, Arc<PipeWriter>)> {
 }
 
 pub fn new_pair_with_capacity(capacity: usize) -> Result<(Arc<PipeReader>, Arc<PipeWriter>)> {
-    let (producer, consumer) = RingBuffer::new(capacity).split();
+    let buf = Arc::new(Mutex::new(PipeBuf::new(capacity)));
     let (producer_state, consumer_state) =
         Endpoint::new_pair(EndpointState::default(), EndpointState::default());
 
     Ok((
-        PipeReader::new(consumer, consumer_state, StatusFlags::empty())?,
-        PipeWriter::new(producer, producer_state, StatusFlags::empty())?,
+        PipeReader::new(buf.clone(), consumer_state, StatusFlags::empty())?,
+        PipeWriter::new(buf, producer_state, StatusFlags::empty())?,
     ))
 }
 
+/// The buffer of a pipe.
+///
+/// The data in the buffer are kept as fragments of pages. This allows the pages to be moved into
+/// or out of the pipe (e.g., by `splice` and `tee`) without copying the data.
+struct PipeBuf {
+    frags: VecDeque<PipeFrag>,
+    len: usize,
+    capacity: usize,
+    max_frags: usize,
+}
+
+/// A fragment of a page in a [`PipeBuf`].
+struct PipeFrag {
+    frame: UFrame,
+    range: Range<usize>,
+    /// Whether new data can be written into the page after the fragment.
+    ///
+    /// This is only true if the page is allocated by the pipe. Pages that come from elsewhere
+    /// (e.g., the page cache) must not be written to.
+    is_appendable: bool,
+}
+
+impl PipeFrag {
+    fn len(&self) -> usize {
+        self.range.len()
+    }
+
+    fn reader(&self) -> VmReader<'_, Infallible> {
+        let mut reader = self.frame.reader();
+        reader.skip(self.range.start).limit(self.range.len());
+        reader
+    }
+
+    fn clone_prefix(&self, len: usize) -> Self {
+        Self {
+            frame: self.frame.clone(),
+            range: self.range.start..self.range.start + len,
+            is_appendable: false,
+        }
+    }
+}
+
+impl PipeBuf {
+    fn new(capacity: usize) -> Self {
+        Self {
+            frags: VecDeque::new(),
+            len: 0,
+            capacity,
+            max_frags: capacity.div_ceil(PAGE_SIZE),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of bytes that can be written into the buffer.
+    fn free_len(&self) -> usize {
+        let tail_room = match self.frags.back() {
+            Some(frag) if frag.is_appendable => PAGE_SIZE - frag.range.end,
+            _ => 0,
+        };
+        let free_frags = self.max_frags - self.frags.len();
+
+        (self.capacity - self.len).min(tail_room + free_frags * PAGE_SIZE)
+    }
+
+    /// Returns whether a new fragment can be pushed to the buffer.
+    fn has_free_frag(&self) -> bool {
+        self.len < self.capacity && self.frags.len() < self.max_frags
+    }
+
+    fn push_frag(&mut self, frag: PipeFrag) {
+        self.len += frag.len();
+        self.frags.push_back(frag);
+    }
+
+    /// Pops at most `max_len` bytes from the front of the buffer as a fragment.
+    fn pop_frag(&mut self, max_len: usize) -> Option<PipeFrag> {
+        let frag = self.frags.front_mut()?;
+
+        let frag = if frag.len() <= max_len {
+            self.frags.pop_front().unwrap()
+        } else {
+            let prefix = frag.clone_prefix(max_len);
+            frag.range.start += max_len;
+            prefix
+        };
+        self.len -= frag.len();
+
+        Some(frag)
+    }
+
+    /// Consumes `len` bytes from the front of the buffer.
+    fn consume(&mut self, mut len: usize) {
+        while len > 0 {
+            let frag = self.pop_frag(len).unwrap();
+            len -= frag.len();
+        }
+    }
+
+    /// Writes the data from `reader` into the buffer.
+    fn write(&mut self, reader: &mut dyn MultiRead) -> Result<usize> {
+        let mut free_len = self.free_len();
+        let mut written_len = 0;
+        let mut result = Ok(());
+
+        while free_len > 0 && reader.sum_lens() > 0 {
+            if !self
+                .frags
+                .back()
+                .is_some_and(|frag| frag.is_appendable && frag.range.end < PAGE_SIZE)
+            {
+                let frame = match FrameAllocOptions::new().zeroed(false).alloc_frame() {
+                    Ok(frame) => frame,
+                    Err(err) => {
+                        result = Err(err.into());
+                        break;
+                    }
+                };
+                self.frags.push_back(PipeFrag {
+                    frame: frame.into(),
+                    range: 0..0,
+                    is_appendable: true,
+                });
+            }
+
+            let frag = self.frags.back_mut().unwrap();
+            let mut writer = frag.frame.writer();
+            writer
+                .skip(frag.range.end)
+                .limit((PAGE_SIZE - frag.range.end).min(free_len));
+            let len = match reader.read(&mut writer) {
+                Ok(0) => break,
+                Ok(len) => len,
+                Err(err) => {
+                    result = Err(err);
+                    break;
+                }
+            };
+
+            frag.range.end += len;
+            self.len += len;
+            free_len -= len;
+            written_len += len;
+        }
+
+        // Do not keep the page that has been allocated but not written.
+        if self.frags.back().is_some_and(|frag| frag.len() == 0) {
+            self.frags.pop_back();
+        }
+
+        if written_len == 0 {
+            result?;
+        }
+        Ok(written_len)
+    }
+
+    /// Reads at most `max_len` bytes from the buffer by `read`.
+    ///
+    /// `read` is called with the fragments in order and returns the number of bytes that it has
+    /// read from the fragment. The data that have been read are consumed.
+    fn read_by<F>(&mut self, max_len: usize, mut read: F) -> Result<usize>
+    where
+        F: FnMut(VmReader<Infallible>) -> Result<usize>,
+    {
+        let mut read_len = 0;
+
+        while read_len < max_len
+            && let Some(frag) = self.frags.front()
+        {
+            let mut reader = frag.reader();
+            let frag_len = reader.remain().min(max_len - read_len);
+            reader.limit(frag_len);
+
+            let len = match read(reader) {
+                Ok(len) => len,
+                Err(_) if read_len > 0 => break,
+                Err(err) => return Err(err),
+            };
+            self.consume(len);
+            read_len += len;
+
+            if len < frag_len {
+                break;
+            }
+        }
+
+        Ok(read_len)
+    }
+
+    /// Moves at most `max_len` bytes from the buffer to `dst`.
+    ///
+    /// If `is_clone` is true, the data are not consumed from the buffer. In either case, the pages
+    /// are shared instead of being copied.
+    fn transfer_to(&mut self, dst: &mut PipeBuf, max_len: usize, is_clone: bool) -> usize {
+        let max_len = max_len.min(dst.capacity - dst.len);
+        let mut transferred_len = 0;
+
+        if is_clone {
+            for frag in self.frags.iter() {
+                if transferred_len == max_len || !dst.has_free_frag() {
+                    break;
+                }
+                let len = frag.len().min(max_len - transferred_len);
+                dst.push_frag(frag.clone_prefix(len));
+                transferred_len += len;
+            }
+        } else {
+            while transferred_len < max_len
+                && dst.has_free_frag()
+                && let Some(frag) = self.pop_frag(max_len - transferred_len)
+            {
+                transferred_len += frag.len();
+                dst.push_frag(frag);
+            }
+        }
+
+        transferred_len
+    }
+}
+
 pub struct PipeReader {
-    consumer: Mutex<RbConsumer<u8>>,
+    buf: Arc<Mutex<PipeBuf>>,
     state: Endpoint<EndpointState>,
     status_flags: AtomicU32,
 }
 
 impl PipeReader {
     fn new(
-        consumer: RbConsumer<u8>,
+        buf: Arc<Mutex<PipeBuf>>,
         state: Endpoint<EndpointState>,
         status_flags: StatusFlags,
     ) -> Result<Arc<Self>> {
         check_status_flags(status_flags)?;
 
         Ok(Arc::new(Self {
-            consumer: Mutex::new(consumer),
+            buf,
             state,
             status_flags: AtomicU32::new(status_flags.bits()),
         }))
     }
 
-    fn try_read(&self, writer: &mut VmWriter) -> Result<usize> {
+    /// Reads the data from the pipe into `writer`.
+    ///
+    /// If `is_nonblocking` is false, this method blocks until there are data to read.
+    pub fn read_to(&self, writer: &mut dyn MultiWrite, is_nonblocking: bool) -> Result<usize> {
+        if writer.sum_lens() == 0 {
+            // Even the peer endpoint (`PipeWriter`) has been closed, reading an empty buffer is
+            // still fine.
+            return Ok(0);
+        }
+
+        if is_nonblocking {
+            self.try_read(writer)
+        } else {
+            self.wait_events(IoEvents::IN, None, || self.try_read(writer))
+        }
+    }
+
+    fn try_read(&self, writer: &mut dyn MultiWrite) -> Result<usize> {
         let read = || {
-            let mut consumer = self.consumer.lock();
-            consumer.read_fallible(writer)
+            let mut buf = self.buf.lock();
+            buf.read_by(usize::MAX, |mut reader| writer.write(&mut reader))
         };
 
         self.state.read_with(read)
     }
 
+    /// Moves at most `max_len` bytes from the pipe to the pipe of `writer`.
+    ///
+    /// If `is_clone` is true, the data are copied instead of moved, i.e., they are not consumed
+    /// from this pipe. In either case, the pages are shared between the two pipes and the data
+    /// are not copied.
+    ///
+    /// If `is_nonblocking` is false, this method blocks until there are data in this pipe and
+    /// there is room in the other pipe.
+    pub fn splice_to_pipe(
+        &self,
+        writer: &PipeWriter,
+        max_len: usize,
+        is_clone: bool,
+        is_nonblocking: bool,
+    ) -> Result<usize> {
+        if Arc::ptr_eq(&self.buf, &writer.buf) {
+            return_errno_with_message!(Errno::EINVAL, "the two pipes are the same");
+        }
+        if max_len == 0 {
+            return Ok(0);
+        }
+
+        if is_nonblocking {
+            return self.try_splice_to_pipe(writer, max_len, is_clone);
+        }
+
+        self.wait_events(IoEvents::IN, None, || {
+            // Wait for the room in the other pipe only if there are data in this pipe.
+            let spliced_len = writer.wait_events(IoEvents::OUT, None, || {
+                match self.try_splice_to_pipe(writer, max_len, is_clone) {
+                    Err(err) if err.error() == Errno::EAGAIN && self.buf.lock().is_empty() => {
+                        Ok(None)
+                    }
+                    result => result.map(Some),
+                }
+            })?;
+            spliced_len.ok_or_else(|| Error::with_message(Errno::EAGAIN, "the pipe is empty"))
+        })
+    }
+
+    fn try_splice_to_pipe(
+        &self,
+        writer: &PipeWriter,
+        max_len: usize,
+        is_clone: bool,
+    ) -> Result<usize> {
+        let splice = || {
+            // Lock the two pipes in a fixed order to avoid deadlocks.
+            let (mut src, mut dst) = if Arc::as_ptr(&self.buf) < Arc::as_ptr(&writer.buf) {
+                let src = self.buf.lock();
+                (src, writer.buf.lock())
+            } else {
+                let dst = writer.buf.lock();
+                (self.buf.lock(), dst)
+            };
+
+            if src.is_empty() {
+                return Ok(0);
+            }
+            writer
+                .state
+                .write_with(|| Ok(src.transfer_to(&mut dst, max_len, is_clone)))
+        };
+
+        self.state.read_with(splice)
+    }
+
+    /// Moves at most `max_len` bytes out of the pipe by `consume`.
+    ///
+    /// `consume` is called with the data in the pipe in order and returns the number of bytes
+    /// that it has consumed. The data are passed without being copied.
+    ///
+    /// If `is_nonblocking` is false, this method blocks until there are data to read. Note that
+    /// an error from `consume` (including [`Errno::EAGAIN`]) is returned without blocking.
+    pub fn splice_to<F>(
+        &self,
+        max_len: usize,
+        is_nonblocking: bool,
+        mut consume: F,
+    ) -> Result<usize>
+    where
+        F: FnMut(VmReader<Infallible>) -> Result<usize>,
+    {
+        if max_len == 0 {
+            return Ok(0);
+        }
+
+        let result = if is_nonblocking {
+            self.try_splice_to(max_len, &mut consume)
+        } else {
+            self.wait_events(IoEvents::IN, None, || {
+                self.try_splice_to(max_len, &mut consume)
+            })
+        };
+        result?
+    }
+
+    fn try_splice_to<F>(&self, max_len: usize, consume: &mut F) -> Result<Result<usize>>
+    where
+        F: FnMut(VmReader<Infallible>) -> Result<usize>,
+    {
+        let mut buf = self.buf.lock();
+        if buf.is_empty() {
+            // This reports the end-of-file or fails with `EAGAIN`.
+            return self.state.read_with(|| Ok(0)).map(Ok);
+        }
+
+        match buf.read_by(max_len, consume) {
+            Ok(len) if len > 0 => Ok(self.state.read_with(|| Ok(len))),
+            result => Ok(result),
+        }
+    }
+
     fn check_io_events(&self) -> IoEvents {
         let mut events = IoEvents::empty();
         if self.state.is_peer_shutdown() {
             events |= IoEvents::HUP;
         }
-        if !self.consumer.lock().is_empty() {
+        if !self.buf.lock().is_empty() {
             events |= IoEvents::IN;
         }
         events
@@ -98,17 +455,8 @@ impl Pollable for PipeReader {
 
 impl FileLike for PipeReader {
     fn read(&self, writer: &mut VmWriter) -> Result<usize> {
-        if !writer.has_avail() {
-            // Even the peer endpoint (`PipeWriter`) has been closed, reading an empty buffer is
-            // still fine.
-            return Ok(0);
-        }
-
-        if self.status_flags().contains(StatusFlags::O_NONBLOCK) {
-            self.try_read(writer)
-        } else {
-            self.wait_events(IoEvents::IN, None, || self.try_read(writer))
-        }
+        let is_nonblocking = self.status_flags().contains(StatusFlags::O_NONBLOCK);
+        self.read_to(writer, is_nonblocking)
     }
 
     fn status_flags(&self) -> StatusFlags {
@@ -156,43 +504,131 @@ impl Drop for PipeReader {
 }
 
 pub struct PipeWriter {
-    producer: Mutex<RbProducer<u8>>,
+    buf: Arc<Mutex<PipeBuf>>,
     state: Endpoint<EndpointState>,
     status_flags: AtomicU32,
 }
 
 impl PipeWriter {
     fn new(
-        producer: RbProducer<u8>,
+        buf: Arc<Mutex<PipeBuf>>,
         state: Endpoint<EndpointState>,
         status_flags: StatusFlags,
     ) -> Result<Arc<Self>> {
         check_status_flags(status_flags)?;
 
         Ok(Arc::new(Self {
-            producer: Mutex::new(producer),
+            buf,
             state,
             status_flags: AtomicU32::new(status_flags.bits()),
         }))
     }
 
-    fn try_write(&self, reader: &mut VmReader) -> Result<usize> {
+    /// Writes the data from `reader` into the pipe.
+    ///
+    /// If `is_nonblocking` is false, this method blocks until there is room to write.
+    pub fn write_from(&self, reader: &mut dyn MultiRead, is_nonblocking: bool) -> Result<usize> {
+        if reader.sum_lens() == 0 {
+            // Even the peer endpoint (`PipeReader`) has been closed, writing an empty buffer is
+            // still fine.
+            return Ok(0);
+        }
+
+        if is_nonblocking {
+            self.try_write(reader)
+        } else {
+            self.wait_events(IoEvents::OUT, None, || self.try_write(reader))
+        }
+    }
+
+    fn try_write(&self, reader: &mut dyn MultiRead) -> Result<usize> {
         let write = || {
-            let mut producer = self.producer.lock();
-            if reader.remain() <= PIPE_BUF && producer.free_len() < reader.remain() {
+            let mut buf = self.buf.lock();
+            if reader.sum_lens() <= PIPE_BUF && buf.free_len() < reader.sum_lens() {
                 // No sufficient space for an atomic write
                 return Ok(0);
             }
-            producer.write_fallible(reader)
+            buf.write(reader)
         };
 
         self.state.write_with(write)
     }
 
+    /// Moves at most `max_len` bytes into the pipe from `produce`.
+    ///
+    /// `produce` is called with the maximum number of bytes and the maximum number of pages, and
+    /// returns the pages and the ranges of the data in them. The pages are put into the pipe
+    /// without being copied, so they must not be modified afterwards unless the modifications
+    /// are expected to be visible to the pipe (e.g., the pages in the page cache). If `produce`
+    /// returns no pages, it is considered that the end of the input has been reached.
+    ///
+    /// If `is_nonblocking` is false, this method blocks until there is room to write. Note that
+    /// an error from `produce` (including [`Errno::EAGAIN`]) is returned without blocking.
+    pub fn splice_from<F>(
+        &self,
+        max_len: usize,
+        is_nonblocking: bool,
+        mut produce: F,
+    ) -> Result<usize>
+    where
+        F: FnMut(usize, usize) -> Result<Vec<(UFrame, Range<usize>)>>,
+    {
+        if max_len == 0 {
+            return Ok(0);
+        }
+
+        let result = if is_nonblocking {
+            self.try_splice_from(max_len, &mut produce)
+        } else {
+            self.wait_events(IoEvents::OUT, None, || {
+                self.try_splice_from(max_len, &mut produce)
+            })
+        };
+        result?
+    }
+
+    fn try_splice_from<F>(&self, max_len: usize, produce: &mut F) -> Result<Result<usize>>
+    where
+        F: FnMut(usize, usize) -> Result<Vec<(UFrame, Range<usize>)>>,
+    {
+        if self.state.is_shutdown() {
+            return_errno_with_message!(Errno::EPIPE, "the channel is shut down");
+        }
+
+        let mut buf = self.buf.lock();
+        if !buf.has_free_frag() {
+            return_errno_with_message!(Errno::EAGAIN, "the channel is full");
+        }
+        let max_len = max_len.min(buf.capacity - buf.len);
+        let max_frags = buf.max_frags - buf.frags.len();
+
+        let frags = match produce(max_len, max_frags) {
+            Ok(frags) => frags,
+            Err(err) => return Ok(Err(err)),
+        };
+        debug_assert!(frags.len() <= max_frags);
+
+        let mut len = 0;
+        for (frame, range) in frags.into_iter().filter(|(_, range)| !range.is_empty()) {
+            len += range.len();
+            buf.push_frag(PipeFrag {
+                frame,
+                range,
+                is_appendable: false,
+            });
+        }
+        if len == 0 {
+            // This is the end of the input.
+            return Ok(Ok(0));
+        }
+
+        Ok(self.state.write_with(|| Ok(len)))
+    }
+
     fn check_io_events(&self) -> IoEvents {
         if self.state.is_shutdown() {
             IoEvents::ERR | IoEvents::OUT
-        } else if self.producer.lock().free_len() >= PIPE_BUF {
+        } else if self.buf.lock().free_len() >= PIPE_BUF {
             IoEvents::OUT
         } else {
             IoEvents::empty()
@@ -209,17 +645,8 @@ impl Pollable for PipeWriter {
 
 impl FileLike for PipeWriter {
     fn write(&self, reader: &mut VmReader) -> Result<usize> {
-        if !reader.has_remain() {
-            // Even the peer endpoint (`PipeReader`) has been closed, writing an empty buffer is
-            // still fine.
-            return Ok(0);
-        }
-
-        if self.status_flags().contains(StatusFlags::O_NONBLOCK) {
-            self.try_write(reader)
-        } else {
-            self.wait_events(IoEvents::OUT, None, || self.try_write(reader))
-        }
+        let is_nonblocking = self.status_flags().contains(StatusFlags::O_NONBLOCK);
+        self.write_from(reader, is_nonblocking)
     }
 
     fn status_flags(&self) -> StatusFlags {
@@ -0,0 +1,58 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! This module offers `/proc/interrupts` file support, which tells the user
+//! space about the number of interrupts per CPU per IRQ line.
+//!
+//! Reference: <https://www.kernel.org/doc/html/latest/filesystems/proc.html#proc-interrupts>
+
+use alloc::format;
+use core::fmt::Write;
+
+use ostd::{cpu::all_cpus, trap::irq};
+
+use crate::{
+    fs::{
+        procfs::template::{FileOps, ProcFileBuilder},
+        utils::Inode,
+    },
+    prelude::*,
+};
+
+/// Represents the inode at `/proc/interrupts`.
+pub struct InterruptsFileOps;
+
+impl InterruptsFileOps {
+    pub fn new_inode(parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        ProcFileBuilder::new(Self).parent(parent).build().unwrap()
+    }
+}
+
+impl FileOps for InterruptsFileOps {
+    fn data(&self) -> Result<Vec<u8>> {
+        let mut output = String::new();
+
+        output.push_str("    ");
+        for cpu in all_cpus() {
+            write!(output, " {:>10}", format!("CPU{}", cpu.as_usize())).unwrap();
+        }
+        output.push('\n');
+
+        // Like Linux, only the IRQ lines that have been triggered are listed.
+        for irq_num in irq::irq_nums() {
+            let counts = all_cpus()
+                .map(|cpu| irq::irq_count_on_cpu(irq_num, cpu))
+                .collect::<Vec<_>>();
+            if counts.iter().all(|&count| count == 0) {
+                continue;
+            }
+
+            write!(output, "{:>3}:", irq_num).unwrap();
+            for count in counts {
+                write!(output, " {:>10}", count).unwrap();
+            }
+            output.push('\n');
+        }
+
+        Ok(output.into_bytes())
+    }
+}
@@ -11,9 +11,10 @@ use alloc::format;
 use crate::{
     fs::{
         procfs::template::{FileOps, ProcFileBuilder},
-        utils::Inode,
+        utils::{writeback, Inode},
     },
     prelude::*,
+    vm::swap,
 };
 
 /// Represents the inode at `/proc/meminfo`.
@@ -37,9 +38,15 @@ impl FileOps for MemInfoFileOps {
         let total = total / 1024;
         let available = available / 1024;
         let free = total - available;
+        // The memory waiting to be written back to the disks.
+        let dirty = writeback::nr_dirty_pages() * PAGE_SIZE / 1024;
+        // The swap space, in which the free part is not used by the swapped-out pages.
+        let swap_total = swap::nr_total_pages() * PAGE_SIZE / 1024;
+        let swap_free = swap::nr_free_pages() * PAGE_SIZE / 1024;
         let output = format!(
-            "MemTotal:\t{} kB\nMemFree:\t{} kB\nMemAvailable:\t{} kB\n",
-            total, free, available
+            "MemTotal:\t{} kB\nMemFree:\t{} kB\nMemAvailable:\t{} kB\nDirty:\t{} kB\n\
+             SwapTotal:\t{} kB\nSwapFree:\t{} kB\n",
+            total, free, available, dirty, swap_total, swap_free
         );
         Ok(output.into_bytes())
     }
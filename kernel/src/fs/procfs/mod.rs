@@ -4,13 +4,20 @@ use core::sync::atomic::{AtomicU64, Ordering};
 
 use self::{
     cpuinfo::CpuInfoFileOps,
+    interrupts::InterruptsFileOps,
     loadavg::LoadAvgFileOps,
     meminfo::MemInfoFileOps,
+    mounts::MountsSymOps,
+    net::NetDirOps,
     pid::PidDirOps,
     self_::SelfSymOps,
+    stat::StatFileOps,
     sys::SysDirOps,
+    sysvipc::SysvIpcDirOps,
     template::{DirOps, ProcDir, ProcDirBuilder, ProcSymBuilder, SymOps},
     thread_self::ThreadSelfSymOps,
+    uptime::UptimeFileOps,
+    version::VersionFileOps,
 };
 use crate::{
     events::Observer,
@@ -28,17 +35,29 @@ use crate::{
 
 mod cpuinfo;
 mod filesystems;
+mod interrupts;
 mod loadavg;
 mod meminfo;
+mod mounts;
+mod net;
 mod pid;
 mod self_;
+mod stat;
 mod sys;
+pub mod sysctl;
+mod sysvipc;
 mod template;
 mod thread_self;
+mod uptime;
+mod version;
+
+pub use pid::namespace_of_inode;
 
 pub(super) fn init() {
     let procfs_type = Arc::new(ProcFsType);
     super::registry::register(procfs_type).unwrap();
+
+    sys::init();
 }
 
 /// Magic number.
@@ -84,6 +103,10 @@ impl FileSystem for ProcFS {
     fn flags(&self) -> FsFlags {
         FsFlags::empty()
     }
+
+    fn name(&self) -> &'static str {
+        "proc"
+    }
 }
 
 struct ProcFsType;
@@ -141,6 +164,8 @@ impl DirOps for RootDirOps {
             SelfSymOps::new_inode(this_ptr.clone())
         } else if name == "sys" {
             SysDirOps::new_inode(this_ptr.clone())
+        } else if name == "sysvipc" {
+            SysvIpcDirOps::new_inode(this_ptr.clone())
         } else if name == "thread-self" {
             ThreadSelfSymOps::new_inode(this_ptr.clone())
         } else if name == "filesystems" {
@@ -151,6 +176,18 @@ impl DirOps for RootDirOps {
             LoadAvgFileOps::new_inode(this_ptr.clone())
         } else if name == "cpuinfo" {
             CpuInfoFileOps::new_inode(this_ptr.clone())
+        } else if name == "stat" {
+            StatFileOps::new_inode(this_ptr.clone())
+        } else if name == "uptime" {
+            UptimeFileOps::new_inode(this_ptr.clone())
+        } else if name == "interrupts" {
+            InterruptsFileOps::new_inode(this_ptr.clone())
+        } else if name == "version" {
+            VersionFileOps::new_inode(this_ptr.clone())
+        } else if name == "mounts" {
+            MountsSymOps::new_inode(this_ptr.clone())
+        } else if name == "net" {
+            NetDirOps::new_inode(this_ptr.clone())
         } else if let Ok(pid) = name.parse::<Pid>() {
             let process_ref =
                 process_table::get_process(pid).ok_or_else(|| Error::new(Errno::ENOENT))?;
@@ -172,6 +209,8 @@ impl DirOps for RootDirOps {
             ThreadSelfSymOps::new_inode(this_ptr.clone())
         });
         cached_children.put_entry_if_not_found("sys", || SysDirOps::new_inode(this_ptr.clone()));
+        cached_children
+            .put_entry_if_not_found("sysvipc", || SysvIpcDirOps::new_inode(this_ptr.clone()));
         cached_children.put_entry_if_not_found("filesystems", || {
             FileSystemsFileOps::new_inode(this_ptr.clone())
         });
@@ -181,6 +220,17 @@ impl DirOps for RootDirOps {
             .put_entry_if_not_found("loadavg", || LoadAvgFileOps::new_inode(this_ptr.clone()));
         cached_children
             .put_entry_if_not_found("cpuinfo", || CpuInfoFileOps::new_inode(this_ptr.clone()));
+        cached_children.put_entry_if_not_found("stat", || StatFileOps::new_inode(this_ptr.clone()));
+        cached_children
+            .put_entry_if_not_found("uptime", || UptimeFileOps::new_inode(this_ptr.clone()));
+        cached_children.put_entry_if_not_found("interrupts", || {
+            InterruptsFileOps::new_inode(this_ptr.clone())
+        });
+        cached_children
+            .put_entry_if_not_found("version", || VersionFileOps::new_inode(this_ptr.clone()));
+        cached_children
+            .put_entry_if_not_found("mounts", || MountsSymOps::new_inode(this_ptr.clone()));
+        cached_children.put_entry_if_not_found("net", || NetDirOps::new_inode(this_ptr.clone()));
         for process in process_table::process_table_mut().iter() {
             let pid = process.pid().to_string();
             cached_children.put_entry_if_not_found(&pid, || {
@@ -0,0 +1,66 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! This module offers `/proc/net/dev` file support, which tells the user space
+//! about the statistics of the network interfaces in the network namespace of
+//! the current thread.
+//!
+//! Reference: <https://man7.org/linux/man-pages/man5/proc_net.5.html>
+
+use core::fmt::Write;
+
+use crate::{
+    fs::{
+        procfs::template::{FileOps, ProcFileBuilder},
+        utils::Inode,
+    },
+    net::NetNamespace,
+    prelude::*,
+};
+
+/// Represents the inode at `/proc/net/dev`.
+pub struct DevFileOps;
+
+impl DevFileOps {
+    pub fn new_inode(parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        ProcFileBuilder::new(Self).parent(parent).build().unwrap()
+    }
+}
+
+impl FileOps for DevFileOps {
+    fn data(&self) -> Result<Vec<u8>> {
+        let mut output = String::from(concat!(
+            "Inter-|   Receive                                                |  Transmit\n",
+            " face |bytes    packets errs drop fifo frame compressed multicast",
+            "|bytes    packets errs drop fifo colls carrier compressed\n",
+        ));
+
+        // TODO: Count the errors, the dropped packets, and the multicast packets.
+        for iface in NetNamespace::current().ifaces() {
+            let stats = iface.stats();
+            writeln!(
+                output,
+                "{:>6}:{:>8} {:>7} {:>4} {:>4} {:>4} {:>5} {:>10} {:>9} {:>8} {:>7} {:>4} {:>4} {:>4} {:>5} {:>7} {:>10}",
+                iface.name(),
+                stats.rx_bytes,
+                stats.rx_packets,
+                0,
+                0,
+                0,
+                0,
+                0,
+                0,
+                stats.tx_bytes,
+                stats.tx_packets,
+                0,
+                0,
+                0,
+                0,
+                0,
+                0,
+            )
+            .unwrap();
+        }
+
+        Ok(output.into_bytes())
+    }
+}
@@ -0,0 +1,160 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! This module offers `/proc/net/tcp`, `/proc/net/udp`, `/proc/net/tcp6` and `/proc/net/udp6`
+//! file support, which list the TCP and UDP sockets in the network namespace of the current
+//! thread.
+//!
+//! Reference: <https://www.kernel.org/doc/html/latest/networking/proc_net_tcp.html>
+
+use alloc::format;
+use core::fmt::Write;
+
+use aster_bigtcp::{
+    socket::{SocketInfo, SocketInfoKind, TcpState},
+    wire::{IpAddress, IpEndpoint, Ipv4Address, Ipv6Address},
+};
+
+use super::ipv4_to_hex;
+use crate::{
+    fs::{
+        procfs::template::{FileOps, ProcFileBuilder},
+        utils::Inode,
+    },
+    net::{socket::ip::IpFamily, NetNamespace},
+    prelude::*,
+};
+
+/// The protocol of the sockets listed in an [`InetFileOps`] file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InetProtocol {
+    Tcp,
+    Udp,
+}
+
+/// Represents the inode at `/proc/net/tcp`, `/proc/net/udp`, `/proc/net/tcp6` or
+/// `/proc/net/udp6`.
+pub struct InetFileOps {
+    protocol: InetProtocol,
+    family: IpFamily,
+}
+
+impl InetFileOps {
+    pub fn new_inode(
+        protocol: InetProtocol,
+        family: IpFamily,
+        parent: Weak<dyn Inode>,
+    ) -> Arc<dyn Inode> {
+        ProcFileBuilder::new(Self { protocol, family })
+            .parent(parent)
+            .build()
+            .unwrap()
+    }
+}
+
+impl FileOps for InetFileOps {
+    fn data(&self) -> Result<Vec<u8>> {
+        let mut output = match (self.protocol, self.family) {
+            (InetProtocol::Tcp, IpFamily::Ipv4) => String::from(
+                "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode\n",
+            ),
+            (InetProtocol::Udp, IpFamily::Ipv4) => String::from(
+                "   sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode ref pointer drops\n",
+            ),
+            (InetProtocol::Tcp, IpFamily::Ipv6) => String::from(
+                "  sl  local_address                         remote_address                        st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode\n",
+            ),
+            (InetProtocol::Udp, IpFamily::Ipv6) => String::from(
+                "  sl  local_address                         remote_address                        st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode ref pointer drops\n",
+            ),
+        };
+
+        let socket_infos = NetNamespace::current()
+            .ifaces()
+            .iter()
+            .flat_map(|iface| iface.socket_infos())
+            .filter(|info| match info.kind {
+                SocketInfoKind::Tcp(_) => self.protocol == InetProtocol::Tcp,
+                SocketInfoKind::Udp => self.protocol == InetProtocol::Udp,
+            })
+            .filter(|info| match info.local_endpoint.addr {
+                IpAddress::Ipv4(_) => self.family == IpFamily::Ipv4,
+                IpAddress::Ipv6(_) => self.family == IpFamily::Ipv6,
+            })
+            .collect::<Vec<_>>();
+
+        // TODO: Report the timers, the owners, and the inodes of the sockets.
+        for (slot, info) in socket_infos.iter().enumerate() {
+            let unspecified_addr = match self.family {
+                IpFamily::Ipv4 => IpAddress::Ipv4(Ipv4Address::UNSPECIFIED),
+                IpFamily::Ipv6 => IpAddress::Ipv6(Ipv6Address::UNSPECIFIED),
+            };
+            let remote_endpoint = info
+                .remote_endpoint
+                .unwrap_or(IpEndpoint::new(unspecified_addr, 0));
+
+            // The slot number is right-aligned to a different width for each protocol.
+            let slot_width = match (self.protocol, self.family) {
+                (InetProtocol::Udp, IpFamily::Ipv4) => 5,
+                _ => 4,
+            };
+            write!(
+                output,
+                "{:>width$}: {} {} {:02X} {:08X}:{:08X} 00:00000000 00000000 {:>5} {:>8} {}",
+                slot,
+                endpoint_to_hex(&info.local_endpoint),
+                endpoint_to_hex(&remote_endpoint),
+                state_number(info),
+                info.send_queue_len,
+                info.recv_queue_len,
+                0,
+                0,
+                0,
+                width = slot_width,
+            )
+            .unwrap();
+            if self.protocol == InetProtocol::Udp {
+                output.push_str(" 2 0000000000000000 0");
+            }
+            output.push('\n');
+        }
+
+        Ok(output.into_bytes())
+    }
+}
+
+fn endpoint_to_hex(endpoint: &IpEndpoint) -> String {
+    let addr = match endpoint.addr {
+        IpAddress::Ipv4(addr) => ipv4_to_hex(addr),
+        // Linux prints an IPv6 address as four `__be32`s, each with `%08X`.
+        IpAddress::Ipv6(addr) => addr
+            .octets()
+            .chunks_exact(4)
+            .map(|word| ipv4_to_hex(Ipv4Address::new(word[0], word[1], word[2], word[3])))
+            .collect(),
+    };
+    format!("{}:{:04X}", addr, endpoint.port)
+}
+
+/// Returns the state number used by Linux (see `include/net/tcp_states.h`).
+fn state_number(info: &SocketInfo) -> u8 {
+    const TCP_CLOSE: u8 = 7;
+
+    let SocketInfoKind::Tcp(state) = info.kind else {
+        // A UDP socket that is not connected is reported as closed.
+        return TCP_CLOSE;
+    };
+
+    match state {
+        TcpState::Established => 1,
+        TcpState::SynSent => 2,
+        TcpState::SynReceived => 3,
+        TcpState::FinWait1 => 4,
+        TcpState::FinWait2 => 5,
+        TcpState::TimeWait => 6,
+        TcpState::Closed => TCP_CLOSE,
+        TcpState::CloseWait => 8,
+        TcpState::LastAck => 9,
+        TcpState::Listen => 10,
+        TcpState::Closing => 11,
+    }
+}
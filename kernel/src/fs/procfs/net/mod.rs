@@ -0,0 +1,83 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use alloc::format;
+
+use aster_bigtcp::wire::Ipv4Address;
+
+use self::{
+    dev::DevFileOps,
+    inet::{InetFileOps, InetProtocol},
+    pnp::PnpFileOps,
+    route::RouteFileOps,
+    unix::UnixFileOps,
+};
+use crate::{
+    fs::{
+        procfs::template::{DirOps, ProcDir, ProcDirBuilder},
+        utils::{DirEntryVecExt, Inode},
+    },
+    net::socket::ip::IpFamily,
+    prelude::*,
+};
+
+mod dev;
+mod inet;
+mod pnp;
+mod route;
+mod unix;
+
+/// Represents the inode at `/proc/net`.
+pub struct NetDirOps;
+
+impl NetDirOps {
+    pub fn new_inode(parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        ProcDirBuilder::new(Self).parent(parent).build().unwrap()
+    }
+}
+
+impl DirOps for NetDirOps {
+    fn lookup_child(&self, this_ptr: Weak<dyn Inode>, name: &str) -> Result<Arc<dyn Inode>> {
+        let inode = match name {
+            "dev" => DevFileOps::new_inode(this_ptr.clone()),
+            "pnp" => PnpFileOps::new_inode(this_ptr.clone()),
+            "route" => RouteFileOps::new_inode(this_ptr.clone()),
+            "tcp" => InetFileOps::new_inode(InetProtocol::Tcp, IpFamily::Ipv4, this_ptr.clone()),
+            "udp" => InetFileOps::new_inode(InetProtocol::Udp, IpFamily::Ipv4, this_ptr.clone()),
+            "tcp6" => InetFileOps::new_inode(InetProtocol::Tcp, IpFamily::Ipv6, this_ptr.clone()),
+            "udp6" => InetFileOps::new_inode(InetProtocol::Udp, IpFamily::Ipv6, this_ptr.clone()),
+            "unix" => UnixFileOps::new_inode(this_ptr.clone()),
+            _ => return_errno!(Errno::ENOENT),
+        };
+        Ok(inode)
+    }
+
+    fn populate_children(&self, this_ptr: Weak<dyn Inode>) {
+        let this = {
+            let this = this_ptr.upgrade().unwrap();
+            this.downcast_ref::<ProcDir<NetDirOps>>().unwrap().this()
+        };
+        let mut cached_children = this.cached_children().write();
+        cached_children.put_entry_if_not_found("dev", || DevFileOps::new_inode(this_ptr.clone()));
+        cached_children.put_entry_if_not_found("pnp", || PnpFileOps::new_inode(this_ptr.clone()));
+        cached_children
+            .put_entry_if_not_found("route", || RouteFileOps::new_inode(this_ptr.clone()));
+        cached_children.put_entry_if_not_found("tcp", || {
+            InetFileOps::new_inode(InetProtocol::Tcp, IpFamily::Ipv4, this_ptr.clone())
+        });
+        cached_children.put_entry_if_not_found("tcp6", || {
+            InetFileOps::new_inode(InetProtocol::Tcp, IpFamily::Ipv6, this_ptr.clone())
+        });
+        cached_children.put_entry_if_not_found("udp", || {
+            InetFileOps::new_inode(InetProtocol::Udp, IpFamily::Ipv4, this_ptr.clone())
+        });
+        cached_children.put_entry_if_not_found("udp6", || {
+            InetFileOps::new_inode(InetProtocol::Udp, IpFamily::Ipv6, this_ptr.clone())
+        });
+        cached_children.put_entry_if_not_found("unix", || UnixFileOps::new_inode(this_ptr.clone()));
+    }
+}
+
+/// Formats an IPv4 address in the way that Linux prints a `__be32` with `%08X`.
+fn ipv4_to_hex(addr: Ipv4Address) -> String {
+    format!("{:08X}", u32::from_le_bytes(addr.octets()))
+}
@@ -0,0 +1,47 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! This module offers `/proc/net/pnp` file support, which tells the user
+//! space about the configuration obtained by the in-kernel DHCP client.
+//!
+//! Its format is compatible with `/etc/resolv.conf`.
+//!
+//! Reference: <https://www.kernel.org/doc/html/v6.13/admin-guide/nfs/nfsroot.html>
+
+use core::fmt::Write;
+
+use crate::{
+    fs::{
+        procfs::template::{FileOps, ProcFileBuilder},
+        utils::Inode,
+    },
+    net::iface::dhcp_lease,
+    prelude::*,
+};
+
+/// Represents the inode at `/proc/net/pnp`.
+pub struct PnpFileOps;
+
+impl PnpFileOps {
+    pub fn new_inode(parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        ProcFileBuilder::new(Self).parent(parent).build().unwrap()
+    }
+}
+
+impl FileOps for PnpFileOps {
+    fn data(&self) -> Result<Vec<u8>> {
+        let mut output = String::new();
+
+        let Some(lease) = dhcp_lease() else {
+            writeln!(output, "#MANUAL").unwrap();
+            return Ok(output.into_bytes());
+        };
+
+        writeln!(output, "#PROTO: DHCP").unwrap();
+        for dns_server in lease.dns_servers.iter() {
+            writeln!(output, "nameserver {}", dns_server).unwrap();
+        }
+        writeln!(output, "bootserver {}", lease.server).unwrap();
+
+        Ok(output.into_bytes())
+    }
+}
@@ -0,0 +1,93 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! This module offers `/proc/net/route` file support, which tells the user
+//! space about the IPv4 routing table of the network namespace of the current
+//! thread.
+//!
+//! Reference: <https://man7.org/linux/man-pages/man5/proc_net.5.html>
+
+use alloc::format;
+use core::fmt::Write;
+
+use aster_bigtcp::{iface::InterfaceFlags, wire::Ipv4Address};
+
+use super::ipv4_to_hex;
+use crate::{
+    fs::{
+        procfs::template::{FileOps, ProcFileBuilder},
+        utils::Inode,
+    },
+    net::NetNamespace,
+    prelude::*,
+};
+
+/// Represents the inode at `/proc/net/route`.
+pub struct RouteFileOps;
+
+impl RouteFileOps {
+    pub fn new_inode(parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        ProcFileBuilder::new(Self).parent(parent).build().unwrap()
+    }
+}
+
+/// The route is usable.
+const RTF_UP: u16 = 0x0001;
+/// The destination is reached via a gateway.
+const RTF_GATEWAY: u16 = 0x0002;
+
+/// The width to which each line is padded, as Linux does.
+const LINE_WIDTH: usize = 127;
+
+impl FileOps for RouteFileOps {
+    fn data(&self) -> Result<Vec<u8>> {
+        let mut output = String::new();
+        writeln!(
+            output,
+            "{:<width$}",
+            "Iface\tDestination\tGateway \tFlags\tRefCnt\tUse\tMetric\tMask\t\tMTU\tWindow\tIRTT",
+            width = LINE_WIDTH,
+        )
+        .unwrap();
+
+        // Routes to the loopback interface belong to the local routing table, which is not
+        // listed here.
+        for iface in NetNamespace::current().ifaces() {
+            if iface.flags().contains(InterfaceFlags::LOOPBACK) {
+                continue;
+            }
+
+            let mut push_route =
+                |dest: Ipv4Address, gateway: Ipv4Address, flags: u16, mask: Ipv4Address| {
+                    let line = format!(
+                        "{}\t{}\t{}\t{:04X}\t0\t0\t0\t{}\t0\t0\t0",
+                        iface.name(),
+                        ipv4_to_hex(dest),
+                        ipv4_to_hex(gateway),
+                        flags,
+                        ipv4_to_hex(mask),
+                    );
+                    writeln!(output, "{:<width$}", line, width = LINE_WIDTH).unwrap();
+                };
+
+            for route in iface.ipv4_routes() {
+                push_route(
+                    route.cidr.address(),
+                    route.gateway,
+                    RTF_UP | RTF_GATEWAY,
+                    route.cidr.netmask(),
+                );
+            }
+
+            for cidr in iface.ipv4_cidrs() {
+                push_route(
+                    cidr.network().address(),
+                    Ipv4Address::UNSPECIFIED,
+                    RTF_UP,
+                    cidr.netmask(),
+                );
+            }
+        }
+
+        Ok(output.into_bytes())
+    }
+}
@@ -0,0 +1,61 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! This module offers `/proc/net/unix` file support, which lists the UNIX
+//! domain sockets.
+//!
+//! Reference: <https://man7.org/linux/man-pages/man5/proc_net.5.html>
+
+use core::fmt::Write;
+
+use crate::{
+    fs::{
+        procfs::template::{FileOps, ProcFileBuilder},
+        utils::Inode,
+    },
+    net::socket::unix::{self, UnixSocketAddr},
+    prelude::*,
+};
+
+/// Represents the inode at `/proc/net/unix`.
+pub struct UnixFileOps;
+
+impl UnixFileOps {
+    pub fn new_inode(parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        ProcFileBuilder::new(Self).parent(parent).build().unwrap()
+    }
+}
+
+/// The flag indicating that the socket is listening.
+const SO_ACCEPTCON: u32 = 1 << 16;
+/// The `SS_UNCONNECTED` socket state.
+const SS_UNCONNECTED: u8 = 1;
+
+impl FileOps for UnixFileOps {
+    fn data(&self) -> Result<Vec<u8>> {
+        let mut output = String::from("Num       RefCount Protocol Flags    Type St Inode Path\n");
+
+        // TODO: List the sockets that are not listening and report the inodes of the sockets.
+        for (addr, sock_type) in unix::listening_addrs() {
+            write!(
+                output,
+                "0000000000000000: {:08X} {:08X} {:08X} {:04X} {:02X} {:>5}",
+                2, 0, SO_ACCEPTCON, sock_type as i32, SS_UNCONNECTED, 0,
+            )
+            .unwrap();
+
+            match addr {
+                UnixSocketAddr::Unnamed => (),
+                UnixSocketAddr::Path(path) => write!(output, " {}", path).unwrap(),
+                UnixSocketAddr::Abstract(name) => {
+                    // Like Linux, the leading null byte and the embedded null bytes are shown
+                    // as `@`.
+                    let name = String::from_utf8_lossy(&name).replace('\0', "@");
+                    write!(output, " @{}", name).unwrap();
+                }
+            }
+            output.push('\n');
+        }
+
+        Ok(output.into_bytes())
+    }
+}
@@ -0,0 +1,52 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use crate::{
+    fs::{
+        procfs::template::{FileOps, ProcFileBuilder},
+        utils::{Inode, InodeMode},
+    },
+    prelude::*,
+    process::posix_thread::AsPosixThread,
+    Process,
+};
+
+/// Represents the inode at `/proc/[pid]/environ`.
+pub struct EnvironFileOps(Arc<Process>);
+
+impl EnvironFileOps {
+    pub fn new_inode(process_ref: Arc<Process>, parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        // Like Linux, the environment variables are only readable by the owner of the process.
+        let owner = process_ref
+            .main_thread()
+            .as_posix_thread()
+            .unwrap()
+            .credentials()
+            .euid();
+
+        let inode = ProcFileBuilder::new(Self(process_ref))
+            .parent(parent)
+            .mode(InodeMode::from_bits_truncate(0o400))
+            .build()
+            .unwrap();
+        inode.set_owner(owner).unwrap();
+        inode
+    }
+}
+
+impl FileOps for EnvironFileOps {
+    fn data(&self) -> Result<Vec<u8>> {
+        let environ_output = if self.0.status().is_zombie() {
+            // Returns 0 characters for zombie process.
+            Vec::new()
+        } else {
+            let Ok(envp_cstrs) = self.0.vm().init_stack_reader().envp() else {
+                return Ok(Vec::new());
+            };
+            envp_cstrs
+                .into_iter()
+                .flat_map(|c_str| c_str.into_bytes_with_nul().into_iter())
+                .collect()
+        };
+        Ok(environ_output)
+    }
+}
@@ -0,0 +1,53 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use crate::{
+    fs::{
+        procfs::{ProcSymBuilder, SymOps},
+        utils::Inode,
+    },
+    prelude::*,
+    process::posix_thread::AsPosixThread,
+    Process,
+};
+
+/// The kind of directories in the filesystem information of a process.
+#[derive(Clone, Copy)]
+pub enum FsLinkKind {
+    Cwd,
+    Root,
+}
+
+/// Represents the inode at `/proc/[pid]/cwd` or `/proc/[pid]/root`.
+pub struct FsLinkSymOps {
+    process_ref: Arc<Process>,
+    kind: FsLinkKind,
+}
+
+impl FsLinkSymOps {
+    pub fn new_inode(
+        process_ref: Arc<Process>,
+        kind: FsLinkKind,
+        parent: Weak<dyn Inode>,
+    ) -> Arc<dyn Inode> {
+        ProcSymBuilder::new(Self { process_ref, kind })
+            .parent(parent)
+            .build()
+            .unwrap()
+    }
+}
+
+impl SymOps for FsLinkSymOps {
+    fn read_link(&self) -> Result<String> {
+        let main_thread = self.process_ref.main_thread();
+        let Some(fs) = main_thread.as_posix_thread().unwrap().fs().lock().upgrade() else {
+            return_errno_with_message!(Errno::ENOENT, "the process has exited");
+        };
+
+        let fs_resolver = fs.resolver().read();
+        let path = match self.kind {
+            FsLinkKind::Cwd => fs_resolver.cwd(),
+            FsLinkKind::Root => fs_resolver.root(),
+        };
+        Ok(path.abs_path())
+    }
+}
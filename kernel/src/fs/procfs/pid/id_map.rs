@@ -0,0 +1,103 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use alloc::format;
+
+use ostd::task::Task;
+
+use crate::{
+    fs::{
+        procfs::template::{FileOps, ProcFileBuilder},
+        utils::{Inode, InodeMode},
+    },
+    prelude::*,
+    process::{
+        namespace::{IdMap, UserNamespace},
+        posix_thread::AsPosixThread,
+    },
+    Process,
+};
+
+/// The kind of IDs in a map.
+#[derive(Clone, Copy)]
+pub enum IdKind {
+    Uid,
+    Gid,
+}
+
+/// Represents the inode at `/proc/[pid]/uid_map` or `/proc/[pid]/gid_map`.
+pub struct IdMapFileOps {
+    process_ref: Arc<Process>,
+    kind: IdKind,
+}
+
+impl IdMapFileOps {
+    pub fn new_inode(
+        process_ref: Arc<Process>,
+        kind: IdKind,
+        parent: Weak<dyn Inode>,
+    ) -> Arc<dyn Inode> {
+        // Like Linux, the file is owned by the process, so that the creator of a user namespace
+        // can write the map without privileges.
+        let owner = process_ref
+            .main_thread()
+            .as_posix_thread()
+            .unwrap()
+            .credentials()
+            .euid();
+
+        let inode = ProcFileBuilder::new(Self { process_ref, kind })
+            .parent(parent)
+            .mode(InodeMode::from_bits_truncate(0o644))
+            .build()
+            .unwrap();
+        inode.set_owner(owner).unwrap();
+        inode
+    }
+
+    fn user_ns(&self) -> Result<Arc<UserNamespace>> {
+        let main_thread = self.process_ref.main_thread();
+        let ns_proxy = main_thread.as_posix_thread().unwrap().ns_proxy().lock();
+        let ns_proxy = ns_proxy
+            .as_ref()
+            .ok_or_else(|| Error::with_message(Errno::ESRCH, "the process has exited"))?;
+        Ok(ns_proxy.user_ns().clone())
+    }
+}
+
+impl FileOps for IdMapFileOps {
+    fn data(&self) -> Result<Vec<u8>> {
+        let user_ns = self.user_ns()?;
+        let map = match self.kind {
+            IdKind::Uid => user_ns.uid_map(),
+            IdKind::Gid => user_ns.gid_map(),
+        };
+
+        let mut output = String::new();
+        for extent in map.map(|map| map.extents()).unwrap_or_default() {
+            output.push_str(&format!(
+                "{:>10} {:>10} {:>10}\n",
+                extent.first, extent.lower_first, extent.count
+            ));
+        }
+        Ok(output.into_bytes())
+    }
+
+    fn write(&self, data: &[u8]) -> Result<()> {
+        let content = core::str::from_utf8(data)
+            .map_err(|_| Error::with_message(Errno::EINVAL, "the map is not valid UTF-8"))?;
+        let map = IdMap::parse(content)?;
+
+        let user_ns = self.user_ns()?;
+        let current_user_ns = {
+            let current = Task::current().unwrap();
+            let ns_proxy = current.as_thread_local().unwrap().borrow_ns_proxy();
+            ns_proxy.unwrap().user_ns().clone()
+        };
+        let credentials = current_thread!().as_posix_thread().unwrap().credentials();
+
+        match self.kind {
+            IdKind::Uid => user_ns.set_uid_map(map, &credentials, &current_user_ns),
+            IdKind::Gid => user_ns.set_gid_map(map, &credentials, &current_user_ns),
+        }
+    }
+}
@@ -0,0 +1,80 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use alloc::format;
+
+use crate::{
+    fs::{
+        procfs::template::{FileOps, ProcFileBuilder},
+        utils::Inode,
+    },
+    prelude::*,
+    process::ResourceType,
+    Process,
+};
+
+/// Represents the inode at `/proc/[pid]/limits`.
+pub struct LimitsFileOps(Arc<Process>);
+
+impl LimitsFileOps {
+    pub fn new_inode(process_ref: Arc<Process>, parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        ProcFileBuilder::new(Self(process_ref))
+            .parent(parent)
+            .build()
+            .unwrap()
+    }
+}
+
+impl FileOps for LimitsFileOps {
+    /// Reference: <https://elixir.bootlin.com/linux/v6.15/source/fs/proc/base.c#L623>
+    fn data(&self) -> Result<Vec<u8>> {
+        let resource_limits = self.0.resource_limits();
+
+        let mut output = format!(
+            "{:<25} {:<20} {:<20} {:<10}\n",
+            "Limit", "Soft Limit", "Hard Limit", "Units"
+        );
+        for (resource, name, unit) in LIMIT_DESCRIPTIONS {
+            let (cur, max) = resource_limits.get_rlimit(resource).get_cur_and_max();
+            output.push_str(&format!(
+                "{:<25} {:<20} {:<20} {:<10}\n",
+                name,
+                limit_to_string(cur),
+                limit_to_string(max),
+                unit
+            ));
+        }
+
+        Ok(output.into_bytes())
+    }
+}
+
+fn limit_to_string(limit: u64) -> String {
+    if limit == u64::MAX {
+        "unlimited".to_string()
+    } else {
+        limit.to_string()
+    }
+}
+
+const LIMIT_DESCRIPTIONS: [(ResourceType, &str, &str); 16] = [
+    (ResourceType::RLIMIT_CPU, "Max cpu time", "seconds"),
+    (ResourceType::RLIMIT_FSIZE, "Max file size", "bytes"),
+    (ResourceType::RLIMIT_DATA, "Max data size", "bytes"),
+    (ResourceType::RLIMIT_STACK, "Max stack size", "bytes"),
+    (ResourceType::RLIMIT_CORE, "Max core file size", "bytes"),
+    (ResourceType::RLIMIT_RSS, "Max resident set", "bytes"),
+    (ResourceType::RLIMIT_NPROC, "Max processes", "processes"),
+    (ResourceType::RLIMIT_NOFILE, "Max open files", "files"),
+    (ResourceType::RLIMIT_MEMLOCK, "Max locked memory", "bytes"),
+    (ResourceType::RLIMIT_AS, "Max address space", "bytes"),
+    (ResourceType::RLIMIT_LOCKS, "Max file locks", "locks"),
+    (
+        ResourceType::RLIMIT_SIGPENDING,
+        "Max pending signals",
+        "signals",
+    ),
+    (ResourceType::RLIMIT_MSGQUEUE, "Max msgqueue size", "bytes"),
+    (ResourceType::RLIMIT_NICE, "Max nice priority", ""),
+    (ResourceType::RLIMIT_RTPRIO, "Max realtime priority", ""),
+    (ResourceType::RLIMIT_RTTIME, "Max realtime timeout", "us"),
+];
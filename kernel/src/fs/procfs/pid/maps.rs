@@ -0,0 +1,96 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use alloc::format;
+
+use crate::{
+    fs::{
+        device::DeviceId,
+        procfs::template::{FileOps, ProcFileBuilder},
+        utils::Inode,
+    },
+    prelude::*,
+    vm::{
+        perms::VmPerms,
+        vmar::vm_mapping::{VmMapping, VmMappingName},
+    },
+    Process,
+};
+
+/// Represents the inode at `/proc/[pid]/maps`.
+pub struct MapsFileOps(Arc<Process>);
+
+impl MapsFileOps {
+    pub fn new_inode(process_ref: Arc<Process>, parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        ProcFileBuilder::new(Self(process_ref))
+            .parent(parent)
+            .build()
+            .unwrap()
+    }
+}
+
+impl FileOps for MapsFileOps {
+    /// Reference: <https://www.kernel.org/doc/html/latest/filesystems/proc.html#process-specific-subdirectories>
+    fn data(&self) -> Result<Vec<u8>> {
+        let root_vmar = self.0.lock_root_vmar();
+        let Some(vmar) = root_vmar.as_ref() else {
+            // Returns 0 characters for zombie process.
+            return Ok(Vec::new());
+        };
+
+        let mut output = String::new();
+        let query_guard = vmar.query(vmar.base()..vmar.base() + vmar.size());
+        for vm_mapping in query_guard.iter() {
+            output.push_str(&mapping_line(vm_mapping));
+            output.push('\n');
+        }
+
+        Ok(output.into_bytes())
+    }
+}
+
+/// Formats the line that describes a mapping in `/proc/[pid]/maps`, without the trailing
+/// newline.
+pub(super) fn mapping_line(vm_mapping: &VmMapping) -> String {
+    let perms = vm_mapping.perms();
+    let flag = |is_set: bool, ch: char| if is_set { ch } else { '-' };
+    let perms = [
+        flag(perms.contains(VmPerms::READ), 'r'),
+        flag(perms.contains(VmPerms::WRITE), 'w'),
+        flag(perms.contains(VmPerms::EXEC), 'x'),
+        if vm_mapping.is_shared() { 's' } else { 'p' },
+    ];
+
+    let (offset, device_id, ino, name) = match vm_mapping.name() {
+        Some(VmMappingName::File(path)) => {
+            let metadata = path.inode().metadata();
+            (
+                vm_mapping.vmo_offset().unwrap_or(0),
+                DeviceId::from_encoded_u64(metadata.dev),
+                metadata.ino,
+                path.abs_path(),
+            )
+        }
+        Some(VmMappingName::Special(name)) => {
+            (0, DeviceId::from_encoded_u64(0), 0, name.to_string())
+        }
+        None => (0, DeviceId::from_encoded_u64(0), 0, String::new()),
+    };
+
+    let mut line = format!(
+        "{:08x}-{:08x} {} {:08x} {:02x}:{:02x} {}",
+        vm_mapping.map_to_addr(),
+        vm_mapping.map_end(),
+        perms.iter().collect::<String>(),
+        offset,
+        device_id.major(),
+        device_id.minor(),
+        ino,
+    );
+    if !name.is_empty() {
+        // Like Linux, pads the line so that the names are aligned.
+        const NAME_COLUMN: usize = 73;
+        line.push_str(&" ".repeat(NAME_COLUMN.saturating_sub(line.len()).max(1)));
+        line.push_str(&name);
+    }
+    line
+}
@@ -1,8 +1,25 @@
 // SPDX-License-Identifier: MPL-2.0
 
+pub use self::ns::namespace_of_inode;
 use self::{
-    cmdline::CmdlineFileOps, comm::CommFileOps, exe::ExeSymOps, fd::FdDirOps, stat::StatFileOps,
-    status::StatusFileOps, task::TaskDirOps,
+    cmdline::CmdlineFileOps,
+    comm::CommFileOps,
+    environ::EnvironFileOps,
+    exe::ExeSymOps,
+    fd::FdDirOps,
+    fs_link::{FsLinkKind, FsLinkSymOps},
+    id_map::{IdKind, IdMapFileOps},
+    limits::LimitsFileOps,
+    maps::MapsFileOps,
+    mountinfo::MountInfoFileOps,
+    mounts::MountsFileOps,
+    ns::NsDirOps,
+    oom_score::OomScoreFileOps,
+    oom_score_adj::OomScoreAdjFileOps,
+    smaps::SmapsFileOps,
+    stat::StatFileOps,
+    status::StatusFileOps,
+    task::TaskDirOps,
 };
 use super::template::{DirOps, ProcDir, ProcDirBuilder};
 use crate::{
@@ -17,8 +34,19 @@ use crate::{
 
 mod cmdline;
 mod comm;
+mod environ;
 mod exe;
 mod fd;
+mod fs_link;
+mod id_map;
+mod limits;
+mod maps;
+mod mountinfo;
+mod mounts;
+mod ns;
+mod oom_score;
+mod oom_score_adj;
+mod smaps;
 mod stat;
 mod status;
 mod task;
@@ -72,6 +100,19 @@ impl DirOps for PidDirOps {
                 StatFileOps::new_inode(self.0.clone(), self.0.main_thread(), true, this_ptr.clone())
             }
             "task" => TaskDirOps::new_inode(self.0.clone(), this_ptr.clone()),
+            "ns" => NsDirOps::new_inode(self.0.clone(), this_ptr.clone()),
+            "uid_map" => IdMapFileOps::new_inode(self.0.clone(), IdKind::Uid, this_ptr.clone()),
+            "gid_map" => IdMapFileOps::new_inode(self.0.clone(), IdKind::Gid, this_ptr.clone()),
+            "mountinfo" => MountInfoFileOps::new_inode(self.0.clone(), this_ptr.clone()),
+            "mounts" => MountsFileOps::new_inode(self.0.clone(), this_ptr.clone()),
+            "maps" => MapsFileOps::new_inode(self.0.clone(), this_ptr.clone()),
+            "smaps" => SmapsFileOps::new_inode(self.0.clone(), this_ptr.clone()),
+            "environ" => EnvironFileOps::new_inode(self.0.clone(), this_ptr.clone()),
+            "limits" => LimitsFileOps::new_inode(self.0.clone(), this_ptr.clone()),
+            "cwd" => FsLinkSymOps::new_inode(self.0.clone(), FsLinkKind::Cwd, this_ptr.clone()),
+            "root" => FsLinkSymOps::new_inode(self.0.clone(), FsLinkKind::Root, this_ptr.clone()),
+            "oom_score" => OomScoreFileOps::new_inode(self.0.clone(), this_ptr.clone()),
+            "oom_score_adj" => OomScoreAdjFileOps::new_inode(self.0.clone(), this_ptr.clone()),
             _ => return_errno!(Errno::ENOENT),
         };
         Ok(inode)
@@ -104,5 +145,44 @@ impl DirOps for PidDirOps {
         cached_children.put_entry_if_not_found("task", || {
             TaskDirOps::new_inode(self.0.clone(), this_ptr.clone())
         });
+        cached_children.put_entry_if_not_found("ns", || {
+            NsDirOps::new_inode(self.0.clone(), this_ptr.clone())
+        });
+        cached_children.put_entry_if_not_found("uid_map", || {
+            IdMapFileOps::new_inode(self.0.clone(), IdKind::Uid, this_ptr.clone())
+        });
+        cached_children.put_entry_if_not_found("gid_map", || {
+            IdMapFileOps::new_inode(self.0.clone(), IdKind::Gid, this_ptr.clone())
+        });
+        cached_children.put_entry_if_not_found("mountinfo", || {
+            MountInfoFileOps::new_inode(self.0.clone(), this_ptr.clone())
+        });
+        cached_children.put_entry_if_not_found("mounts", || {
+            MountsFileOps::new_inode(self.0.clone(), this_ptr.clone())
+        });
+        cached_children.put_entry_if_not_found("maps", || {
+            MapsFileOps::new_inode(self.0.clone(), this_ptr.clone())
+        });
+        cached_children.put_entry_if_not_found("smaps", || {
+            SmapsFileOps::new_inode(self.0.clone(), this_ptr.clone())
+        });
+        cached_children.put_entry_if_not_found("environ", || {
+            EnvironFileOps::new_inode(self.0.clone(), this_ptr.clone())
+        });
+        cached_children.put_entry_if_not_found("limits", || {
+            LimitsFileOps::new_inode(self.0.clone(), this_ptr.clone())
+        });
+        cached_children.put_entry_if_not_found("cwd", || {
+            FsLinkSymOps::new_inode(self.0.clone(), FsLinkKind::Cwd, this_ptr.clone())
+        });
+        cached_children.put_entry_if_not_found("root", || {
+            FsLinkSymOps::new_inode(self.0.clone(), FsLinkKind::Root, this_ptr.clone())
+        });
+        cached_children.put_entry_if_not_found("oom_score", || {
+            OomScoreFileOps::new_inode(self.0.clone(), this_ptr.clone())
+        });
+        cached_children.put_entry_if_not_found("oom_score_adj", || {
+            OomScoreAdjFileOps::new_inode(self.0.clone(), this_ptr.clone())
+        });
     }
 }
@@ -0,0 +1,99 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use alloc::format;
+
+use crate::{
+    fs::{
+        device::DeviceId,
+        path::{MountNode, Path},
+        procfs::template::{FileOps, ProcFileBuilder},
+        utils::Inode,
+    },
+    prelude::*,
+    process::posix_thread::AsPosixThread,
+    Process,
+};
+
+/// Represents the inode at `/proc/[pid]/mountinfo`.
+pub struct MountInfoFileOps(Arc<Process>);
+
+impl MountInfoFileOps {
+    pub fn new_inode(process_ref: Arc<Process>, parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        ProcFileBuilder::new(Self(process_ref))
+            .parent(parent)
+            .build()
+            .unwrap()
+    }
+}
+
+impl FileOps for MountInfoFileOps {
+    /// Reference: <https://www.kernel.org/doc/html/latest/filesystems/proc.html#proc-pid-mountinfo-information-about-mounts>
+    fn data(&self) -> Result<Vec<u8>> {
+        let mut output = String::new();
+        for mount in mounts_of(&self.0) {
+            output.push_str(&mount_info_line(&mount));
+        }
+        Ok(output.into_bytes())
+    }
+}
+
+/// Returns the mounts in the mount namespace of the process, in the order they were mounted
+/// in each level of the mount tree.
+pub(super) fn mounts_of(process: &Process) -> Vec<Arc<MountNode>> {
+    let main_thread = process.main_thread();
+    let Some(ns_proxy) = main_thread
+        .as_posix_thread()
+        .unwrap()
+        .ns_proxy()
+        .lock()
+        .clone()
+    else {
+        return Vec::new();
+    };
+
+    let mut mounts = Vec::new();
+    let mut stack = vec![ns_proxy.mnt_ns().root()];
+    while let Some(mount) = stack.pop() {
+        let mut children = mount.children();
+        // Visit the children in the order they were mounted.
+        children.sort_by_key(|child| core::cmp::Reverse(child.id()));
+        stack.extend(children);
+
+        mounts.push(mount);
+    }
+    mounts
+}
+
+fn mount_info_line(mount: &Arc<MountNode>) -> String {
+    let parent_id = mount
+        .parent()
+        .and_then(|parent| parent.upgrade())
+        .map_or(mount.id(), |parent| parent.id());
+    let device_id = DeviceId::from_encoded_u64(mount.fs().root_inode().metadata().dev);
+    let mount_point = Path::new_fs_root(mount.clone()).abs_path();
+
+    let mut optional_fields = String::new();
+    if let Some(peer_group_id) = mount.peer_group_id() {
+        optional_fields.push_str(&format!(" shared:{}", peer_group_id));
+    }
+    if let Some(master_id) = mount.master_id() {
+        optional_fields.push_str(&format!(" master:{}", master_id));
+    }
+    if mount.is_unbindable() {
+        optional_fields.push_str(" unbindable");
+    }
+
+    let fs_name = mount.fs().name();
+    format!(
+        "{} {} {}:{} {} {} rw,relatime{} - {} {} rw\n",
+        mount.id(),
+        parent_id,
+        device_id.major(),
+        device_id.minor(),
+        mount.root_path_in_fs(),
+        mount_point,
+        optional_fields,
+        fs_name,
+        fs_name,
+    )
+}
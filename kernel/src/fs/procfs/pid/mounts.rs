@@ -0,0 +1,42 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use alloc::format;
+
+use super::mountinfo::mounts_of;
+use crate::{
+    fs::{
+        path::Path,
+        procfs::template::{FileOps, ProcFileBuilder},
+        utils::Inode,
+    },
+    prelude::*,
+    Process,
+};
+
+/// Represents the inode at `/proc/[pid]/mounts`.
+pub struct MountsFileOps(Arc<Process>);
+
+impl MountsFileOps {
+    pub fn new_inode(process_ref: Arc<Process>, parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        ProcFileBuilder::new(Self(process_ref))
+            .parent(parent)
+            .build()
+            .unwrap()
+    }
+}
+
+impl FileOps for MountsFileOps {
+    /// Reference: <https://man7.org/linux/man-pages/man5/fstab.5.html>
+    fn data(&self) -> Result<Vec<u8>> {
+        let mut output = String::new();
+        for mount in mounts_of(&self.0) {
+            let fs_name = mount.fs().name();
+            let mount_point = Path::new_fs_root(mount.clone()).abs_path();
+            output.push_str(&format!(
+                "{} {} {} rw,relatime 0 0\n",
+                fs_name, mount_point, fs_name
+            ));
+        }
+        Ok(output.into_bytes())
+    }
+}
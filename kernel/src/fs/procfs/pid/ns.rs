@@ -0,0 +1,154 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use alloc::format;
+
+use crate::{
+    fs::{
+        procfs::template::{
+            DirOps, FileOps, ProcDir, ProcDirBuilder, ProcFile, ProcFileBuilder, ProcSymBuilder,
+            SymOps,
+        },
+        utils::{DirEntryVecExt, Inode},
+    },
+    prelude::*,
+    process::{namespace::Namespace, posix_thread::AsPosixThread},
+    Process,
+};
+
+/// The names of the entries in `/proc/[pid]/ns`.
+const NS_ENTRY_NAMES: [&str; 7] = [
+    "ipc",
+    "mnt",
+    "net",
+    "pid",
+    "pid_for_children",
+    "user",
+    "uts",
+];
+
+/// Represents the inode at `/proc/[pid]/ns`.
+pub struct NsDirOps(Arc<Process>);
+
+impl NsDirOps {
+    pub fn new_inode(process_ref: Arc<Process>, parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        ProcDirBuilder::new(Self(process_ref))
+            .parent(parent)
+            .build()
+            .unwrap()
+    }
+
+    /// Returns the namespace referred to by the entry, or `None` if the process has exited.
+    fn namespace(&self, entry_name: &str) -> Option<Namespace> {
+        let main_thread = self.0.main_thread();
+        let ns_proxy = main_thread
+            .as_posix_thread()
+            .unwrap()
+            .ns_proxy()
+            .lock()
+            .clone()?;
+
+        let ns = match entry_name {
+            "ipc" => Namespace::Ipc(ns_proxy.ipc_ns().clone()),
+            "mnt" => Namespace::Mnt(ns_proxy.mnt_ns().clone()),
+            "net" => Namespace::Net(ns_proxy.net_ns().clone()),
+            "pid" => Namespace::Pid(self.0.pid_ns().clone()),
+            "pid_for_children" => Namespace::Pid(ns_proxy.pid_ns_for_children().clone()),
+            "user" => Namespace::User(ns_proxy.user_ns().clone()),
+            "uts" => Namespace::Uts(ns_proxy.uts_ns().clone()),
+            _ => return None,
+        };
+        Some(ns)
+    }
+}
+
+impl DirOps for NsDirOps {
+    fn lookup_child(&self, this_ptr: Weak<dyn Inode>, name: &str) -> Result<Arc<dyn Inode>> {
+        if let Some(ns) = self.namespace(name) {
+            return Ok(NsSymOps::new_inode(ns, this_ptr));
+        }
+
+        // The entries are symbolic links to hidden namespace files (e.g., `uts:[4026531838]`),
+        // which can be opened to refer to the namespaces.
+        for entry_name in NS_ENTRY_NAMES {
+            if let Some(ns) = self.namespace(entry_name)
+                && ns_file_name(&ns) == name
+            {
+                return Ok(NsFileOps::new_inode(ns, this_ptr));
+            }
+        }
+
+        return_errno!(Errno::ENOENT)
+    }
+
+    fn populate_children(&self, this_ptr: Weak<dyn Inode>) {
+        let this = {
+            let this = this_ptr.upgrade().unwrap();
+            this.downcast_ref::<ProcDir<NsDirOps>>().unwrap().this()
+        };
+        let mut cached_children = this.cached_children().write();
+
+        for entry_name in NS_ENTRY_NAMES {
+            let Some(ns) = self.namespace(entry_name) else {
+                return;
+            };
+            cached_children
+                .put_entry_if_not_found(entry_name, || NsSymOps::new_inode(ns, this_ptr.clone()));
+        }
+    }
+}
+
+fn ns_file_name(ns: &Namespace) -> String {
+    format!("{}:[{}]", ns.type_name(), ns.id())
+}
+
+/// Represents the inode at `/proc/[pid]/ns/[name]`.
+struct NsSymOps(Namespace);
+
+impl NsSymOps {
+    pub fn new_inode(ns: Namespace, parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        ProcSymBuilder::new(Self(ns))
+            .parent(parent)
+            .build()
+            .unwrap()
+    }
+}
+
+impl SymOps for NsSymOps {
+    fn read_link(&self) -> Result<String> {
+        Ok(ns_file_name(&self.0))
+    }
+}
+
+/// Represents the namespace file that a `/proc/[pid]/ns/[name]` link points to.
+///
+/// The inode number of the file is the ID of the namespace.
+pub struct NsFileOps(Namespace);
+
+impl NsFileOps {
+    pub fn new_inode(ns: Namespace, parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        let ino = ns.id();
+        ProcFileBuilder::new(Self(ns))
+            .parent(parent)
+            .ino(ino)
+            .build()
+            .unwrap()
+    }
+
+    /// Returns the namespace that the file refers to.
+    pub fn namespace(&self) -> &Namespace {
+        &self.0
+    }
+}
+
+impl FileOps for NsFileOps {
+    fn data(&self) -> Result<Vec<u8>> {
+        return_errno_with_message!(Errno::EINVAL, "namespace files cannot be read")
+    }
+}
+
+/// Returns the namespace that the inode refers to, if it is a namespace file.
+pub fn namespace_of_inode(inode: &dyn Inode) -> Option<Namespace> {
+    inode
+        .downcast_ref::<ProcFile<NsFileOps>>()
+        .map(|file| file.inner().namespace().clone())
+}
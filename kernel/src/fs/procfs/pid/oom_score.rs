@@ -0,0 +1,32 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use alloc::format;
+
+use crate::{
+    fs::{
+        procfs::template::{FileOps, ProcFileBuilder},
+        utils::{Inode, InodeMode},
+    },
+    prelude::*,
+    vm::oom,
+    Process,
+};
+
+/// Represents the inode at `/proc/[pid]/oom_score`.
+pub struct OomScoreFileOps(Arc<Process>);
+
+impl OomScoreFileOps {
+    pub fn new_inode(process_ref: Arc<Process>, parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        ProcFileBuilder::new(Self(process_ref))
+            .parent(parent)
+            .mode(InodeMode::from_bits_truncate(0o444))
+            .build()
+            .unwrap()
+    }
+}
+
+impl FileOps for OomScoreFileOps {
+    fn data(&self) -> Result<Vec<u8>> {
+        Ok(format!("{}\n", oom::oom_score(&self.0)).into_bytes())
+    }
+}
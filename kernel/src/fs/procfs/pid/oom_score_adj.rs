@@ -0,0 +1,39 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use alloc::format;
+
+use crate::{
+    fs::{
+        procfs::template::{FileOps, ProcFileBuilder},
+        utils::{Inode, InodeMode},
+    },
+    prelude::*,
+    Process,
+};
+
+/// Represents the inode at `/proc/[pid]/oom_score_adj`.
+pub struct OomScoreAdjFileOps(Arc<Process>);
+
+impl OomScoreAdjFileOps {
+    pub fn new_inode(process_ref: Arc<Process>, parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        ProcFileBuilder::new(Self(process_ref))
+            .parent(parent)
+            .mode(InodeMode::from_bits_truncate(0o644))
+            .build()
+            .unwrap()
+    }
+}
+
+impl FileOps for OomScoreAdjFileOps {
+    fn data(&self) -> Result<Vec<u8>> {
+        Ok(format!("{}\n", self.0.oom_score_adj()).into_bytes())
+    }
+
+    fn write(&self, data: &[u8]) -> Result<()> {
+        let oom_score_adj = core::str::from_utf8(data)
+            .ok()
+            .and_then(|content| content.trim().parse::<i32>().ok())
+            .ok_or_else(|| Error::with_message(Errno::EINVAL, "the value is not an integer"))?;
+        self.0.set_oom_score_adj(oom_score_adj)
+    }
+}
@@ -0,0 +1,158 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use alloc::format;
+use core::fmt::Write;
+
+use ostd::{
+    mm::{PageFlags, VmSpace},
+    task::disable_preempt,
+};
+
+use super::maps::mapping_line;
+use crate::{
+    fs::{
+        procfs::template::{FileOps, ProcFileBuilder},
+        utils::Inode,
+    },
+    prelude::*,
+    vm::{perms::VmPerms, vmar::vm_mapping::VmMapping},
+    Process,
+};
+
+/// Represents the inode at `/proc/[pid]/smaps`.
+pub struct SmapsFileOps(Arc<Process>);
+
+impl SmapsFileOps {
+    pub fn new_inode(process_ref: Arc<Process>, parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        ProcFileBuilder::new(Self(process_ref))
+            .parent(parent)
+            .build()
+            .unwrap()
+    }
+}
+
+impl FileOps for SmapsFileOps {
+    /// Reference: <https://www.kernel.org/doc/html/latest/filesystems/proc.html#process-specific-subdirectories>
+    fn data(&self) -> Result<Vec<u8>> {
+        let root_vmar = self.0.lock_root_vmar();
+        let Some(vmar) = root_vmar.as_ref() else {
+            // Returns 0 characters for zombie process.
+            return Ok(Vec::new());
+        };
+
+        let mut output = String::new();
+        let query_guard = vmar.query(vmar.base()..vmar.base() + vmar.size());
+        for vm_mapping in query_guard.iter() {
+            let usage = MappingUsage::collect(vm_mapping, vmar.vm_space())?;
+
+            writeln!(output, "{}", mapping_line(vm_mapping)).unwrap();
+            let mut write_field = |name: &str, bytes: usize| {
+                writeln!(output, "{:<16}{:>8} kB", name, bytes / 1024).unwrap();
+            };
+            write_field("Size:", vm_mapping.map_size());
+            write_field("KernelPageSize:", PAGE_SIZE);
+            write_field("MMUPageSize:", PAGE_SIZE);
+            write_field("Rss:", usage.rss);
+            write_field("Pss:", usage.pss);
+            write_field("Pss_Dirty:", usage.pss_dirty);
+            write_field("Shared_Clean:", usage.shared_clean);
+            write_field("Shared_Dirty:", usage.shared_dirty);
+            write_field("Private_Clean:", usage.private_clean);
+            write_field("Private_Dirty:", usage.private_dirty);
+            write_field("Referenced:", usage.referenced);
+            write_field("Anonymous:", usage.anonymous);
+            write_field("LazyFree:", 0);
+            write_field("AnonHugePages:", 0);
+            write_field("ShmemPmdMapped:", 0);
+            write_field("FilePmdMapped:", 0);
+            write_field("Shared_Hugetlb:", 0);
+            write_field("Private_Hugetlb:", 0);
+            write_field("Swap:", 0);
+            write_field("SwapPss:", 0);
+            write_field("Locked:", 0);
+            writeln!(output, "THPeligible:    0").unwrap();
+            writeln!(output, "VmFlags: {}", vm_flags(vm_mapping)).unwrap();
+        }
+
+        Ok(output.into_bytes())
+    }
+}
+
+/// The memory usage of a mapping in bytes.
+#[derive(Default)]
+struct MappingUsage {
+    rss: usize,
+    pss: usize,
+    pss_dirty: usize,
+    shared_clean: usize,
+    shared_dirty: usize,
+    private_clean: usize,
+    private_dirty: usize,
+    referenced: usize,
+    anonymous: usize,
+}
+
+impl MappingUsage {
+    /// Collects the usage by walking through the pages of the mapping that are mapped in the
+    /// page table.
+    fn collect(vm_mapping: &VmMapping, vm_space: &VmSpace) -> Result<Self> {
+        let mut usage = Self::default();
+        let is_anonymous = vm_mapping.vmo().is_none();
+
+        let preempt_guard = disable_preempt();
+        let range = vm_mapping.map_to_addr()..vm_mapping.map_end();
+        let cursor = vm_space.cursor(&preempt_guard, &range)?;
+        for (_, item) in cursor {
+            let Some((frame, prop)) = item else {
+                continue;
+            };
+
+            // The number of the page tables that map the frame is approximated by the
+            // reference count of the frame. It excludes the handle that we are holding now,
+            // and the reference held by the VMO (e.g., the page cache), if any.
+            let map_count = {
+                let holders = 1 + (!is_anonymous) as u64;
+                frame.reference_count().saturating_sub(holders).max(1) as usize
+            };
+            let is_dirty = prop.flags.contains(PageFlags::DIRTY);
+
+            usage.rss += PAGE_SIZE;
+            usage.pss += PAGE_SIZE / map_count;
+            if is_dirty {
+                usage.pss_dirty += PAGE_SIZE / map_count;
+            }
+            match (map_count > 1, is_dirty) {
+                (true, true) => usage.shared_dirty += PAGE_SIZE,
+                (true, false) => usage.shared_clean += PAGE_SIZE,
+                (false, true) => usage.private_dirty += PAGE_SIZE,
+                (false, false) => usage.private_clean += PAGE_SIZE,
+            }
+            if prop.flags.contains(PageFlags::ACCESSED) {
+                usage.referenced += PAGE_SIZE;
+            }
+            if is_anonymous {
+                usage.anonymous += PAGE_SIZE;
+            }
+        }
+
+        Ok(usage)
+    }
+}
+
+fn vm_flags(vm_mapping: &VmMapping) -> String {
+    let perms = vm_mapping.perms();
+    let mut flags = Vec::new();
+    if perms.contains(VmPerms::READ) {
+        flags.push("rd");
+    }
+    if perms.contains(VmPerms::WRITE) {
+        flags.push("wr");
+    }
+    if perms.contains(VmPerms::EXEC) {
+        flags.push("ex");
+    }
+    if vm_mapping.is_shared() {
+        flags.push("sh");
+    }
+    flags.join(" ")
+}
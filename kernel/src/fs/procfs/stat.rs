@@ -0,0 +1,107 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! This module offers `/proc/stat` file support, which tells the user space
+//! about the kernel and system statistics since the system booted.
+//!
+//! Reference: <https://www.man7.org/linux/man-pages/man5/proc_stat.5.html>
+
+use alloc::format;
+use core::fmt::Write;
+
+use ostd::{cpu::all_cpus, trap::irq};
+
+use crate::{
+    fs::{
+        procfs::template::{FileOps, ProcFileBuilder},
+        utils::Inode,
+    },
+    prelude::*,
+    process::posix_thread,
+    sched::{
+        self,
+        cpu_time::{self, CpuTime},
+    },
+    time::{
+        clocks::{MonotonicClock, RealTimeClock},
+        Clock,
+    },
+};
+
+/// Represents the inode at `/proc/stat`.
+pub struct StatFileOps;
+
+impl StatFileOps {
+    pub fn new_inode(parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        ProcFileBuilder::new(Self).parent(parent).build().unwrap()
+    }
+}
+
+impl FileOps for StatFileOps {
+    fn data(&self) -> Result<Vec<u8>> {
+        let mut output = String::new();
+
+        // The times are reported in jiffies, which are used as the clock ticks
+        // (see also `/proc/[pid]/stat`).
+        writeln!(
+            output,
+            "cpu  {}",
+            cpu_time_fields(&cpu_time::total_cpu_time())
+        )
+        .unwrap();
+        for cpu in all_cpus() {
+            writeln!(
+                output,
+                "cpu{} {}",
+                cpu.as_usize(),
+                cpu_time_fields(&cpu_time::cpu_time_on(cpu))
+            )
+            .unwrap();
+        }
+
+        // The IRQ numbers below the allocatable range are never triggered.
+        let irq_counts = (0..=*irq::irq_nums().end())
+            .map(|irq_num| {
+                if irq::irq_nums().contains(&irq_num) {
+                    all_cpus()
+                        .map(|cpu| irq::irq_count_on_cpu(irq_num, cpu))
+                        .sum()
+                } else {
+                    0
+                }
+            })
+            .collect::<Vec<usize>>();
+        write!(output, "intr {}", irq_counts.iter().sum::<usize>()).unwrap();
+        for count in irq_counts {
+            write!(output, " {}", count).unwrap();
+        }
+        output.push('\n');
+
+        // TODO: Count the context switches.
+        writeln!(output, "ctxt 0").unwrap();
+
+        let boot_time = RealTimeClock::get()
+            .read_time()
+            .saturating_sub(MonotonicClock::get().read_time());
+        writeln!(output, "btime {}", boot_time.as_secs()).unwrap();
+
+        // Each process or thread takes a new TID, so the last allocated TID
+        // approximates the number of forks since boot.
+        writeln!(output, "processes {}", posix_thread::last_tid()).unwrap();
+
+        let (nr_queued, nr_running) = sched::nr_queued_and_running();
+        writeln!(output, "procs_running {}", nr_queued + nr_running).unwrap();
+        // TODO: Count the tasks blocked on I/O.
+        writeln!(output, "procs_blocked 0").unwrap();
+
+        Ok(output.into_bytes())
+    }
+}
+
+/// Formats the fields of a `cpu` line, i.e., `user nice system idle iowait irq
+/// softirq steal guest guest_nice`.
+fn cpu_time_fields(cpu_time: &CpuTime) -> String {
+    format!(
+        "{} {} {} {} 0 0 0 0 0 0",
+        cpu_time.user, cpu_time.nice, cpu_time.system, cpu_time.idle
+    )
+}
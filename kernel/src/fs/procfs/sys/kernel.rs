@@ -0,0 +1,106 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! This module registers the sysctls under `/proc/sys/kernel`.
+//!
+//! Reference: <https://docs.kernel.org/admin-guide/sysctl/kernel.html>
+
+use log::LevelFilter;
+use ostd::task::Task;
+
+use crate::{
+    fs::procfs::{
+        sysctl::{self, IntSysctl, StrSysctl, SysctlEntry},
+        version::field_to_str,
+    },
+    prelude::*,
+    process::{
+        credentials::capabilities::CapSet,
+        namespace::{UtsNamespace, UTS_FIELD_LEN},
+        posix_thread::PID_MAX,
+    },
+};
+
+pub(super) fn init() {
+    // The console log level starts with the level specified in the kernel
+    // command line.
+    PRINTK.lock()[0] = match log::max_level() {
+        LevelFilter::Off => 0,
+        LevelFilter::Error => 4,
+        LevelFilter::Warn => 5,
+        LevelFilter::Info => 7,
+        LevelFilter::Debug => 8,
+        LevelFilter::Trace => 9,
+    };
+
+    let entries = vec![
+        SysctlEntry::read_only(
+            "cap_last_cap",
+            IntSysctl::new(
+                i64::MIN..=i64::MAX,
+                || [CapSet::most_significant_bit() as i64],
+                |_| {},
+            ),
+        ),
+        SysctlEntry::read_only(
+            "pid_max",
+            IntSysctl::new(i64::MIN..=i64::MAX, || [PID_MAX as i64], |_| {}),
+        ),
+        SysctlEntry::writable(
+            "hostname",
+            StrSysctl::new(
+                UTS_FIELD_LEN - 1,
+                || field_to_str(&current_uts_ns().uts_name().nodename).to_string(),
+                |name| current_uts_ns().set_hostname(name.as_bytes()),
+            ),
+            CapSet::SYS_ADMIN,
+        ),
+        SysctlEntry::writable(
+            "domainname",
+            StrSysctl::new(
+                UTS_FIELD_LEN - 1,
+                || field_to_str(&current_uts_ns().uts_name().domainname).to_string(),
+                |name| current_uts_ns().set_domainname(name.as_bytes()),
+            ),
+            CapSet::SYS_ADMIN,
+        ),
+        SysctlEntry::writable(
+            "printk",
+            IntSysctl::new(0..=15, || *PRINTK.lock(), set_printk),
+            CapSet::SYS_ADMIN,
+        ),
+    ];
+
+    sysctl::register("kernel", entries).unwrap();
+}
+
+/// Returns the UTS namespace of the current thread.
+///
+/// Like Linux, the host name and the NIS domain name under `/proc/sys/kernel`
+/// are those in the UTS namespace of the accessing thread.
+fn current_uts_ns() -> Arc<UtsNamespace> {
+    let current = Task::current().unwrap();
+    let ns_proxy = current.as_thread_local().unwrap().borrow_ns_proxy();
+    ns_proxy.unwrap().uts_ns().clone()
+}
+
+/// The values of `kernel.printk`, i.e., the console log level, the default
+/// message log level, the minimum console log level, and the default console
+/// log level.
+static PRINTK: SpinLock<[i64; 4]> = SpinLock::new([7, 4, 1, 7]);
+
+fn set_printk(values: [i64; 4]) {
+    // A message is printed if its level is less than the console log level.
+    // `KERN_NOTICE` (5) has no counterpart in the `log` crate, so the console
+    // log level 6 behaves the same as 5.
+    let level_filter = match values[0] {
+        ..=3 => LevelFilter::Off,
+        4 => LevelFilter::Error,
+        5 | 6 => LevelFilter::Warn,
+        7 => LevelFilter::Info,
+        8 => LevelFilter::Debug,
+        _ => LevelFilter::Trace,
+    };
+    log::set_max_level(level_filter);
+
+    *PRINTK.lock() = values;
+}
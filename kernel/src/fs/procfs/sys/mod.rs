@@ -1,32 +1,48 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use self::kernel::KernelDirOps;
+//! This module offers the inodes under `/proc/sys`, which are generated from
+//! the sysctls registered in [`super::sysctl`].
+
+use super::sysctl::{self, SysctlDir, SysctlEntry, SysctlNode};
 use crate::{
     fs::{
-        procfs::template::{DirOps, ProcDir, ProcDirBuilder},
-        utils::{DirEntryVecExt, Inode},
+        procfs::template::{DirOps, FileOps, ProcDir, ProcDirBuilder, ProcFileBuilder},
+        utils::{DirEntryVecExt, Inode, InodeMode},
     },
     prelude::*,
 };
 
 mod kernel;
 
-/// Represents the inode at `/proc/sys`.
-pub struct SysDirOps;
+pub(super) fn init() {
+    kernel::init();
+}
+
+/// Represents the inode at `/proc/sys` or a directory under it.
+pub struct SysDirOps {
+    dir: Arc<SysctlDir>,
+}
 
 impl SysDirOps {
+    /// Creates the inode at `/proc/sys`.
     pub fn new_inode(parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
-        ProcDirBuilder::new(Self).parent(parent).build().unwrap()
+        Self::new_inode_for(sysctl::root().clone(), parent)
+    }
+
+    fn new_inode_for(dir: Arc<SysctlDir>, parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        ProcDirBuilder::new(Self { dir })
+            .parent(parent)
+            .build()
+            .unwrap()
     }
 }
 
 impl DirOps for SysDirOps {
     fn lookup_child(&self, this_ptr: Weak<dyn Inode>, name: &str) -> Result<Arc<dyn Inode>> {
-        let inode = match name {
-            "kernel" => KernelDirOps::new_inode(this_ptr.clone()),
-            _ => return_errno!(Errno::ENOENT),
+        let Some(node) = self.dir.lookup(name) else {
+            return_errno!(Errno::ENOENT);
         };
-        Ok(inode)
+        Ok(new_node_inode(node, this_ptr))
     }
 
     fn populate_children(&self, this_ptr: Weak<dyn Inode>) {
@@ -35,7 +51,42 @@ impl DirOps for SysDirOps {
             this.downcast_ref::<ProcDir<SysDirOps>>().unwrap().this()
         };
         let mut cached_children = this.cached_children().write();
-        cached_children
-            .put_entry_if_not_found("kernel", || KernelDirOps::new_inode(this_ptr.clone()))
+        for (name, node) in self.dir.children() {
+            cached_children
+                .put_entry_if_not_found(&name, || new_node_inode(node.clone(), this_ptr.clone()));
+        }
+    }
+}
+
+fn new_node_inode(node: SysctlNode, parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+    match node {
+        SysctlNode::Dir(dir) => SysDirOps::new_inode_for(dir, parent),
+        SysctlNode::Entry(entry) => SysctlFileOps::new_inode(entry, parent),
+    }
+}
+
+/// Represents a sysctl file under `/proc/sys`.
+struct SysctlFileOps {
+    entry: Arc<SysctlEntry>,
+}
+
+impl SysctlFileOps {
+    fn new_inode(entry: Arc<SysctlEntry>, parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        let mode = if entry.is_writable() { 0o644 } else { 0o444 };
+        ProcFileBuilder::new(Self { entry })
+            .parent(parent)
+            .mode(InodeMode::from_bits_truncate(mode))
+            .build()
+            .unwrap()
+    }
+}
+
+impl FileOps for SysctlFileOps {
+    fn data(&self) -> Result<Vec<u8>> {
+        Ok(self.entry.read().into_bytes())
+    }
+
+    fn write(&self, data: &[u8]) -> Result<()> {
+        self.entry.write(data)
     }
 }
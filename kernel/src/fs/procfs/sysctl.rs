@@ -0,0 +1,267 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The registry of sysctls, i.e., the tunable kernel parameters under `/proc/sys`.
+//!
+//! A subsystem exposes its sysctls by calling [`register`] with the directory
+//! (e.g., `"net/ipv4"`) and the entries in the directory. Each entry has a typed
+//! value, which parses and validates the data written by the user space, and
+//! optionally a capability that a thread must have to write the entry.
+//!
+//! Reference: <https://docs.kernel.org/admin-guide/sysctl/index.html>
+
+use alloc::collections::btree_map::BTreeMap;
+use core::ops::RangeInclusive;
+
+use spin::Once;
+
+use crate::{
+    prelude::*,
+    process::{credentials::capabilities::CapSet, posix_thread::AsPosixThread},
+};
+
+/// Registers the sysctl entries in the directory at `path`.
+///
+/// The path is relative to `/proc/sys` and its components are separated by
+/// `/`. Any missing directories on the path are created.
+///
+/// If an entry conflicts with an existing entry or directory, this method
+/// fails with `EEXIST` and none of the entries are registered.
+pub fn register(path: &str, entries: Vec<SysctlEntry>) -> Result<()> {
+    let mut dir = root().clone();
+    for name in path.split('/').filter(|name| !name.is_empty()) {
+        dir = dir.lookup_or_create_dir(name)?;
+    }
+
+    let mut children = dir.children.write();
+    for (idx, entry) in entries.iter().enumerate() {
+        if children.contains_key(entry.name)
+            || entries[..idx].iter().any(|prev| prev.name == entry.name)
+        {
+            return_errno_with_message!(Errno::EEXIST, "the sysctl entry already exists");
+        }
+    }
+    for entry in entries {
+        children.insert(entry.name.to_string(), SysctlNode::Entry(Arc::new(entry)));
+    }
+
+    Ok(())
+}
+
+/// A sysctl entry, i.e., a file under `/proc/sys`.
+pub struct SysctlEntry {
+    name: &'static str,
+    value: Box<dyn SysctlValue>,
+    write_cap: Option<CapSet>,
+}
+
+impl SysctlEntry {
+    /// Creates a read-only entry.
+    pub fn read_only(name: &'static str, value: impl SysctlValue) -> Self {
+        Self {
+            name,
+            value: Box::new(value),
+            write_cap: None,
+        }
+    }
+
+    /// Creates an entry that can be written by the threads with `write_cap`.
+    pub fn writable(name: &'static str, value: impl SysctlValue, write_cap: CapSet) -> Self {
+        Self {
+            name,
+            value: Box::new(value),
+            write_cap: Some(write_cap),
+        }
+    }
+
+    /// Returns whether the entry is writable.
+    pub fn is_writable(&self) -> bool {
+        self.write_cap.is_some()
+    }
+
+    /// Reads the value of the entry as the content of the file.
+    pub fn read(&self) -> String {
+        let mut output = self.value.read();
+        output.push('\n');
+        output
+    }
+
+    /// Writes the value of the entry on behalf of the current thread.
+    pub fn write(&self, data: &[u8]) -> Result<()> {
+        let Some(write_cap) = self.write_cap else {
+            return_errno_with_message!(Errno::EPERM, "the sysctl entry is read-only");
+        };
+
+        let credentials = {
+            let current = current_thread!();
+            current.as_posix_thread().unwrap().credentials()
+        };
+        if !credentials.effective_capset().contains(write_cap) {
+            return_errno_with_message!(
+                Errno::EPERM,
+                "the thread does not have the capability to write the sysctl entry"
+            );
+        }
+
+        let input = core::str::from_utf8(data)
+            .map_err(|_| Error::with_message(Errno::EINVAL, "the value is not valid UTF-8"))?;
+        self.value.write(input)
+    }
+}
+
+/// The value of a sysctl entry.
+pub trait SysctlValue: Send + Sync + 'static {
+    /// Formats the value, without the trailing newline.
+    fn read(&self) -> String;
+
+    /// Parses the input and updates the value.
+    fn write(&self, input: &str) -> Result<()>;
+}
+
+/// A sysctl value consisting of `N` integers, each of which is within a range.
+///
+/// The integers are separated by tabs when read and by whitespace when
+/// written. Like Linux, if fewer than `N` integers are written, the remaining
+/// integers keep their values.
+pub struct IntSysctl<const N: usize> {
+    range: RangeInclusive<i64>,
+    get: fn() -> [i64; N],
+    set: fn([i64; N]),
+    check: Option<fn(&[i64; N]) -> Result<()>>,
+}
+
+impl<const N: usize> IntSysctl<N> {
+    /// Creates a value that is accessed via `get` and `set`.
+    pub const fn new(range: RangeInclusive<i64>, get: fn() -> [i64; N], set: fn([i64; N])) -> Self {
+        Self {
+            range,
+            get,
+            set,
+            check: None,
+        }
+    }
+
+    /// Sets the function that checks the relations between the integers before they are set.
+    pub const fn check(mut self, check: fn(&[i64; N]) -> Result<()>) -> Self {
+        self.check = Some(check);
+        self
+    }
+}
+
+impl<const N: usize> SysctlValue for IntSysctl<N> {
+    fn read(&self) -> String {
+        (self.get)().map(|value| value.to_string()).join("\t")
+    }
+
+    fn write(&self, input: &str) -> Result<()> {
+        let mut values = (self.get)();
+
+        let mut nr_values = 0;
+        for word in input.split_ascii_whitespace() {
+            if nr_values == N {
+                return_errno_with_message!(Errno::EINVAL, "too many integers are written");
+            }
+            let value = word
+                .parse::<i64>()
+                .map_err(|_| Error::with_message(Errno::EINVAL, "the integer is invalid"))?;
+            if !self.range.contains(&value) {
+                return_errno_with_message!(Errno::EINVAL, "the integer is out of range");
+            }
+            values[nr_values] = value;
+            nr_values += 1;
+        }
+        if nr_values == 0 {
+            return_errno_with_message!(Errno::EINVAL, "no integer is written");
+        }
+        if let Some(check) = self.check {
+            check(&values)?;
+        }
+
+        (self.set)(values);
+        Ok(())
+    }
+}
+
+/// A sysctl value consisting of a string of a limited length.
+///
+/// Like Linux, only the characters before the first newline are written.
+pub struct StrSysctl {
+    max_len: usize,
+    get: fn() -> String,
+    set: fn(&str) -> Result<()>,
+}
+
+impl StrSysctl {
+    /// Creates a value that is accessed via `get` and `set`.
+    pub const fn new(max_len: usize, get: fn() -> String, set: fn(&str) -> Result<()>) -> Self {
+        Self { max_len, get, set }
+    }
+}
+
+impl SysctlValue for StrSysctl {
+    fn read(&self) -> String {
+        (self.get)()
+    }
+
+    fn write(&self, input: &str) -> Result<()> {
+        let value = input.split('\n').next().unwrap();
+        if value.len() > self.max_len {
+            return_errno_with_message!(Errno::EINVAL, "the string is too long");
+        }
+
+        (self.set)(value)
+    }
+}
+
+/// A directory of sysctl entries, i.e., a directory under `/proc/sys`.
+pub(super) struct SysctlDir {
+    children: RwLock<BTreeMap<String, SysctlNode>>,
+}
+
+/// A node in a [`SysctlDir`].
+#[derive(Clone)]
+pub(super) enum SysctlNode {
+    Dir(Arc<SysctlDir>),
+    Entry(Arc<SysctlEntry>),
+}
+
+impl SysctlDir {
+    fn new() -> Self {
+        Self {
+            children: RwLock::new(BTreeMap::new()),
+        }
+    }
+
+    /// Looks up a child node by its name.
+    pub(super) fn lookup(&self, name: &str) -> Option<SysctlNode> {
+        self.children.read().get(name).cloned()
+    }
+
+    /// Returns the names and nodes of all children.
+    pub(super) fn children(&self) -> Vec<(String, SysctlNode)> {
+        let children = self.children.read();
+        children
+            .iter()
+            .map(|(name, node)| (name.clone(), node.clone()))
+            .collect()
+    }
+
+    fn lookup_or_create_dir(&self, name: &str) -> Result<Arc<SysctlDir>> {
+        let mut children = self.children.write();
+        let node = children
+            .entry(name.to_string())
+            .or_insert_with(|| SysctlNode::Dir(Arc::new(SysctlDir::new())));
+        match node {
+            SysctlNode::Dir(dir) => Ok(dir.clone()),
+            SysctlNode::Entry(_) => {
+                return_errno_with_message!(Errno::EEXIST, "the sysctl directory is an entry")
+            }
+        }
+    }
+}
+
+/// Returns the directory at `/proc/sys`.
+pub(super) fn root() -> &'static Arc<SysctlDir> {
+    static ROOT: Once<Arc<SysctlDir>> = Once::new();
+
+    ROOT.call_once(|| Arc::new(SysctlDir::new()))
+}
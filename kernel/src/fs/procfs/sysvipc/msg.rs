@@ -0,0 +1,65 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! This module offers `/proc/sysvipc/msg` file support, which lists the
+//! System V message queues that currently exist.
+//!
+//! Reference: <https://man7.org/linux/man-pages/man5/proc_sysvipc.5.html>
+
+use alloc::format;
+
+use ostd::task::Task;
+
+use crate::{
+    fs::{
+        procfs::template::{FileOps, ProcFileBuilder},
+        utils::Inode,
+    },
+    ipc::msg::msg_queue::msg_queues,
+    prelude::*,
+};
+
+/// Represents the inode at `/proc/sysvipc/msg`.
+pub struct MsgFileOps;
+
+impl MsgFileOps {
+    pub fn new_inode(parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        ProcFileBuilder::new(Self).parent(parent).build().unwrap()
+    }
+}
+
+impl FileOps for MsgFileOps {
+    fn data(&self) -> Result<Vec<u8>> {
+        let mut output = String::from(
+            "       key      msqid perms      cbytes       qnum lspid lrpid   uid   gid  cuid  cgid      stime      rtime      ctime\n",
+        );
+
+        let ipc_ns = {
+            let current = Task::current().unwrap();
+            let ns_proxy = current.as_thread_local().unwrap().borrow_ns_proxy();
+            ns_proxy.unwrap().ipc_ns().clone()
+        };
+
+        for msg_queue in msg_queues(&ipc_ns) {
+            let permission = msg_queue.permission();
+            output.push_str(&format!(
+                "{:>10} {:>10}  {:>4o}  {:>10} {:>10} {:>5} {:>5} {:>5} {:>5} {:>5} {:>5} {:>10} {:>10} {:>10}\n",
+                permission.key(),
+                msg_queue.id(),
+                permission.mode(),
+                msg_queue.cbytes(),
+                msg_queue.qnum(),
+                msg_queue.lspid(),
+                msg_queue.lrpid(),
+                u32::from(permission.uid()),
+                u32::from(permission.gid()),
+                u32::from(permission.cuid()),
+                u32::from(permission.cguid()),
+                msg_queue.stime().as_secs(),
+                msg_queue.rtime().as_secs(),
+                msg_queue.ctime().as_secs(),
+            ));
+        }
+
+        Ok(output.into_bytes())
+    }
+}
@@ -0,0 +1,67 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! This module offers `/proc/sysvipc/shm` file support, which lists the
+//! System V shared memory segments that currently exist.
+//!
+//! Reference: <https://man7.org/linux/man-pages/man5/proc_sysvipc.5.html>
+
+use alloc::format;
+
+use ostd::task::Task;
+
+use crate::{
+    fs::{
+        procfs::template::{FileOps, ProcFileBuilder},
+        utils::Inode,
+    },
+    ipc::shm::shm_seg::shm_segs,
+    prelude::*,
+};
+
+/// Represents the inode at `/proc/sysvipc/shm`.
+pub struct ShmFileOps;
+
+impl ShmFileOps {
+    pub fn new_inode(parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        ProcFileBuilder::new(Self).parent(parent).build().unwrap()
+    }
+}
+
+impl FileOps for ShmFileOps {
+    fn data(&self) -> Result<Vec<u8>> {
+        let mut output = String::from(
+            "       key      shmid perms                  size  cpid  lpid nattch   uid   gid  cuid  cgid      atime      dtime      ctime                   rss                  swap\n",
+        );
+
+        let ipc_ns = {
+            let current = Task::current().unwrap();
+            let ns_proxy = current.as_thread_local().unwrap().borrow_ns_proxy();
+            ns_proxy.unwrap().ipc_ns().clone()
+        };
+
+        for shm_seg in shm_segs(&ipc_ns) {
+            let permission = shm_seg.permission();
+            output.push_str(&format!(
+                "{:>10} {:>10}  {:>4o} {:>21} {:>5} {:>5}  {:>5} {:>5} {:>5} {:>5} {:>5} {:>10} {:>10} {:>10} {:>21} {:>21}\n",
+                permission.key(),
+                shm_seg.id(),
+                permission.mode(),
+                shm_seg.size(),
+                shm_seg.cpid(),
+                shm_seg.lpid(),
+                shm_seg.nattch(),
+                u32::from(permission.uid()),
+                u32::from(permission.gid()),
+                u32::from(permission.cuid()),
+                u32::from(permission.cguid()),
+                shm_seg.atime().as_secs(),
+                shm_seg.dtime().as_secs(),
+                shm_seg.ctime().as_secs(),
+                shm_seg.num_resident_pages() * PAGE_SIZE,
+                0,
+            ));
+        }
+
+        Ok(output.into_bytes())
+    }
+}
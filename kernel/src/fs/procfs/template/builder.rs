@@ -8,7 +8,7 @@ use super::{
     sym::{ProcSym, SymOps},
 };
 use crate::{
-    fs::utils::{FileSystem, Inode},
+    fs::utils::{FileSystem, Inode, InodeMode},
     prelude::*,
 };
 
@@ -63,6 +63,7 @@ pub struct ProcFileBuilder<O: FileOps> {
     // Mandatory field
     file: O,
     // Optional fields
+    mode: Option<InodeMode>,
     optional_builder: Option<OptionalBuilder>,
 }
 
@@ -71,6 +72,7 @@ impl<O: FileOps> ProcFileBuilder<O> {
         let optional_builder: OptionalBuilder = Default::default();
         Self {
             file,
+            mode: None,
             optional_builder: Some(optional_builder),
         }
     }
@@ -83,9 +85,22 @@ impl<O: FileOps> ProcFileBuilder<O> {
         self.optional_builder(|ob| ob.volatile())
     }
 
+    pub fn ino(self, ino: u64) -> Self {
+        self.optional_builder(|ob| ob.ino(ino))
+    }
+
+    /// Sets the permission mode of the file, which is `0o444` by default.
+    pub fn mode(mut self, mode: InodeMode) -> Self {
+        self.mode = Some(mode);
+        self
+    }
+
     pub fn build(mut self) -> Result<Arc<ProcFile<O>>> {
-        let (fs, _, _, is_volatile) = self.optional_builder.take().unwrap().build()?;
-        Ok(ProcFile::new(self.file, fs, is_volatile))
+        let (fs, _, ino, is_volatile) = self.optional_builder.take().unwrap().build()?;
+        let mode = self
+            .mode
+            .unwrap_or_else(|| InodeMode::from_bits_truncate(0o444));
+        Ok(ProcFile::new(self.file, fs, ino, mode, is_volatile))
     }
 
     fn optional_builder<F>(mut self, f: F) -> Self
@@ -17,15 +17,21 @@ pub struct ProcFile<F: FileOps> {
 }
 
 impl<F: FileOps> ProcFile<F> {
-    pub fn new(file: F, fs: Weak<dyn FileSystem>, is_volatile: bool) -> Arc<Self> {
+    pub fn new(
+        file: F,
+        fs: Weak<dyn FileSystem>,
+        ino: Option<u64>,
+        mode: InodeMode,
+        is_volatile: bool,
+    ) -> Arc<Self> {
         let common = {
-            let arc_fs = fs.upgrade().unwrap();
-            let procfs = arc_fs.downcast_ref::<ProcFS>().unwrap();
-            let metadata = Metadata::new_file(
-                procfs.alloc_id(),
-                InodeMode::from_bits_truncate(0o444),
-                super::BLOCK_SIZE,
-            );
+            let ino = ino.unwrap_or_else(|| {
+                let arc_fs = fs.upgrade().unwrap();
+                let procfs = arc_fs.downcast_ref::<ProcFS>().unwrap();
+                procfs.alloc_id()
+            });
+
+            let metadata = Metadata::new_file(ino, mode, super::BLOCK_SIZE);
             Common::new(metadata, fs, is_volatile)
         };
         Arc::new(Self {
@@ -33,6 +39,10 @@ impl<F: FileOps> ProcFile<F> {
             common,
         })
     }
+
+    pub fn inner(&self) -> &F {
+        &self.inner
+    }
 }
 
 #[inherit_methods(from = "self.common")]
@@ -75,12 +85,22 @@ impl<F: FileOps + 'static> Inode for ProcFile<F> {
         self.read_at(offset, writer)
     }
 
-    fn write_at(&self, _offset: usize, _reader: &mut VmReader) -> Result<usize> {
-        Err(Error::new(Errno::EPERM))
+    fn write_at(&self, offset: usize, reader: &mut VmReader) -> Result<usize> {
+        self.write_direct_at(offset, reader)
     }
 
-    fn write_direct_at(&self, _offset: usize, _reader: &mut VmReader) -> Result<usize> {
-        Err(Error::new(Errno::EPERM))
+    fn write_direct_at(&self, offset: usize, reader: &mut VmReader) -> Result<usize> {
+        if offset != 0 {
+            return_errno_with_message!(Errno::EINVAL, "the file can only be written at offset 0");
+        }
+
+        // Like Linux, at most one page can be written at a time.
+        let len = reader.remain().min(PAGE_SIZE);
+        let mut data = vec![0u8; len];
+        reader.read_fallible(&mut VmWriter::from(data.as_mut_slice()))?;
+        self.inner.write(&data)?;
+
+        Ok(len)
     }
 
     fn read_link(&self) -> Result<String> {
@@ -102,4 +122,11 @@ impl<F: FileOps + 'static> Inode for ProcFile<F> {
 
 pub trait FileOps: Sync + Send {
     fn data(&self) -> Result<Vec<u8>>;
+
+    /// Writes the data to the file.
+    ///
+    /// The file is read-only by default.
+    fn write(&self, _data: &[u8]) -> Result<()> {
+        Err(Error::new(Errno::EPERM))
+    }
 }
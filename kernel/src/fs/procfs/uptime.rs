@@ -0,0 +1,41 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! This module offers `/proc/uptime` file support, which tells the user space
+//! about the uptime of the system and the time spent in the idle task.
+//!
+//! Reference: <https://www.man7.org/linux/man-pages/man5/proc_uptime.5.html>
+
+use alloc::format;
+
+use ostd::arch::timer::TIMER_FREQ;
+
+use crate::{
+    fs::{
+        procfs::template::{FileOps, ProcFileBuilder},
+        utils::Inode,
+    },
+    prelude::*,
+    sched::cpu_time,
+    time::{clocks::BootTimeClock, Clock},
+};
+
+/// Represents the inode at `/proc/uptime`.
+pub struct UptimeFileOps;
+
+impl UptimeFileOps {
+    pub fn new_inode(parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        ProcFileBuilder::new(Self).parent(parent).build().unwrap()
+    }
+}
+
+impl FileOps for UptimeFileOps {
+    fn data(&self) -> Result<Vec<u8>> {
+        let uptime = BootTimeClock::get().read_time().as_secs_f64();
+        // The idle time is the sum over all CPUs, so it can exceed the uptime.
+        let idle_time = cpu_time::total_cpu_time().idle as f64 / TIMER_FREQ as f64;
+
+        let output = format!("{:.2} {:.2}\n", uptime, idle_time);
+
+        Ok(output.into_bytes())
+    }
+}
@@ -0,0 +1,55 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! This module offers `/proc/version` file support, which tells the user space
+//! about the version of the running kernel.
+//!
+//! Reference: <https://www.man7.org/linux/man-pages/man5/proc_version.5.html>
+
+use alloc::format;
+
+use ostd::task::Task;
+
+use crate::{
+    fs::{
+        procfs::template::{FileOps, ProcFileBuilder},
+        utils::Inode,
+    },
+    prelude::*,
+};
+
+/// Represents the inode at `/proc/version`.
+pub struct VersionFileOps;
+
+impl VersionFileOps {
+    pub fn new_inode(parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        ProcFileBuilder::new(Self).parent(parent).build().unwrap()
+    }
+}
+
+impl FileOps for VersionFileOps {
+    fn data(&self) -> Result<Vec<u8>> {
+        // Like `uname`, we report the names in the UTS namespace of the current thread.
+        let uts_name = {
+            let current = Task::current().unwrap();
+            let ns_proxy = current.as_thread_local().unwrap().borrow_ns_proxy();
+            ns_proxy.unwrap().uts_ns().uts_name()
+        };
+
+        let output = format!(
+            "{} version {} (asterinas@asterinas) (rustc) {}\n",
+            field_to_str(&uts_name.sysname),
+            field_to_str(&uts_name.release),
+            field_to_str(&uts_name.version),
+        );
+
+        Ok(output.into_bytes())
+    }
+}
+
+pub(super) fn field_to_str(field: &[u8]) -> &str {
+    let len = field
+        .iter()
+        .position(|&byte| byte == 0)
+        .unwrap_or(field.len());
+    core::str::from_utf8(&field[..len]).unwrap_or("")
+}
@@ -0,0 +1,77 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! This module registers the sysctls under `/proc/sys/fs`.
+//!
+//! Reference: <https://docs.kernel.org/admin-guide/sysctl/fs.html>
+
+use core::sync::atomic::{AtomicI64, Ordering};
+
+use crate::{
+    fs::procfs::sysctl::{self, IntSysctl, SysctlEntry},
+    prelude::*,
+    process::{credentials::capabilities::CapSet, posix_thread::AsPosixThread},
+    thread::Thread,
+    vm,
+};
+
+/// The maximum number of file handles that the kernel allocates.
+static FILE_MAX: AtomicI64 = AtomicI64::new(0);
+
+/// The number of the allocated file handles.
+///
+/// Only the handles of the opened paths (i.e., the [`InodeHandle`]s) are counted.
+///
+/// [`InodeHandle`]: super::inode_handle::InodeHandle
+static NR_FILES: AtomicI64 = AtomicI64::new(0);
+
+pub(super) fn init() {
+    // Like Linux, the default limit is about 10% of the memory in KiB,
+    // but not less than `NR_FILE`.
+    const NR_FILE: i64 = 8192;
+    let file_max = ((vm::mem_total() / 1024 / 10) as i64).max(NR_FILE);
+    FILE_MAX.store(file_max, Ordering::Relaxed);
+
+    let entries = vec![SysctlEntry::writable(
+        "file-max",
+        IntSysctl::new(
+            0..=i64::MAX,
+            || [FILE_MAX.load(Ordering::Relaxed)],
+            |[file_max]| FILE_MAX.store(file_max, Ordering::Relaxed),
+        ),
+        CapSet::SYS_ADMIN,
+    )];
+
+    sysctl::register("fs", entries).unwrap();
+}
+
+/// Accounts a new file handle.
+///
+/// Like Linux, only the threads with `CAP_SYS_ADMIN` can allocate file handles beyond
+/// `file-max`.
+pub(super) fn alloc_file() -> Result<()> {
+    let nr_files = NR_FILES.fetch_add(1, Ordering::Relaxed);
+    if nr_files < FILE_MAX.load(Ordering::Relaxed) || is_current_sys_admin() {
+        return Ok(());
+    }
+
+    NR_FILES.fetch_sub(1, Ordering::Relaxed);
+    return_errno_with_message!(Errno::ENFILE, "too many open files in the system");
+}
+
+/// Releases a file handle accounted by [`alloc_file`].
+pub(super) fn free_file() {
+    NR_FILES.fetch_sub(1, Ordering::Relaxed);
+}
+
+fn is_current_sys_admin() -> bool {
+    // The files opened by the kernel threads are not limited.
+    let Some(thread) = Thread::current() else {
+        return true;
+    };
+    thread.as_posix_thread().is_none_or(|posix_thread| {
+        posix_thread
+            .credentials()
+            .effective_capset()
+            .contains(CapSet::SYS_ADMIN)
+    })
+}
@@ -55,6 +55,10 @@ impl FileSystem for SysFs {
     fn flags(&self) -> FsFlags {
         FsFlags::empty()
     }
+
+    fn name(&self) -> &'static str {
+        "sysfs"
+    }
 }
 
 pub(super) struct SysFsType;
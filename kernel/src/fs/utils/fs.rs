@@ -51,6 +51,9 @@ pub trait FileSystem: Any + Sync + Send {
     fn sb(&self) -> SuperBlock;
 
     fn flags(&self) -> FsFlags;
+
+    /// Returns the name of the file system type, as shown in `/proc/[pid]/mountinfo`.
+    fn name(&self) -> &'static str;
 }
 
 impl dyn FileSystem {
@@ -2,7 +2,7 @@
 
 #![expect(unused_variables)]
 
-use core::{any::TypeId, time::Duration};
+use core::{any::TypeId, ops::Range, time::Duration};
 
 use aster_rights::Full;
 use aster_systree::SysPerms;
@@ -465,6 +465,14 @@ pub trait Inode: Any + Sync + Send {
         Ok(())
     }
 
+    /// Writes back the data of the file within `range`.
+    ///
+    /// Unlike [`Inode::sync_data`], this method neither writes back the metadata nor
+    /// flushes the device. By default, all the data of the file is synchronized.
+    fn sync_data_range(&self, range: Range<usize>) -> Result<()> {
+        self.sync_data()
+    }
+
     /// Manipulates a range of space of the file according to the specified allocate mode,
     /// the manipulated range starts at `offset` and continues for `len` bytes.
     fn fallocate(&self, mode: FallocMode, offset: usize, len: usize) -> Result<()> {
@@ -40,6 +40,7 @@ mod random_test;
 mod range_lock;
 mod status_flags;
 pub mod systree_inode;
+pub mod writeback;
 mod xattr;
 
 use core::{
@@ -49,6 +50,11 @@ use core::{
 
 use crate::prelude::*;
 
+pub(super) fn init() {
+    page_cache::init();
+    writeback::init();
+}
+
 #[derive(Copy, PartialEq, Eq, Clone, Debug)]
 pub enum SeekFrom {
     Start(usize),
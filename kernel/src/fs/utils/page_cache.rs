@@ -5,6 +5,7 @@
 use core::{
     ops::Range,
     sync::atomic::{AtomicU8, Ordering},
+    time::Duration,
 };
 
 use align_ext::AlignExt;
@@ -14,13 +15,29 @@ use lru::LruCache;
 use ostd::{
     impl_untyped_frame_meta_for,
     mm::{Frame, FrameAllocOptions, UFrame, VmIoFill},
+    timer::Jiffies,
 };
+use spin::Once;
 
+use super::writeback;
 use crate::{
     prelude::*,
-    vm::vmo::{get_page_idx_range, Pager, Vmo, VmoFlags, VmoOptions},
+    vm::{
+        reclaim::{self, Shrinker},
+        vmo::{get_page_idx_range, Pager, Vmo, VmoFlags, VmoOptions, WeakVmo},
+    },
 };
 
+/// All the page caches, keyed by the addresses of their managers.
+static PAGE_CACHES: Mutex<BTreeMap<usize, Weak<PageCacheManager>>> = Mutex::new(BTreeMap::new());
+
+static PAGE_CACHE_SHRINKER: Once<Arc<PageCacheShrinker>> = Once::new();
+
+pub(super) fn init() {
+    let shrinker = PAGE_CACHE_SHRINKER.call_once(|| Arc::new(PageCacheShrinker));
+    reclaim::register_shrinker(Arc::downgrade(shrinker) as Weak<dyn Shrinker>);
+}
+
 pub struct PageCache {
     pages: Vmo<Full>,
     manager: Arc<PageCacheManager>,
@@ -29,12 +46,7 @@ pub struct PageCache {
 impl PageCache {
     /// Creates an empty size page cache associated with a new backend.
     pub fn new(backend: Weak<dyn PageCacheBackend>) -> Result<Self> {
-        let manager = Arc::new(PageCacheManager::new(backend));
-        let pages = VmoOptions::<Full>::new(0)
-            .flags(VmoFlags::RESIZABLE)
-            .pager(manager.clone())
-            .alloc()?;
-        Ok(Self { pages, manager })
+        Self::with_capacity(0, backend)
     }
 
     /// Creates a page cache associated with an existing backend.
@@ -42,11 +54,15 @@ impl PageCache {
     /// The `capacity` is the initial cache size required by the backend.
     /// This size usually corresponds to the size of the backend.
     pub fn with_capacity(capacity: usize, backend: Weak<dyn PageCacheBackend>) -> Result<Self> {
-        let manager = Arc::new(PageCacheManager::new(backend));
+        let manager = PageCacheManager::new(backend);
         let pages = VmoOptions::<Full>::new(capacity)
             .flags(VmoFlags::RESIZABLE)
             .pager(manager.clone())
             .alloc()?;
+        manager.vmo.call_once(|| pages.downgrade());
+        PAGE_CACHES
+            .lock()
+            .insert(Arc::as_ptr(&manager) as usize, Arc::downgrade(&manager));
         Ok(Self { pages, manager })
     }
 
@@ -74,6 +90,11 @@ impl PageCache {
         self.manager.backend()
     }
 
+    /// Returns the number of dirty pages.
+    pub fn nr_dirty_pages(&self) -> usize {
+        self.manager.nr_dirty_pages()
+    }
+
     /// Resizes the current page cache to a target size.
     pub fn resize(&self, new_size: usize) -> Result<()> {
         // If the new size is smaller and not page-aligned,
@@ -326,32 +347,209 @@ impl ReadaheadState {
     }
 }
 
-struct PageCacheManager {
+pub(super) struct PageCacheManager {
     pages: Mutex<LruCache<usize, CachePage>>,
+    /// The indexes of the dirty pages, with the time when they became dirty.
+    ///
+    /// The lock must be acquired after that of `pages`.
+    dirty_pages: Mutex<BTreeMap<usize, Duration>>,
     backend: Weak<dyn PageCacheBackend>,
     ra_state: Mutex<ReadaheadState>,
+    /// The VMO of the page cache, from which the pages are evicted.
+    vmo: Once<WeakVmo>,
+    self_ref: Weak<Self>,
 }
 
 impl PageCacheManager {
-    pub fn new(backend: Weak<dyn PageCacheBackend>) -> Self {
-        Self {
+    pub fn new(backend: Weak<dyn PageCacheBackend>) -> Arc<Self> {
+        Arc::new_cyclic(|weak_ref| Self {
             pages: Mutex::new(LruCache::unbounded()),
+            dirty_pages: Mutex::new(BTreeMap::new()),
             backend,
             ra_state: Mutex::new(ReadaheadState::new()),
-        }
+            vmo: Once::new(),
+            self_ref: weak_ref.clone(),
+        })
     }
 
     pub fn backend(&self) -> Arc<dyn PageCacheBackend> {
         self.backend.upgrade().unwrap()
     }
 
+    /// Returns the number of dirty pages.
+    pub(super) fn nr_dirty_pages(&self) -> usize {
+        self.dirty_pages.lock().len()
+    }
+
+    /// Returns the number of cached pages, or zero if the pages are locked.
+    fn nr_pages(&self) -> usize {
+        self.pages.try_lock().map_or(0, |pages| pages.len())
+    }
+
+    /// Scans at most `nr_to_scan` least recently used pages, evicting the clean pages
+    /// that are not in use.
+    ///
+    /// The pages are skipped if they are locked, so that the reclamation triggered
+    /// by the allocations under the lock does not deadlock.
+    ///
+    /// Returns the number of the pages evicted.
+    fn shrink(&self, nr_to_scan: usize) -> usize {
+        let Some(mut pages) = self.pages.try_lock() else {
+            return 0;
+        };
+        let Some(vmo) = self.vmo.get() else {
+            return 0;
+        };
+
+        let candidates: Vec<usize> = pages
+            .iter()
+            .rev()
+            .take(nr_to_scan)
+            .filter(|(_, page)| page.load_state() == PageState::UpToDate)
+            .map(|(idx, _)| *idx)
+            .collect();
+
+        let mut nr_evicted = 0;
+        for idx in candidates {
+            // A page that is not in use is referenced only by `pages` and the VMO.
+            if !vmo.evict_page_if(idx, |frame| frame.reference_count() == 2) {
+                continue;
+            }
+            if pages
+                .peek(&idx)
+                .is_some_and(|page| page.reference_count() == 1)
+            {
+                pages.pop(&idx);
+                nr_evicted += 1;
+            }
+        }
+        nr_evicted
+    }
+
     // Discard pages without writing them back to disk.
     pub fn discard_range(&self, range: Range<usize>) {
         let page_idx_range = get_page_idx_range(&range);
         let mut pages = self.pages.lock();
-        for idx in page_idx_range {
+        for idx in page_idx_range.clone() {
             pages.pop(&idx);
         }
+        self.clear_dirty(page_idx_range);
+    }
+
+    /// Marks the page at `idx` as dirty, which must be in `pages`.
+    ///
+    /// Returns whether the page was clean before.
+    fn mark_dirty(&self, idx: usize, page: &mut CachePage) -> bool {
+        page.store_state(PageState::Dirty);
+
+        let mut dirty_pages = self.dirty_pages.lock();
+        if dirty_pages.contains_key(&idx) {
+            return false;
+        }
+        if dirty_pages.is_empty() {
+            writeback::register(self.self_ref.clone());
+        }
+        dirty_pages.insert(idx, Jiffies::elapsed().as_duration());
+        writeback::account_dirtied(1);
+        true
+    }
+
+    /// Forgets the dirty pages in `page_idx_range`, which have been written back
+    /// or removed from `pages`.
+    fn clear_dirty(&self, page_idx_range: Range<usize>) {
+        let idxs: Vec<usize> = self
+            .dirty_pages
+            .lock()
+            .range(page_idx_range)
+            .map(|(idx, _)| *idx)
+            .collect();
+        self.clear_dirty_pages(&idxs);
+    }
+
+    /// Forgets the dirty pages at `idxs`.
+    fn clear_dirty_pages(&self, idxs: &[usize]) {
+        let mut dirty_pages = self.dirty_pages.lock();
+        let nr_cleared = idxs
+            .iter()
+            .filter(|idx| dirty_pages.remove(idx).is_some())
+            .count();
+        if nr_cleared == 0 {
+            return;
+        }
+        writeback::account_cleaned(nr_cleared);
+        if dirty_pages.is_empty() {
+            writeback::unregister(&self.self_ref);
+        }
+    }
+
+    /// Writes back the dirty pages that became dirty before `dirtied_before`,
+    /// or all the dirty pages if `dirtied_before` is `None`.
+    ///
+    /// Returns the number of pages written back.
+    pub(super) fn write_back(&self, dirtied_before: Option<Duration>) -> Result<usize> {
+        let mut pages = self.pages.lock();
+        let Some(backend) = self.backend.upgrade() else {
+            return Ok(0);
+        };
+        let backend_npages = backend.npages();
+
+        let candidates: Vec<usize> = self
+            .dirty_pages
+            .lock()
+            .iter()
+            .filter(|(_, dirtied_at)| dirtied_before.is_none_or(|before| **dirtied_at < before))
+            .map(|(idx, _)| *idx)
+            .collect();
+
+        let mut bio_waiter = BioWaiter::new();
+        let mut written = Vec::new();
+        let mut stale = Vec::new();
+        for idx in candidates {
+            match pages.peek(&idx) {
+                Some(page) if page.load_state() == PageState::Dirty => {
+                    // The pages beyond the backend are written back after it is resized.
+                    if idx < backend_npages {
+                        bio_waiter.concat(backend.write_page_async(idx, page)?);
+                        written.push(idx);
+                    }
+                }
+                _ => stale.push(idx),
+            }
+        }
+
+        if !matches!(bio_waiter.wait(), Some(BioStatus::Complete)) {
+            return_errno!(Errno::EIO);
+        }
+
+        self.finish_writeback(&mut pages, &written);
+        self.clear_dirty_pages(&stale);
+        Ok(written.len())
+    }
+
+    /// Marks the pages at `idxs`, which have been written back, as clean.
+    ///
+    /// The pages that are still mapped are kept dirty, since they may be modified
+    /// via the mappings without being noticed. They are written back again later.
+    fn finish_writeback(&self, pages: &mut LruCache<usize, CachePage>, idxs: &[usize]) {
+        let (cleaned, mapped): (Vec<usize>, Vec<usize>) = idxs
+            .iter()
+            .copied()
+            .partition(|idx| pages.peek(idx).is_none_or(|page| !is_mapped(page)));
+
+        for idx in cleaned.iter() {
+            if let Some(page) = pages.peek_mut(idx) {
+                page.store_state(PageState::UpToDate);
+            }
+        }
+        self.clear_dirty_pages(&cleaned);
+
+        let now = Jiffies::elapsed().as_duration();
+        let mut dirty_pages = self.dirty_pages.lock();
+        for idx in mapped {
+            if let Some(dirtied_at) = dirty_pages.get_mut(&idx) {
+                *dirtied_at = now;
+            }
+        }
     }
 
     pub fn evict_range(&self, range: Range<usize>) -> Result<()> {
@@ -361,11 +559,13 @@ impl PageCacheManager {
         let mut pages = self.pages.lock();
         let backend = self.backend();
         let backend_npages = backend.npages();
+        let mut written = Vec::new();
         for idx in page_idx_range.start..page_idx_range.end {
             if let Some(page) = pages.peek(&idx) {
                 if page.load_state() == PageState::Dirty && idx < backend_npages {
                     let waiter = backend.write_page_async(idx, page)?;
                     bio_waiter.concat(waiter);
+                    written.push(idx);
                 }
             }
         }
@@ -378,9 +578,11 @@ impl PageCacheManager {
         for (_, page) in pages
             .iter_mut()
             .filter(|(idx, _)| page_idx_range.contains(*idx))
+            .filter(|(_, page)| page.load_state() != PageState::Dirty)
         {
             page.store_state(PageState::UpToDate);
         }
+        self.finish_writeback(&mut pages, &written);
         Ok(())
     }
 
@@ -447,17 +649,27 @@ impl Pager for PageCacheManager {
 
     fn update_page(&self, idx: usize) -> Result<()> {
         let mut pages = self.pages.lock();
-        if let Some(page) = pages.get_mut(&idx) {
-            page.store_state(PageState::Dirty);
+        let newly_dirtied = if let Some(page) = pages.get_mut(&idx) {
+            self.mark_dirty(idx, page)
         } else {
             warn!("The page {} is not in page cache", idx);
-        }
+            false
+        };
+        drop(pages);
 
+        if newly_dirtied {
+            writeback::balance_dirty_pages(self);
+        }
         Ok(())
     }
 
     fn decommit_page(&self, idx: usize) -> Result<()> {
-        let page_result = self.pages.lock().pop(&idx);
+        let page_result = {
+            let mut pages = self.pages.lock();
+            let page = pages.pop(&idx);
+            self.clear_dirty(idx..idx + 1);
+            page
+        };
         if let Some(page) = page_result {
             if let PageState::Dirty = page.load_state() {
                 let Some(backend) = self.backend.upgrade() else {
@@ -482,6 +694,62 @@ impl Pager for PageCacheManager {
     }
 }
 
+impl Drop for PageCacheManager {
+    fn drop(&mut self) {
+        PAGE_CACHES.lock().remove(&(self as *const Self as usize));
+
+        let dirty_pages = self.dirty_pages.get_mut();
+        if !dirty_pages.is_empty() {
+            writeback::account_cleaned(dirty_pages.len());
+            writeback::unregister(&self.self_ref);
+        }
+    }
+}
+
+/// Returns whether the page is mapped, which is referenced by more than `pages`
+/// of its page cache and the VMO.
+fn is_mapped(page: &CachePage) -> bool {
+    page.reference_count() > 2
+}
+
+/// The shrinker that evicts the clean pages not in use from the page caches.
+struct PageCacheShrinker;
+
+impl PageCacheShrinker {
+    fn page_caches() -> Vec<Arc<PageCacheManager>> {
+        PAGE_CACHES
+            .lock()
+            .values()
+            .filter_map(Weak::upgrade)
+            .collect()
+    }
+}
+
+impl Shrinker for PageCacheShrinker {
+    fn count_objects(&self) -> usize {
+        Self::page_caches()
+            .iter()
+            .map(|cache| cache.nr_pages())
+            .sum()
+    }
+
+    fn scan_objects(&self, nr_to_scan: usize) -> usize {
+        let caches = Self::page_caches();
+        let nr_pages: Vec<usize> = caches.iter().map(|cache| cache.nr_pages()).collect();
+        let nr_total_pages: usize = nr_pages.iter().sum();
+        if nr_total_pages == 0 {
+            return 0;
+        }
+
+        // Each page cache is scanned in proportion to its size.
+        caches
+            .iter()
+            .zip(nr_pages)
+            .map(|(cache, nr_pages)| cache.shrink((nr_to_scan * nr_pages).div_ceil(nr_total_pages)))
+            .sum()
+    }
+}
+
 /// A page in the page cache.
 pub type CachePage = Frame<CachePageMeta>;
 
@@ -500,24 +768,28 @@ pub trait CachePageExt {
 
     /// Allocates a new cache page which content and state are uninitialized.
     fn alloc_uninit() -> Result<CachePage> {
-        let meta = CachePageMeta {
-            state: AtomicPageState::new(PageState::Uninit),
-        };
-        let page = FrameAllocOptions::new()
-            .zeroed(false)
-            .alloc_frame_with(meta)?;
-        Ok(page)
+        reclaim::alloc_with_reclaim(|| {
+            let meta = CachePageMeta {
+                state: AtomicPageState::new(PageState::Uninit),
+            };
+            let page = FrameAllocOptions::new()
+                .zeroed(false)
+                .alloc_frame_with(meta)?;
+            Ok(page)
+        })
     }
 
     /// Allocates a new zeroed cache page with the wanted state.
     fn alloc_zero(state: PageState) -> Result<CachePage> {
-        let meta = CachePageMeta {
-            state: AtomicPageState::new(state),
-        };
-        let page = FrameAllocOptions::new()
-            .zeroed(true)
-            .alloc_frame_with(meta)?;
-        Ok(page)
+        reclaim::alloc_with_reclaim(|| {
+            let meta = CachePageMeta {
+                state: AtomicPageState::new(state),
+            };
+            let page = FrameAllocOptions::new()
+                .zeroed(true)
+                .alloc_frame_with(meta)?;
+            Ok(page)
+        })
     }
 
     /// Loads the current state of the cache page.
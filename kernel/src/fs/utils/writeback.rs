@@ -0,0 +1,228 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The writeback of the dirty pages in the page caches.
+//!
+//! The page caches holding dirty pages are registered here, and their dirty pages
+//! are written back to the backends by a work item in the normal-priority work queue,
+//! which is submitted in the following cases:
+//!  * periodically, to write back the pages that have been dirty for long;
+//!  * when the dirty pages exceed the background threshold, to write back all of them.
+//!
+//! If the dirty pages exceed the dirty threshold, the writers are throttled until the
+//! writeback catches up. The throttling is per backend: only the writers of a page cache
+//! holding more than its share of the dirty pages are paused, so a slow backend does not
+//! stall the writers of the others. A pause is bounded, since the writers may hold the
+//! locks that the writeback of their backends needs.
+//!
+//! The thresholds and the intervals are tunable under `/proc/sys/vm`.
+//!
+//! Reference: <https://docs.kernel.org/admin-guide/sysctl/vm.html>
+
+use alloc::collections::btree_map::BTreeMap;
+use core::{
+    sync::atomic::{AtomicI64, AtomicUsize, Ordering},
+    time::Duration,
+};
+
+use ostd::{sync::WaitQueue, timer::Jiffies};
+use spin::Once;
+
+use super::page_cache::PageCacheManager;
+use crate::{
+    fs::procfs::sysctl::{self, IntSysctl, SysctlEntry},
+    prelude::*,
+    process::credentials::capabilities::CapSet,
+    thread::work_queue::{submit_work_item, work_item::WorkItem, WorkPriority},
+    time::{
+        clocks::MonotonicClock,
+        timer::{Timeout, Timer},
+    },
+    vm,
+};
+
+/// The percentage of memory that can be dirty before the background writeback starts.
+static DIRTY_BACKGROUND_RATIO: AtomicI64 = AtomicI64::new(10);
+/// The percentage of memory that can be dirty before the writers are throttled.
+static DIRTY_RATIO: AtomicI64 = AtomicI64::new(20);
+/// The age in centiseconds after which a dirty page is written back.
+static DIRTY_EXPIRE_CENTISECS: AtomicI64 = AtomicI64::new(3000);
+/// The interval in centiseconds of the periodic writeback, which is disabled if zero.
+static DIRTY_WRITEBACK_CENTISECS: AtomicI64 = AtomicI64::new(500);
+
+/// The longest time that a writer is paused each time it is throttled.
+const MAX_PAUSE: Duration = Duration::from_millis(200);
+
+/// The number of the dirty pages in all the page caches.
+static NR_DIRTY_PAGES: AtomicUsize = AtomicUsize::new(0);
+/// The number of pages in the memory.
+static NR_TOTAL_PAGES: Once<usize> = Once::new();
+
+/// The page caches holding dirty pages, keyed by their addresses.
+static DIRTY_CACHES: Mutex<BTreeMap<usize, Weak<PageCacheManager>>> = Mutex::new(BTreeMap::new());
+
+/// The queue of the throttled writers, which is woken up when pages are written back.
+static THROTTLE_QUEUE: WaitQueue = WaitQueue::new();
+
+static WRITEBACK_WORK: Once<Arc<WorkItem>> = Once::new();
+static WRITEBACK_TIMER: Once<Arc<Timer>> = Once::new();
+
+pub fn init() {
+    NR_TOTAL_PAGES.call_once(|| vm::mem_total() / PAGE_SIZE);
+    WRITEBACK_WORK.call_once(|| WorkItem::new(Box::new(write_back_work)));
+    WRITEBACK_TIMER.call_once(|| {
+        MonotonicClock::timer_manager().create_timer(|| {
+            submit_work_item(WRITEBACK_WORK.get().unwrap().clone(), WorkPriority::Normal);
+        })
+    });
+    arm_timer();
+
+    let entries = vec![
+        SysctlEntry::writable(
+            "dirty_background_ratio",
+            IntSysctl::new(
+                0..=100,
+                || [DIRTY_BACKGROUND_RATIO.load(Ordering::Relaxed)],
+                |[ratio]| DIRTY_BACKGROUND_RATIO.store(ratio, Ordering::Relaxed),
+            ),
+            CapSet::SYS_ADMIN,
+        ),
+        SysctlEntry::writable(
+            "dirty_ratio",
+            IntSysctl::new(
+                0..=100,
+                || [DIRTY_RATIO.load(Ordering::Relaxed)],
+                |[ratio]| DIRTY_RATIO.store(ratio, Ordering::Relaxed),
+            ),
+            CapSet::SYS_ADMIN,
+        ),
+        SysctlEntry::writable(
+            "dirty_expire_centisecs",
+            IntSysctl::new(
+                0..=i32::MAX as i64,
+                || [DIRTY_EXPIRE_CENTISECS.load(Ordering::Relaxed)],
+                |[centisecs]| DIRTY_EXPIRE_CENTISECS.store(centisecs, Ordering::Relaxed),
+            ),
+            CapSet::SYS_ADMIN,
+        ),
+        SysctlEntry::writable(
+            "dirty_writeback_centisecs",
+            IntSysctl::new(
+                0..=i32::MAX as i64,
+                || [DIRTY_WRITEBACK_CENTISECS.load(Ordering::Relaxed)],
+                |[centisecs]| {
+                    DIRTY_WRITEBACK_CENTISECS.store(centisecs, Ordering::Relaxed);
+                    arm_timer();
+                },
+            ),
+            CapSet::SYS_ADMIN,
+        ),
+    ];
+    sysctl::register("vm", entries).unwrap();
+}
+
+/// Returns the number of the dirty pages in all the page caches.
+pub fn nr_dirty_pages() -> usize {
+    NR_DIRTY_PAGES.load(Ordering::Relaxed)
+}
+
+/// Registers a page cache that starts to hold dirty pages.
+pub(super) fn register(cache: Weak<PageCacheManager>) {
+    DIRTY_CACHES
+        .lock()
+        .insert(cache.as_ptr() as *const () as usize, cache);
+}
+
+/// Unregisters a page cache that holds no more dirty pages.
+pub(super) fn unregister(cache: &Weak<PageCacheManager>) {
+    DIRTY_CACHES
+        .lock()
+        .remove(&(cache.as_ptr() as *const () as usize));
+}
+
+/// Accounts the pages that become dirty.
+pub(super) fn account_dirtied(nr_pages: usize) {
+    NR_DIRTY_PAGES.fetch_add(nr_pages, Ordering::Relaxed);
+}
+
+/// Accounts the dirty pages that are written back or dropped.
+pub(super) fn account_cleaned(nr_pages: usize) {
+    NR_DIRTY_PAGES.fetch_sub(nr_pages, Ordering::Relaxed);
+}
+
+/// Starts the background writeback or throttles the writer of `cache`,
+/// if there are too many dirty pages.
+pub(super) fn balance_dirty_pages(cache: &PageCacheManager) {
+    // The writeback is not available before initialization.
+    let Some(work) = WRITEBACK_WORK.get() else {
+        return;
+    };
+
+    let (background_thresh, dirty_thresh) = dirty_thresholds();
+    if nr_dirty_pages() <= background_thresh {
+        return;
+    }
+    submit_work_item(work.clone(), WorkPriority::Normal);
+
+    if nr_dirty_pages() <= dirty_thresh {
+        return;
+    }
+    let nr_dirty_caches = DIRTY_CACHES.lock().len().max(1);
+    if cache.nr_dirty_pages() <= dirty_thresh / nr_dirty_caches {
+        return;
+    }
+
+    // The pause ends at the timeout anyway, so the error is ignored.
+    let _ = THROTTLE_QUEUE.wait_until_or_timeout(
+        || (nr_dirty_pages() <= dirty_thresh).then_some(()),
+        &MAX_PAUSE,
+    );
+}
+
+/// Returns the background threshold and the dirty threshold, in pages.
+fn dirty_thresholds() -> (usize, usize) {
+    let nr_total_pages = *NR_TOTAL_PAGES.get().unwrap();
+    let threshold =
+        |ratio: &AtomicI64| nr_total_pages * ratio.load(Ordering::Relaxed) as usize / 100;
+
+    let dirty_thresh = threshold(&DIRTY_RATIO);
+    // Like Linux, the background threshold is at most half of the dirty threshold.
+    let background_thresh = threshold(&DIRTY_BACKGROUND_RATIO).min(dirty_thresh / 2);
+    (background_thresh, dirty_thresh)
+}
+
+/// Sets the timer of the periodic writeback with the current interval.
+fn arm_timer() {
+    let Some(timer) = WRITEBACK_TIMER.get() else {
+        return;
+    };
+
+    let centisecs = DIRTY_WRITEBACK_CENTISECS.load(Ordering::Relaxed) as u64;
+    if centisecs == 0 {
+        timer.cancel();
+        return;
+    }
+    let interval = Duration::from_millis(centisecs * 10);
+    timer.set_interval(interval);
+    timer.set_timeout(Timeout::After(interval));
+}
+
+fn write_back_work() {
+    let caches: Vec<Arc<PageCacheManager>> = DIRTY_CACHES
+        .lock()
+        .values()
+        .filter_map(Weak::upgrade)
+        .collect();
+
+    let (background_thresh, _) = dirty_thresholds();
+    let expire = Duration::from_millis(DIRTY_EXPIRE_CENTISECS.load(Ordering::Relaxed) as u64 * 10);
+    let expired_before = Jiffies::elapsed().as_duration().saturating_sub(expire);
+    for cache in caches {
+        // Above the background threshold, the pages are written back regardless of
+        // their ages.
+        let dirtied_before = (nr_dirty_pages() <= background_thresh).then_some(expired_before);
+        if let Err(err) = cache.write_back(dirtied_before) {
+            warn!("failed to write back the dirty pages: {:?}", err);
+        }
+        THROTTLE_QUEUE.wake_all();
+    }
+}
@@ -1,15 +1,25 @@
 // SPDX-License-Identifier: MPL-2.0
 
+use aster_rights::ReadOp;
+
 use crate::{
     prelude::*,
-    process::{Gid, Uid},
+    process::{credentials::capabilities::CapSet, Credentials, Gid, Uid},
 };
 
+pub mod msg;
+mod namespace;
 pub mod semaphore;
+pub mod shm;
+
+pub use namespace::IpcNamespace;
 
 #[expect(non_camel_case_types)]
 pub type key_t = i32;
 
+/// The key that always creates a new IPC object.
+pub const IPC_PRIVATE: key_t = 0;
+
 bitflags! {
     pub struct IpcFlags: u32{
         /// Create key if key does not exist
@@ -40,7 +50,7 @@ pub enum IpcControlCmd {
     SEM_SETALL = 17,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub struct IpcPermission {
     key: key_t,
     /// Owner's UID
@@ -85,7 +95,63 @@ impl IpcPermission {
         self.mode
     }
 
-    pub(self) fn new_sem_perm(key: key_t, uid: Uid, gid: Gid, mode: u16) -> Self {
+    /// Returns whether the credentials belong to the owner or the creator,
+    /// or have the `CAP_SYS_ADMIN` capability.
+    ///
+    /// Only such a user is allowed to perform `IPC_SET` or `IPC_RMID`.
+    pub fn is_owner_or_creator(&self, credentials: &Credentials<ReadOp>) -> bool {
+        let euid = credentials.euid();
+        euid == self.uid
+            || euid == self.cuid
+            || credentials.effective_capset().contains(CapSet::SYS_ADMIN)
+    }
+
+    /// Checks whether the credentials are granted the requested access.
+    ///
+    /// `requested` contains the "other" bits of the permission mode (i.e.,
+    /// `0o4` for reading and `0o2` for writing).
+    ///
+    /// Reference: <https://elixir.bootlin.com/linux/v6.0.9/source/ipc/util.c#L533>
+    pub fn check_access(&self, credentials: &Credentials<ReadOp>, requested: u16) -> Result<()> {
+        let euid = credentials.euid();
+        let is_in_group =
+            |gid: Gid| credentials.egid() == gid || credentials.groups().contains(&gid);
+
+        let granted = if euid == self.uid || euid == self.cuid {
+            self.mode >> 6
+        } else if is_in_group(self.gid) || is_in_group(self.cguid) {
+            self.mode >> 3
+        } else {
+            self.mode
+        };
+
+        if requested & !granted & 0o7 != 0
+            && !credentials.effective_capset().contains(CapSet::IPC_OWNER)
+        {
+            return_errno_with_message!(Errno::EACCES, "the IPC permission is denied");
+        }
+
+        Ok(())
+    }
+
+    /// Updates the owner and the permission mode, as is done by `IPC_SET`.
+    pub(self) fn set_owner_and_mode(&mut self, uid: Uid, gid: Gid, mode: u16) {
+        self.uid = uid;
+        self.gid = gid;
+        self.mode = (self.mode & !0o777) | (mode & 0o777);
+    }
+
+    /// Sets the bits in the permission mode that are not permission bits.
+    pub(self) fn set_mode_flags(&mut self, flags: u16) {
+        self.mode |= flags & !0o777;
+    }
+
+    /// Makes the key private, so that the object can no longer be found by its key.
+    pub(self) fn set_key_private(&mut self) {
+        self.key = IPC_PRIVATE;
+    }
+
+    pub(self) fn new(key: key_t, uid: Uid, gid: Gid, mode: u16) -> Self {
         Self {
             key,
             uid,
@@ -97,6 +163,37 @@ impl IpcPermission {
     }
 }
 
-pub(super) fn init() {
-    semaphore::init();
+/// The `ipc64_perm` structure, which is used by `IPC_STAT` and `IPC_SET`.
+#[derive(Debug, Clone, Copy, Pod)]
+#[repr(C)]
+pub struct IpcPerm64 {
+    pub key: key_t,
+    pub uid: u32,
+    pub gid: u32,
+    pub cuid: u32,
+    pub cgid: u32,
+    pub mode: u32,
+    pub seq: u16,
+    pub pad2: u16,
+    pub pad3: u32,
+    pub unused1: u64,
+    pub unused2: u64,
+}
+
+impl From<&IpcPermission> for IpcPerm64 {
+    fn from(permission: &IpcPermission) -> Self {
+        Self {
+            key: permission.key,
+            uid: permission.uid.into(),
+            gid: permission.gid.into(),
+            cuid: permission.cuid.into(),
+            cgid: permission.cguid.into(),
+            mode: permission.mode as u32,
+            seq: 0,
+            pad2: 0,
+            pad3: 0,
+            unused1: 0,
+            unused2: 0,
+        }
+    }
 }
@@ -0,0 +1,34 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! System V message queues.
+//!
+//! A message queue holds typed messages. Senders block when the queue is
+//! full, while receivers block until a message of the requested type arrives.
+//! Both can be interrupted by signals.
+
+use crate::prelude::*;
+
+pub mod msg_queue;
+
+// The following constant values are derived from the default values in Linux.
+
+/// Maximum size in bytes of a message.
+pub const MSGMAX: usize = 8192;
+/// Default maximum size in bytes of all messages in a queue.
+pub const MSGMNB: usize = 16384;
+/// Maximum number of message queues.
+pub const MSGMNI: usize = 32000;
+
+bitflags! {
+    /// Flags for `msgsnd` and `msgrcv`.
+    pub struct MsgFlags: u32 {
+        /// Return immediately instead of blocking.
+        const IPC_NOWAIT = 0o4000;
+        /// Truncate the message if it is too long.
+        const MSG_NOERROR = 0o10000;
+        /// Receive the first message whose type differs from the requested type.
+        const MSG_EXCEPT = 0o20000;
+        /// Copy the message instead of removing it from the queue.
+        const MSG_COPY = 0o40000;
+    }
+}
@@ -0,0 +1,444 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use core::time::Duration;
+
+use aster_rights::ReadOp;
+use id_alloc::IdAlloc;
+use ostd::sync::WaitQueue;
+
+use super::{MsgFlags, MSGMNB, MSGMNI};
+use crate::{
+    ipc::{key_t, IpcNamespace, IpcPermission, IPC_PRIVATE},
+    prelude::*,
+    process::{credentials::capabilities::CapSet, Credentials, Gid, Pid, Uid},
+    time::clocks::RealTimeCoarseClock,
+};
+
+/// A message in a message queue.
+#[derive(Debug)]
+pub struct Message {
+    /// Message type, which is always positive
+    mtype: i64,
+    /// Message content
+    data: Box<[u8]>,
+}
+
+impl Message {
+    pub fn new(mtype: i64, data: Box<[u8]>) -> Self {
+        debug_assert!(mtype > 0);
+        Self { mtype, data }
+    }
+
+    pub fn mtype(&self) -> i64 {
+        self.mtype
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+pub struct MsgQueue {
+    /// ID of the queue
+    id: key_t,
+    /// Inner
+    inner: SpinLock<MsgQueueInner>,
+    /// Senders that wait for free space
+    send_wait_queue: WaitQueue,
+    /// Receivers that wait for messages
+    recv_wait_queue: WaitQueue,
+}
+
+struct MsgQueueInner {
+    /// Queue permission
+    permission: IpcPermission,
+    /// Messages in the order of arrival
+    messages: VecDeque<Message>,
+    /// Total size in bytes of the messages in the queue
+    cbytes: usize,
+    /// Maximum total size in bytes of the messages in the queue
+    qbytes: usize,
+    /// Last `msgsnd` time
+    stime: u64,
+    /// Last `msgrcv` time
+    rtime: u64,
+    /// Creation time or last modification via `msgctl`
+    ctime: u64,
+    /// PID of the last `msgsnd` caller
+    lspid: Pid,
+    /// PID of the last `msgrcv` caller
+    lrpid: Pid,
+    /// Whether the queue has been removed by `IPC_RMID`
+    is_removed: bool,
+}
+
+impl MsgQueueInner {
+    /// Finds the index of the first message that matches `msgtyp`.
+    ///
+    /// Reference: <https://man7.org/linux/man-pages/man2/msgrcv.2.html>
+    fn find_message(&self, msgtyp: i64, flags: MsgFlags) -> Option<usize> {
+        if msgtyp == 0 {
+            return (!self.messages.is_empty()).then_some(0);
+        }
+
+        if msgtyp > 0 {
+            let is_except = flags.contains(MsgFlags::MSG_EXCEPT);
+            return self
+                .messages
+                .iter()
+                .position(|message| (message.mtype == msgtyp) != is_except);
+        }
+
+        // Find the first message with the lowest type that is less than or
+        // equal to the absolute value of `msgtyp`.
+        let max_mtype = msgtyp.checked_neg().unwrap_or(i64::MAX);
+        self.messages
+            .iter()
+            .enumerate()
+            .filter(|(_, message)| message.mtype <= max_mtype)
+            .min_by_key(|(_, message)| message.mtype)
+            .map(|(index, _)| index)
+    }
+}
+
+impl MsgQueue {
+    fn new(id: key_t, key: key_t, mode: u16, credentials: &Credentials<ReadOp>) -> Self {
+        let permission = IpcPermission::new(key, credentials.euid(), credentials.egid(), mode);
+
+        Self {
+            id,
+            inner: SpinLock::new(MsgQueueInner {
+                permission,
+                messages: VecDeque::new(),
+                cbytes: 0,
+                qbytes: MSGMNB,
+                stime: 0,
+                rtime: 0,
+                ctime: now_secs(),
+                lspid: 0,
+                lrpid: 0,
+                is_removed: false,
+            }),
+            send_wait_queue: WaitQueue::new(),
+            recv_wait_queue: WaitQueue::new(),
+        }
+    }
+
+    pub fn id(&self) -> key_t {
+        self.id
+    }
+
+    pub fn permission(&self) -> IpcPermission {
+        self.inner.lock().permission
+    }
+
+    /// Returns the total size in bytes of the messages in the queue.
+    pub fn cbytes(&self) -> usize {
+        self.inner.lock().cbytes
+    }
+
+    /// Returns the number of messages in the queue.
+    pub fn qnum(&self) -> usize {
+        self.inner.lock().messages.len()
+    }
+
+    /// Returns the maximum total size in bytes of the messages in the queue.
+    pub fn qbytes(&self) -> usize {
+        self.inner.lock().qbytes
+    }
+
+    pub fn stime(&self) -> Duration {
+        Duration::from_secs(self.inner.lock().stime)
+    }
+
+    pub fn rtime(&self) -> Duration {
+        Duration::from_secs(self.inner.lock().rtime)
+    }
+
+    pub fn ctime(&self) -> Duration {
+        Duration::from_secs(self.inner.lock().ctime)
+    }
+
+    pub fn lspid(&self) -> Pid {
+        self.inner.lock().lspid
+    }
+
+    pub fn lrpid(&self) -> Pid {
+        self.inner.lock().lrpid
+    }
+
+    /// Updates the owner, the permission mode and the maximum queue size, as
+    /// is done by `IPC_SET`.
+    pub fn set(
+        &self,
+        uid: Uid,
+        gid: Gid,
+        mode: u16,
+        qbytes: usize,
+        credentials: &Credentials<ReadOp>,
+    ) -> Result<()> {
+        if qbytes > MSGMNB
+            && !credentials
+                .effective_capset()
+                .contains(CapSet::SYS_RESOURCE)
+        {
+            return_errno_with_message!(Errno::EPERM, "the queue size exceeds MSGMNB");
+        }
+
+        {
+            let mut inner = self.inner.lock();
+            if !inner.permission.is_owner_or_creator(credentials) {
+                return_errno_with_message!(Errno::EPERM, "only the owner can change the queue");
+            }
+            inner.permission.set_owner_and_mode(uid, gid, mode);
+            inner.qbytes = qbytes;
+            inner.ctime = now_secs();
+        }
+
+        // The queue may have more free space now.
+        self.send_wait_queue.wake_all();
+
+        Ok(())
+    }
+
+    /// Sends a message to the queue.
+    ///
+    /// If there is not enough space in the queue, the caller is blocked until
+    /// the space is available, unless `IPC_NOWAIT` is specified.
+    pub fn send(&self, message: Message, flags: MsgFlags, pid: Pid) -> Result<()> {
+        let mut message = Some(message);
+
+        self.send_wait_queue.pause_until(|| {
+            let mut inner = self.inner.lock();
+            if inner.is_removed {
+                return Some(Err(Error::with_message(
+                    Errno::EIDRM,
+                    "the message queue is removed",
+                )));
+            }
+
+            // Like Linux, the number of messages is also limited by `qbytes`,
+            // so that the queue cannot be flooded with empty messages.
+            let len = message.as_ref().unwrap().data.len();
+            if inner.cbytes + len <= inner.qbytes && inner.messages.len() < inner.qbytes {
+                inner.cbytes += len;
+                inner.messages.push_back(message.take().unwrap());
+                inner.stime = now_secs();
+                inner.lspid = pid;
+                return Some(Ok(()));
+            }
+
+            if flags.contains(MsgFlags::IPC_NOWAIT) {
+                return Some(Err(Error::with_message(
+                    Errno::EAGAIN,
+                    "the message queue is full",
+                )));
+            }
+            None
+        })??;
+
+        self.recv_wait_queue.wake_all();
+
+        Ok(())
+    }
+
+    /// Receives a message that matches `msgtyp` from the queue.
+    ///
+    /// If there is no such message, the caller is blocked until one arrives,
+    /// unless `IPC_NOWAIT` is specified. If the message is longer than
+    /// `max_size`, it is truncated when `MSG_NOERROR` is specified, or it is
+    /// left in the queue otherwise.
+    pub fn receive(
+        &self,
+        msgtyp: i64,
+        max_size: usize,
+        flags: MsgFlags,
+        pid: Pid,
+    ) -> Result<Message> {
+        let mut message = self.recv_wait_queue.pause_until(|| {
+            let mut inner = self.inner.lock();
+            if inner.is_removed {
+                return Some(Err(Error::with_message(
+                    Errno::EIDRM,
+                    "the message queue is removed",
+                )));
+            }
+
+            let Some(index) = inner.find_message(msgtyp, flags) else {
+                if flags.contains(MsgFlags::IPC_NOWAIT) {
+                    return Some(Err(Error::with_message(
+                        Errno::ENOMSG,
+                        "no message of the requested type",
+                    )));
+                }
+                return None;
+            };
+
+            if inner.messages[index].data.len() > max_size && !flags.contains(MsgFlags::MSG_NOERROR)
+            {
+                return Some(Err(Error::with_message(
+                    Errno::E2BIG,
+                    "the message is too long",
+                )));
+            }
+
+            let message = inner.messages.remove(index).unwrap();
+            inner.cbytes -= message.data.len();
+            inner.rtime = now_secs();
+            inner.lrpid = pid;
+            Some(Ok(message))
+        })??;
+
+        self.send_wait_queue.wake_all();
+
+        if message.data.len() > max_size {
+            message.data = message.data[..max_size].into();
+        }
+
+        Ok(message)
+    }
+
+    /// Marks the queue as removed and wakes up all the blocked callers.
+    fn set_removed(&self) {
+        self.inner.lock().is_removed = true;
+        self.send_wait_queue.wake_all();
+        self.recv_wait_queue.wake_all();
+    }
+}
+
+/// The message queues in an IPC namespace.
+pub(in crate::ipc) struct MsgQueueTable {
+    id_allocator: SpinLock<IdAlloc>,
+    inner: RwLock<MsgQueueTableInner>,
+}
+
+struct MsgQueueTableInner {
+    /// The queues, keyed by their IDs
+    queues: BTreeMap<key_t, Arc<MsgQueue>>,
+    /// The IDs of the queues that are not private, keyed by their keys
+    key_to_id: BTreeMap<key_t, key_t>,
+}
+
+impl MsgQueueTable {
+    pub(in crate::ipc) fn new() -> Self {
+        let mut id_allocator = IdAlloc::with_capacity(MSGMNI + 1);
+        // Remove the first index 0
+        id_allocator.alloc();
+
+        Self {
+            id_allocator: SpinLock::new(id_allocator),
+            inner: RwLock::new(MsgQueueTableInner {
+                queues: BTreeMap::new(),
+                key_to_id: BTreeMap::new(),
+            }),
+        }
+    }
+
+    fn free_id(&self, id: key_t) {
+        self.id_allocator.lock().free(id as usize);
+    }
+}
+
+/// Creates a message queue with `key` and a newly allocated ID.
+///
+/// If `key` is `IPC_PRIVATE`, the queue can only be found by its ID.
+///
+/// Returns the ID of the queue.
+pub fn create_msg_queue(
+    ipc_ns: &IpcNamespace,
+    key: key_t,
+    mode: u16,
+    credentials: &Credentials<ReadOp>,
+) -> Result<key_t> {
+    let table = ipc_ns.msg_queues();
+    let id = table
+        .id_allocator
+        .lock()
+        .alloc()
+        .ok_or(Error::new(Errno::ENOSPC))? as key_t;
+    let msg_queue = Arc::new(MsgQueue::new(id, key, mode, credentials));
+
+    let mut table_inner = table.inner.write();
+    if key != IPC_PRIVATE {
+        // Another queue may have been created with the same key concurrently.
+        if table_inner.key_to_id.contains_key(&key) {
+            drop(table_inner);
+            table.free_id(id);
+            return_errno_with_message!(Errno::EEXIST, "the key is already in use");
+        }
+        table_inner.key_to_id.insert(key, id);
+    }
+    table_inner.queues.insert(id, msg_queue);
+
+    Ok(id)
+}
+
+/// Gets the message queue with the ID.
+pub fn get_msg_queue(ipc_ns: &IpcNamespace, id: key_t) -> Result<Arc<MsgQueue>> {
+    ipc_ns
+        .msg_queues()
+        .inner
+        .read()
+        .queues
+        .get(&id)
+        .cloned()
+        .ok_or(Error::new(Errno::ENOENT))
+}
+
+/// Finds the message queue with the key, which must not be `IPC_PRIVATE`.
+pub fn find_msg_queue_by_key(ipc_ns: &IpcNamespace, key: key_t) -> Option<Arc<MsgQueue>> {
+    debug_assert_ne!(key, IPC_PRIVATE);
+
+    let table_inner = ipc_ns.msg_queues().inner.read();
+    let id = table_inner.key_to_id.get(&key)?;
+    table_inner.queues.get(id).cloned()
+}
+
+/// Removes the message queue with the ID, as is done by `IPC_RMID`.
+///
+/// The callers blocked on the queue will fail with `EIDRM`.
+pub fn remove_msg_queue(
+    ipc_ns: &IpcNamespace,
+    id: key_t,
+    credentials: &Credentials<ReadOp>,
+) -> Result<()> {
+    let table = ipc_ns.msg_queues();
+    let msg_queue = {
+        let mut table_inner = table.inner.write();
+        let msg_queue = table_inner
+            .queues
+            .get(&id)
+            .ok_or(Error::new(Errno::EINVAL))?;
+        let permission = msg_queue.permission();
+        if !permission.is_owner_or_creator(credentials) {
+            return_errno_with_message!(Errno::EPERM, "only the owner can remove the queue");
+        }
+
+        let key = permission.key();
+        if key != IPC_PRIVATE {
+            table_inner.key_to_id.remove(&key);
+        }
+        table_inner.queues.remove(&id).unwrap()
+    };
+    table.free_id(id);
+
+    msg_queue.set_removed();
+
+    Ok(())
+}
+
+/// Returns all the message queues in the IPC namespace.
+pub fn msg_queues(ipc_ns: &IpcNamespace) -> Vec<Arc<MsgQueue>> {
+    ipc_ns
+        .msg_queues()
+        .inner
+        .read()
+        .queues
+        .values()
+        .cloned()
+        .collect()
+}
+
+fn now_secs() -> u64 {
+    RealTimeCoarseClock::get().read_time().as_secs()
+}
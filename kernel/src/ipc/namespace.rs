@@ -0,0 +1,65 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use spin::Once;
+
+use super::{
+    msg::msg_queue::MsgQueueTable, semaphore::system_v::sem_set::SemSetTable,
+    shm::shm_seg::ShmSegTable,
+};
+use crate::{
+    prelude::*,
+    process::namespace::{alloc_ns_id, UserNamespace},
+};
+
+/// An IPC namespace, which isolates the System V IPC objects.
+///
+/// POSIX message queues are not isolated yet, since they live in a file system.
+pub struct IpcNamespace {
+    id: u64,
+    owner: Arc<UserNamespace>,
+    msg_queues: MsgQueueTable,
+    sem_sets: SemSetTable,
+    shm_segs: ShmSegTable,
+}
+
+static INIT_IPC_NS: Once<Arc<IpcNamespace>> = Once::new();
+
+impl IpcNamespace {
+    /// Returns the initial IPC namespace.
+    pub fn get_init_singleton() -> &'static Arc<IpcNamespace> {
+        INIT_IPC_NS.call_once(|| Self::new(UserNamespace::get_init_singleton().clone()))
+    }
+
+    /// Creates an empty IPC namespace owned by `owner`.
+    pub fn new(owner: Arc<UserNamespace>) -> Arc<Self> {
+        Arc::new(Self {
+            id: alloc_ns_id(),
+            owner,
+            msg_queues: MsgQueueTable::new(),
+            sem_sets: SemSetTable::new(),
+            shm_segs: ShmSegTable::new(),
+        })
+    }
+
+    /// Returns the ID of the namespace.
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Returns the user namespace that owns the namespace.
+    pub fn owner(&self) -> &Arc<UserNamespace> {
+        &self.owner
+    }
+
+    pub(super) fn msg_queues(&self) -> &MsgQueueTable {
+        &self.msg_queues
+    }
+
+    pub(super) fn sem_sets(&self) -> &SemSetTable {
+        &self.sem_sets
+    }
+
+    pub(super) fn shm_segs(&self) -> &ShmSegTable {
+        &self.shm_segs
+    }
+}
@@ -153,7 +153,8 @@ pub fn sem_op(
         warn!("Found duplicate sop");
     }
 
-    let local_sem_sets = sem_sets();
+    let ipc_ns = ctx.thread_local.borrow_ns_proxy().unwrap().ipc_ns().clone();
+    let local_sem_sets = sem_sets(&ipc_ns);
     let sem_set = local_sem_sets
         .get(&sem_id)
         .ok_or(Error::new(Errno::EINVAL))?;
@@ -205,7 +206,7 @@ pub fn sem_op(
         Status::Removed => Err(Error::new(Errno::EIDRM)),
         Status::Pending => {
             // FIXME: Getting sem_sets maybe time-consuming.
-            let sem_sets = sem_sets();
+            let sem_sets = sem_sets(&ipc_ns);
             let sem_set = sem_sets.get(&sem_id).ok_or(Error::new(Errno::EINVAL))?;
             let mut inner = sem_set.inner();
 
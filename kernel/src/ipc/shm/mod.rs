@@ -0,0 +1,41 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! System V shared memory.
+//!
+//! Each shared memory segment is backed by an anonymous VMO. Attaching a
+//! segment maps a capability of the VMO into the address space of the caller
+//! as a shared mapping, so all attachers observe the same physical pages.
+
+use crate::prelude::*;
+
+pub mod shm_seg;
+
+// The following constant values are derived from the default values in Linux.
+
+/// Minimum size in bytes of a shared memory segment.
+pub const SHMMIN: usize = 1;
+/// Maximum size in bytes of a shared memory segment.
+pub const SHMMAX: usize = usize::MAX - (1 << 24);
+/// Maximum number of shared memory segments.
+pub const SHMMNI: usize = 4096;
+/// Maximum number of pages of all shared memory segments.
+pub const SHMALL: usize = usize::MAX - (1 << 24);
+/// Alignment of the attach addresses.
+pub const SHMLBA: usize = PAGE_SIZE;
+
+/// The mode bit indicating that the segment will be destroyed after the last detach.
+pub const SHM_DEST: u16 = 0o1000;
+
+bitflags! {
+    /// Flags for `shmat`.
+    pub struct ShmFlags: u32 {
+        /// Attach the segment for read-only access.
+        const SHM_RDONLY = 0o10000;
+        /// Round the attach address down to a multiple of `SHMLBA`.
+        const SHM_RND = 0o20000;
+        /// Take over the region on attach.
+        const SHM_REMAP = 0o40000;
+        /// Allow the segment to be executed.
+        const SHM_EXEC = 0o100000;
+    }
+}
@@ -0,0 +1,518 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use core::{
+    ops::Range,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
+
+use align_ext::AlignExt;
+use aster_rights::{Full, ReadOp, Rights};
+use id_alloc::IdAlloc;
+
+use super::{ShmFlags, SHMALL, SHMLBA, SHMMAX, SHMMIN, SHMMNI, SHM_DEST};
+use crate::{
+    ipc::{key_t, IpcNamespace, IpcPermission, IPC_PRIVATE},
+    prelude::*,
+    process::{Credentials, Gid, Pid, Uid},
+    time::clocks::RealTimeCoarseClock,
+    vm::{
+        perms::VmPerms,
+        vmar::Vmar,
+        vmo::{Vmo, VmoOptions},
+    },
+};
+
+#[derive(Debug)]
+pub struct ShmSegment {
+    /// ID of the segment
+    id: key_t,
+    /// Size in bytes, as is requested by `shmget`
+    size: usize,
+    /// The VMO that holds the pages of the segment
+    vmo: Vmo<Rights>,
+    /// Inner
+    inner: SpinLock<ShmSegmentInner>,
+}
+
+#[derive(Debug)]
+struct ShmSegmentInner {
+    /// Segment permission
+    permission: IpcPermission,
+    /// Number of current attaches
+    nattch: usize,
+    /// Whether the segment has been removed from the segment table
+    is_removed: bool,
+    /// Last attach time
+    atime: u64,
+    /// Last detach time
+    dtime: u64,
+    /// Creation time or last modification via `shmctl`
+    ctime: u64,
+    /// PID of the creator
+    cpid: Pid,
+    /// PID of the last `shmat` or `shmdt` caller
+    lpid: Pid,
+}
+
+impl ShmSegment {
+    fn new(
+        id: key_t,
+        key: key_t,
+        size: usize,
+        mode: u16,
+        credentials: &Credentials<ReadOp>,
+        pid: Pid,
+    ) -> Result<Self> {
+        let vmo = VmoOptions::<Rights>::new(size.align_up(PAGE_SIZE)).alloc()?;
+        let permission = IpcPermission::new(key, credentials.euid(), credentials.egid(), mode);
+
+        Ok(Self {
+            id,
+            size,
+            vmo,
+            inner: SpinLock::new(ShmSegmentInner {
+                permission,
+                nattch: 0,
+                is_removed: false,
+                atime: 0,
+                dtime: 0,
+                ctime: now_secs(),
+                cpid: pid,
+                lpid: 0,
+            }),
+        })
+    }
+
+    pub fn id(&self) -> key_t {
+        self.id
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Returns the number of pages that the segment occupies.
+    pub fn num_pages(&self) -> usize {
+        self.size.div_ceil(PAGE_SIZE)
+    }
+
+    /// Returns the number of pages that are currently resident in memory.
+    pub fn num_resident_pages(&self) -> usize {
+        self.vmo.num_committed_pages()
+    }
+
+    /// Returns the number of current attaches.
+    pub fn nattch(&self) -> usize {
+        self.inner.lock().nattch
+    }
+
+    pub fn permission(&self) -> IpcPermission {
+        self.inner.lock().permission
+    }
+
+    pub fn atime(&self) -> Duration {
+        Duration::from_secs(self.inner.lock().atime)
+    }
+
+    pub fn dtime(&self) -> Duration {
+        Duration::from_secs(self.inner.lock().dtime)
+    }
+
+    pub fn ctime(&self) -> Duration {
+        Duration::from_secs(self.inner.lock().ctime)
+    }
+
+    pub fn cpid(&self) -> Pid {
+        self.inner.lock().cpid
+    }
+
+    pub fn lpid(&self) -> Pid {
+        self.inner.lock().lpid
+    }
+
+    /// Updates the owner and the permission mode, as is done by `IPC_SET`.
+    pub fn set_owner_and_mode(&self, uid: Uid, gid: Gid, mode: u16) {
+        let mut inner = self.inner.lock();
+        inner.permission.set_owner_and_mode(uid, gid, mode);
+        inner.ctime = now_secs();
+    }
+
+    /// Attaches the segment to the address space of `root_vmar`.
+    ///
+    /// If `addr` is zero, the segment is attached at an address chosen by
+    /// the kernel. Otherwise, the segment is attached at `addr`, which must be
+    /// aligned to `SHMLBA` unless `SHM_RND` is specified.
+    ///
+    /// Returns the address where the segment is attached.
+    pub fn attach(
+        self: &Arc<Self>,
+        ipc_ns: &Arc<IpcNamespace>,
+        root_vmar: &Vmar<Full>,
+        addr: Vaddr,
+        flags: ShmFlags,
+        credentials: &Credentials<ReadOp>,
+        pid: Pid,
+    ) -> Result<Vaddr> {
+        let (mut perms, mut requested) = if flags.contains(ShmFlags::SHM_RDONLY) {
+            (VmPerms::READ, 0o4)
+        } else {
+            (VmPerms::READ | VmPerms::WRITE, 0o6)
+        };
+        if flags.contains(ShmFlags::SHM_EXEC) {
+            perms |= VmPerms::EXEC;
+            requested |= 0o1;
+        }
+        self.permission().check_access(credentials, requested)?;
+
+        let addr = if addr % SHMLBA == 0 {
+            addr
+        } else if flags.contains(ShmFlags::SHM_RND) {
+            addr.align_down(SHMLBA)
+        } else {
+            return_errno_with_message!(Errno::EINVAL, "the attach address is not aligned");
+        };
+
+        let map_size = self.size.align_up(PAGE_SIZE);
+        let mut options = root_vmar
+            .new_map(map_size, perms)?
+            .vmo(self.vmo.dup()?)
+            .is_shared(true);
+
+        if addr != 0 {
+            let end = addr.checked_add(map_size).ok_or_else(|| {
+                Error::with_message(Errno::EINVAL, "the attach address overflows")
+            })?;
+            if !flags.contains(ShmFlags::SHM_REMAP)
+                && root_vmar.query(addr..end).iter().next().is_some()
+            {
+                return_errno_with_message!(Errno::EINVAL, "the attach region is already mapped");
+            }
+            options = options
+                .offset(addr)
+                .can_overwrite(flags.contains(ShmFlags::SHM_REMAP));
+        } else if flags.contains(ShmFlags::SHM_REMAP) {
+            return_errno_with_message!(Errno::EINVAL, "SHM_REMAP requires an attach address");
+        }
+
+        let map_addr = options
+            .shm_attach(ShmAttach::new(ipc_ns.clone(), self.clone())?)
+            .build()?;
+
+        let mut inner = self.inner.lock();
+        inner.atime = now_secs();
+        inner.lpid = pid;
+
+        Ok(map_addr)
+    }
+}
+
+impl Drop for ShmSegment {
+    fn drop(&mut self) {
+        TOTAL_PAGES.fetch_sub(self.num_pages(), Ordering::Relaxed);
+    }
+}
+
+/// An attach of a shared memory segment.
+///
+/// An attach is created by `shmat` and is shared by the mappings that it
+/// creates, including those split from them by `mprotect` or `munmap`. Forking
+/// duplicates the attach. The segment is detached once all the mappings of the
+/// attach are unmapped, e.g., by `shmdt`, `munmap`, `execve` or exiting.
+pub struct ShmAttach {
+    ipc_ns: Arc<IpcNamespace>,
+    shm_seg: Arc<ShmSegment>,
+}
+
+impl ShmAttach {
+    fn new(ipc_ns: Arc<IpcNamespace>, shm_seg: Arc<ShmSegment>) -> Result<Arc<Self>> {
+        {
+            let mut inner = shm_seg.inner.lock();
+            if inner.is_removed {
+                return_errno_with_message!(Errno::EIDRM, "the segment has been removed");
+            }
+            inner.nattch += 1;
+        }
+
+        Ok(Arc::new(Self { ipc_ns, shm_seg }))
+    }
+
+    /// Duplicates the attach for a forked address space.
+    pub fn fork(&self) -> Arc<Self> {
+        // The segment cannot be removed while `self` is attached.
+        self.shm_seg.inner.lock().nattch += 1;
+
+        Arc::new(Self {
+            ipc_ns: self.ipc_ns.clone(),
+            shm_seg: self.shm_seg.clone(),
+        })
+    }
+}
+
+impl Debug for ShmAttach {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("ShmAttach")
+            .field("id", &self.shm_seg.id)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for ShmAttach {
+    fn drop(&mut self) {
+        let table = self.ipc_ns.shm_segs();
+
+        // Lock the table first, so that the segment cannot be attached again by
+        // its ID between the last detach and the removal.
+        let mut table_inner = table.inner.write();
+        let mut inner = self.shm_seg.inner.lock();
+        inner.nattch -= 1;
+        inner.dtime = now_secs();
+
+        if inner.nattch > 0 || inner.permission.mode() & SHM_DEST == 0 || inner.is_removed {
+            return;
+        }
+        inner.is_removed = true;
+        drop(inner);
+
+        // The segment has been marked by `IPC_RMID`, so it is destroyed on the last detach.
+        let removed = table_inner.segments.remove(&self.shm_seg.id);
+        drop(table_inner);
+        table.free_id(self.shm_seg.id);
+        drop(removed);
+    }
+}
+
+/// Detaches the segment attached at `addr` from the address space of `root_vmar`.
+pub fn detach_shm_seg(root_vmar: &Vmar<Full>, addr: Vaddr, pid: Pid) -> Result<()> {
+    if addr % PAGE_SIZE != 0 {
+        return_errno_with_message!(Errno::EINVAL, "the detach address is not aligned");
+    }
+
+    let shm_attach = {
+        let query_guard = root_vmar.query(addr..addr + 1);
+        query_guard
+            .iter()
+            .find(|mapping| mapping.map_to_addr() == addr && mapping.vmo_offset() == Some(0))
+            .and_then(|mapping| mapping.shm_attach().cloned())
+            .ok_or_else(|| {
+                Error::with_message(Errno::EINVAL, "no segment is attached at the address")
+            })?
+    };
+    let shm_seg = shm_attach.shm_seg.clone();
+
+    // The attached region may have been partially unmapped or split into
+    // multiple mappings, so remove every mapping that belongs to this attach.
+    let end = addr.saturating_add(shm_seg.size.align_up(PAGE_SIZE));
+    let ranges: Vec<Range<Vaddr>> = root_vmar
+        .query(addr..end)
+        .iter()
+        .filter(|mapping| {
+            mapping
+                .shm_attach()
+                .is_some_and(|attach| Arc::ptr_eq(attach, &shm_attach))
+                && mapping.vmo_offset() == Some(mapping.map_to_addr() - addr)
+        })
+        .map(|mapping| mapping.map_to_addr()..mapping.map_end())
+        .collect();
+
+    shm_seg.inner.lock().lpid = pid;
+    // The attach is detached when the last reference to it is dropped.
+    drop(shm_attach);
+
+    for range in ranges {
+        root_vmar.remove_mapping(range)?;
+    }
+
+    Ok(())
+}
+
+/// The shared memory segments in an IPC namespace.
+pub(in crate::ipc) struct ShmSegTable {
+    id_allocator: SpinLock<IdAlloc>,
+    inner: RwLock<ShmSegTableInner>,
+}
+
+struct ShmSegTableInner {
+    /// The segments, keyed by their IDs
+    segments: BTreeMap<key_t, Arc<ShmSegment>>,
+    /// The IDs of the segments that are not private, keyed by their keys
+    key_to_id: BTreeMap<key_t, key_t>,
+}
+
+impl ShmSegTable {
+    pub(in crate::ipc) fn new() -> Self {
+        let mut id_allocator = IdAlloc::with_capacity(SHMMNI + 1);
+        // Remove the first index 0
+        id_allocator.alloc();
+
+        Self {
+            id_allocator: SpinLock::new(id_allocator),
+            inner: RwLock::new(ShmSegTableInner {
+                segments: BTreeMap::new(),
+                key_to_id: BTreeMap::new(),
+            }),
+        }
+    }
+
+    fn free_id(&self, id: key_t) {
+        self.id_allocator.lock().free(id as usize);
+    }
+}
+
+/// Creates a segment with `key` and a newly allocated ID.
+///
+/// If `key` is `IPC_PRIVATE`, the segment can only be found by its ID.
+///
+/// Returns the ID of the segment.
+pub fn create_shm_seg(
+    ipc_ns: &IpcNamespace,
+    key: key_t,
+    size: usize,
+    mode: u16,
+    credentials: &Credentials<ReadOp>,
+    pid: Pid,
+) -> Result<key_t> {
+    check_size(size)?;
+
+    let table = ipc_ns.shm_segs();
+    let id = table
+        .id_allocator
+        .lock()
+        .alloc()
+        .ok_or(Error::new(Errno::ENOSPC))? as key_t;
+
+    insert_shm_seg(table, id, key, size, mode, credentials, pid)
+        .inspect_err(|_| table.free_id(id))?;
+
+    Ok(id)
+}
+
+/// Gets the segment with the ID.
+pub fn get_shm_seg(ipc_ns: &IpcNamespace, id: key_t) -> Result<Arc<ShmSegment>> {
+    ipc_ns
+        .shm_segs()
+        .inner
+        .read()
+        .segments
+        .get(&id)
+        .cloned()
+        .ok_or(Error::new(Errno::ENOENT))
+}
+
+/// Finds the segment with the key, which must not be `IPC_PRIVATE`.
+pub fn find_shm_seg_by_key(ipc_ns: &IpcNamespace, key: key_t) -> Option<Arc<ShmSegment>> {
+    debug_assert_ne!(key, IPC_PRIVATE);
+
+    let table_inner = ipc_ns.shm_segs().inner.read();
+    let id = table_inner.key_to_id.get(&key)?;
+    table_inner.segments.get(id).cloned()
+}
+
+/// Removes the segment with the ID, as is done by `IPC_RMID`.
+///
+/// If the segment is still attached, it is marked with `SHM_DEST` and its
+/// key becomes private. The segment is destroyed on the last detach.
+pub fn remove_shm_seg(
+    ipc_ns: &IpcNamespace,
+    id: key_t,
+    credentials: &Credentials<ReadOp>,
+) -> Result<()> {
+    let table = ipc_ns.shm_segs();
+    let removed = {
+        let mut table_inner = table.inner.write();
+        let shm_seg = table_inner
+            .segments
+            .get(&id)
+            .cloned()
+            .ok_or(Error::new(Errno::EINVAL))?;
+
+        let mut inner = shm_seg.inner.lock();
+        if !inner.permission.is_owner_or_creator(credentials) {
+            return_errno_with_message!(Errno::EPERM, "only the owner can remove the segment");
+        }
+
+        let key = inner.permission.key();
+        if key != IPC_PRIVATE {
+            table_inner.key_to_id.remove(&key);
+        }
+        inner.permission.set_key_private();
+        inner.permission.set_mode_flags(SHM_DEST);
+        inner.ctime = now_secs();
+
+        if inner.nattch > 0 {
+            return Ok(());
+        }
+        inner.is_removed = true;
+        drop(inner);
+
+        table_inner.segments.remove(&id)
+    };
+    table.free_id(id);
+    drop(removed);
+
+    Ok(())
+}
+
+/// Returns all the segments in the IPC namespace.
+pub fn shm_segs(ipc_ns: &IpcNamespace) -> Vec<Arc<ShmSegment>> {
+    ipc_ns
+        .shm_segs()
+        .inner
+        .read()
+        .segments
+        .values()
+        .cloned()
+        .collect()
+}
+
+fn insert_shm_seg(
+    table: &ShmSegTable,
+    id: key_t,
+    key: key_t,
+    size: usize,
+    mode: u16,
+    credentials: &Credentials<ReadOp>,
+    pid: Pid,
+) -> Result<()> {
+    let num_pages = size.div_ceil(PAGE_SIZE);
+    TOTAL_PAGES
+        .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |total| {
+            total
+                .checked_add(num_pages)
+                .filter(|total| *total <= SHMALL)
+        })
+        .map_err(|_| Error::with_message(Errno::ENOSPC, "the total size exceeds SHMALL"))?;
+
+    // From now on, `TOTAL_PAGES` is restored by dropping the segment on errors.
+    let shm_seg = Arc::new(
+        ShmSegment::new(id, key, size, mode, credentials, pid).inspect_err(|_| {
+            TOTAL_PAGES.fetch_sub(num_pages, Ordering::Relaxed);
+        })?,
+    );
+
+    let mut table_inner = table.inner.write();
+    if key != IPC_PRIVATE {
+        // Another segment may have been created with the same key concurrently.
+        if table_inner.key_to_id.contains_key(&key) {
+            return_errno_with_message!(Errno::EEXIST, "the key is already in use");
+        }
+        table_inner.key_to_id.insert(key, id);
+    }
+    table_inner.segments.insert(id, shm_seg);
+
+    Ok(())
+}
+
+fn check_size(size: usize) -> Result<()> {
+    if !(SHMMIN..=SHMMAX).contains(&size) {
+        return_errno_with_message!(Errno::EINVAL, "the segment size is out of range");
+    }
+    Ok(())
+}
+
+fn now_secs() -> u64 {
+    RealTimeCoarseClock::get().read_time().as_secs()
+}
+
+/// Total number of pages of all shared memory segments
+static TOTAL_PAGES: AtomicUsize = AtomicUsize::new(0);
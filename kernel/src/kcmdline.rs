@@ -161,6 +161,10 @@ impl From<&str> for KCmdlineArg {
                         }
                         result.initproc.path = Some(value.to_string());
                     }
+                    "ip" => {
+                        // The IP autoconfiguration is handled by the network stack, so it is
+                        // not passed to the initproc.
+                    }
                     _ => {
                         // If the option is not recognized, it is passed to the initproc.
                         // Pattern 'option=value' is treated as the init environment.
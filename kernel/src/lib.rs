@@ -100,17 +100,19 @@ pub fn init() {
     util::random::init();
     driver::init();
     time::init();
+    vm::init();
     #[cfg(target_arch = "x86_64")]
     net::init();
     sched::init();
     fs::rootfs::init(boot_info().initramfs.expect("No initramfs found!")).unwrap();
     device::init().unwrap();
-    syscall::init();
     vdso::init();
     process::init();
 }
 
 fn ap_init() {
+    sched::cpu_time::init_on_current_cpu();
+
     fn ap_idle_thread() {
         log::info!(
             "Kernel idle thread for CPU #{} started.",
@@ -138,7 +140,6 @@ fn init_thread() {
     #[cfg(target_arch = "x86_64")]
     net::lazy_init();
     fs::lazy_init();
-    ipc::init();
     // driver::pci::virtio::block::block_device_test();
     let thread = ThreadOptions::new(|| {
         println!("[kernel] Hello world from kernel!");
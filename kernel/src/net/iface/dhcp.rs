@@ -0,0 +1,220 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The in-kernel DHCPv4 client.
+//!
+//! Like the IP autoconfiguration of Linux, the client is enabled by `ip=dhcp` (or `ip=on`,
+//! `ip=any`) on the kernel command line. It configures the address and the default route of the
+//! virtio interface as leases are acquired and lost.
+//!
+//! Reference: <https://www.kernel.org/doc/html/v6.13/admin-guide/nfs/nfsroot.html>
+
+use core::time::Duration;
+
+use aster_bigtcp::{
+    iface::Ipv4Route,
+    socket::{DhcpConfig, DhcpEvent},
+    wire::{Ipv4Address, Ipv4Cidr},
+};
+use ostd::{boot::boot_info, sync::Waiter};
+use spin::Once;
+
+use super::{ext::BigtcpExt, Iface};
+use crate::{
+    events::IoEvents,
+    net::socket::{
+        ip::DatagramObserver,
+        netlink::{notify_del_addr, notify_del_route, notify_new_addr, notify_new_route},
+    },
+    prelude::*,
+    process::signal::{PollHandle, Pollable, Pollee},
+    thread::kernel_thread::ThreadOptions,
+};
+
+type DhcpSocket = aster_bigtcp::socket::DhcpSocket<BigtcpExt>;
+
+/// Returns whether the kernel command line asks for configuring the network via DHCP.
+pub(super) fn is_enabled() -> bool {
+    static IS_ENABLED: Once<bool> = Once::new();
+
+    *IS_ENABLED.call_once(|| {
+        let Some(value) = boot_info()
+            .kernel_cmdline
+            .split_whitespace()
+            .take_while(|arg| *arg != "--")
+            .filter_map(|arg| arg.strip_prefix("ip="))
+            .last()
+        else {
+            return false;
+        };
+
+        // The value is either the autoconfiguration method or a colon-separated list in the form
+        // of `<client-ip>:<server-ip>:<gw-ip>:<netmask>:<hostname>:<device>:<autoconf>:...`.
+        let autoconf = value.split(':').nth(6).unwrap_or(value);
+        matches!(autoconf, "dhcp" | "on" | "any")
+    })
+}
+
+/// The delay before the DHCP client waits for events again after failing to do so.
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// Starts the DHCP client on `iface` in a kernel thread.
+pub(super) fn spawn_dhcp_client(iface: Arc<Iface>) {
+    let task_fn = move || {
+        let client = match DhcpClient::new(iface) {
+            Ok(client) => client,
+            Err(err) => {
+                warn!("failed to start the DHCP client: {:?}", err);
+                return;
+            }
+        };
+
+        loop {
+            let event = match client.wait_events(IoEvents::IN, None, || client.try_recv_event()) {
+                Ok(event) => event,
+                Err(err) => {
+                    warn!("failed to wait for DHCP events: {:?}", err);
+                    let waiter = Waiter::new_pair().0;
+                    let _ = waiter.wait_until_or_timeout(|| -> Option<()> { None }, &RETRY_DELAY);
+                    continue;
+                }
+            };
+            client.handle_event(event);
+        }
+    };
+
+    ThreadOptions::new(task_fn).spawn();
+}
+
+/// Returns the configuration of the current lease, if any.
+pub fn dhcp_lease() -> Option<DhcpConfig> {
+    DHCP_LEASE.lock().clone()
+}
+
+/// The configuration of the current lease, which has been applied to the iface.
+//
+// This is a `Mutex` because the netlink notifications are sent while holding the lock, so that
+// they are sent in the same order as the configuration changes.
+static DHCP_LEASE: Mutex<Option<DhcpConfig>> = Mutex::new(None);
+
+struct DhcpClient {
+    socket: DhcpSocket,
+    pollee: Pollee,
+}
+
+impl DhcpClient {
+    fn new(iface: Arc<Iface>) -> Result<Self> {
+        let pollee = Pollee::new();
+        let observer = DatagramObserver::new(pollee.clone());
+
+        let Ok(socket) = DhcpSocket::new_bind(iface, observer) else {
+            return_errno_with_message!(Errno::EADDRINUSE, "the DHCP client is already running");
+        };
+        socket.iface().poll();
+
+        Ok(Self { socket, pollee })
+    }
+
+    fn try_recv_event(&self) -> Result<DhcpEvent> {
+        let event = self.socket.poll_event();
+        self.pollee.invalidate();
+
+        event.ok_or_else(|| Error::with_message(Errno::EAGAIN, "no DHCP event is available"))
+    }
+
+    fn handle_event(&self, event: DhcpEvent) {
+        let iface = self.socket.iface();
+        let mut lease = DHCP_LEASE.lock();
+
+        let new_config = match event {
+            DhcpEvent::Configured(config) => Some(config),
+            DhcpEvent::Deconfigured => None,
+        };
+        let old_config = lease.take();
+
+        let old_address = old_config.as_ref().map(|config| config.address);
+        let new_address = new_config.as_ref().map(|config| config.address);
+        let old_route = old_config.as_ref().and_then(default_route);
+        let new_route = new_config.as_ref().and_then(default_route);
+
+        if old_route != new_route {
+            if let Some(route) = old_route {
+                del_route(iface, route);
+            }
+        }
+        if old_address != new_address {
+            if let Some(cidr) = old_address {
+                del_address(iface, cidr);
+            }
+            if let Some(cidr) = new_address {
+                add_address(iface, cidr);
+            }
+        }
+        if old_route != new_route {
+            if let Some(route) = new_route {
+                add_route(iface, route);
+            }
+        }
+
+        match new_config.as_ref() {
+            Some(config) => info!(
+                "DHCP: {} configured with {} (gateway: {:?})",
+                iface.name(),
+                config.address,
+                config.router
+            ),
+            None => info!("DHCP: {} deconfigured", iface.name()),
+        }
+
+        *lease = new_config;
+    }
+}
+
+impl Pollable for DhcpClient {
+    fn poll(&self, mask: IoEvents, poller: Option<&mut PollHandle>) -> IoEvents {
+        self.pollee.poll_with(mask, poller, || {
+            if self.socket.has_event() {
+                IoEvents::IN
+            } else {
+                IoEvents::empty()
+            }
+        })
+    }
+}
+
+fn default_route(config: &DhcpConfig) -> Option<Ipv4Route> {
+    config.router.map(|gateway| Ipv4Route {
+        cidr: Ipv4Cidr::new(Ipv4Address::UNSPECIFIED, 0),
+        gateway,
+    })
+}
+
+fn add_address(iface: &Arc<Iface>, cidr: Ipv4Cidr) {
+    match iface.add_ipv4_cidr(cidr) {
+        Ok(()) => notify_new_addr(iface, cidr),
+        Err(err) => warn!("DHCP: failed to add the address {}: {:?}", cidr, err),
+    }
+}
+
+fn del_address(iface: &Arc<Iface>, cidr: Ipv4Cidr) {
+    // The address may have been deleted by the user.
+    if iface.del_ipv4_cidr(cidr).is_ok() {
+        notify_del_addr(iface, cidr);
+    }
+}
+
+fn add_route(iface: &Arc<Iface>, route: Ipv4Route) {
+    match iface.add_ipv4_route(route) {
+        Ok(()) => notify_new_route(iface, route),
+        Err(err) => warn!("DHCP: failed to add the route {:?}: {:?}", route, err),
+    }
+}
+
+fn del_route(iface: &Arc<Iface>, route: Ipv4Route) {
+    // The route may have been deleted by the user.
+    if iface
+        .del_ipv4_route(route.cidr, Some(route.gateway))
+        .is_ok()
+    {
+        notify_del_route(iface, route);
+    }
+}
@@ -10,7 +10,11 @@ use aster_bigtcp::{
 use aster_softirq::BottomHalfDisabled;
 use spin::Once;
 
-use super::{poll::poll_ifaces, Iface};
+use super::{
+    dhcp,
+    poll::{poll_ifaces, spawn_background_poll_thread},
+    Iface,
+};
 use crate::{net::iface::sched::PollScheduler, prelude::*};
 
 static IFACES: Once<Vec<Arc<Iface>>> = Once::new();
@@ -54,6 +58,15 @@ pub fn init() {
     poll_ifaces();
 }
 
+/// Creates a loopback interface for a new network namespace and starts polling it.
+pub fn new_ns_loopback() -> Arc<Iface> {
+    let iface = new_loopback();
+    // FIXME: The background poll thread holds the interface forever, so the interface is leaked
+    // even after the network namespace is dropped.
+    spawn_background_poll_thread(iface.clone());
+    iface
+}
+
 fn new_virtio() -> Option<Arc<Iface>> {
     use aster_bigtcp::{
         iface::EtherIface,
@@ -92,11 +105,21 @@ fn new_virtio() -> Option<Arc<Iface>> {
         | InterfaceFlags::MULTICAST
         | InterfaceFlags::LOWER_UP;
 
+    // If DHCP is enabled, the address and the gateway will be configured by the DHCP client.
+    let (ip_cidr, gateway) = if dhcp::is_enabled() {
+        (None, None)
+    } else {
+        (
+            Some(Ipv4Cidr::new(VIRTIO_ADDRESS, VIRTIO_ADDRESS_PREFIX_LEN)),
+            Some(VIRTIO_GATEWAY),
+        )
+    };
+
     Some(EtherIface::new(
         Wrapper(virtio_net),
         EthernetAddress(ether_addr),
-        Ipv4Cidr::new(VIRTIO_ADDRESS, VIRTIO_ADDRESS_PREFIX_LEN),
-        VIRTIO_GATEWAY,
+        ip_cidr,
+        gateway,
         "eth0".to_owned(),
         PollScheduler::new(),
         flags,
@@ -107,11 +130,13 @@ fn new_loopback() -> Arc<Iface> {
     use aster_bigtcp::{
         device::{Loopback, Medium},
         iface::IpIface,
-        wire::{Ipv4Address, Ipv4Cidr},
+        wire::{Ipv4Address, Ipv4Cidr, Ipv6Address, Ipv6Cidr},
     };
 
     const LOOPBACK_ADDRESS: Ipv4Address = Ipv4Address::new(127, 0, 0, 1);
     const LOOPBACK_ADDRESS_PREFIX_LEN: u8 = 8; // mask: 255.0.0.0
+    const LOOPBACK_IPV6_ADDRESS: Ipv6Address = Ipv6Address::LOCALHOST;
+    const LOOPBACK_IPV6_ADDRESS_PREFIX_LEN: u8 = 128;
 
     struct Wrapper(Mutex<Loopback>);
 
@@ -134,12 +159,21 @@ fn new_loopback() -> Arc<Iface> {
         | InterfaceFlags::RUNNING
         | InterfaceFlags::LOWER_UP;
 
-    IpIface::new(
+    let iface = IpIface::new(
         Wrapper(Mutex::new(Loopback::new(Medium::Ip))),
         Ipv4Cidr::new(LOOPBACK_ADDRESS, LOOPBACK_ADDRESS_PREFIX_LEN),
         "lo".to_owned(),
         PollScheduler::new(),
         InterfaceType::LOOPBACK,
         flags,
-    ) as Arc<Iface>
+    ) as Arc<Iface>;
+
+    iface
+        .add_ipv6_cidr(Ipv6Cidr::new(
+            LOOPBACK_IPV6_ADDRESS,
+            LOOPBACK_IPV6_ADDRESS_PREFIX_LEN,
+        ))
+        .unwrap();
+
+    iface
 }
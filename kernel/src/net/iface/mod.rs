@@ -1,11 +1,13 @@
 // SPDX-License-Identifier: MPL-2.0
 
+mod dhcp;
 mod ext;
 mod init;
 mod poll;
 mod sched;
 
-pub use init::{init, iter_all_ifaces, loopback_iface, virtio_iface};
+pub use dhcp::dhcp_lease;
+pub use init::{init, iter_all_ifaces, loopback_iface, new_ns_loopback, virtio_iface};
 pub use poll::lazy_init;
 
 pub type Iface = dyn aster_bigtcp::iface::Iface<ext::BigtcpExt>;
@@ -16,3 +18,6 @@ pub type RawTcpSocketExt = aster_bigtcp::socket::RawTcpSocketExt<ext::BigtcpExt>
 pub type TcpConnection = aster_bigtcp::socket::TcpConnection<ext::BigtcpExt>;
 pub type TcpListener = aster_bigtcp::socket::TcpListener<ext::BigtcpExt>;
 pub type UdpSocket = aster_bigtcp::socket::UdpSocket<ext::BigtcpExt>;
+pub type IcmpSocket = aster_bigtcp::socket::IcmpSocket<ext::BigtcpExt>;
+pub type RawIpSocket = aster_bigtcp::socket::RawIpSocket<ext::BigtcpExt>;
+pub type PacketSocket = aster_bigtcp::socket::PacketSocket<ext::BigtcpExt>;
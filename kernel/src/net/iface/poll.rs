@@ -6,7 +6,7 @@ use core::time::Duration;
 use log::trace;
 use ostd::timer::Jiffies;
 
-use super::{iter_all_ifaces, Iface};
+use super::{dhcp, iter_all_ifaces, virtio_iface, Iface};
 use crate::{
     sched::{Nice, SchedPolicy},
     thread::kernel_thread::ThreadOptions,
@@ -17,6 +17,10 @@ pub fn lazy_init() {
     for iface in iter_all_ifaces() {
         spawn_background_poll_thread(iface.clone());
     }
+
+    if let Some(iface) = virtio_iface().filter(|_| dhcp::is_enabled()) {
+        dhcp::spawn_dhcp_client(iface.clone());
+    }
 }
 
 pub(super) fn poll_ifaces() {
@@ -25,7 +29,7 @@ pub(super) fn poll_ifaces() {
     }
 }
 
-fn spawn_background_poll_thread(iface: Arc<Iface>) {
+pub(super) fn spawn_background_poll_thread(iface: Arc<Iface>) {
     let task_fn = move || {
         trace!("spawn background poll thread for {}", iface.name());
 
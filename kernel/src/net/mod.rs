@@ -1,10 +1,14 @@
 // SPDX-License-Identifier: MPL-2.0
 
 pub mod iface;
+mod namespace;
 pub mod socket;
 
+pub use namespace::NetNamespace;
+
 pub fn init() {
     iface::init();
+    socket::ip::init();
     socket::netlink::init();
     socket::vsock::init();
 }
@@ -0,0 +1,82 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use ostd::task::Task;
+use spin::Once;
+
+use super::iface::{iter_all_ifaces, new_ns_loopback, Iface};
+use crate::{
+    prelude::*,
+    process::namespace::{alloc_ns_id, UserNamespace},
+};
+
+/// A network namespace, which isolates the network interfaces.
+///
+/// The initial namespace owns all the interfaces backed by devices, while a new namespace only
+/// has its own loopback interface.
+pub struct NetNamespace {
+    id: u64,
+    /// The interfaces, where the first one is always the loopback interface.
+    ifaces: Vec<Arc<Iface>>,
+    owner: Arc<UserNamespace>,
+}
+
+static INIT_NET_NS: Once<Arc<NetNamespace>> = Once::new();
+
+impl NetNamespace {
+    /// Returns the initial network namespace.
+    pub fn get_init_singleton() -> &'static Arc<NetNamespace> {
+        INIT_NET_NS.call_once(|| {
+            Arc::new(Self {
+                id: alloc_ns_id(),
+                ifaces: iter_all_ifaces().cloned().collect(),
+                owner: UserNamespace::get_init_singleton().clone(),
+            })
+        })
+    }
+
+    /// Creates a network namespace owned by `owner`, which has only a loopback interface.
+    pub fn new(owner: Arc<UserNamespace>) -> Arc<Self> {
+        Arc::new(Self {
+            id: alloc_ns_id(),
+            ifaces: vec![new_ns_loopback()],
+            owner,
+        })
+    }
+
+    /// Returns the network namespace of the current thread.
+    pub fn current() -> Arc<NetNamespace> {
+        let current = Task::current().unwrap();
+        let ns_proxy = current.as_thread_local().unwrap().borrow_ns_proxy();
+        ns_proxy.unwrap().net_ns().clone()
+    }
+
+    /// Returns the ID of the namespace.
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Returns the user namespace that owns the namespace.
+    pub fn owner(&self) -> &Arc<UserNamespace> {
+        &self.owner
+    }
+
+    /// Returns the interfaces in the namespace.
+    pub fn ifaces(&self) -> &[Arc<Iface>] {
+        &self.ifaces
+    }
+
+    /// Returns the loopback interface of the namespace.
+    pub fn loopback_iface(&self) -> &Arc<Iface> {
+        &self.ifaces[0]
+    }
+
+    /// Returns the default interface used to reach remote addresses.
+    ///
+    /// This is the first non-loopback interface, or the loopback interface if there is none.
+    //
+    // FIXME: Instead of hardcoding the rules here, we should choose the
+    // default interface according to the routing table.
+    pub fn default_iface(&self) -> &Arc<Iface> {
+        self.ifaces.get(1).unwrap_or(&self.ifaces[0])
+    }
+}
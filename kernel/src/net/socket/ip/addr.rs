@@ -1,37 +1,97 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use aster_bigtcp::wire::{IpAddress, IpEndpoint, Ipv4Address};
+use aster_bigtcp::wire::{IpAddress, IpEndpoint, Ipv4Address, Ipv6Address};
 
 use crate::{net::socket::util::SocketAddr, prelude::*, return_errno_with_message};
 
-impl TryFrom<SocketAddr> for IpEndpoint {
-    type Error = Error;
+/// The address family of an IP socket.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IpFamily {
+    /// An `AF_INET` socket.
+    Ipv4,
+    /// An `AF_INET6` socket.
+    Ipv6,
+}
+
+impl IpFamily {
+    /// Converts the socket address to which the socket will be bound to a local endpoint.
+    ///
+    /// For `AF_INET6` sockets, IPv4-mapped IPv6 addresses are converted to IPv4 endpoints. If
+    /// `v6only` is set, they are rejected with `EINVAL`.
+    pub(super) fn local_endpoint(
+        self,
+        socket_addr: SocketAddr,
+        v6only: bool,
+    ) -> Result<IpEndpoint> {
+        self.endpoint(socket_addr, v6only, Errno::EINVAL)
+    }
+
+    /// Converts the socket address to which the socket will connect or send to a remote endpoint.
+    ///
+    /// For `AF_INET6` sockets, IPv4-mapped IPv6 addresses are converted to IPv4 endpoints. If
+    /// `v6only` is set, they are rejected with `ENETUNREACH`.
+    pub(super) fn remote_endpoint(
+        self,
+        socket_addr: SocketAddr,
+        v6only: bool,
+    ) -> Result<IpEndpoint> {
+        self.endpoint(socket_addr, v6only, Errno::ENETUNREACH)
+    }
 
-    fn try_from(value: SocketAddr) -> Result<Self> {
-        match value {
-            SocketAddr::IPv4(addr, port) => Ok(IpEndpoint::new(addr.into(), port)),
+    fn endpoint(
+        self,
+        socket_addr: SocketAddr,
+        v6only: bool,
+        mapped_errno: Errno,
+    ) -> Result<IpEndpoint> {
+        match (self, socket_addr) {
+            (IpFamily::Ipv4, SocketAddr::IPv4(addr, port)) => {
+                Ok(IpEndpoint::new(addr.into(), port))
+            }
+            (IpFamily::Ipv6, SocketAddr::IPv6(addr, port)) => match addr.to_ipv4_mapped() {
+                Some(_) if v6only => return_errno_with_message!(
+                    mapped_errno,
+                    "IPv4-mapped addresses are disabled by `IPV6_V6ONLY`"
+                ),
+                Some(ipv4_addr) => Ok(IpEndpoint::new(ipv4_addr.into(), port)),
+                None => Ok(IpEndpoint::new(addr.into(), port)),
+            },
+            // In Linux, `struct sockaddr_in` is rejected by `AF_INET6` sockets because it is
+            // shorter than `SIN6_LEN_RFC2133`.
+            (IpFamily::Ipv6, SocketAddr::IPv4(..)) => {
+                return_errno_with_message!(Errno::EINVAL, "the socket address length is too small")
+            }
             _ => return_errno_with_message!(
                 Errno::EAFNOSUPPORT,
                 "the address is in an unsupported address family"
             ),
         }
     }
-}
 
-impl From<IpEndpoint> for SocketAddr {
-    fn from(endpoint: IpEndpoint) -> Self {
+    /// Converts the endpoint to a socket address of this address family.
+    ///
+    /// For `AF_INET6` sockets, IPv4 endpoints are reported as IPv4-mapped IPv6 addresses.
+    pub(super) fn socket_addr(self, endpoint: IpEndpoint) -> SocketAddr {
         let port = endpoint.port;
-        match endpoint.addr {
-            IpAddress::Ipv4(addr) => SocketAddr::IPv4(addr, port),
-            // TODO: support IPv6
+        match (self, endpoint.addr) {
+            (IpFamily::Ipv4, IpAddress::Ipv4(addr)) => SocketAddr::IPv4(addr, port),
+            (IpFamily::Ipv6, IpAddress::Ipv4(addr)) => {
+                SocketAddr::IPv6(addr.to_ipv6_mapped(), port)
+            }
+            (_, IpAddress::Ipv6(addr)) => SocketAddr::IPv6(addr, port),
         }
     }
-}
 
-/// A local endpoint, which indicates that the local endpoint is unspecified.
-///
-/// According to the Linux man pages and the Linux implementation, `getsockname()` will _not_ fail
-/// even if the socket is unbound. Instead, it will return an unspecified socket address. This
-/// unspecified endpoint helps with that.
-pub(super) const UNSPECIFIED_LOCAL_ENDPOINT: IpEndpoint =
-    IpEndpoint::new(IpAddress::Ipv4(Ipv4Address::UNSPECIFIED), 0);
+    /// Returns a local endpoint, which indicates that the local endpoint is unspecified.
+    ///
+    /// According to the Linux man pages and the Linux implementation, `getsockname()` will _not_
+    /// fail even if the socket is unbound. Instead, it will return an unspecified socket address.
+    /// This unspecified endpoint helps with that.
+    pub(super) const fn unspecified_endpoint(self) -> IpEndpoint {
+        let addr = match self {
+            IpFamily::Ipv4 => IpAddress::Ipv4(Ipv4Address::UNSPECIFIED),
+            IpFamily::Ipv6 => IpAddress::Ipv6(Ipv6Address::UNSPECIFIED),
+        };
+        IpEndpoint::new(addr, 0)
+    }
+}
@@ -3,48 +3,77 @@
 use aster_bigtcp::{
     errors::BindError,
     iface::BindPortConfig,
-    wire::{IpAddress, IpEndpoint},
+    wire::{IpAddress, IpEndpoint, Ipv4Address},
 };
 
 use crate::{
-    net::iface::{iter_all_ifaces, loopback_iface, virtio_iface, BoundPort, Iface},
+    net::{
+        iface::{BoundPort, Iface},
+        NetNamespace,
+    },
     prelude::*,
 };
 
 pub(super) fn get_iface_to_bind(ip_addr: &IpAddress) -> Option<Arc<Iface>> {
-    let IpAddress::Ipv4(ipv4_addr) = ip_addr;
-    iter_all_ifaces()
-        .find(|iface| {
-            if let Some(iface_ipv4_addr) = iface.ipv4_addr() {
-                iface_ipv4_addr == *ipv4_addr
-            } else {
-                false
-            }
-        })
+    let net_ns = NetNamespace::current();
+    net_ns
+        .ifaces()
+        .iter()
+        .find(|iface| has_ip_addr(iface, ip_addr))
+        .map(Clone::clone)
+}
+
+pub(super) fn get_iface_by_index(ifindex: u32) -> Option<Arc<Iface>> {
+    let net_ns = NetNamespace::current();
+    net_ns
+        .ifaces()
+        .iter()
+        .find(|iface| iface.index() == ifindex)
         .map(Clone::clone)
 }
 
+/// Returns whether the address is the limited broadcast address or the broadcast address of the
+/// subnet of some iface.
+pub(super) fn is_ipv4_broadcast(ipv4_addr: &Ipv4Address) -> bool {
+    if ipv4_addr.is_broadcast() {
+        return true;
+    }
+
+    let net_ns = NetNamespace::current();
+    net_ns.ifaces().iter().any(|iface| {
+        iface
+            .ipv4_cidrs()
+            .iter()
+            .any(|cidr| cidr.broadcast() == Some(*ipv4_addr))
+    })
+}
+
 /// Get a suitable iface to deal with sendto/connect request if the socket is not bound to an iface.
 /// If the remote address is the same as that of some iface, we will use the iface.
 /// Otherwise, we will use a default interface.
-fn get_ephemeral_iface(remote_ip_addr: &IpAddress) -> Arc<Iface> {
-    let IpAddress::Ipv4(remote_ipv4_addr) = remote_ip_addr;
-    if let Some(iface) = iter_all_ifaces().find(|iface| {
-        if let Some(iface_ipv4_addr) = iface.ipv4_addr() {
-            iface_ipv4_addr == *remote_ipv4_addr
-        } else {
-            false
-        }
-    }) {
+pub(super) fn get_ephemeral_iface(remote_ip_addr: &IpAddress) -> Arc<Iface> {
+    let net_ns = NetNamespace::current();
+    if let Some(iface) = net_ns
+        .ifaces()
+        .iter()
+        .find(|iface| has_ip_addr(iface, remote_ip_addr))
+    {
         return iface.clone();
     }
 
-    // FIXME: Instead of hardcoding the rules here, we should choose the
-    // default interface according to the routing table.
-    if let Some(virtio_iface) = virtio_iface() {
-        virtio_iface.clone()
-    } else {
-        loopback_iface().clone()
+    net_ns.default_iface().clone()
+}
+
+fn has_ip_addr(iface: &Iface, ip_addr: &IpAddress) -> bool {
+    match ip_addr {
+        IpAddress::Ipv4(ipv4_addr) => iface
+            .ipv4_cidrs()
+            .iter()
+            .any(|cidr| cidr.address() == *ipv4_addr),
+        IpAddress::Ipv6(ipv6_addr) => iface
+            .ipv6_cidrs()
+            .iter()
+            .any(|cidr| cidr.address() == *ipv6_addr),
     }
 }
 
@@ -61,7 +90,20 @@ pub(super) fn bind_port(endpoint: &IpEndpoint, can_reuse: bool) -> Result<BoundP
 
     let bind_port_config = BindPortConfig::new(endpoint.port, can_reuse);
 
-    Ok(iface.bind(bind_port_config)?)
+    Ok(iface.bind(endpoint.addr, bind_port_config)?)
+}
+
+/// Binds a port on the unspecified IPv4 address (i.e., `INADDR_ANY`).
+///
+/// FIXME: The socket should receive packets from all ifaces. Currently, it is bound to the default
+/// iface and only receives packets from that iface. In addition, binding a port on the unspecified
+/// address does not conflict with binding the same port on a specific address.
+pub(super) fn bind_port_unspecified(port: u16, can_reuse: bool) -> Result<BoundPort> {
+    let iface = NetNamespace::current().default_iface().clone();
+
+    let bind_port_config = BindPortConfig::new(port, can_reuse);
+
+    Ok(iface.bind(IpAddress::Ipv4(Ipv4Address::UNSPECIFIED), bind_port_config)?)
 }
 
 impl From<BindError> for Error {
@@ -77,8 +119,29 @@ impl From<BindError> for Error {
     }
 }
 
-pub(super) fn get_ephemeral_endpoint(remote_endpoint: &IpEndpoint) -> IpEndpoint {
+pub(super) fn get_ephemeral_endpoint(remote_endpoint: &IpEndpoint) -> Result<IpEndpoint> {
     let iface = get_ephemeral_iface(&remote_endpoint.addr);
-    let ip_addr = iface.ipv4_addr().unwrap();
-    IpEndpoint::new(IpAddress::Ipv4(ip_addr), 0)
+
+    let ip_addr = match remote_endpoint.addr {
+        IpAddress::Ipv4(_) => iface.ipv4_addr().map(IpAddress::Ipv4),
+        IpAddress::Ipv6(remote_ipv6_addr) => {
+            // Prefer a source address whose scope matches that of the remote address.
+            let ipv6_cidrs = iface.ipv6_cidrs();
+            let is_remote_link_local = remote_ipv6_addr.is_unicast_link_local();
+            ipv6_cidrs
+                .iter()
+                .find(|cidr| cidr.address().is_unicast_link_local() == is_remote_link_local)
+                .or_else(|| ipv6_cidrs.first())
+                .map(|cidr| IpAddress::Ipv6(cidr.address()))
+        }
+    };
+
+    let Some(ip_addr) = ip_addr else {
+        return_errno_with_message!(
+            Errno::EADDRNOTAVAIL,
+            "the iface has no address in the same family as the remote address"
+        );
+    };
+
+    Ok(IpEndpoint::new(ip_addr, 0))
 }
@@ -0,0 +1,75 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use aster_bigtcp::wire::Ipv4Address;
+
+use crate::{net::socket::util::CControlHeader, prelude::*, util::net::CSocketOptionLevel};
+
+/// IP-level control messages.
+#[derive(Debug)]
+pub enum IpControlMessage {
+    /// The packet information enabled by `IP_PKTINFO`.
+    PktInfo(CInPktInfo),
+    /// The TTL enabled by `IP_RECVTTL`.
+    Ttl(i32),
+}
+
+impl IpControlMessage {
+    pub(super) fn new_pktinfo(ifindex: u32, spec_dst: Ipv4Address, addr: Ipv4Address) -> Self {
+        Self::PktInfo(CInPktInfo {
+            ipi_ifindex: ifindex as i32,
+            ipi_spec_dst: spec_dst.octets(),
+            ipi_addr: addr.octets(),
+        })
+    }
+
+    pub(super) fn new_ttl(ttl: u8) -> Self {
+        Self::Ttl(ttl as i32)
+    }
+
+    pub fn write_to(&self, writer: &mut VmWriter) -> Result<CControlHeader> {
+        let (type_, bytes) = match self {
+            Self::PktInfo(pktinfo) => (CControlType::PKTINFO, pktinfo.as_bytes()),
+            Self::Ttl(ttl) => (CControlType::TTL, ttl.as_bytes()),
+        };
+
+        let payload_len = bytes
+            .len()
+            .min(CControlHeader::payload_len_from_total(writer.avail())?);
+        if payload_len != bytes.len() {
+            warn!("setting MSG_CTRUNC is not supported");
+        }
+
+        let header = CControlHeader::new(CSocketOptionLevel::SOL_IP, type_ as i32, payload_len);
+        writer.write_val(&header)?;
+        writer.write_fallible(&mut VmReader::from(&bytes[..payload_len]))?;
+
+        Ok(header)
+    }
+}
+
+/// `struct in_pktinfo` in Linux.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.0.9/source/include/uapi/linux/in.h>.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+pub struct CInPktInfo {
+    /// The index of the iface on which the packet was received.
+    ipi_ifindex: i32,
+    /// The local address of the packet.
+    ipi_spec_dst: [u8; 4],
+    /// The destination address in the header of the packet.
+    ipi_addr: [u8; 4],
+}
+
+/// IP-level control message types.
+///
+/// They have the same values as the corresponding socket options.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.0.9/source/include/uapi/linux/in.h>.
+#[repr(i32)]
+#[derive(Debug, Clone, Copy)]
+#[expect(clippy::upper_case_acronyms)]
+enum CControlType {
+    TTL = 2,
+    PKTINFO = 8,
+}
@@ -1,14 +1,18 @@
 // SPDX-License-Identifier: MPL-2.0
 
+use core::sync::atomic::Ordering;
+
 use aster_bigtcp::{
-    errors::udp::{RecvError, SendError},
-    wire::IpEndpoint,
+    errors::{icmp, udp},
+    socket::UdpSendOptions,
+    wire::{IpAddress, IpEndpoint},
 };
 
+use super::SendOptions;
 use crate::{
     events::IoEvents,
     net::{
-        iface::{BoundPort, Iface, UdpSocket},
+        iface::{BoundPort, IcmpSocket, Iface, UdpSocket},
         socket::util::{datagram_common, SendRecvFlags},
     },
     prelude::*,
@@ -16,32 +20,73 @@ use crate::{
 };
 
 pub(super) struct BoundDatagram {
-    bound_socket: UdpSocket,
+    bound_socket: BoundSocket,
     remote_endpoint: Option<IpEndpoint>,
+    send_options: Arc<SendOptions>,
+}
+
+enum BoundSocket {
+    Udp(UdpSocket),
+    /// An ICMP "ping" socket.
+    Ping(IcmpSocket),
 }
 
 impl BoundDatagram {
-    pub(super) fn new(bound_socket: UdpSocket) -> Self {
+    pub(super) fn new_udp(bound_socket: UdpSocket, send_options: Arc<SendOptions>) -> Self {
+        Self {
+            bound_socket: BoundSocket::Udp(bound_socket),
+            remote_endpoint: None,
+            send_options,
+        }
+    }
+
+    pub(super) fn new_ping(bound_socket: IcmpSocket, send_options: Arc<SendOptions>) -> Self {
         Self {
-            bound_socket,
+            bound_socket: BoundSocket::Ping(bound_socket),
             remote_endpoint: None,
+            send_options,
         }
     }
 
     pub(super) fn iface(&self) -> &Arc<Iface> {
-        self.bound_socket.iface()
+        self.bound_port().iface()
     }
 
     pub(super) fn bound_port(&self) -> &BoundPort {
-        self.bound_socket.bound_port()
+        match &self.bound_socket {
+            BoundSocket::Udp(bound_socket) => bound_socket.bound_port(),
+            BoundSocket::Ping(bound_socket) => bound_socket.bound_port(),
+        }
+    }
+
+    /// Receives a packet, along with the information about the packet if it is a UDP packet.
+    pub(super) fn try_recv_with_info(
+        &self,
+        writer: &mut dyn MultiWrite,
+    ) -> Result<(usize, IpEndpoint, Option<RecvInfo>)> {
+        match &self.bound_socket {
+            BoundSocket::Udp(bound_socket) => try_recv_udp(bound_socket, writer),
+            BoundSocket::Ping(bound_socket) => {
+                let (recv_bytes, remote_endpoint) = try_recv_ping(bound_socket, writer)?;
+                Ok((recv_bytes, remote_endpoint, None))
+            }
+        }
     }
 }
 
+/// The information about a received packet, which can be reported in control messages.
+pub(super) struct RecvInfo {
+    /// The destination address in the IP header.
+    pub(super) dst_addr: IpAddress,
+    /// The TTL (or the hop limit) in the IP header.
+    pub(super) hop_limit: u8,
+}
+
 impl datagram_common::Bound for BoundDatagram {
     type Endpoint = IpEndpoint;
 
     fn local_endpoint(&self) -> Self::Endpoint {
-        self.bound_socket.local_endpoint().unwrap()
+        self.bound_port().endpoint()
     }
 
     fn remote_endpoint(&self) -> Option<&Self::Endpoint> {
@@ -57,22 +102,8 @@ impl datagram_common::Bound for BoundDatagram {
         writer: &mut dyn MultiWrite,
         _flags: SendRecvFlags,
     ) -> Result<(usize, Self::Endpoint)> {
-        let result = self.bound_socket.recv(|packet, udp_metadata| {
-            let copied_res = writer.write(&mut VmReader::from(packet));
-            let endpoint = udp_metadata.endpoint;
-            (copied_res, endpoint)
-        });
-
-        match result {
-            Ok((Ok(res), endpoint)) => Ok((res, endpoint)),
-            Ok((Err(e), _)) => Err(e),
-            Err(RecvError::Exhausted) => {
-                return_errno_with_message!(Errno::EAGAIN, "the receive buffer is empty")
-            }
-            Err(RecvError::Truncated) => {
-                unreachable!("`recv` should never fail with `RecvError::Truncated`")
-            }
-        }
+        let (recv_bytes, remote_endpoint, _) = self.try_recv_with_info(writer)?;
+        Ok((recv_bytes, remote_endpoint))
     }
 
     fn try_send(
@@ -81,45 +112,167 @@ impl datagram_common::Bound for BoundDatagram {
         remote: &Self::Endpoint,
         _flags: SendRecvFlags,
     ) -> Result<usize> {
-        let result = self
-            .bound_socket
-            .send(reader.sum_lens(), *remote, |socket_buffer| {
-                // FIXME: If copy failed, we should not send any packet.
-                // But current smoltcp API seems not to support this behavior.
-                reader
-                    .read(&mut VmWriter::from(socket_buffer))
-                    .inspect_err(|e| {
-                        warn!("unexpected UDP packet {e:#?} will be sent");
-                    })
-            });
-
-        match result {
-            Ok(inner) => inner,
-            Err(SendError::TooLarge) => {
-                return_errno_with_message!(Errno::EMSGSIZE, "the message is too large");
+        match &self.bound_socket {
+            BoundSocket::Udp(bound_socket) => {
+                try_send_udp(bound_socket, reader, remote, &self.send_options)
             }
-            Err(SendError::Unaddressable) => {
-                return_errno_with_message!(Errno::EINVAL, "the destination address is invalid");
+            BoundSocket::Ping(bound_socket) => try_send_ping(bound_socket, reader, remote),
+        }
+    }
+
+    fn check_io_events(&self) -> IoEvents {
+        let (can_recv, can_send) = match &self.bound_socket {
+            BoundSocket::Udp(bound_socket) => {
+                bound_socket.raw_with(|socket| (socket.can_recv(), socket.can_send()))
             }
-            Err(SendError::BufferFull) => {
-                return_errno_with_message!(Errno::EAGAIN, "the send buffer is full");
+            BoundSocket::Ping(bound_socket) => {
+                bound_socket.raw_with(|socket| (socket.can_recv(), socket.can_send()))
             }
+        };
+
+        let mut events = IoEvents::empty();
+
+        if can_recv {
+            events |= IoEvents::IN;
+        }
+
+        if can_send {
+            events |= IoEvents::OUT;
         }
+
+        events
     }
+}
 
-    fn check_io_events(&self) -> IoEvents {
-        self.bound_socket.raw_with(|socket| {
-            let mut events = IoEvents::empty();
+fn try_recv_udp(
+    bound_socket: &UdpSocket,
+    writer: &mut dyn MultiWrite,
+) -> Result<(usize, IpEndpoint, Option<RecvInfo>)> {
+    let result = bound_socket.recv(|packet, recv_meta| {
+        let copied_res = writer.write(&mut VmReader::from(packet));
+        (copied_res, recv_meta)
+    });
 
-            if socket.can_recv() {
-                events |= IoEvents::IN;
-            }
+    match result {
+        Ok((Ok(res), recv_meta)) => {
+            let recv_info = RecvInfo {
+                dst_addr: recv_meta.local_addr,
+                hop_limit: recv_meta.hop_limit,
+            };
+            Ok((res, recv_meta.remote_endpoint, Some(recv_info)))
+        }
+        Ok((Err(e), _)) => Err(e),
+        Err(udp::RecvError::Exhausted) => {
+            return_errno_with_message!(Errno::EAGAIN, "the receive buffer is empty")
+        }
+        Err(udp::RecvError::Truncated) => {
+            unreachable!("`recv` should never fail with `RecvError::Truncated`")
+        }
+    }
+}
 
-            if socket.can_send() {
-                events |= IoEvents::OUT;
-            }
+fn try_send_udp(
+    bound_socket: &UdpSocket,
+    reader: &mut dyn MultiRead,
+    remote: &IpEndpoint,
+    send_options: &SendOptions,
+) -> Result<usize> {
+    let hop_limit = match remote.addr {
+        IpAddress::Ipv4(ipv4_addr) if ipv4_addr.is_multicast() => {
+            send_options.multicast_hop_limit.load(Ordering::Relaxed)
+        }
+        _ => send_options.hop_limit.load(Ordering::Relaxed),
+    };
+    let options = UdpSendOptions {
+        hop_limit,
+        multicast_loop: send_options.multicast_loop.load(Ordering::Relaxed),
+    };
+
+    let result = bound_socket.send(reader.sum_lens(), *remote, options, |socket_buffer| {
+        // FIXME: If copy failed, we should not send any packet.
+        // But current smoltcp API seems not to support this behavior.
+        reader
+            .read(&mut VmWriter::from(socket_buffer))
+            .inspect_err(|e| {
+                warn!("unexpected UDP packet {e:#?} will be sent");
+            })
+    });
+
+    match result {
+        Ok(inner) => inner,
+        Err(udp::SendError::TooLarge) => {
+            return_errno_with_message!(Errno::EMSGSIZE, "the message is too large");
+        }
+        Err(udp::SendError::Unaddressable) => {
+            return_errno_with_message!(Errno::EINVAL, "the destination address is invalid");
+        }
+        Err(udp::SendError::BufferFull) => {
+            return_errno_with_message!(Errno::EAGAIN, "the send buffer is full");
+        }
+    }
+}
+
+fn try_recv_ping(
+    bound_socket: &IcmpSocket,
+    writer: &mut dyn MultiWrite,
+) -> Result<(usize, IpEndpoint)> {
+    let result = bound_socket.recv(|message, remote_addr| {
+        let copied_res = writer.write(&mut VmReader::from(message));
+        (copied_res, remote_addr)
+    });
 
-            events
-        })
+    match result {
+        // ICMP messages have no ports, so the port of the remote endpoint is always zero.
+        Ok((Ok(res), remote_addr)) => Ok((res, IpEndpoint::new(remote_addr, 0))),
+        Ok((Err(e), _)) => Err(e),
+        Err(icmp::RecvError::Exhausted) => {
+            return_errno_with_message!(Errno::EAGAIN, "the receive buffer is empty")
+        }
+        Err(icmp::RecvError::Truncated) => {
+            unreachable!("`recv` should never fail with `RecvError::Truncated`")
+        }
+    }
+}
+
+/// The length of the header of ICMP echo messages.
+const ICMP_ECHO_HEADER_LEN: usize = 8;
+/// The type of ICMP echo requests.
+const ICMP_ECHO_REQUEST: u8 = 8;
+
+fn try_send_ping(
+    bound_socket: &IcmpSocket,
+    reader: &mut dyn MultiRead,
+    remote: &IpEndpoint,
+) -> Result<usize> {
+    let size = reader.sum_lens();
+    if size < ICMP_ECHO_HEADER_LEN {
+        return_errno_with_message!(Errno::EINVAL, "the ICMP message is too short");
+    }
+
+    let mut message = vec![0u8; size];
+    reader.read(&mut VmWriter::from(message.as_mut_slice()))?;
+
+    // Like Linux, only echo requests can be sent. The identifier is always overwritten with the
+    // bound port, so that the echo replies can be delivered to this socket.
+    if message[0] != ICMP_ECHO_REQUEST || message[1] != 0 {
+        return_errno_with_message!(Errno::EINVAL, "the ICMP message is not an echo request");
+    }
+    message[4..6].copy_from_slice(&bound_socket.bound_port().port().to_be_bytes());
+
+    let result = bound_socket.send(size, remote.addr, |socket_buffer| {
+        socket_buffer.copy_from_slice(&message)
+    });
+
+    match result {
+        Ok(()) => Ok(size),
+        Err(icmp::SendError::TooLarge) => {
+            return_errno_with_message!(Errno::EMSGSIZE, "the message is too large");
+        }
+        Err(icmp::SendError::Unaddressable) => {
+            return_errno_with_message!(Errno::EINVAL, "the destination address is invalid");
+        }
+        Err(icmp::SendError::BufferFull) => {
+            return_errno_with_message!(Errno::EAGAIN, "the send buffer is full");
+        }
     }
 }
@@ -1,27 +1,40 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use core::sync::atomic::{AtomicBool, Ordering};
+use core::sync::atomic::{AtomicBool, AtomicU8, Ordering};
 
-use aster_bigtcp::wire::IpEndpoint;
-use bound::BoundDatagram;
+use aster_bigtcp::wire::{IpAddress, IpEndpoint};
+use bound::{BoundDatagram, RecvInfo};
 use unbound::{BindOptions, UnboundDatagram};
 
-use super::addr::UNSPECIFIED_LOCAL_ENDPOINT;
+use super::{
+    addr::IpFamily,
+    common::is_ipv4_broadcast,
+    multicast::MulticastMemberships,
+    options::{
+        AddMembership, DropMembership, IpOptionSet, Ipv6OptionSet, SetIpLevelOption,
+        SetIpv6LevelOption,
+    },
+    sysctl::ping_group_contains,
+    IpControlMessage,
+};
 use crate::{
     events::IoEvents,
-    match_sock_option_mut,
+    match_sock_option_mut, match_sock_option_ref,
     net::socket::{
         options::{Error as SocketError, SocketOption},
         private::SocketPrivate,
         util::{
             datagram_common::{select_remote_and_bind, Bound, Inner},
             options::{GetSocketLevelOption, SetSocketLevelOption, SocketOptionSet},
-            MessageHeader, SendRecvFlags, SocketAddr,
+            ControlMessage, MessageHeader, SendRecvFlags, SocketAddr,
         },
         Socket,
     },
     prelude::*,
-    process::signal::{PollHandle, Pollable, Pollee},
+    process::{
+        posix_thread::AsPosixThread,
+        signal::{PollHandle, Pollable, Pollee},
+    },
     util::{MultiRead, MultiWrite},
 };
 
@@ -32,49 +45,214 @@ mod unbound;
 #[derive(Debug, Clone)]
 struct OptionSet {
     socket: SocketOptionSet,
+    ip: IpOptionSet,
+    ipv6: Ipv6OptionSet,
     // TODO: UDP option set
 }
 
 impl OptionSet {
     fn new() -> Self {
         let socket = SocketOptionSet::new_udp();
-        OptionSet { socket }
+        let ip = IpOptionSet::new_udp();
+        let ipv6 = Ipv6OptionSet::new();
+        OptionSet { socket, ip, ipv6 }
     }
 }
 
+/// The options that determine how outgoing packets are sent.
+///
+/// They are shared with the unbound and bound datagram sockets, so that the IP-level options can
+/// take effect without locking the socket state.
+//
+// TODO: Set the TOS field of outgoing packets according to `IP_TOS`.
+struct SendOptions {
+    hop_limit: AtomicU8,
+    multicast_hop_limit: AtomicU8,
+    multicast_loop: AtomicBool,
+}
+
+impl SendOptions {
+    fn new(ip_options: &IpOptionSet) -> Self {
+        Self {
+            hop_limit: AtomicU8::new(ip_options.ttl().get()),
+            multicast_hop_limit: AtomicU8::new(ip_options.multicast_ttl()),
+            multicast_loop: AtomicBool::new(ip_options.multicast_loop()),
+        }
+    }
+
+    fn update(&self, ip_options: &IpOptionSet) {
+        self.hop_limit
+            .store(ip_options.ttl().get(), Ordering::Relaxed);
+        self.multicast_hop_limit
+            .store(ip_options.multicast_ttl(), Ordering::Relaxed);
+        self.multicast_loop
+            .store(ip_options.multicast_loop(), Ordering::Relaxed);
+    }
+}
+
+/// The protocol of a datagram socket.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DatagramProtocol {
+    Udp,
+    /// ICMP "ping" sockets (i.e., `IPPROTO_ICMP` with `SOCK_DGRAM`).
+    ///
+    /// Reference: <https://lwn.net/Articles/420800/>
+    Ping,
+}
+
 pub struct DatagramSocket {
+    family: IpFamily,
+    send_options: Arc<SendOptions>,
     // Lock order: `inner` first, `options` second
     inner: RwMutex<Inner<UnboundDatagram, BoundDatagram>>,
     options: RwLock<OptionSet>,
+    memberships: MulticastMemberships,
 
     is_nonblocking: AtomicBool,
     pollee: Pollee,
 }
 
 impl DatagramSocket {
-    pub fn new(is_nonblocking: bool) -> Arc<Self> {
-        let unbound_datagram = UnboundDatagram::new();
+    pub fn new(family: IpFamily, is_nonblocking: bool) -> Arc<Self> {
+        Self::new_with_protocol(family, DatagramProtocol::Udp, is_nonblocking)
+    }
+
+    /// Creates an ICMP "ping" socket.
+    ///
+    /// Only the groups in the range specified by `net.ipv4.ping_group_range` are allowed to create
+    /// ping sockets.
+    pub fn new_ping(family: IpFamily, is_nonblocking: bool) -> Result<Arc<Self>> {
+        if family != IpFamily::Ipv4 {
+            return_errno_with_message!(
+                Errno::EPROTONOSUPPORT,
+                "ICMPv6 ping sockets are not supported"
+            );
+        }
+
+        let credentials = {
+            let current = current_thread!();
+            current.as_posix_thread().unwrap().credentials()
+        };
+        let is_allowed = ping_group_contains(credentials.egid().into())
+            || credentials
+                .groups()
+                .iter()
+                .any(|gid| ping_group_contains((*gid).into()));
+        if !is_allowed {
+            return_errno_with_message!(
+                Errno::EACCES,
+                "the groups are not allowed to create ping sockets"
+            );
+        }
+
+        Ok(Self::new_with_protocol(
+            family,
+            DatagramProtocol::Ping,
+            is_nonblocking,
+        ))
+    }
+
+    fn new_with_protocol(
+        family: IpFamily,
+        protocol: DatagramProtocol,
+        is_nonblocking: bool,
+    ) -> Arc<Self> {
+        let options = OptionSet::new();
+        let send_options = Arc::new(SendOptions::new(&options.ip));
+        let unbound_datagram = UnboundDatagram::new(protocol, send_options.clone());
+
         Arc::new(Self {
+            family,
+            send_options,
             inner: RwMutex::new(Inner::Unbound(unbound_datagram)),
-            options: RwLock::new(OptionSet::new()),
+            options: RwLock::new(options),
+            memberships: MulticastMemberships::new(),
             is_nonblocking: AtomicBool::new(is_nonblocking),
             pollee: Pollee::new(),
         })
     }
 
+    fn remote_endpoint(&self, socket_addr: SocketAddr) -> Result<IpEndpoint> {
+        let v6only = self.options.read().ipv6.v6only();
+        self.family.remote_endpoint(socket_addr, v6only)
+    }
+
+    /// Checks whether the socket is allowed to send packets to the remote endpoint.
+    ///
+    /// Like Linux, sending packets to a broadcast address requires `SO_BROADCAST`.
+    fn check_broadcast(&self, remote_endpoint: &IpEndpoint) -> Result<()> {
+        let IpAddress::Ipv4(ipv4_addr) = remote_endpoint.addr else {
+            return Ok(());
+        };
+
+        if is_ipv4_broadcast(&ipv4_addr) && !self.options.read().socket.broadcast() {
+            return_errno_with_message!(
+                Errno::EACCES,
+                "sending to a broadcast address requires `SO_BROADCAST`"
+            );
+        }
+
+        Ok(())
+    }
+
     fn try_recv(
         &self,
         writer: &mut dyn MultiWrite,
-        flags: SendRecvFlags,
-    ) -> Result<(usize, SocketAddr)> {
-        let recv_bytes = self
-            .inner
-            .read()
-            .try_recv(writer, flags)
-            .map(|(recv_bytes, remote_endpoint)| (recv_bytes, remote_endpoint.into()))?;
+        _flags: SendRecvFlags,
+    ) -> Result<(usize, SocketAddr, Vec<ControlMessage>)> {
+        let inner = self.inner.read();
+
+        let Inner::Bound(bound_datagram) = &*inner else {
+            return_errno_with_message!(Errno::EAGAIN, "the socket is not bound");
+        };
+        let (recv_bytes, remote_endpoint, recv_info) = bound_datagram.try_recv_with_info(writer)?;
+        let control_messages = match recv_info {
+            Some(recv_info) => self.generate_control(bound_datagram, recv_info),
+            None => Vec::new(),
+        };
+
+        drop(inner);
         self.pollee.invalidate();
 
-        Ok(recv_bytes)
+        Ok((
+            recv_bytes,
+            self.family.socket_addr(remote_endpoint),
+            control_messages,
+        ))
+    }
+
+    /// Generates the IP-level control messages enabled by the socket options.
+    fn generate_control(
+        &self,
+        bound_datagram: &BoundDatagram,
+        recv_info: RecvInfo,
+    ) -> Vec<ControlMessage> {
+        let IpAddress::Ipv4(dst_addr) = recv_info.dst_addr else {
+            return Vec::new();
+        };
+
+        let ip_options = self.options.read().ip;
+        let mut control_messages = Vec::new();
+
+        if ip_options.pktinfo() {
+            let iface = bound_datagram.iface();
+            // For multicast and broadcast packets, the local address is the address of the iface
+            // on which the packets are received.
+            let spec_dst = if dst_addr.is_multicast() || is_ipv4_broadcast(&dst_addr) {
+                iface.ipv4_addr().unwrap_or(dst_addr)
+            } else {
+                dst_addr
+            };
+            let msg = IpControlMessage::new_pktinfo(iface.index(), spec_dst, dst_addr);
+            control_messages.push(ControlMessage::Ip(msg));
+        }
+
+        if ip_options.recvttl() {
+            let msg = IpControlMessage::new_ttl(recv_info.hop_limit);
+            control_messages.push(ControlMessage::Ip(msg));
+        }
+
+        control_messages
     }
 
     fn try_send(
@@ -130,16 +308,20 @@ impl SocketPrivate for DatagramSocket {
 
 impl Socket for DatagramSocket {
     fn bind(&self, socket_addr: SocketAddr) -> Result<()> {
-        let endpoint = socket_addr.try_into()?;
-        let can_reuse = self.options.read().socket.reuse_addr();
+        let mut inner = self.inner.write();
+        let options = self.options.read();
 
-        self.inner
-            .write()
-            .bind(&endpoint, &self.pollee, BindOptions { can_reuse })
+        let endpoint = self
+            .family
+            .local_endpoint(socket_addr, options.ipv6.v6only())?;
+        let can_reuse = options.socket.reuse_addr();
+
+        inner.bind(&endpoint, &self.pollee, BindOptions { can_reuse })
     }
 
     fn connect(&self, socket_addr: SocketAddr) -> Result<()> {
-        let endpoint = socket_addr.try_into()?;
+        let endpoint = self.remote_endpoint(socket_addr)?;
+        self.check_broadcast(&endpoint)?;
 
         self.inner.write().connect(&endpoint, &self.pollee)
     }
@@ -149,9 +331,9 @@ impl Socket for DatagramSocket {
             .inner
             .read()
             .addr()
-            .unwrap_or(UNSPECIFIED_LOCAL_ENDPOINT);
+            .unwrap_or(self.family.unspecified_endpoint());
 
-        Ok(endpoint.into())
+        Ok(self.family.socket_addr(endpoint))
     }
 
     fn peer_addr(&self) -> Result<SocketAddr> {
@@ -160,7 +342,7 @@ impl Socket for DatagramSocket {
                 Error::with_message(Errno::ENOTCONN, "the socket is not connected")
             })?;
 
-        Ok(endpoint.into())
+        Ok(self.family.socket_addr(endpoint))
     }
 
     fn sendmsg(
@@ -180,7 +362,11 @@ impl Socket for DatagramSocket {
         } = message_header;
 
         let endpoint = match addr {
-            Some(addr) => Some(addr.try_into()?),
+            Some(addr) => {
+                let endpoint = self.remote_endpoint(addr)?;
+                self.check_broadcast(&endpoint)?;
+                Some(endpoint)
+            }
             None => None,
         };
 
@@ -203,12 +389,10 @@ impl Socket for DatagramSocket {
             warn!("unsupported flags: {:?}", flags);
         }
 
-        let (received_bytes, peer_addr) =
+        let (received_bytes, peer_addr, control_messages) =
             self.block_on(IoEvents::IN, || self.try_recv(writer, flags))?;
 
-        // TODO: Receive control message
-
-        let message_header = MessageHeader::new(Some(peer_addr), Vec::new());
+        let message_header = MessageHeader::new(Some(peer_addr), control_messages);
 
         Ok((received_bytes, message_header))
     }
@@ -224,14 +408,67 @@ impl Socket for DatagramSocket {
         });
 
         let inner = self.inner.read();
-        self.options.read().socket.get_option(option, &*inner)
+        let options = self.options.read();
+
+        // Deal with socket-level options
+        match options.socket.get_option(option, &*inner) {
+            Err(err) if err.error() == Errno::ENOPROTOOPT => (),
+            res => return res,
+        }
+
+        // Deal with IP-level options
+        match options.ip.get_option(option) {
+            Err(err) if err.error() == Errno::ENOPROTOOPT => (),
+            res => return res,
+        }
+
+        // Deal with IPv6-level options
+        match options.ipv6.get_option(option) {
+            Err(err) if err.error() == Errno::ENOPROTOOPT => Err(err),
+            _ if self.family == IpFamily::Ipv4 => return_errno_with_message!(
+                Errno::EOPNOTSUPP,
+                "IPv6-level options are not available on IPv4 sockets"
+            ),
+            res => res,
+        }
     }
 
     fn set_option(&self, option: &dyn SocketOption) -> Result<()> {
+        // The memberships are managed without locking the socket state because joining or leaving
+        // a multicast group requires polling the iface.
+        match_sock_option_ref!(option, {
+            ip_add_membership: AddMembership => {
+                return self.memberships.join(ip_add_membership.get().unwrap());
+            },
+            ip_drop_membership: DropMembership => {
+                return self.memberships.leave(ip_drop_membership.get().unwrap());
+            },
+            _ => ()
+        });
+
         let inner = self.inner.read();
         let mut options = self.options.write();
 
-        match options.socket.set_option(option, &*inner) {
+        // Deal with socket-level options, IP-level options, and then IPv6-level options
+        let result = match options.socket.set_option(option, &*inner) {
+            Err(err) if err.error() == Errno::ENOPROTOOPT => {
+                match options.ip.set_option(option, &*inner) {
+                    Err(err)
+                        if err.error() == Errno::ENOPROTOOPT && self.family == IpFamily::Ipv6 =>
+                    {
+                        options.ipv6.set_option(option, &*inner)
+                    }
+                    Ok(need_iface_poll) => {
+                        self.send_options.update(&options.ip);
+                        Ok(need_iface_poll)
+                    }
+                    result => result,
+                }
+            }
+            result => result,
+        };
+
+        match result {
             Err(e) => Err(e),
             Ok(need_iface_poll) => {
                 let iface_to_poll = need_iface_poll
@@ -260,6 +497,21 @@ impl GetSocketLevelOption for Inner<UnboundDatagram, BoundDatagram> {
     }
 }
 
+impl SetIpLevelOption for Inner<UnboundDatagram, BoundDatagram> {
+    fn set_hdrincl(&self, _hdrincl: bool) -> Result<()> {
+        return_errno_with_message!(
+            Errno::ENOPROTOOPT,
+            "IP_HDRINCL cannot be set on datagram sockets"
+        );
+    }
+}
+
+impl SetIpv6LevelOption for Inner<UnboundDatagram, BoundDatagram> {
+    fn is_bound(&self) -> bool {
+        matches!(self, Inner::Bound(_))
+    }
+}
+
 impl SetSocketLevelOption for Inner<UnboundDatagram, BoundDatagram> {
     fn set_reuse_addr(&self, reuse_addr: bool) {
         let Inner::Bound(bound) = self else {
@@ -1,25 +1,32 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use aster_bigtcp::{socket::UdpSocket, wire::IpEndpoint};
+use aster_bigtcp::wire::{IpAddress, IpEndpoint};
 
-use super::{bound::BoundDatagram, observer::DatagramObserver};
+use super::{bound::BoundDatagram, observer::DatagramObserver, DatagramProtocol, SendOptions};
 use crate::{
     events::IoEvents,
-    net::socket::{
-        ip::common::{bind_port, get_ephemeral_endpoint},
-        util::datagram_common,
+    net::{
+        iface::{IcmpSocket, UdpSocket},
+        socket::{
+            ip::common::{bind_port, bind_port_unspecified, get_ephemeral_endpoint},
+            util::datagram_common,
+        },
     },
     prelude::*,
     process::signal::Pollee,
 };
 
 pub(super) struct UnboundDatagram {
-    _private: (),
+    protocol: DatagramProtocol,
+    send_options: Arc<SendOptions>,
 }
 
 impl UnboundDatagram {
-    pub(super) fn new() -> Self {
-        Self { _private: () }
+    pub(super) fn new(protocol: DatagramProtocol, send_options: Arc<SendOptions>) -> Self {
+        Self {
+            protocol,
+            send_options,
+        }
     }
 }
 
@@ -39,17 +46,32 @@ impl datagram_common::Unbound for UnboundDatagram {
         pollee: &Pollee,
         options: BindOptions,
     ) -> Result<Self::Bound> {
-        let bound_port = bind_port(endpoint, options.can_reuse)?;
+        let bound_port = match (self.protocol, endpoint.addr) {
+            (DatagramProtocol::Udp, IpAddress::Ipv4(addr)) if addr.is_unspecified() => {
+                bind_port_unspecified(endpoint.port, options.can_reuse)?
+            }
+            _ => bind_port(endpoint, options.can_reuse)?,
+        };
+        let observer = DatagramObserver::new(pollee.clone());
 
-        let bound_socket =
-            match UdpSocket::new_bind(bound_port, DatagramObserver::new(pollee.clone())) {
-                Ok(bound_socket) => bound_socket,
+        let bound_datagram = match self.protocol {
+            DatagramProtocol::Udp => match UdpSocket::new_bind(bound_port, observer) {
+                Ok(bound_socket) => BoundDatagram::new_udp(bound_socket, self.send_options.clone()),
                 Err((_, err)) => {
                     unreachable!("`new_bind` fails with {:?}, which should not happen", err)
                 }
-            };
+            },
+            DatagramProtocol::Ping => match IcmpSocket::new_bind(bound_port, observer) {
+                Ok(bound_socket) => {
+                    BoundDatagram::new_ping(bound_socket, self.send_options.clone())
+                }
+                Err((_, err)) => {
+                    unreachable!("`new_bind` fails with {:?}, which should not happen", err)
+                }
+            },
+        };
 
-        Ok(BoundDatagram::new(bound_socket))
+        Ok(bound_datagram)
     }
 
     fn bind_ephemeral(
@@ -57,7 +79,7 @@ impl datagram_common::Unbound for UnboundDatagram {
         remote_endpoint: &Self::Endpoint,
         pollee: &Pollee,
     ) -> Result<Self::Bound> {
-        let endpoint = get_ephemeral_endpoint(remote_endpoint);
+        let endpoint = get_ephemeral_endpoint(remote_endpoint)?;
         self.bind(&endpoint, pollee, BindOptions { can_reuse: false })
     }
 
@@ -2,11 +2,23 @@
 
 mod addr;
 mod common;
+mod ctrl_msg;
 mod datagram;
+mod multicast;
 pub mod options;
+mod raw;
 mod stream;
+mod sysctl;
 
+pub use addr::IpFamily;
+pub use ctrl_msg::IpControlMessage;
 pub(in crate::net) use datagram::observer::DatagramObserver;
 pub use datagram::DatagramSocket;
+pub use raw::RawSocket;
 pub(in crate::net) use stream::observer::StreamObserver;
 pub use stream::{options as stream_options, StreamSocket};
+pub(in crate::net) use sysctl::{tcp_rmem_default, tcp_wmem_default};
+
+pub(in crate::net) fn init() {
+    sysctl::init();
+}
@@ -0,0 +1,130 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use aster_bigtcp::wire::Ipv4Address;
+
+use super::{
+    common::{get_iface_by_index, get_iface_to_bind},
+    options::IpMreqn,
+};
+use crate::{
+    net::{iface::Iface, NetNamespace},
+    prelude::*,
+};
+
+/// The IPv4 multicast group memberships of a socket.
+pub(super) struct MulticastMemberships {
+    memberships: Mutex<Vec<Membership>>,
+}
+
+/// A membership added by `IP_ADD_MEMBERSHIP`.
+struct Membership {
+    group: Ipv4Address,
+    iface: Arc<Iface>,
+}
+
+/// The maximum number of memberships of a socket.
+///
+/// This is the default value of `net.ipv4.igmp_max_memberships` in Linux.
+///
+/// Reference: <https://docs.kernel.org/networking/ip-sysctl.html>.
+const MAX_MEMBERSHIPS: usize = 20;
+
+impl MulticastMemberships {
+    pub(super) const fn new() -> Self {
+        Self {
+            memberships: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Joins the multicast group on the iface specified by `mreqn`.
+    pub(super) fn join(&self, mreqn: &IpMreqn) -> Result<()> {
+        let group = mreqn.multiaddr();
+        if !group.is_multicast() {
+            return_errno_with_message!(Errno::EINVAL, "the address is not a multicast address");
+        }
+
+        let iface = find_iface(mreqn)?;
+
+        let mut memberships = self.memberships.lock();
+
+        if memberships
+            .iter()
+            .any(|membership| membership.matches(group, &iface))
+        {
+            return_errno_with_message!(Errno::EADDRINUSE, "the group has already been joined");
+        }
+        if memberships.len() >= MAX_MEMBERSHIPS {
+            return_errno_with_message!(Errno::ENOBUFS, "too many groups have been joined");
+        }
+
+        iface.join_ipv4_multicast_group(group);
+        memberships.push(Membership {
+            group,
+            iface: iface.clone(),
+        });
+
+        drop(memberships);
+        iface.poll();
+
+        Ok(())
+    }
+
+    /// Leaves the multicast group on the iface specified by `mreqn`.
+    pub(super) fn leave(&self, mreqn: &IpMreqn) -> Result<()> {
+        let group = mreqn.multiaddr();
+        let iface = find_iface(mreqn)?;
+
+        let mut memberships = self.memberships.lock();
+
+        let Some(index) = memberships
+            .iter()
+            .position(|membership| membership.matches(group, &iface))
+        else {
+            return_errno_with_message!(Errno::EADDRNOTAVAIL, "the group has not been joined");
+        };
+
+        let membership = memberships.swap_remove(index);
+        drop(memberships);
+
+        membership.release();
+
+        Ok(())
+    }
+}
+
+impl Drop for MulticastMemberships {
+    fn drop(&mut self) {
+        for membership in self.memberships.get_mut().drain(..) {
+            membership.release();
+        }
+    }
+}
+
+impl Membership {
+    fn matches(&self, group: Ipv4Address, iface: &Arc<Iface>) -> bool {
+        self.group == group && self.iface.index() == iface.index()
+    }
+
+    fn release(self) {
+        self.iface.leave_ipv4_multicast_group(self.group);
+        self.iface.poll();
+    }
+}
+
+/// Finds the iface specified by `mreqn`.
+///
+/// Like Linux, the iface can be specified by either its index or one of its addresses. If neither
+/// is specified, the default iface is used.
+fn find_iface(mreqn: &IpMreqn) -> Result<Arc<Iface>> {
+    let iface = if mreqn.imr_ifindex != 0 {
+        get_iface_by_index(mreqn.imr_ifindex as u32)
+    } else if !mreqn.address().is_unspecified() {
+        get_iface_to_bind(&mreqn.address().into())
+    } else {
+        // FIXME: Linux looks up the routing table to find the iface via which the multicast
+        // packets can be sent.
+        Some(NetNamespace::current().default_iface().clone())
+    };
+
+    iface.ok_or_else(|| Error::with_message(Errno::ENODEV, "the iface does not exist"))
+}
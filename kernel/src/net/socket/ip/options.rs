@@ -2,8 +2,9 @@
 
 use core::num::NonZeroU8;
 
-use aster_bigtcp::socket::NeedIfacePoll;
+use aster_bigtcp::{socket::NeedIfacePoll, wire::Ipv4Address};
 
+use super::common::{get_iface_by_index, get_iface_to_bind};
 use crate::{
     impl_socket_options, match_sock_option_mut, match_sock_option_ref,
     net::socket::options::SocketOption, prelude::*,
@@ -17,17 +18,40 @@ pub(super) struct IpOptionSet {
     tos: u8,
     ttl: IpTtl,
     hdrincl: bool,
+    pktinfo: bool,
+    recvttl: bool,
+    multicast_if: IpMreqn,
+    multicast_ttl: u8,
+    multicast_loop: bool,
 }
 
 const DEFAULT_TTL: u8 = 64;
+const DEFAULT_MULTICAST_TTL: u8 = 1;
 pub(super) const INET_ECN_MASK: u8 = 3;
 
 impl IpOptionSet {
     pub(super) const fn new_tcp() -> Self {
+        Self::new_with_hdrincl(false)
+    }
+
+    pub(super) const fn new_udp() -> Self {
+        Self::new_with_hdrincl(false)
+    }
+
+    pub(super) const fn new_raw(hdrincl: bool) -> Self {
+        Self::new_with_hdrincl(hdrincl)
+    }
+
+    const fn new_with_hdrincl(hdrincl: bool) -> Self {
         Self {
             tos: 0,
             ttl: IpTtl(None),
-            hdrincl: false,
+            hdrincl,
+            pktinfo: false,
+            recvttl: false,
+            multicast_if: IpMreqn::UNSPECIFIED,
+            multicast_ttl: DEFAULT_MULTICAST_TTL,
+            multicast_loop: true,
         }
     }
 
@@ -45,6 +69,26 @@ impl IpOptionSet {
                 let hdrincl = self.hdrincl();
                 ip_hdrincl.set(hdrincl);
             },
+            ip_pktinfo: PktInfo => {
+                let pktinfo = self.pktinfo();
+                ip_pktinfo.set(pktinfo);
+            },
+            ip_recvttl: RecvTtl => {
+                let recvttl = self.recvttl();
+                ip_recvttl.set(recvttl);
+            },
+            ip_multicast_if: MulticastIf => {
+                let multicast_if = self.multicast_if();
+                ip_multicast_if.set(multicast_if);
+            },
+            ip_multicast_ttl: MulticastTtl => {
+                let multicast_ttl = self.multicast_ttl();
+                ip_multicast_ttl.set(multicast_ttl);
+            },
+            ip_multicast_loop: MulticastLoop => {
+                let multicast_loop = self.multicast_loop();
+                ip_multicast_loop.set(multicast_loop);
+            },
             _ => return_errno_with_message!(Errno::ENOPROTOOPT, "the socket option is unknown")
         });
 
@@ -73,6 +117,40 @@ impl IpOptionSet {
                 socket.set_hdrincl(*hdrincl)?;
                 self.set_hdrincl(*hdrincl);
             },
+            ip_pktinfo: PktInfo => {
+                let pktinfo = ip_pktinfo.get().unwrap();
+                self.set_pktinfo(*pktinfo);
+            },
+            ip_recvttl: RecvTtl => {
+                let recvttl = ip_recvttl.get().unwrap();
+                self.set_recvttl(*recvttl);
+            },
+            ip_multicast_if: MulticastIf => {
+                let multicast_if = ip_multicast_if.get().unwrap();
+                self.set_multicast_if(check_multicast_if(multicast_if)?);
+            },
+            ip_multicast_ttl: MulticastTtl => {
+                let multicast_ttl = ip_multicast_ttl.get().unwrap();
+                self.set_multicast_ttl(*multicast_ttl);
+            },
+            ip_multicast_loop: MulticastLoop => {
+                let multicast_loop = ip_multicast_loop.get().unwrap();
+                self.set_multicast_loop(*multicast_loop);
+            },
+            _ip_add_membership: AddMembership => {
+                // Sockets that support multicast group memberships should handle this option
+                // before calling this method.
+                return_errno_with_message!(
+                    Errno::EPROTO,
+                    "the socket does not support multicast group memberships"
+                );
+            },
+            _ip_drop_membership: DropMembership => {
+                return_errno_with_message!(
+                    Errno::EPROTO,
+                    "the socket does not support multicast group memberships"
+                );
+            },
             _ => return_errno_with_message!(Errno::ENOPROTOOPT, "the socket option to be set is unknown")
         });
 
@@ -80,12 +158,135 @@ impl IpOptionSet {
     }
 }
 
+/// IPv6-level socket options.
+#[derive(Debug, Clone, Copy, CopyGetters, Setters)]
+#[get_copy = "pub"]
+#[set = "pub"]
+pub(super) struct Ipv6OptionSet {
+    v6only: bool,
+}
+
+impl Ipv6OptionSet {
+    pub(super) const fn new() -> Self {
+        Self { v6only: false }
+    }
+
+    pub(super) fn get_option(&self, option: &mut dyn SocketOption) -> Result<()> {
+        match_sock_option_mut!(option, {
+            ipv6_v6only: V6Only => {
+                let v6only = self.v6only();
+                ipv6_v6only.set(v6only);
+            },
+            _ => return_errno_with_message!(Errno::ENOPROTOOPT, "the socket option is unknown")
+        });
+
+        Ok(())
+    }
+
+    pub(super) fn set_option(
+        &mut self,
+        option: &dyn SocketOption,
+        socket: &dyn SetIpv6LevelOption,
+    ) -> Result<NeedIfacePoll> {
+        match_sock_option_ref!(option, {
+            ipv6_v6only: V6Only => {
+                // Linux forbids changing this option once a local port has been assigned.
+                if socket.is_bound() {
+                    return_errno_with_message!(
+                        Errno::EINVAL,
+                        "`IPV6_V6ONLY` cannot be changed after the socket is bound"
+                    );
+                }
+                let v6only = ipv6_v6only.get().unwrap();
+                self.set_v6only(*v6only);
+            },
+            _ => return_errno_with_message!(Errno::ENOPROTOOPT, "the socket option to be set is unknown")
+        });
+
+        Ok(NeedIfacePoll::FALSE)
+    }
+}
+
+/// Checks the interface for outgoing multicast packets and fills in its index.
+///
+/// Like Linux, the interface can be specified by either its index or one of its addresses. If
+/// neither is specified, the interface is selected by the routing table.
+fn check_multicast_if(mreqn: &IpMreqn) -> Result<IpMreqn> {
+    // TODO: Send the multicast packets via the specified interface. Currently, they are always
+    // sent via the interface to which the socket is bound.
+    if mreqn.imr_ifindex != 0 {
+        if get_iface_by_index(mreqn.imr_ifindex as u32).is_none() {
+            return_errno_with_message!(Errno::EADDRNOTAVAIL, "the iface does not exist");
+        }
+        return Ok(*mreqn);
+    }
+
+    let address = mreqn.address();
+    if address.is_unspecified() {
+        return Ok(IpMreqn::UNSPECIFIED);
+    }
+
+    let Some(iface) = get_iface_to_bind(&address.into()) else {
+        return_errno_with_message!(
+            Errno::EADDRNOTAVAIL,
+            "the address is not available from the local machine"
+        );
+    };
+
+    Ok(IpMreqn {
+        imr_ifindex: iface.index() as i32,
+        ..*mreqn
+    })
+}
+
 impl_socket_options!(
     pub struct Tos(i32);
     pub struct Ttl(IpTtl);
     pub struct Hdrincl(bool);
+    pub struct PktInfo(bool);
+    pub struct RecvTtl(bool);
+    pub struct MulticastIf(IpMreqn);
+    pub struct MulticastTtl(u8);
+    pub struct MulticastLoop(bool);
+    pub struct AddMembership(IpMreqn);
+    pub struct DropMembership(IpMreqn);
+    pub struct V6Only(bool);
 );
 
+/// A request to manage the multicast group memberships (i.e., `struct ip_mreqn`).
+///
+/// It is also used to select the interface for outgoing multicast packets.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.0.9/source/include/uapi/linux/in.h>
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, PartialEq, Eq)]
+pub struct IpMreqn {
+    /// The address of the multicast group.
+    pub imr_multiaddr: [u8; 4],
+    /// The local address of the interface.
+    pub imr_address: [u8; 4],
+    /// The interface index.
+    pub imr_ifindex: i32,
+}
+
+impl IpMreqn {
+    const UNSPECIFIED: Self = Self {
+        imr_multiaddr: [0; 4],
+        imr_address: [0; 4],
+        imr_ifindex: 0,
+    };
+
+    /// Returns the address of the multicast group.
+    pub fn multiaddr(&self) -> Ipv4Address {
+        Ipv4Address::from(self.imr_multiaddr)
+    }
+
+    /// Returns the local address of the interface.
+    pub fn address(&self) -> Ipv4Address {
+        Ipv4Address::from(self.imr_address)
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct IpTtl(Option<NonZeroU8>);
 
@@ -106,3 +307,7 @@ impl IpTtl {
 pub(super) trait SetIpLevelOption {
     fn set_hdrincl(&self, _hdrincl: bool) -> Result<()>;
 }
+
+pub(super) trait SetIpv6LevelOption {
+    fn is_bound(&self) -> bool;
+}
@@ -0,0 +1,148 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use core::sync::atomic::Ordering;
+
+use aster_bigtcp::{
+    errors::raw::{RecvError, SendError},
+    socket::RawIpHeader,
+    wire::{IpAddress, IpEndpoint, IPV4_HEADER_LEN},
+};
+
+use super::HeaderOptions;
+use crate::{
+    events::IoEvents,
+    net::{
+        iface::{Iface, RawIpSocket},
+        socket::util::{datagram_common, SendRecvFlags},
+    },
+    prelude::*,
+    util::{MultiRead, MultiWrite},
+};
+
+pub(super) struct BoundRaw {
+    bound_socket: RawIpSocket,
+    remote_endpoint: Option<IpEndpoint>,
+    header_options: Arc<HeaderOptions>,
+}
+
+impl BoundRaw {
+    pub(super) fn new(bound_socket: RawIpSocket, header_options: Arc<HeaderOptions>) -> Self {
+        Self {
+            bound_socket,
+            remote_endpoint: None,
+            header_options,
+        }
+    }
+
+    pub(super) fn iface(&self) -> &Arc<Iface> {
+        self.bound_socket.iface()
+    }
+}
+
+impl datagram_common::Bound for BoundRaw {
+    type Endpoint = IpEndpoint;
+
+    fn local_endpoint(&self) -> Self::Endpoint {
+        IpEndpoint::new(
+            IpAddress::Ipv4(self.bound_socket.local_addr()),
+            self.header_options.protocol as u16,
+        )
+    }
+
+    fn remote_endpoint(&self) -> Option<&Self::Endpoint> {
+        self.remote_endpoint.as_ref()
+    }
+
+    fn set_remote_endpoint(&mut self, endpoint: &Self::Endpoint) {
+        self.remote_endpoint = Some(*endpoint)
+    }
+
+    fn try_recv(
+        &self,
+        writer: &mut dyn MultiWrite,
+        _flags: SendRecvFlags,
+    ) -> Result<(usize, Self::Endpoint)> {
+        let result = self.bound_socket.recv(|packet, src_addr| {
+            let copied_res = writer.write(&mut VmReader::from(packet));
+            (copied_res, src_addr)
+        });
+
+        match result {
+            // Raw IP packets have no ports, so the port of the remote endpoint is always zero.
+            Ok((Ok(res), src_addr)) => Ok((res, IpEndpoint::new(IpAddress::Ipv4(src_addr), 0))),
+            Ok((Err(e), _)) => Err(e),
+            Err(RecvError::Exhausted) => {
+                return_errno_with_message!(Errno::EAGAIN, "the receive buffer is empty")
+            }
+            Err(RecvError::Truncated) => {
+                unreachable!("`recv` should never fail with `RecvError::Truncated`")
+            }
+        }
+    }
+
+    fn try_send(
+        &self,
+        reader: &mut dyn MultiRead,
+        remote: &Self::Endpoint,
+        _flags: SendRecvFlags,
+    ) -> Result<usize> {
+        let size = reader.sum_lens();
+
+        let header = if self.header_options.hdrincl.load(Ordering::Relaxed) {
+            if size < IPV4_HEADER_LEN {
+                return_errno_with_message!(
+                    Errno::EINVAL,
+                    "the packet is too short to include an IP header"
+                );
+            }
+            RawIpHeader::Included
+        } else {
+            let IpAddress::Ipv4(dst_addr) = remote.addr else {
+                unreachable!("raw sockets only support IPv4 endpoints");
+            };
+            RawIpHeader::Generated {
+                dst_addr,
+                hop_limit: self.header_options.hop_limit.load(Ordering::Relaxed),
+            }
+        };
+
+        let result = self.bound_socket.send(size, header, |socket_buffer| {
+            // FIXME: If copy failed, we should not send any packet.
+            // But current smoltcp API seems not to support this behavior.
+            reader
+                .read(&mut VmWriter::from(socket_buffer))
+                .inspect_err(|e| {
+                    warn!("unexpected raw IP packet {e:#?} will be sent");
+                })
+        });
+
+        match result {
+            Ok(inner) => inner,
+            Err(SendError::TooLarge) => {
+                return_errno_with_message!(Errno::EMSGSIZE, "the message is too large");
+            }
+            Err(SendError::Unaddressable) => {
+                return_errno_with_message!(Errno::EINVAL, "the source address is unavailable");
+            }
+            Err(SendError::BufferFull) => {
+                return_errno_with_message!(Errno::EAGAIN, "the send buffer is full");
+            }
+        }
+    }
+
+    fn check_io_events(&self) -> IoEvents {
+        self.bound_socket.raw_with(|socket| {
+            let mut events = IoEvents::empty();
+
+            if socket.can_recv() {
+                events |= IoEvents::IN;
+            }
+
+            if socket.can_send() {
+                events |= IoEvents::OUT;
+            }
+
+            events
+        })
+    }
+}
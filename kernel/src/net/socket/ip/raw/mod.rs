@@ -0,0 +1,346 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use core::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+
+use aster_bigtcp::wire::IpEndpoint;
+use bound::BoundRaw;
+use unbound::UnboundRaw;
+
+use super::{
+    addr::IpFamily,
+    options::{IpOptionSet, SetIpLevelOption},
+};
+use crate::{
+    events::IoEvents,
+    match_sock_option_mut,
+    net::socket::{
+        options::{Error as SocketError, SocketOption},
+        private::SocketPrivate,
+        util::{
+            datagram_common::{select_remote_and_bind, Bound, Inner},
+            options::{GetSocketLevelOption, SetSocketLevelOption, SocketOptionSet},
+            MessageHeader, SendRecvFlags, SocketAddr,
+        },
+        Socket,
+    },
+    prelude::*,
+    process::{
+        credentials::capabilities::CapSet,
+        posix_thread::AsPosixThread,
+        signal::{PollHandle, Pollable, Pollee},
+    },
+    util::{MultiRead, MultiWrite},
+};
+
+mod bound;
+mod unbound;
+
+/// The IP protocol number of `IPPROTO_RAW`, which implies `IP_HDRINCL`.
+const IPPROTO_RAW: u8 = 255;
+
+#[derive(Debug, Clone)]
+struct OptionSet {
+    socket: SocketOptionSet,
+    ip: IpOptionSet,
+}
+
+impl OptionSet {
+    fn new(protocol: u8) -> Self {
+        let socket = SocketOptionSet::new_udp();
+        let ip = IpOptionSet::new_raw(protocol == IPPROTO_RAW);
+        OptionSet { socket, ip }
+    }
+}
+
+/// The options that determine how the IP headers of outgoing packets are built.
+///
+/// They are shared with the unbound and bound raw sockets, so that the IP-level options can take
+/// effect without locking the socket state.
+struct HeaderOptions {
+    protocol: u8,
+    hdrincl: AtomicBool,
+    hop_limit: AtomicU8,
+}
+
+impl HeaderOptions {
+    fn new(protocol: u8, ip_options: &IpOptionSet) -> Self {
+        Self {
+            protocol,
+            hdrincl: AtomicBool::new(ip_options.hdrincl()),
+            hop_limit: AtomicU8::new(ip_options.ttl().get()),
+        }
+    }
+
+    fn update(&self, ip_options: &IpOptionSet) {
+        self.hdrincl.store(ip_options.hdrincl(), Ordering::Relaxed);
+        self.hop_limit
+            .store(ip_options.ttl().get(), Ordering::Relaxed);
+    }
+}
+
+/// A raw IPv4 socket (i.e., `SOCK_RAW` with `AF_INET`).
+///
+/// Reference: <https://man7.org/linux/man-pages/man7/raw.7.html>
+pub struct RawSocket {
+    header_options: Arc<HeaderOptions>,
+    // Lock order: `inner` first, `options` second
+    inner: RwMutex<Inner<UnboundRaw, BoundRaw>>,
+    options: RwLock<OptionSet>,
+
+    is_nonblocking: AtomicBool,
+    pollee: Pollee,
+}
+
+impl RawSocket {
+    /// Creates a raw socket of the IP protocol.
+    ///
+    /// The current thread must have the `CAP_NET_RAW` capability.
+    pub fn new(protocol: i32, is_nonblocking: bool) -> Result<Arc<Self>> {
+        let protocol = match u8::try_from(protocol) {
+            Ok(0) => return_errno_with_message!(
+                Errno::EPROTONOSUPPORT,
+                "raw sockets cannot be created with `IPPROTO_IP`"
+            ),
+            Ok(protocol) => protocol,
+            Err(_) => return_errno_with_message!(Errno::EINVAL, "the protocol is invalid"),
+        };
+
+        let credentials = {
+            let current = current_thread!();
+            current.as_posix_thread().unwrap().credentials()
+        };
+        if !credentials.effective_capset().contains(CapSet::NET_RAW) {
+            return_errno_with_message!(
+                Errno::EPERM,
+                "the thread does not have the capability to create raw sockets"
+            );
+        }
+
+        let options = OptionSet::new(protocol);
+        let header_options = Arc::new(HeaderOptions::new(protocol, &options.ip));
+        let unbound_raw = UnboundRaw::new(header_options.clone());
+
+        Ok(Arc::new(Self {
+            header_options,
+            inner: RwMutex::new(Inner::Unbound(unbound_raw)),
+            options: RwLock::new(options),
+            is_nonblocking: AtomicBool::new(is_nonblocking),
+            pollee: Pollee::new(),
+        }))
+    }
+
+    fn try_recv(
+        &self,
+        writer: &mut dyn MultiWrite,
+        flags: SendRecvFlags,
+    ) -> Result<(usize, SocketAddr)> {
+        let (recv_bytes, remote_endpoint) = self.inner.read().try_recv(writer, flags)?;
+        self.pollee.invalidate();
+
+        Ok((recv_bytes, IpFamily::Ipv4.socket_addr(remote_endpoint)))
+    }
+
+    fn try_send(
+        &self,
+        reader: &mut dyn MultiRead,
+        remote: Option<&IpEndpoint>,
+        flags: SendRecvFlags,
+    ) -> Result<usize> {
+        let (sent_bytes, iface_to_poll) = select_remote_and_bind(
+            &self.inner,
+            remote,
+            || {
+                let remote_endpoint = remote.ok_or_else(|| {
+                    Error::with_message(
+                        Errno::EDESTADDRREQ,
+                        "the destination address is not specified",
+                    )
+                })?;
+                self.inner
+                    .write()
+                    .bind_ephemeral(remote_endpoint, &self.pollee)
+            },
+            |bound_raw, remote_endpoint| {
+                let sent_bytes = bound_raw.try_send(reader, remote_endpoint, flags)?;
+                let iface_to_poll = bound_raw.iface().clone();
+                Ok((sent_bytes, iface_to_poll))
+            },
+        )?;
+
+        self.pollee.invalidate();
+        iface_to_poll.poll();
+
+        Ok(sent_bytes)
+    }
+}
+
+impl Pollable for RawSocket {
+    fn poll(&self, mask: IoEvents, poller: Option<&mut PollHandle>) -> IoEvents {
+        self.pollee
+            .poll_with(mask, poller, || self.inner.read().check_io_events())
+    }
+}
+
+impl SocketPrivate for RawSocket {
+    fn is_nonblocking(&self) -> bool {
+        self.is_nonblocking.load(Ordering::Relaxed)
+    }
+
+    fn set_nonblocking(&self, is_nonblocking: bool) {
+        self.is_nonblocking.store(is_nonblocking, Ordering::Relaxed);
+    }
+}
+
+impl Socket for RawSocket {
+    fn bind(&self, socket_addr: SocketAddr) -> Result<()> {
+        let endpoint = IpFamily::Ipv4.local_endpoint(socket_addr, false)?;
+
+        self.inner.write().bind(&endpoint, &self.pollee, ())
+    }
+
+    fn connect(&self, socket_addr: SocketAddr) -> Result<()> {
+        let endpoint = IpFamily::Ipv4.remote_endpoint(socket_addr, false)?;
+
+        self.inner.write().connect(&endpoint, &self.pollee)
+    }
+
+    fn addr(&self) -> Result<SocketAddr> {
+        // Like Linux, the port of a raw socket is its protocol number.
+        let endpoint = self.inner.read().addr().unwrap_or_else(|| {
+            let unspecified_endpoint = IpFamily::Ipv4.unspecified_endpoint();
+            IpEndpoint::new(
+                unspecified_endpoint.addr,
+                self.header_options.protocol as u16,
+            )
+        });
+
+        Ok(IpFamily::Ipv4.socket_addr(endpoint))
+    }
+
+    fn peer_addr(&self) -> Result<SocketAddr> {
+        let endpoint =
+            *self.inner.read().peer_addr().ok_or_else(|| {
+                Error::with_message(Errno::ENOTCONN, "the socket is not connected")
+            })?;
+
+        Ok(IpFamily::Ipv4.socket_addr(endpoint))
+    }
+
+    fn sendmsg(
+        &self,
+        reader: &mut dyn MultiRead,
+        message_header: MessageHeader,
+        flags: SendRecvFlags,
+    ) -> Result<usize> {
+        // TODO: Deal with flags
+        if !flags.is_all_supported() {
+            warn!("unsupported flags: {:?}", flags);
+        }
+
+        let MessageHeader {
+            addr,
+            control_messages,
+        } = message_header;
+
+        let endpoint = match addr {
+            Some(addr) => Some(IpFamily::Ipv4.remote_endpoint(addr, false)?),
+            None => None,
+        };
+
+        if !control_messages.is_empty() {
+            // TODO: Support sending control message
+            warn!("sending control message is not supported");
+        }
+
+        // TODO: Block if the send buffer is full
+        self.try_send(reader, endpoint.as_ref(), flags)
+    }
+
+    fn recvmsg(
+        &self,
+        writer: &mut dyn MultiWrite,
+        flags: SendRecvFlags,
+    ) -> Result<(usize, MessageHeader)> {
+        // TODO: Deal with flags
+        if !flags.is_all_supported() {
+            warn!("unsupported flags: {:?}", flags);
+        }
+
+        let (received_bytes, peer_addr) =
+            self.block_on(IoEvents::IN, || self.try_recv(writer, flags))?;
+
+        // TODO: Receive control message
+
+        let message_header = MessageHeader::new(Some(peer_addr), Vec::new());
+
+        Ok((received_bytes, message_header))
+    }
+
+    fn get_option(&self, option: &mut dyn SocketOption) -> Result<()> {
+        match_sock_option_mut!(option, {
+            socket_errors: SocketError => {
+                // TODO: Support socket errors for raw sockets
+                socket_errors.set(None);
+                return Ok(());
+            },
+            _ => ()
+        });
+
+        let inner = self.inner.read();
+        let options = self.options.read();
+
+        // Deal with socket-level options
+        match options.socket.get_option(option, &*inner) {
+            Err(err) if err.error() == Errno::ENOPROTOOPT => (),
+            res => return res,
+        }
+
+        // Deal with IP-level options
+        options.ip.get_option(option)
+    }
+
+    fn set_option(&self, option: &dyn SocketOption) -> Result<()> {
+        let inner = self.inner.read();
+        let mut options = self.options.write();
+
+        // Deal with socket-level options and then IP-level options
+        let need_iface_poll = match options.socket.set_option(option, &*inner) {
+            Err(err) if err.error() == Errno::ENOPROTOOPT => {
+                let need_iface_poll = options.ip.set_option(option, &*inner)?;
+                self.header_options.update(&options.ip);
+                need_iface_poll
+            }
+            result => result?,
+        };
+
+        let iface_to_poll = need_iface_poll
+            .then(|| match &*inner {
+                Inner::Unbound(_) => None,
+                Inner::Bound(bound_raw) => Some(bound_raw.iface().clone()),
+            })
+            .flatten();
+
+        drop(inner);
+        drop(options);
+
+        if let Some(iface) = iface_to_poll {
+            iface.poll();
+        }
+
+        Ok(())
+    }
+}
+
+impl GetSocketLevelOption for Inner<UnboundRaw, BoundRaw> {
+    fn is_listening(&self) -> bool {
+        false
+    }
+}
+
+impl SetSocketLevelOption for Inner<UnboundRaw, BoundRaw> {}
+
+impl SetIpLevelOption for Inner<UnboundRaw, BoundRaw> {
+    fn set_hdrincl(&self, _hdrincl: bool) -> Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,93 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use aster_bigtcp::wire::{IpAddress, IpEndpoint, IpProtocol, Ipv4Address};
+
+use super::{bound::BoundRaw, HeaderOptions};
+use crate::{
+    events::IoEvents,
+    net::{
+        iface::{Iface, RawIpSocket},
+        socket::{
+            ip::{
+                common::{get_ephemeral_iface, get_iface_to_bind},
+                DatagramObserver,
+            },
+            util::datagram_common,
+        },
+    },
+    prelude::*,
+    process::signal::Pollee,
+};
+
+pub(super) struct UnboundRaw {
+    header_options: Arc<HeaderOptions>,
+}
+
+impl UnboundRaw {
+    pub(super) fn new(header_options: Arc<HeaderOptions>) -> Self {
+        Self { header_options }
+    }
+
+    fn bind_to_iface(
+        &self,
+        iface: Arc<Iface>,
+        local_addr: Ipv4Address,
+        pollee: &Pollee,
+    ) -> BoundRaw {
+        let bound_socket = RawIpSocket::new_bind(
+            iface,
+            local_addr,
+            IpProtocol::from(self.header_options.protocol),
+            DatagramObserver::new(pollee.clone()),
+        );
+
+        BoundRaw::new(bound_socket, self.header_options.clone())
+    }
+}
+
+impl datagram_common::Unbound for UnboundRaw {
+    type Endpoint = IpEndpoint;
+    type BindOptions = ();
+
+    type Bound = BoundRaw;
+
+    fn bind(
+        &mut self,
+        endpoint: &Self::Endpoint,
+        pollee: &Pollee,
+        _options: (),
+    ) -> Result<Self::Bound> {
+        let IpAddress::Ipv4(local_addr) = endpoint.addr else {
+            unreachable!("raw sockets only support IPv4 endpoints");
+        };
+
+        // FIXME: A raw socket bound to the unspecified address should receive packets from all
+        // ifaces. Currently, it only receives packets from the default iface.
+        let iface = if local_addr.is_unspecified() {
+            get_ephemeral_iface(&endpoint.addr)
+        } else {
+            get_iface_to_bind(&endpoint.addr).ok_or_else(|| {
+                Error::with_message(
+                    Errno::EADDRNOTAVAIL,
+                    "the address is not available from the local machine",
+                )
+            })?
+        };
+
+        Ok(self.bind_to_iface(iface, local_addr, pollee))
+    }
+
+    fn bind_ephemeral(
+        &mut self,
+        remote_endpoint: &Self::Endpoint,
+        pollee: &Pollee,
+    ) -> Result<Self::Bound> {
+        let iface = get_ephemeral_iface(&remote_endpoint.addr);
+
+        Ok(self.bind_to_iface(iface, Ipv4Address::UNSPECIFIED, pollee))
+    }
+
+    fn check_io_events(&self) -> IoEvents {
+        IoEvents::OUT
+    }
+}
@@ -1,9 +1,6 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use core::{
-    net::Ipv4Addr,
-    sync::atomic::{AtomicBool, Ordering},
-};
+use core::sync::atomic::{AtomicBool, Ordering};
 
 use aster_bigtcp::{socket::RawTcpOption, wire::IpEndpoint};
 
@@ -12,10 +9,7 @@ use crate::{
     events::IoEvents,
     net::{
         iface::BoundPort,
-        socket::{
-            ip::common::{bind_port, get_ephemeral_endpoint},
-            util::SocketAddr,
-        },
+        socket::ip::common::{bind_port, get_ephemeral_endpoint},
     },
     prelude::*,
 };
@@ -98,8 +92,9 @@ impl InitStream {
         let bound_port = if let Some(bound_port) = self.bound_port {
             bound_port
         } else {
-            let endpoint = get_ephemeral_endpoint(remote_endpoint);
-            match bind_port(&endpoint, can_reuse) {
+            let bind_result = get_ephemeral_endpoint(remote_endpoint)
+                .and_then(|endpoint| bind_port(&endpoint, can_reuse));
+            match bind_result {
                 Ok(bound_port) => bound_port,
                 Err(err) => return Err((err, self)),
             }
@@ -169,12 +164,7 @@ impl InitStream {
         }
     }
 
-    pub(super) fn try_recv(&self) -> Result<(usize, SocketAddr)> {
-        // FIXME: Linux does not return addresses for `recvfrom` on connection-oriented sockets.
-        // This is a placeholder that has no Linux equivalent. (Note also that in this case
-        // `getpeeraddr` will simply fail with `ENOTCONN`).
-        const UNSPECIFIED_SOCKET_ADDR: SocketAddr = SocketAddr::IPv4(Ipv4Addr::UNSPECIFIED, 0);
-
+    pub(super) fn try_recv(&self) -> Result<usize> {
         // Below are some magic checks to make our behavior identical to Linux.
 
         if self.is_connect_done {
@@ -185,7 +175,7 @@ impl InitStream {
             return Err(err);
         }
 
-        Ok((0, UNSPECIFIED_SOCKET_ADDR))
+        Ok(0)
     }
 
     pub(super) fn try_send(&self) -> Result<usize> {
@@ -199,7 +189,7 @@ impl InitStream {
     pub(super) fn local_endpoint(&self) -> Option<IpEndpoint> {
         self.bound_port
             .as_ref()
-            .map(|bound_port| bound_port.endpoint().unwrap())
+            .map(|bound_port| bound_port.endpoint())
     }
 
     pub(super) fn check_io_events(&self) -> IoEvents {
@@ -20,8 +20,8 @@ use takeable::Takeable;
 use util::{Retrans, TcpOptionSet};
 
 use super::{
-    addr::UNSPECIFIED_LOCAL_ENDPOINT,
-    options::{IpOptionSet, SetIpLevelOption},
+    addr::IpFamily,
+    options::{IpOptionSet, Ipv6OptionSet, SetIpLevelOption, SetIpv6LevelOption},
 };
 use crate::{
     events::IoEvents,
@@ -53,6 +53,7 @@ pub mod options;
 mod util;
 
 pub struct StreamSocket {
+    family: IpFamily,
     // Lock order: `state` first, `options` second
     state: RwLock<Takeable<State>, PreemptDisabled>,
     options: RwLock<OptionSet>,
@@ -76,6 +77,7 @@ enum State {
 struct OptionSet {
     socket: SocketOptionSet,
     ip: IpOptionSet,
+    ipv6: Ipv6OptionSet,
     tcp: TcpOptionSet,
 }
 
@@ -83,8 +85,14 @@ impl OptionSet {
     fn new() -> Self {
         let socket = SocketOptionSet::new_tcp();
         let ip = IpOptionSet::new_tcp();
+        let ipv6 = Ipv6OptionSet::new();
         let tcp = TcpOptionSet::new();
-        OptionSet { socket, ip, tcp }
+        OptionSet {
+            socket,
+            ip,
+            ipv6,
+            tcp,
+        }
     }
 
     fn raw(&self) -> RawTcpOption {
@@ -96,9 +104,10 @@ impl OptionSet {
 }
 
 impl StreamSocket {
-    pub fn new(is_nonblocking: bool) -> Arc<Self> {
+    pub fn new(family: IpFamily, is_nonblocking: bool) -> Arc<Self> {
         let init_stream = InitStream::new();
         Arc::new(Self {
+            family,
             state: RwLock::new(Takeable::new(State::Init(init_stream))),
             options: RwLock::new(OptionSet::new()),
             is_nonblocking: AtomicBool::new(is_nonblocking),
@@ -106,9 +115,14 @@ impl StreamSocket {
         })
     }
 
-    fn new_accepted(connected_stream: ConnectedStream) -> Arc<Self> {
+    fn new_accepted(
+        family: IpFamily,
+        ipv6_options: Ipv6OptionSet,
+        connected_stream: ConnectedStream,
+    ) -> Arc<Self> {
         let options = connected_stream.raw_with(|raw_tcp_socket| {
             let mut options = OptionSet::new();
+            options.ipv6 = ipv6_options;
 
             if raw_tcp_socket.keep_alive().is_some() {
                 options.socket.set_keep_alive(true);
@@ -127,6 +141,7 @@ impl StreamSocket {
         connected_stream.init_observer(StreamObserver::new(pollee.clone()));
 
         Arc::new(Self {
+            family,
             options: RwLock::new(options),
             state: RwLock::new(Takeable::new(State::Connected(connected_stream))),
             is_nonblocking: AtomicBool::new(false),
@@ -295,8 +310,12 @@ impl StreamSocket {
 
         let accepted = listen_stream.try_accept().map(|connected_stream| {
             let remote_endpoint = connected_stream.remote_endpoint();
-            let accepted_socket = Self::new_accepted(connected_stream);
-            (accepted_socket as _, remote_endpoint.into())
+            let ipv6_options = self.options.read().ipv6;
+            let accepted_socket = Self::new_accepted(self.family, ipv6_options, connected_stream);
+            (
+                accepted_socket as _,
+                self.family.socket_addr(remote_endpoint),
+            )
         });
         let iface_to_poll = listen_stream.iface().clone();
 
@@ -307,11 +326,7 @@ impl StreamSocket {
         accepted
     }
 
-    fn try_recv(
-        &self,
-        writer: &mut dyn MultiWrite,
-        flags: SendRecvFlags,
-    ) -> Result<(usize, SocketAddr)> {
+    fn try_recv(&self, writer: &mut dyn MultiWrite, flags: SendRecvFlags) -> Result<usize> {
         let state = self.read_updated_state();
 
         let connected_stream = match state.as_ref() {
@@ -334,14 +349,13 @@ impl StreamSocket {
 
         let (recv_bytes, need_poll) = result?;
         let iface_to_poll = need_poll.then(|| connected_stream.iface().clone());
-        let remote_endpoint = connected_stream.remote_endpoint();
 
         drop(state);
         if let Some(iface) = iface_to_poll {
             iface.poll();
         }
 
-        Ok((recv_bytes, remote_endpoint.into()))
+        Ok(recv_bytes)
     }
 
     fn try_send(&self, reader: &mut dyn MultiRead, flags: SendRecvFlags) -> Result<usize> {
@@ -421,19 +435,24 @@ impl SocketPrivate for StreamSocket {
 
 impl Socket for StreamSocket {
     fn bind(&self, socket_addr: SocketAddr) -> Result<()> {
-        let endpoint = socket_addr.try_into()?;
-
         let mut state = self.write_updated_state();
+        let options = self.options.read();
+
+        let endpoint = self
+            .family
+            .local_endpoint(socket_addr, options.ipv6.v6only())?;
+
         let State::Init(init_stream) = state.as_mut() else {
             return_errno_with_message!(Errno::EINVAL, "the socket is already bound to an address");
         };
 
-        let can_reuse = self.options.read().socket.reuse_addr();
+        let can_reuse = options.socket.reuse_addr();
         init_stream.bind(&endpoint, can_reuse)
     }
 
     fn connect(&self, socket_addr: SocketAddr) -> Result<()> {
-        let remote_endpoint = socket_addr.try_into()?;
+        let v6only = self.options.read().ipv6.v6only();
+        let remote_endpoint = self.family.remote_endpoint(socket_addr, v6only)?;
 
         if let Some(result) = self.start_connect(&remote_endpoint) {
             return result;
@@ -510,12 +529,12 @@ impl Socket for StreamSocket {
         let local_endpoint = match state.as_ref() {
             State::Init(init_stream) => init_stream
                 .local_endpoint()
-                .unwrap_or(UNSPECIFIED_LOCAL_ENDPOINT),
+                .unwrap_or(self.family.unspecified_endpoint()),
             State::Connecting(connecting_stream) => connecting_stream.local_endpoint(),
             State::Listen(listen_stream) => listen_stream.local_endpoint(),
             State::Connected(connected_stream) => connected_stream.local_endpoint(),
         };
-        Ok(local_endpoint.into())
+        Ok(self.family.socket_addr(local_endpoint))
     }
 
     fn peer_addr(&self) -> Result<SocketAddr> {
@@ -527,7 +546,7 @@ impl Socket for StreamSocket {
             State::Connecting(connecting_stream) => connecting_stream.remote_endpoint(),
             State::Connected(connected_stream) => connected_stream.remote_endpoint(),
         };
-        Ok(remote_endpoint.into())
+        Ok(self.family.socket_addr(remote_endpoint))
     }
 
     fn sendmsg(
@@ -569,7 +588,7 @@ impl Socket for StreamSocket {
             warn!("unsupported flags: {:?}", flags);
         }
 
-        let (received_bytes, _) = self.block_on(IoEvents::IN, || self.try_recv(writer, flags))?;
+        let received_bytes = self.block_on(IoEvents::IN, || self.try_recv(writer, flags))?;
 
         // TODO: Receive control message
 
@@ -604,6 +623,16 @@ impl Socket for StreamSocket {
             res => return res,
         }
 
+        // Deal with IPv6-level options
+        match options.ipv6.get_option(option) {
+            Err(err) if err.error() == Errno::ENOPROTOOPT => (),
+            _ if self.family == IpFamily::Ipv4 => return_errno_with_message!(
+                Errno::EOPNOTSUPP,
+                "IPv6-level options are not available on IPv4 sockets"
+            ),
+            res => return res,
+        }
+
         // Deal with TCP-level options
         // FIXME: Here we only return the previously set values, without actually
         // asking the underlying sockets for the real, effective values.
@@ -672,8 +701,18 @@ impl Socket for StreamSocket {
                 // Deal with IP-level options
                 match options.ip.set_option(option, state.as_ref()) {
                     Err(err) if err.error() == Errno::ENOPROTOOPT => {
-                        // Deal with TCP-level options
-                        do_tcp_setsockopt(option, &mut options, state.as_mut())?
+                        // Deal with IPv6-level options and then TCP-level options
+                        let ipv6_result = match self.family {
+                            IpFamily::Ipv6 => options.ipv6.set_option(option, state.as_ref()),
+                            IpFamily::Ipv4 => Err(Error::new(Errno::ENOPROTOOPT)),
+                        };
+                        match ipv6_result {
+                            Err(err) if err.error() == Errno::ENOPROTOOPT => {
+                                do_tcp_setsockopt(option, &mut options, state.as_mut())?
+                            }
+                            Err(err) => return Err(err),
+                            Ok(need_iface_poll) => need_iface_poll,
+                        }
                     }
                     Err(err) => return Err(err),
                     Ok(need_iface_poll) => need_iface_poll,
@@ -851,6 +890,15 @@ impl SetIpLevelOption for State {
     }
 }
 
+impl SetIpv6LevelOption for State {
+    fn is_bound(&self) -> bool {
+        match self {
+            State::Init(init_stream) => init_stream.bound_port().is_some(),
+            State::Connecting(_) | State::Connected(_) | State::Listen(_) => true,
+        }
+    }
+}
+
 impl Drop for StreamSocket {
     fn drop(&mut self) {
         let state = self.state.get_mut().take();
@@ -0,0 +1,91 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! This module registers the sysctls under `/proc/sys/net/ipv4`.
+//!
+//! Reference: <https://docs.kernel.org/networking/ip-sysctl.html>
+
+use aster_bigtcp::socket::{TCP_RECV_BUF_LEN, TCP_SEND_BUF_LEN};
+
+use crate::{
+    fs::procfs::sysctl::{self, IntSysctl, SysctlEntry},
+    prelude::*,
+    process::credentials::capabilities::CapSet,
+};
+
+// TODO: The sizes of the buffers in `aster_bigtcp` are fixed. Currently, only
+// the default sizes are used, as the initial values of `SO_RCVBUF` and
+// `SO_SNDBUF`.
+
+/// The minimum, default, and maximum sizes of the receive buffer of TCP sockets.
+static TCP_RMEM: SpinLock<[i64; 3]> = SpinLock::new([4096, TCP_RECV_BUF_LEN as i64, 6291456]);
+/// The minimum, default, and maximum sizes of the send buffer of TCP sockets.
+static TCP_WMEM: SpinLock<[i64; 3]> = SpinLock::new([4096, TCP_SEND_BUF_LEN as i64, 4194304]);
+/// The range of group IDs that are allowed to create ICMP "ping" sockets.
+///
+/// The default range is empty, so no group is allowed.
+static PING_GROUP_RANGE: SpinLock<[i64; 2]> = SpinLock::new([1, 0]);
+
+pub(super) fn init() {
+    const BUF_SIZE_RANGE: core::ops::RangeInclusive<i64> = 1..=i32::MAX as i64;
+
+    let entries = vec![
+        SysctlEntry::writable(
+            "tcp_rmem",
+            IntSysctl::new(
+                BUF_SIZE_RANGE,
+                || *TCP_RMEM.lock(),
+                |values| *TCP_RMEM.lock() = values,
+            )
+            .check(check_buf_sizes),
+            CapSet::NET_ADMIN,
+        ),
+        SysctlEntry::writable(
+            "tcp_wmem",
+            IntSysctl::new(
+                BUF_SIZE_RANGE,
+                || *TCP_WMEM.lock(),
+                |values| *TCP_WMEM.lock() = values,
+            )
+            .check(check_buf_sizes),
+            CapSet::NET_ADMIN,
+        ),
+        SysctlEntry::writable(
+            "ping_group_range",
+            IntSysctl::new(
+                0..=(u32::MAX - 1) as i64,
+                || *PING_GROUP_RANGE.lock(),
+                |values| *PING_GROUP_RANGE.lock() = values,
+            ),
+            CapSet::NET_ADMIN,
+        ),
+    ];
+
+    sysctl::register("net/ipv4", entries).unwrap();
+}
+
+/// Checks that the minimum, default, and maximum sizes of a buffer are in order.
+fn check_buf_sizes([min, default, max]: &[i64; 3]) -> Result<()> {
+    if min > default || default > max {
+        return_errno_with_message!(
+            Errno::EINVAL,
+            "the buffer sizes must satisfy min <= default <= max"
+        );
+    }
+    Ok(())
+}
+
+/// Returns the default size of the receive buffer of TCP sockets.
+pub(in crate::net) fn tcp_rmem_default() -> u32 {
+    TCP_RMEM.lock()[1] as u32
+}
+
+/// Returns the default size of the send buffer of TCP sockets.
+pub(in crate::net) fn tcp_wmem_default() -> u32 {
+    TCP_WMEM.lock()[1] as u32
+}
+
+/// Returns whether the group is allowed to create ICMP "ping" sockets.
+pub(super) fn ping_group_contains(gid: u32) -> bool {
+    let [low, high] = *PING_GROUP_RANGE.lock();
+    (low..=high).contains(&(gid as i64))
+}
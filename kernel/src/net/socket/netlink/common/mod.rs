@@ -5,7 +5,7 @@ use core::sync::atomic::{AtomicBool, Ordering};
 pub(super) use bound::BoundNetlink;
 use unbound::UnboundNetlink;
 
-use super::{GroupIdSet, NetlinkSocketAddr};
+use super::{addr::MAX_GROUPS, GroupIdSet, NetlinkSocketAddr};
 use crate::{
     events::IoEvents,
     match_sock_option_ref,
@@ -29,6 +29,8 @@ mod unbound;
 
 pub struct NetlinkSocket<P: SupportedNetlinkProtocol> {
     inner: RwMutex<Inner<UnboundNetlink<P>, BoundNetlink<P::Message>>>,
+    /// Raw messages sent by the kernel, which are not in the format of the protocol.
+    raw_messages: SpinLock<VecDeque<Box<[u8]>>>,
 
     is_nonblocking: AtomicBool,
     pollee: Pollee,
@@ -42,6 +44,7 @@ where
         let unbound = UnboundNetlink::new();
         Arc::new(Self {
             inner: RwMutex::new(Inner::Unbound(unbound)),
+            raw_messages: SpinLock::new(VecDeque::new()),
             is_nonblocking: AtomicBool::new(is_nonblocking),
             pollee: Pollee::new(),
         })
@@ -68,12 +71,29 @@ where
         Ok(sent_bytes)
     }
 
+    /// Sends a raw message from the kernel to the socket.
+    ///
+    /// Unlike the messages of the protocol, the message is delivered even if the
+    /// socket is not bound. This is how Linux sends the notifications requested by
+    /// `mq_notify` with `SIGEV_THREAD` to the socket created by the C library.
+    pub fn send_raw_from_kernel(&self, message: Box<[u8]>) {
+        // FIXME: We should verify the socket buffer length to ensure
+        // that adding the message doesn't exceed the buffer capacity.
+        self.raw_messages.lock().push_back(message);
+        self.pollee.notify(IoEvents::IN);
+    }
+
     // FIXME: This method is marked as `pub(super)` because it's invoked during kernel mode testing.
     pub(super) fn try_recv(
         &self,
         writer: &mut dyn MultiWrite,
         flags: SendRecvFlags,
     ) -> Result<(usize, SocketAddr)> {
+        if let Some(recv_bytes) = self.try_recv_raw(writer, flags)? {
+            self.pollee.invalidate();
+            return Ok((recv_bytes, NetlinkSocketAddr::new_unspecified().into()));
+        }
+
         let recv_bytes = self
             .inner
             .read()
@@ -83,6 +103,39 @@ where
 
         Ok(recv_bytes)
     }
+
+    /// Receives a raw message sent by [`Self::send_raw_from_kernel`], if any.
+    fn try_recv_raw(
+        &self,
+        writer: &mut dyn MultiWrite,
+        flags: SendRecvFlags,
+    ) -> Result<Option<usize>> {
+        // The message is taken out first, since writing to the user space may block.
+        let message = {
+            let mut raw_messages = self.raw_messages.lock();
+            if flags.contains(SendRecvFlags::MSG_PEEK) {
+                raw_messages.front().cloned()
+            } else {
+                raw_messages.pop_front()
+            }
+        };
+        let Some(message) = message else {
+            return Ok(None);
+        };
+
+        let len = message.len().min(writer.sum_lens());
+        writer.write(&mut VmReader::from(&message[..len]))?;
+
+        Ok(Some(len))
+    }
+
+    fn check_io_events(&self) -> IoEvents {
+        let mut events = self.inner.read().check_io_events();
+        if !self.raw_messages.lock().is_empty() {
+            events |= IoEvents::IN;
+        }
+        events
+    }
 }
 
 impl<P: SupportedNetlinkProtocol> Socket for NetlinkSocket<P>
@@ -198,7 +251,7 @@ where
 {
     fn poll(&self, mask: IoEvents, poller: Option<&mut PollHandle>) -> IoEvents {
         self.pollee
-            .poll_with(mask, poller, || self.inner.read().check_io_events())
+            .poll_with(mask, poller, || self.check_io_events())
     }
 }
 
@@ -224,15 +277,27 @@ fn do_set_netlink_option<P: SupportedNetlinkProtocol>(
 ) -> Result<()> {
     match_sock_option_ref!(option, {
         add_membership: AddMembership => {
-            let groups = add_membership.get().unwrap();
-            inner.write().add_groups(GroupIdSet::new(*groups));
+            let group = add_membership.get().unwrap();
+            inner.write().add_groups(group_to_group_id_set(*group)?);
         },
         drop_membership: DropMembership => {
-            let groups = drop_membership.get().unwrap();
-            inner.write().drop_groups(GroupIdSet::new(*groups));
+            let group = drop_membership.get().unwrap();
+            inner.write().drop_groups(group_to_group_id_set(*group)?);
         },
         _ => return_errno_with_message!(Errno::ENOPROTOOPT, "the socket option to be set is unknown")
     });
 
     Ok(())
 }
+
+/// Converts a group number in the membership options to a [`GroupIdSet`].
+///
+/// Unlike the groups in socket addresses, which are bit masks, the group in
+/// the membership options is a single group number starting from one.
+fn group_to_group_id_set(group: u32) -> Result<GroupIdSet> {
+    if group == 0 || group > MAX_GROUPS {
+        return_errno_with_message!(Errno::EINVAL, "the group number is invalid");
+    }
+
+    Ok(GroupIdSet::new(1 << (group - 1)))
+}
@@ -111,5 +111,9 @@ pub enum CSegmentType {
     NEWROUTE = 24,
     DELROUTE = 25,
     GETROUTE = 26,
+
+    NEWNEIGH = 28,
+    DELNEIGH = 29,
+    GETNEIGH = 30,
     // TODO: The list is not exhaustive.
 }
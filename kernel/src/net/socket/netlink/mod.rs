@@ -50,6 +50,9 @@ pub use addr::{GroupIdSet, NetlinkSocketAddr};
 pub use kobject_uevent::NetlinkUeventSocket;
 pub use options::{AddMembership, DropMembership};
 pub use route::NetlinkRouteSocket;
+pub(in crate::net) use route::{
+    notify_del_addr, notify_del_route, notify_new_addr, notify_new_route,
+};
 pub use table::{is_valid_protocol, StandardNetlinkProtocol};
 
 pub(in crate::net) fn init() {
@@ -4,16 +4,21 @@
 
 use core::num::NonZeroU32;
 
-use super::util::finish_response;
+use aster_bigtcp::wire::{Ipv4Address, Ipv4Cidr};
+
+use super::util::{check_net_admin, find_iface_by_index, finish_response, notify, RtnlGroup};
 use crate::{
     net::{
-        iface::{iter_all_ifaces, Iface},
+        iface::Iface,
         socket::netlink::{
-            message::{CMsgSegHdr, CSegmentType, GetRequestFlags, SegHdrCommonFlags},
+            message::{
+                CMsgSegHdr, CSegmentType, GetRequestFlags, NewRequestFlags, SegHdrCommonFlags,
+            },
             route::message::{
                 AddrAttr, AddrMessageFlags, AddrSegment, AddrSegmentBody, RtScope, RtnlSegment,
             },
         },
+        NetNamespace,
     },
     prelude::*,
     util::net::CSocketAddrFamily,
@@ -28,9 +33,15 @@ pub(super) fn do_get_addr(request_segment: &AddrSegment) -> Result<Vec<RtnlSegme
         return_errno_with_message!(Errno::EOPNOTSUPP, "GETADDR only supports dump requests");
     }
 
-    let mut response_segments: Vec<RtnlSegment> = iter_all_ifaces()
+    let mut response_segments: Vec<RtnlSegment> = NetNamespace::current()
+        .ifaces()
+        .iter()
         // GETADDR only supports dump mode, so we're going to report all addresses.
-        .filter_map(|iface| iface_to_new_addr(request_segment.header(), iface))
+        .flat_map(|iface| {
+            iface.ipv4_cidrs().into_iter().map(|cidr| {
+                new_addr_segment(request_segment.header(), CSegmentType::NEWADDR, iface, cidr)
+            })
+        })
         .map(RtnlSegment::NewAddr)
         .collect();
 
@@ -39,22 +50,159 @@ pub(super) fn do_get_addr(request_segment: &AddrSegment) -> Result<Vec<RtnlSegme
     Ok(response_segments)
 }
 
-fn iface_to_new_addr(request_header: &CMsgSegHdr, iface: &Arc<Iface>) -> Option<AddrSegment> {
-    let ipv4_addr = iface.ipv4_addr()?;
+pub(super) fn do_new_addr(request_segment: &AddrSegment) -> Result<Vec<RtnlSegment>> {
+    check_net_admin()?;
+
+    let (iface, cidr) = parse_addr_request(request_segment)?;
+    let Some(cidr) = cidr else {
+        return_errno_with_message!(Errno::EINVAL, "the local address is not specified");
+    };
+
+    let flags = NewRequestFlags::from_bits_truncate(request_segment.header().flags);
+    if iface.ipv4_cidrs().contains(&cidr) {
+        if flags.contains(NewRequestFlags::EXCL) || !flags.contains(NewRequestFlags::REPLACE) {
+            return_errno_with_message!(Errno::EEXIST, "the address already exists");
+        }
+        // Replacing the address changes nothing, since no other properties are supported.
+        return Ok(Vec::new());
+    }
+    if !flags.contains(NewRequestFlags::CREATE) {
+        return_errno_with_message!(Errno::ENOENT, "the address does not exist");
+    }
+
+    iface.add_ipv4_cidr(cidr)?;
+
+    let segment = new_addr_segment(
+        request_segment.header(),
+        CSegmentType::NEWADDR,
+        &iface,
+        cidr,
+    );
+    notify(RtnlGroup::IPV4_IFADDR, RtnlSegment::NewAddr(segment));
+
+    Ok(Vec::new())
+}
+
+pub(super) fn do_del_addr(request_segment: &AddrSegment) -> Result<Vec<RtnlSegment>> {
+    check_net_admin()?;
+
+    let (iface, local_cidr) = parse_addr_request(request_segment)?;
+
+    // Like Linux, the prefix length only matters if the address is specified.
+    let address = request_segment.attrs().iter().find_map(|attr| match attr {
+        AddrAttr::Address(address) => Some(Ipv4Address::from(*address)),
+        _ => None,
+    });
+    let prefix_len = request_segment.body().prefix_len;
+
+    let Some(cidr) = iface.ipv4_cidrs().into_iter().find(|cidr| {
+        local_cidr.is_none_or(|local_cidr| cidr.address() == local_cidr.address())
+            && address.is_none_or(|address| {
+                cidr.prefix_len() == prefix_len
+                    && Ipv4Cidr::new(address, prefix_len).network() == cidr.network()
+            })
+    }) else {
+        return_errno_with_message!(Errno::EADDRNOTAVAIL, "the address does not exist");
+    };
+
+    iface.del_ipv4_cidr(cidr)?;
+
+    let segment = new_addr_segment(
+        request_segment.header(),
+        CSegmentType::DELADDR,
+        &iface,
+        cidr,
+    );
+    notify(RtnlGroup::IPV4_IFADDR, RtnlSegment::DelAddr(segment));
 
+    Ok(Vec::new())
+}
+
+/// Notifies user space that the kernel itself has added an address to the iface.
+pub(in crate::net) fn notify_new_addr(iface: &Arc<Iface>, cidr: Ipv4Cidr) {
+    let segment = new_addr_segment(
+        &CMsgSegHdr::new_zeroed(),
+        CSegmentType::NEWADDR,
+        iface,
+        cidr,
+    );
+    notify(RtnlGroup::IPV4_IFADDR, RtnlSegment::NewAddr(segment));
+}
+
+/// Notifies user space that the kernel itself has deleted an address from the iface.
+pub(in crate::net) fn notify_del_addr(iface: &Arc<Iface>, cidr: Ipv4Cidr) {
+    let segment = new_addr_segment(
+        &CMsgSegHdr::new_zeroed(),
+        CSegmentType::DELADDR,
+        iface,
+        cidr,
+    );
+    notify(RtnlGroup::IPV4_IFADDR, RtnlSegment::DelAddr(segment));
+}
+
+/// Parses a request that adds or deletes an address.
+///
+/// This method returns the iface and the local address with its prefix length, if specified.
+fn parse_addr_request(request_segment: &AddrSegment) -> Result<(Arc<Iface>, Option<Ipv4Cidr>)> {
+    let body = request_segment.body();
+
+    if body.family != CSocketAddrFamily::AF_INET as i32 {
+        return_errno_with_message!(Errno::EOPNOTSUPP, "the address family is not supported");
+    }
+    if body.prefix_len > 32 {
+        return_errno_with_message!(Errno::EINVAL, "the prefix length is invalid");
+    }
+
+    let Some(index) = body.index else {
+        return_errno_with_message!(Errno::ENODEV, "the interface is not specified");
+    };
+    let iface = find_iface_by_index(index.get())?;
+
+    // If the local address is not specified, the address is used instead.
+    let local = request_segment
+        .attrs()
+        .iter()
+        .find_map(|attr| match attr {
+            AddrAttr::Local(local) => Some(*local),
+            _ => None,
+        })
+        .or_else(|| {
+            request_segment.attrs().iter().find_map(|attr| match attr {
+                AddrAttr::Address(address) => Some(*address),
+                _ => None,
+            })
+        });
+    let cidr = local.map(|local| Ipv4Cidr::new(Ipv4Address::from(local), body.prefix_len));
+
+    Ok((iface, cidr))
+}
+
+fn new_addr_segment(
+    request_header: &CMsgSegHdr,
+    type_: CSegmentType,
+    iface: &Arc<Iface>,
+    cidr: Ipv4Cidr,
+) -> AddrSegment {
     let header = CMsgSegHdr {
         len: 0,
-        type_: CSegmentType::NEWADDR as _,
+        type_: type_ as _,
         flags: SegHdrCommonFlags::empty().bits(),
         seq: request_header.seq,
         pid: request_header.pid,
     };
 
+    let ipv4_addr = cidr.address();
+    let scope = if ipv4_addr.is_loopback() {
+        RtScope::HOST
+    } else {
+        RtScope::UNIVERSE
+    };
+
     let addr_message = AddrSegmentBody {
         family: CSocketAddrFamily::AF_INET as _,
-        prefix_len: iface.prefix_len().unwrap(),
+        prefix_len: cidr.prefix_len(),
         flags: AddrMessageFlags::PERMANENT,
-        scope: RtScope::HOST,
+        scope,
         index: NonZeroU32::new(iface.index()),
     };
 
@@ -64,5 +212,5 @@ fn iface_to_new_addr(request_header: &CMsgSegHdr, iface: &Arc<Iface>) -> Option<
         AddrAttr::Local(ipv4_addr.octets()),
     ];
 
-    Some(AddrSegment::new(header, addr_message, attrs))
+    AddrSegment::new(header, addr_message, attrs)
 }
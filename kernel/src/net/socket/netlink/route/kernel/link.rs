@@ -4,16 +4,19 @@
 
 use core::num::NonZero;
 
-use aster_bigtcp::iface::InterfaceType;
+use aster_bigtcp::iface::{InterfaceFlags, InterfaceType};
 
-use super::util::finish_response;
+use super::util::{check_net_admin, finish_response, notify, RtnlGroup};
 use crate::{
     net::{
-        iface::{iter_all_ifaces, Iface},
+        iface::Iface,
         socket::netlink::{
-            message::{CMsgSegHdr, CSegmentType, GetRequestFlags, SegHdrCommonFlags},
+            message::{
+                CMsgSegHdr, CSegmentType, GetRequestFlags, NewRequestFlags, SegHdrCommonFlags,
+            },
             route::message::{LinkAttr, LinkSegment, LinkSegmentBody, RtnlSegment},
         },
+        NetNamespace,
     },
     prelude::*,
     util::net::CSocketAddrFamily,
@@ -22,7 +25,9 @@ use crate::{
 pub(super) fn do_get_link(request_segment: &LinkSegment) -> Result<Vec<RtnlSegment>> {
     let filter_by = FilterBy::from_request(request_segment)?;
 
-    let mut response_segments: Vec<RtnlSegment> = iter_all_ifaces()
+    let mut response_segments: Vec<RtnlSegment> = NetNamespace::current()
+        .ifaces()
+        .iter()
         // Filter to include only requested links.
         .filter(|iface| match &filter_by {
             FilterBy::Index(index) => *index == iface.index(),
@@ -44,6 +49,83 @@ pub(super) fn do_get_link(request_segment: &LinkSegment) -> Result<Vec<RtnlSegme
     Ok(response_segments)
 }
 
+/// Handles a SETLINK request or a NEWLINK request that changes an existing link.
+pub(super) fn do_set_link(request_segment: &LinkSegment) -> Result<Vec<RtnlSegment>> {
+    check_net_admin()?;
+
+    let header = request_segment.header();
+    let body = request_segment.body();
+    let is_new_link = header.type_ == CSegmentType::NEWLINK as u16;
+    let flags = NewRequestFlags::from_bits_truncate(header.flags);
+
+    let required_name = request_segment.attrs().iter().find_map(|attr| {
+        if let LinkAttr::Name(name) = attr {
+            Some(name.to_str().unwrap())
+        } else {
+            None
+        }
+    });
+
+    // `index` takes precedence over `required_name`.
+    let iface = NetNamespace::current()
+        .ifaces()
+        .iter()
+        .find(|iface| match (body.index, required_name) {
+            (Some(index), _) => index.get() == iface.index(),
+            (None, Some(name)) => name == iface.name(),
+            (None, None) => false,
+        })
+        .cloned();
+    let Some(iface) = iface else {
+        if is_new_link && flags.contains(NewRequestFlags::CREATE) {
+            return_errno_with_message!(Errno::EOPNOTSUPP, "creating links is not supported");
+        }
+        return_errno_with_message!(Errno::ENODEV, "no link found");
+    };
+    if is_new_link && flags.contains(NewRequestFlags::EXCL) {
+        return_errno_with_message!(Errno::EEXIST, "the link already exists");
+    }
+    if body.index.is_some() && required_name.is_some_and(|name| name != iface.name()) {
+        return_errno_with_message!(Errno::EOPNOTSUPP, "renaming links is not supported");
+    }
+
+    let mtu = request_segment.attrs().iter().find_map(|attr| {
+        if let LinkAttr::Mtu(mtu) = attr {
+            Some(*mtu as usize)
+        } else {
+            None
+        }
+    });
+    if let Some(mtu) = mtu {
+        iface.set_mtu(mtu)?;
+    }
+
+    // Like Linux, if the change mask is empty, all flags are replaced.
+    // Reference: <https://elixir.bootlin.com/linux/v6.13/source/net/core/rtnetlink.c#L3457>.
+    let old_flags = iface.flags();
+    if !body.flags.is_empty() || !body.change.is_empty() {
+        let new_flags = if body.change.is_empty() {
+            body.flags
+        } else {
+            (old_flags - body.change) | (body.flags & body.change)
+        };
+        iface.set_flags(new_flags);
+    }
+    let new_flags = iface.flags();
+
+    // Resume polling the iface after it is brought up.
+    if !old_flags.contains(InterfaceFlags::UP) && new_flags.contains(InterfaceFlags::UP) {
+        iface.poll();
+    }
+
+    if mtu.is_some() || old_flags != new_flags {
+        let segment = iface_to_new_link(header, &iface);
+        notify(RtnlGroup::LINK, RtnlSegment::NewLink(segment));
+    }
+
+    Ok(Vec::new())
+}
+
 enum FilterBy<'a> {
     Index(u32),
     Name(&'a str),
@@ -91,11 +173,10 @@ impl<'a> FilterBy<'a> {
 // Reference: <https://docs.kernel.org/userspace-api/netlink/intro.html#strict-checking>.
 
 fn validate_getlink_request(body: &LinkSegmentBody) -> Result<()> {
-    // FIXME: The Linux implementation also checks the `padding` and `change` fields,
-    // but these fields are lost during the conversion of a `CIfInfoMsg` to `LinkSegmentBody`.
-    // We should consider including the `change` field in `LinkSegmentBody`.
+    // FIXME: The Linux implementation also checks the `padding` field,
+    // but the field is lost during the conversion of a `CIfInfoMsg` to `LinkSegmentBody`.
     // Reference: <https://elixir.bootlin.com/linux/v6.13/source/net/core/rtnetlink.c#L4043>.
-    if !body.flags.is_empty() || body.type_ != InterfaceType::NETROM {
+    if !body.flags.is_empty() || !body.change.is_empty() || body.type_ != InterfaceType::NETROM {
         return_errno_with_message!(Errno::EINVAL, "the flags or the type is not valid");
     }
 
@@ -103,9 +184,9 @@ fn validate_getlink_request(body: &LinkSegmentBody) -> Result<()> {
 }
 
 fn validate_dumplink_request(body: &LinkSegmentBody) -> Result<()> {
-    // FIXME: The Linux implementation also checks the `padding` and `change` fields.
+    // FIXME: The Linux implementation also checks the `padding` field.
     // Reference: <https://elixir.bootlin.com/linux/v6.13/source/net/core/rtnetlink.c#L2378>.
-    if !body.flags.is_empty() || body.type_ != InterfaceType::NETROM {
+    if !body.flags.is_empty() || !body.change.is_empty() || body.type_ != InterfaceType::NETROM {
         return_errno_with_message!(Errno::EINVAL, "the flags or the type is not valid");
     }
 
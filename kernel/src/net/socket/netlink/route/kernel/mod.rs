@@ -9,7 +9,7 @@ use super::message::{RtnlMessage, RtnlSegment};
 use crate::{
     net::socket::netlink::{
         addr::PortNum,
-        message::{CSegmentType, ErrorSegment, ProtocolSegment},
+        message::{CSegmentType, ErrorSegment, ProtocolSegment, SegHdrCommonFlags},
         table::{NetlinkRouteProtocol, SupportedNetlinkProtocol},
     },
     prelude::*,
@@ -17,8 +17,13 @@ use crate::{
 
 mod addr;
 mod link;
+mod neigh;
+mod route;
 mod util;
 
+pub(in crate::net) use addr::{notify_del_addr, notify_new_addr};
+pub(in crate::net) use route::{notify_del_route, notify_new_route};
+
 pub(super) struct NetlinkRouteKernelSocket {
     _private: PhantomData<()>,
 }
@@ -40,7 +45,17 @@ impl NetlinkRouteKernelSocket {
 
             let response_segments = match segment {
                 RtnlSegment::GetLink(request_segment) => link::do_get_link(request_segment),
+                RtnlSegment::NewLink(request_segment) | RtnlSegment::SetLink(request_segment) => {
+                    link::do_set_link(request_segment)
+                }
                 RtnlSegment::GetAddr(request_segment) => addr::do_get_addr(request_segment),
+                RtnlSegment::NewAddr(request_segment) => addr::do_new_addr(request_segment),
+                RtnlSegment::DelAddr(request_segment) => addr::do_del_addr(request_segment),
+                RtnlSegment::GetRoute(request_segment) => route::do_get_route(request_segment),
+                RtnlSegment::NewRoute(request_segment) => route::do_new_route(request_segment),
+                RtnlSegment::DelRoute(request_segment) => route::do_del_route(request_segment),
+                RtnlSegment::GetNeigh(request_segment) => neigh::do_get_neigh(request_segment),
+                RtnlSegment::NewNeigh(request_segment) => neigh::do_new_neigh(request_segment),
                 _ => {
                     // FIXME: The error is currently silently ignored.
                     warn!("unsupported request type: {:?}", segment_type);
@@ -49,11 +64,20 @@ impl NetlinkRouteKernelSocket {
             };
 
             let response = match response_segments {
-                Ok(segments) => RtnlMessage::new(segments),
-                Err(error) => {
-                    // TODO: Deal with the `NetlinkMessageCommonFlags::ACK` flag.
-                    // Should we return `ErrorSegment` if ACK flag does not exist?
+                Ok(segments) if !segments.is_empty() => RtnlMessage::new(segments),
+                Ok(_) => {
+                    // Requests that change the configuration have no response segments. An
+                    // acknowledgment is sent only if it is requested.
                     // Reference: <https://docs.kernel.org/userspace-api/netlink/intro.html#netlink-message-types>.
+                    let flags = SegHdrCommonFlags::from_bits_truncate(request_header.flags);
+                    if !flags.contains(SegHdrCommonFlags::ACK) {
+                        continue;
+                    }
+                    let ack_segment = ErrorSegment::new_from_request(request_header, None);
+                    RtnlMessage::new(vec![RtnlSegment::Error(ack_segment)])
+                }
+                Err(error) => {
+                    // Errors are always reported, regardless of the `ACK` flag.
                     let err_segment = ErrorSegment::new_from_request(request_header, Some(error));
                     RtnlMessage::new(vec![RtnlSegment::Error(err_segment)])
                 }
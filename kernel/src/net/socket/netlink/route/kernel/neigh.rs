@@ -0,0 +1,137 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Handle neighbor-related requests.
+
+use core::num::NonZeroU32;
+
+use aster_bigtcp::wire::{EthernetAddress, Ipv4Address};
+
+use super::util::{check_net_admin, find_iface_by_index, finish_response, notify, RtnlGroup};
+use crate::{
+    net::{
+        iface::Iface,
+        socket::netlink::{
+            message::{
+                CMsgSegHdr, CSegmentType, GetRequestFlags, NewRequestFlags, SegHdrCommonFlags,
+            },
+            route::message::{
+                NeighAttr, NeighSegment, NeighSegmentBody, NeighState, RouteType, RtnlSegment,
+            },
+        },
+        NetNamespace,
+    },
+    prelude::*,
+    util::net::CSocketAddrFamily,
+};
+
+pub(super) fn do_get_neigh(request_segment: &NeighSegment) -> Result<Vec<RtnlSegment>> {
+    let dump_all = {
+        let flags = GetRequestFlags::from_bits_truncate(request_segment.header().flags);
+        flags.contains(GetRequestFlags::DUMP)
+    };
+    if !dump_all {
+        return_errno_with_message!(Errno::EOPNOTSUPP, "GETNEIGH only supports dump requests");
+    }
+
+    let body = request_segment.body();
+    let mut response_segments = Vec::new();
+
+    if body.family == CSocketAddrFamily::AF_UNSPEC as i32
+        || body.family == CSocketAddrFamily::AF_INET as i32
+    {
+        for iface in NetNamespace::current().ifaces() {
+            // Like Linux, the neighbors can be filtered by the interface index.
+            if body.index.is_some_and(|index| index.get() != iface.index()) {
+                continue;
+            }
+
+            for (ip_addr, ether_addr) in iface.neighbors() {
+                let segment =
+                    new_neigh_segment(request_segment.header(), iface, ip_addr, ether_addr);
+                response_segments.push(RtnlSegment::NewNeigh(segment));
+            }
+        }
+    }
+
+    finish_response(request_segment.header(), dump_all, &mut response_segments);
+
+    Ok(response_segments)
+}
+
+pub(super) fn do_new_neigh(request_segment: &NeighSegment) -> Result<Vec<RtnlSegment>> {
+    check_net_admin()?;
+
+    let body = request_segment.body();
+    if body.family != CSocketAddrFamily::AF_INET as i32 {
+        return_errno_with_message!(Errno::EAFNOSUPPORT, "the address family is not supported");
+    }
+    let Some(index) = body.index else {
+        return_errno_with_message!(Errno::EINVAL, "the interface is not specified");
+    };
+    let iface = find_iface_by_index(index.get())?;
+
+    let mut ip_addr = None;
+    let mut ether_addr = None;
+    for attr in request_segment.attrs() {
+        match attr {
+            NeighAttr::Dst(addr) => ip_addr = Some(Ipv4Address::from(*addr)),
+            NeighAttr::LlAddr(addr) => ether_addr = Some(EthernetAddress(*addr)),
+        }
+    }
+    let (Some(ip_addr), Some(ether_addr)) = (ip_addr, ether_addr) else {
+        return_errno_with_message!(
+            Errno::EINVAL,
+            "the network address or the link-layer address is not specified"
+        );
+    };
+
+    let flags = NewRequestFlags::from_bits_truncate(request_segment.header().flags);
+    let exists = iface
+        .neighbors()
+        .iter()
+        .any(|(neigh_ip_addr, _)| *neigh_ip_addr == ip_addr);
+    if exists && flags.contains(NewRequestFlags::EXCL) {
+        return_errno_with_message!(Errno::EEXIST, "the neighbor already exists");
+    }
+    if !exists && !flags.contains(NewRequestFlags::CREATE) {
+        return_errno_with_message!(Errno::ENOENT, "the neighbor does not exist");
+    }
+
+    iface.set_neighbor(ip_addr, ether_addr)?;
+
+    let segment = new_neigh_segment(request_segment.header(), &iface, ip_addr, ether_addr);
+    notify(RtnlGroup::NEIGH, RtnlSegment::NewNeigh(segment));
+
+    Ok(Vec::new())
+}
+
+fn new_neigh_segment(
+    request_header: &CMsgSegHdr,
+    iface: &Arc<Iface>,
+    ip_addr: Ipv4Address,
+    ether_addr: EthernetAddress,
+) -> NeighSegment {
+    let header = CMsgSegHdr {
+        len: 0,
+        type_: CSegmentType::NEWNEIGH as _,
+        flags: SegHdrCommonFlags::empty().bits(),
+        seq: request_header.seq,
+        pid: request_header.pid,
+    };
+
+    // FIXME: The state of the entries is not tracked, so we report all entries as reachable.
+    let body = NeighSegmentBody {
+        family: CSocketAddrFamily::AF_INET as _,
+        index: NonZeroU32::new(iface.index()),
+        state: NeighState::REACHABLE,
+        flags: 0,
+        type_: RouteType::UNICAST as _,
+    };
+
+    let attrs = vec![
+        NeighAttr::Dst(ip_addr.octets()),
+        NeighAttr::LlAddr(ether_addr.0),
+    ];
+
+    NeighSegment::new(header, body, attrs)
+}
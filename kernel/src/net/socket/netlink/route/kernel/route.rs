@@ -0,0 +1,308 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Handle route-related requests.
+
+use aster_bigtcp::{
+    iface::{InterfaceFlags, Ipv4Route},
+    wire::{Ipv4Address, Ipv4Cidr},
+};
+
+use super::util::{check_net_admin, find_iface_by_index, finish_response, notify, RtnlGroup};
+use crate::{
+    net::{
+        iface::Iface,
+        socket::netlink::{
+            message::{
+                CMsgSegHdr, CSegmentType, GetRequestFlags, NewRequestFlags, SegHdrCommonFlags,
+            },
+            route::message::{
+                RouteAttr, RouteSegment, RouteSegmentBody, RouteType, RtProtocol, RtScope, RtTable,
+                RtnlSegment,
+            },
+        },
+        NetNamespace,
+    },
+    prelude::*,
+    util::net::CSocketAddrFamily,
+};
+
+pub(super) fn do_get_route(request_segment: &RouteSegment) -> Result<Vec<RtnlSegment>> {
+    let dump_all = {
+        let flags = GetRequestFlags::from_bits_truncate(request_segment.header().flags);
+        flags.contains(GetRequestFlags::DUMP)
+    };
+    if !dump_all {
+        return_errno_with_message!(Errno::EOPNOTSUPP, "GETROUTE only supports dump requests");
+    }
+
+    let family = request_segment.body().family;
+    let mut response_segments = Vec::new();
+
+    if family == CSocketAddrFamily::AF_UNSPEC as i32 || family == CSocketAddrFamily::AF_INET as i32
+    {
+        let request_header = request_segment.header();
+        for iface in NetNamespace::current().ifaces() {
+            for cidr in iface.ipv4_cidrs() {
+                let segment = new_prefix_route_segment(request_header, iface, cidr);
+                response_segments.push(RtnlSegment::NewRoute(segment));
+            }
+            for route in iface.ipv4_routes() {
+                let segment =
+                    new_route_segment(request_header, CSegmentType::NEWROUTE, iface, route);
+                response_segments.push(RtnlSegment::NewRoute(segment));
+            }
+        }
+    }
+
+    finish_response(request_segment.header(), dump_all, &mut response_segments);
+
+    Ok(response_segments)
+}
+
+pub(super) fn do_new_route(request_segment: &RouteSegment) -> Result<Vec<RtnlSegment>> {
+    check_net_admin()?;
+
+    let request = RouteRequest::parse(request_segment)?;
+    let Some(gateway) = request.gateway else {
+        return_errno_with_message!(
+            Errno::EOPNOTSUPP,
+            "only the routes via gateways are supported"
+        );
+    };
+
+    // Find the iface through which the gateway is reachable.
+    let reaches_gateway = |iface: &Arc<Iface>| {
+        iface
+            .ipv4_cidrs()
+            .iter()
+            .any(|cidr| cidr.contains_addr(&gateway))
+    };
+    let iface = match request.oif {
+        Some(oif) => Some(find_iface_by_index(oif)?).filter(reaches_gateway),
+        None => NetNamespace::current()
+            .ifaces()
+            .iter()
+            .find(|iface| reaches_gateway(iface))
+            .cloned(),
+    };
+    let Some(iface) = iface else {
+        return_errno_with_message!(Errno::ENETUNREACH, "the gateway is unreachable");
+    };
+
+    let flags = NewRequestFlags::from_bits_truncate(request_segment.header().flags);
+    if let Some((old_iface, old_route)) = find_route(request.cidr, None, None) {
+        if flags.contains(NewRequestFlags::EXCL) || !flags.contains(NewRequestFlags::REPLACE) {
+            return_errno_with_message!(Errno::EEXIST, "the route already exists");
+        }
+        old_iface.del_ipv4_route(old_route.cidr, Some(old_route.gateway))?;
+    } else if !flags.contains(NewRequestFlags::CREATE) {
+        return_errno_with_message!(Errno::ENOENT, "the route does not exist");
+    }
+
+    let route = Ipv4Route {
+        cidr: request.cidr,
+        gateway,
+    };
+    iface.add_ipv4_route(route)?;
+
+    let segment = new_route_segment(
+        request_segment.header(),
+        CSegmentType::NEWROUTE,
+        &iface,
+        route,
+    );
+    notify(RtnlGroup::IPV4_ROUTE, RtnlSegment::NewRoute(segment));
+
+    Ok(Vec::new())
+}
+
+pub(super) fn do_del_route(request_segment: &RouteSegment) -> Result<Vec<RtnlSegment>> {
+    check_net_admin()?;
+
+    let request = RouteRequest::parse(request_segment)?;
+    let Some((iface, route)) = find_route(request.cidr, request.gateway, request.oif) else {
+        return_errno_with_message!(Errno::ESRCH, "the route does not exist");
+    };
+
+    iface.del_ipv4_route(route.cidr, Some(route.gateway))?;
+
+    let segment = new_route_segment(
+        request_segment.header(),
+        CSegmentType::DELROUTE,
+        &iface,
+        route,
+    );
+    notify(RtnlGroup::IPV4_ROUTE, RtnlSegment::DelRoute(segment));
+
+    Ok(Vec::new())
+}
+
+/// Notifies user space that the kernel itself has added a route to the iface.
+pub(in crate::net) fn notify_new_route(iface: &Arc<Iface>, route: Ipv4Route) {
+    let segment = new_route_segment(
+        &CMsgSegHdr::new_zeroed(),
+        CSegmentType::NEWROUTE,
+        iface,
+        route,
+    );
+    notify(RtnlGroup::IPV4_ROUTE, RtnlSegment::NewRoute(segment));
+}
+
+/// Notifies user space that the kernel itself has deleted a route from the iface.
+pub(in crate::net) fn notify_del_route(iface: &Arc<Iface>, route: Ipv4Route) {
+    let segment = new_route_segment(
+        &CMsgSegHdr::new_zeroed(),
+        CSegmentType::DELROUTE,
+        iface,
+        route,
+    );
+    notify(RtnlGroup::IPV4_ROUTE, RtnlSegment::DelRoute(segment));
+}
+
+/// A request that adds or deletes a route.
+struct RouteRequest {
+    cidr: Ipv4Cidr,
+    gateway: Option<Ipv4Address>,
+    oif: Option<u32>,
+}
+
+impl RouteRequest {
+    fn parse(request_segment: &RouteSegment) -> Result<Self> {
+        let body = request_segment.body();
+
+        if body.family != CSocketAddrFamily::AF_INET as i32 {
+            return_errno_with_message!(Errno::EOPNOTSUPP, "the address family is not supported");
+        }
+        if body.dst_len > 32 {
+            return_errno_with_message!(Errno::EINVAL, "the prefix length is invalid");
+        }
+        if body.type_ != RouteType::UNICAST && body.type_ != RouteType::UNSPEC {
+            return_errno_with_message!(Errno::EOPNOTSUPP, "only unicast routes are supported");
+        }
+
+        let mut table = body.table as u32;
+        let mut dst = Ipv4Address::UNSPECIFIED;
+        let mut gateway = None;
+        let mut oif = None;
+        for attr in request_segment.attrs() {
+            match attr {
+                RouteAttr::Dst(addr) => dst = Ipv4Address::from(*addr),
+                RouteAttr::Gateway(addr) => gateway = Some(Ipv4Address::from(*addr)),
+                RouteAttr::Oif(index) => oif = Some(*index),
+                RouteAttr::Table(id) => table = *id,
+                RouteAttr::Priority(_) | RouteAttr::PrefSrc(_) => {
+                    warn!("route attribute `{:?}` is ignored", attr);
+                }
+            }
+        }
+
+        if table != RtTable::MAIN as u32 && table != RtTable::UNSPEC as u32 {
+            return_errno_with_message!(Errno::EOPNOTSUPP, "only the main table is supported");
+        }
+
+        let cidr = Ipv4Cidr::new(dst, body.dst_len);
+        if cidr.network().address() != dst {
+            return_errno_with_message!(Errno::EINVAL, "the prefix is invalid for its length");
+        }
+
+        Ok(Self { cidr, gateway, oif })
+    }
+}
+
+/// Finds the route to `cidr`, optionally via `gateway` and through the iface with index `oif`.
+fn find_route(
+    cidr: Ipv4Cidr,
+    gateway: Option<Ipv4Address>,
+    oif: Option<u32>,
+) -> Option<(Arc<Iface>, Ipv4Route)> {
+    NetNamespace::current()
+        .ifaces()
+        .iter()
+        .filter(|iface| oif.is_none_or(|oif| oif == iface.index()))
+        .find_map(|iface| {
+            let route = iface.ipv4_routes().into_iter().find(|route| {
+                route.cidr == cidr && gateway.is_none_or(|gateway| gateway == route.gateway)
+            })?;
+            Some((iface.clone(), route))
+        })
+}
+
+/// Creates a segment that describes a route via a gateway.
+fn new_route_segment(
+    request_header: &CMsgSegHdr,
+    type_: CSegmentType,
+    iface: &Arc<Iface>,
+    route: Ipv4Route,
+) -> RouteSegment {
+    let body = RouteSegmentBody {
+        family: CSocketAddrFamily::AF_INET as _,
+        dst_len: route.cidr.prefix_len(),
+        src_len: 0,
+        tos: 0,
+        table: RtTable::MAIN as _,
+        protocol: RtProtocol::BOOT as _,
+        scope: RtScope::UNIVERSE,
+        type_: RouteType::UNICAST,
+        flags: 0,
+    };
+
+    let mut attrs = vec![RouteAttr::Table(RtTable::MAIN as _)];
+    if route.cidr.prefix_len() != 0 {
+        attrs.push(RouteAttr::Dst(route.cidr.address().octets()));
+    }
+    attrs.push(RouteAttr::Gateway(route.gateway.octets()));
+    attrs.push(RouteAttr::Oif(iface.index()));
+
+    RouteSegment::new(new_header(request_header, type_), body, attrs)
+}
+
+/// Creates a segment that describes the route to the network of an address of the iface.
+///
+/// Like Linux, the routes of loopback ifaces belong to the local table.
+fn new_prefix_route_segment(
+    request_header: &CMsgSegHdr,
+    iface: &Arc<Iface>,
+    cidr: Ipv4Cidr,
+) -> RouteSegment {
+    let is_loopback = iface.flags().contains(InterfaceFlags::LOOPBACK);
+    let (table, scope, type_) = if is_loopback {
+        (RtTable::LOCAL, RtScope::HOST, RouteType::LOCAL)
+    } else {
+        (RtTable::MAIN, RtScope::LINK, RouteType::UNICAST)
+    };
+
+    let body = RouteSegmentBody {
+        family: CSocketAddrFamily::AF_INET as _,
+        dst_len: cidr.prefix_len(),
+        src_len: 0,
+        tos: 0,
+        table: table as _,
+        protocol: RtProtocol::KERNEL as _,
+        scope,
+        type_,
+        flags: 0,
+    };
+
+    let attrs = vec![
+        RouteAttr::Table(table as _),
+        RouteAttr::Dst(cidr.network().address().octets()),
+        RouteAttr::PrefSrc(cidr.address().octets()),
+        RouteAttr::Oif(iface.index()),
+    ];
+
+    RouteSegment::new(
+        new_header(request_header, CSegmentType::NEWROUTE),
+        body,
+        attrs,
+    )
+}
+
+fn new_header(request_header: &CMsgSegHdr, type_: CSegmentType) -> CMsgSegHdr {
+    CMsgSegHdr {
+        len: 0,
+        type_: type_ as _,
+        flags: SegHdrCommonFlags::empty().bits(),
+        seq: request_header.seq,
+        pid: request_header.pid,
+    }
+}
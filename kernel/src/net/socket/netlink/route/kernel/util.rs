@@ -1,11 +1,20 @@
 // SPDX-License-Identifier: MPL-2.0
 
+use aster_bigtcp::errors::iface::ConfigError;
+
 use crate::{
-    net::socket::netlink::{
-        message::{CMsgSegHdr, DoneSegment, ProtocolSegment, SegHdrCommonFlags},
-        route::message::RtnlSegment,
+    net::{
+        iface::Iface,
+        socket::netlink::{
+            message::{CMsgSegHdr, DoneSegment, ProtocolSegment, SegHdrCommonFlags},
+            route::message::{RtnlMessage, RtnlSegment},
+            table::{NetlinkRouteProtocol, SupportedNetlinkProtocol},
+            GroupIdSet,
+        },
+        NetNamespace,
     },
     prelude::*,
+    process::{credentials::capabilities::CapSet, posix_thread::AsPosixThread},
 };
 
 /// Finishes a response message.
@@ -37,3 +46,76 @@ fn add_multi_flag(response_segments: &mut [RtnlSegment]) {
         header.flags = flags.bits();
     }
 }
+
+/// Checks whether the current thread can change the network configuration.
+pub fn check_net_admin() -> Result<()> {
+    let credentials = {
+        let current = current_thread!();
+        current.as_posix_thread().unwrap().credentials()
+    };
+    if !credentials.effective_capset().contains(CapSet::NET_ADMIN) {
+        return_errno_with_message!(
+            Errno::EPERM,
+            "the thread does not have the capability to change the network configuration"
+        );
+    }
+
+    Ok(())
+}
+
+/// Finds the iface with the index in the current network namespace.
+pub fn find_iface_by_index(index: u32) -> Result<Arc<Iface>> {
+    NetNamespace::current()
+        .ifaces()
+        .iter()
+        .find(|iface| iface.index() == index)
+        .cloned()
+        .ok_or_else(|| Error::with_message(Errno::ENODEV, "the interface does not exist"))
+}
+
+/// The multicast groups of the netlink route protocol.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.13/source/include/uapi/linux/rtnetlink.h#L726>.
+#[repr(u32)]
+#[derive(Debug, Clone, Copy)]
+#[expect(non_camel_case_types)]
+#[expect(clippy::upper_case_acronyms)]
+pub enum RtnlGroup {
+    LINK = 1,
+    NEIGH = 3,
+    IPV4_IFADDR = 5,
+    IPV4_ROUTE = 7,
+}
+
+/// Notifies the sockets in the multicast group of a change in the network configuration.
+pub fn notify(group: RtnlGroup, segment: RtnlSegment) {
+    let groups = GroupIdSet::new(1 << (group as u32 - 1));
+    let message = RtnlMessage::new(vec![segment]);
+
+    // Like Linux, a notification is lost if a receiver has no room for it.
+    if let Err(error) = NetlinkRouteProtocol::multicast(groups, message) {
+        debug!(
+            "failed to deliver the netlink route notification: {:?}",
+            error
+        );
+    }
+}
+
+impl From<ConfigError> for Error {
+    fn from(value: ConfigError) -> Self {
+        match value {
+            ConfigError::AlreadyExists => {
+                Error::with_message(Errno::EEXIST, "the entry already exists")
+            }
+            ConfigError::NotFound => Error::with_message(Errno::ENOENT, "the entry does not exist"),
+            ConfigError::NoSpace => {
+                Error::with_message(Errno::ENOSPC, "the interface has no room for more entries")
+            }
+            ConfigError::InvalidValue => Error::with_message(Errno::EINVAL, "the value is invalid"),
+            ConfigError::Unsupported => Error::with_message(
+                Errno::EOPNOTSUPP,
+                "the interface does not support the operation",
+            ),
+        }
+    }
+}
@@ -2,6 +2,8 @@
 
 pub mod addr;
 pub mod link;
+pub mod neigh;
+pub mod route;
 
 /// The size limit for interface names.
 const IFNAME_SIZE: usize = 16;
@@ -0,0 +1,96 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use crate::{
+    net::socket::netlink::message::{Attribute, CAttrHeader},
+    prelude::*,
+    util::MultiRead,
+};
+
+/// Neighbor-related attributes.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.13/source/include/uapi/linux/neighbour.h#L19>.
+#[derive(Debug, Clone, Copy, TryFromInt)]
+#[repr(u16)]
+#[expect(non_camel_case_types)]
+#[expect(clippy::upper_case_acronyms)]
+enum NeighAttrClass {
+    UNSPEC = 0,
+    DST = 1,
+    LLADDR = 2,
+    CACHEINFO = 3,
+    PROBES = 4,
+    VLAN = 5,
+    PORT = 6,
+    VNI = 7,
+    IFINDEX = 8,
+    MASTER = 9,
+    LINK_NETNSID = 10,
+    SRC_VNI = 11,
+    PROTOCOL = 12,
+    NH_ID = 13,
+    FDB_EXT_ATTRS = 14,
+    FLAGS_EXT = 15,
+    NDM_STATE_MASK = 16,
+    NDM_FLAGS_MASK = 17,
+}
+
+#[derive(Debug, Clone)]
+pub enum NeighAttr {
+    Dst([u8; 4]),
+    LlAddr([u8; 6]),
+}
+
+impl NeighAttr {
+    fn class(&self) -> NeighAttrClass {
+        match self {
+            NeighAttr::Dst(_) => NeighAttrClass::DST,
+            NeighAttr::LlAddr(_) => NeighAttrClass::LLADDR,
+        }
+    }
+}
+
+impl Attribute for NeighAttr {
+    fn type_(&self) -> u16 {
+        self.class() as u16
+    }
+
+    fn payload_as_bytes(&self) -> &[u8] {
+        match self {
+            NeighAttr::Dst(dst) => dst,
+            NeighAttr::LlAddr(ll_addr) => ll_addr,
+        }
+    }
+
+    fn read_from(header: &CAttrHeader, reader: &mut dyn MultiRead) -> Result<Option<Self>>
+    where
+        Self: Sized,
+    {
+        let payload_len = header.payload_len();
+
+        // TODO: Currently, `IS_NET_BYTEORDER_MASK` and `IS_NESTED_MASK` are ignored.
+        let Ok(class) = NeighAttrClass::try_from(header.type_()) else {
+            // Unknown attributes should be ignored.
+            // Reference: <https://docs.kernel.org/userspace-api/netlink/intro.html#unknown-attributes>.
+            reader.skip_some(payload_len);
+            return Ok(None);
+        };
+
+        let res = match (class, payload_len) {
+            (NeighAttrClass::DST, 4) => Self::Dst(reader.read_val_opt::<[u8; 4]>()?.unwrap()),
+            (NeighAttrClass::LLADDR, 6) => Self::LlAddr(reader.read_val_opt::<[u8; 6]>()?.unwrap()),
+
+            (NeighAttrClass::DST | NeighAttrClass::LLADDR, _) => {
+                warn!("neighbor attribute `{:?}` contains invalid payload", class);
+                return_errno_with_message!(Errno::EINVAL, "the neighbor attribute is invalid");
+            }
+
+            (_, _) => {
+                warn!("neighbor attribute `{:?}` is not supported", class);
+                reader.skip_some(payload_len);
+                return Ok(None);
+            }
+        };
+
+        Ok(Some(res))
+    }
+}
@@ -0,0 +1,140 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use crate::{
+    net::socket::netlink::message::{Attribute, CAttrHeader},
+    prelude::*,
+    util::MultiRead,
+};
+
+/// Route-related attributes.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.13/source/include/uapi/linux/rtnetlink.h#L370>.
+#[derive(Debug, Clone, Copy, TryFromInt)]
+#[repr(u16)]
+#[expect(non_camel_case_types)]
+#[expect(clippy::upper_case_acronyms)]
+enum RouteAttrClass {
+    UNSPEC = 0,
+    DST = 1,
+    SRC = 2,
+    IIF = 3,
+    OIF = 4,
+    GATEWAY = 5,
+    PRIORITY = 6,
+    PREFSRC = 7,
+    METRICS = 8,
+    MULTIPATH = 9,
+    /// No longer used
+    PROTOINFO = 10,
+    FLOW = 11,
+    CACHEINFO = 12,
+    /// No longer used
+    SESSION = 13,
+    /// No longer used
+    MP_ALGO = 14,
+    TABLE = 15,
+    MARK = 16,
+    MFC_STATS = 17,
+    VIA = 18,
+    NEWDST = 19,
+    PREF = 20,
+    ENCAP_TYPE = 21,
+    ENCAP = 22,
+    EXPIRES = 23,
+    PAD = 24,
+    UID = 25,
+    TTL_PROPAGATE = 26,
+    IP_PROTO = 27,
+    SPORT = 28,
+    DPORT = 29,
+    NH_ID = 30,
+}
+
+#[derive(Debug, Clone)]
+pub enum RouteAttr {
+    Dst([u8; 4]),
+    Oif(u32),
+    Gateway([u8; 4]),
+    Priority(u32),
+    PrefSrc([u8; 4]),
+    Table(u32),
+}
+
+impl RouteAttr {
+    fn class(&self) -> RouteAttrClass {
+        match self {
+            RouteAttr::Dst(_) => RouteAttrClass::DST,
+            RouteAttr::Oif(_) => RouteAttrClass::OIF,
+            RouteAttr::Gateway(_) => RouteAttrClass::GATEWAY,
+            RouteAttr::Priority(_) => RouteAttrClass::PRIORITY,
+            RouteAttr::PrefSrc(_) => RouteAttrClass::PREFSRC,
+            RouteAttr::Table(_) => RouteAttrClass::TABLE,
+        }
+    }
+}
+
+impl Attribute for RouteAttr {
+    fn type_(&self) -> u16 {
+        self.class() as u16
+    }
+
+    fn payload_as_bytes(&self) -> &[u8] {
+        match self {
+            RouteAttr::Dst(dst) => dst,
+            RouteAttr::Oif(oif) => oif.as_bytes(),
+            RouteAttr::Gateway(gateway) => gateway,
+            RouteAttr::Priority(priority) => priority.as_bytes(),
+            RouteAttr::PrefSrc(pref_src) => pref_src,
+            RouteAttr::Table(table) => table.as_bytes(),
+        }
+    }
+
+    fn read_from(header: &CAttrHeader, reader: &mut dyn MultiRead) -> Result<Option<Self>>
+    where
+        Self: Sized,
+    {
+        let payload_len = header.payload_len();
+
+        // TODO: Currently, `IS_NET_BYTEORDER_MASK` and `IS_NESTED_MASK` are ignored.
+        let Ok(class) = RouteAttrClass::try_from(header.type_()) else {
+            // Unknown attributes should be ignored.
+            // Reference: <https://docs.kernel.org/userspace-api/netlink/intro.html#unknown-attributes>.
+            reader.skip_some(payload_len);
+            return Ok(None);
+        };
+
+        let res = match (class, payload_len) {
+            (RouteAttrClass::DST, 4) => Self::Dst(reader.read_val_opt::<[u8; 4]>()?.unwrap()),
+            (RouteAttrClass::OIF, 4) => Self::Oif(reader.read_val_opt::<u32>()?.unwrap()),
+            (RouteAttrClass::GATEWAY, 4) => {
+                Self::Gateway(reader.read_val_opt::<[u8; 4]>()?.unwrap())
+            }
+            (RouteAttrClass::PRIORITY, 4) => Self::Priority(reader.read_val_opt::<u32>()?.unwrap()),
+            (RouteAttrClass::PREFSRC, 4) => {
+                Self::PrefSrc(reader.read_val_opt::<[u8; 4]>()?.unwrap())
+            }
+            (RouteAttrClass::TABLE, 4) => Self::Table(reader.read_val_opt::<u32>()?.unwrap()),
+
+            (
+                RouteAttrClass::DST
+                | RouteAttrClass::OIF
+                | RouteAttrClass::GATEWAY
+                | RouteAttrClass::PRIORITY
+                | RouteAttrClass::PREFSRC
+                | RouteAttrClass::TABLE,
+                _,
+            ) => {
+                warn!("route attribute `{:?}` contains invalid payload", class);
+                return_errno_with_message!(Errno::EINVAL, "the route attribute is invalid");
+            }
+
+            (_, _) => {
+                warn!("route attribute `{:?}` is not supported", class);
+                reader.skip_some(payload_len);
+                return Ok(None);
+            }
+        };
+
+        Ok(Some(res))
+    }
+}
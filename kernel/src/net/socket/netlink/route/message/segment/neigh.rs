@@ -0,0 +1,96 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use core::num::NonZeroU32;
+
+use super::legacy::CRtGenMsg;
+use crate::{
+    net::socket::netlink::{
+        message::{SegmentBody, SegmentCommon},
+        route::message::attr::neigh::NeighAttr,
+    },
+    prelude::*,
+};
+
+pub type NeighSegment = SegmentCommon<NeighSegmentBody, NeighAttr>;
+
+impl SegmentBody for NeighSegmentBody {
+    type CLegacyType = CRtGenMsg;
+    type CType = CNdMsg;
+}
+
+/// `ndmsg` in Linux.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.13/source/include/uapi/linux/neighbour.h#L8>.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+pub struct CNdMsg {
+    pub family: u8,
+    /// Padding byte
+    pub _pad1: u8,
+    /// Padding bytes
+    pub _pad2: u16,
+    /// Link index
+    pub index: u32,
+    /// Neighbor state
+    pub state: u16,
+    /// Flags
+    pub flags: u8,
+    /// Neighbor type
+    pub type_: u8,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct NeighSegmentBody {
+    pub family: i32,
+    pub index: Option<NonZeroU32>,
+    pub state: NeighState,
+    pub flags: u8,
+    pub type_: u8,
+}
+
+impl TryFrom<CNdMsg> for NeighSegmentBody {
+    type Error = Error;
+
+    fn try_from(value: CNdMsg) -> Result<Self> {
+        let index = NonZeroU32::new(value.index);
+        let state = NeighState::from_bits_truncate(value.state);
+
+        Ok(Self {
+            family: value.family as i32,
+            index,
+            state,
+            flags: value.flags,
+            type_: value.type_,
+        })
+    }
+}
+
+impl From<NeighSegmentBody> for CNdMsg {
+    fn from(value: NeighSegmentBody) -> Self {
+        CNdMsg {
+            family: value.family as u8,
+            _pad1: 0,
+            _pad2: 0,
+            index: value.index.map(NonZeroU32::get).unwrap_or(0),
+            state: value.state.bits(),
+            flags: value.flags,
+            type_: value.type_,
+        }
+    }
+}
+
+bitflags! {
+    /// Neighbor cache entry states in [`CNdMsg`].
+    ///
+    /// Reference: <https://elixir.bootlin.com/linux/v6.13/source/include/uapi/linux/neighbour.h#L65>.
+    pub struct NeighState: u16 {
+        const INCOMPLETE = 0x01;
+        const REACHABLE  = 0x02;
+        const STALE      = 0x04;
+        const DELAY      = 0x08;
+        const PROBE      = 0x10;
+        const FAILED     = 0x20;
+        const NOARP      = 0x40;
+        const PERMANENT  = 0x80;
+    }
+}
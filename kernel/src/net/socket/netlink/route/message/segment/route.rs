@@ -1,21 +1,158 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use crate::prelude::*;
+use super::{addr::RtScope, legacy::CRtGenMsg};
+use crate::{
+    net::socket::netlink::{
+        message::{SegmentBody, SegmentCommon},
+        route::message::attr::route::RouteAttr,
+    },
+    prelude::*,
+};
+
+pub type RouteSegment = SegmentCommon<RouteSegmentBody, RouteAttr>;
+
+impl SegmentBody for RouteSegmentBody {
+    type CLegacyType = CRtGenMsg;
+    type CType = CRtMsg;
+}
 
 /// `rtmsg` in Linux.
 ///
 /// Reference: <https://elixir.bootlin.com/linux/v6.13/source/include/uapi/linux/rtnetlink.h#L237>.
 #[repr(C)]
 #[derive(Debug, Clone, Copy, Pod)]
-#[expect(unused)]
-pub(super) struct CRtMsg {
-    family: u8,
-    dst_len: u8,
-    src_len: u8,
-    tos: u8,
-    table: u8,
-    protocol: u8,
-    scope: u8,
-    type_: u8,
-    flags: u32,
+pub struct CRtMsg {
+    pub family: u8,
+    /// The prefix length of the destination
+    pub dst_len: u8,
+    /// The prefix length of the source
+    pub src_len: u8,
+    /// The type of service
+    pub tos: u8,
+    /// The routing table ID
+    pub table: u8,
+    /// The routing protocol
+    pub protocol: u8,
+    /// The distance to the destination
+    pub scope: u8,
+    /// The route type
+    pub type_: u8,
+    /// Flags
+    pub flags: u32,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct RouteSegmentBody {
+    pub family: i32,
+    pub dst_len: u8,
+    pub src_len: u8,
+    pub tos: u8,
+    pub table: u8,
+    pub protocol: u8,
+    pub scope: RtScope,
+    pub type_: RouteType,
+    pub flags: u32,
+}
+
+impl TryFrom<CRtMsg> for RouteSegmentBody {
+    type Error = Error;
+
+    fn try_from(value: CRtMsg) -> Result<Self> {
+        let scope = RtScope::try_from(value.scope)?;
+        let type_ = RouteType::try_from(value.type_)?;
+
+        Ok(Self {
+            family: value.family as i32,
+            dst_len: value.dst_len,
+            src_len: value.src_len,
+            tos: value.tos,
+            table: value.table,
+            protocol: value.protocol,
+            scope,
+            type_,
+            flags: value.flags,
+        })
+    }
+}
+
+impl From<RouteSegmentBody> for CRtMsg {
+    fn from(value: RouteSegmentBody) -> Self {
+        CRtMsg {
+            family: value.family as u8,
+            dst_len: value.dst_len,
+            src_len: value.src_len,
+            tos: value.tos,
+            table: value.table,
+            protocol: value.protocol,
+            scope: value.scope as _,
+            type_: value.type_ as _,
+            flags: value.flags,
+        }
+    }
+}
+
+/// Reserved routing table IDs.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.13/source/include/uapi/linux/rtnetlink.h#L350>.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, TryFromInt)]
+#[expect(clippy::upper_case_acronyms)]
+pub enum RtTable {
+    UNSPEC = 0,
+    // User defined values
+    COMPAT = 252,
+    DEFAULT = 253,
+    MAIN = 254,
+    LOCAL = 255,
+}
+
+/// Routing protocols, i.e., the origins of routes.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.13/source/include/uapi/linux/rtnetlink.h#L279>.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, TryFromInt)]
+#[expect(clippy::upper_case_acronyms)]
+pub enum RtProtocol {
+    UNSPEC = 0,
+    /// Route installed by ICMP redirects
+    REDIRECT = 1,
+    /// Route installed by the kernel
+    KERNEL = 2,
+    /// Route installed during boot
+    BOOT = 3,
+    /// Route installed by the administrator
+    STATIC = 4,
+    // TODO: The list is not exhaustive.
+}
+
+/// Route types.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.13/source/include/uapi/linux/rtnetlink.h#L255>.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, TryFromInt, PartialEq, Eq)]
+#[expect(clippy::upper_case_acronyms)]
+pub enum RouteType {
+    UNSPEC = 0,
+    /// Gateway or direct route
+    UNICAST = 1,
+    /// Accept locally
+    LOCAL = 2,
+    /// Accept locally as broadcast, send as broadcast
+    BROADCAST = 3,
+    /// Accept locally as broadcast, but send as unicast
+    ANYCAST = 4,
+    /// Multicast route
+    MULTICAST = 5,
+    /// Drop
+    BLACKHOLE = 6,
+    /// Destination is unreachable
+    UNREACHABLE = 7,
+    /// Administratively prohibited
+    PROHIBIT = 8,
+    /// Not in this table
+    THROW = 9,
+    /// Translate this address
+    NAT = 10,
+    /// Use external resolver
+    XRESOLVE = 11,
 }
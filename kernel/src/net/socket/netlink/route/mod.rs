@@ -2,6 +2,9 @@
 
 //! Netlink Route Socket.
 
+pub(in crate::net) use kernel::{
+    notify_del_addr, notify_del_route, notify_new_addr, notify_new_route,
+};
 pub(super) use message::RtnlMessage;
 
 use crate::net::socket::netlink::{common::NetlinkSocket, table::NetlinkRouteProtocol};
@@ -27,4 +27,5 @@ impl_socket_options!(
     pub struct SendBufForce(u32);
     pub struct RecvBufForce(u32);
     pub struct PeerGroups(Arc<[Gid]>);
+    pub struct Broadcast(bool);
 );
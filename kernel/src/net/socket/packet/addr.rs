@@ -0,0 +1,80 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use aster_bigtcp::wire::EthernetAddress;
+
+use crate::{net::socket::util::SocketAddr, prelude::*};
+
+/// The socket address of a packet socket.
+///
+/// The address specifies an iface and a link-layer protocol. The addresses of received frames
+/// also describe the frames, i.e., their types and their link-layer source addresses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PacketSocketAddr {
+    /// The link-layer protocol in host byte order.
+    pub protocol: u16,
+    /// The interface index, or zero for all ifaces.
+    pub ifindex: u32,
+    /// The ARP hardware type of the iface.
+    pub hatype: u16,
+    /// The type of the frame.
+    pub packet_type: u8,
+    /// The length of the link-layer address.
+    pub hw_addr_len: u8,
+    /// The link-layer address, padded with zeros.
+    pub hw_addr: [u8; 8],
+}
+
+impl PacketSocketAddr {
+    /// Creates a new address with an Ethernet address.
+    pub fn new_ether(
+        protocol: u16,
+        ifindex: u32,
+        hatype: u16,
+        packet_type: u8,
+        ether_addr: EthernetAddress,
+    ) -> Self {
+        let mut hw_addr = [0; 8];
+        hw_addr[..ETHER_ADDR_LEN].copy_from_slice(ether_addr.as_bytes());
+
+        Self {
+            protocol,
+            ifindex,
+            hatype,
+            packet_type,
+            hw_addr_len: ETHER_ADDR_LEN as u8,
+            hw_addr,
+        }
+    }
+
+    /// Returns the Ethernet address, if the link-layer address is long enough.
+    pub fn ether_addr(&self) -> Option<EthernetAddress> {
+        if (self.hw_addr_len as usize) < ETHER_ADDR_LEN {
+            return None;
+        }
+
+        Some(EthernetAddress::from_bytes(&self.hw_addr[..ETHER_ADDR_LEN]))
+    }
+}
+
+/// The length of Ethernet addresses.
+const ETHER_ADDR_LEN: usize = 6;
+
+impl TryFrom<SocketAddr> for PacketSocketAddr {
+    type Error = Error;
+
+    fn try_from(value: SocketAddr) -> Result<Self> {
+        match value {
+            SocketAddr::Packet(addr) => Ok(addr),
+            _ => return_errno_with_message!(
+                Errno::EINVAL,
+                "the socket address is not a packet socket address"
+            ),
+        }
+    }
+}
+
+impl From<PacketSocketAddr> for SocketAddr {
+    fn from(value: PacketSocketAddr) -> Self {
+        SocketAddr::Packet(value)
+    }
+}
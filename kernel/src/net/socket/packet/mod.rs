@@ -0,0 +1,589 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Packet sockets (i.e., `AF_PACKET` sockets).
+//!
+//! Packet sockets send and receive link-layer frames. `SOCK_RAW` sockets see the whole frames,
+//! while `SOCK_DGRAM` sockets see the frames without the link-layer headers.
+//!
+//! Reference: <https://man7.org/linux/man-pages/man7/packet.7.html>
+
+use core::sync::atomic::{AtomicBool, Ordering};
+
+pub use addr::PacketSocketAddr;
+use aster_bigtcp::{
+    errors::packet::{RecvError, SendError},
+    socket::PacketFilter,
+    wire::{EthernetAddress, EthernetProtocol, ETHERNET_HEADER_LEN},
+};
+pub use options::{AddMembership, DropMembership, PacketMreq};
+
+use super::ip::DatagramObserver;
+use crate::{
+    events::IoEvents,
+    match_sock_option_mut, match_sock_option_ref,
+    net::{
+        iface::{self, Iface},
+        socket::{
+            options::{Error as SocketError, SocketOption},
+            private::SocketPrivate,
+            util::{
+                options::{GetSocketLevelOption, SetSocketLevelOption, SocketOptionSet},
+                MessageHeader, SendRecvFlags, SocketAddr,
+            },
+            Socket,
+        },
+        NetNamespace,
+    },
+    prelude::*,
+    process::{
+        credentials::capabilities::CapSet,
+        posix_thread::AsPosixThread,
+        signal::{PollHandle, Pollable, Pollee},
+    },
+    util::{MultiRead, MultiWrite},
+};
+
+mod addr;
+mod options;
+
+/// The protocol number of `ETH_P_ALL`, which matches frames of all protocols.
+const ETH_P_ALL: u16 = 0x0003;
+
+/// The membership type of `PACKET_MR_PROMISC`, which puts the iface into promiscuous mode.
+const PACKET_MR_PROMISC: u16 = 1;
+
+/// The length of Ethernet addresses.
+const ETHER_ADDR_LEN: usize = 6;
+
+/// A packet socket (i.e., `SOCK_RAW` or `SOCK_DGRAM` with `AF_PACKET`).
+pub struct PacketSocket {
+    is_raw: bool,
+    net_ns: Arc<NetNamespace>,
+    inner: RwMutex<Inner>,
+    memberships: Mutex<Vec<Membership>>,
+    options: RwLock<SocketOptionSet>,
+
+    is_nonblocking: AtomicBool,
+    pollee: Pollee,
+}
+
+struct Inner {
+    /// The link-layer protocol in host byte order, or zero if no frames are received.
+    protocol: u16,
+    /// The index of the bound iface, or zero if the socket is bound to all ifaces.
+    ifindex: u32,
+    /// The sockets attached to the ifaces, at most one for each iface.
+    ///
+    /// Sockets attached to the ifaces that the packet socket is not bound to receive no frames.
+    /// They are only used to send frames.
+    sockets: Vec<iface::PacketSocket>,
+}
+
+/// A membership added by `PACKET_ADD_MEMBERSHIP`.
+struct Membership {
+    mreq: PacketMreq,
+    iface: Arc<Iface>,
+    count: usize,
+}
+
+impl PacketSocket {
+    /// Creates a packet socket of the link-layer protocol.
+    ///
+    /// `protocol` is in network byte order. The current thread must have the `CAP_NET_RAW`
+    /// capability.
+    pub fn new(is_raw: bool, protocol: i32, is_nonblocking: bool) -> Result<Arc<Self>> {
+        let credentials = {
+            let current = current_thread!();
+            current.as_posix_thread().unwrap().credentials()
+        };
+        if !credentials.effective_capset().contains(CapSet::NET_RAW) {
+            return_errno_with_message!(
+                Errno::EPERM,
+                "the thread does not have the capability to create packet sockets"
+            );
+        }
+
+        // Like Linux, only the lower 16 bits of the protocol are used.
+        let protocol = u16::from_be(protocol as u16);
+
+        let net_ns = NetNamespace::current();
+        let pollee = Pollee::new();
+        let sockets = attach_sockets(&net_ns, protocol, 0, &pollee);
+
+        Ok(Arc::new(Self {
+            is_raw,
+            net_ns,
+            inner: RwMutex::new(Inner {
+                protocol,
+                ifindex: 0,
+                sockets,
+            }),
+            memberships: Mutex::new(Vec::new()),
+            options: RwLock::new(SocketOptionSet::new_udp()),
+            is_nonblocking: AtomicBool::new(is_nonblocking),
+            pollee,
+        }))
+    }
+
+    fn find_iface(&self, ifindex: u32) -> Option<Arc<Iface>> {
+        self.net_ns
+            .ifaces()
+            .iter()
+            .find(|iface| iface.index() == ifindex)
+            .cloned()
+    }
+
+    fn try_recv(
+        &self,
+        writer: &mut dyn MultiWrite,
+        flags: SendRecvFlags,
+    ) -> Result<(usize, SocketAddr)> {
+        let inner = self.inner.read();
+
+        // FIXME: If the socket is bound to all ifaces, frames from different ifaces are not
+        // received in the order in which they arrive.
+        for socket in inner.sockets.iter() {
+            let result = socket.recv(|frame, meta| {
+                let data = if self.is_raw {
+                    frame
+                } else {
+                    &frame[ETHERNET_HEADER_LEN..]
+                };
+                let copied_res = writer.write(&mut VmReader::from(data));
+                (copied_res, data.len(), *meta)
+            });
+
+            let (copied_res, data_len, meta) = match result {
+                Ok(res) => res,
+                Err(RecvError::Exhausted) => continue,
+            };
+            let copied_len = copied_res?;
+
+            let iface = socket.iface();
+            let addr = PacketSocketAddr::new_ether(
+                u16::from(meta.protocol),
+                iface.index(),
+                iface.type_() as u16,
+                meta.packet_type as u8,
+                meta.src_addr,
+            );
+
+            drop(inner);
+            self.pollee.invalidate();
+
+            // Like Linux, `MSG_TRUNC` makes the call return the real length of the frame.
+            let recv_len = if flags.contains(SendRecvFlags::MSG_TRUNC) {
+                data_len
+            } else {
+                copied_len
+            };
+            return Ok((recv_len, addr.into()));
+        }
+
+        return_errno_with_message!(Errno::EAGAIN, "the receive buffer is empty")
+    }
+
+    fn try_send(
+        &self,
+        reader: &mut dyn MultiRead,
+        remote: Option<&PacketSocketAddr>,
+    ) -> Result<usize> {
+        let mut inner = self.inner.write();
+
+        let ifindex = remote.map_or(inner.ifindex, |addr| addr.ifindex);
+        if ifindex == 0 {
+            return_errno_with_message!(
+                Errno::ENXIO,
+                "the iface to send the frame is not specified"
+            );
+        }
+        let iface = self.find_iface(ifindex).ok_or_else(|| {
+            Error::with_message(Errno::ENXIO, "the iface to send the frame does not exist")
+        })?;
+
+        // For `SOCK_DGRAM` sockets, the link-layer header is built from the destination address.
+        let header = if self.is_raw {
+            None
+        } else {
+            let dst_addr = remote
+                .and_then(PacketSocketAddr::ether_addr)
+                .ok_or_else(|| {
+                    Error::with_message(Errno::EINVAL, "the destination address is not specified")
+                })?;
+            let src_addr = iface
+                .ether_addr()
+                .unwrap_or(EthernetAddress([0; ETHER_ADDR_LEN]));
+            let protocol = remote.map_or(inner.protocol, |addr| addr.protocol);
+            Some((dst_addr, src_addr, protocol))
+        };
+
+        let socket = inner.socket_to_send(&iface, &self.pollee);
+
+        let size = reader.sum_lens();
+        let header_len = if header.is_some() {
+            ETHERNET_HEADER_LEN
+        } else {
+            0
+        };
+
+        let result = socket.send(header_len + size, |buffer| {
+            if let Some((dst_addr, src_addr, protocol)) = header {
+                buffer[..ETHER_ADDR_LEN].copy_from_slice(dst_addr.as_bytes());
+                buffer[ETHER_ADDR_LEN..ETHER_ADDR_LEN * 2].copy_from_slice(src_addr.as_bytes());
+                buffer[ETHER_ADDR_LEN * 2..ETHERNET_HEADER_LEN]
+                    .copy_from_slice(&protocol.to_be_bytes());
+            }
+
+            // FIXME: If copy failed, we should not send any frame.
+            reader
+                .read(&mut VmWriter::from(&mut buffer[header_len..]))
+                .inspect_err(|e| {
+                    warn!("unexpected link-layer frame {e:#?} will be sent");
+                })
+        });
+
+        drop(inner);
+
+        let sent_bytes = match result {
+            Ok(inner) => inner?,
+            Err(SendError::TooShort) => {
+                return_errno_with_message!(Errno::EINVAL, "the frame is too short");
+            }
+            Err(SendError::TooLarge) => {
+                return_errno_with_message!(Errno::EMSGSIZE, "the message is too large");
+            }
+            Err(SendError::BufferFull) => {
+                return_errno_with_message!(Errno::EAGAIN, "the send buffer is full");
+            }
+        };
+
+        self.pollee.invalidate();
+        iface.poll();
+
+        Ok(sent_bytes)
+    }
+
+    fn add_membership(&self, mreq: &PacketMreq) -> Result<()> {
+        let mreq = normalize_mreq(mreq)?;
+        let iface = self
+            .find_iface(mreq.mr_ifindex as u32)
+            .ok_or_else(|| Error::with_message(Errno::ENODEV, "the iface does not exist"))?;
+
+        let mut memberships = self.memberships.lock();
+
+        if let Some(membership) = memberships
+            .iter_mut()
+            .find(|membership| membership.mreq == mreq)
+        {
+            membership.count += 1;
+            return Ok(());
+        }
+
+        // TODO: Support other membership types. Since all multicast frames are received now,
+        // adding multicast memberships has no effect.
+        if mreq.mr_type == PACKET_MR_PROMISC {
+            iface.inc_promiscuity();
+        }
+        memberships.push(Membership {
+            mreq,
+            iface,
+            count: 1,
+        });
+
+        Ok(())
+    }
+
+    fn drop_membership(&self, mreq: &PacketMreq) -> Result<()> {
+        let mreq = normalize_mreq(mreq)?;
+
+        let mut memberships = self.memberships.lock();
+
+        // Like Linux, dropping a membership that does not exist is not an error.
+        let Some(index) = memberships
+            .iter()
+            .position(|membership| membership.mreq == mreq)
+        else {
+            return Ok(());
+        };
+
+        let membership = &mut memberships[index];
+        membership.count -= 1;
+        if membership.count == 0 {
+            let membership = memberships.swap_remove(index);
+            membership.release();
+        }
+
+        Ok(())
+    }
+}
+
+impl Inner {
+    /// Returns the socket attached to the iface, attaching a new one if there is none.
+    fn socket_to_send(&mut self, iface: &Arc<Iface>, pollee: &Pollee) -> &iface::PacketSocket {
+        let index = match self
+            .sockets
+            .iter()
+            .position(|socket| socket.iface().index() == iface.index())
+        {
+            Some(index) => index,
+            None => {
+                self.sockets.push(iface::PacketSocket::new_bind(
+                    iface.clone(),
+                    PacketFilter::None,
+                    DatagramObserver::new(pollee.clone()),
+                ));
+                self.sockets.len() - 1
+            }
+        };
+
+        &self.sockets[index]
+    }
+
+    fn check_io_events(&self) -> IoEvents {
+        let mut events = IoEvents::empty();
+
+        if self.sockets.iter().any(|socket| socket.can_recv()) {
+            events |= IoEvents::IN;
+        }
+
+        if self.sockets.iter().all(|socket| socket.can_send()) {
+            events |= IoEvents::OUT;
+        }
+
+        events
+    }
+}
+
+impl Membership {
+    fn release(self) {
+        if self.mreq.mr_type == PACKET_MR_PROMISC {
+            self.iface.dec_promiscuity();
+        }
+    }
+}
+
+/// Attaches sockets to the ifaces that a packet socket is bound to.
+fn attach_sockets(
+    net_ns: &NetNamespace,
+    protocol: u16,
+    ifindex: u32,
+    pollee: &Pollee,
+) -> Vec<iface::PacketSocket> {
+    // A packet socket with a zero protocol receives no frames.
+    if protocol == 0 {
+        return Vec::new();
+    }
+
+    let filter = if protocol == ETH_P_ALL {
+        PacketFilter::All
+    } else {
+        PacketFilter::Protocol(EthernetProtocol::from(protocol))
+    };
+
+    net_ns
+        .ifaces()
+        .iter()
+        .filter(|iface| ifindex == 0 || iface.index() == ifindex)
+        .map(|iface| {
+            iface::PacketSocket::new_bind(
+                iface.clone(),
+                filter,
+                DatagramObserver::new(pollee.clone()),
+            )
+        })
+        .collect()
+}
+
+/// Validates a membership request and clears the unused bytes of its address.
+fn normalize_mreq(mreq: &PacketMreq) -> Result<PacketMreq> {
+    let addr_len = mreq.mr_alen as usize;
+    if addr_len > ETHER_ADDR_LEN {
+        return_errno_with_message!(Errno::EINVAL, "the address length is invalid");
+    }
+
+    let mut mreq = *mreq;
+    mreq.mr_address[addr_len..].fill(0);
+
+    Ok(mreq)
+}
+
+impl Pollable for PacketSocket {
+    fn poll(&self, mask: IoEvents, poller: Option<&mut PollHandle>) -> IoEvents {
+        self.pollee
+            .poll_with(mask, poller, || self.inner.read().check_io_events())
+    }
+}
+
+impl SocketPrivate for PacketSocket {
+    fn is_nonblocking(&self) -> bool {
+        self.is_nonblocking.load(Ordering::Relaxed)
+    }
+
+    fn set_nonblocking(&self, is_nonblocking: bool) {
+        self.is_nonblocking.store(is_nonblocking, Ordering::Relaxed);
+    }
+}
+
+impl Socket for PacketSocket {
+    fn bind(&self, socket_addr: SocketAddr) -> Result<()> {
+        let addr = PacketSocketAddr::try_from(socket_addr)?;
+
+        if addr.ifindex != 0 && self.find_iface(addr.ifindex).is_none() {
+            return_errno_with_message!(Errno::ENODEV, "the iface does not exist");
+        }
+
+        let mut inner = self.inner.write();
+
+        // Like Linux, a zero protocol in the address keeps the protocol of the socket.
+        let protocol = if addr.protocol != 0 {
+            addr.protocol
+        } else {
+            inner.protocol
+        };
+
+        // Detach the old sockets first, so that there is at most one socket for each iface.
+        inner.sockets.clear();
+        inner.sockets = attach_sockets(&self.net_ns, protocol, addr.ifindex, &self.pollee);
+        inner.protocol = protocol;
+        inner.ifindex = addr.ifindex;
+
+        drop(inner);
+        self.pollee.invalidate();
+
+        Ok(())
+    }
+
+    fn addr(&self) -> Result<SocketAddr> {
+        let inner = self.inner.read();
+
+        let iface = if inner.ifindex != 0 {
+            self.find_iface(inner.ifindex)
+        } else {
+            None
+        };
+
+        let addr = match iface {
+            Some(iface) => PacketSocketAddr::new_ether(
+                inner.protocol,
+                inner.ifindex,
+                iface.type_() as u16,
+                0,
+                iface
+                    .ether_addr()
+                    .unwrap_or(EthernetAddress([0; ETHER_ADDR_LEN])),
+            ),
+            None => PacketSocketAddr {
+                protocol: inner.protocol,
+                ifindex: 0,
+                hatype: 0,
+                packet_type: 0,
+                hw_addr_len: 0,
+                hw_addr: [0; 8],
+            },
+        };
+
+        Ok(addr.into())
+    }
+
+    fn sendmsg(
+        &self,
+        reader: &mut dyn MultiRead,
+        message_header: MessageHeader,
+        flags: SendRecvFlags,
+    ) -> Result<usize> {
+        // TODO: Deal with flags
+        if !flags.is_all_supported() {
+            warn!("unsupported flags: {:?}", flags);
+        }
+
+        let MessageHeader {
+            addr,
+            control_messages,
+        } = message_header;
+
+        let remote = match addr {
+            Some(addr) => Some(PacketSocketAddr::try_from(addr)?),
+            None => None,
+        };
+
+        if !control_messages.is_empty() {
+            // TODO: Support sending control message
+            warn!("sending control message is not supported");
+        }
+
+        // TODO: Block if the send buffer is full
+        self.try_send(reader, remote.as_ref())
+    }
+
+    fn recvmsg(
+        &self,
+        writer: &mut dyn MultiWrite,
+        flags: SendRecvFlags,
+    ) -> Result<(usize, MessageHeader)> {
+        // TODO: Deal with flags
+        if !flags.is_all_supported() {
+            warn!("unsupported flags: {:?}", flags);
+        }
+
+        let (received_bytes, peer_addr) =
+            self.block_on(IoEvents::IN, || self.try_recv(writer, flags))?;
+
+        // TODO: Receive control message
+
+        let message_header = MessageHeader::new(Some(peer_addr), Vec::new());
+
+        Ok((received_bytes, message_header))
+    }
+
+    fn get_option(&self, option: &mut dyn SocketOption) -> Result<()> {
+        match_sock_option_mut!(option, {
+            socket_errors: SocketError => {
+                // TODO: Support socket errors for packet sockets
+                socket_errors.set(None);
+                return Ok(());
+            },
+            _ => ()
+        });
+
+        let inner = self.inner.read();
+        let options = self.options.read();
+
+        options.get_option(option, &*inner)
+    }
+
+    fn set_option(&self, option: &dyn SocketOption) -> Result<()> {
+        match_sock_option_ref!(option, {
+            add_membership: AddMembership => {
+                return self.add_membership(add_membership.get().unwrap());
+            },
+            drop_membership: DropMembership => {
+                return self.drop_membership(drop_membership.get().unwrap());
+            },
+            _ => ()
+        });
+
+        let inner = self.inner.read();
+        let mut options = self.options.write();
+
+        // Packet sockets are not bound to ports, so there is no need to poll the iface.
+        options.set_option(option, &*inner)?;
+
+        Ok(())
+    }
+}
+
+impl Drop for PacketSocket {
+    fn drop(&mut self) {
+        for membership in self.memberships.get_mut().drain(..) {
+            membership.release();
+        }
+    }
+}
+
+impl GetSocketLevelOption for Inner {
+    fn is_listening(&self) -> bool {
+        false
+    }
+}
+
+impl SetSocketLevelOption for Inner {}
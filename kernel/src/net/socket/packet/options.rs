@@ -0,0 +1,24 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use crate::{impl_socket_options, prelude::*};
+
+impl_socket_options!(
+    pub struct AddMembership(PacketMreq);
+    pub struct DropMembership(PacketMreq);
+);
+
+/// A membership request of packet sockets (i.e., `struct packet_mreq`).
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.0.9/source/include/uapi/linux/if_packet.h#L283>
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, PartialEq, Eq)]
+pub struct PacketMreq {
+    /// The interface index.
+    pub mr_ifindex: i32,
+    /// The action (e.g., `PACKET_MR_PROMISC`).
+    pub mr_type: u16,
+    /// The length of the link-layer address.
+    pub mr_alen: u16,
+    /// The link-layer address.
+    pub mr_address: [u8; 8],
+}
@@ -5,7 +5,7 @@ use core::fmt;
 use aster_rights::ReadOp;
 use ostd::task::Task;
 
-use super::{cred::SocketCred, CUserCred, UnixStreamSocket};
+use super::{cred::SocketCred, CUserCred, UnixDatagramSocket, UnixStreamSocket};
 use crate::{
     fs::{
         file_handle::FileLike,
@@ -215,8 +215,10 @@ impl AuxiliaryData {
         let mut cred = None;
 
         for ctrl_msg in ctrl_msgs.into_iter() {
-            let ControlMessage::Unix(unix_ctrl_msg) = ctrl_msg;
             // TODO: What should we do if there are control messages of other protocols?
+            let ControlMessage::Unix(unix_ctrl_msg) = ctrl_msg else {
+                continue;
+            };
 
             match unix_ctrl_msg.0 {
                 Message::Files(FileMessage {
@@ -247,10 +249,10 @@ impl AuxiliaryData {
         // FIXME: Sending UNIX sockets over UNIX sockets can easily lead to circular references and
         // memory leaks. Linux uses a complex garbage collection algorithm to address these issues.
         // See also <https://elixir.bootlin.com/linux/v6.15/source/net/unix/garbage.c#L592>.
-        if files
-            .iter()
-            .any(|file| (&**file as &dyn Any).is::<UnixStreamSocket>())
-        {
+        if files.iter().any(|file| {
+            let file = &**file as &dyn Any;
+            file.is::<UnixStreamSocket>() || file.is::<UnixDatagramSocket>()
+        }) {
             warn!("UNIX sockets in SCM_RIGHTS messages can leak kernel resource");
 
             let credentials = current_thread!().as_posix_thread().unwrap().credentials();
@@ -276,13 +278,28 @@ impl AuxiliaryData {
 
     /// Generates the control messages from the auxiliary data.
     pub(super) fn generate_control(&mut self, is_pass_cred: bool) -> Vec<ControlMessage> {
-        let mut ctrl_msgs = Vec::new();
+        let files = core::mem::take(&mut self.files);
+        self.build_control(files, is_pass_cred)
+    }
+
+    /// Generates the control messages from the auxiliary data without taking the files.
+    ///
+    /// This is used to peek at a message, in which case the files are duplicated.
+    pub(super) fn peek_control(&self, is_pass_cred: bool) -> Vec<ControlMessage> {
+        self.build_control(self.files.clone(), is_pass_cred)
+    }
 
-        let Self { files, cred } = self;
+    fn build_control(
+        &self,
+        files: Vec<Arc<dyn FileLike>>,
+        is_pass_cred: bool,
+    ) -> Vec<ControlMessage> {
+        let mut ctrl_msgs = Vec::new();
 
         if is_pass_cred {
             let unix_ctrl_msg = UnixControlMessage(Message::Cred(CredMessage {
-                cred: cred
+                cred: self
+                    .cred
                     .as_ref()
                     .map(SocketCred::to_real_c_cred)
                     .unwrap_or_else(CUserCred::new_overflow),
@@ -291,9 +308,7 @@ impl AuxiliaryData {
         }
 
         if !files.is_empty() {
-            let unix_ctrl_msg = UnixControlMessage(Message::Files(FileMessage {
-                files: core::mem::take(files),
-            }));
+            let unix_ctrl_msg = UnixControlMessage(Message::Files(FileMessage { files }));
             ctrl_msgs.push(ControlMessage::Unix(unix_ctrl_msg));
         }
 
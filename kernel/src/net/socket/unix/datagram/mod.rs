@@ -0,0 +1,7 @@
+// SPDX-License-Identifier: MPL-2.0
+
+mod queue;
+mod socket;
+
+pub use socket::UnixDatagramSocket;
+pub(in crate::net) use socket::UNIX_DATAGRAM_DEFAULT_BUF_SIZE;
@@ -0,0 +1,287 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use ostd::sync::WaitQueue;
+
+use crate::{
+    events::IoEvents,
+    net::socket::{
+        unix::{addr::UnixSocketAddrKey, ctrl_msg::AuxiliaryData, UnixSocketAddr},
+        util::{
+            options::{GetSocketLevelOption, SetSocketLevelOption},
+            ControlMessage, SendRecvFlags,
+        },
+    },
+    prelude::*,
+    process::signal::Pollee,
+    util::MultiWrite,
+};
+
+/// A message sent to a UNIX datagram socket.
+pub(super) struct Message {
+    payload: Vec<u8>,
+    src_addr: UnixSocketAddr,
+    aux_data: AuxiliaryData,
+}
+
+impl Message {
+    pub(super) fn new(payload: Vec<u8>, src_addr: UnixSocketAddr, aux_data: AuxiliaryData) -> Self {
+        Self {
+            payload,
+            src_addr,
+            aux_data,
+        }
+    }
+}
+
+/// The receive queue of a UNIX datagram socket.
+///
+/// Senders only hold weak references to the queue, so the messages can no longer be sent to the
+/// queue once the socket is closed.
+pub(super) struct MessageQueue {
+    // `None` if the socket is closed.
+    messages: Mutex<Option<VecDeque<Message>>>,
+    pollee: Pollee,
+    // Senders wait here if the queue is full.
+    wait_queue: WaitQueue,
+    // If the socket is connected, only messages from the peer are accepted.
+    peer: SpinLock<Option<Weak<MessageQueue>>>,
+    is_read_shutdown: AtomicBool,
+    is_pass_cred: AtomicBool,
+}
+
+/// The maximum number of pending messages in a receive queue.
+///
+/// Linux considers the queue to be full only if the number of pending messages _exceeds_ this
+/// limit.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.15/source/net/unix/af_unix.c#L248>.
+const MAX_DGRAM_QLEN: usize = 10;
+
+impl MessageQueue {
+    pub(super) fn new(pollee: Pollee) -> Self {
+        Self {
+            messages: Mutex::new(Some(VecDeque::new())),
+            pollee,
+            wait_queue: WaitQueue::new(),
+            peer: SpinLock::new(None),
+            is_read_shutdown: AtomicBool::new(false),
+            is_pass_cred: AtomicBool::new(false),
+        }
+    }
+
+    /// Pushes a message sent by the socket that owns `sender` to the queue.
+    ///
+    /// On failure, the message is returned along with the error so that the caller can retry.
+    pub(super) fn push(
+        &self,
+        message: Message,
+        sender: &Arc<MessageQueue>,
+    ) -> core::result::Result<(), (Error, Message)> {
+        let mut messages = self.messages.lock();
+
+        let Some(messages) = messages.as_mut() else {
+            return Err((
+                Error::with_message(Errno::ECONNREFUSED, "the remote socket is closed"),
+                message,
+            ));
+        };
+
+        if self
+            .peer
+            .lock()
+            .as_ref()
+            .is_some_and(|peer| !core::ptr::eq(peer.as_ptr(), Arc::as_ptr(sender)))
+        {
+            return Err((
+                Error::with_message(
+                    Errno::EPERM,
+                    "the remote socket is connected to another socket",
+                ),
+                message,
+            ));
+        }
+
+        if self.is_read_shutdown.load(Ordering::Relaxed) {
+            return Err((
+                Error::with_message(Errno::EPIPE, "the remote socket is shut down for reading"),
+                message,
+            ));
+        }
+
+        // TODO: Linux also limits the memory consumed by the in-flight messages of the sender.
+        if messages.len() > MAX_DGRAM_QLEN {
+            return Err((
+                Error::with_message(Errno::EAGAIN, "the receive queue is full"),
+                message,
+            ));
+        }
+
+        messages.push_back(message);
+        self.pollee.notify(IoEvents::IN);
+
+        Ok(())
+    }
+
+    /// Receives a message from the queue.
+    ///
+    /// Returns the number of received bytes (or the length of the whole message if
+    /// [`SendRecvFlags::MSG_TRUNC`] is specified), the sender address, and the control messages.
+    pub(super) fn try_recv(
+        &self,
+        writer: &mut dyn MultiWrite,
+        flags: SendRecvFlags,
+    ) -> Result<(usize, UnixSocketAddr, Vec<ControlMessage>)> {
+        let is_pass_cred = self.is_pass_cred.load(Ordering::Relaxed);
+
+        let mut messages = self.messages.lock();
+        let queue = messages.as_mut().unwrap();
+
+        let Some(front) = queue.front() else {
+            if self.is_read_shutdown.load(Ordering::Relaxed) {
+                return Ok((0, UnixSocketAddr::Unnamed, Vec::new()));
+            }
+            return_errno_with_message!(Errno::EAGAIN, "the receive queue is empty");
+        };
+
+        let (copied_res, payload_len, src_addr, ctrl_msgs) =
+            if flags.contains(SendRecvFlags::MSG_PEEK) {
+                // The peeked files are duplicated, so the message is left intact.
+                let copied_res = writer.write(&mut VmReader::from(front.payload.as_slice()));
+                let ctrl_msgs = front.aux_data.peek_control(is_pass_cred);
+                (
+                    copied_res,
+                    front.payload.len(),
+                    front.src_addr.clone(),
+                    ctrl_msgs,
+                )
+            } else {
+                let mut message = queue.pop_front().unwrap();
+                if queue.is_empty() {
+                    self.pollee.invalidate();
+                }
+                drop(messages);
+                self.wait_queue.wake_one();
+
+                // Like Linux, the message is consumed even if the data cannot be copied.
+                let copied_res = writer.write(&mut VmReader::from(message.payload.as_slice()));
+                let ctrl_msgs = message.aux_data.generate_control(is_pass_cred);
+                (
+                    copied_res,
+                    message.payload.len(),
+                    message.src_addr,
+                    ctrl_msgs,
+                )
+            };
+        let copied_len = copied_res?;
+
+        // Like Linux, `MSG_TRUNC` makes the call return the real length of the message.
+        let recv_len = if flags.contains(SendRecvFlags::MSG_TRUNC) {
+            payload_len
+        } else {
+            copied_len
+        };
+
+        Ok((recv_len, src_addr, ctrl_msgs))
+    }
+
+    /// Waits until `cond` returns a result other than [`Errno::EAGAIN`].
+    ///
+    /// The waiters are woken up when a message is removed from the queue, or when the queue can no
+    /// longer accept messages.
+    pub(super) fn pause_until<F>(&self, mut cond: F) -> Result<()>
+    where
+        F: FnMut() -> Result<()>,
+    {
+        self.wait_queue.pause_until(|| match cond() {
+            Err(err) if err.error() == Errno::EAGAIN => None,
+            result => Some(result),
+        })?
+    }
+
+    pub(super) fn peer(&self) -> Option<Weak<MessageQueue>> {
+        self.peer.lock().clone()
+    }
+
+    pub(super) fn set_peer(&self, peer: Weak<MessageQueue>) {
+        *self.peer.lock() = Some(peer);
+    }
+
+    pub(super) fn shutdown(&self) {
+        self.is_read_shutdown.store(true, Ordering::Relaxed);
+
+        self.pollee
+            .notify(IoEvents::IN | IoEvents::RDHUP | IoEvents::HUP);
+        self.wait_queue.wake_all();
+    }
+
+    /// Closes the queue and drops all the pending messages.
+    pub(super) fn close(&self) {
+        let messages = self.messages.lock().take();
+        self.wait_queue.wake_all();
+
+        // The messages may contain files, so they are dropped after the lock is released.
+        drop(messages);
+    }
+
+    pub(super) fn is_read_shutdown(&self) -> bool {
+        self.is_read_shutdown.load(Ordering::Relaxed)
+    }
+
+    pub(super) fn is_pass_cred(&self) -> bool {
+        self.is_pass_cred.load(Ordering::Relaxed)
+    }
+
+    pub(super) fn check_io_events(&self) -> IoEvents {
+        if self
+            .messages
+            .lock()
+            .as_ref()
+            .is_some_and(|messages| !messages.is_empty())
+        {
+            IoEvents::IN
+        } else {
+            IoEvents::empty()
+        }
+    }
+}
+
+impl GetSocketLevelOption for MessageQueue {
+    fn is_listening(&self) -> bool {
+        false
+    }
+}
+
+impl SetSocketLevelOption for MessageQueue {
+    fn set_pass_cred(&self, pass_cred: bool) {
+        self.is_pass_cred.store(pass_cred, Ordering::Relaxed);
+    }
+}
+
+static BOUND_QUEUES: RwLock<BTreeMap<UnixSocketAddrKey, Weak<MessageQueue>>> =
+    RwLock::new(BTreeMap::new());
+
+pub(super) fn register_queue(addr_key: UnixSocketAddrKey, queue: &Arc<MessageQueue>) {
+    BOUND_QUEUES.write().insert(addr_key, Arc::downgrade(queue));
+}
+
+pub(super) fn unregister_queue(addr_key: &UnixSocketAddrKey) {
+    BOUND_QUEUES.write().remove(addr_key);
+}
+
+/// Looks up the receive queue of the datagram socket bound to the address.
+pub(super) fn lookup_queue(addr: &UnixSocketAddr) -> Result<Arc<MessageQueue>> {
+    let addr_key = addr.connect()?;
+
+    BOUND_QUEUES
+        .read()
+        .get(&addr_key)
+        .and_then(Weak::upgrade)
+        .ok_or_else(|| {
+            Error::with_message(
+                Errno::ECONNREFUSED,
+                "no datagram socket is bound to the remote address",
+            )
+        })
+}
@@ -0,0 +1,350 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use core::{
+    ops::Sub,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use aster_rights::ReadDupOp;
+
+use super::queue::{lookup_queue, register_queue, unregister_queue, Message, MessageQueue};
+use crate::{
+    events::IoEvents,
+    match_sock_option_mut,
+    net::socket::{
+        options::{PeerCred, PeerGroups, SocketOption},
+        private::SocketPrivate,
+        unix::{
+            addr::UnixSocketAddrBound, cred::SocketCred, ctrl_msg::AuxiliaryData, CUserCred,
+            UnixSocketAddr,
+        },
+        util::{
+            options::SocketOptionSet, ControlMessage, MessageHeader, SendRecvFlags,
+            SockShutdownCmd, SocketAddr,
+        },
+        Socket,
+    },
+    prelude::*,
+    process::signal::{PollHandle, Pollable, Pollee},
+    util::{MultiRead, MultiWrite},
+};
+
+/// The default size of the send buffer and the receive buffer.
+///
+/// Like Linux, this is the same as `net.core.wmem_default` and `net.core.rmem_default`, which
+/// also limits the size of a single message.
+pub(in crate::net) const UNIX_DATAGRAM_DEFAULT_BUF_SIZE: usize = 212992;
+
+pub struct UnixDatagramSocket {
+    addr: Mutex<Option<UnixSocketAddrBound>>,
+    // The address of the connected peer, which is used for `getpeername`.
+    peer_addr: Mutex<Option<UnixSocketAddr>>,
+    queue: Arc<MessageQueue>,
+    options: RwMutex<SocketOptionSet>,
+
+    // Only socket pairs know the credentials of their peers.
+    peer_cred: Option<SocketCred>,
+
+    pollee: Pollee,
+    is_write_shutdown: AtomicBool,
+    is_nonblocking: AtomicBool,
+}
+
+impl UnixDatagramSocket {
+    fn new_with_peer_cred(is_nonblocking: bool, peer_cred: Option<SocketCred>) -> Arc<Self> {
+        let pollee = Pollee::new();
+
+        Arc::new(Self {
+            addr: Mutex::new(None),
+            peer_addr: Mutex::new(None),
+            queue: Arc::new(MessageQueue::new(pollee.clone())),
+            options: RwMutex::new(SocketOptionSet::new_unix_datagram()),
+            peer_cred,
+            pollee,
+            is_write_shutdown: AtomicBool::new(false),
+            is_nonblocking: AtomicBool::new(is_nonblocking),
+        })
+    }
+
+    pub fn new(is_nonblocking: bool) -> Arc<Self> {
+        Self::new_with_peer_cred(is_nonblocking, None)
+    }
+
+    pub fn new_pair(is_nonblocking: bool) -> (Arc<Self>, Arc<Self>) {
+        let cred = SocketCred::<ReadDupOp>::new_current();
+
+        let socket_a = Self::new_with_peer_cred(is_nonblocking, Some(cred.dup().restrict()));
+        let socket_b = Self::new_with_peer_cred(is_nonblocking, Some(cred.restrict()));
+
+        socket_a.connect_to(&socket_b.queue, UnixSocketAddr::Unnamed);
+        socket_b.connect_to(&socket_a.queue, UnixSocketAddr::Unnamed);
+
+        (socket_a, socket_b)
+    }
+
+    fn connect_to(&self, peer: &Arc<MessageQueue>, peer_addr: UnixSocketAddr) {
+        let mut locked_peer_addr = self.peer_addr.lock();
+
+        self.queue.set_peer(Arc::downgrade(peer));
+        *locked_peer_addr = Some(peer_addr);
+    }
+
+    fn try_send(&self, message: &mut Option<Message>, remote: &Arc<MessageQueue>) -> Result<()> {
+        match remote.push(message.take().unwrap(), &self.queue) {
+            Ok(()) => Ok(()),
+            Err((err, returned_message)) => {
+                *message = Some(returned_message);
+                Err(err)
+            }
+        }
+    }
+
+    fn try_recv(
+        &self,
+        writer: &mut dyn MultiWrite,
+        flags: SendRecvFlags,
+    ) -> Result<(usize, UnixSocketAddr, Vec<ControlMessage>)> {
+        self.queue.try_recv(writer, flags)
+    }
+
+    fn check_io_events(&self) -> IoEvents {
+        // TODO: Like Linux, the socket should not be writable if the receive queue of the
+        // connected peer is full.
+        let mut events = IoEvents::OUT;
+
+        if self.queue.is_read_shutdown() {
+            events |= IoEvents::RDHUP | IoEvents::IN;
+
+            if self.is_write_shutdown.load(Ordering::Relaxed) {
+                events |= IoEvents::HUP;
+            }
+        }
+
+        events | self.queue.check_io_events()
+    }
+}
+
+impl Pollable for UnixDatagramSocket {
+    fn poll(&self, mask: IoEvents, poller: Option<&mut PollHandle>) -> IoEvents {
+        self.pollee
+            .poll_with(mask, poller, || self.check_io_events())
+    }
+}
+
+impl SocketPrivate for UnixDatagramSocket {
+    fn is_nonblocking(&self) -> bool {
+        self.is_nonblocking.load(Ordering::Relaxed)
+    }
+
+    fn set_nonblocking(&self, nonblocking: bool) {
+        self.is_nonblocking.store(nonblocking, Ordering::Relaxed);
+    }
+}
+
+impl Socket for UnixDatagramSocket {
+    fn bind(&self, socket_addr: SocketAddr) -> Result<()> {
+        let addr_to_bind = UnixSocketAddr::try_from(socket_addr)?;
+
+        let mut addr = self.addr.lock();
+
+        if addr.is_some() {
+            return addr_to_bind.bind_unnamed();
+        }
+
+        let bound_addr = addr_to_bind.bind()?;
+        register_queue(bound_addr.to_key(), &self.queue);
+        *addr = Some(bound_addr);
+
+        Ok(())
+    }
+
+    fn connect(&self, socket_addr: SocketAddr) -> Result<()> {
+        let remote_addr = UnixSocketAddr::try_from(socket_addr)?;
+        let remote = lookup_queue(&remote_addr)?;
+
+        // TODO: Like Linux, connecting to `AF_UNSPEC` should dissolve the association.
+        self.connect_to(&remote, remote_addr);
+
+        Ok(())
+    }
+
+    fn shutdown(&self, cmd: SockShutdownCmd) -> Result<()> {
+        // Unlike stream sockets, shutting down a datagram socket does not affect its peer.
+        if cmd.shut_read() {
+            self.queue.shutdown();
+        }
+
+        if cmd.shut_write() {
+            self.is_write_shutdown.store(true, Ordering::Relaxed);
+            self.pollee.notify(IoEvents::OUT | IoEvents::HUP);
+        }
+
+        Ok(())
+    }
+
+    fn addr(&self) -> Result<SocketAddr> {
+        Ok(self.addr.lock().clone().into())
+    }
+
+    fn peer_addr(&self) -> Result<SocketAddr> {
+        let Some(peer_addr) = self.peer_addr.lock().clone() else {
+            return_errno_with_message!(Errno::ENOTCONN, "the socket is not connected");
+        };
+
+        Ok(peer_addr.into())
+    }
+
+    fn sendmsg(
+        &self,
+        reader: &mut dyn MultiRead,
+        message_header: MessageHeader,
+        flags: SendRecvFlags,
+    ) -> Result<usize> {
+        // TODO: Deal with flags
+        if !flags.is_all_supported() {
+            warn!("unsupported flags: {:?}", flags);
+        }
+
+        let MessageHeader {
+            control_messages,
+            addr,
+        } = message_header;
+
+        let mut aux_data = AuxiliaryData::from_control(control_messages)?;
+
+        if self.is_write_shutdown.load(Ordering::Relaxed) {
+            return_errno_with_message!(Errno::EPIPE, "the socket is shut down for writing");
+        }
+
+        let len = reader.sum_lens();
+        if len > self.options.read().send_buf() as usize {
+            return_errno_with_message!(Errno::EMSGSIZE, "the message is too large");
+        }
+
+        let remote = if let Some(addr) = addr {
+            lookup_queue(&UnixSocketAddr::try_from(addr)?)?
+        } else {
+            let Some(peer) = self.queue.peer() else {
+                return_errno_with_message!(Errno::ENOTCONN, "the socket is not connected");
+            };
+            peer.upgrade().ok_or_else(|| {
+                Error::with_message(Errno::ECONNREFUSED, "the connected socket is closed")
+            })?
+        };
+
+        let mut payload = vec![0; len];
+        reader.read(&mut VmWriter::from(payload.as_mut_slice()))?;
+
+        // TODO: Like Linux, the socket should be bound to an abstract name automatically if
+        // `SO_PASSCRED` is enabled.
+        if self.queue.is_pass_cred() || remote.is_pass_cred() {
+            aux_data.fill_cred();
+        }
+
+        let src_addr = self.addr.lock().clone().into();
+        let mut message = Some(Message::new(payload, src_addr, aux_data));
+
+        if self.is_nonblocking() {
+            self.try_send(&mut message, &remote)?;
+        } else {
+            remote.pause_until(|| self.try_send(&mut message, &remote))?;
+        }
+
+        Ok(len)
+    }
+
+    fn recvmsg(
+        &self,
+        writer: &mut dyn MultiWrite,
+        flags: SendRecvFlags,
+    ) -> Result<(usize, MessageHeader)> {
+        // TODO: Deal with other flags. Only MSG_PEEK and MSG_TRUNC are handled here.
+        if !flags
+            .sub(SendRecvFlags::MSG_PEEK | SendRecvFlags::MSG_TRUNC)
+            .is_all_supported()
+        {
+            warn!("unsupported flags: {:?}", flags);
+        }
+
+        let (received_bytes, src_addr, control_messages) =
+            self.block_on(IoEvents::IN, || self.try_recv(writer, flags))?;
+
+        // Like Linux, no address is reported if the sender is unnamed.
+        let src_addr = match src_addr {
+            UnixSocketAddr::Unnamed => None,
+            addr => Some(addr.into()),
+        };
+        let message_header = MessageHeader::new(src_addr, control_messages);
+
+        Ok((received_bytes, message_header))
+    }
+
+    fn get_option(&self, option: &mut dyn SocketOption) -> Result<()> {
+        // Deal with UNIX-socket-specific socket-level options
+        match do_unix_getsockopt(option, self.peer_cred.as_ref()) {
+            Err(err) if err.error() == Errno::ENOPROTOOPT => (),
+            res => return res,
+        }
+
+        // Deal with socket-level options
+        match self.options.read().get_option(option, &*self.queue) {
+            Err(err) if err.error() == Errno::ENOPROTOOPT => (),
+            res => return res,
+        }
+
+        // TODO: Deal with socket options from other levels
+        warn!("only socket-level options are supported");
+
+        return_errno_with_message!(Errno::ENOPROTOOPT, "the socket option to get is unknown")
+    }
+
+    fn set_option(&self, option: &dyn SocketOption) -> Result<()> {
+        let mut options = self.options.write();
+
+        match options.set_option(option, &*self.queue) {
+            Ok(_) => Ok(()),
+            Err(err) if err.error() == Errno::ENOPROTOOPT => {
+                // TODO: Deal with socket options from other levels
+                warn!("only socket-level options are supported");
+                return_errno_with_message!(
+                    Errno::ENOPROTOOPT,
+                    "the socket option to get is unknown"
+                )
+            }
+            Err(e) => Err(e),
+        }
+    }
+}
+
+impl Drop for UnixDatagramSocket {
+    fn drop(&mut self) {
+        if let Some(addr) = self.addr.get_mut().as_ref() {
+            unregister_queue(&addr.to_key());
+        }
+
+        self.queue.close();
+    }
+}
+
+fn do_unix_getsockopt(option: &mut dyn SocketOption, peer_cred: Option<&SocketCred>) -> Result<()> {
+    match_sock_option_mut!(option, {
+        socket_peer_cred: PeerCred => {
+            let peer_cred = peer_cred
+                .map(SocketCred::to_effective_c_cred)
+                .unwrap_or_else(CUserCred::new_invalid);
+            socket_peer_cred.set(peer_cred);
+        },
+        socket_peer_groups: PeerGroups => {
+            let Some(peer_cred) = peer_cred else {
+                return_errno_with_message!(Errno::ENODATA, "the socket does not have peer groups");
+            };
+            socket_peer_groups.set(peer_cred.groups());
+        },
+        _ => return_errno_with_message!(
+            Errno::ENOPROTOOPT,
+            "the socket option to get is not UNIX-socket-specific"
+        )
+    });
+
+    Ok(())
+}
@@ -16,7 +16,7 @@ use crate::{
     fs::file_handle::FileLike,
     net::socket::{
         unix::{
-            addr::{UnixSocketAddrBound, UnixSocketAddrKey},
+            addr::{UnixSocketAddr, UnixSocketAddrBound, UnixSocketAddrKey},
             cred::SocketCred,
             stream::socket::OptionSet,
         },
@@ -24,6 +24,7 @@ use crate::{
     },
     prelude::*,
     process::signal::Pollee,
+    util::net::SockType,
 };
 
 pub(super) struct Listener {
@@ -316,6 +317,23 @@ fn unregister_backlog(addr: &UnixSocketAddrKey) {
     BACKLOG_TABLE.remove_backlog(addr);
 }
 
+/// Returns the addresses and types of all the listening sockets.
+pub fn listening_addrs() -> Vec<(UnixSocketAddr, SockType)> {
+    BACKLOG_TABLE
+        .backlog_sockets
+        .read()
+        .values()
+        .map(|backlog| {
+            let sock_type = if backlog.is_seqpacket {
+                SockType::SOCK_SEQPACKET
+            } else {
+                SockType::SOCK_STREAM
+            };
+            (backlog.addr().clone().into(), sock_type)
+        })
+        .collect()
+}
+
 pub(super) fn get_backlog(server_key: &UnixSocketAddrKey) -> Result<Arc<Backlog>> {
     BACKLOG_TABLE.get_backlog(server_key).ok_or_else(|| {
         Error::with_message(
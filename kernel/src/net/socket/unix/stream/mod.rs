@@ -6,4 +6,5 @@ mod listener;
 mod socket;
 
 pub(in crate::net) use connected::UNIX_STREAM_DEFAULT_BUF_SIZE;
+pub use listener::listening_addrs;
 pub use socket::UnixStreamSocket;
@@ -3,7 +3,11 @@
 use align_ext::AlignExt;
 
 use super::SocketAddr;
-use crate::{net::socket::unix::UnixControlMessage, prelude::*, util::net::CSocketOptionLevel};
+use crate::{
+    net::socket::{ip::IpControlMessage, unix::UnixControlMessage},
+    prelude::*,
+    util::net::CSocketOptionLevel,
+};
 
 /// Message header used for sendmsg/recvmsg.
 #[derive(Debug)]
@@ -36,6 +40,7 @@ impl MessageHeader {
 #[derive(Debug)]
 pub enum ControlMessage {
     Unix(UnixControlMessage),
+    Ip(IpControlMessage),
 }
 
 impl ControlMessage {
@@ -127,6 +132,7 @@ impl ControlMessage {
     fn write_to(&self, writer: &mut VmWriter) -> Result<CControlHeader> {
         match self {
             Self::Unix(msg) => msg.write_to(writer),
+            Self::Ip(msg) => msg.write_to(writer),
         }
     }
 }
@@ -2,19 +2,18 @@
 
 use core::ops::RangeInclusive;
 
-use aster_bigtcp::socket::{
-    NeedIfacePoll, TCP_RECV_BUF_LEN, TCP_SEND_BUF_LEN, UDP_RECV_PAYLOAD_LEN, UDP_SEND_PAYLOAD_LEN,
-};
+use aster_bigtcp::socket::{NeedIfacePoll, UDP_RECV_PAYLOAD_LEN, UDP_SEND_PAYLOAD_LEN};
 
 use super::LingerOption;
 use crate::{
     match_sock_option_mut, match_sock_option_ref,
     net::socket::{
+        ip,
         options::{
-            AcceptConn, KeepAlive, Linger, PassCred, PeerCred, PeerGroups, Priority, RecvBuf,
-            RecvBufForce, ReuseAddr, ReusePort, SendBuf, SendBufForce, SocketOption,
+            AcceptConn, Broadcast, KeepAlive, Linger, PassCred, PeerCred, PeerGroups, Priority,
+            RecvBuf, RecvBufForce, ReuseAddr, ReusePort, SendBuf, SendBufForce, SocketOption,
         },
-        unix::{CUserCred, UNIX_STREAM_DEFAULT_BUF_SIZE},
+        unix::{CUserCred, UNIX_DATAGRAM_DEFAULT_BUF_SIZE, UNIX_STREAM_DEFAULT_BUF_SIZE},
     },
     prelude::*,
     process::{credentials::capabilities::CapSet, posix_thread::AsPosixThread},
@@ -32,6 +31,7 @@ pub struct SocketOptionSet {
     keep_alive: bool,
     pass_cred: bool,
     priority: i32,
+    broadcast: bool,
 }
 
 impl Default for SocketOptionSet {
@@ -45,6 +45,7 @@ impl Default for SocketOptionSet {
             keep_alive: false,
             pass_cred: false,
             priority: 0,
+            broadcast: false,
         }
     }
 }
@@ -53,8 +54,8 @@ impl SocketOptionSet {
     /// Returns the default socket level options for tcp socket.
     pub(in crate::net) fn new_tcp() -> Self {
         Self {
-            send_buf: TCP_SEND_BUF_LEN as u32,
-            recv_buf: TCP_RECV_BUF_LEN as u32,
+            send_buf: ip::tcp_wmem_default(),
+            recv_buf: ip::tcp_rmem_default(),
             ..Default::default()
         }
     }
@@ -77,6 +78,15 @@ impl SocketOptionSet {
         }
     }
 
+    /// Returns the default socket level options for unix datagram socket.
+    pub(in crate::net) fn new_unix_datagram() -> Self {
+        Self {
+            send_buf: UNIX_DATAGRAM_DEFAULT_BUF_SIZE as u32,
+            recv_buf: UNIX_DATAGRAM_DEFAULT_BUF_SIZE as u32,
+            ..Default::default()
+        }
+    }
+
     /// Gets socket-level options.
     ///
     /// Note that the socket error has to be handled separately, because it is automatically
@@ -140,6 +150,12 @@ impl SocketOptionSet {
                 let recv_buf = self.recv_buf();
                 socket_recvbuf_force.set(recv_buf);
             },
+            socket_broadcast: Broadcast => {
+                // This option only affects IP datagram sockets. However, it also works well with
+                // other sockets for setting and getting.
+                let broadcast = self.broadcast();
+                socket_broadcast.set(broadcast);
+            },
             _socket_peer_groups: PeerGroups => {
                 return_errno_with_message!(Errno::ENODATA, "the socket does not have peer groups");
             },
@@ -201,6 +217,12 @@ impl SocketOptionSet {
                 self.set_pass_cred(*pass_cred);
                 socket.set_pass_cred(*pass_cred);
             },
+            socket_broadcast: Broadcast => {
+                // This option only affects IP datagram sockets. However, it also works well with
+                // other sockets for setting and getting.
+                let broadcast = socket_broadcast.get().unwrap();
+                self.set_broadcast(*broadcast);
+            },
             socket_sendbuf_force: SendBufForce => {
                 check_current_privileged()?;
                 let send_buf = socket_sendbuf_force.get().unwrap();
@@ -5,16 +5,17 @@ use core::{num::NonZeroU64, sync::atomic::Ordering};
 use ostd::{cpu::context::UserContext, sync::RwArc, task::Task, user::UserContextApi};
 
 use super::{
+    namespace::{NsProxy, PidNamespace},
     posix_thread::{AsPosixThread, PosixThreadBuilder, ThreadName},
     process_table,
     process_vm::ProcessVm,
+    ptrace::{self, PtraceEvent},
     rlimit::ResourceLimits,
     signal::{constants::SIGCHLD, sig_disposition::SigDispositions, sig_num::SigNum},
     Credentials, Pid, Process,
 };
 use crate::{
     cpu::LinuxAbi,
-    current_userspace,
     fs::{
         file_table::{FdFlags, FileTable},
         thread_info::ThreadFsInfo,
@@ -152,6 +153,20 @@ impl CloneArgs {
     }
 }
 
+impl CloneArgs {
+    /// Returns the ptrace event that reports the creation of the child.
+    fn ptrace_event(&self) -> PtraceEvent {
+        if self.flags.contains(CloneFlags::CLONE_VFORK) {
+            PtraceEvent::Vfork
+        } else if self.flags.contains(CloneFlags::CLONE_THREAD) || self.exit_signal != Some(SIGCHLD)
+        {
+            PtraceEvent::Clone
+        } else {
+            PtraceEvent::Fork
+        }
+    }
+}
+
 impl From<u64> for CloneFlags {
     fn from(flags: u64) -> Self {
         // We use the lower 32 bits
@@ -173,48 +188,94 @@ impl CloneFlags {
             | CloneFlags::CLONE_PARENT_SETTID
             | CloneFlags::CLONE_CHILD_SETTID
             | CloneFlags::CLONE_CHILD_CLEARTID
-            | CloneFlags::CLONE_VFORK;
+            | CloneFlags::CLONE_VFORK
+            | NsProxy::CLONE_NS_FLAGS;
         let unsupported_flags = *self - supported_flags;
         if !unsupported_flags.is_empty() {
             warn!("contains unsupported clone flags: {:?}", unsupported_flags);
         }
         Ok(())
     }
+
+    /// Checks the invalid combinations of the namespace flags.
+    ///
+    /// Reference: <https://elixir.bootlin.com/linux/v6.15/source/kernel/fork.c#L2029>
+    fn check_namespace_flags(&self) -> Result<()> {
+        if self.contains(CloneFlags::CLONE_NEWNS | CloneFlags::CLONE_FS)
+            || self.contains(CloneFlags::CLONE_NEWUSER | CloneFlags::CLONE_FS)
+        {
+            return_errno_with_message!(
+                Errno::EINVAL,
+                "new mount or user namespaces cannot share the filesystem information"
+            );
+        }
+        if self.contains(CloneFlags::CLONE_THREAD)
+            && self.intersects(CloneFlags::CLONE_NEWUSER | CloneFlags::CLONE_NEWPID)
+        {
+            return_errno_with_message!(
+                Errno::EINVAL,
+                "threads cannot be created in new user or PID namespaces"
+            );
+        }
+        Ok(())
+    }
 }
 
 /// Clone a child thread or child process.
 ///
+/// Returns the TID of the child in the PID namespace of the current process.
+///
 /// FIXME: currently, the child process or thread will be scheduled to run at once,
 /// but this may not be the expected behavior.
 pub fn clone_child(
     ctx: &Context,
-    parent_context: &UserContext,
+    parent_context: &mut UserContext,
     clone_args: CloneArgs,
 ) -> Result<Tid> {
     clone_args.flags.check_unsupported_flags()?;
+    clone_args.flags.check_namespace_flags()?;
+    let ptrace_event = clone_args.ptrace_event();
+
     if clone_args.flags.contains(CloneFlags::CLONE_THREAD) {
         let child_task = clone_child_task(ctx, parent_context, clone_args)?;
         let child_thread = child_task.as_thread().unwrap();
-        child_thread.run();
+        let is_traced = ptrace::trace_child(ctx, child_thread, ptrace_event);
 
         let child_tid = child_thread.as_posix_thread().unwrap().tid();
-        Ok(child_tid)
+        // The child is always visible in the PID namespace of the current process.
+        let child_local_tid = ctx.process.pid_ns().local_id(child_tid).unwrap();
+
+        child_thread.run();
+
+        if is_traced {
+            ptrace::stop_on_clone(ctx, parent_context, ptrace_event, child_tid);
+        }
+        Ok(child_local_tid)
     } else {
         let child_process = clone_child_process(ctx, parent_context, clone_args)?;
         if clone_args.flags.contains(CloneFlags::CLONE_VFORK) {
             child_process.status().set_vfork_child(true);
         }
+        let is_traced = ptrace::trace_child(ctx, &child_process.main_thread(), ptrace_event);
+
+        let child_pid = child_process.pid();
+        let child_local_pid = ctx.process.pid_ns().local_id(child_pid).unwrap();
 
         child_process.run();
 
+        if is_traced {
+            ptrace::stop_on_clone(ctx, parent_context, ptrace_event, child_pid);
+        }
+
         if child_process.status().is_vfork_child() {
             let cond = || (!child_process.status().is_vfork_child()).then_some(());
             let current = ctx.process;
             current.children_wait_queue().wait_until(cond);
+
+            ptrace::stop_on_vfork_done(ctx, parent_context, child_pid);
         }
 
-        let child_pid = child_process.pid();
-        Ok(child_pid)
+        Ok(child_local_pid)
     }
 }
 
@@ -257,6 +318,15 @@ fn clone_child_task(
     // Clone fs
     let child_fs = clone_fs(&thread_local.borrow_fs(), clone_flags);
 
+    // Clone namespaces
+    let child_ns_proxy = clone_ns_proxy(ctx, &child_fs, clone_flags)?;
+    if !Arc::ptr_eq(child_ns_proxy.pid_ns_for_children(), process.pid_ns()) {
+        return_errno_with_message!(
+            Errno::EINVAL,
+            "threads cannot be created if the PID namespace for children has been changed"
+        );
+    }
+
     // Clone FPU context
     let child_fpu_context = thread_local.fpu().clone_context();
 
@@ -275,6 +345,7 @@ fn clone_child_task(
     let thread_name = posix_thread.thread_name().lock().as_ref().cloned();
 
     let child_tid = allocate_posix_tid();
+    process.pid_ns().alloc_ids(child_tid)?;
     let child_task = {
         let credentials = {
             let credentials = ctx.posix_thread.credentials();
@@ -287,10 +358,12 @@ fn clone_child_task(
             .sig_mask(sig_mask)
             .file_table(child_file_table)
             .fs(child_fs)
+            .ns_proxy(child_ns_proxy)
             .fpu_context(child_fpu_context);
 
         // Deal with SETTID/CLEARTID flags
-        clone_parent_settid(child_tid, clone_args.parent_tid, clone_flags)?;
+        clone_parent_settid(ctx, child_tid, clone_args.parent_tid, clone_flags)
+            .inspect_err(|_| process.pid_ns().free_ids(child_tid))?;
         thread_builder = clone_child_cleartid(thread_builder, clone_args.child_tid, clone_flags);
         thread_builder = clone_child_settid(thread_builder, clone_args.child_tid, clone_flags);
 
@@ -341,6 +414,10 @@ fn clone_child_process(
     // Clone the filesystem information
     let child_fs = clone_fs(&thread_local.borrow_fs(), clone_flags);
 
+    // Clone the namespaces
+    let child_ns_proxy = clone_ns_proxy(ctx, &child_fs, clone_flags)?;
+    let child_pid_ns = child_ns_proxy.pid_ns_for_children().clone();
+
     // Clone signal dispositions
     let child_sig_dispositions = clone_sighand(process.sig_dispositions(), clone_flags);
 
@@ -360,6 +437,7 @@ fn clone_child_process(
     let child_nice = process.nice().load(Ordering::Relaxed);
 
     let child_tid = allocate_posix_tid();
+    child_pid_ns.alloc_ids(child_tid)?;
 
     let child = {
         let child_elf_path = process.executable_path();
@@ -376,11 +454,13 @@ fn clone_child_process(
                 .sig_mask(child_sig_mask)
                 .file_table(child_file_table)
                 .fs(child_fs)
+                .ns_proxy(child_ns_proxy)
                 .fpu_context(child_fpu_context)
         };
 
         // Deal with SETTID/CLEARTID flags
-        clone_parent_settid(child_tid, clone_args.parent_tid, clone_flags)?;
+        clone_parent_settid(ctx, child_tid, clone_args.parent_tid, clone_flags)
+            .inspect_err(|_| child_pid_ns.free_ids(child_tid))?;
         child_thread_builder =
             clone_child_cleartid(child_thread_builder, clone_args.child_tid, clone_flags);
         child_thread_builder =
@@ -388,6 +468,7 @@ fn clone_child_process(
 
         create_child_process(
             child_tid,
+            child_pid_ns,
             posix_thread.weak_process(),
             &child_elf_path,
             child_process_vm,
@@ -404,6 +485,9 @@ fn clone_child_process(
         child.set_exit_signal(sig);
     };
 
+    // Inherit the parent's OOM score adjustment
+    child.set_oom_score_adj(process.oom_score_adj()).unwrap();
+
     // Sets parent process and group for child process.
     set_parent_and_group(process, &child);
 
@@ -442,6 +526,7 @@ fn clone_child_settid(
 }
 
 fn clone_parent_settid(
+    ctx: &Context,
     child_tid: Tid,
     parent_tidptr: Option<Vaddr>,
     clone_flags: CloneFlags,
@@ -449,7 +534,9 @@ fn clone_parent_settid(
     if let Some(addr) =
         parent_tidptr.filter(|_| clone_flags.contains(CloneFlags::CLONE_PARENT_SETTID))
     {
-        current_userspace!().write_val(addr, &child_tid)?;
+        // The child is always visible in the PID namespace of the parent.
+        let child_tid = ctx.process.pid_ns().local_id(child_tid).unwrap();
+        ctx.user_space().write_val(addr, &child_tid)?;
     }
     Ok(())
 }
@@ -506,6 +593,23 @@ fn clone_fs(parent_fs: &Arc<ThreadFsInfo>, clone_flags: CloneFlags) -> Arc<Threa
     }
 }
 
+fn clone_ns_proxy(
+    ctx: &Context,
+    child_fs: &ThreadFsInfo,
+    clone_flags: CloneFlags,
+) -> Result<Arc<NsProxy>> {
+    let parent_ns_proxy = ctx.thread_local.borrow_ns_proxy().unwrap().clone();
+
+    let ns_flags = clone_flags & NsProxy::CLONE_NS_FLAGS;
+    if ns_flags.is_empty() {
+        return Ok(parent_ns_proxy);
+    }
+
+    // The filesystem information is never shared if a new mount namespace is created.
+    let mut child_fs_resolver = child_fs.resolver().write();
+    parent_ns_proxy.new_with_flags(ns_flags, ctx, &mut child_fs_resolver)
+}
+
 fn clone_files(parent_file_table: &RwArc<FileTable>, clone_flags: CloneFlags) -> RwArc<FileTable> {
     // if CLONE_FILES is set, the child and parent shares the same file table
     // Otherwise, the child will deep copy a new file table.
@@ -571,6 +675,7 @@ fn clone_pidfd(
 #[expect(clippy::too_many_arguments)]
 fn create_child_process(
     pid: Pid,
+    pid_ns: Arc<PidNamespace>,
     parent: Weak<Process>,
     executable_path: &str,
     process_vm: ProcessVm,
@@ -581,6 +686,7 @@ fn create_child_process(
 ) -> Arc<Process> {
     let child_proc = Process::new(
         pid,
+        pid_ns,
         parent,
         executable_path.to_string(),
         process_vm,
@@ -2,8 +2,12 @@
 
 use core::sync::atomic::Ordering;
 
-use super::{process_table, Pid, Process};
-use crate::{events::IoEvents, prelude::*, process::signal::signals::kernel::KernelSignal};
+use super::{process_table, ptrace::exit_tracer, Pid, Process};
+use crate::{
+    events::IoEvents,
+    prelude::*,
+    process::signal::{constants::SIGKILL, signals::kernel::KernelSignal},
+};
 
 /// Exits the current POSIX process.
 ///
@@ -21,6 +25,10 @@ pub(super) fn exit_process(current_process: &Process) {
 
     current_process.pidfile_pollee.notify(IoEvents::IN);
 
+    exit_tracer(current_process);
+
+    kill_pid_ns_processes(current_process);
+
     send_parent_death_signal(current_process);
 
     move_children_to_reaper_process(current_process);
@@ -45,6 +53,28 @@ fn send_parent_death_signal(current_process: &Process) {
     }
 }
 
+/// Kills the other processes in the PID namespace if `current_process` is the init process of a
+/// child PID namespace.
+//
+// FIXME: Linux also waits for the killed processes to exit before the init process becomes a
+// zombie. See `zap_pid_ns_processes` in
+// <https://elixir.bootlin.com/linux/v6.15/source/kernel/pid_namespace.c#L183>.
+fn kill_pid_ns_processes(current_process: &Process) {
+    let pid_ns = current_process.pid_ns();
+    if pid_ns.parent().is_none() || pid_ns.init_pid() != Some(current_process.pid()) {
+        return;
+    }
+
+    for tid in pid_ns.set_dead() {
+        if tid == current_process.pid() {
+            continue;
+        }
+        if let Some(process) = process_table::get_process(tid) {
+            process.enqueue_signal(KernelSignal::new(SIGKILL));
+        }
+    }
+}
+
 /// Finds a reaper process for `current_process`.
 ///
 /// If there is no reaper process for `current_process`, returns `None`.
@@ -52,8 +82,9 @@ fn find_reaper_process(current_process: &Process) -> Option<Arc<Process>> {
     let mut parent = current_process.parent().lock().process();
 
     while let Some(process) = parent.upgrade() {
+        // The init process is not a subreaper. It adopts the orphans in `get_init_process`.
         if is_init_process(&process) {
-            return Some(process);
+            return None;
         }
 
         if !process.has_child_subreaper.load(Ordering::Acquire) {
@@ -82,9 +113,7 @@ fn move_process_children(
     // Take the lock first to avoid the race when the `reaper_process` is exiting concurrently.
     let mut reaper_process_children = reaper_process.children().lock();
 
-    let is_init = is_init_process(reaper_process);
-    let is_zombie = reaper_process.status().is_zombie();
-    if !is_init && is_zombie {
+    if reaper_process.status().is_zombie() {
         return Err(());
     }
 
@@ -98,7 +127,7 @@ fn move_process_children(
 
 /// Moves the children to a reaper process.
 fn move_children_to_reaper_process(current_process: &Process) {
-    if is_init_process(current_process) {
+    if current_process.pid() == INIT_PROCESS_PID {
         return;
     }
 
@@ -108,11 +137,11 @@ fn move_children_to_reaper_process(current_process: &Process) {
         }
     }
 
-    let Some(init_process) = get_init_process() else {
-        return;
-    };
-
-    let _ = move_process_children(current_process, &init_process);
+    while let Some(init_process) = get_init_process(current_process) {
+        if move_process_children(current_process, &init_process).is_ok() {
+            return;
+        }
+    }
 }
 
 /// Sends a child-death signal to the parent.
@@ -129,11 +158,23 @@ fn send_child_death_signal(current_process: &Process) {
 
 const INIT_PROCESS_PID: Pid = 1;
 
-/// Gets the init process
-fn get_init_process() -> Option<Arc<Process>> {
-    process_table::get_process(INIT_PROCESS_PID)
+/// Gets the init process that adopts the orphans of `current_process`.
+///
+/// This is the init process of the PID namespace of `current_process`. If it is exiting, the init
+/// process of the parent namespace is used instead.
+fn get_init_process(current_process: &Process) -> Option<Arc<Process>> {
+    let mut pid_ns = current_process.pid_ns();
+    loop {
+        if let Some(init_process) = pid_ns.init_pid().and_then(process_table::get_process)
+            && !init_process.status().is_zombie()
+        {
+            return Some(init_process);
+        }
+        pid_ns = pid_ns.parent()?;
+    }
 }
 
+/// Returns whether the process is the init process of its PID namespace.
 fn is_init_process(process: &Process) -> bool {
-    process.pid() == INIT_PROCESS_PID
+    process.pid_ns().init_pid() == Some(process.pid())
 }
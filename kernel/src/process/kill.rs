@@ -28,6 +28,7 @@ pub fn kill(pid: Pid, signal: Option<UserSignal>, ctx: &Context) -> Result<()> {
 
         if !ctx.posix_thread.has_signal_blocked(signal.num()) {
             // Killing the current thread does not raise any permission issues.
+            let signal = signal.with_receiver_ns(ctx.process.pid_ns());
             ctx.posix_thread.enqueue_signal(Box::new(signal));
             return Ok(());
         }
@@ -95,6 +96,7 @@ pub fn tgkill(tid: Tid, tgid: Pid, signal: Option<UserSignal>, ctx: &Context) ->
     if let Some(signal) = signal {
         // We've checked the permission issues above.
         // FIXME: We should take some lock while checking the permission to avoid race conditions.
+        let signal = signal.with_receiver_ns(posix_thread.process().pid_ns());
         posix_thread.enqueue_signal(Box::new(signal));
     }
 
@@ -164,6 +166,7 @@ fn kill_process(process: &Process, signal: Option<UserSignal>, ctx: &Context) ->
         return Ok(());
     }
 
+    let signal = signal.with_receiver_ns(process.pid_ns());
     permitted_thread.enqueue_signal_locked(Box::new(signal), sig_dispositions);
 
     Ok(())
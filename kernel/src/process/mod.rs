@@ -4,6 +4,7 @@ mod clone;
 pub mod credentials;
 mod exit;
 mod kill;
+pub mod namespace;
 mod pid_file;
 pub mod posix_thread;
 #[expect(clippy::module_inception)]
@@ -12,6 +13,7 @@ mod process_filter;
 pub mod process_table;
 mod process_vm;
 mod program_loader;
+pub mod ptrace;
 pub mod rlimit;
 pub mod signal;
 mod status;
@@ -26,10 +28,10 @@ pub use kill::{kill, kill_all, kill_group, tgkill};
 pub use pid_file::PidFile;
 pub use process::{
     broadcast_signal_async, enqueue_signal_async, spawn_init_process, ExitCode, JobControl, Pgid,
-    Pid, Process, ProcessGroup, Session, Sid, Terminal,
+    Pid, Process, ProcessGroup, Session, Sid, Terminal, OOM_SCORE_ADJ_MIN,
 };
 pub use process_filter::ProcessFilter;
-pub use process_vm::{renew_vm_and_map, MAX_LEN_STRING_ARG, MAX_NR_STRING_ARGS};
+pub use process_vm::{renew_vm_and_map, ProcessVmarGuard, MAX_LEN_STRING_ARG, MAX_NR_STRING_ARGS};
 pub use program_loader::{check_executable_file, ProgramToLoad};
 pub use rlimit::ResourceType;
 pub use term_status::TermStatus;
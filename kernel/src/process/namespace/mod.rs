@@ -0,0 +1,241 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Linux namespaces.
+//!
+//! A namespace wraps a global system resource in an abstraction that makes it appear to the
+//! processes within the namespace that they have their own isolated instance of the resource.
+//! The namespaces of a thread are recorded in its [`NsProxy`], which is replaced as a whole when
+//! the thread joins or creates namespaces.
+
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use spin::Once;
+
+use crate::{
+    fs::{fs_resolver::FsResolver, path::MountNamespace},
+    ipc::IpcNamespace,
+    net::NetNamespace,
+    prelude::*,
+    process::CloneFlags,
+};
+
+mod pid;
+mod user;
+mod uts;
+
+pub use pid::PidNamespace;
+pub use user::{IdMap, IdMapExtent, UserNamespace};
+pub use uts::{UtsName, UtsNamespace, UTS_FIELD_LEN};
+
+/// The namespaces of a thread.
+///
+/// Unlike Linux, which records the user namespace in the credentials, the user namespace is also
+/// recorded here for simplicity.
+#[derive(Clone)]
+pub struct NsProxy {
+    user_ns: Arc<UserNamespace>,
+    uts_ns: Arc<UtsNamespace>,
+    ipc_ns: Arc<IpcNamespace>,
+    mnt_ns: Arc<MountNamespace>,
+    pid_ns_for_children: Arc<PidNamespace>,
+    net_ns: Arc<NetNamespace>,
+}
+
+static INIT_NS_PROXY: Once<Arc<NsProxy>> = Once::new();
+
+impl NsProxy {
+    /// The `CLONE_NEW*` flags that are supported.
+    pub const CLONE_NS_FLAGS: CloneFlags = CloneFlags::CLONE_NEWNS
+        .union(CloneFlags::CLONE_NEWUTS)
+        .union(CloneFlags::CLONE_NEWIPC)
+        .union(CloneFlags::CLONE_NEWUSER)
+        .union(CloneFlags::CLONE_NEWPID)
+        .union(CloneFlags::CLONE_NEWNET);
+
+    /// Returns the namespaces of the init process.
+    pub fn get_init_singleton() -> &'static Arc<NsProxy> {
+        INIT_NS_PROXY.call_once(|| {
+            Arc::new(Self {
+                user_ns: UserNamespace::get_init_singleton().clone(),
+                uts_ns: UtsNamespace::get_init_singleton().clone(),
+                ipc_ns: IpcNamespace::get_init_singleton().clone(),
+                mnt_ns: MountNamespace::get_init_singleton().clone(),
+                pid_ns_for_children: PidNamespace::get_init_singleton().clone(),
+                net_ns: NetNamespace::get_init_singleton().clone(),
+            })
+        })
+    }
+
+    /// Creates new namespaces according to the `CLONE_NEW*` flags, as is done by `clone` and
+    /// `unshare`.
+    ///
+    /// If a new mount namespace is created, `fs_resolver` is moved to the corresponding location
+    /// in the new mount tree.
+    pub fn new_with_flags(
+        &self,
+        flags: CloneFlags,
+        ctx: &Context,
+        fs_resolver: &mut FsResolver,
+    ) -> Result<Arc<Self>> {
+        let credentials = ctx.posix_thread.credentials();
+
+        let user_ns = if flags.contains(CloneFlags::CLONE_NEWUSER) {
+            self.user_ns.new_child(&credentials)?
+        } else {
+            self.user_ns.clone()
+        };
+        if flags.intersects(Self::CLONE_NS_FLAGS - CloneFlags::CLONE_NEWUSER) {
+            user_ns.check_admin(&credentials, &user_ns)?;
+        }
+
+        let mut ns_proxy = self.clone();
+        if flags.contains(CloneFlags::CLONE_NEWUTS) {
+            ns_proxy.uts_ns = self.uts_ns.new_copy(user_ns.clone());
+        }
+        if flags.contains(CloneFlags::CLONE_NEWIPC) {
+            ns_proxy.ipc_ns = IpcNamespace::new(user_ns.clone());
+        }
+        if flags.contains(CloneFlags::CLONE_NEWNS) {
+            ns_proxy.mnt_ns = self.mnt_ns.new_copy(user_ns.clone(), fs_resolver);
+        }
+        if flags.contains(CloneFlags::CLONE_NEWPID) {
+            if !Arc::ptr_eq(&self.pid_ns_for_children, ctx.process.pid_ns()) {
+                return_errno_with_message!(
+                    Errno::EINVAL,
+                    "the PID namespace for children has already been changed"
+                );
+            }
+            ns_proxy.pid_ns_for_children = self.pid_ns_for_children.new_child(user_ns.clone())?;
+        }
+        if flags.contains(CloneFlags::CLONE_NEWNET) {
+            ns_proxy.net_ns = NetNamespace::new(user_ns.clone());
+        }
+        ns_proxy.user_ns = user_ns;
+
+        Ok(Arc::new(ns_proxy))
+    }
+
+    /// Returns a copy of the namespaces in which `ns` replaces the one of the same type.
+    pub fn with_namespace(&self, ns: Namespace) -> Self {
+        let mut ns_proxy = self.clone();
+        match ns {
+            Namespace::User(user_ns) => ns_proxy.user_ns = user_ns,
+            Namespace::Uts(uts_ns) => ns_proxy.uts_ns = uts_ns,
+            Namespace::Ipc(ipc_ns) => ns_proxy.ipc_ns = ipc_ns,
+            Namespace::Mnt(mnt_ns) => ns_proxy.mnt_ns = mnt_ns,
+            Namespace::Pid(pid_ns) => ns_proxy.pid_ns_for_children = pid_ns,
+            Namespace::Net(net_ns) => ns_proxy.net_ns = net_ns,
+        }
+        ns_proxy
+    }
+
+    /// Installs the namespaces for the current thread.
+    pub fn install(self: Arc<Self>, ctx: &Context) {
+        *ctx.posix_thread.ns_proxy().lock() = Some(self.clone());
+        ctx.thread_local.borrow_ns_proxy_mut().replace(self);
+    }
+
+    /// Returns the user namespace.
+    pub fn user_ns(&self) -> &Arc<UserNamespace> {
+        &self.user_ns
+    }
+
+    /// Returns the UTS namespace.
+    pub fn uts_ns(&self) -> &Arc<UtsNamespace> {
+        &self.uts_ns
+    }
+
+    /// Returns the IPC namespace.
+    pub fn ipc_ns(&self) -> &Arc<IpcNamespace> {
+        &self.ipc_ns
+    }
+
+    /// Returns the mount namespace.
+    pub fn mnt_ns(&self) -> &Arc<MountNamespace> {
+        &self.mnt_ns
+    }
+
+    /// Returns the PID namespace of the children.
+    ///
+    /// This may differ from the PID namespace of the process itself, which never changes.
+    pub fn pid_ns_for_children(&self) -> &Arc<PidNamespace> {
+        &self.pid_ns_for_children
+    }
+
+    /// Returns the network namespace.
+    pub fn net_ns(&self) -> &Arc<NetNamespace> {
+        &self.net_ns
+    }
+}
+
+/// A reference to a namespace of any type.
+#[derive(Clone)]
+pub enum Namespace {
+    User(Arc<UserNamespace>),
+    Uts(Arc<UtsNamespace>),
+    Ipc(Arc<IpcNamespace>),
+    Mnt(Arc<MountNamespace>),
+    Pid(Arc<PidNamespace>),
+    Net(Arc<NetNamespace>),
+}
+
+impl Namespace {
+    /// Returns the `CLONE_NEW*` flag that represents the type of the namespace.
+    pub fn clone_flag(&self) -> CloneFlags {
+        match self {
+            Self::User(_) => CloneFlags::CLONE_NEWUSER,
+            Self::Uts(_) => CloneFlags::CLONE_NEWUTS,
+            Self::Ipc(_) => CloneFlags::CLONE_NEWIPC,
+            Self::Mnt(_) => CloneFlags::CLONE_NEWNS,
+            Self::Pid(_) => CloneFlags::CLONE_NEWPID,
+            Self::Net(_) => CloneFlags::CLONE_NEWNET,
+        }
+    }
+
+    /// Returns the name of the type of the namespace (e.g., `uts`).
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Self::User(_) => "user",
+            Self::Uts(_) => "uts",
+            Self::Ipc(_) => "ipc",
+            Self::Mnt(_) => "mnt",
+            Self::Pid(_) => "pid",
+            Self::Net(_) => "net",
+        }
+    }
+
+    /// Returns the ID of the namespace, which is also the inode number of its `/proc` handle.
+    pub fn id(&self) -> u64 {
+        match self {
+            Self::User(ns) => ns.id(),
+            Self::Uts(ns) => ns.id(),
+            Self::Ipc(ns) => ns.id(),
+            Self::Mnt(ns) => ns.id(),
+            Self::Pid(ns) => ns.id(),
+            Self::Net(ns) => ns.id(),
+        }
+    }
+
+    /// Returns the user namespace that owns the namespace.
+    ///
+    /// The owner of a user namespace is its parent, which does not exist for the initial one.
+    pub fn owner(&self) -> Option<&Arc<UserNamespace>> {
+        match self {
+            Self::User(ns) => ns.parent(),
+            Self::Uts(ns) => Some(ns.owner()),
+            Self::Ipc(ns) => Some(ns.owner()),
+            Self::Mnt(ns) => Some(ns.owner()),
+            Self::Pid(ns) => Some(ns.owner()),
+            Self::Net(ns) => Some(ns.owner()),
+        }
+    }
+}
+
+/// Allocates a unique namespace ID.
+///
+/// Like Linux, the IDs start from `0xF0000000`, which are used as the inode numbers of the
+/// namespace handles in `/proc/[pid]/ns`.
+pub fn alloc_ns_id() -> u64 {
+    static NEXT_NS_ID: AtomicU64 = AtomicU64::new(0xF000_0000);
+    NEXT_NS_ID.fetch_add(1, Ordering::Relaxed)
+}
@@ -0,0 +1,214 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use spin::Once;
+
+use super::{alloc_ns_id, UserNamespace};
+use crate::{
+    prelude::*,
+    process::{posix_thread::PID_MAX, Pid},
+    thread::Tid,
+};
+
+/// The maximum nesting depth of PID namespaces.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.15/source/include/linux/pid_namespace.h#L15>
+const MAX_PID_NS_LEVEL: u32 = 32;
+
+/// The PID of the init process of a PID namespace.
+const INIT_PID: Pid = 1;
+
+/// A PID namespace.
+///
+/// Each thread has a global TID, which is the one used inside the kernel and in the initial
+/// namespace. In other namespaces, the thread also has a local TID in the namespace of its process
+/// and each ancestor namespace. The IDs are translated when they cross the user-kernel boundary.
+pub struct PidNamespace {
+    id: u64,
+    parent: Option<Arc<PidNamespace>>,
+    level: u32,
+    owner: Arc<UserNamespace>,
+    inner: SpinLock<PidNsInner>,
+}
+
+struct PidNsInner {
+    next_id: Tid,
+    /// The global TIDs indexed by the local TIDs.
+    global_ids: BTreeMap<Tid, Tid>,
+    /// The local TIDs indexed by the global TIDs.
+    local_ids: BTreeMap<Tid, Tid>,
+    /// Whether the init process of the namespace has exited.
+    ///
+    /// No more processes can be created in the namespace after the init process exits.
+    is_dead: bool,
+}
+
+static INIT_PID_NS: Once<Arc<PidNamespace>> = Once::new();
+
+impl PidNamespace {
+    /// Returns the initial PID namespace.
+    pub fn get_init_singleton() -> &'static Arc<PidNamespace> {
+        INIT_PID_NS.call_once(|| {
+            Arc::new(Self {
+                id: alloc_ns_id(),
+                parent: None,
+                level: 0,
+                owner: UserNamespace::get_init_singleton().clone(),
+                inner: SpinLock::new(PidNsInner::new()),
+            })
+        })
+    }
+
+    /// Creates a child PID namespace owned by `owner`.
+    pub fn new_child(self: &Arc<Self>, owner: Arc<UserNamespace>) -> Result<Arc<Self>> {
+        if self.level + 1 > MAX_PID_NS_LEVEL {
+            return_errno_with_message!(Errno::ENOSPC, "too many nested PID namespaces");
+        }
+
+        Ok(Arc::new(Self {
+            id: alloc_ns_id(),
+            parent: Some(self.clone()),
+            level: self.level + 1,
+            owner,
+            inner: SpinLock::new(PidNsInner::new()),
+        }))
+    }
+
+    /// Returns the ID of the namespace.
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Returns the user namespace that owns the namespace.
+    pub fn owner(&self) -> &Arc<UserNamespace> {
+        &self.owner
+    }
+
+    /// Returns the parent namespace, if any.
+    pub fn parent(&self) -> Option<&Arc<PidNamespace>> {
+        self.parent.as_ref()
+    }
+
+    /// Returns whether the namespace is `other` or one of its ancestors.
+    pub fn is_same_or_ancestor_of(&self, other: &PidNamespace) -> bool {
+        let mut ns = other;
+        loop {
+            if core::ptr::eq(ns, self) {
+                return true;
+            }
+            match ns.parent.as_ref() {
+                Some(parent) => ns = parent,
+                None => return false,
+            }
+        }
+    }
+
+    /// Allocates the local TIDs in this namespace and its ancestors for a new thread.
+    ///
+    /// The allocated IDs must be released by [`Self::free_ids`] after the thread is reaped.
+    pub fn alloc_ids(&self, global_tid: Tid) -> Result<()> {
+        let mut ns = self;
+        while let Some(parent) = ns.parent.as_ref() {
+            if let Err(err) = ns.inner.lock().alloc(global_tid) {
+                self.free_ids(global_tid);
+                return Err(err);
+            }
+            ns = parent;
+        }
+
+        Ok(())
+    }
+
+    /// Releases the local TIDs allocated by [`Self::alloc_ids`].
+    pub fn free_ids(&self, global_tid: Tid) {
+        let mut ns = self;
+        while let Some(parent) = ns.parent.as_ref() {
+            let mut inner = ns.inner.lock();
+            if let Some(local_tid) = inner.local_ids.remove(&global_tid) {
+                inner.global_ids.remove(&local_tid);
+            }
+            ns = parent;
+        }
+    }
+
+    /// Translates a global TID to the TID in this namespace.
+    ///
+    /// Returns `None` if the thread is not visible in this namespace.
+    pub fn local_id(&self, global_tid: Tid) -> Option<Tid> {
+        if self.parent.is_none() {
+            return Some(global_tid);
+        }
+        self.inner.lock().local_ids.get(&global_tid).copied()
+    }
+
+    /// Translates a TID in this namespace to the global TID.
+    ///
+    /// Returns `None` if no thread has the TID in this namespace.
+    pub fn global_id(&self, local_tid: Tid) -> Option<Tid> {
+        if self.parent.is_none() {
+            return Some(local_tid);
+        }
+        self.inner.lock().global_ids.get(&local_tid).copied()
+    }
+
+    /// Returns the global PID of the init process of this namespace, if it exists.
+    pub fn init_pid(&self) -> Option<Pid> {
+        self.global_id(INIT_PID)
+    }
+
+    /// Marks the namespace as dead because its init process is exiting.
+    ///
+    /// Returns the global TIDs of all the threads in the namespace, which should be killed.
+    pub(in crate::process) fn set_dead(&self) -> Vec<Tid> {
+        let mut inner = self.inner.lock();
+        inner.is_dead = true;
+        inner.local_ids.keys().copied().collect()
+    }
+}
+
+impl PidNsInner {
+    const fn new() -> Self {
+        Self {
+            next_id: INIT_PID,
+            global_ids: BTreeMap::new(),
+            local_ids: BTreeMap::new(),
+            is_dead: false,
+        }
+    }
+
+    fn alloc(&mut self, global_tid: Tid) -> Result<()> {
+        if self.is_dead {
+            return_errno_with_message!(Errno::ENOMEM, "the init process of the namespace exited");
+        }
+
+        // Find an unused ID after the last allocated one, wrapping back to the first non-init ID.
+        let mut local_tid = self.next_id;
+        while self.global_ids.contains_key(&local_tid) {
+            local_tid = if local_tid + 1 >= PID_MAX {
+                INIT_PID + 1
+            } else {
+                local_tid + 1
+            };
+            if local_tid == self.next_id {
+                return_errno_with_message!(Errno::EAGAIN, "no PID is available");
+            }
+        }
+        self.next_id = if local_tid + 1 >= PID_MAX {
+            INIT_PID + 1
+        } else {
+            local_tid + 1
+        };
+
+        self.global_ids.insert(local_tid, global_tid);
+        self.local_ids.insert(global_tid, local_tid);
+        Ok(())
+    }
+}
+
+impl Debug for PidNamespace {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("PidNamespace")
+            .field("id", &self.id)
+            .field("level", &self.level)
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,355 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use aster_rights::ReadOp;
+use spin::Once;
+
+use super::alloc_ns_id;
+use crate::{
+    prelude::*,
+    process::{credentials::capabilities::CapSet, Credentials, Gid, Uid},
+};
+
+/// The maximum nesting depth of user namespaces.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.15/source/kernel/user_namespace.c#L88>
+const MAX_USER_NS_LEVEL: u32 = 32;
+
+/// The maximum number of extents in a UID or GID map.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.15/source/include/linux/user_namespace.h#L15>
+const MAX_ID_MAP_EXTENTS: usize = 340;
+
+/// A user namespace.
+///
+/// A user namespace maps the UIDs and GIDs inside the namespace to the ones in its parent
+/// namespace. Only the maps are per-namespace. The credentials of threads always hold the UIDs
+/// and GIDs in the initial user namespace, which are translated when reported to user space.
+pub struct UserNamespace {
+    id: u64,
+    parent: Option<Arc<UserNamespace>>,
+    level: u32,
+    /// The effective UID of the creator (in the initial namespace).
+    owner: Uid,
+    uid_map: Once<IdMap>,
+    gid_map: Once<IdMap>,
+}
+
+static INIT_USER_NS: Once<Arc<UserNamespace>> = Once::new();
+
+impl UserNamespace {
+    /// Returns the initial user namespace.
+    pub fn get_init_singleton() -> &'static Arc<UserNamespace> {
+        INIT_USER_NS.call_once(|| {
+            let identity = IdMap(vec![IdMapExtent {
+                first: 0,
+                lower_first: 0,
+                count: u32::MAX,
+            }]);
+
+            let uid_map = Once::new();
+            uid_map.call_once(|| identity.clone());
+            let gid_map = Once::new();
+            gid_map.call_once(|| identity);
+
+            Arc::new(Self {
+                id: alloc_ns_id(),
+                parent: None,
+                level: 0,
+                owner: Uid::new_root(),
+                uid_map,
+                gid_map,
+            })
+        })
+    }
+
+    /// Creates a child user namespace owned by the given credentials.
+    pub fn new_child(self: &Arc<Self>, credentials: &Credentials<ReadOp>) -> Result<Arc<Self>> {
+        if self.level + 1 > MAX_USER_NS_LEVEL {
+            return_errno_with_message!(Errno::EUSERS, "too many nested user namespaces");
+        }
+
+        Ok(Arc::new(Self {
+            id: alloc_ns_id(),
+            parent: Some(self.clone()),
+            level: self.level + 1,
+            owner: credentials.euid(),
+            uid_map: Once::new(),
+            gid_map: Once::new(),
+        }))
+    }
+
+    /// Returns the ID of the namespace.
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Returns the parent namespace, if any.
+    pub fn parent(&self) -> Option<&Arc<UserNamespace>> {
+        self.parent.as_ref()
+    }
+
+    /// Returns whether the namespace is `other` or one of its ancestors.
+    pub fn is_same_or_ancestor_of(&self, other: &UserNamespace) -> bool {
+        let mut ns = other;
+        loop {
+            if core::ptr::eq(ns, self) {
+                return true;
+            }
+            match ns.parent.as_ref() {
+                Some(parent) => ns = parent,
+                None => return false,
+            }
+        }
+    }
+
+    /// Checks whether the credentials have administrative privileges over the namespace.
+    ///
+    /// Since capabilities are not per-namespace, this is an approximation of Linux's
+    /// `ns_capable(ns, CAP_SYS_ADMIN)`. The credentials have the privileges if they have
+    /// `CAP_SYS_ADMIN`, or if the namespace (or one of its ancestors) is owned by their effective
+    /// UID and the thread lives in that owner namespace or above.
+    pub fn check_admin(
+        &self,
+        credentials: &Credentials<ReadOp>,
+        current_ns: &UserNamespace,
+    ) -> Result<()> {
+        if credentials.effective_capset().contains(CapSet::SYS_ADMIN) {
+            return Ok(());
+        }
+
+        let mut ns = self;
+        while let Some(parent) = ns.parent.as_ref() {
+            if ns.owner == credentials.euid() && current_ns.is_same_or_ancestor_of(ns) {
+                return Ok(());
+            }
+            ns = parent;
+        }
+
+        return_errno_with_message!(
+            Errno::EPERM,
+            "the namespace is not administered by the user"
+        );
+    }
+
+    /// Maps a UID in the initial namespace to the one in this namespace.
+    ///
+    /// Returns [`Uid::OVERFLOW`] if the UID is not mapped.
+    pub fn map_uid_to_ns(&self, uid: Uid) -> Uid {
+        self.map_to_ns(u32::from(uid), |ns| &ns.uid_map)
+            .map_or(Uid::OVERFLOW, Uid::new)
+    }
+
+    /// Maps a GID in the initial namespace to the one in this namespace.
+    ///
+    /// Returns [`Gid::OVERFLOW`] if the GID is not mapped.
+    pub fn map_gid_to_ns(&self, gid: Gid) -> Gid {
+        self.map_to_ns(u32::from(gid), |ns| &ns.gid_map)
+            .map_or(Gid::OVERFLOW, Gid::new)
+    }
+
+    /// Maps a UID in this namespace to the one in the initial namespace.
+    pub fn map_uid_from_ns(&self, uid: Uid) -> Option<Uid> {
+        self.map_from_ns(u32::from(uid), |ns| &ns.uid_map)
+            .map(Uid::new)
+    }
+
+    /// Maps a GID in this namespace to the one in the initial namespace.
+    pub fn map_gid_from_ns(&self, gid: Gid) -> Option<Gid> {
+        self.map_from_ns(u32::from(gid), |ns| &ns.gid_map)
+            .map(Gid::new)
+    }
+
+    fn map_to_ns(&self, id: u32, map_of: fn(&UserNamespace) -> &Once<IdMap>) -> Option<u32> {
+        let mut chain = Vec::with_capacity(self.level as usize);
+        let mut ns = self;
+        while let Some(parent) = ns.parent.as_ref() {
+            chain.push(ns);
+            ns = parent;
+        }
+
+        chain
+            .iter()
+            .rev()
+            .try_fold(id, |id, ns| map_of(ns).get()?.map_up(id))
+    }
+
+    fn map_from_ns(&self, id: u32, map_of: fn(&UserNamespace) -> &Once<IdMap>) -> Option<u32> {
+        let mut id = id;
+        let mut ns = self;
+        while let Some(parent) = ns.parent.as_ref() {
+            id = map_of(ns).get()?.map_down(id)?;
+            ns = parent;
+        }
+        Some(id)
+    }
+
+    /// Returns the UID map, which is empty if the map has not been written.
+    pub fn uid_map(&self) -> Option<&IdMap> {
+        self.uid_map.get()
+    }
+
+    /// Returns the GID map, which is empty if the map has not been written.
+    pub fn gid_map(&self) -> Option<&IdMap> {
+        self.gid_map.get()
+    }
+
+    /// Sets the UID map of the namespace, as is done by writing to `/proc/[pid]/uid_map`.
+    pub fn set_uid_map(
+        &self,
+        map: IdMap,
+        credentials: &Credentials<ReadOp>,
+        current_ns: &UserNamespace,
+    ) -> Result<()> {
+        self.check_set_map(&map, credentials, current_ns, u32::from(credentials.euid()))?;
+        self.set_map(&self.uid_map, map)
+    }
+
+    /// Sets the GID map of the namespace, as is done by writing to `/proc/[pid]/gid_map`.
+    pub fn set_gid_map(
+        &self,
+        map: IdMap,
+        credentials: &Credentials<ReadOp>,
+        current_ns: &UserNamespace,
+    ) -> Result<()> {
+        self.check_set_map(&map, credentials, current_ns, u32::from(credentials.egid()))?;
+        self.set_map(&self.gid_map, map)
+    }
+
+    /// Checks whether the credentials can write the map.
+    ///
+    /// Like Linux, a user that has administrative privileges over the parent namespace can write
+    /// arbitrary maps, while the owner of the namespace can only map its own ID.
+    ///
+    /// Reference: <https://elixir.bootlin.com/linux/v6.15/source/kernel/user_namespace.c#L1175>
+    fn check_set_map(
+        &self,
+        map: &IdMap,
+        credentials: &Credentials<ReadOp>,
+        current_ns: &UserNamespace,
+        own_id: u32,
+    ) -> Result<()> {
+        let Some(parent) = self.parent.as_ref() else {
+            return_errno_with_message!(Errno::EPERM, "the initial maps cannot be changed");
+        };
+
+        if parent.check_admin(credentials, current_ns).is_ok() {
+            return Ok(());
+        }
+
+        if let [extent] = map.0.as_slice()
+            && extent.count == 1
+            && extent.lower_first == own_id
+            && self.owner == credentials.euid()
+        {
+            return Ok(());
+        }
+
+        return_errno_with_message!(Errno::EPERM, "the map cannot be written by the user");
+    }
+
+    fn set_map(&self, slot: &Once<IdMap>, map: IdMap) -> Result<()> {
+        let mut map = Some(map);
+        slot.call_once(|| map.take().unwrap());
+        if map.is_some() {
+            return_errno_with_message!(Errno::EPERM, "the map has already been written");
+        }
+        Ok(())
+    }
+}
+
+impl Debug for UserNamespace {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("UserNamespace")
+            .field("id", &self.id)
+            .field("level", &self.level)
+            .field("owner", &self.owner)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A UID or GID map of a user namespace.
+#[derive(Debug, Clone)]
+pub struct IdMap(Vec<IdMapExtent>);
+
+/// A range of IDs that are mapped to a range of IDs in the parent namespace.
+#[derive(Debug, Clone, Copy)]
+pub struct IdMapExtent {
+    /// The first ID in the namespace.
+    pub first: u32,
+    /// The first ID in the parent namespace.
+    pub lower_first: u32,
+    /// The number of IDs.
+    pub count: u32,
+}
+
+impl IdMap {
+    /// Parses a map in the format of `/proc/[pid]/uid_map`.
+    ///
+    /// Each line consists of three numbers, which are the first ID in the namespace, the first ID
+    /// in the parent namespace, and the number of IDs.
+    pub fn parse(content: &str) -> Result<Self> {
+        let mut extents: Vec<IdMapExtent> = Vec::new();
+
+        for line in content.lines().filter(|line| !line.trim().is_empty()) {
+            let mut numbers = line.split_whitespace().map(|number| number.parse::<u32>());
+            let (Some(Ok(first)), Some(Ok(lower_first)), Some(Ok(count)), None) = (
+                numbers.next(),
+                numbers.next(),
+                numbers.next(),
+                numbers.next(),
+            ) else {
+                return_errno_with_message!(Errno::EINVAL, "the map line is malformed");
+            };
+
+            if count == 0
+                || first.checked_add(count - 1).is_none()
+                || lower_first.checked_add(count - 1).is_none()
+            {
+                return_errno_with_message!(Errno::EINVAL, "the map range is invalid");
+            }
+
+            let extent = IdMapExtent {
+                first,
+                lower_first,
+                count,
+            };
+            let overlaps =
+                |a: (u32, u32), b: (u32, u32)| a.0 <= b.0 + b.1 - 1 && b.0 <= a.0 + a.1 - 1;
+            if extents.iter().any(|other| {
+                overlaps((first, count), (other.first, other.count))
+                    || overlaps((lower_first, count), (other.lower_first, other.count))
+            }) {
+                return_errno_with_message!(Errno::EINVAL, "the map ranges overlap");
+            }
+
+            extents.push(extent);
+        }
+
+        if extents.is_empty() || extents.len() > MAX_ID_MAP_EXTENTS {
+            return_errno_with_message!(Errno::EINVAL, "the number of map lines is invalid");
+        }
+
+        Ok(Self(extents))
+    }
+
+    /// Returns the extents of the map.
+    pub fn extents(&self) -> &[IdMapExtent] {
+        &self.0
+    }
+
+    /// Maps an ID in the parent namespace to the one in the namespace.
+    fn map_up(&self, id: u32) -> Option<u32> {
+        self.0.iter().find_map(|extent| {
+            let offset = id.checked_sub(extent.lower_first)?;
+            (offset < extent.count).then(|| extent.first + offset)
+        })
+    }
+
+    /// Maps an ID in the namespace to the one in the parent namespace.
+    fn map_down(&self, id: u32) -> Option<u32> {
+        self.0.iter().find_map(|extent| {
+            let offset = id.checked_sub(extent.first)?;
+            (offset < extent.count).then(|| extent.lower_first + offset)
+        })
+    }
+}
@@ -0,0 +1,119 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use spin::Once;
+
+use super::{alloc_ns_id, UserNamespace};
+use crate::prelude::*;
+
+/// The length of each field in [`UtsName`], including the terminating null byte.
+pub const UTS_FIELD_LEN: usize = 65;
+
+/// The `struct new_utsname` returned by `uname`.
+#[derive(Debug, Clone, Copy, Pod)]
+#[repr(C)]
+pub struct UtsName {
+    pub sysname: [u8; UTS_FIELD_LEN],
+    pub nodename: [u8; UTS_FIELD_LEN],
+    pub release: [u8; UTS_FIELD_LEN],
+    pub version: [u8; UTS_FIELD_LEN],
+    pub machine: [u8; UTS_FIELD_LEN],
+    pub domainname: [u8; UTS_FIELD_LEN],
+}
+
+impl UtsName {
+    const fn new() -> Self {
+        UtsName {
+            sysname: [0; UTS_FIELD_LEN],
+            nodename: [0; UTS_FIELD_LEN],
+            release: [0; UTS_FIELD_LEN],
+            version: [0; UTS_FIELD_LEN],
+            machine: [0; UTS_FIELD_LEN],
+            domainname: [0; UTS_FIELD_LEN],
+        }
+    }
+}
+
+/// A UTS namespace, which isolates the host name and the NIS domain name.
+pub struct UtsNamespace {
+    id: u64,
+    uts_name: RwLock<UtsName>,
+    owner: Arc<UserNamespace>,
+}
+
+static INIT_UTS_NS: Once<Arc<UtsNamespace>> = Once::new();
+
+impl UtsNamespace {
+    /// Returns the initial UTS namespace.
+    pub fn get_init_singleton() -> &'static Arc<UtsNamespace> {
+        INIT_UTS_NS.call_once(|| {
+            // We don't use the real name and version of our os here. Instead, we pick up fake
+            // values witch is the same as the ones of linux. The values are used to fool glibc
+            // since glibc will check the version and os name.
+            let mut uts_name = UtsName::new();
+            copy_field(b"Linux", &mut uts_name.sysname);
+            copy_field(b"WHITLEY", &mut uts_name.nodename);
+            copy_field(b"5.13.0", &mut uts_name.release);
+            copy_field(b"5.13.0", &mut uts_name.version);
+            copy_field(b"x86_64", &mut uts_name.machine);
+            copy_field(b"", &mut uts_name.domainname);
+
+            Arc::new(Self {
+                id: alloc_ns_id(),
+                uts_name: RwLock::new(uts_name),
+                owner: UserNamespace::get_init_singleton().clone(),
+            })
+        })
+    }
+
+    /// Creates a copy of the namespace, which will be owned by `owner`.
+    pub fn new_copy(&self, owner: Arc<UserNamespace>) -> Arc<Self> {
+        Arc::new(Self {
+            id: alloc_ns_id(),
+            uts_name: RwLock::new(*self.uts_name.read()),
+            owner,
+        })
+    }
+
+    /// Returns the ID of the namespace.
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Returns the user namespace that owns the namespace.
+    pub fn owner(&self) -> &Arc<UserNamespace> {
+        &self.owner
+    }
+
+    /// Returns the UTS name.
+    pub fn uts_name(&self) -> UtsName {
+        *self.uts_name.read()
+    }
+
+    /// Sets the host name.
+    pub fn set_hostname(&self, hostname: &[u8]) -> Result<()> {
+        let mut uts_name = self.uts_name.write();
+        set_field(hostname, &mut uts_name.nodename)
+    }
+
+    /// Sets the NIS domain name.
+    pub fn set_domainname(&self, domainname: &[u8]) -> Result<()> {
+        let mut uts_name = self.uts_name.write();
+        set_field(domainname, &mut uts_name.domainname)
+    }
+}
+
+fn copy_field(src: &[u8], dst: &mut [u8; UTS_FIELD_LEN]) {
+    let len = src.len().min(dst.len());
+    dst[..len].copy_from_slice(&src[..len]);
+}
+
+fn set_field(src: &[u8], dst: &mut [u8; UTS_FIELD_LEN]) -> Result<()> {
+    // The last byte is reserved for the terminating null byte.
+    if src.len() >= UTS_FIELD_LEN {
+        return_errno_with_message!(Errno::EINVAL, "the name is too long");
+    }
+
+    dst.fill(0);
+    dst[..src.len()].copy_from_slice(src);
+    Ok(())
+}
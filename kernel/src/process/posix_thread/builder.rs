@@ -16,7 +16,9 @@ use crate::{
     fs::{file_table::FileTable, thread_info::ThreadFsInfo},
     prelude::*,
     process::{
+        namespace::NsProxy,
         posix_thread::name::ThreadName,
+        ptrace::PtraceState,
         signal::{sig_mask::AtomicSigMask, sig_queues::SigQueues},
         Credentials, Process,
     },
@@ -39,6 +41,7 @@ pub struct PosixThreadBuilder {
     clear_child_tid: Vaddr,
     file_table: Option<RwArc<FileTable>>,
     fs: Option<Arc<ThreadFsInfo>>,
+    ns_proxy: Option<Arc<NsProxy>>,
     sig_mask: AtomicSigMask,
     sig_queues: SigQueues,
     sched_policy: SchedPolicy,
@@ -57,6 +60,7 @@ impl PosixThreadBuilder {
             clear_child_tid: 0,
             file_table: None,
             fs: None,
+            ns_proxy: None,
             sig_mask: AtomicSigMask::new_empty(),
             sig_queues: SigQueues::new(),
             sched_policy: SchedPolicy::Fair(Nice::default()),
@@ -94,6 +98,11 @@ impl PosixThreadBuilder {
         self
     }
 
+    pub fn ns_proxy(mut self, ns_proxy: Arc<NsProxy>) -> Self {
+        self.ns_proxy = Some(ns_proxy);
+        self
+    }
+
     pub fn sig_mask(mut self, sig_mask: AtomicSigMask) -> Self {
         self.sig_mask = sig_mask;
         self
@@ -115,6 +124,7 @@ impl PosixThreadBuilder {
             clear_child_tid,
             file_table,
             fs,
+            ns_proxy,
             sig_mask,
             sig_queues,
             sched_policy,
@@ -125,6 +135,8 @@ impl PosixThreadBuilder {
 
         let fs = fs.unwrap_or_else(|| Arc::new(ThreadFsInfo::default()));
 
+        let ns_proxy = ns_proxy.unwrap_or_else(|| NsProxy::get_init_singleton().clone());
+
         let root_vmar = process
             .upgrade()
             .unwrap()
@@ -145,6 +157,8 @@ impl PosixThreadBuilder {
                     name: Mutex::new(thread_name),
                     credentials,
                     file_table: Mutex::new(Some(file_table.clone_ro())),
+                    fs: Mutex::new(Arc::downgrade(&fs)),
+                    ns_proxy: Mutex::new(Some(ns_proxy.clone())),
                     sig_mask,
                     sig_queues,
                     signalled_waker: SpinLock::new(None),
@@ -152,6 +166,7 @@ impl PosixThreadBuilder {
                     virtual_timer_manager,
                     prof_timer_manager,
                     io_priority: AtomicU32::new(0),
+                    ptrace_state: PtraceState::new(),
                 }
             };
 
@@ -169,6 +184,7 @@ impl PosixThreadBuilder {
                 root_vmar,
                 file_table,
                 fs,
+                ns_proxy,
                 fpu_context,
             );
 
@@ -8,9 +8,11 @@ use super::{
 };
 use crate::{
     current_userspace,
+    fs::notify,
     prelude::*,
     process::{
         exit::exit_process,
+        ptrace::exit_tracee,
         signal::{constants::SIGKILL, signals::kernel::KernelSignal},
         task_set::TaskSet,
         TermStatus,
@@ -69,6 +71,9 @@ fn exit_internal(term_status: TermStatus, is_exiting_group: bool) {
         tasks.remove_exited(&current_task)
     };
 
+    // The exiting thread is no longer traced.
+    exit_tracee(posix_thread, posix_process.status().exit_code());
+
     wake_clear_ctid(thread_local);
 
     wake_robust_list(thread_local, posix_thread.tid());
@@ -77,14 +82,18 @@ fn exit_internal(term_status: TermStatus, is_exiting_group: bool) {
     // process is reaped by its parent.
     if posix_thread.tid() != posix_process.pid() {
         thread_table::remove_thread(posix_thread.tid());
+        posix_process.pid_ns().free_ids(posix_thread.tid());
     }
 
     // Drop fields in `PosixThread`.
     *posix_thread.file_table().lock() = None;
+    *posix_thread.ns_proxy().lock() = None;
 
     // Drop fields in `ThreadLocal`.
     *thread_local.root_vmar().borrow_mut() = None;
     thread_local.borrow_file_table_mut().remove();
+    thread_local.borrow_ns_proxy_mut().remove();
+    notify::flush_close_events();
 
     if is_last_thread {
         exit_process(&posix_process);
@@ -7,6 +7,8 @@ use ostd::sync::{RoArc, Waker};
 
 use super::{
     kill::SignalSenderIds,
+    namespace::NsProxy,
+    ptrace::PtraceState,
     signal::{
         sig_disposition::SigDispositions,
         sig_mask::{AtomicSigMask, SigMask, SigSet},
@@ -19,7 +21,7 @@ use super::{
 };
 use crate::{
     events::Observer,
-    fs::file_table::FileTable,
+    fs::{file_table::FileTable, thread_info::ThreadFsInfo},
     prelude::*,
     process::signal::constants::SIGCONT,
     thread::{Thread, Tid},
@@ -40,7 +42,7 @@ pub use exit::{do_exit, do_exit_group};
 pub use name::{ThreadName, MAX_THREAD_NAME_LEN};
 pub use posix_thread_ext::AsPosixThread;
 pub use robust_list::RobustListHead;
-pub use thread_local::{AsThreadLocal, FileTableRefMut, ThreadLocal};
+pub use thread_local::{AsThreadLocal, FileTableRefMut, NsProxyRefMut, ThreadLocal};
 
 pub struct PosixThread {
     // Immutable part
@@ -56,6 +58,11 @@ pub struct PosixThread {
     // Files
     /// File table
     file_table: Mutex<Option<RoArc<FileTable>>>,
+    /// FS information, which is owned by the [`ThreadLocal`]
+    fs: Mutex<Weak<ThreadFsInfo>>,
+
+    /// Namespaces
+    ns_proxy: Mutex<Option<Arc<NsProxy>>>,
 
     // Signal
     /// Blocked signals
@@ -77,6 +84,9 @@ pub struct PosixThread {
 
     /// I/O Scheduling priority value
     io_priority: AtomicU32,
+
+    /// The tracing state of the thread
+    ptrace_state: PtraceState,
 }
 
 impl PosixThread {
@@ -101,6 +111,20 @@ impl PosixThread {
         &self.file_table
     }
 
+    /// Returns the FS information of the thread.
+    ///
+    /// The FS information cannot be upgraded if the thread has exited.
+    pub fn fs(&self) -> &Mutex<Weak<ThreadFsInfo>> {
+        &self.fs
+    }
+
+    /// Returns the namespaces of the thread.
+    ///
+    /// The namespaces are `None` if the thread has exited.
+    pub fn ns_proxy(&self) -> &Mutex<Option<Arc<NsProxy>>> {
+        &self.ns_proxy
+    }
+
     /// Get the reference to the signal mask of the thread.
     ///
     /// Note that while this function offers mutable access to the signal mask,
@@ -116,9 +140,11 @@ impl PosixThread {
 
     /// Returns whether the thread has some pending signals
     /// that are not blocked.
+    ///
+    /// A pending `PTRACE_INTERRUPT` request is treated like a pending signal.
     pub fn has_pending(&self) -> bool {
         let blocked = self.sig_mask().load(Ordering::Relaxed);
-        self.sig_queues.has_pending(blocked)
+        self.sig_queues.has_pending(blocked) || self.ptrace_state.is_interrupted()
     }
 
     /// Returns whether the signal is blocked by the thread.
@@ -240,6 +266,11 @@ impl PosixThread {
         self.wake_signalled_waker();
     }
 
+    /// Returns the tracing state of the thread.
+    pub fn ptrace_state(&self) -> &PtraceState {
+        &self.ptrace_state
+    }
+
     /// Returns a reference to the profiling clock of the current thread.
     pub fn prof_clock(&self) -> &Arc<ProfClock> {
         &self.prof_clock
@@ -9,7 +9,7 @@ use super::RobustListHead;
 use crate::{
     fs::{file_table::FileTable, thread_info::ThreadFsInfo},
     prelude::*,
-    process::signal::SigStack,
+    process::{namespace::NsProxy, signal::SigStack},
     vm::vmar::Vmar,
 };
 
@@ -33,6 +33,9 @@ pub struct ThreadLocal {
     /// File system.
     fs: RefCell<Arc<ThreadFsInfo>>,
 
+    // Namespaces.
+    ns_proxy: RefCell<Option<Arc<NsProxy>>>,
+
     // User FPU context.
     fpu_context: RefCell<FpuContext>,
     fpu_state: Cell<FpuState>,
@@ -44,6 +47,13 @@ pub struct ThreadLocal {
     sig_context: Cell<Option<Vaddr>>,
     /// Stack address, size, and flags for the signal handler.
     sig_stack: RefCell<SigStack>,
+
+    // Deferred work.
+    /// The work to be done before the current system call returns.
+    ///
+    /// Some work cannot be done in place (e.g., installing files into the file table while the
+    /// file table is borrowed), so it is deferred until the system call completes.
+    task_works: RefCell<Vec<Box<dyn FnOnce(&ThreadLocal)>>>,
 }
 
 impl ThreadLocal {
@@ -53,6 +63,7 @@ impl ThreadLocal {
         root_vmar: Vmar<Full>,
         file_table: RwArc<FileTable>,
         fs: Arc<ThreadFsInfo>,
+        ns_proxy: Arc<NsProxy>,
         fpu_context: FpuContext,
     ) -> Self {
         Self {
@@ -62,10 +73,12 @@ impl ThreadLocal {
             robust_list: RefCell::new(None),
             file_table: RefCell::new(Some(file_table)),
             fs: RefCell::new(fs),
+            ns_proxy: RefCell::new(Some(ns_proxy)),
             sig_context: Cell::new(None),
             sig_stack: RefCell::new(SigStack::default()),
             fpu_context: RefCell::new(fpu_context),
             fpu_state: Cell::new(FpuState::Unloaded),
+            task_works: RefCell::new(Vec::new()),
         }
     }
 
@@ -101,6 +114,14 @@ impl ThreadLocal {
         self.fs.borrow_mut()
     }
 
+    pub fn borrow_ns_proxy(&self) -> NsProxyRef {
+        NsProxyRef(self.ns_proxy.borrow())
+    }
+
+    pub fn borrow_ns_proxy_mut(&self) -> NsProxyRefMut {
+        NsProxyRefMut(self.ns_proxy.borrow_mut())
+    }
+
     pub fn sig_context(&self) -> &Cell<Option<Vaddr>> {
         &self.sig_context
     }
@@ -112,6 +133,24 @@ impl ThreadLocal {
     pub fn fpu(&self) -> ThreadFpu<'_> {
         ThreadFpu(self)
     }
+
+    /// Defers `work` until the current system call completes.
+    pub fn add_task_work(&self, work: Box<dyn FnOnce(&ThreadLocal)>) {
+        self.task_works.borrow_mut().push(work);
+    }
+
+    /// Runs the deferred work, which must be called when nothing in `self` is borrowed.
+    pub fn run_task_works(&self) {
+        loop {
+            let works = core::mem::take(&mut *self.task_works.borrow_mut());
+            if works.is_empty() {
+                return;
+            }
+            for work in works {
+                work(self);
+            }
+        }
+    }
 }
 
 /// The current state of `ThreadFpu`.
@@ -238,6 +277,37 @@ impl FileTableRefMut<'_> {
     }
 }
 
+/// An immutable, shared reference to the namespaces in [`ThreadLocal`].
+pub struct NsProxyRef<'a>(Ref<'a, Option<Arc<NsProxy>>>);
+
+impl NsProxyRef<'_> {
+    /// Unwraps and returns a reference to the namespaces.
+    ///
+    /// # Panics
+    ///
+    /// This method will panic if the thread has exited and the namespaces have been dropped.
+    pub fn unwrap(&self) -> &Arc<NsProxy> {
+        self.0.as_ref().unwrap()
+    }
+}
+
+/// A mutable, exclusive reference to the namespaces in [`ThreadLocal`].
+pub struct NsProxyRefMut<'a>(RefMut<'a, Option<Arc<NsProxy>>>);
+
+impl NsProxyRefMut<'_> {
+    /// Removes the namespaces and drops them.
+    pub(super) fn remove(&mut self) {
+        *self.0 = None;
+    }
+
+    /// Replaces the namespaces with new ones.
+    ///
+    /// The caller should also update the namespaces in the `PosixThread`.
+    pub fn replace(&mut self, new_ns_proxy: Arc<NsProxy>) {
+        *self.0 = Some(new_ns_proxy);
+    }
+}
+
 /// A trait to provide the `as_thread_local` method for tasks.
 pub trait AsThreadLocal {
     /// Returns the associated [`ThreadLocal`].
@@ -12,6 +12,7 @@ use crate::{
     },
     prelude::*,
     process::{
+        namespace::PidNamespace,
         posix_thread::{allocate_posix_tid, PosixThreadBuilder, ThreadName},
         process_table,
         process_vm::ProcessVm,
@@ -58,6 +59,7 @@ fn create_init_process(
 
     let init_proc = Process::new(
         pid,
+        PidNamespace::get_init_singleton().clone(),
         parent,
         executable_path.to_string(),
         process_vm,
@@ -1,9 +1,10 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use core::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use core::sync::atomic::{AtomicBool, AtomicI32, AtomicU32, Ordering};
 
 use self::timer_manager::PosixTimerManager;
 use super::{
+    namespace::PidNamespace,
     posix_thread::AsPosixThread,
     process_table,
     process_vm::{Heap, InitStackReader, ProcessVm, ProcessVmarGuard},
@@ -20,7 +21,7 @@ use crate::{
     prelude::*,
     process::{signal::Pollee, status::StopWaitStatus, WaitOptions},
     sched::{AtomicNice, Nice},
-    thread::{AsThread, Thread},
+    thread::{AsThread, Thread, Tid},
     time::clocks::ProfClock,
 };
 
@@ -52,6 +53,11 @@ pub type Sid = u32;
 
 pub type ExitCode = u32;
 
+/// The minimum OOM score adjustment, which disables OOM killing for the process.
+pub const OOM_SCORE_ADJ_MIN: i32 = -1000;
+/// The maximum OOM score adjustment.
+pub const OOM_SCORE_ADJ_MAX: i32 = 1000;
+
 pub(super) fn init() {
     timer_manager::init();
 }
@@ -60,6 +66,8 @@ pub(super) fn init() {
 pub struct Process {
     // Immutable Part
     pid: Pid,
+    /// The PID namespace that the process belongs to.
+    pid_ns: Arc<PidNamespace>,
 
     process_vm: ProcessVm,
     /// Wait for child status changed
@@ -77,6 +85,8 @@ pub struct Process {
     pub(super) parent: ParentProcess,
     /// Children processes
     children: Mutex<BTreeMap<Pid, Arc<Process>>>,
+    /// Threads traced by the process
+    tracees: Mutex<BTreeMap<Tid, Arc<Thread>>>,
     /// Process group
     pub(super) process_group: Mutex<Weak<ProcessGroup>>,
     /// resource limits
@@ -85,6 +95,9 @@ pub struct Process {
     /// According to POSIX.1, the nice value is a per-process attribute,
     /// the threads in a process should share a nice value.
     nice: AtomicNice,
+    /// The adjustment to the badness score used to select a victim when the system is
+    /// out of memory, ranging from [`OOM_SCORE_ADJ_MIN`] to [`OOM_SCORE_ADJ_MAX`].
+    oom_score_adj: AtomicI32,
 
     // Child reaper attribute
     /// Whether the process is a child subreaper.
@@ -184,8 +197,10 @@ impl Process {
         Some(Task::current()?.as_posix_thread()?.process())
     }
 
+    #[expect(clippy::too_many_arguments)]
     pub(super) fn new(
         pid: Pid,
+        pid_ns: Arc<PidNamespace>,
         parent: Weak<Process>,
         executable_path: String,
         process_vm: ProcessVm,
@@ -202,6 +217,7 @@ impl Process {
 
         Arc::new_cyclic(|process_ref: &Weak<Process>| Self {
             pid,
+            pid_ns,
             tasks: Mutex::new(TaskSet::new()),
             executable_path: RwLock::new(executable_path),
             process_vm,
@@ -210,6 +226,7 @@ impl Process {
             status: ProcessStatus::default(),
             parent: ParentProcess::new(parent),
             children: Mutex::new(BTreeMap::new()),
+            tracees: Mutex::new(BTreeMap::new()),
             process_group: Mutex::new(Weak::new()),
             is_child_subreaper: AtomicBool::new(false),
             has_child_subreaper: AtomicBool::new(false),
@@ -218,6 +235,7 @@ impl Process {
             exit_signal: AtomicSigNum::new_empty(),
             resource_limits,
             nice: AtomicNice::new(nice),
+            oom_score_adj: AtomicI32::new(0),
             timer_manager: PosixTimerManager::new(&prof_clock, process_ref),
             prof_clock,
         })
@@ -242,6 +260,11 @@ impl Process {
         self.pid
     }
 
+    /// Gets the PID namespace of the process.
+    pub fn pid_ns(&self) -> &Arc<PidNamespace> {
+        &self.pid_ns
+    }
+
     /// Gets the profiling clock of the process.
     pub fn prof_clock(&self) -> &Arc<ProfClock> {
         &self.prof_clock
@@ -272,6 +295,22 @@ impl Process {
         &self.nice
     }
 
+    /// Returns the OOM score adjustment of the process.
+    pub fn oom_score_adj(&self) -> i32 {
+        self.oom_score_adj.load(Ordering::Relaxed)
+    }
+
+    /// Sets the OOM score adjustment of the process.
+    ///
+    /// The value must be within [`OOM_SCORE_ADJ_MIN`] and [`OOM_SCORE_ADJ_MAX`].
+    pub fn set_oom_score_adj(&self, oom_score_adj: i32) -> Result<()> {
+        if !(OOM_SCORE_ADJ_MIN..=OOM_SCORE_ADJ_MAX).contains(&oom_score_adj) {
+            return_errno_with_message!(Errno::EINVAL, "the OOM score adjustment is out of range");
+        }
+        self.oom_score_adj.store(oom_score_adj, Ordering::Relaxed);
+        Ok(())
+    }
+
     pub fn main_thread(&self) -> Arc<Thread> {
         self.tasks.lock().main().as_thread().unwrap().clone()
     }
@@ -290,6 +329,10 @@ impl Process {
         &self.children
     }
 
+    pub(super) fn tracees(&self) -> &Mutex<BTreeMap<Tid, Arc<Thread>>> {
+        &self.tracees
+    }
+
     pub fn children_wait_queue(&self) -> &WaitQueue {
         &self.children_wait_queue
     }
@@ -589,6 +632,10 @@ impl Process {
         self.process_vm.lock_root_vmar()
     }
 
+    pub fn try_lock_root_vmar(&self) -> Option<ProcessVmarGuard> {
+        self.process_vm.try_lock_root_vmar()
+    }
+
     pub fn heap(&self) -> &Heap {
         self.process_vm.heap()
     }
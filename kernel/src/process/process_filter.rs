@@ -23,8 +23,16 @@ impl ProcessFilter {
 
         match which {
             P_ALL => Ok(ProcessFilter::Any),
-            P_PID => Ok(ProcessFilter::WithPid(id)),
-            P_PGID => Ok(ProcessFilter::WithPgid(id)),
+            P_PID => Ok(ProcessFilter::WithPid(to_global_id(
+                id,
+                ctx,
+                Errno::ECHILD,
+            )?)),
+            P_PGID => Ok(ProcessFilter::WithPgid(to_global_id(
+                id,
+                ctx,
+                Errno::ECHILD,
+            )?)),
             P_PIDFD => {
                 let file = {
                     let mut file_table = ctx.thread_local.borrow_file_table_mut();
@@ -40,25 +48,36 @@ impl ProcessFilter {
     }
 
     // For `wait4` and `kill`.
-    pub fn from_id(wait_pid: i32) -> Self {
+    //
+    // The PIDs and PGIDs are translated from the PID namespace of the current process. If they are
+    // not visible in the namespace, an error of `errno` is returned.
+    pub fn from_id(wait_pid: i32, ctx: &Context, errno: Errno) -> Result<Self> {
         // Reference:
         // <https://man7.org/linux/man-pages/man2/waitpid.2.html>
         // <https://man7.org/linux/man-pages/man2/kill.2.html>
         if wait_pid < -1 {
             // "wait for any child process whose process group ID is equal to the absolute value of
             // `pid`"
-            ProcessFilter::WithPgid((-wait_pid).cast_unsigned())
+            let pgid = to_global_id((-wait_pid).cast_unsigned(), ctx, errno)?;
+            Ok(ProcessFilter::WithPgid(pgid))
         } else if wait_pid == -1 {
             // "wait for any child process"
-            ProcessFilter::Any
+            Ok(ProcessFilter::Any)
         } else if wait_pid == 0 {
             // "wait for any child process whose process group ID is equal to that of the calling
             // process at the time of the call to `waitpid()`"
-            let pgid = current!().pgid();
-            ProcessFilter::WithPgid(pgid)
+            Ok(ProcessFilter::WithPgid(ctx.process.pgid()))
         } else {
             // "wait for the child whose process ID is equal to the value of `pid`"
-            ProcessFilter::WithPid(wait_pid.cast_unsigned())
+            let pid = to_global_id(wait_pid.cast_unsigned(), ctx, errno)?;
+            Ok(ProcessFilter::WithPid(pid))
         }
     }
 }
+
+fn to_global_id(id: Pid, ctx: &Context, errno: Errno) -> Result<Pid> {
+    ctx.process
+        .pid_ns()
+        .global_id(id)
+        .ok_or_else(|| Error::with_message(errno, "the process is not visible in the namespace"))
+}
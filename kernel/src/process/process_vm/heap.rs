@@ -7,7 +7,11 @@ use aster_rights::Full;
 
 use crate::{
     prelude::*,
-    vm::{perms::VmPerms, vmar::Vmar},
+    vm::{
+        self,
+        perms::VmPerms,
+        vmar::{vm_mapping::VmMappingName, Vmar},
+    },
 };
 
 /// The base address of user heap
@@ -42,6 +46,7 @@ impl Heap {
                 .new_map(PAGE_SIZE, perms)
                 .unwrap()
                 .offset(self.base)
+                .name(VmMappingName::Special("[heap]"))
         };
         vmar_map_options.build()?;
 
@@ -84,6 +89,8 @@ impl Heap {
                 let current_heap_end = current_heap_end.align_up(PAGE_SIZE);
                 let new_heap_end = new_heap_end.align_up(PAGE_SIZE);
 
+                vm::check_overcommit(new_heap_end - current_heap_end, false)?;
+
                 // Remove the reserved space.
                 root_vmar.remove_mapping(current_heap_end..new_heap_end)?;
 
@@ -32,7 +32,7 @@ use crate::{
     util::random::getrandom,
     vm::{
         perms::VmPerms,
-        vmar::Vmar,
+        vmar::{vm_mapping::VmMappingName, Vmar},
         vmo::{Vmo, VmoOptions, VmoRightsOp},
     },
 };
@@ -182,6 +182,7 @@ impl InitStack {
                 .new_map(self.max_size, perms)?
                 .offset(map_addr)
                 .vmo(vmo.dup().to_dyn())
+                .name(VmMappingName::Special("[stack]"))
         };
         vmar_map_options.build()?;
 
@@ -147,6 +147,13 @@ impl ProcessVm {
         }
     }
 
+    /// Tries to lock the root VMAR, returning `None` if it is locked.
+    pub fn try_lock_root_vmar(&self) -> Option<ProcessVmarGuard> {
+        self.root_vmar
+            .try_lock()
+            .map(|inner| ProcessVmarGuard { inner })
+    }
+
     /// Returns a reader for reading contents from
     /// the `InitStack`.
     pub fn init_stack_reader(&self) -> InitStackReader {
@@ -29,7 +29,7 @@ use crate::{
     vm::{
         perms::VmPerms,
         util::duplicate_frame,
-        vmar::Vmar,
+        vmar::{vm_mapping::VmMappingName, Vmar},
         vmo::{CommitFlags, VmoRightsOp},
     },
 };
@@ -371,6 +371,7 @@ fn map_segment_vmo(
             .new_map(segment_size, perms)?
             .vmo(segment_vmo.dup()?)
             .vmo_offset(segment_offset)
+            .name(VmMappingName::File(elf_file.clone()))
             .can_overwrite(true);
         vm_map_options = vm_map_options.offset(offset).handle_page_faults_around();
         let map_addr = vm_map_options.build()?;
@@ -515,7 +516,8 @@ fn map_vdso_to_vm(process_vm: &ProcessVm) -> Option<Vaddr> {
     let options = root_vmar
         .new_map(VDSO_VMO_SIZE, VmPerms::empty())
         .unwrap()
-        .vmo(vdso_vmo.dup().unwrap());
+        .vmo(vdso_vmo.dup().unwrap())
+        .name(VmMappingName::Special("[vdso]"));
 
     let vdso_data_base = options.build().unwrap();
     let vdso_text_base = vdso_data_base + 0x4000;
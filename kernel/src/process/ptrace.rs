@@ -0,0 +1,924 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Process tracing.
+//!
+//! A thread (the tracee) can be traced by a process (the tracer) via the `ptrace` system call.
+//! The tracee enters a ptrace-stop when a signal is about to be delivered, when a traced event
+//! (e.g., `fork` or `execve`) happens, or when it enters or exits a system call if the tracer
+//! asks for it. The tracer is notified of the ptrace-stops via the `wait` family of system calls.
+//! While the tracee is stopped, the tracer can inspect and modify its registers and memory, and
+//! then resume it.
+//!
+//! Reference: <https://man7.org/linux/man-pages/man2/ptrace.2.html>
+
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use ostd::{
+    cpu::context::{FpuContext, UserContext},
+    sync::WaitQueue,
+};
+
+use super::{
+    credentials::capabilities::CapSet,
+    posix_thread::{AsPosixThread, PosixThread},
+    signal::{
+        c_types::siginfo_t,
+        constants::{SIGCHLD, SIGKILL, SIGSTOP, SIGTRAP, SI_KERNEL},
+        sig_mask::SigSet,
+        sig_num::SigNum,
+        signals::{
+            kernel::KernelSignal,
+            user::{UserSignal, UserSignalKind},
+            Signal,
+        },
+        with_sigmask_changed, Pause,
+    },
+    Process, TermStatus,
+};
+use crate::{
+    arch::ptrace::{set_single_step, UserRegs},
+    cpu::LinuxAbi,
+    prelude::*,
+    thread::{Thread, Tid},
+};
+
+bitflags! {
+    /// The options of a tracee, which are set by `PTRACE_SETOPTIONS` or `PTRACE_SEIZE`.
+    pub struct PtraceOptions: u32 {
+        const PTRACE_O_TRACESYSGOOD = 1 << 0;
+        const PTRACE_O_TRACEFORK = 1 << 1;
+        const PTRACE_O_TRACEVFORK = 1 << 2;
+        const PTRACE_O_TRACECLONE = 1 << 3;
+        const PTRACE_O_TRACEEXEC = 1 << 4;
+        const PTRACE_O_TRACEVFORKDONE = 1 << 5;
+        const PTRACE_O_TRACEEXIT = 1 << 6;
+        const PTRACE_O_TRACESECCOMP = 1 << 7;
+        const PTRACE_O_EXITKILL = 1 << 20;
+        const PTRACE_O_SUSPEND_SECCOMP = 1 << 21;
+    }
+}
+
+/// The events that cause ptrace-event-stops.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum PtraceEvent {
+    Fork = 1,
+    Vfork = 2,
+    Clone = 3,
+    Exec = 4,
+    VforkDone = 5,
+    Exit = 6,
+    Stop = 128,
+}
+
+impl PtraceEvent {
+    /// Returns the option that enables the event.
+    fn option(self) -> PtraceOptions {
+        match self {
+            Self::Fork => PtraceOptions::PTRACE_O_TRACEFORK,
+            Self::Vfork => PtraceOptions::PTRACE_O_TRACEVFORK,
+            Self::Clone => PtraceOptions::PTRACE_O_TRACECLONE,
+            Self::Exec => PtraceOptions::PTRACE_O_TRACEEXEC,
+            Self::VforkDone => PtraceOptions::PTRACE_O_TRACEVFORKDONE,
+            Self::Exit => PtraceOptions::PTRACE_O_TRACEEXIT,
+            Self::Stop => PtraceOptions::empty(),
+        }
+    }
+
+    /// Returns the status reported to the tracer, which is the second byte of the wait status.
+    fn status(self) -> u32 {
+        SIGTRAP.as_u8() as u32 | ((self as u32) << 8)
+    }
+}
+
+/// How the tracee runs after it is resumed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResumeMode {
+    /// Runs until the next ptrace-stop (i.e., `PTRACE_CONT`).
+    Continue,
+    /// Also stops at the entries and exits of system calls (i.e., `PTRACE_SYSCALL`).
+    Syscall,
+    /// Also stops after executing a single instruction (i.e., `PTRACE_SINGLESTEP`).
+    SingleStep,
+}
+
+/// The tracing state of a POSIX thread.
+pub struct PtraceState {
+    /// Whether the thread is traced.
+    ///
+    /// This is a fast path to avoid locking `inner` if the thread is not traced.
+    is_traced: AtomicBool,
+    /// Whether the tracer has requested the thread to stop (i.e., `PTRACE_INTERRUPT`).
+    is_interrupted: AtomicBool,
+    inner: SpinLock<PtraceInner>,
+    /// The wait queue in which the stopped thread waits for the tracer.
+    resume_queue: WaitQueue,
+}
+
+struct PtraceInner {
+    tracer: Weak<Process>,
+    /// Whether the thread is attached via `PTRACE_SEIZE`.
+    is_seized: bool,
+    options: PtraceOptions,
+    resume_mode: ResumeMode,
+    /// The message of the last ptrace-event-stop (i.e., `PTRACE_GETEVENTMSG`).
+    event_msg: usize,
+    stop: Option<PtraceStop>,
+    /// The exit status to report to the tracer via `wait`, if the thread has exited.
+    exit_status: Option<u32>,
+}
+
+/// A ptrace-stop of a tracee.
+pub struct PtraceStop {
+    /// The status reported to the tracer.
+    ///
+    /// The lowest byte is the signal number and the second byte is the event, if any.
+    status: u32,
+    /// Whether the stop has been reported to the tracer via `wait`.
+    is_reported: bool,
+    /// Whether the tracer has resumed the tracee.
+    is_resumed: bool,
+    /// The signal to deliver after the tracee is resumed.
+    signal: Option<Box<dyn Signal>>,
+    /// The information of the signal that causes the stop.
+    siginfo: siginfo_t,
+    user_ctx: UserContext,
+    fpu_context: FpuContext,
+    /// The number of the system call, if the tracee stops at a system call.
+    syscall_num: Option<usize>,
+}
+
+impl PtraceStop {
+    /// Returns the general-purpose registers of the tracee.
+    pub fn user_regs(&self) -> UserRegs {
+        UserRegs::new(&self.user_ctx, self.syscall_num)
+    }
+
+    /// Sets the general-purpose registers of the tracee.
+    pub fn set_user_regs(&mut self, regs: &UserRegs) {
+        regs.copy_to(&mut self.user_ctx, &mut self.syscall_num);
+    }
+
+    /// Reads a word at `offset` in the user area of the tracee.
+    pub fn peek_user(&self, offset: usize) -> Result<usize> {
+        crate::arch::ptrace::peek_user(&self.user_ctx, self.syscall_num, offset)
+    }
+
+    /// Writes a word at `offset` in the user area of the tracee.
+    pub fn poke_user(&mut self, offset: usize, value: usize) -> Result<()> {
+        crate::arch::ptrace::poke_user(&mut self.user_ctx, &mut self.syscall_num, offset, value)
+    }
+
+    /// Returns the FPU context of the tracee.
+    pub fn fpu_context(&self) -> &FpuContext {
+        &self.fpu_context
+    }
+
+    /// Returns the mutable FPU context of the tracee.
+    pub fn fpu_context_mut(&mut self) -> &mut FpuContext {
+        &mut self.fpu_context
+    }
+
+    /// Returns the information of the signal that causes the stop.
+    pub fn siginfo(&self) -> &siginfo_t {
+        &self.siginfo
+    }
+}
+
+impl PtraceState {
+    pub(super) fn new() -> Self {
+        Self {
+            is_traced: AtomicBool::new(false),
+            is_interrupted: AtomicBool::new(false),
+            inner: SpinLock::new(PtraceInner {
+                tracer: Weak::new(),
+                is_seized: false,
+                options: PtraceOptions::empty(),
+                resume_mode: ResumeMode::Continue,
+                event_msg: 0,
+                stop: None,
+                exit_status: None,
+            }),
+            resume_queue: WaitQueue::new(),
+        }
+    }
+
+    /// Returns whether the thread is traced.
+    pub fn is_traced(&self) -> bool {
+        self.is_traced.load(Ordering::Acquire)
+    }
+
+    /// Returns whether the tracer has requested the thread to stop.
+    pub(super) fn is_interrupted(&self) -> bool {
+        self.is_interrupted.load(Ordering::Relaxed)
+    }
+
+    /// Returns the tracer of the thread, if any.
+    pub fn tracer(&self) -> Option<Arc<Process>> {
+        if !self.is_traced() {
+            return None;
+        }
+        self.inner.lock().tracer.upgrade()
+    }
+
+    /// Calls `op` with the current ptrace-stop of the thread.
+    ///
+    /// # Errors
+    ///
+    /// This method will return `ESRCH` if the thread is not traced by `tracer` or is not in a
+    /// ptrace-stop.
+    pub fn with_stop<R>(
+        &self,
+        tracer: &Process,
+        op: impl FnOnce(&mut PtraceStop) -> Result<R>,
+    ) -> Result<R> {
+        let mut inner = self.inner.lock();
+        let stop = inner.stop_by(tracer)?;
+        op(stop)
+    }
+
+    /// Sets the options of the thread.
+    pub fn set_options(&self, tracer: &Process, options: PtraceOptions) -> Result<()> {
+        let mut inner = self.inner.lock();
+        inner.stop_by(tracer)?;
+        inner.options = options;
+        Ok(())
+    }
+
+    /// Returns the message of the last ptrace-event-stop.
+    pub fn event_msg(&self, tracer: &Process) -> Result<usize> {
+        let mut inner = self.inner.lock();
+        inner.stop_by(tracer)?;
+        Ok(inner.event_msg)
+    }
+
+    /// Returns the status of the ptrace-stop to report to `tracer` via `wait`, if any.
+    ///
+    /// If `is_nowait` is false, the stop is marked as reported, so it will not be reported again.
+    pub(super) fn wait_stopped(&self, tracer: &Process, is_nowait: bool) -> Option<u32> {
+        let mut inner = self.inner.lock();
+        let stop = inner.stop_by(tracer).ok()?;
+        if stop.is_reported {
+            return None;
+        }
+        if !is_nowait {
+            stop.is_reported = true;
+        }
+        Some(stop.status)
+    }
+
+    /// Returns the exit status to report to `tracer` via `wait`, if the thread has exited.
+    pub(super) fn wait_exited(&self, tracer: &Process) -> Option<u32> {
+        let inner = self.inner.lock();
+        if !inner.is_traced_by(tracer) {
+            return None;
+        }
+        inner.exit_status
+    }
+}
+
+impl PtraceInner {
+    fn is_traced_by(&self, tracer: &Process) -> bool {
+        core::ptr::eq(self.tracer.as_ptr(), tracer)
+    }
+
+    fn stop_by(&mut self, tracer: &Process) -> Result<&mut PtraceStop> {
+        if !self.is_traced_by(tracer) {
+            return_errno_with_message!(Errno::ESRCH, "the thread is not traced by the process");
+        }
+        match self.stop.as_mut() {
+            Some(stop) if !stop.is_resumed => Ok(stop),
+            _ => return_errno_with_message!(Errno::ESRCH, "the thread is not stopped"),
+        }
+    }
+
+    /// Detaches the thread from its tracer.
+    ///
+    /// The thread will be resumed if it is stopped. If `signal` is given, the signal will be
+    /// delivered to the thread.
+    fn detach(&mut self, signal: Option<Box<dyn Signal>>) {
+        self.tracer = Weak::new();
+        self.is_seized = false;
+        self.options = PtraceOptions::empty();
+        self.resume_mode = ResumeMode::Continue;
+
+        if let Some(stop) = self.stop.as_mut()
+            && !stop.is_resumed
+        {
+            let _ = set_single_step(&mut stop.user_ctx, false);
+            stop.resume(signal);
+        }
+    }
+}
+
+impl PtraceStop {
+    fn resume(&mut self, signal: Option<Box<dyn Signal>>) {
+        self.is_resumed = true;
+        self.signal = signal;
+    }
+}
+
+/// Creates the signal that the tracer delivers to the tracee when resuming it.
+///
+/// If the tracer changes the signal, the new signal appears to be sent by the tracer.
+fn signal_from_tracer(
+    tracer_ctx: &Context,
+    tracee: &PosixThread,
+    sig_num: Option<SigNum>,
+) -> Option<Box<dyn Signal>> {
+    let sig_num = sig_num?;
+    let signal = UserSignal::new(
+        sig_num,
+        UserSignalKind::Kill,
+        tracer_ctx.process.pid(),
+        tracer_ctx.posix_thread.credentials().ruid(),
+    )
+    .with_receiver_ns(tracee.process().pid_ns());
+    Some(Box::new(signal))
+}
+
+// *********** Tracer side ***********
+
+/// Attaches the tracer to the thread (i.e., `PTRACE_ATTACH` and `PTRACE_SEIZE`).
+///
+/// If `options` is `None`, the thread is attached via `PTRACE_ATTACH` and will be stopped by
+/// `SIGSTOP`. Otherwise, it is attached via `PTRACE_SEIZE` with the given options and will not
+/// be stopped.
+pub fn attach(
+    tracer_ctx: &Context,
+    thread: &Arc<Thread>,
+    options: Option<PtraceOptions>,
+) -> Result<()> {
+    let posix_thread = thread.as_posix_thread().unwrap();
+    if core::ptr::eq(posix_thread.process().as_ref(), tracer_ctx.process) {
+        return_errno_with_message!(Errno::EPERM, "a thread cannot trace its own process");
+    }
+    check_attach_permission(tracer_ctx.posix_thread, posix_thread)?;
+
+    let tracer = tracer_ctx.posix_thread.process();
+    let mut tracees = tracer.tracees().lock();
+    if thread.is_exited() {
+        return_errno_with_message!(Errno::ESRCH, "the thread has exited");
+    }
+    link_tracee(&tracer, &mut tracees, thread, options)?;
+    drop(tracees);
+
+    if options.is_none() {
+        posix_thread.enqueue_signal(Box::new(KernelSignal::new(SIGSTOP)));
+    }
+
+    Ok(())
+}
+
+/// Makes the current thread traced by its parent (i.e., `PTRACE_TRACEME`).
+pub fn trace_me(ctx: &Context) -> Result<()> {
+    let Some(parent) = ctx.process.parent().lock().process().upgrade() else {
+        return_errno_with_message!(Errno::EPERM, "the init process cannot be traced");
+    };
+
+    let thread = current_thread!();
+    let mut tracees = parent.tracees().lock();
+    link_tracee(&parent, &mut tracees, &thread, None)
+}
+
+/// Resumes the thread from the current ptrace-stop.
+///
+/// If `sig_num` is given, the signal will be delivered to the thread.
+pub fn resume(
+    tracer_ctx: &Context,
+    thread: &Arc<Thread>,
+    mode: ResumeMode,
+    sig_num: Option<SigNum>,
+) -> Result<()> {
+    let posix_thread = thread.as_posix_thread().unwrap();
+    let state = posix_thread.ptrace_state();
+    let signal = signal_from_tracer(tracer_ctx, posix_thread, sig_num);
+
+    let mut inner = state.inner.lock();
+    let stop = inner.stop_by(tracer_ctx.process)?;
+    set_single_step(&mut stop.user_ctx, mode == ResumeMode::SingleStep)?;
+    stop.resume(signal);
+    inner.resume_mode = mode;
+    drop(inner);
+
+    state.resume_queue.wake_all();
+    Ok(())
+}
+
+/// Detaches the tracer from the thread (i.e., `PTRACE_DETACH`).
+///
+/// The thread must be in a ptrace-stop. If `sig_num` is given, the signal will be delivered to
+/// the thread.
+pub fn detach(tracer_ctx: &Context, thread: &Arc<Thread>, sig_num: Option<SigNum>) -> Result<()> {
+    let posix_thread = thread.as_posix_thread().unwrap();
+    let state = posix_thread.ptrace_state();
+    let signal = signal_from_tracer(tracer_ctx, posix_thread, sig_num);
+
+    let mut tracees = tracer_ctx.process.tracees().lock();
+    let mut inner = state.inner.lock();
+    inner.stop_by(tracer_ctx.process)?;
+    inner.detach(signal);
+    state.is_traced.store(false, Ordering::Release);
+    drop(inner);
+    tracees.remove(&posix_thread.tid());
+    drop(tracees);
+
+    state.resume_queue.wake_all();
+    Ok(())
+}
+
+/// Requests the thread to enter a ptrace-event-stop (i.e., `PTRACE_INTERRUPT`).
+///
+/// The thread must be attached via `PTRACE_SEIZE`.
+pub fn interrupt(tracer_ctx: &Context, thread: &Arc<Thread>) -> Result<()> {
+    let posix_thread = thread.as_posix_thread().unwrap();
+    let state = posix_thread.ptrace_state();
+
+    let inner = state.inner.lock();
+    if !inner.is_traced_by(tracer_ctx.process) {
+        return_errno_with_message!(Errno::ESRCH, "the thread is not traced by the process");
+    }
+    if !inner.is_seized {
+        return_errno_with_message!(Errno::EIO, "the thread is not attached via PTRACE_SEIZE");
+    }
+    // If the thread is already stopped, there is nothing to do.
+    if inner.stop.as_ref().is_some_and(|stop| !stop.is_resumed) {
+        return Ok(());
+    }
+    state.is_interrupted.store(true, Ordering::Relaxed);
+    drop(inner);
+
+    posix_thread.wake_signalled_waker();
+    Ok(())
+}
+
+/// Kills the thread (i.e., `PTRACE_KILL`).
+pub fn kill(tracer_ctx: &Context, thread: &Arc<Thread>) -> Result<()> {
+    let posix_thread = thread.as_posix_thread().unwrap();
+    if !posix_thread
+        .ptrace_state()
+        .inner
+        .lock()
+        .is_traced_by(tracer_ctx.process)
+    {
+        return_errno_with_message!(Errno::ESRCH, "the thread is not traced by the process");
+    }
+
+    posix_thread.enqueue_signal(Box::new(KernelSignal::new(SIGKILL)));
+    Ok(())
+}
+
+/// Detaches all the tracees of the exiting process.
+///
+/// The stopped tracees will be resumed. If `PTRACE_O_EXITKILL` is set, the tracees will be
+/// killed.
+pub(super) fn exit_tracer(tracer: &Process) {
+    let tracees = core::mem::take(&mut *tracer.tracees().lock());
+
+    for thread in tracees.values() {
+        let posix_thread = thread.as_posix_thread().unwrap();
+        let state = posix_thread.ptrace_state();
+
+        let mut inner = state.inner.lock();
+        if !inner.is_traced_by(tracer) {
+            continue;
+        }
+        let is_exit_kill = inner.options.contains(PtraceOptions::PTRACE_O_EXITKILL);
+        inner.detach(None);
+        state.is_traced.store(false, Ordering::Release);
+        drop(inner);
+
+        if is_exit_kill {
+            posix_thread.enqueue_signal(Box::new(KernelSignal::new(SIGKILL)));
+        }
+        state.resume_queue.wake_all();
+    }
+}
+
+fn check_attach_permission(tracer: &PosixThread, tracee: &PosixThread) -> Result<()> {
+    let tracer_cred = tracer.credentials();
+    if tracer_cred.effective_capset().contains(CapSet::SYS_PTRACE) {
+        return Ok(());
+    }
+
+    let tracee_cred = tracee.credentials();
+    let ruid = tracer_cred.ruid();
+    let rgid = tracer_cred.rgid();
+    if [tracee_cred.ruid(), tracee_cred.euid(), tracee_cred.suid()]
+        .iter()
+        .all(|uid| *uid == ruid)
+        && [tracee_cred.rgid(), tracee_cred.egid(), tracee_cred.sgid()]
+            .iter()
+            .all(|gid| *gid == rgid)
+    {
+        return Ok(());
+    }
+
+    return_errno_with_message!(Errno::EPERM, "the thread cannot be traced by the process");
+}
+
+/// Links the thread to the tracer as a tracee.
+fn link_tracee(
+    tracer: &Arc<Process>,
+    tracees: &mut BTreeMap<Tid, Arc<Thread>>,
+    thread: &Arc<Thread>,
+    options: Option<PtraceOptions>,
+) -> Result<()> {
+    let posix_thread = thread.as_posix_thread().unwrap();
+    let state = posix_thread.ptrace_state();
+
+    let mut inner = state.inner.lock();
+    if inner.tracer.strong_count() > 0 {
+        return_errno_with_message!(Errno::EPERM, "the thread is already traced");
+    }
+    inner.tracer = Arc::downgrade(tracer);
+    inner.is_seized = options.is_some();
+    inner.options = options.unwrap_or(PtraceOptions::empty());
+    inner.resume_mode = ResumeMode::Continue;
+    state.is_traced.store(true, Ordering::Release);
+    drop(inner);
+
+    tracees.insert(posix_thread.tid(), thread.clone());
+    Ok(())
+}
+
+// *********** Tracee side ***********
+
+/// The state after the tracee is resumed from a ptrace-stop.
+struct Resumed {
+    signal: Option<Box<dyn Signal>>,
+    syscall_num: Option<usize>,
+    is_killed: bool,
+}
+
+/// Stops the current thread and waits for the tracer to resume it.
+///
+/// Returns `None` if the current thread is not traced.
+fn ptrace_stop(
+    ctx: &Context,
+    user_ctx: &mut UserContext,
+    status: u32,
+    siginfo: siginfo_t,
+    syscall_num: Option<usize>,
+) -> Option<Resumed> {
+    let state = ctx.posix_thread.ptrace_state();
+    if !state.is_traced() {
+        return None;
+    }
+
+    let stop = PtraceStop {
+        status,
+        is_reported: false,
+        is_resumed: false,
+        signal: None,
+        siginfo,
+        user_ctx: user_ctx.clone(),
+        fpu_context: ctx.thread_local.fpu().clone_context(),
+        syscall_num,
+    };
+
+    let tracer = {
+        let mut inner = state.inner.lock();
+        let tracer = inner.tracer.upgrade()?;
+        inner.stop = Some(stop);
+        // Any ptrace-stop satisfies the pending `PTRACE_INTERRUPT` request.
+        state.is_interrupted.store(false, Ordering::Relaxed);
+        tracer
+    };
+
+    tracer.children_wait_queue().wake_all();
+    tracer.enqueue_signal(KernelSignal::new(SIGCHLD));
+
+    // Only `SIGKILL` can wake up the stopped thread.
+    let res = with_sigmask_changed(
+        ctx,
+        |_| !SigSet::from(SIGKILL),
+        || {
+            state.resume_queue.pause_until(|| {
+                let inner = state.inner.lock();
+                match inner.stop.as_ref() {
+                    Some(stop) if !stop.is_resumed => None,
+                    _ => Some(()),
+                }
+            })
+        },
+    );
+
+    let stop = state.inner.lock().stop.take()?;
+    *user_ctx = stop.user_ctx;
+    ctx.thread_local.fpu().set_context(stop.fpu_context);
+
+    Some(Resumed {
+        signal: stop.signal,
+        syscall_num: stop.syscall_num,
+        is_killed: res.is_err(),
+    })
+}
+
+/// Stops the current thread with a ptrace-event-stop, if the event is traced.
+///
+/// Returns whether the thread has stopped.
+fn stop_on_event(
+    ctx: &Context,
+    user_ctx: &mut UserContext,
+    event: PtraceEvent,
+    msg: usize,
+) -> bool {
+    let state = ctx.posix_thread.ptrace_state();
+    if !state.is_traced() {
+        return false;
+    }
+
+    {
+        let mut inner = state.inner.lock();
+        if event != PtraceEvent::Stop && !inner.options.contains(event.option()) {
+            return false;
+        }
+        inner.event_msg = msg;
+    }
+
+    let siginfo = siginfo_t::new(SIGTRAP, SI_KERNEL);
+    ptrace_stop(ctx, user_ctx, event.status(), siginfo, None).is_some()
+}
+
+/// Stops the current thread with a signal-delivery-stop, if the thread is traced.
+///
+/// Returns the signal to deliver after the thread is resumed.
+pub(super) fn stop_on_signal(
+    ctx: &Context,
+    user_ctx: &mut UserContext,
+    signal: Box<dyn Signal>,
+) -> Option<Box<dyn Signal>> {
+    let sig_num = signal.num();
+    if sig_num == SIGKILL {
+        return Some(signal);
+    }
+
+    let status = sig_num.as_u8() as u32;
+    let Some(resumed) = ptrace_stop(ctx, user_ctx, status, signal.to_info(), None) else {
+        return Some(signal);
+    };
+    if resumed.is_killed {
+        // Deliver the `SIGKILL` that has woken up the thread.
+        return ctx.posix_thread.dequeue_signal(&!SigSet::from(SIGKILL));
+    }
+
+    let new_signal = resumed.signal?;
+    if new_signal.num() == sig_num {
+        return Some(signal);
+    }
+
+    // The new signal is queued if it is blocked.
+    if ctx.posix_thread.has_signal_blocked(new_signal.num()) {
+        ctx.posix_thread.enqueue_signal(new_signal);
+        return None;
+    }
+    Some(new_signal)
+}
+
+/// Stops the current thread with a group-stop, if the thread is traced.
+///
+/// Returns whether the thread has stopped.
+pub(super) fn stop_on_group_stop(
+    ctx: &Context,
+    user_ctx: &mut UserContext,
+    sig_num: SigNum,
+) -> bool {
+    let state = ctx.posix_thread.ptrace_state();
+    if !state.is_traced() {
+        return false;
+    }
+
+    // The tracee attached via `PTRACE_SEIZE` reports group-stops as `PTRACE_EVENT_STOP`.
+    let mut status = sig_num.as_u8() as u32;
+    if state.inner.lock().is_seized {
+        status |= (PtraceEvent::Stop as u32) << 8;
+    }
+
+    let siginfo = siginfo_t::new(sig_num, SI_KERNEL);
+    ptrace_stop(ctx, user_ctx, status, siginfo, None).is_some()
+}
+
+/// Stops the current thread with a ptrace-event-stop, if `PTRACE_INTERRUPT` is requested.
+///
+/// Returns whether the thread has stopped.
+pub(super) fn stop_on_interrupt(ctx: &Context, user_ctx: &mut UserContext) -> bool {
+    let state = ctx.posix_thread.ptrace_state();
+    if !state.is_interrupted() {
+        return false;
+    }
+
+    stop_on_event(ctx, user_ctx, PtraceEvent::Stop, 0)
+}
+
+/// The action on a system call after the syscall-entry-stop.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyscallEntryAction {
+    /// The system call is executed.
+    Execute,
+    /// The system call is skipped by the tracer, which still expects the syscall-exit-stop.
+    Skip,
+    /// The thread is killed, so the system call is abandoned.
+    Abandon,
+}
+
+/// Stops the current thread at the entry of a system call, if requested by the tracer.
+///
+/// Returns the action on the system call. The system call number may be changed by the tracer.
+pub fn stop_on_syscall_entry(ctx: &Context, user_ctx: &mut UserContext) -> SyscallEntryAction {
+    let Some(status) = syscall_stop_status(ctx) else {
+        return SyscallEntryAction::Execute;
+    };
+
+    let syscall_num = user_ctx.syscall_num();
+    // Like Linux, the return value is `-ENOSYS` if the tracer decides to skip the system call.
+    #[cfg(target_arch = "x86_64")]
+    user_ctx.set_syscall_ret(-(Errno::ENOSYS as i32) as usize);
+
+    let siginfo = siginfo_t::new(SIGTRAP, SI_KERNEL);
+    let Some(resumed) = ptrace_stop(ctx, user_ctx, status, siginfo, Some(syscall_num)) else {
+        return SyscallEntryAction::Execute;
+    };
+    if resumed.is_killed {
+        return SyscallEntryAction::Abandon;
+    }
+
+    match resumed.syscall_num {
+        Some(syscall_num) => {
+            user_ctx.set_syscall_num(syscall_num);
+            SyscallEntryAction::Execute
+        }
+        None => SyscallEntryAction::Skip,
+    }
+}
+
+/// Stops the current thread at the exit of a system call, if requested by the tracer.
+pub fn stop_on_syscall_exit(ctx: &Context, user_ctx: &mut UserContext, syscall_num: usize) {
+    if ctx.thread.is_exited() {
+        return;
+    }
+
+    let Some(status) = syscall_stop_status(ctx) else {
+        return;
+    };
+
+    let siginfo = siginfo_t::new(SIGTRAP, SI_KERNEL);
+    ptrace_stop(ctx, user_ctx, status, siginfo, Some(syscall_num));
+}
+
+fn syscall_stop_status(ctx: &Context) -> Option<u32> {
+    let state = ctx.posix_thread.ptrace_state();
+    if !state.is_traced() {
+        return None;
+    }
+
+    let inner = state.inner.lock();
+    if inner.resume_mode != ResumeMode::Syscall {
+        return None;
+    }
+
+    // With `PTRACE_O_TRACESYSGOOD`, the syscall-stops can be distinguished from other stops.
+    let mut status = SIGTRAP.as_u8() as u32;
+    if inner.options.contains(PtraceOptions::PTRACE_O_TRACESYSGOOD) {
+        status |= 0x80;
+    }
+    Some(status)
+}
+
+/// Makes the child traced by the tracer of the current thread, if the event is traced.
+///
+/// After the child starts running, [`stop_on_clone`] should be called to notify the tracer.
+///
+/// Returns whether the child is traced.
+pub(super) fn trace_child(ctx: &Context, child: &Arc<Thread>, event: PtraceEvent) -> bool {
+    let state = ctx.posix_thread.ptrace_state();
+    if !state.is_traced() {
+        return false;
+    }
+
+    let (tracer, options, is_seized) = {
+        let inner = state.inner.lock();
+        if !inner.options.contains(event.option()) {
+            return false;
+        }
+        let Some(tracer) = inner.tracer.upgrade() else {
+            return false;
+        };
+        (tracer, inner.options, inner.is_seized)
+    };
+
+    let child_thread = child.as_posix_thread().unwrap();
+    let mut tracees = tracer.tracees().lock();
+    if link_tracee(&tracer, &mut tracees, child, is_seized.then_some(options)).is_err() {
+        return false;
+    }
+    drop(tracees);
+
+    // The child attached via `PTRACE_ATTACH` starts with `SIGSTOP`, while the child attached
+    // via `PTRACE_SEIZE` starts with `PTRACE_EVENT_STOP`.
+    if is_seized {
+        child_thread
+            .ptrace_state()
+            .is_interrupted
+            .store(true, Ordering::Relaxed);
+    } else {
+        child_thread.ptrace_state().inner.lock().options = options;
+        child_thread.enqueue_signal(Box::new(KernelSignal::new(SIGSTOP)));
+    }
+
+    true
+}
+
+/// Notifies the tracer that a child has been created by the current thread.
+pub(super) fn stop_on_clone(
+    ctx: &Context,
+    user_ctx: &mut UserContext,
+    event: PtraceEvent,
+    child_tid: Tid,
+) {
+    stop_on_event(ctx, user_ctx, event, child_tid as usize);
+}
+
+/// Notifies the tracer that the child created by `vfork` has released the memory.
+pub(super) fn stop_on_vfork_done(ctx: &Context, user_ctx: &mut UserContext, child_tid: Tid) {
+    stop_on_event(ctx, user_ctx, PtraceEvent::VforkDone, child_tid as usize);
+}
+
+/// Notifies the tracer that the current thread has executed a new program.
+///
+/// If `PTRACE_O_TRACEEXEC` is not set, the thread attached via `PTRACE_ATTACH` or
+/// `PTRACE_TRACEME` will receive a `SIGTRAP`.
+pub fn stop_on_exec(ctx: &Context, user_ctx: &mut UserContext) {
+    let state = ctx.posix_thread.ptrace_state();
+    if !state.is_traced() {
+        return;
+    }
+
+    if stop_on_event(
+        ctx,
+        user_ctx,
+        PtraceEvent::Exec,
+        ctx.posix_thread.tid() as usize,
+    ) {
+        return;
+    }
+
+    if !state.inner.lock().is_seized {
+        ctx.posix_thread
+            .enqueue_signal(Box::new(KernelSignal::new(SIGTRAP)));
+    }
+}
+
+/// Notifies the tracer that the current thread is exiting.
+pub fn stop_on_exit(ctx: &Context, user_ctx: &mut UserContext, term_status: TermStatus) {
+    stop_on_event(
+        ctx,
+        user_ctx,
+        PtraceEvent::Exit,
+        term_status.as_u32() as usize,
+    );
+}
+
+/// Reports the exit of the exiting thread to its tracer, or detaches the thread from its tracer.
+///
+/// Like Linux, the exit is reported to the tracer via `wait`, unless the tracer is the parent and
+/// the thread is the main thread, whose exit is reported as the exit of the process.
+pub(super) fn exit_tracee(posix_thread: &PosixThread, exit_code: u32) {
+    let state = posix_thread.ptrace_state();
+    let Some(tracer) = state.tracer() else {
+        return;
+    };
+
+    let process = posix_thread.process();
+    let is_reported_by_process = posix_thread.tid() == process.pid()
+        && process
+            .parent()
+            .lock()
+            .process()
+            .upgrade()
+            .is_some_and(|parent| Arc::ptr_eq(&parent, &tracer));
+
+    let mut tracees = tracer.tracees().lock();
+    let mut inner = state.inner.lock();
+    if !inner.is_traced_by(&tracer) {
+        return;
+    }
+    state.is_traced.store(false, Ordering::Release);
+    if is_reported_by_process {
+        inner.detach(None);
+        drop(inner);
+        tracees.remove(&posix_thread.tid());
+        drop(tracees);
+
+        tracer.children_wait_queue().wake_all();
+        return;
+    }
+
+    // The thread remains a tracee until the tracer waits for its exit.
+    inner.exit_status = Some(exit_code);
+    drop(inner);
+    drop(tracees);
+
+    tracer.children_wait_queue().wake_all();
+    tracer.enqueue_signal(KernelSignal::new(SIGCHLD));
+}
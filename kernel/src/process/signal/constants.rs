@@ -99,6 +99,11 @@ pub const BUS_OBJERR: i32 = 3;
 pub const BUS_MCEERR_AR: i32 = 4;
 pub const BUS_MCEERR_AO: i32 = 5;
 
+pub const TRAP_BRKPT: i32 = 1;
+pub const TRAP_TRACE: i32 = 2;
+pub const TRAP_BRANCH: i32 = 3;
+pub const TRAP_HWBKPT: i32 = 4;
+
 pub const CLD_EXITED: i32 = 1;
 pub const CLD_KILLED: i32 = 2;
 pub const CLD_DUMPED: i32 = 3;
@@ -35,7 +35,7 @@ use crate::{
     cpu::LinuxAbi,
     current_userspace,
     prelude::*,
-    process::{posix_thread::do_exit_group, signal::c_types::stack_t, TermStatus},
+    process::{posix_thread::do_exit_group, ptrace, signal::c_types::stack_t, TermStatus},
 };
 
 pub trait SignalContext {
@@ -64,14 +64,29 @@ pub fn handle_pending_signal(
     let posix_thread = ctx.posix_thread;
     let current = ctx.process;
 
+    let is_ptrace_interrupted = ptrace::stop_on_interrupt(ctx, user_ctx);
+
     let signal = {
         let sig_mask = posix_thread.sig_mask().load(Ordering::Relaxed);
         if let Some(signal) = posix_thread.dequeue_signal(&sig_mask) {
             signal
         } else {
+            // The system call interrupted by `PTRACE_INTERRUPT` is restarted transparently.
+            if is_ptrace_interrupted && let Some(syscall_number) = syscall_restart {
+                restart_syscall(user_ctx, syscall_number);
+            }
             return;
         }
     };
+
+    // A traced thread stops before the signal is delivered. The tracer may suppress the signal
+    // or replace it with another one.
+    let Some(signal) = ptrace::stop_on_signal(ctx, user_ctx, signal) else {
+        if let Some(syscall_number) = syscall_restart {
+            restart_syscall(user_ctx, syscall_number);
+        }
+        return;
+    };
     let sig_num = signal.num();
     trace!("sig_num = {:?}, sig_name = {}", sig_num, sig_num.sig_name());
 
@@ -93,16 +108,7 @@ pub fn handle_pending_signal(
             if let Some(syscall_number) = syscall_restart
                 && flags.contains(SigActionFlags::SA_RESTART)
             {
-                #[cfg(target_arch = "x86_64")]
-                const SYSCALL_INSTR_LEN: usize = 2; // syscall
-                #[cfg(target_arch = "riscv64")]
-                const SYSCALL_INSTR_LEN: usize = 4; // ecall
-                #[cfg(target_arch = "loongarch64")]
-                const SYSCALL_INSTR_LEN: usize = 4; // syscall
-
-                user_ctx.set_syscall_num(syscall_number);
-                user_ctx
-                    .set_instruction_pointer(user_ctx.instruction_pointer() - SYSCALL_INSTR_LEN);
+                restart_syscall(user_ctx, syscall_number);
             }
 
             if flags.contains(SigActionFlags::SA_RESETHAND) {
@@ -141,17 +147,37 @@ pub fn handle_pending_signal(
                         current.executable_path(),
                         sig_num.sig_name()
                     );
+                    ptrace::stop_on_exit(ctx, user_ctx, TermStatus::Killed(sig_num));
                     // We should exit current here, since we cannot restore a valid status from trap now.
                     do_exit_group(TermStatus::Killed(sig_num));
                 }
                 SigDefaultAction::Ign => {}
-                SigDefaultAction::Stop => ctx.process.stop(sig_num),
+                SigDefaultAction::Stop => {
+                    // A traced thread reports the stop to the tracer instead of stopping the
+                    // whole process.
+                    if !ptrace::stop_on_group_stop(ctx, user_ctx, sig_num) {
+                        ctx.process.stop(sig_num);
+                    }
+                }
                 SigDefaultAction::Cont => ctx.process.resume(),
             }
         }
     }
 }
 
+/// Restarts the system call by rewinding the instruction pointer to the system call instruction.
+fn restart_syscall(user_ctx: &mut UserContext, syscall_number: usize) {
+    #[cfg(target_arch = "x86_64")]
+    const SYSCALL_INSTR_LEN: usize = 2; // syscall
+    #[cfg(target_arch = "riscv64")]
+    const SYSCALL_INSTR_LEN: usize = 4; // ecall
+    #[cfg(target_arch = "loongarch64")]
+    const SYSCALL_INSTR_LEN: usize = 4; // syscall
+
+    user_ctx.set_syscall_num(syscall_number);
+    user_ctx.set_instruction_pointer(user_ctx.instruction_pointer() - SYSCALL_INSTR_LEN);
+}
+
 #[expect(clippy::too_many_arguments)]
 pub fn handle_user_signal(
     ctx: &Context,
@@ -4,6 +4,7 @@
 
 use super::Signal;
 use crate::process::{
+    namespace::PidNamespace,
     signal::{
         c_types::siginfo_t,
         constants::{SI_QUEUE, SI_TKILL, SI_USER},
@@ -15,6 +16,10 @@ use crate::process::{
 #[derive(Debug, Clone, Copy)]
 pub struct UserSignal {
     num: SigNum,
+    /// The PID of the sender.
+    ///
+    /// It is the global PID until the signal is translated to the PID namespace of the
+    /// receiver by [`UserSignal::with_receiver_ns`].
     pid: Pid,
     uid: Uid,
     kind: UserSignalKind,
@@ -44,6 +49,14 @@ impl UserSignal {
     pub fn kind(&self) -> UserSignalKind {
         self.kind
     }
+
+    /// Translates the PID of the sender to `pid_ns`, which is the PID namespace of the receiver.
+    ///
+    /// If the sender is not visible in the namespace, the PID becomes zero.
+    pub fn with_receiver_ns(mut self, pid_ns: &PidNamespace) -> Self {
+        self.pid = pid_ns.local_id(self.pid).unwrap_or(0);
+        self
+    }
 }
 
 impl Signal for UserSignal {
@@ -58,9 +71,9 @@ impl Signal for UserSignal {
             UserSignalKind::Sigqueue => SI_QUEUE,
         };
 
-        siginfo_t::new(self.num, code)
-        // info.set_si_pid(self.pid);
-        // info.set_si_uid(self.uid);
+        let mut info = siginfo_t::new(self.num, code);
+        info.set_pid_uid(self.pid, self.uid);
+        info
         // if let UserSignalKind::Sigqueue(val) = self.kind {
         //     info.set_si_value(val);
         // }
@@ -14,6 +14,7 @@ use crate::{
         status::StopWaitStatus,
         Uid,
     },
+    thread::Thread,
     time::clocks::ProfClock,
 };
 
@@ -94,7 +95,28 @@ pub fn do_wait(
                     })
                     .collect::<Box<_>>();
 
-                if unwaited_children.is_empty() {
+                // Lock order: children of process -> tracees of process
+                let mut tracees_lock = ctx.process.tracees().lock();
+
+                let unwaited_tracees = tracees_lock
+                    .values()
+                    .filter(|tracee| {
+                        let posix_thread = tracee.as_posix_thread().unwrap();
+                        let Some(process) = posix_thread.weak_process().upgrade() else {
+                            return false;
+                        };
+                        match &child_filter {
+                            ProcessFilter::Any => true,
+                            ProcessFilter::WithPid(pid) => posix_thread.tid() == *pid,
+                            ProcessFilter::WithPgid(pgid) => process.pgid() == *pgid,
+                            ProcessFilter::WithPidfd(pid_file) => {
+                                Arc::ptr_eq(pid_file.process(), &process)
+                            }
+                        }
+                    })
+                    .collect::<Box<_>>();
+
+                if unwaited_children.is_empty() && unwaited_tracees.is_empty() {
                     return Some(Err(Error::with_message(
                         Errno::ECHILD,
                         "the process has no child to wait",
@@ -108,6 +130,19 @@ pub fn do_wait(
                     return Some(Ok(Some(status)));
                 }
 
+                if let Some(status) = wait_ptrace_exited(ctx.process, &unwaited_tracees) {
+                    if !wait_options.contains(WaitOptions::WNOWAIT) {
+                        tracees_lock.remove(&status.pid());
+                    }
+                    return Some(Ok(Some(status)));
+                }
+
+                if let Some(status) =
+                    wait_ptrace_stopped(ctx.process, &unwaited_tracees, wait_options)
+                {
+                    return Some(Ok(Some(status)));
+                }
+
                 if let Some(status) = wait_stopped_or_continued(&unwaited_children, wait_options) {
                     return Some(Ok(Some(status)));
                 }
@@ -136,31 +171,45 @@ pub enum WaitStatus {
     Zombie(Arc<Process>),
     Stop(Arc<Process>, SigNum),
     Continue(Arc<Process>),
+    /// A ptrace-stop of a traced thread, with the status reported to the tracer.
+    PtraceStop(Arc<Thread>, u32),
+    /// The exit of a traced thread, with the exit code reported to the tracer.
+    PtraceExit(Arc<Thread>, ExitCode),
 }
 
 impl WaitStatus {
     pub fn pid(&self) -> Pid {
-        self.process().pid()
+        match self {
+            WaitStatus::PtraceStop(thread, _) | WaitStatus::PtraceExit(thread, _) => {
+                thread.as_posix_thread().unwrap().tid()
+            }
+            _ => self.process().unwrap().pid(),
+        }
     }
 
     pub fn uid(&self) -> Uid {
-        self.process()
-            .main_thread()
-            .as_posix_thread()
-            .unwrap()
-            .credentials()
-            .ruid()
+        let thread = match self {
+            WaitStatus::PtraceStop(thread, _) | WaitStatus::PtraceExit(thread, _) => thread.clone(),
+            _ => self.process().unwrap().main_thread(),
+        };
+        thread.as_posix_thread().unwrap().credentials().ruid()
     }
 
     pub fn prof_clock(&self) -> &Arc<ProfClock> {
-        self.process().prof_clock()
+        match self {
+            WaitStatus::PtraceStop(thread, _) | WaitStatus::PtraceExit(thread, _) => {
+                thread.as_posix_thread().unwrap().prof_clock()
+            }
+            _ => self.process().unwrap().prof_clock(),
+        }
     }
 
-    fn process(&self) -> &Arc<Process> {
+    fn process(&self) -> Option<&Arc<Process>> {
         match self {
             WaitStatus::Zombie(process)
             | WaitStatus::Stop(process, _)
-            | WaitStatus::Continue(process) => process,
+            | WaitStatus::Continue(process) => Some(process),
+            WaitStatus::PtraceStop(..) | WaitStatus::PtraceExit(..) => None,
         }
     }
 }
@@ -196,13 +245,47 @@ fn wait_stopped_or_continued(
     None
 }
 
+/// Finds an exited tracee whose exit has not been reported.
+fn wait_ptrace_exited(tracer: &Process, unwaited_tracees: &[&Arc<Thread>]) -> Option<WaitStatus> {
+    unwaited_tracees.iter().find_map(|tracee| {
+        let exit_code = tracee
+            .as_posix_thread()
+            .unwrap()
+            .ptrace_state()
+            .wait_exited(tracer)?;
+        Some(WaitStatus::PtraceExit((*tracee).clone(), exit_code))
+    })
+}
+
+/// Finds a tracee in a ptrace-stop that has not been reported.
+///
+/// Unlike group-stops of the children, ptrace-stops are reported even if `WSTOPPED` is not set.
+fn wait_ptrace_stopped(
+    tracer: &Process,
+    unwaited_tracees: &[&Arc<Thread>],
+    wait_options: WaitOptions,
+) -> Option<WaitStatus> {
+    let is_nowait = wait_options.contains(WaitOptions::WNOWAIT);
+
+    unwaited_tracees.iter().find_map(|tracee| {
+        let status = tracee
+            .as_posix_thread()
+            .unwrap()
+            .ptrace_state()
+            .wait_stopped(tracer, is_nowait)?;
+        Some(WaitStatus::PtraceStop((*tracee).clone(), status))
+    })
+}
+
 /// Free zombie child with pid, returns the exit code of child process.
 fn reap_zombie_child(pid: Pid, children_lock: &mut BTreeMap<Pid, Arc<Process>>) -> ExitCode {
     let child_process = children_lock.remove(&pid).unwrap();
     assert!(child_process.status().is_zombie());
 
     for task in child_process.tasks().lock().as_slice() {
-        thread_table::remove_thread(task.as_posix_thread().unwrap().tid());
+        let tid = task.as_posix_thread().unwrap().tid();
+        thread_table::remove_thread(tid);
+        child_process.pid_ns().free_ids(tid);
     }
 
     // Lock order: children of process -> session table -> group table
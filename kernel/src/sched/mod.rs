@@ -7,5 +7,5 @@ mod stats;
 pub use self::{
     nice::{AtomicNice, Nice},
     sched_class::{init, RealTimePolicy, RealTimePriority, SchedAttr, SchedPolicy},
-    stats::{loadavg, nr_queued_and_running},
+    stats::{cpu_time, loadavg, nr_queued_and_running},
 };
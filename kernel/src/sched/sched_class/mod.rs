@@ -23,7 +23,7 @@ use ostd::{
 
 use super::{
     nice::Nice,
-    stats::{set_stats_from_scheduler, SchedulerStats},
+    stats::{cpu_time, set_stats_from_scheduler, SchedulerStats},
 };
 use crate::thread::{AsThread, Thread};
 
@@ -53,6 +53,8 @@ pub fn init() {
     // We set this after injecting the scheduler into ostd,
     // so that the loadavg statistics are updated after the scheduler is used.
     set_stats_from_scheduler(scheduler);
+
+    cpu_time::init_on_current_cpu();
 }
 
 /// Represents the middle layer between scheduling classes and generic scheduler
@@ -0,0 +1,103 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! This module accounts the time that each CPU spends in different modes.
+//!
+//! The accounting is tick-based: on each timer interrupt, one jiffy is
+//! charged to the mode that the interrupted CPU was running in.
+//!
+//! Reference: <https://github.com/torvalds/linux/blob/46132e3/kernel/sched/cputime.c>
+
+use core::sync::atomic::{AtomicU64, Ordering::Relaxed};
+
+use ostd::{
+    arch::trap::is_kernel_interrupted, cpu::CpuId, cpu_local, timer, trap::irq::disable_local,
+};
+
+use crate::{sched::SchedPolicy, thread::Thread};
+
+/// The time that a CPU has spent in different modes, measured in jiffies.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct CpuTime {
+    /// The time spent in user mode.
+    pub user: u64,
+    /// The time spent in user mode with a positive nice value.
+    pub nice: u64,
+    /// The time spent in kernel mode.
+    pub system: u64,
+    /// The time spent in the idle task.
+    pub idle: u64,
+}
+
+impl core::ops::AddAssign for CpuTime {
+    fn add_assign(&mut self, rhs: Self) {
+        self.user += rhs.user;
+        self.nice += rhs.nice;
+        self.system += rhs.system;
+        self.idle += rhs.idle;
+    }
+}
+
+struct AtomicCpuTime {
+    user: AtomicU64,
+    nice: AtomicU64,
+    system: AtomicU64,
+    idle: AtomicU64,
+}
+
+impl AtomicCpuTime {
+    const fn new() -> Self {
+        Self {
+            user: AtomicU64::new(0),
+            nice: AtomicU64::new(0),
+            system: AtomicU64::new(0),
+            idle: AtomicU64::new(0),
+        }
+    }
+
+    fn load(&self) -> CpuTime {
+        CpuTime {
+            user: self.user.load(Relaxed),
+            nice: self.nice.load(Relaxed),
+            system: self.system.load(Relaxed),
+            idle: self.idle.load(Relaxed),
+        }
+    }
+}
+
+cpu_local! {
+    static CPU_TIME: AtomicCpuTime = AtomicCpuTime::new();
+}
+
+/// Returns the time that `cpu` has spent in different modes.
+pub fn cpu_time_on(cpu: CpuId) -> CpuTime {
+    CPU_TIME.get_on_cpu(cpu).load()
+}
+
+/// Returns the time that all CPUs have spent in different modes.
+pub fn total_cpu_time() -> CpuTime {
+    ostd::cpu::all_cpus().fold(CpuTime::default(), |mut total, cpu| {
+        total += cpu_time_on(cpu);
+        total
+    })
+}
+
+/// Starts accounting the CPU time on the current CPU.
+///
+/// This function should be called exactly once on each CPU.
+pub fn init_on_current_cpu() {
+    timer::register_callback(account_tick);
+}
+
+fn account_tick() {
+    let irq_guard = disable_local();
+    let cpu_time = CPU_TIME.get_with(&irq_guard);
+
+    let policy = Thread::current().map(|thread| thread.sched_attr().policy());
+    let counter = match policy {
+        Some(SchedPolicy::Idle) => &cpu_time.idle,
+        _ if is_kernel_interrupted() => &cpu_time.system,
+        Some(SchedPolicy::Fair(nice)) if i8::from(nice) > 0 => &cpu_time.nice,
+        _ => &cpu_time.user,
+    };
+    counter.fetch_add(1, Relaxed);
+}
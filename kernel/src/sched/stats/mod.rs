@@ -1,5 +1,6 @@
 // SPDX-License-Identifier: MPL-2.0
 
+pub mod cpu_time;
 pub mod loadavg;
 mod scheduler_stats;
 
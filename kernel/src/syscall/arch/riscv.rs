@@ -17,6 +17,7 @@ use super::{
     clone::{sys_clone, sys_clone3},
     close::{sys_close, sys_close_range},
     connect::sys_connect,
+    copy_file_range::sys_copy_file_range,
     dup::{sys_dup, sys_dup3},
     epoll::{sys_epoll_create1, sys_epoll_ctl, sys_epoll_pwait, sys_epoll_pwait2},
     eventfd::sys_eventfd2,
@@ -25,6 +26,7 @@ use super::{
     exit_group::sys_exit_group,
     fadvise64::sys_fadvise64,
     fallocate::sys_fallocate,
+    fanotify::{sys_fanotify_init, sys_fanotify_mark},
     fcntl::sys_fcntl,
     flock::sys_flock,
     fsync::{sys_fdatasync, sys_fsync},
@@ -53,6 +55,7 @@ use super::{
     gettimeofday::sys_gettimeofday,
     getuid::sys_getuid,
     impl_syscall_nums_and_dispatch_fn,
+    inotify::{sys_inotify_add_watch, sys_inotify_init1, sys_inotify_rm_watch},
     ioctl::sys_ioctl,
     kill::sys_kill,
     link::sys_linkat,
@@ -65,18 +68,30 @@ use super::{
     mmap::sys_mmap,
     mount::sys_mount,
     mprotect::sys_mprotect,
+    mq_getsetattr::sys_mq_getsetattr,
+    mq_notify::sys_mq_notify,
+    mq_open::sys_mq_open,
+    mq_timedreceive::sys_mq_timedreceive,
+    mq_timedsend::sys_mq_timedsend,
+    mq_unlink::sys_mq_unlink,
     mremap::sys_mremap,
+    msgctl::sys_msgctl,
+    msgget::sys_msgget,
+    msgrcv::sys_msgrcv,
+    msgsnd::sys_msgsnd,
     msync::sys_msync,
     munmap::sys_munmap,
     nanosleep::{sys_clock_nanosleep, sys_nanosleep},
     open::sys_openat,
     pidfd_open::sys_pidfd_open,
     pipe::sys_pipe2,
+    pivot_root::sys_pivot_root,
     prctl::sys_prctl,
     pread64::sys_pread64,
     preadv::{sys_preadv, sys_preadv2, sys_readv},
     prlimit64::{sys_getrlimit, sys_prlimit64, sys_setrlimit},
     pselect6::sys_pselect6,
+    ptrace::sys_ptrace,
     pwrite64::sys_pwrite64,
     pwritev::{sys_pwritev, sys_pwritev2, sys_writev},
     read::sys_read,
@@ -113,6 +128,7 @@ use super::{
     setgid::sys_setgid,
     setgroups::sys_setgroups,
     setitimer::{sys_getitimer, sys_setitimer},
+    setns::sys_setns,
     setpgid::sys_setpgid,
     setregid::sys_setregid,
     setresgid::sys_setresgid,
@@ -121,16 +137,22 @@ use super::{
     setsid::sys_setsid,
     setsockopt::sys_setsockopt,
     setuid::sys_setuid,
+    shmat::sys_shmat,
+    shmctl::sys_shmctl,
+    shmdt::sys_shmdt,
+    shmget::sys_shmget,
     shutdown::sys_shutdown,
     sigaltstack::sys_sigaltstack,
     signalfd::sys_signalfd4,
     socket::sys_socket,
     socketpair::sys_socketpair,
+    splice::{sys_splice, sys_tee, sys_vmsplice},
     stat::{sys_fstat, sys_fstatat},
     statfs::{sys_fstatfs, sys_statfs},
     statx::sys_statx,
+    swapon::{sys_swapoff, sys_swapon},
     symlink::sys_symlinkat,
-    sync::sys_sync,
+    sync::{sys_sync, sys_sync_file_range, sys_syncfs},
     tgkill::sys_tgkill,
     timer_create::{sys_timer_create, sys_timer_delete},
     timer_settime::{sys_timer_gettime, sys_timer_settime},
@@ -140,8 +162,9 @@ use super::{
     truncate::{sys_ftruncate, sys_truncate},
     umask::sys_umask,
     umount::sys_umount,
-    uname::sys_uname,
+    uname::{sys_setdomainname, sys_sethostname, sys_uname},
     unlink::sys_unlinkat,
+    unshare::sys_unshare,
     utimens::sys_utimensat,
     wait4::sys_wait4,
     waitid::sys_waitid,
@@ -157,6 +180,9 @@ impl_syscall_nums_and_dispatch_fn! {
     SYS_DUP = 23                 => sys_dup(args[..1]);
     SYS_DUP3 = 24                => sys_dup3(args[..3]);
     SYS_FCNTL = 25               => sys_fcntl(args[..3]);
+    SYS_INOTIFY_INIT1 = 26       => sys_inotify_init1(args[..1]);
+    SYS_INOTIFY_ADD_WATCH = 27   => sys_inotify_add_watch(args[..3]);
+    SYS_INOTIFY_RM_WATCH = 28    => sys_inotify_rm_watch(args[..2]);
     SYS_IOCTL = 29               => sys_ioctl(args[..3]);
     SYS_IOPRIO_SET = 30          => sys_ioprio_set(args[..3]);
     SYS_IOPRIO_GET = 31          => sys_ioprio_get(args[..2]);
@@ -169,6 +195,7 @@ impl_syscall_nums_and_dispatch_fn! {
     SYS_RENAMEAT = 38            => sys_renameat(args[..4]);
     SYS_UMOUNT = 39              => sys_umount(args[..2]);
     SYS_MOUNT = 40               => sys_mount(args[..5]);
+    SYS_PIVOT_ROOT = 41          => sys_pivot_root(args[..2]);
     SYS_STATFS = 43              => sys_statfs(args[..2]);
     SYS_FSTATFS = 44             => sys_fstatfs(args[..2]);
     SYS_TRUNCATE = 45            => sys_truncate(args[..2]);
@@ -198,19 +225,24 @@ impl_syscall_nums_and_dispatch_fn! {
     SYS_SENDFILE64 = 71          => sys_sendfile(args[..4]);
     SYS_PSELECT6 = 72            => sys_pselect6(args[..6]);
     SYS_SIGNALFD4 = 74           => sys_signalfd4(args[..4]);
+    SYS_VMSPLICE = 75            => sys_vmsplice(args[..4]);
+    SYS_SPLICE = 76              => sys_splice(args[..6]);
+    SYS_TEE = 77                 => sys_tee(args[..4]);
     SYS_READLINKAT = 78          => sys_readlinkat(args[..4]);
     SYS_NEWFSTATAT = 79          => sys_fstatat(args[..4]);
     SYS_NEWFSTAT = 80            => sys_fstat(args[..2]);
     SYS_SYNC = 81                => sys_sync(args[..0]);
     SYS_FSYNC = 82               => sys_fsync(args[..1]);
     SYS_FDATASYNC = 83           => sys_fdatasync(args[..1]);
+    SYS_SYNC_FILE_RANGE = 84     => sys_sync_file_range(args[..4]);
     SYS_TIMERFD_CREATE = 85        => sys_timerfd_create(args[..2]);
     SYS_CAPGET = 90              => sys_capget(args[..2]);
     SYS_CAPSET = 91              => sys_capset(args[..2]);
-    SYS_EXIT = 93                => sys_exit(args[..1]);
-    SYS_EXIT_GROUP = 94          => sys_exit_group(args[..1]);
+    SYS_EXIT = 93                => sys_exit(args[..1], &mut user_ctx);
+    SYS_EXIT_GROUP = 94          => sys_exit_group(args[..1], &mut user_ctx);
     SYS_WAITID = 95              => sys_waitid(args[..5]);
     SYS_SET_TID_ADDRESS = 96     => sys_set_tid_address(args[..1]);
+    SYS_UNSHARE = 97             => sys_unshare(args[..1]);
     SYS_FUTEX = 98               => sys_futex(args[..6]);
     SYS_SET_ROBUST_LIST = 99     => sys_set_robust_list(args[..2]);
     SYS_NANOSLEEP = 101          => sys_nanosleep(args[..2]);
@@ -218,6 +250,7 @@ impl_syscall_nums_and_dispatch_fn! {
     SYS_SETITIMER = 103          => sys_setitimer(args[..3]);
     SYS_TIMER_CREATE = 107       => sys_timer_create(args[..3]);
     SYS_TIMER_DELETE = 111       => sys_timer_delete(args[..1]);
+    SYS_PTRACE = 117             => sys_ptrace(args[..4]);
     SYS_SCHED_SETPARAM = 118     => sys_sched_setparam(args[..2]);
     SYS_SCHED_SETSCHEDULER = 119 => sys_sched_setscheduler(args[..3]);
     SYS_SCHED_GETSCHEDULER = 120 => sys_sched_getscheduler(args[..1]);
@@ -253,6 +286,8 @@ impl_syscall_nums_and_dispatch_fn! {
     SYS_GETGROUPS = 158          => sys_getgroups(args[..2]);
     SYS_SETGROUPS = 159          => sys_setgroups(args[..2]);
     SYS_NEWUNAME = 160           => sys_uname(args[..1]);
+    SYS_SETHOSTNAME = 161        => sys_sethostname(args[..2]);
+    SYS_SETDOMAINNAME = 162      => sys_setdomainname(args[..2]);
     SYS_GETRLIMIT = 163          => sys_getrlimit(args[..2]);
     SYS_SETRLIMIT = 164          => sys_setrlimit(args[..2]);
     SYS_GETRUSAGE = 165          => sys_getrusage(args[..2]);
@@ -267,9 +302,23 @@ impl_syscall_nums_and_dispatch_fn! {
     SYS_GETGID = 176             => sys_getgid(args[..0]);
     SYS_GETEGID = 177            => sys_getegid(args[..0]);
     SYS_GETTID = 178             => sys_gettid(args[..0]);
+    SYS_MQ_OPEN = 180            => sys_mq_open(args[..4]);
+    SYS_MQ_UNLINK = 181          => sys_mq_unlink(args[..1]);
+    SYS_MQ_TIMEDSEND = 182       => sys_mq_timedsend(args[..5]);
+    SYS_MQ_TIMEDRECEIVE = 183    => sys_mq_timedreceive(args[..5]);
+    SYS_MQ_NOTIFY = 184          => sys_mq_notify(args[..2]);
+    SYS_MQ_GETSETATTR = 185      => sys_mq_getsetattr(args[..3]);
+    SYS_MSGGET = 186             => sys_msgget(args[..2]);
+    SYS_MSGCTL = 187             => sys_msgctl(args[..3]);
+    SYS_MSGRCV = 188             => sys_msgrcv(args[..5]);
+    SYS_MSGSND = 189             => sys_msgsnd(args[..4]);
     SYS_SEMGET = 190             => sys_semget(args[..3]);
     SYS_SEMCTL = 191             => sys_semctl(args[..4]);
     SYS_SEMOP = 193              => sys_semop(args[..3]);
+    SYS_SHMGET = 194             => sys_shmget(args[..3]);
+    SYS_SHMCTL = 195             => sys_shmctl(args[..3]);
+    SYS_SHMAT = 196              => sys_shmat(args[..3]);
+    SYS_SHMDT = 197              => sys_shmdt(args[..1]);
     SYS_SOCKET = 198             => sys_socket(args[..3]);
     SYS_SOCKETPAIR = 199         => sys_socketpair(args[..4]);
     SYS_BIND = 200               => sys_bind(args[..3]);
@@ -288,21 +337,28 @@ impl_syscall_nums_and_dispatch_fn! {
     SYS_BRK = 214                => sys_brk(args[..1]);
     SYS_MUNMAP = 215             => sys_munmap(args[..2]);
     SYS_MREMAP = 216           => sys_mremap(args[..5]);
-    SYS_CLONE = 220              => sys_clone(args[..5], &user_ctx);
+    SYS_CLONE = 220              => sys_clone(args[..5], &mut user_ctx);
     SYS_EXECVE = 221             => sys_execve(args[..3], &mut user_ctx);
     SYS_MMAP = 222               => sys_mmap(args[..6]);
     SYS_FADVISE64 = 223          => sys_fadvise64(args[..4]);
+    SYS_SWAPON = 224             => sys_swapon(args[..2]);
+    SYS_SWAPOFF = 225            => sys_swapoff(args[..1]);
     SYS_MPROTECT = 226           => sys_mprotect(args[..3]);
     SYS_MSYNC = 227              => sys_msync(args[..3]);
     SYS_MADVISE = 233            => sys_madvise(args[..3]);
     SYS_ACCEPT4 = 242            => sys_accept4(args[..4]);
     SYS_WAIT4 = 260              => sys_wait4(args[..4]);
     SYS_PRLIMIT64 = 261          => sys_prlimit64(args[..4]);
+    SYS_FANOTIFY_INIT = 262      => sys_fanotify_init(args[..2]);
+    SYS_FANOTIFY_MARK = 263      => sys_fanotify_mark(args[..5]);
+    SYS_SYNCFS = 267             => sys_syncfs(args[..1]);
+    SYS_SETNS = 268              => sys_setns(args[..2]);
     SYS_SCHED_SETATTR = 274      => sys_sched_setattr(args[..3]);
     SYS_SCHED_GETATTR = 275      => sys_sched_getattr(args[..4]);
     SYS_GETRANDOM = 278          => sys_getrandom(args[..3]);
     SYS_MEMFD_CREATE = 279     => sys_memfd_create(args[..2]);
     SYS_EXECVEAT = 281           => sys_execveat(args[..5], &mut user_ctx);
+    SYS_COPY_FILE_RANGE = 285    => sys_copy_file_range(args[..6]);
     SYS_PREADV2 = 286            => sys_preadv2(args[..5]);
     SYS_PWRITEV2 = 287           => sys_pwritev2(args[..5]);
     SYS_STATX = 291              => sys_statx(args[..5]);
@@ -315,7 +371,7 @@ impl_syscall_nums_and_dispatch_fn! {
     SYS_UTIMENSAT = 412          => sys_utimensat(args[..4]);
     SYS_SEMTIMEDOP = 420         => sys_semtimedop(args[..4]);
     SYS_PIDFD_OPEN = 434         => sys_pidfd_open(args[..2]);
-    SYS_CLONE3 = 435             => sys_clone3(args[..2], &user_ctx);
+    SYS_CLONE3 = 435             => sys_clone3(args[..2], &mut user_ctx);
     SYS_CLOSE_RANGE = 436      => sys_close_range(args[..3]);
     SYS_FACCESSAT2 = 439         => sys_faccessat2(args[..4]);
     SYS_EPOLL_PWAIT2 = 441       => sys_epoll_pwait2(args[..5]);
@@ -19,11 +19,11 @@ pub fn sys_clone(
     child_tidptr: Vaddr,
     tls: u64,
     ctx: &Context,
-    parent_context: &UserContext,
+    parent_context: &mut UserContext,
 ) -> Result<SyscallReturn> {
     let args = CloneArgs::for_clone(clone_flags, parent_tidptr, child_tidptr, tls, new_sp)?;
     debug!("flags = {:?}, child_stack_ptr = 0x{:x}, parent_tid_ptr = 0x{:x?}, child tid ptr = 0x{:x}, tls = 0x{:x}", args.flags, args.stack, args.parent_tid, args.child_tid, args.tls);
-    let child_pid = clone_child(ctx, parent_context, args).unwrap();
+    let child_pid = clone_child(ctx, parent_context, args)?;
     Ok(SyscallReturn::Return(child_pid as _))
 }
 
@@ -31,7 +31,7 @@ pub fn sys_clone3(
     clong_args_addr: Vaddr,
     size: usize,
     ctx: &Context,
-    parent_context: &UserContext,
+    parent_context: &mut UserContext,
 ) -> Result<SyscallReturn> {
     trace!(
         "clone args addr = 0x{:x}, size = 0x{:x}",
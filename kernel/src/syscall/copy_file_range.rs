@@ -0,0 +1,182 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::{splice::read_offset, SyscallReturn};
+use crate::{
+    fs::{
+        file_handle::FileLike,
+        file_table::{FileDesc, WithFileTable},
+        inode_handle::InodeHandle,
+        utils::{InodeType, SeekFrom, StatusFlags},
+    },
+    prelude::*,
+};
+
+pub fn sys_copy_file_range(
+    fd_in: FileDesc,
+    off_in_ptr: Vaddr,
+    fd_out: FileDesc,
+    off_out_ptr: Vaddr,
+    len: usize,
+    flags: u32,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    debug!(
+        "fd_in = {}, off_in_ptr = 0x{:x}, fd_out = {}, off_out_ptr = 0x{:x}, len = 0x{:x}, flags = {}",
+        fd_in, off_in_ptr, fd_out, off_out_ptr, len, flags
+    );
+
+    if flags != 0 {
+        return_errno_with_message!(Errno::EINVAL, "the flags must be zero");
+    }
+
+    let (file_in, file_out) = ctx
+        .thread_local
+        .borrow_file_table_mut()
+        .read_with(|inner| {
+            let file_in = inner.get_file(fd_in)?.clone();
+            let file_out = inner.get_file(fd_out)?.clone();
+            Ok::<_, Error>((file_in, file_out))
+        })?;
+    let handle_in = as_regular_file(&file_in)?;
+    let handle_out = as_regular_file(&file_out)?;
+    if !file_in.access_mode().is_readable() {
+        return_errno_with_message!(Errno::EBADF, "the input file is not readable");
+    }
+    if !file_out.access_mode().is_writable() {
+        return_errno_with_message!(Errno::EBADF, "the output file is not writable");
+    }
+    if file_out.status_flags().contains(StatusFlags::O_APPEND) {
+        return_errno_with_message!(Errno::EBADF, "the output file is append-only");
+    }
+
+    let mut offset_in = read_offset(off_in_ptr, ctx)?.unwrap_or_else(|| handle_in.offset());
+    let mut offset_out = read_offset(off_out_ptr, ctx)?.unwrap_or_else(|| handle_out.offset());
+
+    // The range to copy is limited by the size of the input file.
+    let len = len.min(handle_in.path().size().saturating_sub(offset_in));
+    if offset_out
+        .checked_add(len)
+        .is_none_or(|end| end > isize::MAX as usize)
+    {
+        return_errno_with_message!(Errno::EFBIG, "the range to write is too large");
+    }
+
+    if Arc::ptr_eq(handle_in.path().inode(), handle_out.path().inode())
+        && offset_in < offset_out + len
+        && offset_out < offset_in + len
+    {
+        return_errno_with_message!(Errno::EINVAL, "the ranges in the same file overlap");
+    }
+
+    let copied_len = copy_range(handle_in, &mut offset_in, handle_out, &mut offset_out, len)?;
+
+    if off_in_ptr == 0 {
+        handle_in.seek(SeekFrom::Start(offset_in))?;
+    } else {
+        ctx.user_space()
+            .write_val(off_in_ptr, &(offset_in as i64))?;
+    }
+    if off_out_ptr == 0 {
+        handle_out.seek(SeekFrom::Start(offset_out))?;
+    } else {
+        ctx.user_space()
+            .write_val(off_out_ptr, &(offset_out as i64))?;
+    }
+
+    Ok(SyscallReturn::Return(copied_len as _))
+}
+
+/// Copies `len` bytes from `handle_in` to `handle_out` and advances the offsets.
+///
+/// If the input file is backed by the page cache, the data are copied from the pages in the page
+/// cache directly to the output file. Otherwise, the data are copied through a buffer.
+fn copy_range(
+    handle_in: &InodeHandle,
+    offset_in: &mut usize,
+    handle_out: &InodeHandle,
+    offset_out: &mut usize,
+    len: usize,
+) -> Result<usize> {
+    // The maximum number of pages to copy in one round.
+    const MAX_PAGES: usize = 16;
+
+    let mut buffer = None;
+    let mut copied_len = 0;
+
+    while copied_len < len {
+        let max_len = len - copied_len;
+
+        let result = match handle_in.read_pages_at(*offset_in, max_len, MAX_PAGES) {
+            Ok(Some(pages)) => {
+                let mut round_len = 0;
+                let mut result = Ok(());
+                for (page, range) in pages {
+                    let mut reader = page.reader();
+                    reader.skip(range.start).limit(range.len());
+                    match handle_out.write_at(*offset_out, &mut reader.to_fallible()) {
+                        Ok(written_len) => {
+                            round_len += written_len;
+                            *offset_in += written_len;
+                            *offset_out += written_len;
+                            if written_len < range.len() {
+                                break;
+                            }
+                        }
+                        Err(err) => {
+                            result = Err(err);
+                            break;
+                        }
+                    }
+                }
+                result.map(|_| round_len)
+            }
+            Ok(None) => {
+                let buffer = buffer.get_or_insert_with(|| vec![0u8; PAGE_SIZE].into_boxed_slice());
+                copy_with_buffer(
+                    handle_in, offset_in, handle_out, offset_out, max_len, buffer,
+                )
+            }
+            Err(err) => Err(err),
+        };
+
+        match result {
+            Ok(0) => break,
+            Ok(round_len) => copied_len += round_len,
+            Err(_) if copied_len > 0 => break,
+            Err(err) => return Err(err),
+        }
+    }
+
+    Ok(copied_len)
+}
+
+fn copy_with_buffer(
+    handle_in: &InodeHandle,
+    offset_in: &mut usize,
+    handle_out: &InodeHandle,
+    offset_out: &mut usize,
+    max_len: usize,
+    buffer: &mut [u8],
+) -> Result<usize> {
+    let max_len = max_len.min(buffer.len());
+    let mut writer = VmWriter::from(&mut buffer[..max_len]).to_fallible();
+    let read_len = handle_in.read_at(*offset_in, &mut writer)?;
+    let mut reader = VmReader::from(&buffer[..read_len]).to_fallible();
+    let written_len = handle_out.write_at(*offset_out, &mut reader)?;
+
+    *offset_in += written_len;
+    *offset_out += written_len;
+    Ok(written_len)
+}
+
+fn as_regular_file(file: &Arc<dyn FileLike>) -> Result<&InodeHandle> {
+    let Some(inode_handle) = file.downcast_ref::<InodeHandle>() else {
+        return_errno_with_message!(Errno::EINVAL, "the file is not a regular file");
+    };
+
+    match inode_handle.path().type_() {
+        InodeType::File => Ok(inode_handle),
+        InodeType::Dir => return_errno_with_message!(Errno::EISDIR, "the file is a directory"),
+        _ => return_errno_with_message!(Errno::EINVAL, "the file is not a regular file"),
+    }
+}
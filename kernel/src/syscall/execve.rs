@@ -15,8 +15,8 @@ use crate::{
     },
     prelude::*,
     process::{
-        check_executable_file, posix_thread::ThreadName, renew_vm_and_map, Credentials, Process,
-        ProgramToLoad, MAX_LEN_STRING_ARG, MAX_NR_STRING_ARGS,
+        check_executable_file, posix_thread::ThreadName, ptrace::stop_on_exec, renew_vm_and_map,
+        Credentials, Process, ProgramToLoad, MAX_LEN_STRING_ARG, MAX_NR_STRING_ARGS,
     },
 };
 
@@ -167,6 +167,9 @@ fn do_execve(
     // set new user stack top
     user_context.set_stack_pointer(elf_load_info.user_stack_top as _);
     debug!("user stack top: 0x{:x}", elf_load_info.user_stack_top);
+
+    // Notify the tracer after the new program is loaded.
+    stop_on_exec(ctx, user_context);
     Ok(())
 }
 
@@ -1,15 +1,22 @@
 // SPDX-License-Identifier: MPL-2.0
 
+use ostd::cpu::context::UserContext;
+
 use crate::{
     prelude::*,
-    process::{posix_thread::do_exit, TermStatus},
+    process::{posix_thread::do_exit, ptrace::stop_on_exit, TermStatus},
     syscall::SyscallReturn,
 };
 
-pub fn sys_exit(exit_code: i32, _ctx: &Context) -> Result<SyscallReturn> {
+pub fn sys_exit(
+    exit_code: i32,
+    ctx: &Context,
+    user_ctx: &mut UserContext,
+) -> Result<SyscallReturn> {
     debug!("exid code = {}", exit_code);
 
     let term_status = TermStatus::Exited(exit_code as _);
+    stop_on_exit(ctx, user_ctx, term_status);
     do_exit(term_status);
 
     Ok(SyscallReturn::Return(0))
@@ -1,15 +1,22 @@
 // SPDX-License-Identifier: MPL-2.0
 
+use ostd::cpu::context::UserContext;
+
 use crate::{
     prelude::*,
-    process::{posix_thread::do_exit_group, TermStatus},
+    process::{posix_thread::do_exit_group, ptrace::stop_on_exit, TermStatus},
     syscall::SyscallReturn,
 };
 
 /// Exit all thread in a process.
-pub fn sys_exit_group(exit_code: u64, _ctx: &Context) -> Result<SyscallReturn> {
+pub fn sys_exit_group(
+    exit_code: u64,
+    ctx: &Context,
+    user_ctx: &mut UserContext,
+) -> Result<SyscallReturn> {
     // Exit all thread in current process
     let term_status = TermStatus::Exited(exit_code as _);
+    stop_on_exit(ctx, user_ctx, term_status);
     do_exit_group(term_status);
     Ok(SyscallReturn::Return(0))
 }
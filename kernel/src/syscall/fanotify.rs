@@ -0,0 +1,135 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::SyscallReturn;
+use crate::{
+    fs::{
+        file_table::{get_file_fast, FdFlags, FileDesc},
+        fs_resolver::FsPath,
+        notify::{FanotifyFile, FanotifyInitFlags, FanotifyMarkFlags, FanotifyTarget, FsEvents},
+        utils::{InodeType, Permission},
+    },
+    prelude::*,
+    process::credentials::capabilities::CapSet,
+    syscall::constants::MAX_FILENAME_LEN,
+};
+
+pub fn sys_fanotify_init(flags: u32, event_f_flags: u32, ctx: &Context) -> Result<SyscallReturn> {
+    let flags = FanotifyInitFlags::from_bits(flags)
+        .ok_or_else(|| Error::with_message(Errno::EINVAL, "unknown flags"))?;
+    debug!("flags = {:?}, event_f_flags = {:#x}", flags, event_f_flags);
+
+    if !ctx
+        .posix_thread
+        .credentials()
+        .effective_capset()
+        .contains(CapSet::SYS_ADMIN)
+    {
+        return_errno_with_message!(
+            Errno::EPERM,
+            "creating fanotify instances requires CAP_SYS_ADMIN"
+        );
+    }
+
+    // `O_LARGEFILE` is implied on 64-bit architectures.
+    let event_f_flags = event_f_flags & !O_LARGEFILE;
+    let fanotify_file = FanotifyFile::new(flags, event_f_flags)?;
+    let fd_flags = if flags.contains(FanotifyInitFlags::FAN_CLOEXEC) {
+        FdFlags::CLOEXEC
+    } else {
+        FdFlags::empty()
+    };
+    let file_table = ctx.thread_local.borrow_file_table();
+    let fd = file_table.unwrap().write().insert(fanotify_file, fd_flags);
+
+    Ok(SyscallReturn::Return(fd as _))
+}
+
+pub fn sys_fanotify_mark(
+    fd: FileDesc,
+    flags: u32,
+    mask: u64,
+    dirfd: FileDesc,
+    path_addr: Vaddr,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    let flags = FanotifyMarkFlags::from_bits(flags)
+        .ok_or_else(|| Error::with_message(Errno::EINVAL, "unknown flags"))?;
+    let events = u32::try_from(mask)
+        .ok()
+        .and_then(FsEvents::from_bits)
+        .ok_or_else(|| Error::with_message(Errno::EINVAL, "unknown events"))?;
+    debug!(
+        "fd = {}, flags = {:?}, events = {:?}, dirfd = {}, path_addr = 0x{:x}",
+        fd, flags, events, dirfd, path_addr
+    );
+
+    let action = flags
+        & (FanotifyMarkFlags::FAN_MARK_ADD
+            | FanotifyMarkFlags::FAN_MARK_REMOVE
+            | FanotifyMarkFlags::FAN_MARK_FLUSH);
+    if action.bits().count_ones() != 1 {
+        return_errno_with_message!(
+            Errno::EINVAL,
+            "exactly one of FAN_MARK_ADD, FAN_MARK_REMOVE and FAN_MARK_FLUSH should be specified"
+        );
+    }
+
+    let mut file_table = ctx.thread_local.borrow_file_table_mut();
+    let file = get_file_fast!(&mut file_table, fd);
+    let fanotify_file = file
+        .downcast_ref::<FanotifyFile>()
+        .ok_or_else(|| Error::with_message(Errno::EINVAL, "the file is not a fanotify file"))?;
+
+    let is_mount = flags.contains(FanotifyMarkFlags::FAN_MARK_MOUNT);
+    if action == FanotifyMarkFlags::FAN_MARK_FLUSH {
+        if !(flags - FanotifyMarkFlags::FAN_MARK_FLUSH - FanotifyMarkFlags::FAN_MARK_MOUNT)
+            .is_empty()
+        {
+            return_errno_with_message!(Errno::EINVAL, "invalid flags for FAN_MARK_FLUSH");
+        }
+        fanotify_file.flush_marks(is_mount);
+        return Ok(SyscallReturn::Return(0));
+    }
+
+    let path = {
+        // A null path means the file of `dirfd`.
+        let path_name = if path_addr == 0 {
+            CString::default()
+        } else {
+            let path_name = ctx.user_space().read_cstring(path_addr, MAX_FILENAME_LEN)?;
+            if path_name.is_empty() {
+                return_errno_with_message!(Errno::ENOENT, "the path is empty");
+            }
+            path_name
+        };
+        let path_name = path_name.to_string_lossy();
+        let fs_path = FsPath::new(dirfd, path_name.as_ref())?;
+        let fs = ctx.thread_local.borrow_fs();
+        let resolver = fs.resolver().read();
+        if flags.contains(FanotifyMarkFlags::FAN_MARK_DONT_FOLLOW) {
+            resolver.lookup_no_follow(&fs_path)?
+        } else {
+            resolver.lookup(&fs_path)?
+        }
+    };
+    if flags.contains(FanotifyMarkFlags::FAN_MARK_ONLYDIR) && path.type_() != InodeType::Dir {
+        return_errno_with_message!(Errno::ENOTDIR, "the path is not a directory");
+    }
+    // Marking a file requires the permission to read it.
+    path.inode().check_permission(Permission::MAY_READ)?;
+
+    let target = if is_mount {
+        FanotifyTarget::Mount(path.mount_node().clone())
+    } else {
+        FanotifyTarget::Inode(path.inode().clone())
+    };
+    if action == FanotifyMarkFlags::FAN_MARK_ADD {
+        fanotify_file.add_mark(target, events)?;
+    } else {
+        fanotify_file.remove_mark(&target, events)?;
+    }
+
+    Ok(SyscallReturn::Return(0))
+}
+
+const O_LARGEFILE: u32 = 0o100000;
@@ -4,7 +4,9 @@ use super::SyscallReturn;
 use crate::{prelude::*, process::Gid};
 
 pub fn sys_getegid(ctx: &Context) -> Result<SyscallReturn> {
-    let egid = ctx.posix_thread.credentials().egid();
+    let ns_proxy = ctx.thread_local.borrow_ns_proxy();
+    let user_ns = ns_proxy.unwrap().user_ns();
+    let egid = user_ns.map_gid_to_ns(ctx.posix_thread.credentials().egid());
 
     Ok(SyscallReturn::Return(<Gid as Into<u32>>::into(egid) as _))
 }
@@ -4,7 +4,9 @@ use super::SyscallReturn;
 use crate::{prelude::*, process::Uid};
 
 pub fn sys_geteuid(ctx: &Context) -> Result<SyscallReturn> {
-    let euid = ctx.posix_thread.credentials().euid();
+    let ns_proxy = ctx.thread_local.borrow_ns_proxy();
+    let user_ns = ns_proxy.unwrap().user_ns();
+    let euid = user_ns.map_uid_to_ns(ctx.posix_thread.credentials().euid());
 
     Ok(SyscallReturn::Return(<Uid as Into<u32>>::into(euid) as _))
 }
@@ -14,18 +14,27 @@ pub fn sys_getpgid(pid: Pid, ctx: &Context) -> Result<SyscallReturn> {
 
     // "If `pid` is equal to 0, getpgid() shall return the process group ID of the calling
     // process."
+    let pid_ns = ctx.process.pid_ns();
     if pid == 0 {
-        return Ok(SyscallReturn::Return(ctx.process.pgid() as _));
+        return Ok(SyscallReturn::Return(
+            pid_ns.local_id(ctx.process.pgid()).unwrap_or(0) as _,
+        ));
     }
 
-    let process = process_table::get_process(pid).ok_or(Error::with_message(
-        Errno::ESRCH,
-        "the process to get the PGID does not exist",
-    ))?;
+    let process = pid_ns
+        .global_id(pid)
+        .and_then(process_table::get_process)
+        .ok_or(Error::with_message(
+            Errno::ESRCH,
+            "the process to get the PGID does not exist",
+        ))?;
 
     // The man pages allow the implementation to return `EPERM` if `process` is in a different
     // session than the current process. Linux does not perform this check by default, but some
     // strict security policies (e.g. SELinux) may do so.
 
-    Ok(SyscallReturn::Return(process.pgid() as _))
+    // The PGID is zero if it is outside the PID namespace.
+    Ok(SyscallReturn::Return(
+        pid_ns.local_id(process.pgid()).unwrap_or(0) as _,
+    ))
 }
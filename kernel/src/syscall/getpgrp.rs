@@ -4,5 +4,12 @@ use super::SyscallReturn;
 use crate::prelude::*;
 
 pub fn sys_getpgrp(ctx: &Context) -> Result<SyscallReturn> {
-    Ok(SyscallReturn::Return(ctx.process.pgid() as _))
+    // The process group is invisible if its leader is outside the PID namespace, in which case
+    // zero is returned.
+    let pgid = ctx
+        .process
+        .pid_ns()
+        .local_id(ctx.process.pgid())
+        .unwrap_or(0);
+    Ok(SyscallReturn::Return(pgid as _))
 }
@@ -4,5 +4,12 @@ use super::SyscallReturn;
 use crate::prelude::*;
 
 pub fn sys_getppid(ctx: &Context) -> Result<SyscallReturn> {
-    Ok(SyscallReturn::Return(ctx.process.parent().pid() as _))
+    // The parent is invisible if it is outside the PID namespace (e.g., for the init process of
+    // the namespace), in which case zero is returned.
+    let ppid = ctx
+        .process
+        .pid_ns()
+        .local_id(ctx.process.parent().pid())
+        .unwrap_or(0);
+    Ok(SyscallReturn::Return(ppid as _))
 }
@@ -13,14 +13,16 @@ pub fn sys_getresgid(
 
     let credentials = ctx.posix_thread.credentials();
     let user_space = ctx.user_space();
+    let ns_proxy = ctx.thread_local.borrow_ns_proxy();
+    let user_ns = ns_proxy.unwrap().user_ns();
 
-    let rgid = credentials.rgid();
+    let rgid = user_ns.map_gid_to_ns(credentials.rgid());
     user_space.write_val(rgid_ptr, &rgid)?;
 
-    let egid = credentials.egid();
+    let egid = user_ns.map_gid_to_ns(credentials.egid());
     user_space.write_val(egid_ptr, &egid)?;
 
-    let sgid = credentials.sgid();
+    let sgid = user_ns.map_gid_to_ns(credentials.sgid());
     user_space.write_val(sgid_ptr, &sgid)?;
 
     Ok(SyscallReturn::Return(0))
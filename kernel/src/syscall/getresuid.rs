@@ -13,14 +13,16 @@ pub fn sys_getresuid(
 
     let credentials = ctx.posix_thread.credentials();
     let user_space = ctx.user_space();
+    let ns_proxy = ctx.thread_local.borrow_ns_proxy();
+    let user_ns = ns_proxy.unwrap().user_ns();
 
-    let ruid = credentials.ruid();
+    let ruid = user_ns.map_uid_to_ns(credentials.ruid());
     user_space.write_val(ruid_ptr, &ruid)?;
 
-    let euid = credentials.euid();
+    let euid = user_ns.map_uid_to_ns(credentials.euid());
     user_space.write_val(euid_ptr, &euid)?;
 
-    let suid = credentials.suid();
+    let suid = user_ns.map_uid_to_ns(credentials.suid());
     user_space.write_val(suid_ptr, &suid)?;
 
     Ok(SyscallReturn::Return(0))
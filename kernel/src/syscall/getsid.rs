@@ -13,18 +13,27 @@ pub fn sys_getsid(pid: Pid, ctx: &Context) -> Result<SyscallReturn> {
     // <https://www.man7.org/linux/man-pages/man2/getsid.2.html>.
 
     // "If `pid` is 0, getsid() returns the session ID of the calling process."
+    let pid_ns = ctx.process.pid_ns();
     if pid == 0 {
-        return Ok(SyscallReturn::Return(ctx.process.sid() as _));
+        return Ok(SyscallReturn::Return(
+            pid_ns.local_id(ctx.process.sid()).unwrap_or(0) as _,
+        ));
     }
 
-    let process = process_table::get_process(pid).ok_or(Error::with_message(
-        Errno::ESRCH,
-        "the process to get the SID does not exist",
-    ))?;
+    let process = pid_ns
+        .global_id(pid)
+        .and_then(process_table::get_process)
+        .ok_or(Error::with_message(
+            Errno::ESRCH,
+            "the process to get the SID does not exist",
+        ))?;
 
     // The man pages allow the implementation to return `EPERM` if `process` is in a different
     // session than the current process. Linux does not perform this check by default, but some
     // strict security policies (e.g. SELinux) may do so.
 
-    Ok(SyscallReturn::Return(process.sid() as _))
+    // The SID is zero if it is outside the PID namespace.
+    Ok(SyscallReturn::Return(
+        pid_ns.local_id(process.sid()).unwrap_or(0) as _,
+    ))
 }
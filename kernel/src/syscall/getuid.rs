@@ -4,7 +4,9 @@ use super::SyscallReturn;
 use crate::{prelude::*, process::Uid};
 
 pub fn sys_getuid(ctx: &Context) -> Result<SyscallReturn> {
-    let uid = ctx.posix_thread.credentials().ruid();
+    let ns_proxy = ctx.thread_local.borrow_ns_proxy();
+    let user_ns = ns_proxy.unwrap().user_ns();
+    let uid = user_ns.map_uid_to_ns(ctx.posix_thread.credentials().ruid());
 
     Ok(SyscallReturn::Return(<Uid as Into<u32>>::into(uid) as _))
 }
@@ -0,0 +1,97 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::SyscallReturn;
+use crate::{
+    fs::{
+        file_table::{get_file_fast, FdFlags, FileDesc},
+        fs_resolver::FsPath,
+        notify::{FsEvents, InotifyFile, WatchFlags},
+        utils::{CreationFlags, InodeType, Permission, StatusFlags},
+    },
+    prelude::*,
+    syscall::constants::MAX_FILENAME_LEN,
+};
+
+pub fn sys_inotify_init(ctx: &Context) -> Result<SyscallReturn> {
+    sys_inotify_init1(0, ctx)
+}
+
+pub fn sys_inotify_init1(flags: u32, ctx: &Context) -> Result<SyscallReturn> {
+    let flags = Flags::from_bits(flags)
+        .ok_or_else(|| Error::with_message(Errno::EINVAL, "unknown flags"))?;
+    debug!("flags = {:?}", flags);
+
+    let inotify_file = InotifyFile::new(flags.contains(Flags::IN_NONBLOCK));
+    let fd_flags = if flags.contains(Flags::IN_CLOEXEC) {
+        FdFlags::CLOEXEC
+    } else {
+        FdFlags::empty()
+    };
+    let file_table = ctx.thread_local.borrow_file_table();
+    let fd = file_table.unwrap().write().insert(inotify_file, fd_flags);
+
+    Ok(SyscallReturn::Return(fd as _))
+}
+
+pub fn sys_inotify_add_watch(
+    fd: FileDesc,
+    path_addr: Vaddr,
+    mask: u32,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    let path_name = ctx.user_space().read_cstring(path_addr, MAX_FILENAME_LEN)?;
+    let events = FsEvents::from_bits_truncate(mask);
+    let flags = WatchFlags::from_bits_truncate(mask);
+    debug!(
+        "fd = {}, path = {:?}, events = {:?}, flags = {:?}",
+        fd, path_name, events, flags
+    );
+
+    let mut file_table = ctx.thread_local.borrow_file_table_mut();
+    let file = get_file_fast!(&mut file_table, fd);
+    let inotify_file = file
+        .downcast_ref::<InotifyFile>()
+        .ok_or_else(|| Error::with_message(Errno::EINVAL, "the file is not an inotify file"))?;
+
+    let path = {
+        let path_name = path_name.to_string_lossy();
+        let fs_path = FsPath::try_from(path_name.as_ref())?;
+        let fs = ctx.thread_local.borrow_fs();
+        let resolver = fs.resolver().read();
+        if flags.contains(WatchFlags::IN_DONT_FOLLOW) {
+            resolver.lookup_no_follow(&fs_path)?
+        } else {
+            resolver.lookup(&fs_path)?
+        }
+    };
+    if flags.contains(WatchFlags::IN_ONLYDIR) && path.type_() != InodeType::Dir {
+        return_errno_with_message!(Errno::ENOTDIR, "the path is not a directory");
+    }
+    // Watching a file requires the permission to read it.
+    path.inode().check_permission(Permission::MAY_READ)?;
+
+    let wd = inotify_file.add_watch(path.inode(), events, flags)?;
+
+    Ok(SyscallReturn::Return(wd as _))
+}
+
+pub fn sys_inotify_rm_watch(fd: FileDesc, wd: i32, ctx: &Context) -> Result<SyscallReturn> {
+    debug!("fd = {}, wd = {}", fd, wd);
+
+    let mut file_table = ctx.thread_local.borrow_file_table_mut();
+    let file = get_file_fast!(&mut file_table, fd);
+    let inotify_file = file
+        .downcast_ref::<InotifyFile>()
+        .ok_or_else(|| Error::with_message(Errno::EINVAL, "the file is not an inotify file"))?;
+
+    inotify_file.remove_watch(wd)?;
+
+    Ok(SyscallReturn::Return(0))
+}
+
+bitflags! {
+    struct Flags: u32 {
+        const IN_CLOEXEC = CreationFlags::O_CLOEXEC.bits();
+        const IN_NONBLOCK = StatusFlags::O_NONBLOCK.bits();
+    }
+}
@@ -14,7 +14,7 @@ use crate::{
 };
 
 pub fn sys_kill(process_filter: u64, sig_num: u64, ctx: &Context) -> Result<SyscallReturn> {
-    let process_filter = ProcessFilter::from_id(process_filter as _);
+    let process_filter = ProcessFilter::from_id(process_filter as _, ctx, Errno::ESRCH)?;
     let sig_num = if sig_num == 0 {
         None
     } else {
@@ -7,9 +7,17 @@ use aster_rights::Rights;
 
 use super::SyscallReturn;
 use crate::{
-    fs::file_table::{get_file_fast, FileDesc},
+    fs::{
+        file_table::{get_file_fast, FileDesc},
+        inode_handle::InodeHandle,
+    },
     prelude::*,
-    vm::{perms::VmPerms, vmar::is_userspace_vaddr, vmo::VmoOptions},
+    vm::{
+        self,
+        perms::VmPerms,
+        vmar::{is_userspace_vaddr, vm_mapping::VmMappingName},
+        vmo::VmoOptions,
+    },
 };
 
 pub fn sys_mmap(
@@ -84,6 +92,17 @@ fn do_sys_mmap(
         vm_perms
     };
 
+    // Like Linux, the memory is committed for the private writable mappings and the shared
+    // anonymous mappings, which cannot be backed by the files.
+    let is_accountable = if option.typ() == MMapType::Private {
+        vm_perms.contains(VmPerms::WRITE)
+    } else {
+        option.flags.contains(MMapFlags::MAP_ANONYMOUS)
+    };
+    if is_accountable {
+        vm::check_overcommit(len, option.flags.contains(MMapFlags::MAP_NORESERVE))?;
+    }
+
     let user_space = ctx.user_space();
     let root_vmar = user_space.root_vmar();
     let vm_map_options = {
@@ -142,6 +161,9 @@ fn do_sys_mmap(
                 .inode(inode.clone())
                 .vmo_offset(offset)
                 .handle_page_faults_around();
+            if let Some(inode_handle) = file.downcast_ref::<InodeHandle>() {
+                options = options.name(VmMappingName::File(inode_handle.path().clone()));
+            }
         }
 
         options
@@ -6,7 +6,13 @@ pub use clock_gettime::ClockId;
 use ostd::cpu::context::UserContext;
 pub use timer_create::create_timer;
 
-use crate::{context::Context, cpu::LinuxAbi, prelude::*};
+use crate::{
+    context::Context,
+    cpu::LinuxAbi,
+    fs::notify,
+    prelude::*,
+    process::ptrace::{stop_on_syscall_entry, stop_on_syscall_exit, SyscallEntryAction},
+};
 
 mod accept;
 mod access;
@@ -34,6 +40,7 @@ mod clone;
 mod close;
 mod connect;
 mod constants;
+mod copy_file_range;
 mod dup;
 mod epoll;
 mod eventfd;
@@ -42,6 +49,7 @@ mod exit;
 mod exit_group;
 mod fadvise64;
 mod fallocate;
+mod fanotify;
 mod fcntl;
 mod flock;
 mod fork;
@@ -72,6 +80,7 @@ mod gettid;
 mod gettimeofday;
 mod getuid;
 mod getxattr;
+mod inotify;
 mod ioctl;
 mod kill;
 mod link;
@@ -85,7 +94,17 @@ mod mknod;
 mod mmap;
 mod mount;
 mod mprotect;
+mod mq_getsetattr;
+mod mq_notify;
+mod mq_open;
+mod mq_timedreceive;
+mod mq_timedsend;
+mod mq_unlink;
 mod mremap;
+mod msgctl;
+mod msgget;
+mod msgrcv;
+mod msgsnd;
 mod msync;
 mod munmap;
 mod nanosleep;
@@ -93,6 +112,7 @@ mod open;
 mod pause;
 mod pidfd_open;
 mod pipe;
+mod pivot_root;
 mod poll;
 mod ppoll;
 mod prctl;
@@ -100,6 +120,7 @@ mod pread64;
 mod preadv;
 mod prlimit64;
 mod pselect6;
+mod ptrace;
 mod pwrite64;
 mod pwritev;
 mod read;
@@ -140,6 +161,7 @@ mod setfsuid;
 mod setgid;
 mod setgroups;
 mod setitimer;
+mod setns;
 mod setpgid;
 mod setregid;
 mod setresgid;
@@ -149,14 +171,20 @@ mod setsid;
 mod setsockopt;
 mod setuid;
 mod setxattr;
+mod shmat;
+mod shmctl;
+mod shmdt;
+mod shmget;
 mod shutdown;
 mod sigaltstack;
 mod signalfd;
 mod socket;
 mod socketpair;
+mod splice;
 mod stat;
 mod statfs;
 mod statx;
+mod swapon;
 mod symlink;
 mod sync;
 mod sysinfo;
@@ -172,6 +200,7 @@ mod umask;
 mod umount;
 mod uname;
 mod unlink;
+mod unshare;
 mod utimens;
 mod wait4;
 mod waitid;
@@ -354,6 +383,17 @@ impl SyscallArgument {
 }
 
 pub fn handle_syscall(ctx: &Context, user_ctx: &mut UserContext) {
+    // The tracer may skip the system call or change its number and arguments.
+    let syscall_num = user_ctx.syscall_num();
+    match stop_on_syscall_entry(ctx, user_ctx) {
+        SyscallEntryAction::Execute => (),
+        SyscallEntryAction::Skip => {
+            stop_on_syscall_exit(ctx, user_ctx, syscall_num);
+            return;
+        }
+        SyscallEntryAction::Abandon => return,
+    }
+
     let syscall_frame = SyscallArgument::new_from_context(user_ctx);
     let syscall_return = arch::syscall_dispatch(
         syscall_frame.syscall_number,
@@ -374,6 +414,13 @@ pub fn handle_syscall(ctx: &Context, user_ctx: &mut UserContext) {
             user_ctx.set_syscall_ret((-errno) as usize)
         }
     }
+
+    ctx.thread_local.run_task_works();
+
+    // The files released by the system call (e.g., `close`) produce close events.
+    notify::flush_close_events();
+
+    stop_on_syscall_exit(ctx, user_ctx, syscall_frame.syscall_number as usize);
 }
 
 #[macro_export]
@@ -396,7 +443,3 @@ macro_rules! log_syscall_entry {
         }
     };
 }
-
-pub(super) fn init() {
-    uname::init();
-}
@@ -4,7 +4,7 @@ use super::SyscallReturn;
 use crate::{
     fs::{
         fs_resolver::{FsPath, AT_FDCWD},
-        path::Path,
+        path::{Path, PropagationType},
         registry::FsProperties,
         utils::{FileSystem, InodeType},
     },
@@ -62,7 +62,7 @@ pub fn sys_mount(
         | mount_flags.contains(MountFlags::MS_SLAVE)
         | mount_flags.contains(MountFlags::MS_UNBINDABLE)
     {
-        do_change_type()?;
+        do_change_type(mount_flags, dst_path)?;
     } else if mount_flags.contains(MountFlags::MS_MOVE) {
         do_move_mount_old(devname, dst_path, ctx)?;
     } else {
@@ -106,8 +106,32 @@ fn do_bind_mount(src_name: CString, dst_path: Path, recursive: bool, ctx: &Conte
     Ok(())
 }
 
-fn do_change_type() -> Result<()> {
-    return_errno_with_message!(Errno::EINVAL, "do_change_type is not supported");
+/// Change the propagation type of a mount.
+///
+/// If `MS_REC` is set, then change the propagation type of all the mounts in the
+/// mount tree. Such as use user command `mount --make-rshared dst`.
+fn do_change_type(flags: MountFlags, dst_path: Path) -> Result<()> {
+    if !dst_path.is_root_of_mount() {
+        return_errno_with_message!(Errno::EINVAL, "dst_name is not the root of a mount");
+    }
+
+    let type_flags = flags - MountFlags::MS_REC - MountFlags::MS_SILENT;
+    let propagation_type = if type_flags == MountFlags::MS_SHARED {
+        PropagationType::Shared
+    } else if type_flags == MountFlags::MS_SLAVE {
+        PropagationType::Slave
+    } else if type_flags == MountFlags::MS_PRIVATE {
+        PropagationType::Private
+    } else if type_flags == MountFlags::MS_UNBINDABLE {
+        PropagationType::Unbindable
+    } else {
+        return_errno_with_message!(Errno::EINVAL, "invalid propagation type flags");
+    };
+
+    dst_path
+        .mount_node()
+        .set_propagation(propagation_type, flags.contains(MountFlags::MS_REC));
+    Ok(())
 }
 
 /// Move a mount from src location to dst location.
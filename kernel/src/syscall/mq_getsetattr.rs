@@ -0,0 +1,79 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::SyscallReturn;
+use crate::{
+    fs::{
+        file_table::{get_file_fast, FileDesc},
+        mqueue::MqueueInode,
+        utils::StatusFlags,
+    },
+    prelude::*,
+};
+
+pub fn sys_mq_getsetattr(
+    mqdes: FileDesc,
+    new_attr_addr: Vaddr,
+    old_attr_addr: Vaddr,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    debug!(
+        "mqdes = {}, new_attr_addr = {:#x}, old_attr_addr = {:#x}",
+        mqdes, new_attr_addr, old_attr_addr
+    );
+
+    let new_attr = if new_attr_addr == 0 {
+        None
+    } else {
+        let new_attr = ctx.user_space().read_val::<CMqAttr>(new_attr_addr)?;
+        if new_attr.mq_flags & !(StatusFlags::O_NONBLOCK.bits() as i64) != 0 {
+            return_errno_with_message!(Errno::EINVAL, "only O_NONBLOCK can be set");
+        }
+        Some(new_attr)
+    };
+
+    let mut file_table = ctx.thread_local.borrow_file_table_mut();
+    let file = get_file_fast!(&mut file_table, mqdes);
+    let queue = MqueueInode::from_file(&file)?;
+
+    let status_flags = file.status_flags();
+    if old_attr_addr != 0 {
+        let attr = queue.attr();
+        let old_attr = CMqAttr {
+            mq_flags: (status_flags & StatusFlags::O_NONBLOCK).bits() as i64,
+            mq_maxmsg: attr.maxmsg as i64,
+            mq_msgsize: attr.msgsize as i64,
+            mq_curmsgs: queue.num_messages() as i64,
+            ..Default::default()
+        };
+        ctx.user_space().write_val(old_attr_addr, &old_attr)?;
+    }
+
+    // Only the `O_NONBLOCK` flag can be changed, and the other attributes are ignored.
+    if let Some(new_attr) = new_attr {
+        let new_status_flags = if new_attr.mq_flags != 0 {
+            status_flags | StatusFlags::O_NONBLOCK
+        } else {
+            status_flags - StatusFlags::O_NONBLOCK
+        };
+        file.set_status_flags(new_status_flags)?;
+    }
+
+    Ok(SyscallReturn::Return(0))
+}
+
+/// The attributes of a message queue, which is `struct mq_attr` in C.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.15.7/source/include/uapi/linux/mqueue.h#L29>
+#[derive(Debug, Clone, Copy, Default, Pod)]
+#[repr(C)]
+pub(super) struct CMqAttr {
+    /// Message queue flags
+    pub(super) mq_flags: i64,
+    /// Maximum number of messages
+    pub(super) mq_maxmsg: i64,
+    /// Maximum size in bytes of a message
+    pub(super) mq_msgsize: i64,
+    /// Number of messages currently queued
+    pub(super) mq_curmsgs: i64,
+    __reserved: [i64; 4],
+}
@@ -0,0 +1,77 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::SyscallReturn;
+use crate::{
+    fs::{
+        file_table::{get_file_fast, FileDesc},
+        mqueue::{MqueueInode, NotifyMethod, NOTIFY_COOKIE_LEN},
+    },
+    net::socket::netlink::NetlinkRouteSocket,
+    prelude::*,
+    process::signal::{
+        c_types::{sigevent_t, SigNotify},
+        sig_num::SigNum,
+    },
+};
+
+pub fn sys_mq_notify(mqdes: FileDesc, sevp_addr: Vaddr, ctx: &Context) -> Result<SyscallReturn> {
+    debug!("mqdes = {}, sevp_addr = {:#x}", mqdes, sevp_addr);
+
+    let method = if sevp_addr == 0 {
+        None
+    } else {
+        let sig_event = ctx.user_space().read_val::<sigevent_t>(sevp_addr)?;
+        let sigev_notify = SigNotify::try_from(sig_event.sigev_notify)?;
+        match sigev_notify {
+            // Do nothing when a message arrives.
+            SigNotify::SIGEV_NONE => Some(NotifyMethod::None),
+            // Send a signal to the current process when a message arrives.
+            SigNotify::SIGEV_SIGNAL => {
+                let signo = u8::try_from(sig_event.sigev_signo)
+                    .map_err(|_| Error::with_message(Errno::EINVAL, "invalid signal number"))?;
+                Some(NotifyMethod::Signal(
+                    SigNum::try_from(signo)?,
+                    sig_event.sigev_value,
+                ))
+            }
+            // The C library implements `SIGEV_THREAD` by passing a netlink socket in
+            // `sig_event.sigev_signo` and a cookie in `sig_event.sigev_value`, and it
+            // expects the kernel to write the cookie to the socket when a message arrives.
+            SigNotify::SIGEV_THREAD => {
+                let socket = {
+                    let mut file_table = ctx.thread_local.borrow_file_table_mut();
+                    get_file_fast!(&mut file_table, sig_event.sigev_signo).into_owned()
+                };
+                if socket.downcast_ref::<NetlinkRouteSocket>().is_none() {
+                    return_errno_with_message!(Errno::EBADF, "the file is not a netlink socket");
+                }
+
+                let cookie = ctx
+                    .user_space()
+                    .read_val::<[u8; NOTIFY_COOKIE_LEN]>(sig_event.sigev_value.read_ptr())?;
+
+                Some(NotifyMethod::Cookie { socket, cookie })
+            }
+            SigNotify::SIGEV_THREAD_ID => {
+                return_errno_with_message!(Errno::EINVAL, "SIGEV_THREAD_ID is not supported")
+            }
+        }
+    };
+
+    // The file table is locked, so that the file descriptor cannot be closed
+    // before the registration is done.
+    let file_table = ctx.thread_local.borrow_file_table();
+    let file_table_locked = file_table.unwrap().read();
+    let fd_entry = file_table_locked.get_entry(mqdes)?;
+    let queue = MqueueInode::from_file(fd_entry.file())?;
+
+    let pid = ctx.process.pid();
+    match method {
+        Some(method) => {
+            queue.register_notification(ctx.posix_thread.weak_process(), pid, method, fd_entry)?
+        }
+        None => queue.unregister_notification(pid),
+    }
+
+    Ok(SyscallReturn::Return(0))
+}
@@ -0,0 +1,113 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::{mq_getsetattr::CMqAttr, SyscallReturn};
+use crate::{
+    fs::{
+        file_table::FdFlags,
+        inode_handle::InodeHandle,
+        mqueue::{MqAttr, MqueueFs, HARD_MSGMAX, HARD_MSGSIZEMAX, MSGSIZE_MAX, MSG_MAX},
+        utils::{AccessMode, CreationFlags, InodeMode, StatusFlags},
+    },
+    prelude::*,
+    process::credentials::capabilities::CapSet,
+    syscall::constants::MAX_FILENAME_LEN,
+};
+
+pub fn sys_mq_open(
+    name_addr: Vaddr,
+    oflag: u32,
+    mode: u16,
+    attr_addr: Vaddr,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    let name = ctx.user_space().read_cstring(name_addr, MAX_FILENAME_LEN)?;
+    let name = name.to_string_lossy();
+    debug!(
+        "name = {:?}, oflag = {:#o}, mode = {:#o}, attr_addr = {:#x}",
+        name, oflag, mode, attr_addr
+    );
+
+    // Like Linux, the leading slash is stripped by the C library, so the name
+    // cannot contain any slash.
+    if name.is_empty() || name.contains('/') {
+        return_errno_with_message!(Errno::EACCES, "the queue name is invalid");
+    }
+
+    let access_mode = AccessMode::from_u32(oflag)?;
+    let creation_flags = CreationFlags::from_bits_truncate(oflag);
+    let status_flags = StatusFlags::from_bits_truncate(oflag) & StatusFlags::O_NONBLOCK;
+
+    let root_path = MqueueFs::root_path();
+    let inode_handle = if creation_flags.contains(CreationFlags::O_CREAT) {
+        // Reading the attributes may sleep, so it is done before taking the lock.
+        let attr = if attr_addr == 0 {
+            None
+        } else {
+            Some(ctx.user_space().read_val::<CMqAttr>(attr_addr)?)
+        };
+        let credentials = ctx.posix_thread.credentials();
+        let has_sys_resource = credentials
+            .effective_capset()
+            .contains(CapSet::SYS_RESOURCE);
+
+        let mode = {
+            let umask = ctx.thread_local.borrow_fs().umask().read().get();
+            InodeMode::from_bits_truncate(mode & !umask & 0o777)
+        };
+        let (_, is_created) = MqueueFs::singleton().lookup_or_create_queue(
+            &name,
+            creation_flags.contains(CreationFlags::O_EXCL),
+            mode,
+            credentials.fsuid(),
+            credentials.fsgid(),
+            || match attr {
+                Some(attr) => check_attr(attr, has_sys_resource),
+                None => Ok(MqAttr::default()),
+            },
+        )?;
+
+        let path = root_path.lookup(&name)?;
+        if is_created {
+            // Don't check access mode for newly created queue
+            InodeHandle::new_unchecked_access(path, access_mode, status_flags)?
+        } else {
+            InodeHandle::new(path, access_mode, status_flags)?
+        }
+    } else {
+        let path = root_path.lookup(&name)?;
+        InodeHandle::new(path, access_mode, status_flags)?
+    };
+
+    let fd = {
+        let file_table = ctx.thread_local.borrow_file_table();
+        let mut file_table_locked = file_table.unwrap().write();
+        let fd_flags = if creation_flags.contains(CreationFlags::O_CLOEXEC) {
+            FdFlags::CLOEXEC
+        } else {
+            FdFlags::empty()
+        };
+        file_table_locked.insert(Arc::new(inode_handle), fd_flags)
+    };
+
+    Ok(SyscallReturn::Return(fd as _))
+}
+
+fn check_attr(attr: CMqAttr, has_sys_resource: bool) -> Result<MqAttr> {
+    if attr.mq_maxmsg <= 0 || attr.mq_msgsize <= 0 {
+        return_errno_with_message!(Errno::EINVAL, "the queue attributes are not positive");
+    }
+
+    let (max_msgs, max_msgsize) = if has_sys_resource {
+        (HARD_MSGMAX, HARD_MSGSIZEMAX)
+    } else {
+        (MSG_MAX, MSGSIZE_MAX)
+    };
+    if attr.mq_maxmsg as usize > max_msgs || attr.mq_msgsize as usize > max_msgsize {
+        return_errno_with_message!(Errno::EINVAL, "the queue attributes exceed the limits");
+    }
+
+    Ok(MqAttr {
+        maxmsg: attr.mq_maxmsg as usize,
+        msgsize: attr.mq_msgsize as usize,
+    })
+}
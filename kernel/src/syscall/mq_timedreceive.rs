@@ -0,0 +1,54 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::{
+    mq_timedsend::{map_wait_error, read_abs_timeout_from_user},
+    SyscallReturn,
+};
+use crate::{
+    fs::{
+        file_table::{get_file_fast, FileDesc},
+        mqueue::MqueueInode,
+        utils::StatusFlags,
+    },
+    prelude::*,
+};
+
+pub fn sys_mq_timedreceive(
+    mqdes: FileDesc,
+    msg_ptr: Vaddr,
+    msg_len: usize,
+    msg_prio_addr: Vaddr,
+    abs_timeout_addr: Vaddr,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    debug!(
+        "mqdes = {}, msg_ptr = {:#x}, msg_len = {}, msg_prio_addr = {:#x}, abs_timeout_addr = {:#x}",
+        mqdes, msg_ptr, msg_len, msg_prio_addr, abs_timeout_addr
+    );
+
+    let timeout = read_abs_timeout_from_user(abs_timeout_addr, ctx)?;
+
+    let mut file_table = ctx.thread_local.borrow_file_table_mut();
+    let file = get_file_fast!(&mut file_table, mqdes);
+    let queue = MqueueInode::from_file(&file)?;
+    if !file.access_mode().is_readable() {
+        return_errno_with_message!(Errno::EBADF, "the queue is not opened for reading");
+    }
+    // The buffer must be large enough for the longest message in the queue.
+    if msg_len < queue.attr().msgsize {
+        return_errno_with_message!(Errno::EMSGSIZE, "the buffer is too small");
+    }
+
+    let is_nonblocking = file.status_flags().contains(StatusFlags::O_NONBLOCK);
+    let (data, priority) = queue
+        .receive(is_nonblocking, timeout.as_ref())
+        .map_err(map_wait_error)?;
+
+    let user_space = ctx.user_space();
+    user_space.write_bytes(msg_ptr, &mut VmReader::from(&data[..]))?;
+    if msg_prio_addr != 0 {
+        user_space.write_val(msg_prio_addr, &priority)?;
+    }
+
+    Ok(SyscallReturn::Return(data.len() as _))
+}
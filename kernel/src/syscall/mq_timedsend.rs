@@ -0,0 +1,80 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use core::time::Duration;
+
+use super::SyscallReturn;
+use crate::{
+    fs::{
+        file_table::{get_file_fast, FileDesc},
+        mqueue::{MqueueInode, MQ_PRIO_MAX},
+        utils::StatusFlags,
+    },
+    prelude::*,
+    time::{clocks::RealTimeClock, timespec_t},
+};
+
+pub fn sys_mq_timedsend(
+    mqdes: FileDesc,
+    msg_ptr: Vaddr,
+    msg_len: usize,
+    msg_prio: u32,
+    abs_timeout_addr: Vaddr,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    debug!(
+        "mqdes = {}, msg_ptr = {:#x}, msg_len = {}, msg_prio = {}, abs_timeout_addr = {:#x}",
+        mqdes, msg_ptr, msg_len, msg_prio, abs_timeout_addr
+    );
+
+    if msg_prio >= MQ_PRIO_MAX {
+        return_errno_with_message!(Errno::EINVAL, "the message priority is too large");
+    }
+    let timeout = read_abs_timeout_from_user(abs_timeout_addr, ctx)?;
+
+    let mut file_table = ctx.thread_local.borrow_file_table_mut();
+    let file = get_file_fast!(&mut file_table, mqdes);
+    let queue = MqueueInode::from_file(&file)?;
+    if !file.access_mode().is_writable() {
+        return_errno_with_message!(Errno::EBADF, "the queue is not opened for writing");
+    }
+    if msg_len > queue.attr().msgsize {
+        return_errno_with_message!(Errno::EMSGSIZE, "the message is too long");
+    }
+
+    let mut data = vec![0u8; msg_len].into_boxed_slice();
+    ctx.user_space()
+        .read_bytes(msg_ptr, &mut VmWriter::from(&mut data[..]))?;
+
+    let is_nonblocking = file.status_flags().contains(StatusFlags::O_NONBLOCK);
+    let sender = (ctx.process.pid(), ctx.posix_thread.credentials().ruid());
+    queue
+        .send(data, msg_prio, is_nonblocking, timeout.as_ref(), sender)
+        .map_err(map_wait_error)?;
+
+    Ok(SyscallReturn::Return(0))
+}
+
+/// Reads the absolute timeout measured against `CLOCK_REALTIME` and converts
+/// it to the remaining duration.
+pub(super) fn read_abs_timeout_from_user(
+    abs_timeout_addr: Vaddr,
+    ctx: &Context,
+) -> Result<Option<Duration>> {
+    if abs_timeout_addr == 0 {
+        return Ok(None);
+    }
+
+    let abs_timeout =
+        Duration::try_from(ctx.user_space().read_val::<timespec_t>(abs_timeout_addr)?)?;
+    let now = RealTimeClock::get().read_time();
+
+    Ok(Some(abs_timeout.saturating_sub(now)))
+}
+
+pub(super) fn map_wait_error(err: Error) -> Error {
+    match err.error() {
+        Errno::ETIME => Error::with_message(Errno::ETIMEDOUT, "the timeout expired"),
+        Errno::EINTR => Error::new(Errno::ERESTARTSYS),
+        _ => err,
+    }
+}
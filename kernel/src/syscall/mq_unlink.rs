@@ -0,0 +1,20 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::SyscallReturn;
+use crate::{fs::mqueue::MqueueFs, prelude::*, syscall::constants::MAX_FILENAME_LEN};
+
+pub fn sys_mq_unlink(name_addr: Vaddr, ctx: &Context) -> Result<SyscallReturn> {
+    let name = ctx.user_space().read_cstring(name_addr, MAX_FILENAME_LEN)?;
+    let name = name.to_string_lossy();
+    debug!("name = {:?}", name);
+
+    if name.is_empty() || name.contains('/') {
+        return_errno_with_message!(Errno::EACCES, "the queue name is invalid");
+    }
+
+    // The queue is removed from the file system immediately, but it is only
+    // destroyed after all the descriptors referring to it are closed.
+    MqueueFs::root_path().unlink(&name)?;
+
+    Ok(SyscallReturn::Return(0))
+}
@@ -0,0 +1,88 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::SyscallReturn;
+use crate::{
+    ipc::{
+        msg::msg_queue::{get_msg_queue, remove_msg_queue},
+        IpcControlCmd, IpcPerm64,
+    },
+    prelude::*,
+    process::{Gid, Uid},
+};
+
+pub fn sys_msgctl(msqid: i32, cmd: i32, buf: Vaddr, ctx: &Context) -> Result<SyscallReturn> {
+    if msqid <= 0 {
+        return_errno!(Errno::EINVAL);
+    }
+
+    // The C library may request the 64-bit version of the structures, which
+    // is the only version that we support.
+    const IPC_64: i32 = 0x100;
+    let cmd = IpcControlCmd::try_from(cmd & !IPC_64)?;
+    debug!(
+        "[sys_msgctl] msqid = {}, cmd = {:?}, buf = {:#x}",
+        msqid, cmd, buf
+    );
+
+    let credentials = ctx.posix_thread.credentials();
+    let ipc_ns = ctx.thread_local.borrow_ns_proxy().unwrap().ipc_ns().clone();
+
+    match cmd {
+        IpcControlCmd::IPC_RMID => {
+            remove_msg_queue(&ipc_ns, msqid, &credentials)?;
+        }
+        IpcControlCmd::IPC_SET => {
+            let msqid_ds: MsqidDs = ctx.user_space().read_val(buf)?;
+            let msg_queue = get_msg_queue(&ipc_ns, msqid).map_err(|_| Error::new(Errno::EINVAL))?;
+
+            let perm = &msqid_ds.msg_perm;
+            msg_queue.set(
+                Uid::new(perm.uid),
+                Gid::new(perm.gid),
+                perm.mode as u16,
+                msqid_ds.msg_qbytes as usize,
+                &credentials,
+            )?;
+        }
+        IpcControlCmd::IPC_STAT => {
+            let msg_queue = get_msg_queue(&ipc_ns, msqid).map_err(|_| Error::new(Errno::EINVAL))?;
+            let permission = msg_queue.permission();
+            permission.check_access(&credentials, 0o4)?;
+
+            let msqid_ds = MsqidDs {
+                msg_perm: IpcPerm64::from(&permission),
+                msg_stime: msg_queue.stime().as_secs() as i64,
+                msg_rtime: msg_queue.rtime().as_secs() as i64,
+                msg_ctime: msg_queue.ctime().as_secs() as i64,
+                msg_cbytes: msg_queue.cbytes() as u64,
+                msg_qnum: msg_queue.qnum() as u64,
+                msg_qbytes: msg_queue.qbytes() as u64,
+                msg_lspid: msg_queue.lspid(),
+                msg_lrpid: msg_queue.lrpid(),
+                unused4: 0,
+                unused5: 0,
+            };
+            ctx.user_space().write_val(buf, &msqid_ds)?;
+        }
+        _ => return_errno_with_message!(Errno::EINVAL, "the msgctl command is not supported"),
+    }
+
+    Ok(SyscallReturn::Return(0))
+}
+
+/// The `msqid64_ds` structure, which is used by `IPC_STAT` and `IPC_SET`.
+#[derive(Debug, Clone, Copy, Pod)]
+#[repr(C)]
+struct MsqidDs {
+    msg_perm: IpcPerm64,
+    msg_stime: i64,
+    msg_rtime: i64,
+    msg_ctime: i64,
+    msg_cbytes: u64,
+    msg_qnum: u64,
+    msg_qbytes: u64,
+    msg_lspid: u32,
+    msg_lrpid: u32,
+    unused4: u64,
+    unused5: u64,
+}
@@ -0,0 +1,55 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::SyscallReturn;
+use crate::{
+    ipc::{
+        msg::msg_queue::{create_msg_queue, find_msg_queue_by_key},
+        IpcFlags, IPC_PRIVATE,
+    },
+    prelude::*,
+};
+
+pub fn sys_msgget(key: i32, msgflg: i32, ctx: &Context) -> Result<SyscallReturn> {
+    if key < 0 {
+        return_errno!(Errno::EINVAL);
+    }
+
+    let flags = IpcFlags::from_bits_truncate(msgflg as u32);
+    let mode: u16 = (msgflg as u32 & 0o777) as u16;
+    let credentials = ctx.posix_thread.credentials();
+    let ipc_ns = ctx.thread_local.borrow_ns_proxy().unwrap().ipc_ns().clone();
+
+    debug!("[sys_msgget] key = {}, flags = {:?}", key, msgflg);
+
+    // Create a new message queue directly
+    if key == IPC_PRIVATE {
+        return Ok(SyscallReturn::Return(
+            create_msg_queue(&ipc_ns, key, mode, &credentials)? as isize,
+        ));
+    }
+
+    // Get a message queue, and create if necessary
+    let id = match find_msg_queue_by_key(&ipc_ns, key) {
+        Some(msg_queue) => {
+            if flags.contains(IpcFlags::IPC_CREAT | IpcFlags::IPC_EXCL) {
+                return_errno!(Errno::EEXIST);
+            }
+
+            let requested = (mode >> 6) | (mode >> 3) | mode;
+            msg_queue
+                .permission()
+                .check_access(&credentials, requested & 0o7)?;
+
+            msg_queue.id()
+        }
+        None => {
+            if !flags.contains(IpcFlags::IPC_CREAT) {
+                return_errno_with_message!(Errno::ENOENT, "no message queue exists for the key");
+            }
+
+            create_msg_queue(&ipc_ns, key, mode, &credentials)?
+        }
+    };
+
+    Ok(SyscallReturn::Return(id as isize))
+}
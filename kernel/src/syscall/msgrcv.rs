@@ -0,0 +1,44 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::SyscallReturn;
+use crate::{
+    ipc::msg::{msg_queue::get_msg_queue, MsgFlags},
+    prelude::*,
+};
+
+pub fn sys_msgrcv(
+    msqid: i32,
+    msgp: Vaddr,
+    msgsz: usize,
+    msgtyp: i64,
+    msgflg: i32,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    let flags = MsgFlags::from_bits_truncate(msgflg as u32);
+    debug!(
+        "[sys_msgrcv] msqid = {}, msgp = {:#x}, msgsz = {}, msgtyp = {}, flags = {:?}",
+        msqid, msgp, msgsz, msgtyp, flags
+    );
+
+    if msqid <= 0 || (msgsz as isize) < 0 {
+        return_errno!(Errno::EINVAL);
+    }
+    if flags.contains(MsgFlags::MSG_COPY) {
+        return_errno_with_message!(Errno::ENOSYS, "MSG_COPY is not supported");
+    }
+
+    let ipc_ns = ctx.thread_local.borrow_ns_proxy().unwrap().ipc_ns().clone();
+    let msg_queue = get_msg_queue(&ipc_ns, msqid).map_err(|_| Error::new(Errno::EINVAL))?;
+    msg_queue
+        .permission()
+        .check_access(&ctx.posix_thread.credentials(), 0o4)?;
+
+    let message = msg_queue.receive(msgtyp, msgsz, flags, ctx.process.pid())?;
+
+    // The buffer starts with the message type, followed by the message content.
+    let user_space = ctx.user_space();
+    user_space.write_val(msgp, &message.mtype())?;
+    user_space.write_bytes(msgp + size_of::<i64>(), &mut VmReader::from(message.data()))?;
+
+    Ok(SyscallReturn::Return(message.data().len() as isize))
+}
@@ -0,0 +1,51 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::SyscallReturn;
+use crate::{
+    ipc::msg::{
+        msg_queue::{get_msg_queue, Message},
+        MsgFlags, MSGMAX,
+    },
+    prelude::*,
+};
+
+pub fn sys_msgsnd(
+    msqid: i32,
+    msgp: Vaddr,
+    msgsz: usize,
+    msgflg: i32,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    let flags = MsgFlags::from_bits_truncate(msgflg as u32);
+    debug!(
+        "[sys_msgsnd] msqid = {}, msgp = {:#x}, msgsz = {}, flags = {:?}",
+        msqid, msgp, msgsz, flags
+    );
+
+    if msqid <= 0 || msgsz > MSGMAX {
+        return_errno!(Errno::EINVAL);
+    }
+
+    let ipc_ns = ctx.thread_local.borrow_ns_proxy().unwrap().ipc_ns().clone();
+    let msg_queue = get_msg_queue(&ipc_ns, msqid).map_err(|_| Error::new(Errno::EINVAL))?;
+    msg_queue
+        .permission()
+        .check_access(&ctx.posix_thread.credentials(), 0o2)?;
+
+    // The buffer starts with the message type, followed by the message content.
+    let user_space = ctx.user_space();
+    let mtype = user_space.read_val::<i64>(msgp)?;
+    if mtype <= 0 {
+        return_errno_with_message!(Errno::EINVAL, "the message type must be positive");
+    }
+    let mut data = vec![0u8; msgsz];
+    user_space.read_bytes(
+        msgp + size_of::<i64>(),
+        &mut VmWriter::from(data.as_mut_slice()),
+    )?;
+
+    let message = Message::new(mtype, data.into_boxed_slice());
+    msg_queue.send(message, flags, ctx.process.pid())?;
+
+    Ok(SyscallReturn::Return(0))
+}
@@ -0,0 +1,83 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::SyscallReturn;
+use crate::{
+    fs::{
+        fs_resolver::{FsPath, FsResolver},
+        path::{MountNamespace, Path},
+    },
+    prelude::*,
+    process::{posix_thread::AsPosixThread, process_table},
+    syscall::constants::MAX_FILENAME_LEN,
+};
+
+pub fn sys_pivot_root(
+    new_root_ptr: Vaddr,
+    put_old_ptr: Vaddr,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    let user_space = ctx.user_space();
+    let new_root_name = user_space.read_cstring(new_root_ptr, MAX_FILENAME_LEN)?;
+    let put_old_name = user_space.read_cstring(put_old_ptr, MAX_FILENAME_LEN)?;
+    debug!(
+        "new_root = {:?}, put_old = {:?}",
+        new_root_name, put_old_name
+    );
+
+    let ns_proxy = ctx.thread_local.borrow_ns_proxy().unwrap().clone();
+    let mnt_ns = ns_proxy.mnt_ns();
+    mnt_ns
+        .owner()
+        .check_admin(&ctx.posix_thread.credentials(), ns_proxy.user_ns())?;
+
+    let (root, new_root, put_old) = {
+        let fs_ref = ctx.thread_local.borrow_fs();
+        let fs = fs_ref.resolver().read();
+        let new_root = lookup_dir(&fs, &new_root_name.to_string_lossy())?;
+        let put_old = lookup_dir(&fs, &put_old_name.to_string_lossy())?;
+        (fs.root().clone(), new_root, put_old)
+    };
+
+    mnt_ns.pivot_root(&root, &new_root, &put_old)?;
+    replace_fs_refs(mnt_ns, &root, &new_root);
+
+    Ok(SyscallReturn::Return(0))
+}
+
+fn lookup_dir(fs: &FsResolver, path: &str) -> Result<Path> {
+    if path.is_empty() {
+        return_errno_with_message!(Errno::ENOENT, "path is empty");
+    }
+    let fs_path = FsPath::try_from(path)?;
+    fs.lookup(&fs_path)
+}
+
+/// Replaces the root and the current working directory of the threads in the mount namespace
+/// that are `old_root` with `new_root`.
+fn replace_fs_refs(mnt_ns: &Arc<MountNamespace>, old_root: &Path, new_root: &Path) {
+    for process in process_table::process_table_mut().iter() {
+        for task in process.tasks().lock().as_slice() {
+            let posix_thread = task.as_posix_thread().unwrap();
+
+            let is_in_mnt_ns = posix_thread
+                .ns_proxy()
+                .lock()
+                .as_ref()
+                .is_some_and(|ns_proxy| Arc::ptr_eq(ns_proxy.mnt_ns(), mnt_ns));
+            if !is_in_mnt_ns {
+                continue;
+            }
+            let Some(fs) = posix_thread.fs().lock().upgrade() else {
+                continue;
+            };
+
+            let mut fs_resolver = fs.resolver().write();
+            if fs_resolver.root() == old_root {
+                fs_resolver.set_root(new_root.clone());
+            }
+            if fs_resolver.cwd() == old_root {
+                fs_resolver.set_cwd(new_root.clone());
+            }
+        }
+    }
+}
@@ -0,0 +1,275 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use aster_rights::Full;
+
+use super::SyscallReturn;
+use crate::{
+    arch::ptrace::UserRegs,
+    prelude::*,
+    process::{
+        posix_thread::{thread_table, AsPosixThread},
+        ptrace::{self, PtraceOptions, PtraceStop, ResumeMode},
+        signal::sig_num::SigNum,
+    },
+    thread::{Thread, Tid},
+    vm::vmar::Vmar,
+};
+
+pub fn sys_ptrace(
+    request: i64,
+    pid: Tid,
+    addr: Vaddr,
+    data: usize,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    let request = PtraceRequest::try_from(request)
+        .map_err(|_| Error::with_message(Errno::EIO, "the ptrace request is invalid"))?;
+    debug!(
+        "request = {:?}, pid = {}, addr = 0x{:x}, data = 0x{:x}",
+        request, pid, addr, data
+    );
+
+    if request == PtraceRequest::PTRACE_TRACEME {
+        ptrace::trace_me(ctx)?;
+        return Ok(SyscallReturn::Return(0));
+    }
+
+    let tracee = thread_table::get_thread(pid)
+        .ok_or_else(|| Error::with_message(Errno::ESRCH, "the thread does not exist"))?;
+    let state = tracee.as_posix_thread().unwrap().ptrace_state();
+    let tracer = ctx.process;
+
+    match request {
+        PtraceRequest::PTRACE_TRACEME => unreachable!(),
+        PtraceRequest::PTRACE_ATTACH => ptrace::attach(ctx, &tracee, None)?,
+        PtraceRequest::PTRACE_SEIZE => {
+            if addr != 0 {
+                return_errno_with_message!(Errno::EIO, "the address must be zero");
+            }
+            // Unlike `PTRACE_SETOPTIONS`, invalid options are reported as `EIO`.
+            let options = parse_options(data)
+                .map_err(|_| Error::with_message(Errno::EIO, "the ptrace options are invalid"))?;
+            ptrace::attach(ctx, &tracee, Some(options))?;
+        }
+        PtraceRequest::PTRACE_DETACH => ptrace::detach(ctx, &tracee, parse_signal(data)?)?,
+        PtraceRequest::PTRACE_INTERRUPT => ptrace::interrupt(ctx, &tracee)?,
+        PtraceRequest::PTRACE_KILL => ptrace::kill(ctx, &tracee)?,
+        PtraceRequest::PTRACE_CONT => {
+            ptrace::resume(ctx, &tracee, ResumeMode::Continue, parse_signal(data)?)?
+        }
+        PtraceRequest::PTRACE_SYSCALL => {
+            ptrace::resume(ctx, &tracee, ResumeMode::Syscall, parse_signal(data)?)?
+        }
+        PtraceRequest::PTRACE_SINGLESTEP => {
+            ptrace::resume(ctx, &tracee, ResumeMode::SingleStep, parse_signal(data)?)?
+        }
+        PtraceRequest::PTRACE_SETOPTIONS => state.set_options(tracer, parse_options(data)?)?,
+        PtraceRequest::PTRACE_GETEVENTMSG => {
+            let event_msg = state.event_msg(tracer)?;
+            ctx.user_space().write_val(data, &(event_msg as u64))?;
+        }
+        PtraceRequest::PTRACE_GETSIGINFO => {
+            let siginfo = state.with_stop(tracer, |stop| Ok(*stop.siginfo()))?;
+            ctx.user_space().write_val(data, &siginfo)?;
+        }
+        PtraceRequest::PTRACE_PEEKTEXT | PtraceRequest::PTRACE_PEEKDATA => {
+            // Only check that the tracee is stopped.
+            state.with_stop(tracer, |_| Ok(()))?;
+            let mut word = [0u8; size_of::<usize>()];
+            access_memory(&tracee, addr, |vmar| vmar.read_remote(addr, &mut word))?;
+            ctx.user_space()
+                .write_val(data, &usize::from_ne_bytes(word))?;
+        }
+        PtraceRequest::PTRACE_POKETEXT | PtraceRequest::PTRACE_POKEDATA => {
+            state.with_stop(tracer, |_| Ok(()))?;
+            let word = data.to_ne_bytes();
+            access_memory(&tracee, addr, |vmar| vmar.write_remote(addr, &word))?;
+        }
+        PtraceRequest::PTRACE_PEEKUSER => {
+            let word = state.with_stop(tracer, |stop| stop.peek_user(addr))?;
+            ctx.user_space().write_val(data, &word)?;
+        }
+        PtraceRequest::PTRACE_POKEUSER => {
+            state.with_stop(tracer, |stop| stop.poke_user(addr, data))?;
+        }
+        #[cfg(target_arch = "x86_64")]
+        PtraceRequest::PTRACE_GETREGS => {
+            let regs = state.with_stop(tracer, |stop| Ok(stop.user_regs()))?;
+            ctx.user_space().write_val(data, &regs)?;
+        }
+        #[cfg(target_arch = "x86_64")]
+        PtraceRequest::PTRACE_SETREGS => {
+            let regs: UserRegs = ctx.user_space().read_val(data)?;
+            state.with_stop(tracer, |stop| {
+                stop.set_user_regs(&regs);
+                Ok(())
+            })?;
+        }
+        #[cfg(target_arch = "x86_64")]
+        PtraceRequest::PTRACE_GETFPREGS => {
+            let fp_regs = state.with_stop(tracer, |stop| Ok(read_fp_regs(stop)))?;
+            ctx.user_space()
+                .write_bytes(data, &mut VmReader::from(fp_regs.as_slice()))?;
+        }
+        #[cfg(target_arch = "x86_64")]
+        PtraceRequest::PTRACE_SETFPREGS => {
+            let mut fp_regs = vec![0u8; crate::arch::ptrace::USER_FP_REGS_SIZE];
+            ctx.user_space()
+                .read_bytes(data, &mut VmWriter::from(fp_regs.as_mut_slice()))?;
+            state.with_stop(tracer, |stop| {
+                write_fp_regs(stop, &fp_regs);
+                Ok(())
+            })?;
+        }
+        PtraceRequest::PTRACE_GETREGSET => {
+            let iov: UserIovec = ctx.user_space().read_val(data)?;
+            let regs = state.with_stop(tracer, |stop| read_regset(stop, addr))?;
+            let len = regs.len().min(iov.len);
+            ctx.user_space()
+                .write_bytes(iov.base, &mut VmReader::from(&regs[..len]))?;
+            ctx.user_space()
+                .write_val(data + core::mem::offset_of!(UserIovec, len), &len)?;
+        }
+        PtraceRequest::PTRACE_SETREGSET => {
+            let iov: UserIovec = ctx.user_space().read_val(data)?;
+            let mut regs = vec![0u8; regset_size(addr)?.min(iov.len)];
+            ctx.user_space()
+                .read_bytes(iov.base, &mut VmWriter::from(regs.as_mut_slice()))?;
+            state.with_stop(tracer, |stop| write_regset(stop, addr, &regs))?;
+            ctx.user_space()
+                .write_val(data + core::mem::offset_of!(UserIovec, len), &regs.len())?;
+        }
+        #[cfg(not(target_arch = "x86_64"))]
+        _ => return_errno_with_message!(Errno::EIO, "the ptrace request is not supported"),
+    }
+
+    Ok(SyscallReturn::Return(0))
+}
+
+#[expect(non_camel_case_types)]
+#[repr(i64)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, TryFromInt)]
+enum PtraceRequest {
+    PTRACE_TRACEME = 0,
+    PTRACE_PEEKTEXT = 1,
+    PTRACE_PEEKDATA = 2,
+    PTRACE_PEEKUSER = 3,
+    PTRACE_POKETEXT = 4,
+    PTRACE_POKEDATA = 5,
+    PTRACE_POKEUSER = 6,
+    PTRACE_CONT = 7,
+    PTRACE_KILL = 8,
+    PTRACE_SINGLESTEP = 9,
+    PTRACE_GETREGS = 12,
+    PTRACE_SETREGS = 13,
+    PTRACE_GETFPREGS = 14,
+    PTRACE_SETFPREGS = 15,
+    PTRACE_ATTACH = 16,
+    PTRACE_DETACH = 17,
+    PTRACE_SYSCALL = 24,
+    PTRACE_SETOPTIONS = 0x4200,
+    PTRACE_GETEVENTMSG = 0x4201,
+    PTRACE_GETSIGINFO = 0x4202,
+    PTRACE_GETREGSET = 0x4204,
+    PTRACE_SETREGSET = 0x4205,
+    PTRACE_SEIZE = 0x4206,
+    PTRACE_INTERRUPT = 0x4207,
+}
+
+/// The types of register sets used by `PTRACE_GETREGSET` and `PTRACE_SETREGSET`.
+//
+// Reference: <https://elixir.bootlin.com/linux/v6.15.7/source/include/uapi/linux/elf.h#L382>
+const NT_PRSTATUS: usize = 1;
+#[cfg(target_arch = "x86_64")]
+const NT_PRFPREG: usize = 2;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+struct UserIovec {
+    base: Vaddr,
+    len: usize,
+}
+
+fn parse_options(data: usize) -> Result<PtraceOptions> {
+    u32::try_from(data)
+        .ok()
+        .and_then(PtraceOptions::from_bits)
+        .ok_or_else(|| Error::with_message(Errno::EINVAL, "the ptrace options are invalid"))
+}
+
+fn parse_signal(data: usize) -> Result<Option<SigNum>> {
+    if data == 0 {
+        return Ok(None);
+    }
+
+    u8::try_from(data)
+        .ok()
+        .and_then(|sig_num| SigNum::try_from(sig_num).ok())
+        .map(Some)
+        .ok_or_else(|| Error::with_message(Errno::EIO, "the signal number is invalid"))
+}
+
+/// Accesses the memory of the tracee.
+///
+/// Like Linux, any failure to access the memory is reported as `EIO`.
+fn access_memory(
+    tracee: &Arc<Thread>,
+    addr: Vaddr,
+    op: impl FnOnce(&Vmar<Full>) -> Result<()>,
+) -> Result<()> {
+    let Some(process) = tracee.as_posix_thread().unwrap().weak_process().upgrade() else {
+        return_errno_with_message!(Errno::ESRCH, "the process has exited");
+    };
+    let vmar_guard = process.lock_root_vmar();
+    let Some(vmar) = vmar_guard.as_ref() else {
+        return_errno_with_message!(Errno::ESRCH, "the process has exited");
+    };
+
+    op(vmar).map_err(|err| {
+        debug!("failed to access the memory at 0x{:x}: {:?}", addr, err);
+        Error::with_message(Errno::EIO, "the memory cannot be accessed")
+    })
+}
+
+fn regset_size(regset: usize) -> Result<usize> {
+    match regset {
+        NT_PRSTATUS => Ok(size_of::<UserRegs>()),
+        #[cfg(target_arch = "x86_64")]
+        NT_PRFPREG => Ok(crate::arch::ptrace::USER_FP_REGS_SIZE),
+        _ => return_errno_with_message!(Errno::EINVAL, "the register set is not supported"),
+    }
+}
+
+fn read_regset(stop: &PtraceStop, regset: usize) -> Result<Vec<u8>> {
+    match regset {
+        NT_PRSTATUS => Ok(stop.user_regs().as_bytes().to_vec()),
+        #[cfg(target_arch = "x86_64")]
+        NT_PRFPREG => Ok(read_fp_regs(stop)),
+        _ => return_errno_with_message!(Errno::EINVAL, "the register set is not supported"),
+    }
+}
+
+fn write_regset(stop: &mut PtraceStop, regset: usize, bytes: &[u8]) -> Result<()> {
+    match regset {
+        NT_PRSTATUS => {
+            let mut regs = stop.user_regs();
+            regs.as_bytes_mut()[..bytes.len()].copy_from_slice(bytes);
+            stop.set_user_regs(&regs);
+        }
+        #[cfg(target_arch = "x86_64")]
+        NT_PRFPREG => write_fp_regs(stop, bytes),
+        _ => return_errno_with_message!(Errno::EINVAL, "the register set is not supported"),
+    }
+
+    Ok(())
+}
+
+#[cfg(target_arch = "x86_64")]
+fn read_fp_regs(stop: &PtraceStop) -> Vec<u8> {
+    crate::arch::ptrace::fp_regs(stop.fpu_context()).to_vec()
+}
+
+#[cfg(target_arch = "x86_64")]
+fn write_fp_regs(stop: &mut PtraceStop, bytes: &[u8]) {
+    crate::arch::ptrace::fp_regs_mut(stop.fpu_context_mut())[..bytes.len()].copy_from_slice(bytes);
+}
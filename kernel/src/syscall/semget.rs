@@ -24,6 +24,7 @@ pub fn sys_semget(key: i32, nsems: i32, semflags: i32, ctx: &Context) -> Result<
     let mode: u16 = (semflags as u32 & 0x1FF) as u16;
     let nsems = nsems as usize;
     let credentials = ctx.posix_thread.credentials();
+    let ipc_ns = ctx.thread_local.borrow_ns_proxy().unwrap().ipc_ns().clone();
 
     debug!(
         "[sys_semget] key = {}, nsems = {}, flags = {:?}",
@@ -37,12 +38,13 @@ pub fn sys_semget(key: i32, nsems: i32, semflags: i32, ctx: &Context) -> Result<
             return_errno!(Errno::EINVAL);
         }
         return Ok(SyscallReturn::Return(
-            create_sem_set(nsems, mode, credentials)? as isize,
+            create_sem_set(&ipc_ns, nsems, mode, credentials)? as isize,
         ));
     }
 
     // Get a semaphore set, and create if necessary
     match check_sem(
+        &ipc_ns,
         key,
         Some(nsems),
         PermissionMode::ALTER | PermissionMode::READ,
@@ -61,7 +63,7 @@ pub fn sys_semget(key: i32, nsems: i32, semflags: i32, ctx: &Context) -> Result<
                 return_errno!(Errno::EINVAL);
             }
 
-            create_sem_set_with_id(key, nsems, mode, credentials)?
+            create_sem_set_with_id(&ipc_ns, key, nsems, mode, credentials)?
         }
     };
 
@@ -0,0 +1,157 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::SyscallReturn;
+use crate::{
+    fs::{
+        file_handle::FileLike,
+        file_table::{get_file_fast, FileDesc},
+        inode_handle::InodeHandle,
+        procfs::namespace_of_inode,
+    },
+    prelude::*,
+    process::{
+        namespace::{Namespace, NsProxy},
+        posix_thread::AsPosixThread,
+        CloneFlags, PidFile,
+    },
+};
+
+pub fn sys_setns(fd: FileDesc, nstype: i32, ctx: &Context) -> Result<SyscallReturn> {
+    debug!("fd = {}, nstype = {:#x}", fd, nstype);
+
+    let nstype = CloneFlags::from_bits(nstype as u32)
+        .ok_or_else(|| Error::with_message(Errno::EINVAL, "invalid namespace types"))?;
+
+    let file = {
+        let mut file_table = ctx.thread_local.borrow_file_table_mut();
+        get_file_fast!(&mut file_table, fd).into_owned()
+    };
+    let namespaces = namespaces_of_file(&file, nstype)?;
+
+    let mut ns_proxy = ctx.thread_local.borrow_ns_proxy().unwrap().as_ref().clone();
+    let credentials = ctx.posix_thread.credentials();
+    let mut new_mnt_ns = None;
+
+    // The user namespace comes first, so the other namespaces are checked against it.
+    for ns in namespaces {
+        let current_user_ns = ns_proxy.user_ns().clone();
+
+        // Reference: <https://elixir.bootlin.com/linux/v6.15/source/kernel/nsproxy.c#L360>
+        match &ns {
+            Namespace::User(user_ns) => {
+                if Arc::ptr_eq(user_ns, &current_user_ns) {
+                    return_errno_with_message!(
+                        Errno::EINVAL,
+                        "the thread is already in the user namespace"
+                    );
+                }
+                if ctx.process.tasks().lock().as_slice().len() > 1 {
+                    return_errno_with_message!(Errno::EINVAL, "the process is multithreaded");
+                }
+                check_fs_unshared(ctx)?;
+                user_ns.check_admin(&credentials, &current_user_ns)?;
+            }
+            Namespace::Mnt(mnt_ns) => {
+                check_fs_unshared(ctx)?;
+                mnt_ns.owner().check_admin(&credentials, &current_user_ns)?;
+                current_user_ns.check_admin(&credentials, &current_user_ns)?;
+                new_mnt_ns = Some(mnt_ns.clone());
+            }
+            Namespace::Pid(pid_ns) => {
+                if !ctx.process.pid_ns().is_same_or_ancestor_of(pid_ns) {
+                    return_errno_with_message!(
+                        Errno::EINVAL,
+                        "the PID namespace is not a descendant of the current one"
+                    );
+                }
+                pid_ns.owner().check_admin(&credentials, &current_user_ns)?;
+                current_user_ns.check_admin(&credentials, &current_user_ns)?;
+            }
+            _ => {
+                ns.owner()
+                    .unwrap()
+                    .check_admin(&credentials, &current_user_ns)?;
+                current_user_ns.check_admin(&credentials, &current_user_ns)?;
+            }
+        }
+
+        ns_proxy = ns_proxy.with_namespace(ns);
+    }
+
+    if let Some(mnt_ns) = new_mnt_ns {
+        let fs = ctx.thread_local.borrow_fs();
+        let mut fs_resolver = fs.resolver().write();
+        fs_resolver.set_root(mnt_ns.root_path());
+        fs_resolver.set_cwd(mnt_ns.root_path());
+    }
+    Arc::new(ns_proxy).install(ctx);
+
+    Ok(SyscallReturn::Return(0))
+}
+
+/// Returns the namespaces referred to by the file that should be joined.
+///
+/// The file is either a namespace file in `/proc/[pid]/ns` or a PID file descriptor. In the latter
+/// case, the namespaces of the process that are selected by `nstype` are returned.
+fn namespaces_of_file(file: &Arc<dyn FileLike>, nstype: CloneFlags) -> Result<Vec<Namespace>> {
+    if let Some(pid_file) = file.downcast_ref::<PidFile>() {
+        if nstype.is_empty() || !NsProxy::CLONE_NS_FLAGS.contains(nstype) {
+            return_errno_with_message!(
+                Errno::EINVAL,
+                "invalid namespace types for a PID file descriptor"
+            );
+        }
+
+        let process = pid_file.process();
+        let main_thread = process.main_thread();
+        let Some(ns_proxy) = main_thread
+            .as_posix_thread()
+            .unwrap()
+            .ns_proxy()
+            .lock()
+            .clone()
+        else {
+            return_errno_with_message!(Errno::ESRCH, "the process has exited");
+        };
+
+        let namespaces = [
+            Namespace::User(ns_proxy.user_ns().clone()),
+            Namespace::Mnt(ns_proxy.mnt_ns().clone()),
+            Namespace::Uts(ns_proxy.uts_ns().clone()),
+            Namespace::Ipc(ns_proxy.ipc_ns().clone()),
+            Namespace::Net(ns_proxy.net_ns().clone()),
+            Namespace::Pid(process.pid_ns().clone()),
+        ];
+        return Ok(namespaces
+            .into_iter()
+            .filter(|ns| nstype.contains(ns.clone_flag()))
+            .collect());
+    }
+
+    let Some(ns) = file
+        .downcast_ref::<InodeHandle>()
+        .and_then(|handle| namespace_of_inode(handle.path().inode().as_ref()))
+    else {
+        return_errno_with_message!(Errno::EINVAL, "the file does not refer to a namespace");
+    };
+    if !nstype.is_empty() && nstype != ns.clone_flag() {
+        return_errno_with_message!(
+            Errno::EINVAL,
+            "the namespace does not match the namespace type"
+        );
+    }
+
+    Ok(vec![ns])
+}
+
+/// Checks that the thread does not share its filesystem information with others, as is required
+/// to join a user or mount namespace.
+fn check_fs_unshared(ctx: &Context) -> Result<()> {
+    if Arc::strong_count(&ctx.thread_local.borrow_fs()) > 1 {
+        return_errno_with_message!(
+            Errno::EINVAL,
+            "the filesystem information is shared with other threads"
+        );
+    }
+    Ok(())
+}
@@ -16,11 +16,27 @@ pub fn sys_setpgid(pid: Pid, pgid: Pgid, ctx: &Context) -> Result<SyscallReturn>
         return_errno_with_message!(Errno::EINVAL, "negative PIDs or PGIDs are not valid");
     }
 
+    let pid_ns = current.pid_ns();
+
     // "If `pid` is zero, then the process ID of the calling process is used."
-    let pid = if pid == 0 { current.pid() } else { pid };
+    let pid = if pid == 0 {
+        current.pid()
+    } else {
+        pid_ns.global_id(pid).ok_or(Error::with_message(
+            Errno::ESRCH,
+            "the process to set the PGID is not visible in the namespace",
+        ))?
+    };
     // "If `pgid` is zero, then the PGID of the process specified by `pid` is made the same as its
     // process ID."
-    let pgid = if pgid == 0 { pid } else { pgid };
+    let pgid = if pgid == 0 {
+        pid
+    } else {
+        pid_ns.global_id(pgid).ok_or(Error::with_message(
+            Errno::EPERM,
+            "the new process group is not visible in the namespace",
+        ))?
+    };
 
     debug!("pid = {}, pgid = {}", pid, pgid);
 
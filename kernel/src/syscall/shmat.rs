@@ -0,0 +1,34 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::SyscallReturn;
+use crate::{
+    ipc::shm::{shm_seg::get_shm_seg, ShmFlags},
+    prelude::*,
+};
+
+pub fn sys_shmat(shmid: i32, shmaddr: Vaddr, shmflg: i32, ctx: &Context) -> Result<SyscallReturn> {
+    if shmid <= 0 {
+        return_errno!(Errno::EINVAL);
+    }
+
+    let flags = ShmFlags::from_bits_truncate(shmflg as u32);
+    debug!(
+        "[sys_shmat] shmid = {}, shmaddr = {:#x}, flags = {:?}",
+        shmid, shmaddr, flags
+    );
+
+    let ipc_ns = ctx.thread_local.borrow_ns_proxy().unwrap().ipc_ns().clone();
+    let shm_seg = get_shm_seg(&ipc_ns, shmid).map_err(|_| Error::new(Errno::EINVAL))?;
+
+    let user_space = ctx.user_space();
+    let addr = shm_seg.attach(
+        &ipc_ns,
+        user_space.root_vmar(),
+        shmaddr,
+        flags,
+        &ctx.posix_thread.credentials(),
+        ctx.process.pid(),
+    )?;
+
+    Ok(SyscallReturn::Return(addr as isize))
+}
@@ -0,0 +1,83 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::SyscallReturn;
+use crate::{
+    ipc::{
+        shm::shm_seg::{get_shm_seg, remove_shm_seg},
+        IpcControlCmd, IpcPerm64,
+    },
+    prelude::*,
+    process::{Gid, Uid},
+};
+
+pub fn sys_shmctl(shmid: i32, cmd: i32, buf: Vaddr, ctx: &Context) -> Result<SyscallReturn> {
+    if shmid <= 0 {
+        return_errno!(Errno::EINVAL);
+    }
+
+    // The C library may request the 64-bit version of the structures, which
+    // is the only version that we support.
+    const IPC_64: i32 = 0x100;
+    let cmd = IpcControlCmd::try_from(cmd & !IPC_64)?;
+    debug!(
+        "[sys_shmctl] shmid = {}, cmd = {:?}, buf = {:#x}",
+        shmid, cmd, buf
+    );
+
+    let credentials = ctx.posix_thread.credentials();
+    let ipc_ns = ctx.thread_local.borrow_ns_proxy().unwrap().ipc_ns().clone();
+
+    match cmd {
+        IpcControlCmd::IPC_RMID => {
+            remove_shm_seg(&ipc_ns, shmid, &credentials)?;
+        }
+        IpcControlCmd::IPC_SET => {
+            let shmid_ds: ShmidDs = ctx.user_space().read_val(buf)?;
+            let shm_seg = get_shm_seg(&ipc_ns, shmid).map_err(|_| Error::new(Errno::EINVAL))?;
+            if !shm_seg.permission().is_owner_or_creator(&credentials) {
+                return_errno_with_message!(Errno::EPERM, "only the owner can change the segment");
+            }
+
+            let perm = &shmid_ds.shm_perm;
+            shm_seg.set_owner_and_mode(Uid::new(perm.uid), Gid::new(perm.gid), perm.mode as u16);
+        }
+        IpcControlCmd::IPC_STAT => {
+            let shm_seg = get_shm_seg(&ipc_ns, shmid).map_err(|_| Error::new(Errno::EINVAL))?;
+            let permission = shm_seg.permission();
+            permission.check_access(&credentials, 0o4)?;
+
+            let shmid_ds = ShmidDs {
+                shm_perm: IpcPerm64::from(&permission),
+                shm_segsz: shm_seg.size(),
+                shm_atime: shm_seg.atime().as_secs() as i64,
+                shm_dtime: shm_seg.dtime().as_secs() as i64,
+                shm_ctime: shm_seg.ctime().as_secs() as i64,
+                shm_cpid: shm_seg.cpid(),
+                shm_lpid: shm_seg.lpid(),
+                shm_nattch: shm_seg.nattch() as u64,
+                unused4: 0,
+                unused5: 0,
+            };
+            ctx.user_space().write_val(buf, &shmid_ds)?;
+        }
+        _ => return_errno_with_message!(Errno::EINVAL, "the shmctl command is not supported"),
+    }
+
+    Ok(SyscallReturn::Return(0))
+}
+
+/// The `shmid64_ds` structure, which is used by `IPC_STAT` and `IPC_SET`.
+#[derive(Debug, Clone, Copy, Pod)]
+#[repr(C)]
+struct ShmidDs {
+    shm_perm: IpcPerm64,
+    shm_segsz: usize,
+    shm_atime: i64,
+    shm_dtime: i64,
+    shm_ctime: i64,
+    shm_cpid: u32,
+    shm_lpid: u32,
+    shm_nattch: u64,
+    unused4: u64,
+    unused5: u64,
+}
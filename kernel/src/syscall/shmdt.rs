@@ -0,0 +1,13 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::SyscallReturn;
+use crate::{ipc::shm::shm_seg::detach_shm_seg, prelude::*};
+
+pub fn sys_shmdt(shmaddr: Vaddr, ctx: &Context) -> Result<SyscallReturn> {
+    debug!("[sys_shmdt] shmaddr = {:#x}", shmaddr);
+
+    let user_space = ctx.user_space();
+    detach_shm_seg(user_space.root_vmar(), shmaddr, ctx.process.pid())?;
+
+    Ok(SyscallReturn::Return(0))
+}
@@ -0,0 +1,62 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::SyscallReturn;
+use crate::{
+    ipc::{
+        shm::shm_seg::{create_shm_seg, find_shm_seg_by_key},
+        IpcFlags, IPC_PRIVATE,
+    },
+    prelude::*,
+};
+
+pub fn sys_shmget(key: i32, size: usize, shmflg: i32, ctx: &Context) -> Result<SyscallReturn> {
+    if key < 0 {
+        return_errno!(Errno::EINVAL);
+    }
+
+    let flags = IpcFlags::from_bits_truncate(shmflg as u32);
+    let mode: u16 = (shmflg as u32 & 0o777) as u16;
+    let credentials = ctx.posix_thread.credentials();
+    let pid = ctx.process.pid();
+    let ipc_ns = ctx.thread_local.borrow_ns_proxy().unwrap().ipc_ns().clone();
+
+    debug!(
+        "[sys_shmget] key = {}, size = {}, flags = {:?}",
+        key, size, shmflg
+    );
+
+    // Create a new segment directly
+    if key == IPC_PRIVATE {
+        return Ok(SyscallReturn::Return(
+            create_shm_seg(&ipc_ns, key, size, mode, &credentials, pid)? as isize,
+        ));
+    }
+
+    // Get a segment, and create if necessary
+    let id = match find_shm_seg_by_key(&ipc_ns, key) {
+        Some(shm_seg) => {
+            if flags.contains(IpcFlags::IPC_CREAT | IpcFlags::IPC_EXCL) {
+                return_errno!(Errno::EEXIST);
+            }
+            if size > shm_seg.size() {
+                return_errno_with_message!(Errno::EINVAL, "the segment is smaller than requested");
+            }
+
+            let requested = (mode >> 6) | (mode >> 3) | mode;
+            shm_seg
+                .permission()
+                .check_access(&credentials, requested & 0o7)?;
+
+            shm_seg.id()
+        }
+        None => {
+            if !flags.contains(IpcFlags::IPC_CREAT) {
+                return_errno_with_message!(Errno::ENOENT, "no segment exists for the key");
+            }
+
+            create_shm_seg(&ipc_ns, key, size, mode, &credentials, pid)?
+        }
+    };
+
+    Ok(SyscallReturn::Return(id as isize))
+}
@@ -4,11 +4,12 @@ use super::SyscallReturn;
 use crate::{
     fs::{file_handle::FileLike, file_table::FdFlags},
     net::socket::{
-        ip::{DatagramSocket, StreamSocket},
+        ip::{DatagramSocket, IpFamily, RawSocket, StreamSocket},
         netlink::{
             is_valid_protocol, NetlinkRouteSocket, NetlinkUeventSocket, StandardNetlinkProtocol,
         },
-        unix::UnixStreamSocket,
+        packet::PacketSocket,
+        unix::{UnixDatagramSocket, UnixStreamSocket},
         vsock::VsockStreamSocket,
     },
     prelude::*,
@@ -32,26 +33,43 @@ pub fn sys_socket(domain: i32, type_: i32, protocol: i32, ctx: &Context) -> Resu
         (CSocketAddrFamily::AF_UNIX, SockType::SOCK_SEQPACKET) => {
             UnixStreamSocket::new(is_nonblocking, true) as Arc<dyn FileLike>
         }
-        (CSocketAddrFamily::AF_INET, SockType::SOCK_STREAM) => {
+        (CSocketAddrFamily::AF_UNIX, SockType::SOCK_DGRAM) => {
+            UnixDatagramSocket::new(is_nonblocking) as Arc<dyn FileLike>
+        }
+        (CSocketAddrFamily::AF_INET | CSocketAddrFamily::AF_INET6, SockType::SOCK_STREAM) => {
+            let family = ip_family_of(domain);
             let protocol = Protocol::try_from(protocol)?;
             debug!("protocol = {:?}", protocol);
             match protocol {
                 Protocol::IPPROTO_IP | Protocol::IPPROTO_TCP => {
-                    StreamSocket::new(is_nonblocking) as Arc<dyn FileLike>
+                    StreamSocket::new(family, is_nonblocking) as Arc<dyn FileLike>
                 }
                 _ => return_errno_with_message!(Errno::EAFNOSUPPORT, "unsupported protocol"),
             }
         }
-        (CSocketAddrFamily::AF_INET, SockType::SOCK_DGRAM) => {
+        (CSocketAddrFamily::AF_INET | CSocketAddrFamily::AF_INET6, SockType::SOCK_DGRAM) => {
+            let family = ip_family_of(domain);
             let protocol = Protocol::try_from(protocol)?;
             debug!("protocol = {:?}", protocol);
             match protocol {
                 Protocol::IPPROTO_IP | Protocol::IPPROTO_UDP => {
-                    DatagramSocket::new(is_nonblocking) as Arc<dyn FileLike>
+                    DatagramSocket::new(family, is_nonblocking) as Arc<dyn FileLike>
+                }
+                Protocol::IPPROTO_ICMP => {
+                    DatagramSocket::new_ping(family, is_nonblocking)? as Arc<dyn FileLike>
                 }
                 _ => return_errno_with_message!(Errno::EAFNOSUPPORT, "unsupported protocol"),
             }
         }
+        (CSocketAddrFamily::AF_INET, SockType::SOCK_RAW) => {
+            debug!("protocol = {}", protocol);
+            RawSocket::new(protocol, is_nonblocking)? as Arc<dyn FileLike>
+        }
+        (CSocketAddrFamily::AF_PACKET, SockType::SOCK_RAW | SockType::SOCK_DGRAM) => {
+            debug!("protocol = {}", protocol);
+            let is_raw = matches!(sock_type, SockType::SOCK_RAW);
+            PacketSocket::new(is_raw, protocol, is_nonblocking)? as Arc<dyn FileLike>
+        }
         (CSocketAddrFamily::AF_NETLINK, SockType::SOCK_RAW | SockType::SOCK_DGRAM) => {
             let netlink_family = StandardNetlinkProtocol::try_from(protocol as u32);
             debug!("netlink family = {:?}", netlink_family);
@@ -98,3 +116,11 @@ pub fn sys_socket(domain: i32, type_: i32, protocol: i32, ctx: &Context) -> Resu
 
     Ok(SyscallReturn::Return(fd as _))
 }
+
+fn ip_family_of(domain: CSocketAddrFamily) -> IpFamily {
+    if domain == CSocketAddrFamily::AF_INET6 {
+        IpFamily::Ipv6
+    } else {
+        IpFamily::Ipv4
+    }
+}
@@ -2,8 +2,11 @@
 
 use super::SyscallReturn;
 use crate::{
-    fs::file_table::{FdFlags, FileDesc},
-    net::socket::unix::UnixStreamSocket,
+    fs::{
+        file_handle::FileLike,
+        file_table::{FdFlags, FileDesc},
+    },
+    net::socket::unix::{UnixDatagramSocket, UnixStreamSocket},
     prelude::*,
     util::net::{CSocketAddrFamily, Protocol, SockFlags, SockType, SOCK_TYPE_MASK},
 };
@@ -26,12 +29,18 @@ pub fn sys_socketpair(
 
     // TODO: deal with all sock_flags and protocol
     let nonblocking = sock_flags.contains(SockFlags::SOCK_NONBLOCK);
-    let (socket_a, socket_b) = match (domain, sock_type) {
+    let (socket_a, socket_b): (Arc<dyn FileLike>, Arc<dyn FileLike>) = match (domain, sock_type) {
         (CSocketAddrFamily::AF_UNIX, SockType::SOCK_STREAM) => {
-            UnixStreamSocket::new_pair(nonblocking, false)
+            let (socket_a, socket_b) = UnixStreamSocket::new_pair(nonblocking, false);
+            (socket_a, socket_b)
         }
         (CSocketAddrFamily::AF_UNIX, SockType::SOCK_SEQPACKET) => {
-            UnixStreamSocket::new_pair(nonblocking, true)
+            let (socket_a, socket_b) = UnixStreamSocket::new_pair(nonblocking, true);
+            (socket_a, socket_b)
+        }
+        (CSocketAddrFamily::AF_UNIX, SockType::SOCK_DGRAM) => {
+            let (socket_a, socket_b) = UnixDatagramSocket::new_pair(nonblocking);
+            (socket_a, socket_b)
         }
         _ => return_errno_with_message!(
             Errno::EAFNOSUPPORT,
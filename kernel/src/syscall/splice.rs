@@ -0,0 +1,299 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use core::ops::Range;
+
+use ostd::mm::{io_util::HasVmReaderWriter, FrameAllocOptions, UFrame};
+
+use super::SyscallReturn;
+use crate::{
+    fs::{
+        file_handle::FileLike,
+        file_table::{get_file_fast, FileDesc, WithFileTable},
+        inode_handle::InodeHandle,
+        pipe::{PipeReader, PipeWriter},
+        utils::{SeekFrom, StatusFlags},
+    },
+    prelude::*,
+    util::{VmReaderArray, VmWriterArray},
+};
+
+pub fn sys_splice(
+    fd_in: FileDesc,
+    off_in_ptr: Vaddr,
+    fd_out: FileDesc,
+    off_out_ptr: Vaddr,
+    len: usize,
+    flags: u32,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    let flags = SpliceFlags::from_bits(flags)
+        .ok_or_else(|| Error::with_message(Errno::EINVAL, "unknown flags"))?;
+    debug!(
+        "fd_in = {}, off_in_ptr = 0x{:x}, fd_out = {}, off_out_ptr = 0x{:x}, len = 0x{:x}, flags = {:?}",
+        fd_in, off_in_ptr, fd_out, off_out_ptr, len, flags
+    );
+
+    let (file_in, file_out) = ctx
+        .thread_local
+        .borrow_file_table_mut()
+        .read_with(|inner| {
+            let file_in = inner.get_file(fd_in)?.clone();
+            let file_out = inner.get_file(fd_out)?.clone();
+            Ok::<_, Error>((file_in, file_out))
+        })?;
+    if !file_in.access_mode().is_readable() {
+        return_errno_with_message!(Errno::EBADF, "the input file is not readable");
+    }
+    if !file_out.access_mode().is_writable() {
+        return_errno_with_message!(Errno::EBADF, "the output file is not writable");
+    }
+
+    // Only `SPLICE_F_NONBLOCK` decides whether to block on the pipes, regardless of the
+    // `O_NONBLOCK` flags of the pipes. The other flags are hints, which are ignored.
+    let is_nonblocking = flags.contains(SpliceFlags::SPLICE_F_NONBLOCK);
+
+    let spliced_len = match (
+        file_in.downcast_ref::<PipeReader>(),
+        file_out.downcast_ref::<PipeWriter>(),
+    ) {
+        (Some(reader), Some(writer)) => {
+            if off_in_ptr != 0 || off_out_ptr != 0 {
+                return_errno_with_message!(Errno::ESPIPE, "the offset of a pipe is not allowed");
+            }
+            reader.splice_to_pipe(writer, len, false, is_nonblocking)?
+        }
+        (Some(reader), None) => {
+            if off_in_ptr != 0 {
+                return_errno_with_message!(Errno::ESPIPE, "the offset of a pipe is not allowed");
+            }
+            if file_out.status_flags().contains(StatusFlags::O_APPEND) {
+                return_errno_with_message!(Errno::EINVAL, "the output file is append-only");
+            }
+
+            let mut offset = read_offset(off_out_ptr, ctx)?;
+            let spliced_len =
+                splice_pipe_to_file(reader, &file_out, offset.as_mut(), len, is_nonblocking)?;
+            write_offset(off_out_ptr, offset, ctx)?;
+            spliced_len
+        }
+        (None, Some(writer)) => {
+            if off_out_ptr != 0 {
+                return_errno_with_message!(Errno::ESPIPE, "the offset of a pipe is not allowed");
+            }
+
+            let mut offset = read_offset(off_in_ptr, ctx)?;
+            let spliced_len =
+                splice_file_to_pipe(&file_in, offset.as_mut(), writer, len, is_nonblocking)?;
+            write_offset(off_in_ptr, offset, ctx)?;
+            spliced_len
+        }
+        (None, None) => {
+            return_errno_with_message!(Errno::EINVAL, "neither of the files is a pipe");
+        }
+    };
+
+    Ok(SyscallReturn::Return(spliced_len as _))
+}
+
+pub fn sys_tee(
+    fd_in: FileDesc,
+    fd_out: FileDesc,
+    len: usize,
+    flags: u32,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    let flags = SpliceFlags::from_bits(flags)
+        .ok_or_else(|| Error::with_message(Errno::EINVAL, "unknown flags"))?;
+    debug!(
+        "fd_in = {}, fd_out = {}, len = 0x{:x}, flags = {:?}",
+        fd_in, fd_out, len, flags
+    );
+
+    let (file_in, file_out) = ctx
+        .thread_local
+        .borrow_file_table_mut()
+        .read_with(|inner| {
+            let file_in = inner.get_file(fd_in)?.clone();
+            let file_out = inner.get_file(fd_out)?.clone();
+            Ok::<_, Error>((file_in, file_out))
+        })?;
+
+    let (Some(reader), Some(writer)) = (
+        file_in.downcast_ref::<PipeReader>(),
+        file_out.downcast_ref::<PipeWriter>(),
+    ) else {
+        return_errno_with_message!(Errno::EINVAL, "the files are not a pipe pair");
+    };
+    let is_nonblocking = flags.contains(SpliceFlags::SPLICE_F_NONBLOCK);
+    let teed_len = reader.splice_to_pipe(writer, len, true, is_nonblocking)?;
+
+    Ok(SyscallReturn::Return(teed_len as _))
+}
+
+pub fn sys_vmsplice(
+    fd: FileDesc,
+    io_vec_ptr: Vaddr,
+    io_vec_count: usize,
+    flags: u32,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    let flags = SpliceFlags::from_bits(flags)
+        .ok_or_else(|| Error::with_message(Errno::EINVAL, "unknown flags"))?;
+    debug!(
+        "fd = {}, io_vec_ptr = 0x{:x}, io_vec_count = 0x{:x}, flags = {:?}",
+        fd, io_vec_ptr, io_vec_count, flags
+    );
+
+    if io_vec_count > IOV_MAX {
+        return_errno_with_message!(Errno::EINVAL, "too many I/O vectors");
+    }
+
+    let mut file_table = ctx.thread_local.borrow_file_table_mut();
+    let file = get_file_fast!(&mut file_table, fd).into_owned();
+    // The operations below may block, so the file table must not be borrowed.
+    drop(file_table);
+
+    // The user pages cannot be given to the pipe because there is no copy-on-write support for
+    // the pages referenced by the pipe. So `SPLICE_F_GIFT` is ignored and the data are copied.
+    let is_nonblocking = flags.contains(SpliceFlags::SPLICE_F_NONBLOCK);
+    let user_space = ctx.user_space();
+    let len = if let Some(writer) = file.downcast_ref::<PipeWriter>() {
+        let mut readers = VmReaderArray::from_user_io_vecs(&user_space, io_vec_ptr, io_vec_count)?;
+        writer.write_from(&mut readers, is_nonblocking)?
+    } else if let Some(reader) = file.downcast_ref::<PipeReader>() {
+        let mut writers = VmWriterArray::from_user_io_vecs(&user_space, io_vec_ptr, io_vec_count)?;
+        reader.read_to(&mut writers, is_nonblocking)?
+    } else {
+        return_errno_with_message!(Errno::EBADF, "the file is not a pipe");
+    };
+
+    Ok(SyscallReturn::Return(len as _))
+}
+
+/// Moves the data from the pipe of `reader` to `file`.
+///
+/// The data are written to `file` at `offset` if it is given, or at the file offset otherwise.
+fn splice_pipe_to_file(
+    reader: &PipeReader,
+    file: &Arc<dyn FileLike>,
+    mut offset: Option<&mut usize>,
+    len: usize,
+    is_nonblocking: bool,
+) -> Result<usize> {
+    reader.splice_to(len, is_nonblocking, |frag_reader| {
+        let mut frag_reader = frag_reader.to_fallible();
+        match offset.as_mut() {
+            Some(offset) => {
+                let written_len = file.write_at(**offset, &mut frag_reader)?;
+                **offset += written_len;
+                Ok(written_len)
+            }
+            None => file.write(&mut frag_reader),
+        }
+    })
+}
+
+/// Moves the data from `file` to the pipe of `writer`.
+///
+/// The data are read from `file` at `offset` if it is given, or at the file offset otherwise. If
+/// the file is backed by the page cache, the pages in the page cache are moved to the pipe
+/// without copying the data.
+fn splice_file_to_pipe(
+    file: &Arc<dyn FileLike>,
+    mut offset: Option<&mut usize>,
+    writer: &PipeWriter,
+    len: usize,
+    is_nonblocking: bool,
+) -> Result<usize> {
+    let inode_handle = file.downcast_ref::<InodeHandle>();
+
+    writer.splice_from(len, is_nonblocking, |max_len, max_pages| {
+        if let Some(inode_handle) = inode_handle {
+            let pos = match offset.as_deref() {
+                Some(offset) => *offset,
+                None => inode_handle.offset(),
+            };
+
+            if let Some(pages) = inode_handle.read_pages_at(pos, max_len, max_pages)? {
+                let read_len = pages.iter().map(|(_, range)| range.len()).sum::<usize>();
+                match offset.as_mut() {
+                    Some(offset) => **offset += read_len,
+                    None => {
+                        inode_handle.seek(SeekFrom::Start(pos + read_len))?;
+                    }
+                }
+                return Ok(pages);
+            }
+        }
+
+        read_to_new_pages(
+            file,
+            offset.as_deref_mut(),
+            max_len.min(max_pages * PAGE_SIZE),
+        )
+    })
+}
+
+/// Reads at most `max_len` bytes from `file` into newly allocated pages.
+fn read_to_new_pages(
+    file: &Arc<dyn FileLike>,
+    offset: Option<&mut usize>,
+    max_len: usize,
+) -> Result<Vec<(UFrame, Range<usize>)>> {
+    let segment = FrameAllocOptions::new()
+        .zeroed(false)
+        .alloc_segment(max_len.div_ceil(PAGE_SIZE))?;
+
+    let read_len = {
+        let mut writer = segment.writer().to_fallible();
+        writer.limit(max_len);
+        match offset {
+            Some(offset) => {
+                let read_len = file.read_at(*offset, &mut writer)?;
+                *offset += read_len;
+                read_len
+            }
+            None => file.read(&mut writer)?,
+        }
+    };
+
+    let pages = segment
+        .take(read_len.div_ceil(PAGE_SIZE))
+        .enumerate()
+        .map(|(idx, frame)| {
+            let len = (read_len - idx * PAGE_SIZE).min(PAGE_SIZE);
+            (frame.into(), 0..len)
+        })
+        .collect();
+    Ok(pages)
+}
+
+pub(super) fn read_offset(offset_ptr: Vaddr, ctx: &Context) -> Result<Option<usize>> {
+    if offset_ptr == 0 {
+        return Ok(None);
+    }
+
+    let offset: i64 = ctx.user_space().read_val(offset_ptr)?;
+    if offset < 0 {
+        return_errno_with_message!(Errno::EINVAL, "offset cannot be negative");
+    }
+    Ok(Some(offset as usize))
+}
+
+fn write_offset(offset_ptr: Vaddr, offset: Option<usize>, ctx: &Context) -> Result<()> {
+    if let Some(offset) = offset {
+        ctx.user_space().write_val(offset_ptr, &(offset as i64))?;
+    }
+    Ok(())
+}
+
+bitflags! {
+    struct SpliceFlags: u32 {
+        const SPLICE_F_MOVE = 1 << 0;
+        const SPLICE_F_NONBLOCK = 1 << 1;
+        const SPLICE_F_MORE = 1 << 2;
+        const SPLICE_F_GIFT = 1 << 3;
+    }
+}
+
+const IOV_MAX: usize = 1024;
@@ -0,0 +1,107 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::SyscallReturn;
+use crate::{
+    fs::{
+        fs_resolver::{FsPath, AT_FDCWD},
+        utils::InodeType,
+    },
+    prelude::*,
+    process::credentials::capabilities::CapSet,
+    syscall::constants::MAX_FILENAME_LEN,
+    vm::swap::{self, SwapBackend},
+};
+
+pub fn sys_swapon(path_addr: Vaddr, flags: i32, ctx: &Context) -> Result<SyscallReturn> {
+    let path_name = ctx.user_space().read_cstring(path_addr, MAX_FILENAME_LEN)?;
+    let priority = flags as u32 & SWAP_FLAG_PRIO_MASK;
+    let flags = SwapFlags::from_bits(flags as u32 & !SWAP_FLAG_PRIO_MASK)
+        .ok_or_else(|| Error::with_message(Errno::EINVAL, "unknown flags"))?;
+    debug!(
+        "path = {:?}, flags = {:?}, priority = {}",
+        path_name, flags, priority
+    );
+
+    check_sys_admin(ctx)?;
+
+    // The discard flags are accepted but ignored, since discarding is only an
+    // optimization for the backends.
+    let priority = if flags.contains(SwapFlags::SWAP_FLAG_PREFER) {
+        Some(priority as i16)
+    } else {
+        None
+    };
+
+    let backend = lookup_backend(&path_name.to_string_lossy(), ctx)?;
+    swap::swap_on(backend, priority)?;
+
+    Ok(SyscallReturn::Return(0))
+}
+
+pub fn sys_swapoff(path_addr: Vaddr, ctx: &Context) -> Result<SyscallReturn> {
+    let path_name = ctx.user_space().read_cstring(path_addr, MAX_FILENAME_LEN)?;
+    debug!("path = {:?}", path_name);
+
+    check_sys_admin(ctx)?;
+
+    let backend = lookup_backend(&path_name.to_string_lossy(), ctx)?;
+    swap::swap_off(&backend)?;
+
+    Ok(SyscallReturn::Return(0))
+}
+
+fn check_sys_admin(ctx: &Context) -> Result<()> {
+    if !ctx
+        .posix_thread
+        .credentials()
+        .effective_capset()
+        .contains(CapSet::SYS_ADMIN)
+    {
+        return_errno_with_message!(Errno::EPERM, "swapping requires CAP_SYS_ADMIN");
+    }
+    Ok(())
+}
+
+/// Looks up the block device or the regular file at `path_name`.
+///
+/// Like `mount`, a block device is named after its device name, optionally prefixed
+/// with `/dev/`.
+fn lookup_backend(path_name: &str, ctx: &Context) -> Result<SwapBackend> {
+    if path_name.is_empty() {
+        return_errno_with_message!(Errno::ENOENT, "path is empty");
+    }
+
+    let device_name = path_name.strip_prefix("/dev/").unwrap_or(path_name);
+    if let Some(device) = aster_block::get_device(device_name) {
+        return Ok(SwapBackend::BlockDevice(device));
+    }
+
+    let fs_path = FsPath::new(AT_FDCWD, path_name)?;
+    let path = ctx
+        .thread_local
+        .borrow_fs()
+        .resolver()
+        .read()
+        .lookup(&fs_path)?;
+    if path.type_() != InodeType::File {
+        return_errno_with_message!(Errno::EINVAL, "the path is not a block device or a file");
+    }
+
+    Ok(SwapBackend::File(path.inode().clone()))
+}
+
+/// The mask of the priority in the flags of `swapon`.
+const SWAP_FLAG_PRIO_MASK: u32 = 0x7fff;
+
+bitflags! {
+    struct SwapFlags: u32 {
+        /// Set the priority in `SWAP_FLAG_PRIO_MASK`.
+        const SWAP_FLAG_PREFER        = 0x8000;
+        /// Discard the freed swap slots.
+        const SWAP_FLAG_DISCARD       = 0x10000;
+        /// Discard the whole swap area once at `swapon`.
+        const SWAP_FLAG_DISCARD_ONCE  = 0x20000;
+        /// Discard the swap slots when they are freed.
+        const SWAP_FLAG_DISCARD_PAGES = 0x40000;
+    }
+}
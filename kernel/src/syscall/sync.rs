@@ -1,9 +1,90 @@
 // SPDX-License-Identifier: MPL-2.0
 
 use super::SyscallReturn;
-use crate::prelude::*;
+use crate::{
+    fs::{
+        file_table::{get_file_fast, FileDesc},
+        utils::InodeType,
+    },
+    prelude::*,
+};
 
-pub fn sys_sync(_ctx: &Context) -> Result<SyscallReturn> {
-    crate::fs::rootfs::root_mount().sync()?;
+pub fn sys_sync(ctx: &Context) -> Result<SyscallReturn> {
+    let ns_proxy = ctx.thread_local.borrow_ns_proxy();
+    ns_proxy.unwrap().mnt_ns().root().sync()?;
     Ok(SyscallReturn::Return(0))
 }
+
+pub fn sys_syncfs(fd: FileDesc, ctx: &Context) -> Result<SyscallReturn> {
+    debug!("fd = {}", fd);
+
+    let mut file_table = ctx.thread_local.borrow_file_table_mut();
+    let file = get_file_fast!(&mut file_table, fd);
+    // The files not related to an inode (e.g., sockets and pipes) have nothing to sync.
+    if let Ok(inode_handle) = file.as_inode_or_err() {
+        inode_handle.path().fs().sync()?;
+    }
+    Ok(SyscallReturn::Return(0))
+}
+
+pub fn sys_sync_file_range(
+    fd: FileDesc,
+    offset: i64,
+    nbytes: i64,
+    flags: u32,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    debug!(
+        "fd = {}, offset = {}, nbytes = {}, flags = {:#x}",
+        fd, offset, nbytes, flags
+    );
+
+    let flags = SyncFileRangeFlags::from_bits(flags)
+        .ok_or_else(|| Error::with_message(Errno::EINVAL, "invalid flags"))?;
+    if offset < 0 || nbytes < 0 {
+        return_errno_with_message!(Errno::EINVAL, "the offset or the length is negative");
+    }
+    let end = offset
+        .checked_add(nbytes)
+        .ok_or_else(|| Error::with_message(Errno::EINVAL, "the range overflows"))?;
+    // A length of zero means the range extends to the end of the file.
+    let range = if nbytes == 0 {
+        offset as usize..usize::MAX
+    } else {
+        offset as usize..end as usize
+    };
+
+    let mut file_table = ctx.thread_local.borrow_file_table_mut();
+    let file = get_file_fast!(&mut file_table, fd);
+    let path = file
+        .as_inode_or_err()
+        .map_err(|_| Error::with_message(Errno::ESPIPE, "the file cannot be synced"))?
+        .path();
+    if !matches!(
+        path.type_(),
+        InodeType::File | InodeType::Dir | InodeType::SymLink
+    ) {
+        return_errno_with_message!(Errno::ESPIPE, "the file cannot be synced");
+    }
+
+    // The writeback is synchronous, so there is no writeback in progress to wait for
+    // before or after it.
+    if flags.contains(SyncFileRangeFlags::WRITE) {
+        path.inode().sync_data_range(range)?;
+    }
+    Ok(SyscallReturn::Return(0))
+}
+
+bitflags! {
+    /// Flags for `sync_file_range`.
+    ///
+    /// See <https://elixir.bootlin.com/linux/v6.15.1/source/include/uapi/linux/fs.h>.
+    struct SyncFileRangeFlags: u32 {
+        /// Waits for the writeback of the pages in the range before writing them.
+        const WAIT_BEFORE = 1;
+        /// Starts the writeback of the dirty pages in the range.
+        const WRITE = 2;
+        /// Waits for the writeback of the pages in the range after writing them.
+        const WAIT_AFTER = 4;
+    }
+}
@@ -23,6 +23,11 @@ pub fn sys_tgkill(tgid: Pid, tid: Tid, sig_num: u8, ctx: &Context) -> Result<Sys
 
     debug!("tgid = {}, pid = {}, sig_num = {:?}", tgid, tid, sig_num);
 
+    let pid_ns = ctx.process.pid_ns();
+    let (Some(tgid), Some(tid)) = (pid_ns.global_id(tgid), pid_ns.global_id(tid)) else {
+        return_errno_with_message!(Errno::ESRCH, "the thread is not visible in the namespace");
+    };
+
     let signal = sig_num.map(|sig_num| {
         let pid = ctx.process.pid();
         let uid = ctx.posix_thread.credentials().ruid();
@@ -5,6 +5,7 @@ use crate::{
     fs::{
         file_table::{get_file_fast, FileDesc},
         fs_resolver::{FsPath, AT_FDCWD},
+        inode_handle::check_not_swap_file,
         utils::PATH_MAX,
     },
     prelude::*,
@@ -40,6 +41,7 @@ pub fn sys_truncate(path_ptr: Vaddr, len: isize, ctx: &Context) -> Result<Syscal
             .read()
             .lookup(&fs_path)?
     };
+    check_not_swap_file(dir_path.inode())?;
     dir_path.resize(len as usize)?;
     Ok(SyscallReturn::Return(0))
 }
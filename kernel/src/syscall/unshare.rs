@@ -0,0 +1,91 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use ostd::sync::RwArc;
+
+use super::SyscallReturn;
+use crate::{
+    prelude::*,
+    process::{namespace::NsProxy, CloneFlags},
+};
+
+pub fn sys_unshare(flags: u64, ctx: &Context) -> Result<SyscallReturn> {
+    let mut flags = u32::try_from(flags)
+        .ok()
+        .and_then(CloneFlags::from_bits)
+        .ok_or_else(|| Error::with_message(Errno::EINVAL, "invalid unshare flags"))?;
+    debug!("flags = {:?}", flags);
+
+    let supported_flags = CloneFlags::CLONE_VM
+        | CloneFlags::CLONE_FS
+        | CloneFlags::CLONE_FILES
+        | CloneFlags::CLONE_SIGHAND
+        | CloneFlags::CLONE_THREAD
+        | CloneFlags::CLONE_SYSVSEM
+        | NsProxy::CLONE_NS_FLAGS;
+    if !supported_flags.contains(flags) {
+        return_errno_with_message!(Errno::EINVAL, "unsupported unshare flags");
+    }
+
+    // Reference: <https://elixir.bootlin.com/linux/v6.15/source/kernel/fork.c#L3393>
+    if flags.contains(CloneFlags::CLONE_NEWUSER) {
+        flags |= CloneFlags::CLONE_THREAD | CloneFlags::CLONE_FS;
+    }
+    if flags.contains(CloneFlags::CLONE_NEWNS) {
+        flags |= CloneFlags::CLONE_FS;
+    }
+
+    // The thread group, the signal handlers and the address space can only be "unshared" if they
+    // are not shared at all.
+    if flags.intersects(CloneFlags::CLONE_THREAD | CloneFlags::CLONE_SIGHAND | CloneFlags::CLONE_VM)
+        && ctx.process.tasks().lock().as_slice().len() > 1
+    {
+        return_errno_with_message!(
+            Errno::EINVAL,
+            "the thread group, signal handlers or address space cannot be unshared"
+        );
+    }
+
+    if flags.contains(CloneFlags::CLONE_SYSVSEM) {
+        warn!("CLONE_SYSVSEM is not supported now");
+    }
+
+    // Prepare all the new resources first, so nothing changes if an error occurs.
+    let new_fs = flags
+        .contains(CloneFlags::CLONE_FS)
+        .then(|| Arc::new(ctx.thread_local.borrow_fs().as_ref().clone()));
+
+    let new_file_table = flags.contains(CloneFlags::CLONE_FILES).then(|| {
+        let file_table = ctx.thread_local.borrow_file_table();
+        RwArc::new(file_table.unwrap().read().clone())
+    });
+
+    let ns_flags = flags & NsProxy::CLONE_NS_FLAGS;
+    let new_ns_proxy = if ns_flags.is_empty() {
+        None
+    } else {
+        let ns_proxy = ctx.thread_local.borrow_ns_proxy().unwrap().clone();
+        // A new mount or user namespace always comes with new filesystem information.
+        let fs = new_fs
+            .clone()
+            .unwrap_or_else(|| ctx.thread_local.borrow_fs().clone());
+        let mut fs_resolver = fs.resolver().write();
+        Some(ns_proxy.new_with_flags(ns_flags, ctx, &mut fs_resolver)?)
+    };
+
+    if let Some(new_fs) = new_fs {
+        *ctx.posix_thread.fs().lock() = Arc::downgrade(&new_fs);
+        *ctx.thread_local.borrow_fs_mut() = new_fs;
+    }
+    if let Some(new_file_table) = new_file_table {
+        *ctx.posix_thread.file_table().lock() = Some(new_file_table.clone_ro());
+        let _ = ctx
+            .thread_local
+            .borrow_file_table_mut()
+            .replace(Some(new_file_table));
+    }
+    if let Some(new_ns_proxy) = new_ns_proxy {
+        new_ns_proxy.install(ctx);
+    }
+
+    Ok(SyscallReturn::Return(0))
+}
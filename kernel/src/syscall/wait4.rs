@@ -20,7 +20,7 @@ pub fn sys_wait4(
         wait_pid as i32, status_ptr, wait_options
     );
     debug!("wait4 current pid = {}", ctx.process.pid());
-    let process_filter = ProcessFilter::from_id(wait_pid as _);
+    let process_filter = ProcessFilter::from_id(wait_pid as _, ctx, Errno::ECHILD)?;
 
     let wait_status =
         do_wait(process_filter, wait_options, ctx).map_err(|err| match err.error() {
@@ -31,7 +31,12 @@ pub fn sys_wait4(
         return Ok(SyscallReturn::Return(0 as _));
     };
 
-    let (return_pid, status_code) = (wait_status.pid(), calculate_status_code(&wait_status));
+    let return_pid = ctx
+        .process
+        .pid_ns()
+        .local_id(wait_status.pid())
+        .unwrap_or(0);
+    let status_code = calculate_status_code(&wait_status);
     if status_ptr != 0 {
         ctx.user_space().write_val(status_ptr as _, &status_code)?;
     }
@@ -54,5 +59,7 @@ fn calculate_status_code(wait_status: &WaitStatus) -> u32 {
         WaitStatus::Zombie(process) => process.status().exit_code(),
         WaitStatus::Stop(_, sig_num) => ((sig_num.as_u8() as u32) << 8) | 0x7f,
         WaitStatus::Continue(_) => 0xffff,
+        WaitStatus::PtraceStop(_, status) => (status << 8) | 0x7f,
+        WaitStatus::PtraceExit(_, exit_code) => *exit_code,
     }
 }
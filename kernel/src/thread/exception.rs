@@ -15,7 +15,7 @@ use crate::{
     current_userspace,
     prelude::*,
     process::signal::signals::fault::FaultSignal,
-    vm::{page_fault_handler::PageFaultHandler, perms::VmPerms, vmar::Vmar},
+    vm::{page_fault_handler::PageFaultHandler, perms::VmPerms, reclaim, vmar::Vmar},
 };
 
 /// Page fault information converted from [`CpuExceptionInfo`].
@@ -52,7 +52,8 @@ fn handle_page_fault_from_vmar(
     root_vmar: &Vmar<Full>,
     page_fault_info: &PageFaultInfo,
 ) -> core::result::Result<(), ()> {
-    if let Err(e) = root_vmar.handle_page_fault(page_fault_info) {
+    // The page fault may fail for the lack of memory, which is reclaimed before retrying.
+    if let Err(e) = reclaim::alloc_or_oom(|| root_vmar.handle_page_fault(page_fault_info)) {
         warn!(
             "page fault handler failed: addr: 0x{:x}, err: {:?}",
             page_fault_info.address, e
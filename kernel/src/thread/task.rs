@@ -58,8 +58,12 @@ pub fn create_new_user_task(
         // Make sure the store operation completes before the clone call returns control to user space
         // in the child process.
         if is_userspace_vaddr(child_tid_ptr) {
+            let child_tid = current_process
+                .pid_ns()
+                .local_id(current_posix_thread.tid())
+                .unwrap();
             current_userspace!()
-                .write_val(child_tid_ptr, &current_posix_thread.tid())
+                .write_val(child_tid_ptr, &child_tid)
                 .unwrap();
         }
 
@@ -73,6 +77,13 @@ pub fn create_new_user_task(
             task: &current_task,
         };
 
+        // Handle the signals that are sent before the thread starts to run, e.g., the `SIGSTOP`
+        // that stops a new thread traced by `ptrace`. The untraced threads handle their signals
+        // after they return from the user mode, as usual.
+        if current_posix_thread.ptrace_state().is_traced() {
+            handle_pending_signal(user_mode.context_mut(), &ctx, None);
+        }
+
         while !current_thread.is_exited() {
             // Execute the user code
             ctx.thread_local.fpu().activate();
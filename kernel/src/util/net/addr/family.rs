@@ -4,7 +4,13 @@ use core::cmp::min;
 
 use ostd::task::Task;
 
-use super::{ip::CSocketAddrInet, netlink::CSocketAddrNetlink, unix, vsock::CSocketAddrVm};
+use super::{
+    ip::{CSocketAddrInet, CSocketAddrInet6, SIN6_LEN_RFC2133},
+    netlink::CSocketAddrNetlink,
+    packet::CSocketAddrLl,
+    unix,
+    vsock::CSocketAddrVm,
+};
 use crate::{current_userspace, net::socket::util::SocketAddr, prelude::*};
 
 /// Address family.
@@ -158,6 +164,14 @@ pub fn read_socket_addr_from_user(addr: Vaddr, addr_len: usize) -> Result<Socket
             let (addr, port) = CSocketAddrInet::from_bytes(storage.as_bytes()).into();
             SocketAddr::IPv4(addr, port)
         }
+        Ok(CSocketAddrFamily::AF_INET6) => {
+            // Like Linux, the trailing `sin6_scope_id` is optional.
+            if addr_len < SIN6_LEN_RFC2133 {
+                return_errno_with_message!(Errno::EINVAL, "the socket address length is too small");
+            }
+            let (addr, port) = CSocketAddrInet6::from_bytes(storage.as_bytes()).into();
+            SocketAddr::IPv6(addr, port)
+        }
         Ok(CSocketAddrFamily::AF_UNIX) => {
             let addr = unix::from_c_bytes(&storage.as_bytes()[..addr_len])?;
             SocketAddr::Unix(addr)
@@ -176,6 +190,13 @@ pub fn read_socket_addr_from_user(addr: Vaddr, addr_len: usize) -> Result<Socket
             let addr = CSocketAddrVm::from_bytes(storage.as_bytes());
             SocketAddr::Vsock(addr.into())
         }
+        Ok(CSocketAddrFamily::AF_PACKET) => {
+            if addr_len < size_of::<CSocketAddrLl>() {
+                return_errno_with_message!(Errno::EINVAL, "the socket address length is too small");
+            }
+            let addr = CSocketAddrLl::from_bytes(storage.as_bytes());
+            SocketAddr::Packet(addr.into())
+        }
         _ => {
             return_errno_with_message!(
                 Errno::EAFNOSUPPORT,
@@ -251,6 +272,11 @@ pub fn write_socket_addr_with_max_len(
             dest,
             max_len as usize,
         )?,
+        SocketAddr::IPv6(addr, port) => write_c_socket_address_util::<CSocketAddrInet6, _>(
+            (*addr, *port),
+            dest,
+            max_len as usize,
+        )?,
         SocketAddr::Unix(addr) => unix::into_c_bytes_and(addr, |bytes| {
             let written_len = min(bytes.len(), max_len as _);
             current_userspace!().write_bytes(dest, &mut VmReader::from(&bytes[..written_len]))?;
@@ -262,6 +288,9 @@ pub fn write_socket_addr_with_max_len(
         SocketAddr::Vsock(addr) => {
             write_c_socket_address_util::<CSocketAddrVm, _>(*addr, dest, max_len as usize)?
         }
+        SocketAddr::Packet(addr) => {
+            write_c_socket_address_util::<CSocketAddrLl, _>(*addr, dest, max_len as usize)?
+        }
     };
 
     Ok(actual_len as i32)
@@ -1,6 +1,6 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use aster_bigtcp::wire::{Ipv4Address, PortNum};
+use aster_bigtcp::wire::{Ipv4Address, Ipv6Address, PortNum};
 
 use super::family::CSocketAddrFamily;
 use crate::prelude::*;
@@ -43,6 +43,50 @@ impl From<CSocketAddrInet> for (Ipv4Address, PortNum) {
     }
 }
 
+/// IPv6 socket address.
+///
+/// See <https://www.man7.org/linux/man-pages/man7/ipv6.7.html>.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+pub(super) struct CSocketAddrInet6 {
+    /// Address family (AF_INET6).
+    sin6_family: u16,
+    /// Port number.
+    sin6_port: CPortNum,
+    /// IPv6 flow information.
+    sin6_flowinfo: u32,
+    /// IPv6 address.
+    sin6_addr: CIn6Addr,
+    /// Scope ID.
+    sin6_scope_id: u32,
+}
+
+/// The length of the IPv6 socket address defined in RFC 2133, which lacks `sin6_scope_id`.
+///
+/// Linux accepts socket addresses of this length. See `SIN6_LEN_RFC2133` in
+/// <https://elixir.bootlin.com/linux/v6.10.2/source/include/net/ipv6.h>.
+pub(super) const SIN6_LEN_RFC2133: usize = 24;
+
+// FIXME: The flow information and the scope ID are currently ignored.
+impl From<(Ipv6Address, PortNum)> for CSocketAddrInet6 {
+    fn from(value: (Ipv6Address, PortNum)) -> Self {
+        Self {
+            sin6_family: CSocketAddrFamily::AF_INET6 as u16,
+            sin6_port: value.1.into(),
+            sin6_flowinfo: 0,
+            sin6_addr: value.0.into(),
+            sin6_scope_id: 0,
+        }
+    }
+}
+
+impl From<CSocketAddrInet6> for (Ipv6Address, PortNum) {
+    fn from(value: CSocketAddrInet6) -> Self {
+        debug_assert_eq!(value.sin6_family, CSocketAddrFamily::AF_INET6 as u16);
+        (value.sin6_addr.into(), value.sin6_port.into())
+    }
+}
+
 /// IPv4 4-byte address.
 #[repr(C)]
 #[derive(Debug, Clone, Copy, Pod)]
@@ -64,6 +108,27 @@ impl From<CInetAddr> for Ipv4Address {
     }
 }
 
+/// IPv6 16-byte address.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+struct CIn6Addr {
+    s6_addr: [u8; 16],
+}
+
+impl From<Ipv6Address> for CIn6Addr {
+    fn from(value: Ipv6Address) -> Self {
+        Self {
+            s6_addr: value.octets(),
+        }
+    }
+}
+
+impl From<CIn6Addr> for Ipv6Address {
+    fn from(value: CIn6Addr) -> Self {
+        Self::from(value.s6_addr)
+    }
+}
+
 /// TCP/UDP port number.
 #[repr(C)]
 #[derive(Debug, Clone, Copy, Pod)]
@@ -0,0 +1,54 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::family::CSocketAddrFamily;
+use crate::{net::socket::packet::PacketSocketAddr, prelude::*};
+
+/// Link-layer socket address.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.0.9/source/include/uapi/linux/if_packet.h#L14>
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+pub(super) struct CSocketAddrLl {
+    /// Address family (AF_PACKET).
+    sll_family: u16,
+    /// Link-layer protocol in network byte order.
+    sll_protocol: u16,
+    /// Interface index.
+    sll_ifindex: i32,
+    /// ARP hardware type.
+    sll_hatype: u16,
+    /// Packet type.
+    sll_pkttype: u8,
+    /// Length of the link-layer address.
+    sll_halen: u8,
+    /// Link-layer address.
+    sll_addr: [u8; 8],
+}
+
+impl From<PacketSocketAddr> for CSocketAddrLl {
+    fn from(value: PacketSocketAddr) -> Self {
+        Self {
+            sll_family: CSocketAddrFamily::AF_PACKET as u16,
+            sll_protocol: value.protocol.to_be(),
+            sll_ifindex: value.ifindex as i32,
+            sll_hatype: value.hatype,
+            sll_pkttype: value.packet_type,
+            sll_halen: value.hw_addr_len,
+            sll_addr: value.hw_addr,
+        }
+    }
+}
+
+impl From<CSocketAddrLl> for PacketSocketAddr {
+    fn from(value: CSocketAddrLl) -> Self {
+        debug_assert_eq!(value.sll_family, CSocketAddrFamily::AF_PACKET as u16);
+        Self {
+            protocol: u16::from_be(value.sll_protocol),
+            ifindex: value.sll_ifindex as u32,
+            hatype: value.sll_hatype,
+            packet_type: value.sll_pkttype,
+            hw_addr_len: value.sll_halen,
+            hw_addr: value.sll_addr,
+        }
+    }
+}
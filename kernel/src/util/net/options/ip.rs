@@ -4,10 +4,16 @@ use int_to_c_enum::TryFromInt;
 
 use super::RawSocketOption;
 use crate::{
-    impl_raw_socket_option,
-    net::socket::ip::options::{Hdrincl, Tos, Ttl},
+    current_userspace, impl_raw_sock_option_set_only, impl_raw_socket_option,
+    net::socket::ip::options::{
+        AddMembership, DropMembership, Hdrincl, IpMreqn, MulticastIf, MulticastLoop, MulticastTtl,
+        PktInfo, RecvTtl, Tos, Ttl,
+    },
     prelude::*,
-    util::net::options::SocketOption,
+    util::net::options::{
+        utils::{ReadFromUser, WriteToUser},
+        SocketOption,
+    },
 };
 
 /// Socket options for IP socket.
@@ -72,6 +78,13 @@ pub fn new_ip_option(name: i32) -> Result<Box<dyn RawSocketOption>> {
         CIpOptionName::TOS => Ok(Box::new(Tos::new())),
         CIpOptionName::TTL => Ok(Box::new(Ttl::new())),
         CIpOptionName::HDRINCL => Ok(Box::new(Hdrincl::new())),
+        CIpOptionName::PKTINFO => Ok(Box::new(PktInfo::new())),
+        CIpOptionName::RECVTTL => Ok(Box::new(RecvTtl::new())),
+        CIpOptionName::MULTICAST_IF => Ok(Box::new(MulticastIf::new())),
+        CIpOptionName::MULTICAST_TTL => Ok(Box::new(MulticastTtl::new())),
+        CIpOptionName::MULTICAST_LOOP => Ok(Box::new(MulticastLoop::new())),
+        CIpOptionName::ADD_MEMBERSHIP => Ok(Box::new(AddMembership::new())),
+        CIpOptionName::DROP_MEMBERSHIP => Ok(Box::new(DropMembership::new())),
         _ => return_errno_with_message!(Errno::ENOPROTOOPT, "unsupported ip level option"),
     }
 }
@@ -79,3 +92,115 @@ pub fn new_ip_option(name: i32) -> Result<Box<dyn RawSocketOption>> {
 impl_raw_socket_option!(Ttl);
 impl_raw_socket_option!(Tos);
 impl_raw_socket_option!(Hdrincl);
+impl_raw_socket_option!(PktInfo);
+impl_raw_socket_option!(RecvTtl);
+impl_raw_sock_option_set_only!(AddMembership);
+impl_raw_sock_option_set_only!(DropMembership);
+
+// IP_MULTICAST_TTL and IP_MULTICAST_LOOP accept a single byte as well as an `int`. Therefore, we
+// manually implement `RawSocketOption` for them.
+impl RawSocketOption for MulticastTtl {
+    fn read_from_user(&mut self, addr: Vaddr, max_len: u32) -> Result<()> {
+        let ttl = match read_int_or_byte(addr, max_len)? {
+            // Like Linux, -1 means the default value.
+            -1 => 1,
+            val @ 0..=255 => val as u8,
+            _ => return_errno_with_message!(Errno::EINVAL, "invalid multicast ttl value"),
+        };
+        self.set(ttl);
+        Ok(())
+    }
+
+    fn write_to_user(&self, addr: Vaddr, max_len: &mut u32) -> Result<usize> {
+        let ttl = self.get().unwrap();
+        write_int_or_byte(*ttl, addr, *max_len)
+    }
+
+    fn as_sock_option_mut(&mut self) -> &mut dyn SocketOption {
+        self
+    }
+
+    fn as_sock_option(&self) -> &dyn SocketOption {
+        self
+    }
+}
+
+impl RawSocketOption for MulticastLoop {
+    fn read_from_user(&mut self, addr: Vaddr, max_len: u32) -> Result<()> {
+        let multicast_loop = read_int_or_byte(addr, max_len)? != 0;
+        self.set(multicast_loop);
+        Ok(())
+    }
+
+    fn write_to_user(&self, addr: Vaddr, max_len: &mut u32) -> Result<usize> {
+        let multicast_loop = self.get().unwrap();
+        write_int_or_byte(*multicast_loop as u8, addr, *max_len)
+    }
+
+    fn as_sock_option_mut(&mut self) -> &mut dyn SocketOption {
+        self
+    }
+
+    fn as_sock_option(&self) -> &dyn SocketOption {
+        self
+    }
+}
+
+fn read_int_or_byte(addr: Vaddr, max_len: u32) -> Result<i32> {
+    if max_len as usize >= size_of::<i32>() {
+        current_userspace!().read_val::<i32>(addr)
+    } else if max_len >= 1 {
+        Ok(current_userspace!().read_val::<u8>(addr)? as i32)
+    } else {
+        return_errno_with_message!(Errno::EINVAL, "max_len is too short");
+    }
+}
+
+fn write_int_or_byte(val: u8, addr: Vaddr, max_len: u32) -> Result<usize> {
+    if max_len as usize >= size_of::<i32>() || max_len == 0 {
+        return (val as i32).write_to_user(addr, max_len);
+    }
+
+    current_userspace!().write_val(addr, &val)?;
+    Ok(size_of::<u8>())
+}
+
+// IP_MULTICAST_IF accepts `struct ip_mreqn`, `struct ip_mreq`, and `struct in_addr`, but only
+// reports `struct in_addr`. Therefore, we manually implement `RawSocketOption` for it.
+impl RawSocketOption for MulticastIf {
+    fn read_from_user(&mut self, addr: Vaddr, max_len: u32) -> Result<()> {
+        const IN_ADDR_LEN: u32 = 4;
+
+        let mreqn = if max_len >= IN_ADDR_LEN * 2 {
+            IpMreqn::read_from_user(addr, max_len)?
+        } else if max_len >= IN_ADDR_LEN {
+            let address = current_userspace!().read_val::<[u8; 4]>(addr)?;
+            IpMreqn {
+                imr_address: address,
+                ..IpMreqn::new_zeroed()
+            }
+        } else {
+            return_errno_with_message!(Errno::EINVAL, "max_len is too short");
+        };
+
+        self.set(mreqn);
+        Ok(())
+    }
+
+    fn write_to_user(&self, addr: Vaddr, max_len: &mut u32) -> Result<usize> {
+        let address = self.get().unwrap().imr_address;
+
+        let write_len = address.len().min(*max_len as usize);
+        current_userspace!().write_bytes(addr, &mut VmReader::from(&address[..write_len]))?;
+
+        Ok(write_len)
+    }
+
+    fn as_sock_option_mut(&mut self) -> &mut dyn SocketOption {
+        self
+    }
+
+    fn as_sock_option(&self) -> &dyn SocketOption {
+        self
+    }
+}
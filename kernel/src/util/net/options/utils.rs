@@ -5,7 +5,11 @@ use core::{num::NonZeroU8, time::Duration};
 use crate::{
     current_userspace,
     net::socket::{
-        ip::{options::IpTtl, stream_options::CongestionControl},
+        ip::{
+            options::{IpMreqn, IpTtl},
+            stream_options::CongestionControl,
+        },
+        packet::PacketMreq,
         unix::CUserCred,
         util::LingerOption,
     },
@@ -240,3 +244,36 @@ impl WriteToUser for CUserCred {
         Ok(write_len)
     }
 }
+
+impl ReadFromUser for PacketMreq {
+    fn read_from_user(addr: Vaddr, max_len: u32) -> Result<Self> {
+        if (max_len as usize) < core::mem::size_of::<PacketMreq>() {
+            return_errno_with_message!(Errno::EINVAL, "max_len is too short");
+        }
+
+        current_userspace!().read_val::<PacketMreq>(addr)
+    }
+}
+
+/// The length of `struct ip_mreq`, which is `struct ip_mreqn` without the interface index.
+const IP_MREQ_LEN: usize = 8;
+
+impl ReadFromUser for IpMreqn {
+    fn read_from_user(addr: Vaddr, max_len: u32) -> Result<Self> {
+        // Like Linux, `struct ip_mreq` is also accepted, in which case the interface index is
+        // zero.
+        let read_len = match max_len as usize {
+            len if len >= core::mem::size_of::<IpMreqn>() => core::mem::size_of::<IpMreqn>(),
+            len if len >= IP_MREQ_LEN => IP_MREQ_LEN,
+            _ => return_errno_with_message!(Errno::EINVAL, "max_len is too short"),
+        };
+
+        let mut mreqn = IpMreqn::new_zeroed();
+        current_userspace!().read_bytes(
+            addr,
+            &mut VmWriter::from(&mut mreqn.as_bytes_mut()[..read_len]),
+        )?;
+
+        Ok(mreqn)
+    }
+}
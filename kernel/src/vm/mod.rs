@@ -20,12 +20,19 @@ use osdk_frame_allocator::FrameAllocator;
 use osdk_heap_allocator::{type_from_layout, HeapAllocator};
 
 pub mod memfd;
+pub mod oom;
 pub mod page_fault_handler;
 pub mod perms;
+pub mod reclaim;
+pub mod swap;
 pub mod util;
 pub mod vmar;
 pub mod vmo;
 
+mod sysctl;
+
+pub use sysctl::check_overcommit;
+
 #[ostd::global_frame_allocator]
 static FRAME_ALLOCATOR: FrameAllocator = FrameAllocator;
 
@@ -37,6 +44,11 @@ const fn slot_type_from_layout(layout: core::alloc::Layout) -> Option<ostd::mm::
     type_from_layout(layout)
 }
 
+pub(super) fn init() {
+    sysctl::init();
+    swap::init();
+}
+
 /// Total physical memory in the entire system in bytes.
 pub fn mem_total() -> usize {
     use ostd::boot::{boot_info, memory_region::MemoryRegionType};
@@ -0,0 +1,108 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The OOM killer, which kills a process to free memory when the system is out of memory.
+//!
+//! The victim is the process with the highest badness score, which is the number of its
+//! resident pages adjusted by its `oom_score_adj`. The init process and the processes
+//! whose `oom_score_adj` is [`OOM_SCORE_ADJ_MIN`] are never killed.
+//!
+//! Reference: <https://elixir.bootlin.com/linux/v6.15/source/mm/oom_kill.c>
+
+use super::vmar::RssType;
+use crate::{
+    prelude::*,
+    process::{
+        process_table,
+        signal::{constants::SIGKILL, signals::kernel::KernelSignal},
+        Process, ProcessVmarGuard, OOM_SCORE_ADJ_MIN,
+    },
+};
+
+/// The last victim, which may not have released its memory yet.
+static OOM_VICTIM: Mutex<Weak<Process>> = Mutex::new(Weak::new());
+
+/// Kills a process to free memory, returning the victim.
+///
+/// Like Linux, no more processes are killed until the last victim releases its memory,
+/// in which case the last victim is returned.
+///
+/// Returns `None` if there is no process that can be killed.
+pub fn out_of_memory() -> Option<Arc<Process>> {
+    let mut last_victim = OOM_VICTIM.lock();
+    if let Some(victim) = last_victim
+        .upgrade()
+        .filter(|victim| !has_released_memory(victim))
+    {
+        return Some(victim);
+    }
+
+    let nr_total_pages = super::mem_total() / PAGE_SIZE;
+    let (victim, points) = select_victim(nr_total_pages)?;
+    warn!(
+        "out of memory: killed process {} ({}) with badness {}, oom_score_adj {}",
+        victim.pid(),
+        victim.executable_path(),
+        points,
+        victim.oom_score_adj()
+    );
+    victim.enqueue_signal(KernelSignal::new(SIGKILL));
+
+    *last_victim = Arc::downgrade(&victim);
+    Some(victim)
+}
+
+/// Returns the OOM score of `process`, which is shown in `/proc/[pid]/oom_score`.
+pub fn oom_score(process: &Process) -> u64 {
+    let nr_total_pages = (super::mem_total() / PAGE_SIZE) as i64;
+    let Some(points) = badness(process, &process.lock_root_vmar(), nr_total_pages as usize) else {
+        return 0;
+    };
+
+    // Like Linux, the badness score is scaled into the range of `0..=1333`.
+    ((1000 + points * 1000 / nr_total_pages) * 2 / 3).max(0) as u64
+}
+
+/// Selects the process with the highest badness score, returning it with the score.
+fn select_victim(nr_total_pages: usize) -> Option<(Arc<Process>, i64)> {
+    let process_table = process_table::process_table_mut();
+
+    let mut victim = None;
+    for process in process_table.iter() {
+        // The VMAR is not waited for, since its lock may be held by the allocator.
+        let Some(vmar) = process.try_lock_root_vmar() else {
+            continue;
+        };
+        let Some(points) = badness(process, &vmar, nr_total_pages) else {
+            continue;
+        };
+        if victim
+            .as_ref()
+            .is_none_or(|(_, max_points)| points > *max_points)
+        {
+            victim = Some((process.clone(), points));
+        }
+    }
+    victim
+}
+
+/// Returns the badness score of `process`, or `None` if it must not be killed.
+fn badness(process: &Process, vmar: &ProcessVmarGuard, nr_total_pages: usize) -> Option<i64> {
+    let oom_score_adj = process.oom_score_adj();
+    if oom_score_adj == OOM_SCORE_ADJ_MIN || process.is_init_process() {
+        return None;
+    }
+    // The VMAR of a process is gone after it exits.
+    let vmar = vmar.as_ref()?;
+
+    let rss =
+        vmar.get_rss_counter(RssType::RSS_ANONPAGES) + vmar.get_rss_counter(RssType::RSS_FILEPAGES);
+    // The adjustment is in the unit of a thousandth of the memory.
+    Some(rss as i64 + oom_score_adj as i64 * nr_total_pages as i64 / 1000)
+}
+
+/// Returns whether `process` has exited and released its memory.
+fn has_released_memory(process: &Process) -> bool {
+    process
+        .try_lock_root_vmar()
+        .is_some_and(|vmar| vmar.as_ref().is_none())
+}
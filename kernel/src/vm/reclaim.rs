@@ -0,0 +1,137 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The reclamation of memory under memory pressure.
+//!
+//! The caches that can give their memory back (e.g., the page caches and the dentry
+//! cache) register [`Shrinker`]s here. When an allocation fails for the lack of free
+//! frames, the caches are shrunk and the allocation is retried. If nothing can be
+//! reclaimed, the OOM killer may be invoked to free memory by killing a process.
+//!
+//! The OOM killer is only invoked by the page faults, see [`alloc_or_oom`]. The other
+//! allocations of user pages (e.g., the commits of the VMO pages in the system calls
+//! and the swap-ins) and those of the caches fail with `ENOMEM` instead, see
+//! [`alloc_with_reclaim`].
+//!
+//! Reference: <https://docs.kernel.org/mm/page_reclaim.html>
+
+use alloc::collections::btree_map::BTreeMap;
+use core::time::Duration;
+
+use ostd::sync::Waiter;
+
+use super::oom;
+use crate::{prelude::*, process::Process};
+
+/// A cache whose objects can be freed to reclaim memory.
+pub trait Shrinker: Send + Sync {
+    /// Returns the number of the objects that can be freed.
+    ///
+    /// The number is an estimation, which guides how many objects are scanned.
+    fn count_objects(&self) -> usize;
+
+    /// Scans at most `nr_to_scan` objects, freeing those not in use.
+    ///
+    /// The objects should be freed in the least recently used order if possible.
+    /// This method must not sleep on the locks that may be held by the allocators,
+    /// so it should skip the objects whose locks cannot be acquired immediately.
+    ///
+    /// Returns the number of the objects freed.
+    fn scan_objects(&self, nr_to_scan: usize) -> usize;
+}
+
+/// The registered shrinkers, keyed by their addresses.
+static SHRINKERS: Mutex<BTreeMap<usize, Weak<dyn Shrinker>>> = Mutex::new(BTreeMap::new());
+
+/// The lowest priority of the reclamation, with which `1 / 2^DEF_PRIORITY` of the
+/// objects in each cache are scanned.
+const DEF_PRIORITY: u32 = 12;
+
+/// The number of the objects that a failed allocation tries to reclaim.
+const NR_TO_RECLAIM: usize = 32;
+
+/// The maximum number of the retries of an allocation that fails for the lack of memory.
+const MAX_RETRIES: usize = 16;
+
+/// The time that a failed allocation waits for an OOM victim to exit each time.
+const OOM_PAUSE: Duration = Duration::from_millis(100);
+
+/// Registers a shrinker, which is unregistered when it is dropped.
+pub fn register_shrinker(shrinker: Weak<dyn Shrinker>) {
+    SHRINKERS
+        .lock()
+        .insert(shrinker.as_ptr() as *const () as usize, shrinker);
+}
+
+/// Reclaims at least `nr_to_reclaim` objects from the caches if possible.
+///
+/// Like Linux, the caches are scanned with increasing portions, so that the objects
+/// are not evicted more than needed.
+///
+/// Returns the number of the objects freed.
+pub fn reclaim(nr_to_reclaim: usize) -> usize {
+    let shrinkers: Vec<Arc<dyn Shrinker>> = {
+        let mut shrinkers = SHRINKERS.lock();
+        shrinkers.retain(|_, shrinker| shrinker.strong_count() > 0);
+        shrinkers.values().filter_map(Weak::upgrade).collect()
+    };
+
+    let mut nr_freed = 0;
+    for priority in (0..=DEF_PRIORITY).rev() {
+        for shrinker in shrinkers.iter() {
+            let nr_to_scan = shrinker.count_objects() >> priority;
+            if nr_to_scan > 0 {
+                nr_freed += shrinker.scan_objects(nr_to_scan);
+            }
+        }
+        if nr_freed >= nr_to_reclaim {
+            break;
+        }
+    }
+    nr_freed
+}
+
+/// Runs `alloc`, retrying it after reclaiming memory if it fails with `ENOMEM`.
+///
+/// The OOM killer is never invoked, so this method is suitable for the allocations
+/// that can fail gracefully (e.g., those of the caches).
+pub fn alloc_with_reclaim<T>(mut alloc: impl FnMut() -> Result<T>) -> Result<T> {
+    for _ in 0..MAX_RETRIES {
+        match alloc() {
+            Err(err) if err.error() == Errno::ENOMEM => {}
+            result => return result,
+        }
+        if reclaim(NR_TO_RECLAIM) == 0 {
+            break;
+        }
+    }
+    alloc()
+}
+
+/// Runs `alloc`, retrying it after reclaiming memory if it fails with `ENOMEM`.
+///
+/// If nothing can be reclaimed, the OOM killer is invoked to kill a process, and
+/// the allocation is retried after the victim exits. The wait for the victim is
+/// bounded, since it may need the locks held by the caller to exit.
+pub fn alloc_or_oom<T>(mut alloc: impl FnMut() -> Result<T>) -> Result<T> {
+    for _ in 0..MAX_RETRIES {
+        match alloc() {
+            Err(err) if err.error() == Errno::ENOMEM => {}
+            result => return result,
+        }
+        if reclaim(NR_TO_RECLAIM) > 0 {
+            continue;
+        }
+
+        let Some(victim) = oom::out_of_memory() else {
+            break;
+        };
+        if Process::current().is_some_and(|current| Arc::ptr_eq(&current, &victim)) {
+            break;
+        }
+        // The error is ignored, since the allocation is retried anyway.
+        let waiter = Waiter::new_pair().0;
+        let _ =
+            waiter.wait_until_or_timeout(|| victim.status().is_zombie().then_some(()), &OOM_PAUSE);
+    }
+    alloc()
+}
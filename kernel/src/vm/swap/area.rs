@@ -0,0 +1,309 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Swap areas, which are the block devices or the regular files holding swapped-out pages.
+
+use core::mem::size_of;
+
+use aster_block::{
+    bio::{BioDirection, BioSegment, BioStatus},
+    id::Bid,
+    BlockDevice, SECTOR_SIZE,
+};
+use ostd::mm::{io_util::HasVmReaderWriter, FrameAllocOptions, UFrame, USegment, VmIo};
+
+use crate::{fs::utils::Inode, prelude::*};
+
+/// The signature at the end of the first page of a swap area made by `mkswap`.
+const SWAP_MAGIC: &[u8; 10] = b"SWAPSPACE2";
+/// The offset of [`SwapHeaderInfo`] in the first page.
+const SWAP_HEADER_INFO_OFFSET: usize = 1024;
+/// The offset of the list of the bad pages in the first page.
+const SWAP_BADPAGES_OFFSET: usize = 1536;
+/// The maximum number of the bad pages in the list.
+const MAX_SWAP_BADPAGES: usize =
+    (PAGE_SIZE - SWAP_MAGIC.len() - SWAP_BADPAGES_OFFSET) / size_of::<u32>();
+
+/// The swap count of a slot that cannot be used (e.g., the header and the bad pages).
+const SWAP_MAP_BAD: u16 = 0x7fff;
+/// The maximum swap count of a slot.
+const SWAP_MAP_MAX: u16 = 0x7ffe;
+/// The flag in the swap count of a slot that is being written.
+const SWAP_WRITEBACK: u16 = 0x8000;
+
+/// The header of a swap area, which is in the first page.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.15/source/include/linux/swap.h>
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod)]
+struct SwapHeaderInfo {
+    version: u32,
+    last_page: u32,
+    nr_badpages: u32,
+    uuid: [u8; 16],
+    volume_name: [u8; 16],
+}
+
+/// The backend of a swap area.
+#[derive(Clone)]
+pub enum SwapBackend {
+    /// A block device, which is used as a swap partition.
+    BlockDevice(Arc<dyn BlockDevice>),
+    /// A regular file, which is used as a swap file.
+    File(Arc<dyn Inode>),
+}
+
+impl SwapBackend {
+    /// Returns whether `self` and `other` are the same block device or file.
+    pub fn is_same(&self, other: &SwapBackend) -> bool {
+        match (self, other) {
+            (Self::BlockDevice(this), Self::BlockDevice(other)) => Arc::ptr_eq(this, other),
+            (Self::File(this), Self::File(other)) => Arc::ptr_eq(this, other),
+            _ => false,
+        }
+    }
+
+    /// Returns the number of the pages in the backend.
+    fn nr_pages(&self) -> usize {
+        match self {
+            Self::BlockDevice(device) => device.metadata().nr_sectors * SECTOR_SIZE / PAGE_SIZE,
+            Self::File(inode) => inode.size() / PAGE_SIZE,
+        }
+    }
+
+    fn read_page(&self, idx: usize, frame: &UFrame) -> Result<()> {
+        match self {
+            Self::BlockDevice(device) => {
+                let bio_segment = BioSegment::new_from_segment(
+                    USegment::from(frame.clone()),
+                    BioDirection::FromDevice,
+                );
+                match device.read_blocks(Bid::new(idx as u64), bio_segment)? {
+                    BioStatus::Complete => Ok(()),
+                    err_status => Err(Error::from(err_status)),
+                }
+            }
+            Self::File(inode) => {
+                // The page cache is bypassed, since the page is already in the memory.
+                let mut writer = frame.writer().to_fallible();
+                if inode.read_direct_at(idx * PAGE_SIZE, &mut writer)? != PAGE_SIZE {
+                    return_errno_with_message!(Errno::EIO, "the swap file is truncated");
+                }
+                Ok(())
+            }
+        }
+    }
+
+    fn write_page(&self, idx: usize, frame: &UFrame) -> Result<()> {
+        match self {
+            Self::BlockDevice(device) => {
+                let bio_segment = BioSegment::new_from_segment(
+                    USegment::from(frame.clone()),
+                    BioDirection::ToDevice,
+                );
+                match device.write_blocks(Bid::new(idx as u64), bio_segment)? {
+                    BioStatus::Complete => Ok(()),
+                    err_status => Err(Error::from(err_status)),
+                }
+            }
+            Self::File(inode) => {
+                let mut reader = frame.reader().to_fallible();
+                if inode.write_direct_at(idx * PAGE_SIZE, &mut reader)? != PAGE_SIZE {
+                    return_errno_with_message!(Errno::EIO, "the swap file is truncated");
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// A swap area, whose pages are called swap slots.
+///
+/// The first slot holds the header, so it is never used.
+pub(super) struct SwapArea {
+    backend: SwapBackend,
+    /// The priority. The areas with higher priorities are used first.
+    priority: i16,
+    /// The number of the usable slots.
+    nr_pages: usize,
+    map: SpinLock<SwapMap>,
+}
+
+/// The usage of the slots in a swap area.
+struct SwapMap {
+    /// The swap count of each slot, which is the number of the references to it.
+    ///
+    /// A slot is free if its swap count is zero and it is not being written.
+    counts: Vec<u16>,
+    /// The number of the free slots.
+    nr_free: usize,
+    /// The slot to search for a free slot from.
+    next: usize,
+    /// Whether free slots can be allocated, which is false if the area is being disabled.
+    is_enabled: bool,
+}
+
+impl SwapArea {
+    /// Creates a swap area on `backend` made by `mkswap`.
+    pub(super) fn new(backend: SwapBackend, priority: i16) -> Result<Self> {
+        if let SwapBackend::File(inode) = &backend {
+            let metadata = inode.metadata();
+            if metadata.blocks * metadata.blk_size < metadata.size {
+                return_errno_with_message!(Errno::EINVAL, "the swap file has holes");
+            }
+        }
+
+        let header: UFrame = FrameAllocOptions::new().alloc_frame()?.into();
+        backend.read_page(0, &header)?;
+
+        let mut magic = [0u8; SWAP_MAGIC.len()];
+        header.read_bytes(PAGE_SIZE - SWAP_MAGIC.len(), &mut magic)?;
+        if magic != *SWAP_MAGIC {
+            return_errno_with_message!(Errno::EINVAL, "unable to find the swap space signature");
+        }
+
+        let info: SwapHeaderInfo = header.read_val(SWAP_HEADER_INFO_OFFSET)?;
+        if info.version != 1 {
+            return_errno_with_message!(Errno::EINVAL, "unsupported swap space version");
+        }
+        let last_page = info.last_page as usize;
+        if last_page == 0 {
+            return_errno_with_message!(Errno::EINVAL, "the swap area is empty");
+        }
+        if last_page >= backend.nr_pages() {
+            return_errno_with_message!(
+                Errno::EINVAL,
+                "the swap area is shorter than the signature indicates"
+            );
+        }
+        let nr_badpages = info.nr_badpages as usize;
+        if nr_badpages > MAX_SWAP_BADPAGES {
+            return_errno_with_message!(Errno::EINVAL, "too many bad pages in the swap area");
+        }
+
+        let mut counts = vec![0u16; last_page + 1];
+        counts[0] = SWAP_MAP_BAD;
+        for i in 0..nr_badpages {
+            let offset = SWAP_BADPAGES_OFFSET + i * size_of::<u32>();
+            let badpage = header.read_val::<u32>(offset)? as usize;
+            if badpage == 0 || badpage > last_page {
+                return_errno_with_message!(Errno::EINVAL, "invalid bad page in the swap area");
+            }
+            counts[badpage] = SWAP_MAP_BAD;
+        }
+        let nr_pages = counts.iter().filter(|count| **count == 0).count();
+        if nr_pages == 0 {
+            return_errno_with_message!(Errno::EINVAL, "no usable page in the swap area");
+        }
+
+        Ok(Self {
+            backend,
+            priority,
+            nr_pages,
+            map: SpinLock::new(SwapMap {
+                counts,
+                nr_free: nr_pages,
+                next: 1,
+                is_enabled: true,
+            }),
+        })
+    }
+
+    pub(super) fn backend(&self) -> &SwapBackend {
+        &self.backend
+    }
+
+    pub(super) fn priority(&self) -> i16 {
+        self.priority
+    }
+
+    pub(super) fn nr_pages(&self) -> usize {
+        self.nr_pages
+    }
+
+    pub(super) fn nr_free_pages(&self) -> usize {
+        self.map.lock().nr_free
+    }
+
+    /// Returns whether no slot is in use.
+    pub(super) fn is_unused(&self) -> bool {
+        self.nr_free_pages() == self.nr_pages
+    }
+
+    pub(super) fn is_enabled(&self) -> bool {
+        self.map.lock().is_enabled
+    }
+
+    pub(super) fn set_enabled(&self, is_enabled: bool) {
+        self.map.lock().is_enabled = is_enabled;
+    }
+
+    /// Allocates a free slot with a swap count of one, returning its offset.
+    pub(super) fn alloc_slot(&self) -> Option<usize> {
+        let mut map = self.map.lock();
+        if !map.is_enabled || map.nr_free == 0 {
+            return None;
+        }
+
+        let len = map.counts.len();
+        let offset = (0..len)
+            .map(|i| (map.next + i) % len)
+            .find(|offset| map.counts[*offset] == 0)?;
+        map.counts[offset] = 1;
+        map.nr_free -= 1;
+        map.next = (offset + 1) % len;
+        Some(offset)
+    }
+
+    /// Increments the swap count of the slot at `offset`.
+    pub(super) fn dup_slot(&self, offset: usize) -> Result<()> {
+        let mut map = self.map.lock();
+        let count = &mut map.counts[offset];
+        if *count & !SWAP_WRITEBACK >= SWAP_MAP_MAX {
+            return_errno_with_message!(Errno::ENOMEM, "the swap count overflows");
+        }
+        *count += 1;
+        Ok(())
+    }
+
+    /// Decrements the swap count of the slot at `offset`.
+    pub(super) fn free_slot(&self, offset: usize) {
+        let mut map = self.map.lock();
+        debug_assert!(map.counts[offset] & !SWAP_WRITEBACK > 0);
+        map.counts[offset] -= 1;
+        map.update_nr_free(offset);
+    }
+
+    /// Marks the slot at `offset` as being written.
+    ///
+    /// The slot is not freed until [`Self::end_writeback`] is called.
+    pub(super) fn start_writeback(&self, offset: usize) {
+        self.map.lock().counts[offset] |= SWAP_WRITEBACK;
+    }
+
+    pub(super) fn end_writeback(&self, offset: usize) {
+        let mut map = self.map.lock();
+        map.counts[offset] &= !SWAP_WRITEBACK;
+        map.update_nr_free(offset);
+    }
+
+    pub(super) fn is_under_writeback(&self, offset: usize) -> bool {
+        self.map.lock().counts[offset] & SWAP_WRITEBACK != 0
+    }
+
+    pub(super) fn read_page(&self, offset: usize, frame: &UFrame) -> Result<()> {
+        self.backend.read_page(offset, frame)
+    }
+
+    pub(super) fn write_page(&self, offset: usize, frame: &UFrame) -> Result<()> {
+        self.backend.write_page(offset, frame)
+    }
+}
+
+impl SwapMap {
+    /// Accounts the slot at `offset` as free if its swap count has dropped to zero.
+    fn update_nr_free(&mut self, offset: usize) {
+        if self.counts[offset] == 0 {
+            self.nr_free += 1;
+        }
+    }
+}
@@ -0,0 +1,355 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Swapping, which writes anonymous pages to swap areas to reclaim memory.
+//!
+//! A swap area is a block device or a regular file prepared by `mkswap`, which is
+//! enabled by `swapon` and disabled by `swapoff`. Its pages are called swap slots.
+//! Under memory pressure, the swap shrinker writes the anonymous pages that are not
+//! recently accessed to free slots, and frees the pages:
+//!  * A private page mapped in a VMAR leaves a swap entry in the VMAR, which is swapped
+//!    in when the page is faulted.
+//!  * A page of an anonymous VMO (e.g., a page of a shared anonymous mapping) leaves a
+//!    swap entry in the VMO, which is swapped in when the page is committed.
+//!
+//! A swap slot is reference-counted, since the swap entries of a VMAR are shared with
+//! its forked children.
+//!
+//! Reference: <https://man7.org/linux/man-pages/man2/swapon.2.html>
+
+mod area;
+mod table;
+
+use core::{
+    cmp::Reverse,
+    sync::atomic::{AtomicBool, AtomicI16, AtomicUsize, Ordering},
+};
+
+use ostd::{
+    mm::{FrameAllocOptions, UFrame},
+    sync::WaitQueue,
+};
+use spin::Once;
+
+use self::area::SwapArea;
+pub use self::area::SwapBackend;
+pub(super) use self::table::SwapTable;
+use super::reclaim::{self, Shrinker};
+use crate::{
+    fs::utils::Inode,
+    prelude::*,
+    process::{process_table, Process},
+};
+
+/// The maximum number of the swap areas.
+const MAX_SWAPFILES: usize = 32;
+
+/// The maximum number of the retries to swap in the pages of an area being disabled.
+///
+/// The pages may be swapped out to the area again by a concurrent swapper, which
+/// has allocated the slots before the area is disabled.
+const MAX_SWAPOFF_RETRIES: usize = 8;
+
+/// The enabled swap areas, indexed by the area indices in the swap entries.
+static SWAP_AREAS: SpinLock<[Option<Arc<SwapArea>>; MAX_SWAPFILES]> =
+    SpinLock::new([const { None }; MAX_SWAPFILES]);
+
+/// The priority of the last swap area enabled without a priority.
+///
+/// Like Linux, such areas get decreasing negative priorities.
+static LEAST_PRIORITY: AtomicI16 = AtomicI16::new(-1);
+
+/// The number of the enabled swap areas backed by files.
+///
+/// It saves the lookups of the swap areas in [`is_swap_file`] if there are no swap files.
+static NR_SWAP_FILES: AtomicUsize = AtomicUsize::new(0);
+
+/// The mutex that serializes `swapon` and `swapoff`.
+static SWAPON_MUTEX: Mutex<()> = Mutex::new(());
+
+/// The queue of the readers of the slots being written, which is woken up when
+/// the writes complete.
+static WRITEBACK_WAIT_QUEUE: WaitQueue = WaitQueue::new();
+
+/// Whether the pages are being swapped out.
+///
+/// Only one task swaps out pages at a time, and it must not reenter the swapping
+/// when its own allocations fail.
+static IS_SWAPPING_OUT: AtomicBool = AtomicBool::new(false);
+
+static SWAP_SHRINKER: Once<Arc<SwapShrinker>> = Once::new();
+
+pub(super) fn init() {
+    let shrinker = SWAP_SHRINKER.call_once(|| Arc::new(SwapShrinker));
+    reclaim::register_shrinker(Arc::downgrade(shrinker) as Weak<dyn Shrinker>);
+}
+
+/// The location of a swapped-out page.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct SwapEntry {
+    /// The index of the swap area.
+    area_idx: usize,
+    /// The index of the slot in the swap area.
+    offset: usize,
+}
+
+impl SwapEntry {
+    pub(super) fn area_idx(&self) -> usize {
+        self.area_idx
+    }
+}
+
+/// Enables swapping to `backend`, which must be prepared by `mkswap`.
+///
+/// The swap areas with higher priorities are used first. If `priority` is `None`,
+/// the area gets a priority lower than those of all the existing areas.
+pub fn swap_on(backend: SwapBackend, priority: Option<i16>) -> Result<()> {
+    let _guard = SWAPON_MUTEX.lock();
+
+    if find_area(&backend).is_some() {
+        return_errno_with_message!(Errno::EBUSY, "the swap area is already enabled");
+    }
+
+    let priority = priority.unwrap_or_else(|| LEAST_PRIORITY.fetch_sub(1, Ordering::Relaxed) - 1);
+    let is_file = matches!(backend, SwapBackend::File(_));
+    let area = Arc::new(SwapArea::new(backend, priority)?);
+
+    let mut areas = SWAP_AREAS.lock();
+    let Some(slot) = areas.iter_mut().find(|slot| slot.is_none()) else {
+        return_errno_with_message!(Errno::EPERM, "too many swap areas");
+    };
+    *slot = Some(area);
+    if is_file {
+        NR_SWAP_FILES.fetch_add(1, Ordering::Relaxed);
+    }
+    Ok(())
+}
+
+/// Disables swapping to `backend`, swapping in all the pages in it.
+pub fn swap_off(backend: &SwapBackend) -> Result<()> {
+    let _guard = SWAPON_MUTEX.lock();
+
+    let Some((area_idx, area)) = find_area(backend) else {
+        return_errno_with_message!(Errno::EINVAL, "the swap area is not enabled");
+    };
+    area.set_enabled(false);
+
+    for _ in 0..MAX_SWAPOFF_RETRIES {
+        if area.is_unused() {
+            break;
+        }
+        if let Err(err) = swap_in_area(area_idx) {
+            area.set_enabled(true);
+            return Err(err);
+        }
+    }
+    if !area.is_unused() {
+        area.set_enabled(true);
+        return_errno_with_message!(Errno::EBUSY, "the swap area is still in use");
+    }
+
+    SWAP_AREAS.lock()[area_idx] = None;
+    if matches!(backend, SwapBackend::File(_)) {
+        NR_SWAP_FILES.fetch_sub(1, Ordering::Relaxed);
+    }
+    Ok(())
+}
+
+/// Returns whether `inode` is the file of an enabled swap area.
+///
+/// Like Linux, such a file cannot be written or truncated through the VFS, since
+/// the swapped-out pages are written to it directly.
+pub fn is_swap_file(inode: &Arc<dyn Inode>) -> bool {
+    if NR_SWAP_FILES.load(Ordering::Relaxed) == 0 {
+        return false;
+    }
+
+    let backend = SwapBackend::File(inode.clone());
+    SWAP_AREAS
+        .lock()
+        .iter()
+        .flatten()
+        .any(|area| area.backend().is_same(&backend))
+}
+
+/// Returns the number of the slots in all the enabled swap areas.
+pub fn nr_total_pages() -> usize {
+    enabled_areas_sum(SwapArea::nr_pages)
+}
+
+/// Returns the number of the free slots in all the enabled swap areas.
+pub fn nr_free_pages() -> usize {
+    enabled_areas_sum(SwapArea::nr_free_pages)
+}
+
+fn enabled_areas_sum(f: impl Fn(&SwapArea) -> usize) -> usize {
+    SWAP_AREAS
+        .lock()
+        .iter()
+        .flatten()
+        .filter(|area| area.is_enabled())
+        .map(|area| f(area.as_ref()))
+        .sum()
+}
+
+fn find_area(backend: &SwapBackend) -> Option<(usize, Arc<SwapArea>)> {
+    SWAP_AREAS
+        .lock()
+        .iter()
+        .enumerate()
+        .find_map(|(idx, area)| {
+            let area = area.as_ref()?;
+            area.backend().is_same(backend).then(|| (idx, area.clone()))
+        })
+}
+
+fn get_area(area_idx: usize) -> Option<Arc<SwapArea>> {
+    SWAP_AREAS.lock()[area_idx].clone()
+}
+
+/// Swaps in all the pages in the swap area `area_idx`.
+fn swap_in_area(area_idx: usize) -> Result<()> {
+    let processes: Vec<Arc<Process>> = process_table::process_table_mut().iter().cloned().collect();
+    for process in processes {
+        let Some(vmar) = process
+            .lock_root_vmar()
+            .as_ref()
+            .map(|vmar| vmar.dup())
+            .transpose()?
+        else {
+            continue;
+        };
+        vmar.swap_in_area(area_idx)?;
+    }
+
+    super::vmo::swap_in_anon_pages(area_idx)
+}
+
+/// Allocates a free swap slot from the enabled swap area with the highest priority.
+pub(super) fn alloc_slot() -> Option<SwapEntry> {
+    let areas = SWAP_AREAS.lock();
+
+    // The areas are tried in the order of their priorities, since the one with the
+    // highest priority may be full or being disabled.
+    let mut tried = [false; MAX_SWAPFILES];
+    loop {
+        // The areas with equal priorities are used in the order of their indices.
+        let (area_idx, area) = areas
+            .iter()
+            .enumerate()
+            .filter(|(idx, _)| !tried[*idx])
+            .filter_map(|(idx, area)| Some((idx, area.as_ref()?)))
+            .max_by_key(|(idx, area)| (area.priority(), Reverse(*idx)))?;
+        if let Some(offset) = area.alloc_slot() {
+            return Some(SwapEntry { area_idx, offset });
+        }
+        tried[area_idx] = true;
+    }
+}
+
+/// Increments the reference count of the swap slot of `entry`.
+fn dup_slot(entry: SwapEntry) -> Result<()> {
+    let Some(area) = get_area(entry.area_idx) else {
+        return_errno_with_message!(Errno::EIO, "the swap area is not enabled");
+    };
+    area.dup_slot(entry.offset)
+}
+
+/// Decrements the reference count of the swap slot of `entry`.
+pub(super) fn free_slot(entry: SwapEntry) {
+    // An area in use is never removed.
+    if let Some(area) = get_area(entry.area_idx) {
+        area.free_slot(entry.offset);
+    }
+}
+
+/// Marks the swap slot of `entry` as being written, so that it is not read or freed.
+pub(super) fn start_writeback(entry: SwapEntry) {
+    if let Some(area) = get_area(entry.area_idx) {
+        area.start_writeback(entry.offset);
+    }
+}
+
+/// Marks the swap slot of `entry` as written, waking up its readers.
+pub(super) fn end_writeback(entry: SwapEntry) {
+    if let Some(area) = get_area(entry.area_idx) {
+        area.end_writeback(entry.offset);
+    }
+    WRITEBACK_WAIT_QUEUE.wake_all();
+}
+
+/// Reads the page in the swap slot of `entry` into a new frame.
+///
+/// If the slot is being written, this method waits until the write completes.
+pub(super) fn read_page(entry: SwapEntry) -> Result<UFrame> {
+    let Some(area) = get_area(entry.area_idx) else {
+        return_errno_with_message!(Errno::EIO, "the swap area is not enabled");
+    };
+    WRITEBACK_WAIT_QUEUE.wait_until(|| (!area.is_under_writeback(entry.offset)).then_some(()));
+
+    let frame: UFrame = reclaim::alloc_with_reclaim(|| {
+        Ok(FrameAllocOptions::new().zeroed(false).alloc_frame()?)
+    })?
+    .into();
+    area.read_page(entry.offset, &frame)?;
+    Ok(frame)
+}
+
+/// Writes `frame` to the swap slot of `entry`.
+pub(super) fn write_page(entry: SwapEntry, frame: &UFrame) -> Result<()> {
+    let Some(area) = get_area(entry.area_idx) else {
+        return_errno_with_message!(Errno::EIO, "the swap area is not enabled");
+    };
+    area.write_page(entry.offset, frame)
+}
+
+/// The shrinker that reclaims memory by swapping out anonymous pages.
+struct SwapShrinker;
+
+impl Shrinker for SwapShrinker {
+    fn count_objects(&self) -> usize {
+        nr_free_pages()
+    }
+
+    fn scan_objects(&self, nr_to_scan: usize) -> usize {
+        if IS_SWAPPING_OUT.swap(true, Ordering::Acquire) {
+            return 0;
+        }
+
+        let nr_swapped = swap_out(nr_to_scan).unwrap_or_else(|err| {
+            warn!("failed to swap out pages: {:?}", err);
+            0
+        });
+
+        IS_SWAPPING_OUT.store(false, Ordering::Release);
+        nr_swapped
+    }
+}
+
+/// Swaps out the anonymous pages, scanning at most `nr_to_scan` pages.
+///
+/// Returns the number of the swapped-out pages.
+fn swap_out(nr_to_scan: usize) -> Result<usize> {
+    let mut nr_scanned = 0;
+    let mut nr_swapped = 0;
+
+    let processes: Vec<Arc<Process>> = process_table::process_table_mut().iter().cloned().collect();
+    for process in processes {
+        if nr_scanned >= nr_to_scan {
+            return Ok(nr_swapped);
+        }
+
+        // The VMAR is not waited for, since its lock may be held by the allocator.
+        let Some(vmar) = process
+            .try_lock_root_vmar()
+            .and_then(|vmar| vmar.as_ref()?.dup().ok())
+        else {
+            continue;
+        };
+        let (scanned, swapped) = vmar.swap_out(nr_to_scan - nr_scanned)?;
+        nr_scanned += scanned;
+        nr_swapped += swapped;
+    }
+
+    let (_, swapped) = super::vmo::swap_out_anon_pages(nr_to_scan.saturating_sub(nr_scanned))?;
+    Ok(nr_swapped + swapped)
+}
@@ -0,0 +1,120 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use core::ops::Range;
+
+use super::{dup_slot, free_slot, SwapEntry};
+use crate::prelude::*;
+
+/// A table of the swap entries of swapped-out pages.
+///
+/// The entries are keyed by the addresses of the pages in a VMAR, or by the indices
+/// of the pages in a VMO. Each entry holds a reference to its swap slot, which is
+/// released when the entry is removed.
+pub struct SwapTable {
+    // A spin lock is used, since the table is accessed with the page table locked.
+    entries: SpinLock<BTreeMap<usize, SwapEntry>>,
+}
+
+impl SwapTable {
+    pub const fn new() -> Self {
+        Self {
+            entries: SpinLock::new(BTreeMap::new()),
+        }
+    }
+
+    /// Returns the swap entry at `key`.
+    pub fn get(&self, key: usize) -> Option<SwapEntry> {
+        self.entries.lock().get(&key).copied()
+    }
+
+    /// Inserts a swap entry at `key`, which takes over a reference to its slot.
+    pub fn insert(&self, key: usize, entry: SwapEntry) {
+        let old_entry = self.entries.lock().insert(key, entry);
+        debug_assert!(old_entry.is_none());
+    }
+
+    /// Removes the swap entry at `key` if it is still `entry`, releasing its slot.
+    ///
+    /// Returns whether the entry is removed.
+    pub fn remove(&self, key: usize, entry: SwapEntry) -> bool {
+        let mut entries = self.entries.lock();
+        if entries.get(&key) != Some(&entry) {
+            return false;
+        }
+        entries.remove(&key);
+        drop(entries);
+
+        free_slot(entry);
+        true
+    }
+
+    /// Removes the swap entries in `range`, releasing their slots.
+    pub fn remove_range(&self, range: Range<usize>) {
+        let removed = {
+            let mut entries = self.entries.lock();
+            let mut removed = entries.split_off(&range.start);
+            let mut rest = removed.split_off(&range.end);
+            entries.append(&mut rest);
+            removed
+        };
+
+        for entry in removed.into_values() {
+            free_slot(entry);
+        }
+    }
+
+    /// Removes all the swap entries, releasing their slots.
+    pub fn clear(&self) {
+        let removed = core::mem::take(&mut *self.entries.lock());
+        for entry in removed.into_values() {
+            free_slot(entry);
+        }
+    }
+
+    /// Moves the swap entries in `range` to the keys starting from `new_start`.
+    ///
+    /// There must be no entries at the new keys.
+    pub fn move_range(&self, range: Range<usize>, new_start: usize) {
+        let mut entries = self.entries.lock();
+        let moved = {
+            let mut moved = entries.split_off(&range.start);
+            let mut rest = moved.split_off(&range.end);
+            entries.append(&mut rest);
+            moved
+        };
+
+        for (key, entry) in moved {
+            let old_entry = entries.insert(key - range.start + new_start, entry);
+            debug_assert!(old_entry.is_none());
+        }
+    }
+
+    /// Copies the swap entries of `other`, which is used when forking a VMAR.
+    ///
+    /// The swap slots are shared between the two tables.
+    pub fn fork_from(&self, other: &SwapTable) -> Result<()> {
+        let other_entries = other.entries.lock();
+        let mut entries = self.entries.lock();
+        for (key, entry) in other_entries.iter() {
+            dup_slot(*entry)?;
+            entries.insert(*key, *entry);
+        }
+        Ok(())
+    }
+
+    /// Returns the keys of the swap entries in the swap area `area_idx`.
+    pub fn keys_in_area(&self, area_idx: usize) -> Vec<usize> {
+        self.entries
+            .lock()
+            .iter()
+            .filter(|(_, entry)| entry.area_idx() == area_idx)
+            .map(|(key, _)| *key)
+            .collect()
+    }
+}
+
+impl Drop for SwapTable {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
@@ -0,0 +1,66 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! This module registers the sysctls under `/proc/sys/vm`.
+//!
+//! Reference: <https://docs.kernel.org/admin-guide/sysctl/vm.html>
+
+use core::sync::atomic::{AtomicI64, Ordering};
+
+use super::swap;
+use crate::{
+    fs::procfs::sysctl::{self, IntSysctl, SysctlEntry},
+    prelude::*,
+    process::credentials::capabilities::CapSet,
+};
+
+/// The overcommit policy, which is one of the following:
+///  * 0: Heuristic overcommit;
+///  * 1: Always overcommit;
+///  * 2: Don't overcommit.
+static OVERCOMMIT_MEMORY: AtomicI64 = AtomicI64::new(OVERCOMMIT_GUESS);
+
+const OVERCOMMIT_GUESS: i64 = 0;
+const OVERCOMMIT_ALWAYS: i64 = 1;
+const OVERCOMMIT_NEVER: i64 = 2;
+
+pub(super) fn init() {
+    let entries = vec![SysctlEntry::writable(
+        "overcommit_memory",
+        IntSysctl::new(
+            OVERCOMMIT_GUESS..=OVERCOMMIT_NEVER,
+            || [OVERCOMMIT_MEMORY.load(Ordering::Relaxed)],
+            |[policy]| OVERCOMMIT_MEMORY.store(policy, Ordering::Relaxed),
+        ),
+        CapSet::SYS_ADMIN,
+    )];
+
+    sysctl::register("vm", entries).unwrap();
+}
+
+/// Checks whether `size` bytes of memory can be committed under the overcommit policy.
+///
+/// The memory committed by the private writable mappings and the heap is checked. With
+/// `no_reserve` (i.e., `MAP_NORESERVE`), the check is skipped unless overcommitting is
+/// disabled.
+///
+/// Unlike Linux, the memory that has been committed is not accounted. So if overcommitting is
+/// disabled, the memory is refused only if it exceeds the free memory and the free swap space.
+pub fn check_overcommit(size: usize, no_reserve: bool) -> Result<()> {
+    let nr_pages = size.div_ceil(PAGE_SIZE);
+
+    let nr_available_pages = match OVERCOMMIT_MEMORY.load(Ordering::Relaxed) {
+        OVERCOMMIT_ALWAYS => return Ok(()),
+        OVERCOMMIT_NEVER => {
+            osdk_frame_allocator::load_total_free_size() / PAGE_SIZE + swap::nr_free_pages()
+        }
+        _ if no_reserve => return Ok(()),
+        // Like Linux, the heuristic only refuses the obvious overcommits, which exceed the
+        // total memory and the total swap space.
+        _ => super::mem_total() / PAGE_SIZE + swap::nr_total_pages(),
+    };
+
+    if nr_pages > nr_available_pages {
+        return_errno_with_message!(Errno::ENOMEM, "not enough memory to commit");
+    }
+    Ok(())
+}
@@ -14,7 +14,8 @@ use aster_rights::Rights;
 use ostd::{
     cpu::CpuId,
     mm::{
-        tlb::TlbFlushOp, vm_space::CursorMut, PageFlags, PageProperty, VmSpace, MAX_USERSPACE_VADDR,
+        io_util::HasVmReaderWriter, tlb::TlbFlushOp, vm_space::CursorMut, PageFlags, PageProperty,
+        UFrame, VmSpace, MAX_USERSPACE_VADDR,
     },
     sync::RwMutexReadGuard,
     task::disable_preempt,
@@ -22,16 +23,18 @@ use ostd::{
 
 use self::{
     interval_set::{Interval, IntervalSet},
-    vm_mapping::{MappedVmo, VmMapping},
+    vm_mapping::{MappedVmo, VmMapping, VmMappingName},
 };
 use crate::{
     fs::utils::Inode,
+    ipc::shm::shm_seg::ShmAttach,
     prelude::*,
     process::{Process, ResourceType},
     thread::exception::PageFaultInfo,
     util::per_cpu_counter::PerCpuCounter,
     vm::{
         perms::VmPerms,
+        swap::SwapTable,
         vmo::{Vmo, VmoRightsOp},
     },
 };
@@ -153,6 +156,8 @@ struct VmarInner {
     vm_mappings: IntervalSet<Vaddr, VmMapping>,
     /// The total mapped memory in bytes.
     total_vm: usize,
+    /// The swap entries of the swapped-out private pages, keyed by their addresses.
+    swap_entries: SwapTable,
 }
 
 impl VmarInner {
@@ -160,6 +165,7 @@ impl VmarInner {
         Self {
             vm_mappings: IntervalSet::new(),
             total_vm: 0,
+            swap_entries: SwapTable::new(),
         }
     }
 
@@ -316,6 +322,7 @@ impl VmarInner {
 
             rss_delta.add(taken.rss_type(), -(taken.unmap(vm_space) as isize));
         }
+        self.swap_entries.remove_range(range);
 
         Ok(offset..(offset + size))
     }
@@ -523,16 +530,65 @@ impl Vmar_ {
             debug_assert!(vm_mapping.range().contains(&address));
 
             let mut rss_delta = RssDelta::new(self);
-            return vm_mapping.handle_page_fault(&self.vm_space, page_fault_info, &mut rss_delta);
+            return vm_mapping.handle_page_fault(
+                &self.vm_space,
+                page_fault_info,
+                &inner.swap_entries,
+                &mut rss_delta,
+            );
         }
 
         return_errno_with_message!(Errno::EACCES, "page fault addr is not in current vmar");
     }
 
+    /// Accesses the memory in `addr..addr + len` on behalf of another process.
+    ///
+    /// For each page in the range, `op` is called with the frame and the range in the frame.
+    fn access_remote(
+        &self,
+        addr: Vaddr,
+        len: usize,
+        is_write: bool,
+        mut op: impl FnMut(&UFrame, Range<usize>),
+    ) -> Result<()> {
+        let Some(end) = addr.checked_add(len) else {
+            return_errno_with_message!(Errno::EFAULT, "the range overflows");
+        };
+
+        let inner = self.inner.read();
+        let mut rss_delta = RssDelta::new(self);
+
+        let mut cur = addr;
+        while cur < end {
+            let Some(vm_mapping) = inner.vm_mappings.find_one(&cur) else {
+                return_errno_with_message!(Errno::EFAULT, "the address is not mapped");
+            };
+
+            let page_aligned_addr = cur.align_down(PAGE_SIZE);
+            let frame = vm_mapping.get_frame_for_remote_access(
+                &self.vm_space,
+                page_aligned_addr,
+                is_write,
+                &inner.swap_entries,
+                &mut rss_delta,
+            )?;
+
+            let page_end = end.min(page_aligned_addr + PAGE_SIZE);
+            op(
+                &frame,
+                cur - page_aligned_addr..page_end - page_aligned_addr,
+            );
+            cur = page_end;
+        }
+
+        Ok(())
+    }
+
     /// Clears all content of the root VMAR.
     fn clear_root_vmar(&self) -> Result<()> {
         let mut inner = self.inner.write();
         inner.vm_mappings.clear();
+        inner.swap_entries.clear();
 
         // Keep `inner` locked to avoid race conditions.
         let preempt_guard = disable_preempt();
@@ -659,6 +715,9 @@ impl Vmar_ {
         // Note that we have ensured that `new_size >= old_size` at the beginning.
         let new_mapping = old_mapping.clone_for_remap_at(new_range.start).unwrap();
         inner.insert_try_merge(new_mapping.enlarge(new_size - old_size));
+        inner
+            .swap_entries
+            .move_range(old_range.clone(), new_range.start);
 
         let preempt_guard = disable_preempt();
         let total_range = old_range.start.min(new_range.start)..old_range.end.max(new_range.end);
@@ -738,6 +797,7 @@ impl Vmar_ {
 
                 rss_delta.add(vm_mapping.rss_type(), num_copied as isize);
             }
+            new_inner.swap_entries.fork_from(&inner.swap_entries)?;
 
             cur_cursor.flusher().issue_tlb_flush(TlbFlushOp::All);
             cur_cursor.flusher().dispatch_tlb_flush();
@@ -756,6 +816,56 @@ impl Vmar_ {
         let cpu_id = CpuId::current_racy();
         self.rss_counters[rss_type as usize].add(cpu_id, val);
     }
+
+    /// Swaps out at most `nr_to_scan` private pages.
+    ///
+    /// Returns the numbers of the scanned pages and the swapped-out pages.
+    fn swap_out(&self, nr_to_scan: usize) -> Result<(usize, usize)> {
+        // The VMAR is not waited for, since its lock may be held by the allocator.
+        let Some(inner) = self.inner.try_read() else {
+            return Ok((0, 0));
+        };
+        let mut rss_delta = RssDelta::new(self);
+
+        let (mut nr_scanned, mut nr_swapped) = (0, 0);
+        for vm_mapping in inner.vm_mappings.iter() {
+            if nr_scanned >= nr_to_scan {
+                break;
+            }
+            let (scanned, swapped) = vm_mapping.swap_out(
+                &self.vm_space,
+                &inner.swap_entries,
+                nr_to_scan - nr_scanned,
+                &mut rss_delta,
+            )?;
+            nr_scanned += scanned;
+            nr_swapped += swapped;
+        }
+        Ok((nr_scanned, nr_swapped))
+    }
+
+    /// Swaps in all the private pages in the swap area `area_idx`.
+    fn swap_in_area(&self, area_idx: usize) -> Result<()> {
+        let inner = self.inner.read();
+        let mut rss_delta = RssDelta::new(self);
+
+        for addr in inner.swap_entries.keys_in_area(area_idx) {
+            let Some(entry) = inner.swap_entries.get(addr) else {
+                continue;
+            };
+            let Some(vm_mapping) = inner.vm_mappings.find_one(&addr) else {
+                continue;
+            };
+            vm_mapping.swap_in_page(
+                &self.vm_space,
+                addr,
+                entry,
+                &inner.swap_entries,
+                &mut rss_delta,
+            )?;
+        }
+        Ok(())
+    }
 }
 
 /// Sets mappings in the source page table as read-only to trigger COW, and
@@ -818,6 +928,48 @@ impl<R> Vmar<R> {
     pub fn get_mappings_total_size(&self) -> usize {
         self.0.inner.read().total_vm
     }
+
+    /// Swaps out at most `nr_to_scan` private pages.
+    ///
+    /// Returns the numbers of the scanned pages and the swapped-out pages.
+    pub(in crate::vm) fn swap_out(&self, nr_to_scan: usize) -> Result<(usize, usize)> {
+        self.0.swap_out(nr_to_scan)
+    }
+
+    /// Swaps in all the private pages in the swap area `area_idx`.
+    pub(in crate::vm) fn swap_in_area(&self, area_idx: usize) -> Result<()> {
+        self.0.swap_in_area(area_idx)
+    }
+
+    /// Reads the memory at `addr` into `buf` on behalf of another process.
+    ///
+    /// This is used by debuggers (e.g., via `ptrace`), so the permissions of the mappings
+    /// are not checked.
+    pub fn read_remote(&self, addr: Vaddr, buf: &mut [u8]) -> Result<()> {
+        let mut writer = VmWriter::from(buf);
+        self.0
+            .access_remote(addr, writer.avail(), false, |frame, range| {
+                let mut reader = frame.reader();
+                reader.skip(range.start).limit(range.len());
+                reader.read(&mut writer);
+            })
+    }
+
+    /// Writes `buf` to the memory at `addr` on behalf of another process.
+    ///
+    /// This is used by debuggers (e.g., via `ptrace`), so the permissions of the mappings
+    /// are not checked. Writing to read-only private mappings (e.g., to set breakpoints in
+    /// the code) is allowed and will trigger copy-on-write. Writing to read-only shared
+    /// mappings will fail with [`Errno::EFAULT`].
+    pub fn write_remote(&self, addr: Vaddr, buf: &[u8]) -> Result<()> {
+        let mut reader = VmReader::from(buf);
+        self.0
+            .access_remote(addr, reader.remain(), true, |frame, range| {
+                let mut writer = frame.writer();
+                writer.skip(range.start).limit(range.len());
+                writer.write(&mut reader);
+            })
+    }
 }
 
 /// Options for creating a new mapping. The mapping is not allowed to overlap
@@ -827,6 +979,8 @@ pub struct VmarMapOptions<'a, R1, R2> {
     parent: &'a Vmar<R1>,
     vmo: Option<Vmo<R2>>,
     inode: Option<Arc<dyn Inode>>,
+    name: Option<VmMappingName>,
+    shm_attach: Option<Arc<ShmAttach>>,
     perms: VmPerms,
     vmo_offset: usize,
     size: usize,
@@ -851,6 +1005,8 @@ impl<'a, R1, R2> VmarMapOptions<'a, R1, R2> {
             parent,
             vmo: None,
             inode: None,
+            name: None,
+            shm_attach: None,
             perms,
             vmo_offset: 0,
             size,
@@ -952,6 +1108,24 @@ impl<'a, R1, R2> VmarMapOptions<'a, R1, R2> {
         self.handle_page_faults_around = true;
         self
     }
+
+    /// Sets the name of the mapping.
+    ///
+    /// The name is shown in `/proc/[pid]/maps`. Adjacent mappings with
+    /// different names will not be merged.
+    pub fn name(mut self, name: VmMappingName) -> Self {
+        self.name = Some(name);
+        self
+    }
+
+    /// Binds the mapping to a System V shared memory attach.
+    ///
+    /// The attach is shared by the mappings split from the mapping, and is
+    /// detached when all of them are unmapped.
+    pub fn shm_attach(mut self, shm_attach: Arc<ShmAttach>) -> Self {
+        self.shm_attach = Some(shm_attach);
+        self
+    }
 }
 
 impl<R1> VmarMapOptions<'_, R1, Rights> {
@@ -999,6 +1173,8 @@ where
             parent,
             vmo,
             inode,
+            name,
+            shm_attach,
             perms,
             vmo_offset,
             size: map_size,
@@ -1056,10 +1232,12 @@ where
             map_to_addr,
             vmo,
             inode,
+            name,
             is_shared,
             handle_page_faults_around,
             perms,
-        );
+        )
+        .with_shm_attach(shm_attach);
 
         // Add the mapping to the VMAR.
         inner.insert_try_merge(vm_mapping);
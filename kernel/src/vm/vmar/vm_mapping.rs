@@ -16,11 +16,13 @@ use ostd::{
 
 use super::{interval_set::Interval, RssDelta, RssType};
 use crate::{
-    fs::utils::Inode,
+    fs::{path::Path, utils::Inode},
+    ipc::shm::shm_seg::ShmAttach,
     prelude::*,
     thread::exception::PageFaultInfo,
     vm::{
         perms::VmPerms,
+        swap::{self, SwapEntry, SwapTable},
         util::duplicate_frame,
         vmar::is_intersected,
         vmo::{CommitFlags, Vmo, VmoCommitError},
@@ -63,6 +65,10 @@ pub struct VmMapping {
     /// If the inode is `Some`, it means that the mapping is file-backed.
     /// And the `vmo` field must be the page cache of the inode.
     inode: Option<Arc<dyn Inode>>,
+    /// The name of the mapping, if any.
+    name: Option<VmMappingName>,
+    /// The System V shared memory attach that the mapping belongs to, if any.
+    shm_attach: Option<Arc<ShmAttach>>,
     /// Whether the mapping is shared.
     ///
     /// The updates to a shared mapping are visible among processes, or carried
@@ -91,6 +97,7 @@ impl VmMapping {
         map_to_addr: Vaddr,
         vmo: Option<MappedVmo>,
         inode: Option<Arc<dyn Inode>>,
+        name: Option<VmMappingName>,
         is_shared: bool,
         handle_page_faults_around: bool,
         perms: VmPerms,
@@ -100,16 +107,26 @@ impl VmMapping {
             map_to_addr,
             vmo,
             inode,
+            name,
+            shm_attach: None,
             is_shared,
             handle_page_faults_around,
             perms,
         }
     }
 
+    /// Binds the mapping to a System V shared memory attach.
+    pub(super) fn with_shm_attach(self, shm_attach: Option<Arc<ShmAttach>>) -> Self {
+        Self { shm_attach, ..self }
+    }
+
     pub(super) fn new_fork(&self) -> Result<VmMapping> {
         Ok(VmMapping {
             vmo: self.vmo.as_ref().map(|vmo| vmo.dup()).transpose()?,
             inode: self.inode.clone(),
+            name: self.name.clone(),
+            // The forked mapping belongs to a new attach in the forked address space.
+            shm_attach: self.shm_attach.as_ref().map(|attach| attach.fork()),
             ..*self
         })
     }
@@ -117,6 +134,8 @@ impl VmMapping {
     pub(super) fn clone_for_remap_at(&self, va: Vaddr) -> Result<VmMapping> {
         let mut vm_mapping = self.new_fork()?;
         vm_mapping.map_to_addr = va;
+        // The remapped mapping still belongs to the same attach.
+        vm_mapping.shm_attach = self.shm_attach.clone();
         Ok(vm_mapping)
     }
 
@@ -145,6 +164,35 @@ impl VmMapping {
         self.inode.as_ref()
     }
 
+    /// Returns the name of the mapping, if any.
+    pub fn name(&self) -> Option<&VmMappingName> {
+        self.name.as_ref()
+    }
+
+    /// Returns the System V shared memory attach that the mapping belongs to, if any.
+    pub fn shm_attach(&self) -> Option<&Arc<ShmAttach>> {
+        self.shm_attach.as_ref()
+    }
+
+    /// Returns whether the mapping is shared.
+    pub fn is_shared(&self) -> bool {
+        self.is_shared
+    }
+
+    /// Returns the VMO that backs the mapping.
+    ///
+    /// If the mapping is an independent anonymous mapping, `None` is returned.
+    pub fn vmo(&self) -> Option<&Vmo> {
+        self.vmo.as_ref().map(|mapped_vmo| &mapped_vmo.vmo)
+    }
+
+    /// Returns the offset in the VMO where the mapping starts.
+    ///
+    /// If the mapping is an independent anonymous mapping, `None` is returned.
+    pub fn vmo_offset(&self) -> Option<usize> {
+        self.vmo.as_ref().map(|mapped_vmo| mapped_vmo.offset)
+    }
+
     /// Returns the mapping's RSS type.
     pub fn rss_type(&self) -> RssType {
         if self.vmo.is_none() {
@@ -163,6 +211,7 @@ impl VmMapping {
         &self,
         vm_space: &VmSpace,
         page_fault_info: &PageFaultInfo,
+        swap_table: &SwapTable,
         rss_delta: &mut RssDelta,
     ) -> Result<()> {
         if !self.perms.contains(page_fault_info.required_perms) {
@@ -178,11 +227,17 @@ impl VmMapping {
         let page_aligned_addr = page_fault_info.address.align_down(PAGE_SIZE);
         let is_write = page_fault_info.required_perms.contains(VmPerms::WRITE);
 
-        if !is_write && self.vmo.is_some() && self.handle_page_faults_around {
+        // A swapped-out page must be swapped in, instead of being mapped from the VMO.
+        if !is_write
+            && self.vmo.is_some()
+            && self.handle_page_faults_around
+            && swap_table.get(page_aligned_addr).is_none()
+        {
             let res = self.handle_page_faults_around(
                 vm_space,
                 page_aligned_addr,
                 page_fault_info.required_perms,
+                swap_table,
                 rss_delta,
             );
 
@@ -206,6 +261,7 @@ impl VmMapping {
             vm_space,
             page_aligned_addr,
             page_fault_info.required_perms,
+            swap_table,
             rss_delta,
         )
     }
@@ -215,6 +271,7 @@ impl VmMapping {
         vm_space: &VmSpace,
         page_aligned_addr: Vaddr,
         required_perms: VmPerms,
+        swap_table: &SwapTable,
         rss_delta: &mut RssDelta,
     ) -> Result<()> {
         'retry: loop {
@@ -264,6 +321,19 @@ impl VmMapping {
                     cursor.flusher().sync_tlb_flush();
                 }
                 None => {
+                    if let Some(entry) = swap_table.get(page_aligned_addr) {
+                        drop(cursor);
+                        drop(preempt_guard);
+                        self.swap_in_page(
+                            vm_space,
+                            page_aligned_addr,
+                            entry,
+                            swap_table,
+                            rss_delta,
+                        )?;
+                        continue 'retry;
+                    }
+
                     // Map a new frame to the page fault address.
                     let (frame, is_readonly) = match self.prepare_page(page_aligned_addr, is_write)
                     {
@@ -303,9 +373,77 @@ impl VmMapping {
             break 'retry;
         }
 
+        // The page cache must write back the page modified via the shared mapping,
+        // and must not evict it as a clean page.
+        if required_perms.contains(VmPerms::WRITE) && self.is_shared {
+            if let Some(vmo) = &self.vmo {
+                vmo.mark_page_dirty(page_aligned_addr - self.map_to_addr)?;
+            }
+        }
+
         Ok(())
     }
 
+    /// Returns the frame mapped at `page_aligned_addr` for accesses from other processes.
+    ///
+    /// The page is mapped first if it has not been mapped. If `is_write` is true, the returned
+    /// frame is private to this mapping, even if the mapping is read-only. So writing to it
+    /// will not affect other mappings or files.
+    pub(super) fn get_frame_for_remote_access(
+        &self,
+        vm_space: &VmSpace,
+        page_aligned_addr: Vaddr,
+        is_write: bool,
+        swap_table: &SwapTable,
+        rss_delta: &mut RssDelta,
+    ) -> Result<UFrame> {
+        let is_writable = self.perms.contains(VmPerms::WRITE);
+        if is_write && self.is_shared && !is_writable {
+            return_errno_with_message!(Errno::EFAULT, "the shared mapping is not writable");
+        }
+
+        let required_perms = if is_write && is_writable {
+            VmPerms::WRITE
+        } else {
+            VmPerms::empty()
+        };
+
+        loop {
+            self.handle_single_page_fault(
+                vm_space,
+                page_aligned_addr,
+                required_perms,
+                swap_table,
+                rss_delta,
+            )?;
+
+            let preempt_guard = disable_preempt();
+            let mut cursor = vm_space.cursor_mut(
+                &preempt_guard,
+                &(page_aligned_addr..page_aligned_addr + PAGE_SIZE),
+            )?;
+
+            let (_, Some((frame, prop))) = cursor.query().unwrap() else {
+                // The page is unmapped by other threads. Try again.
+                continue;
+            };
+            if !is_write || prop.flags.contains(PageFlags::W) {
+                return Ok(frame);
+            }
+
+            // Perform COW for a private read-only page, but keep it read-only.
+            if frame.reference_count() == 2 {
+                return Ok(frame);
+            }
+            let new_frame: UFrame = duplicate_frame(&frame)?.into();
+            cursor.map(new_frame.clone(), prop);
+            rss_delta.add(self.rss_type(), 1);
+            cursor.flusher().sync_tlb_flush();
+
+            return Ok(new_frame);
+        }
+    }
+
     fn prepare_page(
         &self,
         page_aligned_addr: Vaddr,
@@ -342,6 +480,7 @@ impl VmMapping {
         vm_space: &VmSpace,
         page_aligned_addr: Vaddr,
         required_perms: VmPerms,
+        swap_table: &SwapTable,
         mut rss_delta: &mut RssDelta,
     ) -> Result<()> {
         const SURROUNDING_PAGE_NUM: usize = 16;
@@ -363,6 +502,7 @@ impl VmMapping {
                 vm_space,
                 page_aligned_addr,
                 required_perms,
+                swap_table,
                 rss_delta,
             );
         }
@@ -377,7 +517,10 @@ impl VmMapping {
             let operate =
                 move |commit_fn: &mut dyn FnMut()
                     -> core::result::Result<UFrame, VmoCommitError>| {
-                    if let (_, None) = cursor.query().unwrap() {
+                    // The swapped-out pages are skipped like the mapped ones, since they
+                    // must not be replaced by the pages of the VMO.
+                    let is_unmapped = matches!(cursor.query().unwrap(), (_, None));
+                    if is_unmapped && swap_table.get(cursor.virt_addr()).is_none() {
                         // We regard all the surrounding pages as accessed, no matter
                         // if it is really so. Then the hardware won't bother to update
                         // the accessed bit of the page table on following accesses.
@@ -409,6 +552,167 @@ impl VmMapping {
             }
         }
     }
+
+    /// Swaps in the page at `page_aligned_addr` from the swap slot of `entry`.
+    pub(super) fn swap_in_page(
+        &self,
+        vm_space: &VmSpace,
+        page_aligned_addr: Vaddr,
+        entry: SwapEntry,
+        swap_table: &SwapTable,
+        rss_delta: &mut RssDelta,
+    ) -> Result<()> {
+        // The page is read before locking the page table, since the I/O may sleep.
+        let frame = swap::read_page(entry)?;
+
+        let preempt_guard = disable_preempt();
+        let mut cursor = vm_space
+            .cursor_mut(
+                &preempt_guard,
+                &(page_aligned_addr..page_aligned_addr + PAGE_SIZE),
+            )
+            .unwrap();
+
+        // Skip if the page has been swapped in by other threads.
+        if let (_, Some(_)) = cursor.query().unwrap() {
+            return Ok(());
+        }
+        if !swap_table.remove(page_aligned_addr, entry) {
+            return Ok(());
+        }
+
+        // The page is private to the mapping, so it is mapped with the full permissions.
+        let page_flags = PageFlags::from(self.perms) | PageFlags::ACCESSED;
+        cursor.map(
+            frame,
+            PageProperty::new_user(page_flags, CachePolicy::Writeback),
+        );
+        rss_delta.add(self.rss_type(), 1);
+
+        Ok(())
+    }
+}
+
+/********************************** Swapping *********************************/
+
+impl VmMapping {
+    /// Swaps out the private pages in the mapping, scanning at most `nr_to_scan` pages.
+    ///
+    /// Only the pages that are exclusively owned by the mapping are swapped out. Like
+    /// Linux, a page that has been accessed gets a second chance: its accessed bit is
+    /// cleared, and it is swapped out if it is still not accessed in a later scan.
+    ///
+    /// Returns the numbers of the scanned pages and the swapped-out pages.
+    pub(super) fn swap_out(
+        &self,
+        vm_space: &VmSpace,
+        swap_table: &SwapTable,
+        nr_to_scan: usize,
+        rss_delta: &mut RssDelta,
+    ) -> Result<(usize, usize)> {
+        // The pages of a shared mapping belong to its VMO, which is swapped out separately.
+        if self.is_shared {
+            return Ok((0, 0));
+        }
+
+        let range = self.range();
+        let mut start_addr = range.start;
+        let mut nr_scanned = 0;
+        let mut nr_swapped = 0;
+        while start_addr < range.end && nr_scanned < nr_to_scan {
+            let Some(entry) = swap::alloc_slot() else {
+                break;
+            };
+
+            // Find a page to swap out, and write-protect it so that it is not modified
+            // while being written to the swap slot.
+            let candidate = {
+                let preempt_guard = disable_preempt();
+                let mut cursor = vm_space
+                    .cursor_mut(&preempt_guard, &(start_addr..range.end))
+                    .unwrap();
+
+                let mut candidate = None;
+                while cursor.virt_addr() < range.end && nr_scanned < nr_to_scan {
+                    let Some(va) = cursor.find_next(range.end - cursor.virt_addr()) else {
+                        break;
+                    };
+                    let (_, Some((frame, prop))) = cursor.query().unwrap() else {
+                        panic!("Found mapped page but query failed");
+                    };
+                    nr_scanned += 1;
+
+                    // The reference count of a page that is exclusively owned by the
+                    // mapping is 2 (one for the mapping and one for the frame handle).
+                    // Other pages are shared with the VMO or the forked processes.
+                    if frame.reference_count() != 2 {
+                        if va + PAGE_SIZE >= range.end {
+                            break;
+                        }
+                        cursor.jump(va + PAGE_SIZE).unwrap();
+                        continue;
+                    }
+
+                    let is_accessed = prop.flags.contains(PageFlags::ACCESSED);
+                    let op = |p: &mut PageProperty| {
+                        if is_accessed {
+                            p.flags -= PageFlags::ACCESSED;
+                        } else {
+                            p.flags -= PageFlags::W;
+                        }
+                    };
+                    if let Some(protected_va) = cursor.protect_next(PAGE_SIZE, op) {
+                        cursor
+                            .flusher()
+                            .issue_tlb_flush(TlbFlushOp::Range(protected_va));
+                    }
+                    if !is_accessed {
+                        candidate = Some((va, frame));
+                        break;
+                    }
+                }
+                cursor.flusher().dispatch_tlb_flush();
+                cursor.flusher().sync_tlb_flush();
+
+                candidate
+            };
+            let Some((va, frame)) = candidate else {
+                swap::free_slot(entry);
+                break;
+            };
+            start_addr = va + PAGE_SIZE;
+
+            if let Err(err) = swap::write_page(entry, &frame) {
+                swap::free_slot(entry);
+                return Err(err);
+            }
+
+            let preempt_guard = disable_preempt();
+            let mut cursor = vm_space
+                .cursor_mut(&preempt_guard, &(va..va + PAGE_SIZE))
+                .unwrap();
+
+            // The page may have been unmapped or made writable during the I/O.
+            let (_, item) = cursor.query().unwrap();
+            let is_unchanged = item.is_some_and(|(mapped_frame, prop)| {
+                mapped_frame.start_paddr() == frame.start_paddr()
+                    && !prop.flags.contains(PageFlags::W)
+            });
+            if !is_unchanged {
+                swap::free_slot(entry);
+                continue;
+            }
+
+            cursor.unmap(PAGE_SIZE);
+            cursor.flusher().dispatch_tlb_flush();
+            cursor.flusher().sync_tlb_flush();
+            swap_table.insert(va, entry);
+            rss_delta.add(self.rss_type(), -1);
+            nr_swapped += 1;
+        }
+
+        Ok((nr_scanned, nr_swapped))
+    }
 }
 
 /**************************** Transformations ********************************/
@@ -446,6 +750,8 @@ impl VmMapping {
             map_size: NonZeroUsize::new(left_size).unwrap(),
             vmo: l_vmo,
             inode: self.inode.clone(),
+            name: self.name.clone(),
+            shm_attach: self.shm_attach.clone(),
             ..self
         };
         let right = Self {
@@ -532,6 +838,15 @@ impl VmMapping {
     }
 }
 
+/// The name of a [`VmMapping`], which is shown in `/proc/[pid]/maps`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VmMappingName {
+    /// The mapping maps the content of the file at the path.
+    File(Path),
+    /// The mapping is a special one, such as `[heap]`, `[stack]` and `[vdso]`.
+    Special(&'static str),
+}
+
 /************************** VM Space operations ******************************/
 
 impl VmMapping {
@@ -635,6 +950,14 @@ impl MappedVmo {
         self.vmo.try_operate_on_range(&range, operate)
     }
 
+    /// Marks the page at the input offset in the mapped VMO as dirty.
+    fn mark_page_dirty(&self, page_offset: usize) -> Result<()> {
+        debug_assert!(page_offset % PAGE_SIZE == 0);
+        self.vmo
+            .0
+            .mark_page_dirty((self.offset + page_offset) / PAGE_SIZE)
+    }
+
     /// Duplicates the capability.
     pub fn dup(&self) -> Result<Self> {
         Ok(Self {
@@ -655,7 +978,9 @@ fn try_merge(left: &VmMapping, right: &VmMapping) -> Option<VmMapping> {
     let is_adjacent = left.map_end() == right.map_to_addr();
     let is_type_equal = left.is_shared == right.is_shared
         && left.handle_page_faults_around == right.handle_page_faults_around
-        && left.perms == right.perms;
+        && left.perms == right.perms
+        && left.name == right.name
+        && is_same_shm_attach(left, right);
 
     if !is_adjacent || !is_type_equal {
         return None;
@@ -679,6 +1004,16 @@ fn try_merge(left: &VmMapping, right: &VmMapping) -> Option<VmMapping> {
         map_size,
         vmo,
         inode: left.inode.clone(),
+        name: left.name.clone(),
+        shm_attach: left.shm_attach.clone(),
         ..*left
     })
 }
+
+fn is_same_shm_attach(left: &VmMapping, right: &VmMapping) -> bool {
+    match (&left.shm_attach, &right.shm_attach) {
+        (None, None) => true,
+        (Some(l_attach), Some(r_attach)) => Arc::ptr_eq(l_attach, r_attach),
+        _ => false,
+    }
+}
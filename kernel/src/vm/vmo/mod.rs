@@ -18,7 +18,13 @@ use ostd::{
 };
 use xarray::{Cursor, LockedXArray, XArray};
 
-use crate::prelude::*;
+use crate::{
+    prelude::*,
+    vm::{
+        reclaim,
+        swap::{self, SwapEntry, SwapTable},
+    },
+};
 
 mod dyn_cap;
 mod options;
@@ -155,8 +161,16 @@ pub(super) struct Vmo_ {
     /// the [`XArray`] in the `pages` field. Therefore, the size read after locking the
     /// `pages` will be the latest size.
     size: AtomicUsize,
+    /// The swap entries of the swapped-out pages, keyed by their indices.
+    ///
+    /// Only the pages of anonymous VMOs can be swapped out. The entries are changed
+    /// only when the `pages` are locked.
+    swap_entries: SwapTable,
 }
 
+/// The anonymous VMOs whose pages can be swapped out, keyed by their addresses.
+static ANON_VMOS: Mutex<BTreeMap<usize, Weak<Vmo_>>> = Mutex::new(BTreeMap::new());
+
 impl Debug for Vmo_ {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         f.debug_struct("Vmo_")
@@ -187,7 +201,9 @@ impl Vmo_ {
     /// This operation may involve I/O operations if the VMO is backed by a pager.
     fn prepare_page(&self, page_idx: usize, commit_flags: CommitFlags) -> Result<UFrame> {
         match &self.pager {
-            None => Ok(FrameAllocOptions::new().alloc_frame()?.into()),
+            None => {
+                reclaim::alloc_with_reclaim(|| Ok(FrameAllocOptions::new().alloc_frame()?.into()))
+            }
             Some(pager) => {
                 if commit_flags.will_overwrite() {
                     pager.commit_overwrite(page_idx)
@@ -203,8 +219,30 @@ impl Vmo_ {
     /// This method may involve I/O operations if the VMO needs to fetch a page from
     /// the underlying page cache.
     pub fn commit_on(&self, page_idx: usize, commit_flags: CommitFlags) -> Result<UFrame> {
-        let new_page = self.prepare_page(page_idx, commit_flags)?;
+        loop {
+            let swap_entry = self.swap_entries.get(page_idx);
+            let new_page = match swap_entry {
+                Some(entry) if !commit_flags.will_overwrite() => swap::read_page(entry)?,
+                _ => self.prepare_page(page_idx, commit_flags)?,
+            };
+
+            if let Some(page) = self.store_page(page_idx, new_page, swap_entry)? {
+                return Ok(page);
+            }
+        }
+    }
 
+    /// Stores `new_page` at `page_idx` if no page is committed there, returning the
+    /// committed page.
+    ///
+    /// `swap_entry` is the swap entry from which `new_page` is swapped in, if any.
+    /// Returns `None` if the page has been swapped out with another swap entry.
+    fn store_page(
+        &self,
+        page_idx: usize,
+        new_page: UFrame,
+        swap_entry: Option<SwapEntry>,
+    ) -> Result<Option<UFrame>> {
         let mut locked_pages = self.pages.lock();
         if page_idx * PAGE_SIZE > self.size() {
             return_errno_with_message!(Errno::EINVAL, "the offset is outside the VMO");
@@ -212,11 +250,18 @@ impl Vmo_ {
 
         let mut cursor = locked_pages.cursor_mut(page_idx as u64);
         if let Some(page) = cursor.load() {
-            return Ok(page.clone());
+            return Ok(Some(page.clone()));
+        }
+
+        if self.swap_entries.get(page_idx) != swap_entry {
+            return Ok(None);
+        }
+        if let Some(entry) = swap_entry {
+            self.swap_entries.remove(page_idx, entry);
         }
 
         cursor.store(new_page.clone());
-        Ok(new_page)
+        Ok(Some(new_page))
     }
 
     fn try_commit_with_cursor(
@@ -236,8 +281,23 @@ impl Vmo_ {
             return Err(VmoCommitError::NeedIo(cursor.index() as usize));
         }
 
-        let frame = self.commit_on(cursor.index() as usize, CommitFlags::empty())?;
-        Ok(frame)
+        // Swapping in a page needs I/O.
+        let page_idx = cursor.index() as usize;
+        if self.swap_entries.get(page_idx).is_some() {
+            return Err(VmoCommitError::NeedIo(page_idx));
+        }
+
+        // The reclamation may sleep, so it is left to the slow path with `commit_on`,
+        // which is taken as if the commit needs I/O.
+        let new_page = match FrameAllocOptions::new().alloc_frame() {
+            Ok(frame) => frame.into(),
+            Err(ostd::Error::NoMemory) => return Err(VmoCommitError::NeedIo(page_idx)),
+            Err(err) => return Err(VmoCommitError::Err(err.into())),
+        };
+        match self.store_page(page_idx, new_page, None)? {
+            Some(frame) => Ok(frame),
+            None => Err(VmoCommitError::NeedIo(page_idx)),
+        }
     }
 
     /// Commits the page corresponding to the target offset in the VMO.
@@ -401,6 +461,15 @@ impl Vmo_ {
         Ok(())
     }
 
+    /// Marks the page at `page_idx` as dirty, which is modified without [`Self::write`]
+    /// (e.g., via a shared mapping).
+    pub fn mark_page_dirty(&self, page_idx: usize) -> Result<()> {
+        if let Some(pager) = &self.pager {
+            pager.update_page(page_idx)?;
+        }
+        Ok(())
+    }
+
     /// Clears the target range in current VMO.
     pub fn clear(&self, range: Range<usize>) -> Result<()> {
         let buffer = vec![0u8; range.end - range.start];
@@ -444,6 +513,7 @@ impl Vmo_ {
         let mut cursor = locked_pages.cursor_mut(page_idx_range.start as u64);
 
         let Some(pager) = &self.pager else {
+            self.swap_entries.remove_range(page_idx_range.clone());
             for _ in page_idx_range {
                 cursor.remove();
                 cursor.next();
@@ -473,15 +543,207 @@ impl Vmo_ {
         self.flags
     }
 
+    /// Returns the number of pages that are currently committed.
+    pub fn num_committed_pages(&self) -> usize {
+        let guard = disable_preempt();
+        let num_pages = self.size().div_ceil(PAGE_SIZE) as u64;
+        self.pages.range(&guard, 0..num_pages).count()
+    }
+
+    /// Removes the committed page at `page_idx` without notifying the pager,
+    /// if `evictable` returns `true` for it.
+    ///
+    /// Returns whether the page is not committed afterward.
+    fn evict_page_if(&self, page_idx: usize, evictable: impl Fn(&UFrame) -> bool) -> bool {
+        let mut locked_pages = self.pages.lock();
+        let mut cursor = locked_pages.cursor_mut(page_idx as u64);
+        let Some(page) = cursor.load() else {
+            return true;
+        };
+        if !evictable(&page) {
+            return false;
+        }
+
+        // The page may be referenced by others before it is removed, so it is checked again.
+        let page = cursor.remove().unwrap();
+        if !evictable(&page) {
+            cursor.store(page);
+            return false;
+        }
+        true
+    }
+
     fn replace(&self, page: UFrame, page_idx: usize) -> Result<()> {
         let mut locked_pages = self.pages.lock();
         if page_idx >= self.size() / PAGE_SIZE {
             return_errno_with_message!(Errno::EINVAL, "the page index is outside of the vmo");
         }
 
+        self.swap_entries.remove_range(page_idx..page_idx + 1);
         locked_pages.store(page_idx as u64, page);
         Ok(())
     }
+
+    /// Returns whether the pages of the VMO can be swapped out.
+    fn is_swappable(&self) -> bool {
+        self.pager.is_none() && !self.flags.intersects(VmoFlags::CONTIGUOUS | VmoFlags::DMA)
+    }
+
+    /// Swaps out the pages not in use, scanning at most `nr_to_scan` committed pages.
+    ///
+    /// Returns the numbers of the scanned pages and the swapped-out pages.
+    fn swap_out(&self, nr_to_scan: usize) -> Result<(usize, usize)> {
+        let mut page_idx = 0;
+        let mut nr_scanned = 0;
+        let mut nr_swapped = 0;
+        while nr_scanned < nr_to_scan {
+            let next_page = {
+                let guard = disable_preempt();
+                let num_pages = self.size().div_ceil(PAGE_SIZE) as u64;
+                self.pages
+                    .range(&guard, page_idx as u64..num_pages)
+                    .next()
+                    .map(|(idx, page)| (idx as usize, page.reference_count() == 1))
+            };
+            let Some((idx, is_unused)) = next_page else {
+                break;
+            };
+            page_idx = idx + 1;
+            nr_scanned += 1;
+
+            // A page that is mapped or being accessed is referenced by others.
+            if !is_unused {
+                continue;
+            }
+            let Some(entry) = swap::alloc_slot() else {
+                break;
+            };
+            if self.swap_out_page(idx, entry)? {
+                nr_swapped += 1;
+            }
+        }
+
+        Ok((nr_scanned, nr_swapped))
+    }
+
+    /// Swaps out the page at `page_idx` to the swap slot of `entry`, which is consumed.
+    ///
+    /// Returns whether the page is swapped out.
+    fn swap_out_page(&self, page_idx: usize, entry: SwapEntry) -> Result<bool> {
+        let page = {
+            let mut locked_pages = self.pages.lock();
+            let mut cursor = locked_pages.cursor_mut(page_idx as u64);
+            let page = match cursor.remove() {
+                Some(page) if page.reference_count() == 1 => page,
+                page => {
+                    if let Some(page) = page {
+                        cursor.store(page);
+                    }
+                    drop(locked_pages);
+                    swap::free_slot(entry);
+                    return Ok(false);
+                }
+            };
+
+            // The slot is not read until the page is written to it.
+            swap::start_writeback(entry);
+            self.swap_entries.insert(page_idx, entry);
+            page
+        };
+
+        let res = swap::write_page(entry, &page);
+
+        let mut locked_pages = self.pages.lock();
+        // The page may have been referenced by the readers that load it before its
+        // removal, so it is checked again.
+        let is_swapped = res.is_ok() && page.reference_count() == 1;
+        if !is_swapped && self.swap_entries.remove(page_idx, entry) {
+            locked_pages.store(page_idx as u64, page);
+        }
+        drop(locked_pages);
+        swap::end_writeback(entry);
+
+        res.map(|_| is_swapped)
+    }
+
+    /// Swaps in all the pages in the swap area `area_idx`.
+    fn swap_in_area(&self, area_idx: usize) -> Result<()> {
+        for page_idx in self.swap_entries.keys_in_area(area_idx) {
+            if self.swap_entries.get(page_idx).is_some() {
+                self.commit_on(page_idx, CommitFlags::empty())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Vmo_ {
+    fn drop(&mut self) {
+        if self.is_swappable() {
+            ANON_VMOS.lock().remove(&(self as *const Self as usize));
+        }
+    }
+}
+
+/// Registers a newly allocated VMO, so that its pages can be swapped out if it is anonymous.
+fn register_anon_vmo(vmo: &Arc<Vmo_>) {
+    if vmo.is_swappable() {
+        ANON_VMOS
+            .lock()
+            .insert(Arc::as_ptr(vmo) as usize, Arc::downgrade(vmo));
+    }
+}
+
+/// Returns the next live anonymous VMO whose key is not less than `next_key`,
+/// advancing `next_key` past it.
+fn next_anon_vmo(
+    anon_vmos: &BTreeMap<usize, Weak<Vmo_>>,
+    next_key: &mut usize,
+) -> Option<Arc<Vmo_>> {
+    let (key, vmo) = anon_vmos
+        .range(*next_key..)
+        .find_map(|(key, vmo)| Some((*key, vmo.upgrade()?)))?;
+    *next_key = key + 1;
+    Some(vmo)
+}
+
+/// Swaps out the pages of the anonymous VMOs that are not in use, scanning at most
+/// `nr_to_scan` committed pages.
+///
+/// Returns the numbers of the scanned pages and the swapped-out pages.
+pub(in crate::vm) fn swap_out_anon_pages(nr_to_scan: usize) -> Result<(usize, usize)> {
+    let mut next_key = 0;
+    let mut nr_scanned = 0;
+    let mut nr_swapped = 0;
+    while nr_scanned < nr_to_scan {
+        // The registry is not waited for, since its lock may be held by the allocator.
+        // The guard must be dropped before the VMO, whose drop locks the registry.
+        let Some(vmo) = ANON_VMOS
+            .try_lock()
+            .and_then(|anon_vmos| next_anon_vmo(&anon_vmos, &mut next_key))
+        else {
+            break;
+        };
+
+        let (scanned, swapped) = vmo.swap_out(nr_to_scan - nr_scanned)?;
+        nr_scanned += scanned;
+        nr_swapped += swapped;
+    }
+    Ok((nr_scanned, nr_swapped))
+}
+
+/// Swaps in all the pages of the anonymous VMOs in the swap area `area_idx`.
+pub(in crate::vm) fn swap_in_anon_pages(area_idx: usize) -> Result<()> {
+    let mut next_key = 0;
+    loop {
+        // The guard must be dropped before the VMO, whose drop locks the registry.
+        let vmo = next_anon_vmo(&ANON_VMOS.lock(), &mut next_key);
+        let Some(vmo) = vmo else {
+            break;
+        };
+        vmo.swap_in_area(area_idx)?;
+    }
+    Ok(())
 }
 
 impl<R> Vmo<R> {
@@ -494,6 +756,37 @@ impl<R> Vmo<R> {
     pub fn flags(&self) -> VmoFlags {
         self.0.flags()
     }
+
+    /// Returns the number of pages that are currently committed in a VMO.
+    pub fn num_committed_pages(&self) -> usize {
+        self.0.num_committed_pages()
+    }
+
+    /// Creates a weak reference to the VMO.
+    pub fn downgrade(&self) -> WeakVmo {
+        WeakVmo(Arc::downgrade(&self.0))
+    }
+}
+
+/// A weak reference to a VMO, which does not keep the VMO alive.
+///
+/// It allows the owner of the pages (e.g., the page cache) to evict the pages from
+/// the VMO without holding a capability of the VMO.
+#[derive(Debug, Clone)]
+pub struct WeakVmo(Weak<Vmo_>);
+
+impl WeakVmo {
+    /// Removes the committed page at `page_idx` from the VMO without notifying the pager,
+    /// if `evictable` returns `true` for it.
+    ///
+    /// Returns whether the page is not committed afterward, which is always the case
+    /// if the VMO has been dropped.
+    pub fn evict_page_if(&self, page_idx: usize, evictable: impl Fn(&UFrame) -> bool) -> bool {
+        match self.0.upgrade() {
+            Some(vmo) => vmo.evict_page_if(page_idx, evictable),
+            None => true,
+        }
+    }
 }
 
 /// Gets the page index range that contains the offset range of VMO.
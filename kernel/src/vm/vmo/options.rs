@@ -11,8 +11,11 @@ use aster_rights::{Rights, TRightSet, TRights};
 use ostd::mm::{FrameAllocOptions, UFrame, USegment};
 use xarray::XArray;
 
-use super::{Pager, Vmo, VmoFlags};
-use crate::{prelude::*, vm::vmo::Vmo_};
+use super::{register_anon_vmo, Pager, Vmo, VmoFlags};
+use crate::{
+    prelude::*,
+    vm::{reclaim, swap::SwapTable, vmo::Vmo_},
+};
 
 /// Options for allocating a root VMO.
 ///
@@ -97,7 +100,7 @@ impl VmoOptions<Rights> {
             size, flags, pager, ..
         } = self;
         let vmo_ = alloc_vmo_(size, flags, pager)?;
-        Ok(Vmo(Arc::new(vmo_), Rights::all()))
+        Ok(Vmo(vmo_, Rights::all()))
     }
 }
 
@@ -116,26 +119,33 @@ impl<R: TRights> VmoOptions<TRightSet<R>> {
             pager,
         } = self;
         let vmo_ = alloc_vmo_(size, flags, pager)?;
-        Ok(Vmo(Arc::new(vmo_), TRightSet(R::new())))
+        Ok(Vmo(vmo_, TRightSet(R::new())))
     }
 }
 
-fn alloc_vmo_(size: usize, flags: VmoFlags, pager: Option<Arc<dyn Pager>>) -> Result<Vmo_> {
+fn alloc_vmo_(size: usize, flags: VmoFlags, pager: Option<Arc<dyn Pager>>) -> Result<Arc<Vmo_>> {
     let size = size.align_up(PAGE_SIZE);
     let pages = committed_pages_if_continuous(flags, size)?;
-    Ok(Vmo_ {
+    let vmo_ = Arc::new(Vmo_ {
         pager,
         flags,
         pages,
         size: AtomicUsize::new(size),
-    })
+        swap_entries: SwapTable::new(),
+    });
+    register_anon_vmo(&vmo_);
+    Ok(vmo_)
 }
 
 fn committed_pages_if_continuous(flags: VmoFlags, size: usize) -> Result<XArray<UFrame>> {
     if flags.contains(VmoFlags::CONTIGUOUS) {
         // if the vmo is continuous, we need to allocate frames for the vmo
         let frames_num = size / PAGE_SIZE;
-        let segment: USegment = FrameAllocOptions::new().alloc_segment(frames_num)?.into();
+        let segment: USegment =
+            reclaim::alloc_with_reclaim(
+                || Ok(FrameAllocOptions::new().alloc_segment(frames_num)?),
+            )?
+            .into();
         let committed_pages = XArray::new();
         let mut locked_pages = committed_pages.lock();
         let mut cursor = locked_pages.cursor_mut(0);
@@ -2,7 +2,11 @@
 
 //! IRQ line and IRQ guards.
 
-use core::{fmt::Debug, ops::Deref};
+use core::{
+    fmt::Debug,
+    ops::{Deref, RangeInclusive},
+    sync::atomic::{AtomicUsize, Ordering},
+};
 
 use id_alloc::IdAlloc;
 use spin::Once;
@@ -12,6 +16,8 @@ use crate::{
         irq::{self, IrqRemapping, IRQ_NUM_MAX, IRQ_NUM_MIN},
         trap::TrapFrame,
     },
+    cpu::CpuId,
+    cpu_local,
     prelude::*,
     sync::{GuardTransfer, RwLock, SpinLock, WriteIrqDisabled},
     task::atomic_mode::InAtomicMode,
@@ -138,6 +144,28 @@ const NUMBER_OF_IRQS: usize = (IRQ_NUM_MAX - IRQ_NUM_MIN) as usize + 1;
 static INNERS: [Inner; NUMBER_OF_IRQS] = [const { Inner::new() }; NUMBER_OF_IRQS];
 static ALLOCATOR: Once<SpinLock<IdAlloc>> = Once::new();
 
+cpu_local! {
+    /// The number of times that each IRQ line has been handled on this CPU.
+    static IRQ_COUNTS: [AtomicUsize; NUMBER_OF_IRQS] =
+        [const { AtomicUsize::new(0) }; NUMBER_OF_IRQS];
+}
+
+/// Returns the range of the IRQ numbers that can be allocated as [`IrqLine`]s.
+pub fn irq_nums() -> RangeInclusive<u8> {
+    IRQ_NUM_MIN..=IRQ_NUM_MAX
+}
+
+/// Returns how many times the IRQ with the number `irq_num` has been handled on `cpu`.
+///
+/// # Panics
+///
+/// This function will panic if `irq_num` is not in [`irq_nums`].
+pub fn irq_count_on_cpu(irq_num: u8, cpu: CpuId) -> usize {
+    assert!(irq_nums().contains(&irq_num));
+
+    IRQ_COUNTS.get_on_cpu(cpu)[(irq_num - IRQ_NUM_MIN) as usize].load(Ordering::Relaxed)
+}
+
 fn get_or_init_allocator() -> &'static SpinLock<IdAlloc> {
     ALLOCATOR.call_once(|| SpinLock::new(IdAlloc::with_capacity(NUMBER_OF_IRQS)))
 }
@@ -187,7 +215,13 @@ impl Drop for CallbackHandle {
 }
 
 pub(super) fn process_top_half(trap_frame: &TrapFrame, irq_num: usize) {
-    let inner = &INNERS[irq_num - (IRQ_NUM_MIN as usize)];
+    let index = irq_num - (IRQ_NUM_MIN as usize);
+
+    let irq_guard = disable_local();
+    IRQ_COUNTS.get_with(&irq_guard)[index].fetch_add(1, Ordering::Relaxed);
+    drop(irq_guard);
+
+    let inner = &INNERS[index];
     for callback in &*inner.callbacks.read() {
         callback(trap_frame);
     }
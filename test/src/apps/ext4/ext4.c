@@ -0,0 +1,189 @@
+// SPDX-License-Identifier: MPL-2.0
+
+// The test runs on the image made by `mkfs.ext4` in `test/Makefile`, which has
+// the extents, the hash tree indexes of directories, the metadata checksums and
+// a journal that needs recovery.
+
+#define _GNU_SOURCE
+
+#include <dirent.h>
+#include <fcntl.h>
+#include <sys/mount.h>
+#include <sys/stat.h>
+#include <unistd.h>
+
+#include "../test.h"
+
+#define EXT4_DEVICE "vext4"
+#define EXT4_DIR "/ext4"
+
+// `extents.txt` consists of the numbers from 1 to `NR_LINES`, one per line.
+#define EXTENTS_FILE EXT4_DIR "/extents.txt"
+#define NR_LINES 200000
+// `htree` consists of the files named `file_<i>` for `i` from 1 to `NR_FILES`,
+// each of which contains `i` followed by a newline.
+#define HTREE_DIR EXT4_DIR "/htree"
+#define NR_FILES 1000
+
+// The block of `replay.txt` is rewritten by a transaction left in the journal.
+#define REPLAY_FILE EXT4_DIR "/replay.txt"
+
+#define NEW_FILE EXT4_DIR "/new_file"
+#define NEW_DIR EXT4_DIR "/new_dir"
+#define NEW_FILE_SIZE (1024 * 1024 + 123)
+
+static char buf[2 * 1024 * 1024];
+static char expected[2 * 1024 * 1024];
+
+static void mount_ext4(void)
+{
+	CHECK(mount(EXT4_DEVICE, EXT4_DIR, "ext4", 0, NULL));
+}
+
+static void remount_ext4(void)
+{
+	sync();
+	CHECK(umount(EXT4_DIR));
+	mount_ext4();
+}
+
+FN_SETUP(mount)
+{
+	CHECK(mkdir(EXT4_DIR, 0755));
+
+	// The device is only attached to the x86-64 virtual machines.
+	if (mount(EXT4_DEVICE, EXT4_DIR, "ext4", 0, NULL) == 0)
+		return;
+	if (errno == ENOENT) {
+		fprintf(stderr, "the ext4 device does not exist, skipping\n");
+		exit(EXIT_SUCCESS);
+	}
+	perror("failed to mount the ext4 device");
+	exit(EXIT_FAILURE);
+}
+END_SETUP()
+
+static ssize_t read_file(const char *path)
+{
+	ssize_t len;
+	int fd;
+
+	fd = open(path, O_RDONLY);
+	if (fd < 0)
+		return -1;
+	len = read(fd, buf, sizeof(buf));
+	close(fd);
+	return len;
+}
+
+static int fill_expected_lines(void)
+{
+	int i, len = 0;
+
+	for (i = 1; i <= NR_LINES; i++)
+		len += sprintf(expected + len, "%d\n", i);
+	return len;
+}
+
+static void fill_pattern(char *data, size_t len, int seed)
+{
+	size_t i;
+
+	for (i = 0; i < len; i++)
+		data[i] = (char)(i * 7 + seed);
+}
+
+static int check_htree_files(void)
+{
+	char path[64], content[16];
+	int i, len;
+
+	for (i = 1; i <= NR_FILES; i += 37) {
+		snprintf(path, sizeof(path), HTREE_DIR "/file_%d", i);
+		len = read_file(path);
+		snprintf(content, sizeof(content), "%d\n", i);
+		if (len != strlen(content) || memcmp(buf, content, len) != 0)
+			return -1;
+	}
+	return 0;
+}
+
+FN_TEST(journal_replay)
+{
+	TEST_RES(read_file(REPLAY_FILE),
+		 _ret == 6 && memcmp(buf, "fresh\n", 6) == 0);
+}
+END_TEST()
+
+FN_TEST(read_extents)
+{
+	int len;
+
+	len = fill_expected_lines();
+	TEST_RES(read_file(EXTENTS_FILE),
+		 _ret == len && memcmp(buf, expected, len) == 0);
+}
+END_TEST()
+
+FN_TEST(htree_lookup)
+{
+	struct stat st;
+
+	TEST_SUCC(check_htree_files());
+	TEST_RES(stat(HTREE_DIR "/file_1000", &st), st.st_size == 5);
+	TEST_ERRNO(stat(HTREE_DIR "/file_0", &st), ENOENT);
+	TEST_ERRNO(stat(HTREE_DIR "/file_1001", &st), ENOENT);
+}
+END_TEST()
+
+FN_TEST(write_with_checksums)
+{
+	struct stat st;
+	int fd;
+
+	// Write a file with holes and a directory, whose metadata are checksummed.
+	fill_pattern(expected, NEW_FILE_SIZE, 3);
+	fd = TEST_SUCC(open(NEW_FILE, O_RDWR | O_CREAT | O_TRUNC, 0644));
+	TEST_RES(pwrite(fd, expected, 4096, 0), _ret == 4096);
+	TEST_RES(pwrite(fd, expected + 65536, NEW_FILE_SIZE - 65536, 65536),
+		 _ret == NEW_FILE_SIZE - 65536);
+	TEST_SUCC(close(fd));
+	memset(expected + 4096, 0, 65536 - 4096);
+	TEST_SUCC(mkdir(NEW_DIR, 0755));
+
+	// Modify the indexed directory, which drops the index.
+	TEST_SUCC(unlink(HTREE_DIR "/file_500"));
+	TEST_SUCC(rename(HTREE_DIR "/file_501", HTREE_DIR "/renamed"));
+
+	// The checksums are verified when the file system is mounted again.
+	remount_ext4();
+
+	TEST_RES(read_file(NEW_FILE), _ret == NEW_FILE_SIZE &&
+					      memcmp(buf, expected,
+						     NEW_FILE_SIZE) == 0);
+	TEST_RES(stat(NEW_DIR, &st), S_ISDIR(st.st_mode));
+	TEST_ERRNO(stat(HTREE_DIR "/file_500", &st), ENOENT);
+	TEST_ERRNO(stat(HTREE_DIR "/file_501", &st), ENOENT);
+	TEST_RES(read_file(HTREE_DIR "/renamed"),
+		 _ret == 4 && memcmp(buf, "501\n", 4) == 0);
+	TEST_SUCC(check_htree_files());
+
+	// Shrink the file, which frees the blocks of the extents.
+	TEST_SUCC(truncate(NEW_FILE, 8192));
+	TEST_SUCC(unlink(HTREE_DIR "/renamed"));
+	remount_ext4();
+
+	TEST_RES(read_file(NEW_FILE),
+		 _ret == 8192 && memcmp(buf, expected, 8192) == 0);
+	TEST_SUCC(unlink(NEW_FILE));
+	TEST_SUCC(rmdir(NEW_DIR));
+}
+END_TEST()
+
+FN_TEST(umount)
+{
+	sync();
+	TEST_SUCC(umount(EXT4_DIR));
+	TEST_SUCC(rmdir(EXT4_DIR));
+}
+END_TEST()
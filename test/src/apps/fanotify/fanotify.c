@@ -0,0 +1,231 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#include "../test.h"
+
+#include <fcntl.h>
+#include <unistd.h>
+#include <sys/fanotify.h>
+#include <sys/stat.h>
+#include <sys/uio.h>
+#include <sys/wait.h>
+
+#define DIR_PATH "/tmp/fanotify_test"
+#define FILE_PATH DIR_PATH "/file"
+
+static struct stat file_stat;
+
+static char buf[4096]
+	__attribute__((aligned(__alignof__(struct fanotify_event_metadata))));
+
+static int is_file(int fd)
+{
+	struct stat stat;
+
+	return fstat(fd, &stat) == 0 && stat.st_dev == file_stat.st_dev &&
+	       stat.st_ino == file_stat.st_ino;
+}
+
+FN_SETUP(init)
+{
+	int fd;
+
+	CHECK(mkdir(DIR_PATH, 0755));
+	fd = CHECK(open(FILE_PATH, O_WRONLY | O_CREAT, 0644));
+	CHECK(write(fd, "a", 1));
+	CHECK(fstat(fd, &file_stat));
+	CHECK(close(fd));
+}
+END_SETUP()
+
+FN_TEST(invalid_args)
+{
+	int fan_fd;
+
+	TEST_ERRNO(fanotify_init(FAN_CLASS_CONTENT | FAN_CLASS_PRE_CONTENT,
+				 O_RDONLY),
+		   EINVAL);
+	TEST_ERRNO(fanotify_init(FAN_CLASS_NOTIF, O_RDONLY | O_CREAT), EINVAL);
+
+	fan_fd = TEST_SUCC(fanotify_init(FAN_CLASS_NOTIF, O_RDONLY));
+	TEST_ERRNO(fanotify_mark(fan_fd, 0, FAN_OPEN, AT_FDCWD, FILE_PATH),
+		   EINVAL);
+	TEST_ERRNO(fanotify_mark(fan_fd, FAN_MARK_ADD | FAN_MARK_REMOVE,
+				 FAN_OPEN, AT_FDCWD, FILE_PATH),
+		   EINVAL);
+	// Permission events are not allowed in the notification class.
+	TEST_ERRNO(fanotify_mark(fan_fd, FAN_MARK_ADD, FAN_OPEN_PERM, AT_FDCWD,
+				 FILE_PATH),
+		   EINVAL);
+	TEST_ERRNO(fanotify_mark(fan_fd, FAN_MARK_ADD | FAN_MARK_ONLYDIR,
+				 FAN_OPEN, AT_FDCWD, FILE_PATH),
+		   ENOTDIR);
+	TEST_ERRNO(fanotify_mark(fan_fd, FAN_MARK_REMOVE, FAN_OPEN, AT_FDCWD,
+				 FILE_PATH),
+		   ENOENT);
+	TEST_ERRNO(fanotify_mark(fan_fd, FAN_MARK_ADD, FAN_OPEN, AT_FDCWD,
+				 DIR_PATH "/none"),
+		   ENOENT);
+	TEST_SUCC(close(fan_fd));
+}
+END_TEST()
+
+FN_TEST(notification)
+{
+	struct fanotify_event_metadata *event;
+	int fan_fd, fd, event_fd;
+	char c;
+
+	fan_fd = TEST_SUCC(fanotify_init(FAN_CLASS_NOTIF | FAN_NONBLOCK,
+					 O_RDONLY));
+	TEST_SUCC(fanotify_mark(fan_fd, FAN_MARK_ADD,
+				FAN_OPEN | FAN_MODIFY | FAN_CLOSE_WRITE,
+				AT_FDCWD, FILE_PATH));
+	TEST_ERRNO(read(fan_fd, buf, sizeof(buf)), EAGAIN);
+
+	fd = TEST_SUCC(open(FILE_PATH, O_WRONLY));
+	TEST_RES(write(fd, "b", 1), _ret == 1);
+	TEST_SUCC(close(fd));
+
+	// The events on the same file are merged.
+	event = (struct fanotify_event_metadata *)buf;
+	TEST_RES(read(fan_fd, buf, sizeof(buf)),
+		 _ret == sizeof(*event) &&
+			 event->vers == FANOTIFY_METADATA_VERSION &&
+			 event->mask ==
+				 (FAN_OPEN | FAN_MODIFY | FAN_CLOSE_WRITE) &&
+			 event->pid == getpid() && is_file(event->fd));
+	TEST_SUCC(close(event->fd));
+	TEST_ERRNO(read(fan_fd, buf, sizeof(buf)), EAGAIN);
+
+	// Reading the reported file does not produce events.
+	TEST_SUCC(fanotify_mark(fan_fd, FAN_MARK_ADD, FAN_ACCESS, AT_FDCWD,
+				FILE_PATH));
+	fd = TEST_SUCC(open(FILE_PATH, O_RDONLY));
+	TEST_RES(read(fan_fd, buf, sizeof(buf)),
+		 _ret == sizeof(*event) && event->mask == FAN_OPEN);
+	event_fd = event->fd;
+	TEST_RES(read(event_fd, &c, 1), _ret == 1 && c == 'b');
+	TEST_ERRNO(read(fan_fd, buf, sizeof(buf)), EAGAIN);
+	TEST_SUCC(close(event_fd));
+	TEST_SUCC(close(fd));
+
+	TEST_SUCC(fanotify_mark(fan_fd, FAN_MARK_FLUSH, 0, AT_FDCWD, NULL));
+	fd = TEST_SUCC(open(FILE_PATH, O_RDONLY));
+	TEST_SUCC(close(fd));
+	TEST_ERRNO(read(fan_fd, buf, sizeof(buf)), EAGAIN);
+
+	TEST_SUCC(close(fan_fd));
+}
+END_TEST()
+
+FN_TEST(readv)
+{
+	struct fanotify_event_metadata *event;
+	struct iovec iov = { .iov_base = buf, .iov_len = sizeof(buf) };
+	int fan_fd, fd;
+
+	fan_fd = TEST_SUCC(fanotify_init(FAN_CLASS_NOTIF | FAN_NONBLOCK,
+					 O_RDONLY));
+	TEST_SUCC(fanotify_mark(fan_fd, FAN_MARK_ADD, FAN_OPEN, AT_FDCWD,
+				FILE_PATH));
+
+	fd = TEST_SUCC(open(FILE_PATH, O_RDONLY));
+	TEST_SUCC(close(fd));
+
+	// The reported files are also installed when reading with `readv`.
+	event = (struct fanotify_event_metadata *)buf;
+	TEST_RES(readv(fan_fd, &iov, 1), _ret == sizeof(*event) &&
+						 event->mask == FAN_OPEN &&
+						 is_file(event->fd));
+	TEST_SUCC(close(event->fd));
+	TEST_ERRNO(readv(fan_fd, &iov, 1), EAGAIN);
+
+	TEST_SUCC(close(fan_fd));
+}
+END_TEST()
+
+static int serve_requests(int fan_fd, pid_t pid, const uint64_t *masks,
+			  const uint32_t *responses, int nr_requests)
+{
+	struct fanotify_event_metadata *event;
+	struct fanotify_response response;
+	int nr_served = 0;
+	ssize_t len;
+
+	while (nr_served < nr_requests) {
+		len = read(fan_fd, buf, sizeof(buf));
+		if (len <= 0)
+			return -1;
+
+		for (event = (struct fanotify_event_metadata *)buf;
+		     FAN_EVENT_OK(event, len);
+		     event = FAN_EVENT_NEXT(event, len)) {
+			response.fd = event->fd;
+			// Other processes may access the marked mount.
+			response.response = FAN_ALLOW;
+			if (event->pid == pid && nr_served < nr_requests) {
+				if (event->mask != masks[nr_served] ||
+				    !is_file(event->fd))
+					return -1;
+				response.response = responses[nr_served++];
+			}
+
+			if (write(fan_fd, &response, sizeof(response)) !=
+			    sizeof(response))
+				return -1;
+			close(event->fd);
+		}
+	}
+
+	return 0;
+}
+
+FN_TEST(permission)
+{
+	const uint64_t masks[] = { FAN_OPEN_PERM, FAN_OPEN_PERM,
+				   FAN_ACCESS_PERM };
+	const uint32_t responses[] = { FAN_DENY, FAN_ALLOW, FAN_DENY };
+	struct fanotify_response response = { .fd = 100,
+					      .response = FAN_ALLOW };
+	int fan_fd, status;
+	pid_t pid;
+
+	fan_fd = TEST_SUCC(fanotify_init(FAN_CLASS_CONTENT, O_RDONLY));
+	TEST_ERRNO(write(fan_fd, &response, sizeof(response)), ENOENT);
+	response.response = 0;
+	TEST_ERRNO(write(fan_fd, &response, sizeof(response)), EINVAL);
+
+	TEST_SUCC(fanotify_mark(fan_fd, FAN_MARK_ADD | FAN_MARK_MOUNT,
+				FAN_OPEN_PERM, AT_FDCWD, DIR_PATH));
+	TEST_SUCC(fanotify_mark(fan_fd, FAN_MARK_ADD, FAN_ACCESS_PERM, AT_FDCWD,
+				FILE_PATH));
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		char c;
+		int fd;
+
+		if (open(FILE_PATH, O_RDONLY) >= 0 || errno != EPERM)
+			_exit(1);
+		fd = open(FILE_PATH, O_RDONLY);
+		if (fd < 0)
+			_exit(2);
+		if (read(fd, &c, 1) >= 0 || errno != EPERM)
+			_exit(3);
+		_exit(0);
+	}
+
+	TEST_SUCC(serve_requests(fan_fd, pid, masks, responses, 3));
+	// Closing the fanotify file permits the pending accesses.
+	TEST_SUCC(close(fan_fd));
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && WIFEXITED(status) && WEXITSTATUS(status) == 0);
+}
+END_TEST()
+
+FN_SETUP(cleanup)
+{
+	CHECK(unlink(FILE_PATH));
+	CHECK(rmdir(DIR_PATH));
+}
+END_SETUP()
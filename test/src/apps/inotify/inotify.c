@@ -0,0 +1,249 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#include "../test.h"
+
+#include <fcntl.h>
+#include <unistd.h>
+#include <sys/epoll.h>
+#include <sys/inotify.h>
+#include <sys/stat.h>
+
+#define DIR_PATH "/tmp/inotify_test"
+#define FILE_PATH DIR_PATH "/file"
+#define FILE2_PATH DIR_PATH "/file2"
+#define SUBDIR_PATH DIR_PATH "/subdir"
+
+#define DIR_EVENTS                                                         \
+	(IN_CREATE | IN_DELETE | IN_MODIFY | IN_MOVED_FROM | IN_MOVED_TO | \
+	 IN_CLOSE_WRITE)
+
+static int inotify_fd;
+static int dir_wd;
+
+static char buf[4096]
+	__attribute__((aligned(__alignof__(struct inotify_event))));
+static size_t buf_len;
+static size_t buf_off;
+
+static struct inotify_event *next_event(int fd)
+{
+	struct inotify_event *event;
+	ssize_t len;
+
+	if (buf_off >= buf_len) {
+		len = read(fd, buf, sizeof(buf));
+		if (len < 0)
+			return NULL;
+		buf_len = len;
+		buf_off = 0;
+	}
+
+	event = (struct inotify_event *)(buf + buf_off);
+	buf_off += sizeof(*event) + event->len;
+	return event;
+}
+
+#define IS_EVENT(event, wd_, mask_, name_)                   \
+	((event)->wd == (wd_) && (event)->mask == (mask_) && \
+	 strcmp((event)->len ? (event)->name : "", (name_)) == 0)
+
+FN_SETUP(init)
+{
+	CHECK(mkdir(DIR_PATH, 0755));
+
+	inotify_fd = CHECK(inotify_init1(IN_NONBLOCK | IN_CLOEXEC));
+	dir_wd = CHECK(inotify_add_watch(inotify_fd, DIR_PATH, DIR_EVENTS));
+}
+END_SETUP()
+
+FN_TEST(invalid_args)
+{
+	TEST_ERRNO(inotify_init1(IN_NONBLOCK << 1), EINVAL);
+	TEST_ERRNO(inotify_add_watch(inotify_fd, DIR_PATH, 0), EINVAL);
+	TEST_ERRNO(inotify_add_watch(inotify_fd, DIR_PATH "/none", IN_CREATE),
+		   ENOENT);
+	TEST_ERRNO(inotify_add_watch(inotify_fd, DIR_PATH,
+				     IN_CREATE | IN_MASK_CREATE),
+		   EEXIST);
+	TEST_RES(inotify_add_watch(inotify_fd, DIR_PATH, DIR_EVENTS),
+		 _ret == dir_wd);
+	TEST_ERRNO(inotify_rm_watch(inotify_fd, dir_wd + 100), EINVAL);
+	TEST_ERRNO(next_event(inotify_fd), EAGAIN);
+}
+END_TEST()
+
+FN_TEST(create_modify_close)
+{
+	int fd;
+
+	fd = TEST_SUCC(open(FILE_PATH, O_WRONLY | O_CREAT, 0644));
+	TEST_RES(write(fd, "a", 1), _ret == 1);
+	TEST_RES(write(fd, "b", 1), _ret == 1);
+	TEST_SUCC(close(fd));
+	TEST_SUCC(mkdir(SUBDIR_PATH, 0755));
+
+	TEST_RES(next_event(inotify_fd),
+		 IS_EVENT(_ret, dir_wd, IN_CREATE, "file"));
+	// The same consecutive events are merged.
+	TEST_RES(next_event(inotify_fd),
+		 IS_EVENT(_ret, dir_wd, IN_MODIFY, "file"));
+	TEST_RES(next_event(inotify_fd),
+		 IS_EVENT(_ret, dir_wd, IN_CLOSE_WRITE, "file"));
+	TEST_RES(next_event(inotify_fd),
+		 IS_EVENT(_ret, dir_wd, IN_CREATE | IN_ISDIR, "subdir"));
+	TEST_ERRNO(next_event(inotify_fd), EAGAIN);
+}
+END_TEST()
+
+FN_TEST(move_and_delete)
+{
+	struct inotify_event *event;
+	uint32_t cookie;
+
+	TEST_SUCC(rename(FILE_PATH, FILE2_PATH));
+	event = TEST_RES(next_event(inotify_fd),
+			 IS_EVENT(_ret, dir_wd, IN_MOVED_FROM, "file") &&
+				 _ret->cookie != 0);
+	cookie = event->cookie;
+	TEST_RES(next_event(inotify_fd),
+		 IS_EVENT(_ret, dir_wd, IN_MOVED_TO, "file2") &&
+			 _ret->cookie == cookie);
+
+	TEST_SUCC(unlink(FILE2_PATH));
+	TEST_SUCC(rmdir(SUBDIR_PATH));
+	TEST_RES(next_event(inotify_fd),
+		 IS_EVENT(_ret, dir_wd, IN_DELETE, "file2"));
+	TEST_RES(next_event(inotify_fd),
+		 IS_EVENT(_ret, dir_wd, IN_DELETE | IN_ISDIR, "subdir"));
+	TEST_ERRNO(next_event(inotify_fd), EAGAIN);
+}
+END_TEST()
+
+FN_TEST(oneshot_and_delete_self)
+{
+	int inotify_fd2;
+	int fd, wd;
+
+	inotify_fd2 = TEST_SUCC(inotify_init1(IN_NONBLOCK));
+	fd = TEST_SUCC(open(FILE_PATH, O_WRONLY | O_CREAT, 0644));
+
+	wd = TEST_SUCC(inotify_add_watch(inotify_fd2, FILE_PATH,
+					 IN_MODIFY | IN_ONESHOT));
+	TEST_RES(write(fd, "a", 1), _ret == 1);
+	TEST_RES(write(fd, "b", 1), _ret == 1);
+	TEST_RES(next_event(inotify_fd2), IS_EVENT(_ret, wd, IN_MODIFY, ""));
+	TEST_RES(next_event(inotify_fd2), IS_EVENT(_ret, wd, IN_IGNORED, ""));
+	TEST_ERRNO(next_event(inotify_fd2), EAGAIN);
+	TEST_ERRNO(inotify_rm_watch(inotify_fd2, wd), EINVAL);
+	TEST_SUCC(close(fd));
+
+	wd = TEST_SUCC(
+		inotify_add_watch(inotify_fd2, FILE_PATH, IN_DELETE_SELF));
+	TEST_SUCC(unlink(FILE_PATH));
+	TEST_RES(next_event(inotify_fd2),
+		 IS_EVENT(_ret, wd, IN_DELETE_SELF, ""));
+	TEST_RES(next_event(inotify_fd2), IS_EVENT(_ret, wd, IN_IGNORED, ""));
+	TEST_ERRNO(next_event(inotify_fd2), EAGAIN);
+
+	TEST_SUCC(close(inotify_fd2));
+
+	// Drain the events of the watched directory.
+	while (next_event(inotify_fd) != NULL)
+		;
+}
+END_TEST()
+
+FN_TEST(move_self_and_overwrite)
+{
+	int inotify_fd2;
+	int wd, wd2;
+
+	inotify_fd2 = TEST_SUCC(inotify_init1(IN_NONBLOCK));
+	TEST_SUCC(close(TEST_SUCC(open(FILE_PATH, O_WRONLY | O_CREAT, 0644))));
+	TEST_SUCC(close(TEST_SUCC(open(FILE2_PATH, O_WRONLY | O_CREAT, 0644))));
+
+	wd = TEST_SUCC(inotify_add_watch(inotify_fd2, FILE_PATH, IN_MOVE_SELF));
+	wd2 = TEST_SUCC(
+		inotify_add_watch(inotify_fd2, FILE2_PATH, IN_DELETE_SELF));
+
+	// The overwritten file is deleted.
+	TEST_SUCC(rename(FILE_PATH, FILE2_PATH));
+	TEST_RES(next_event(inotify_fd2), IS_EVENT(_ret, wd, IN_MOVE_SELF, ""));
+	TEST_RES(next_event(inotify_fd2),
+		 IS_EVENT(_ret, wd2, IN_DELETE_SELF, ""));
+	TEST_RES(next_event(inotify_fd2), IS_EVENT(_ret, wd2, IN_IGNORED, ""));
+	TEST_ERRNO(next_event(inotify_fd2), EAGAIN);
+
+	TEST_SUCC(unlink(FILE2_PATH));
+	TEST_SUCC(close(inotify_fd2));
+
+	// The moved directory is reported as a directory.
+	TEST_SUCC(mkdir(SUBDIR_PATH, 0755));
+	while (next_event(inotify_fd) != NULL)
+		;
+	TEST_SUCC(rename(SUBDIR_PATH, DIR_PATH "/subdir2"));
+	TEST_RES(next_event(inotify_fd),
+		 IS_EVENT(_ret, dir_wd, IN_MOVED_FROM | IN_ISDIR, "subdir"));
+	TEST_RES(next_event(inotify_fd),
+		 IS_EVENT(_ret, dir_wd, IN_MOVED_TO | IN_ISDIR, "subdir2"));
+	TEST_SUCC(rmdir(DIR_PATH "/subdir2"));
+
+	// Drain the events of the watched directory.
+	while (next_event(inotify_fd) != NULL)
+		;
+}
+END_TEST()
+
+FN_TEST(small_buffer)
+{
+	char small_buf[sizeof(struct inotify_event)];
+
+	TEST_SUCC(mkdir(SUBDIR_PATH, 0755));
+	TEST_ERRNO(read(inotify_fd, small_buf, sizeof(small_buf)), EINVAL);
+	TEST_RES(next_event(inotify_fd),
+		 IS_EVENT(_ret, dir_wd, IN_CREATE | IN_ISDIR, "subdir") &&
+			 (_ret->len & (sizeof(struct inotify_event) - 1)) == 0);
+	TEST_SUCC(rmdir(SUBDIR_PATH));
+	TEST_RES(next_event(inotify_fd),
+		 IS_EVENT(_ret, dir_wd, IN_DELETE | IN_ISDIR, "subdir"));
+}
+END_TEST()
+
+FN_TEST(epoll)
+{
+	struct epoll_event event = { .events = EPOLLIN };
+	int epfd;
+
+	epfd = TEST_SUCC(epoll_create1(0));
+	TEST_SUCC(epoll_ctl(epfd, EPOLL_CTL_ADD, inotify_fd, &event));
+
+	TEST_RES(epoll_wait(epfd, &event, 1, 0), _ret == 0);
+	TEST_SUCC(mkdir(SUBDIR_PATH, 0755));
+	TEST_RES(epoll_wait(epfd, &event, 1, 0),
+		 _ret == 1 && event.events == EPOLLIN);
+	TEST_RES(next_event(inotify_fd),
+		 IS_EVENT(_ret, dir_wd, IN_CREATE | IN_ISDIR, "subdir"));
+	TEST_RES(epoll_wait(epfd, &event, 1, 0), _ret == 0);
+
+	TEST_SUCC(rmdir(SUBDIR_PATH));
+	TEST_RES(next_event(inotify_fd),
+		 IS_EVENT(_ret, dir_wd, IN_DELETE | IN_ISDIR, "subdir"));
+	TEST_SUCC(close(epfd));
+}
+END_TEST()
+
+FN_TEST(rm_watch)
+{
+	TEST_SUCC(inotify_rm_watch(inotify_fd, dir_wd));
+	TEST_RES(next_event(inotify_fd),
+		 IS_EVENT(_ret, dir_wd, IN_IGNORED, ""));
+	TEST_ERRNO(inotify_rm_watch(inotify_fd, dir_wd), EINVAL);
+
+	TEST_SUCC(mkdir(SUBDIR_PATH, 0755));
+	TEST_ERRNO(next_event(inotify_fd), EAGAIN);
+	TEST_SUCC(rmdir(SUBDIR_PATH));
+
+	TEST_SUCC(close(inotify_fd));
+	TEST_SUCC(rmdir(DIR_PATH));
+}
+END_TEST()
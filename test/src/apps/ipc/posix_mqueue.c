@@ -0,0 +1,263 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#include "../test.h"
+
+#include <fcntl.h>
+#include <mqueue.h>
+#include <signal.h>
+#include <time.h>
+#include <unistd.h>
+#include <sys/epoll.h>
+#include <sys/wait.h>
+
+#define QUEUE_NAME "/asterinas_test_mqueue"
+#define RACE_QUEUE_NAME "/asterinas_test_mqueue_race"
+#define NR_RACERS 8
+#define MAX_MSGS 4
+#define MSG_SIZE 16
+
+static mqd_t mqd;
+
+static volatile int sig_code;
+static volatile int sig_value;
+
+static void signal_handler(int signum, siginfo_t *info, void *context)
+{
+	sig_code = info->si_code;
+	sig_value = info->si_value.sival_int;
+}
+
+static int thread_pipe[2];
+
+static void thread_function(union sigval value)
+{
+	char byte = value.sival_int;
+
+	write(thread_pipe[1], &byte, 1);
+}
+
+FN_TEST(mq_open)
+{
+	struct mq_attr attr = { .mq_maxmsg = MAX_MSGS, .mq_msgsize = MSG_SIZE };
+	struct mq_attr bad_attr = { .mq_maxmsg = 0, .mq_msgsize = MSG_SIZE };
+
+	TEST_ERRNO(mq_open(QUEUE_NAME, O_RDWR), ENOENT);
+	TEST_ERRNO(mq_open(QUEUE_NAME, O_RDWR | O_CREAT, 0600, &bad_attr),
+		   EINVAL);
+
+	mqd = TEST_SUCC(mq_open(QUEUE_NAME, O_RDWR | O_CREAT, 0600, &attr));
+	TEST_ERRNO(mq_open(QUEUE_NAME, O_RDWR | O_CREAT | O_EXCL, 0600, &attr),
+		   EEXIST);
+	TEST_RES(mq_getattr(mqd, &attr),
+		 attr.mq_flags == 0 && attr.mq_maxmsg == MAX_MSGS &&
+			 attr.mq_msgsize == MSG_SIZE && attr.mq_curmsgs == 0);
+}
+END_TEST()
+
+FN_TEST(exclusive_create_race)
+{
+	struct mq_attr attr = { .mq_maxmsg = MAX_MSGS, .mq_msgsize = MSG_SIZE };
+	int i, status, nr_created = 0, nr_existing = 0;
+	pid_t pid;
+
+	for (i = 0; i < NR_RACERS; i++) {
+		pid = TEST_SUCC(fork());
+		if (pid == 0) {
+			if (mq_open(RACE_QUEUE_NAME, O_RDWR | O_CREAT | O_EXCL,
+				    0600, &attr) >= 0)
+				exit(EXIT_SUCCESS);
+			exit(errno == EEXIST ? 1 : 2);
+		}
+	}
+
+	// Only one of the concurrent callers can create the queue.
+	for (i = 0; i < NR_RACERS; i++) {
+		TEST_RES(wait(&status), WIFEXITED(status));
+		if (WEXITSTATUS(status) == 0)
+			nr_created++;
+		else if (WEXITSTATUS(status) == 1)
+			nr_existing++;
+	}
+	TEST_RES(nr_created, _ret == 1 && nr_existing == NR_RACERS - 1);
+
+	TEST_SUCC(mq_unlink(RACE_QUEUE_NAME));
+}
+END_TEST()
+
+FN_TEST(priority_order)
+{
+	char buf[MSG_SIZE];
+	unsigned int prio;
+
+	TEST_SUCC(mq_send(mqd, "a", 2, 1));
+	TEST_SUCC(mq_send(mqd, "b", 2, 5));
+	TEST_SUCC(mq_send(mqd, "c", 2, 1));
+	TEST_SUCC(mq_send(mqd, "d", 2, 3));
+
+	TEST_RES(mq_receive(mqd, buf, MSG_SIZE, &prio),
+		 _ret == 2 && prio == 5 && strcmp(buf, "b") == 0);
+	TEST_RES(mq_receive(mqd, buf, MSG_SIZE, &prio),
+		 _ret == 2 && prio == 3 && strcmp(buf, "d") == 0);
+	TEST_RES(mq_receive(mqd, buf, MSG_SIZE, &prio),
+		 _ret == 2 && prio == 1 && strcmp(buf, "a") == 0);
+	TEST_RES(mq_receive(mqd, buf, MSG_SIZE, &prio),
+		 _ret == 2 && prio == 1 && strcmp(buf, "c") == 0);
+}
+END_TEST()
+
+FN_TEST(invalid_sizes)
+{
+	char buf[MSG_SIZE + 1] = { 0 };
+
+	TEST_ERRNO(mq_send(mqd, buf, MSG_SIZE + 1, 0), EMSGSIZE);
+	TEST_ERRNO(mq_receive(mqd, buf, MSG_SIZE - 1, NULL), EMSGSIZE);
+	TEST_ERRNO(mq_send(mqd, buf, 1, 32768), EINVAL);
+}
+END_TEST()
+
+FN_TEST(nonblocking_and_timeout)
+{
+	struct mq_attr attr = { .mq_flags = O_NONBLOCK };
+	struct timespec timeout = { 0 };
+	char buf[MSG_SIZE];
+	int i;
+
+	TEST_ERRNO(mq_timedreceive(mqd, buf, MSG_SIZE, NULL, &timeout),
+		   ETIMEDOUT);
+
+	TEST_SUCC(mq_setattr(mqd, &attr, NULL));
+	TEST_ERRNO(mq_receive(mqd, buf, MSG_SIZE, NULL), EAGAIN);
+	for (i = 0; i < MAX_MSGS; i++)
+		TEST_SUCC(mq_send(mqd, "x", 1, 0));
+	TEST_ERRNO(mq_send(mqd, "x", 1, 0), EAGAIN);
+	TEST_RES(mq_getattr(mqd, &attr),
+		 attr.mq_flags == O_NONBLOCK && attr.mq_curmsgs == MAX_MSGS);
+
+	attr.mq_flags = 0;
+	TEST_SUCC(mq_setattr(mqd, &attr, NULL));
+	TEST_ERRNO(mq_timedsend(mqd, "x", 1, 0, &timeout), ETIMEDOUT);
+	for (i = 0; i < MAX_MSGS; i++)
+		TEST_RES(mq_receive(mqd, buf, MSG_SIZE, NULL), _ret == 1);
+}
+END_TEST()
+
+FN_TEST(epoll)
+{
+	struct epoll_event event = { .events = EPOLLIN | EPOLLOUT };
+	char buf[MSG_SIZE];
+	int epfd;
+
+	epfd = TEST_SUCC(epoll_create1(0));
+	TEST_SUCC(epoll_ctl(epfd, EPOLL_CTL_ADD, mqd, &event));
+
+	TEST_RES(epoll_wait(epfd, &event, 1, 0),
+		 _ret == 1 && event.events == EPOLLOUT);
+	TEST_SUCC(mq_send(mqd, "x", 1, 0));
+	TEST_RES(epoll_wait(epfd, &event, 1, 0),
+		 _ret == 1 && event.events == (EPOLLIN | EPOLLOUT));
+	TEST_RES(mq_receive(mqd, buf, MSG_SIZE, NULL), _ret == 1);
+	TEST_RES(epoll_wait(epfd, &event, 1, 0),
+		 _ret == 1 && event.events == EPOLLOUT);
+
+	TEST_SUCC(close(epfd));
+}
+END_TEST()
+
+FN_TEST(notify_signal)
+{
+	struct sigaction sa = { .sa_sigaction = signal_handler,
+				.sa_flags = SA_SIGINFO };
+	struct sigevent sev = { .sigev_notify = SIGEV_SIGNAL,
+				.sigev_signo = SIGUSR1,
+				.sigev_value.sival_int = 42 };
+	char buf[MSG_SIZE];
+
+	TEST_SUCC(sigaction(SIGUSR1, &sa, NULL));
+
+	TEST_SUCC(mq_notify(mqd, &sev));
+	TEST_ERRNO(mq_notify(mqd, &sev), EBUSY);
+
+	TEST_SUCC(mq_send(mqd, "x", 1, 0));
+	TEST_RES(sig_code, _ret == SI_MESGQ && sig_value == 42);
+	TEST_RES(mq_receive(mqd, buf, MSG_SIZE, NULL), _ret == 1);
+
+	// The registration is removed after the notification.
+	sig_code = 0;
+	TEST_SUCC(mq_send(mqd, "x", 1, 0));
+	TEST_RES(sig_code, _ret == 0);
+	TEST_RES(mq_receive(mqd, buf, MSG_SIZE, NULL), _ret == 1);
+
+	// The registration can be removed explicitly.
+	TEST_SUCC(mq_notify(mqd, &sev));
+	TEST_SUCC(mq_notify(mqd, NULL));
+	TEST_SUCC(mq_send(mqd, "x", 1, 0));
+	TEST_RES(sig_code, _ret == 0);
+	TEST_RES(mq_receive(mqd, buf, MSG_SIZE, NULL), _ret == 1);
+}
+END_TEST()
+
+FN_TEST(notify_close)
+{
+	struct sigevent sev = { .sigev_notify = SIGEV_SIGNAL,
+				.sigev_signo = SIGUSR1,
+				.sigev_value.sival_int = 42 };
+	char buf[MSG_SIZE];
+	int fd;
+
+	// The registration is removed when the registering descriptor is closed.
+	fd = TEST_SUCC(dup(mqd));
+	TEST_SUCC(mq_notify(fd, &sev));
+	TEST_ERRNO(mq_notify(mqd, &sev), EBUSY);
+	TEST_SUCC(close(fd));
+
+	sig_code = 0;
+	TEST_SUCC(mq_send(mqd, "x", 1, 0));
+	TEST_RES(sig_code, _ret == 0);
+	TEST_RES(mq_receive(mqd, buf, MSG_SIZE, NULL), _ret == 1);
+
+	TEST_SUCC(mq_notify(mqd, &sev));
+	TEST_SUCC(mq_notify(mqd, NULL));
+}
+END_TEST()
+
+FN_TEST(notify_thread)
+{
+	struct sigevent sev = { .sigev_notify = SIGEV_THREAD,
+				.sigev_notify_function = thread_function,
+				.sigev_value.sival_int = 'z' };
+	char buf[MSG_SIZE];
+	char byte;
+
+	TEST_SUCC(pipe(thread_pipe));
+
+	TEST_SUCC(mq_notify(mqd, &sev));
+	TEST_ERRNO(mq_notify(mqd, &sev), EBUSY);
+
+	// The C library starts a thread to run the function when a message arrives.
+	TEST_SUCC(mq_send(mqd, "x", 1, 0));
+	TEST_RES(read(thread_pipe[0], &byte, 1), _ret == 1 && byte == 'z');
+	TEST_RES(mq_receive(mqd, buf, MSG_SIZE, NULL), _ret == 1);
+
+	// The registration is removed after the notification.
+	TEST_SUCC(mq_notify(mqd, &sev));
+	TEST_SUCC(mq_notify(mqd, NULL));
+
+	TEST_SUCC(close(thread_pipe[0]));
+	TEST_SUCC(close(thread_pipe[1]));
+}
+END_TEST()
+
+FN_TEST(mq_unlink)
+{
+	char buf[MSG_SIZE];
+
+	TEST_SUCC(mq_unlink(QUEUE_NAME));
+	TEST_ERRNO(mq_unlink(QUEUE_NAME), ENOENT);
+	TEST_ERRNO(mq_open(QUEUE_NAME, O_RDWR), ENOENT);
+
+	// The queue can still be used until it is closed.
+	TEST_SUCC(mq_send(mqd, "x", 1, 0));
+	TEST_RES(mq_receive(mqd, buf, MSG_SIZE, NULL), _ret == 1);
+	TEST_SUCC(mq_close(mqd));
+}
+END_TEST()
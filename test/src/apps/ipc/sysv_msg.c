@@ -0,0 +1,144 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include "../test.h"
+
+#include <signal.h>
+#include <unistd.h>
+#include <sys/ipc.h>
+#include <sys/msg.h>
+#include <sys/wait.h>
+
+#define MSG_KEY 0x4d53
+#define TEXT_SIZE 16
+
+struct message {
+	long mtype;
+	char mtext[TEXT_SIZE];
+};
+
+static int msg_id;
+
+static int send_message(long mtype, const char *text)
+{
+	struct message msg = { .mtype = mtype };
+
+	strcpy(msg.mtext, text);
+	return msgsnd(msg_id, &msg, TEXT_SIZE, IPC_NOWAIT);
+}
+
+FN_TEST(msgget)
+{
+	msg_id = TEST_SUCC(msgget(MSG_KEY, IPC_CREAT | 0600));
+	TEST_RES(msgget(MSG_KEY, 0600), _ret == msg_id);
+	TEST_ERRNO(msgget(MSG_KEY, IPC_CREAT | IPC_EXCL | 0600), EEXIST);
+	TEST_ERRNO(msgget(MSG_KEY + 1, 0600), ENOENT);
+
+	// Keys are not IDs, so keys larger than `MSGMNI` can be used.
+	TEST_RES(msgget(0x7fffffff, IPC_CREAT | 0600),
+		 _ret > 0 && _ret != msg_id &&
+			 msgctl(_ret, IPC_RMID, NULL) == 0);
+}
+END_TEST()
+
+FN_TEST(private_id_is_not_key)
+{
+	int private_id;
+
+	// The ID of a private queue cannot be used as its key.
+	private_id = TEST_SUCC(msgget(IPC_PRIVATE, 0600));
+	TEST_ERRNO(msgget(private_id, 0600), ENOENT);
+	TEST_RES(msgget(private_id, IPC_CREAT | 0600),
+		 _ret > 0 && _ret != private_id &&
+			 msgctl(_ret, IPC_RMID, NULL) == 0);
+	TEST_SUCC(msgctl(private_id, IPC_RMID, NULL));
+}
+END_TEST()
+
+FN_TEST(receive_by_type)
+{
+	struct message msg;
+
+	TEST_SUCC(send_message(3, "three"));
+	TEST_SUCC(send_message(1, "one"));
+	TEST_SUCC(send_message(2, "two"));
+
+	TEST_RES(msgrcv(msg_id, &msg, TEXT_SIZE, 2, 0),
+		 _ret == TEXT_SIZE && msg.mtype == 2 &&
+			 strcmp(msg.mtext, "two") == 0);
+	TEST_RES(msgrcv(msg_id, &msg, TEXT_SIZE, -3, 0),
+		 msg.mtype == 1 && strcmp(msg.mtext, "one") == 0);
+	TEST_RES(msgrcv(msg_id, &msg, TEXT_SIZE, 1, MSG_EXCEPT),
+		 msg.mtype == 3 && strcmp(msg.mtext, "three") == 0);
+	TEST_ERRNO(msgrcv(msg_id, &msg, TEXT_SIZE, 0, IPC_NOWAIT), ENOMSG);
+}
+END_TEST()
+
+FN_TEST(too_long)
+{
+	struct message msg;
+
+	TEST_SUCC(send_message(1, "truncated"));
+	TEST_ERRNO(msgrcv(msg_id, &msg, 4, 0, IPC_NOWAIT), E2BIG);
+	TEST_RES(msgrcv(msg_id, &msg, 4, 0, IPC_NOWAIT | MSG_NOERROR),
+		 _ret == 4 && memcmp(msg.mtext, "trun", 4) == 0);
+	TEST_ERRNO(msgrcv(msg_id, &msg, TEXT_SIZE, 0, IPC_NOWAIT), ENOMSG);
+}
+END_TEST()
+
+FN_TEST(blocking_receive)
+{
+	struct message msg;
+	int status;
+	pid_t pid;
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		usleep(100 * 1000);
+		exit(send_message(5, "five") < 0 ? EXIT_FAILURE : EXIT_SUCCESS);
+	}
+
+	TEST_RES(msgrcv(msg_id, &msg, TEXT_SIZE, 5, 0),
+		 msg.mtype == 5 && strcmp(msg.mtext, "five") == 0);
+	TEST_RES(wait4(pid, &status, 0, NULL),
+		 _ret == pid && WIFEXITED(status) && WEXITSTATUS(status) == 0);
+}
+END_TEST()
+
+static void signal_handler(int signum)
+{
+}
+
+FN_TEST(interrupted_by_signal)
+{
+	struct sigaction action = { .sa_handler = signal_handler };
+	struct message msg;
+
+	TEST_SUCC(sigaction(SIGALRM, &action, NULL));
+	TEST_SUCC(alarm(1));
+	TEST_ERRNO(msgrcv(msg_id, &msg, TEXT_SIZE, 0, 0), EINTR);
+}
+END_TEST()
+
+FN_TEST(msgctl)
+{
+	struct msqid_ds ds;
+
+	TEST_SUCC(send_message(1, "one"));
+	TEST_RES(msgctl(msg_id, IPC_STAT, &ds),
+		 ds.msg_qnum == 1 && ds.msg_cbytes == TEXT_SIZE &&
+			 ds.msg_lspid == getpid() &&
+			 ds.msg_perm.__key == MSG_KEY &&
+			 (ds.msg_perm.mode & 0777) == 0600);
+
+	ds.msg_perm.mode = 0640;
+	TEST_SUCC(msgctl(msg_id, IPC_SET, &ds));
+	TEST_RES(msgctl(msg_id, IPC_STAT, &ds),
+		 (ds.msg_perm.mode & 0777) == 0640);
+
+	TEST_SUCC(msgctl(msg_id, IPC_RMID, NULL));
+	TEST_ERRNO(msgctl(msg_id, IPC_STAT, &ds), EINVAL);
+	TEST_ERRNO(send_message(1, "one"), EINVAL);
+}
+END_TEST()
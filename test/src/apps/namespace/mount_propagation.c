@@ -0,0 +1,194 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <fcntl.h>
+#include <sched.h>
+#include <unistd.h>
+#include <sys/mount.h>
+#include <sys/stat.h>
+#include <sys/wait.h>
+
+#include "../test.h"
+
+#define BASE "/tmp/mount_propagation_test"
+#define DIR_A BASE "/a"
+#define DIR_B BASE "/b"
+
+static int status;
+
+// Enters a new mount namespace and prepares two empty directories, `DIR_A`
+// and `DIR_B`, whose mounts are not shared with the outside.
+static void enter_mount_ns(void)
+{
+	CHECK(unshare(CLONE_NEWNS));
+	CHECK(mount(NULL, "/", NULL, MS_REC | MS_PRIVATE, NULL));
+
+	CHECK(mount("ramfs", BASE, "ramfs", 0, NULL));
+	CHECK(mkdir(DIR_A, 0755));
+	CHECK(mkdir(DIR_B, 0755));
+	CHECK(mount("ramfs", DIR_A, "ramfs", 0, NULL));
+	CHECK(mkdir(DIR_A "/x", 0755));
+	CHECK(mkdir(DIR_A "/y", 0755));
+}
+
+static void create_file(const char *path)
+{
+	int fd;
+
+	fd = CHECK(open(path, O_CREAT | O_WRONLY, 0644));
+	CHECK(close(fd));
+}
+
+static int mountinfo_contains(const char *tag)
+{
+	char buf[4096];
+	ssize_t len;
+	int fd;
+
+	fd = open("/proc/self/mountinfo", O_RDONLY);
+	if (fd < 0)
+		return 0;
+	len = read(fd, buf, sizeof(buf) - 1);
+	close(fd);
+	if (len < 0)
+		return 0;
+	buf[len] = '\0';
+	return strstr(buf, tag) != NULL;
+}
+
+FN_SETUP(base)
+{
+	CHECK(mkdir(BASE, 0755));
+}
+END_SETUP()
+
+FN_TEST(shared)
+{
+	pid_t pid;
+
+	pid = CHECK(fork());
+	if (pid == 0) {
+		enter_mount_ns();
+		CHECK(mount(NULL, DIR_A, NULL, MS_SHARED, NULL));
+		CHECK(mount(DIR_A, DIR_B, NULL, MS_BIND, NULL));
+
+		// Mounts propagate between peers in both directions.
+		CHECK(mount("ramfs", DIR_A "/x", "ramfs", 0, NULL));
+		create_file(DIR_A "/x/file");
+		CHECK(access(DIR_B "/x/file", F_OK));
+
+		CHECK(mount("ramfs", DIR_B "/y", "ramfs", 0, NULL));
+		create_file(DIR_B "/y/file");
+		CHECK(access(DIR_A "/y/file", F_OK));
+
+		CHECK_WITH(mountinfo_contains(" shared:"), _ret);
+
+		// So do unmounts.
+		CHECK(umount(DIR_B "/x"));
+		CHECK_WITH(access(DIR_A "/x/file", F_OK),
+			   _ret < 0 && errno == ENOENT);
+		_exit(0);
+	}
+
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && WIFEXITED(status) && WEXITSTATUS(status) == 0);
+}
+END_TEST()
+
+FN_TEST(slave)
+{
+	pid_t pid;
+
+	pid = CHECK(fork());
+	if (pid == 0) {
+		enter_mount_ns();
+		CHECK(mount(NULL, DIR_A, NULL, MS_SHARED, NULL));
+		CHECK(mount(DIR_A, DIR_B, NULL, MS_BIND, NULL));
+		CHECK(mount(NULL, DIR_B, NULL, MS_SLAVE, NULL));
+
+		// Mounts propagate from the master to the slave only.
+		CHECK(mount("ramfs", DIR_A "/x", "ramfs", 0, NULL));
+		create_file(DIR_A "/x/file");
+		CHECK(access(DIR_B "/x/file", F_OK));
+
+		CHECK(mount("ramfs", DIR_B "/y", "ramfs", 0, NULL));
+		create_file(DIR_B "/y/file");
+		CHECK_WITH(access(DIR_A "/y/file", F_OK),
+			   _ret < 0 && errno == ENOENT);
+
+		CHECK_WITH(mountinfo_contains(" master:"), _ret);
+		_exit(0);
+	}
+
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && WIFEXITED(status) && WEXITSTATUS(status) == 0);
+}
+END_TEST()
+
+FN_TEST(private)
+{
+	pid_t pid;
+
+	pid = CHECK(fork());
+	if (pid == 0) {
+		enter_mount_ns();
+		CHECK(mount(NULL, DIR_A, NULL, MS_SHARED, NULL));
+		CHECK(mount(DIR_A, DIR_B, NULL, MS_BIND, NULL));
+		CHECK(mount(NULL, DIR_B, NULL, MS_PRIVATE, NULL));
+
+		CHECK(mount("ramfs", DIR_A "/x", "ramfs", 0, NULL));
+		create_file(DIR_A "/x/file");
+		CHECK_WITH(access(DIR_B "/x/file", F_OK),
+			   _ret < 0 && errno == ENOENT);
+		_exit(0);
+	}
+
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && WIFEXITED(status) && WEXITSTATUS(status) == 0);
+}
+END_TEST()
+
+FN_TEST(unbindable)
+{
+	pid_t pid;
+
+	pid = CHECK(fork());
+	if (pid == 0) {
+		enter_mount_ns();
+		CHECK(mount(NULL, DIR_A, NULL, MS_UNBINDABLE, NULL));
+		CHECK_WITH(mount(DIR_A, DIR_B, NULL, MS_BIND, NULL),
+			   _ret < 0 && errno == EINVAL);
+		CHECK_WITH(mountinfo_contains(" unbindable"), _ret);
+		_exit(0);
+	}
+
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && WIFEXITED(status) && WEXITSTATUS(status) == 0);
+}
+END_TEST()
+
+FN_TEST(invalid_type)
+{
+	pid_t pid;
+
+	pid = CHECK(fork());
+	if (pid == 0) {
+		enter_mount_ns();
+		CHECK_WITH(mount(NULL, DIR_A, NULL, MS_SHARED | MS_SLAVE, NULL),
+			   _ret < 0 && errno == EINVAL);
+		CHECK_WITH(mount(NULL, DIR_A "/x", NULL, MS_SHARED, NULL),
+			   _ret < 0 && errno == EINVAL);
+		_exit(0);
+	}
+
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && WIFEXITED(status) && WEXITSTATUS(status) == 0);
+}
+END_TEST()
+
+FN_SETUP(cleanup)
+{
+	CHECK(rmdir(BASE));
+}
+END_SETUP()
@@ -0,0 +1,187 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <fcntl.h>
+#include <sched.h>
+#include <signal.h>
+#include <unistd.h>
+#include <sys/utsname.h>
+#include <sys/wait.h>
+
+#include "../test.h"
+
+static int status;
+
+#define IS_EXITED_WITH(code) \
+	(WIFEXITED(status) && WEXITSTATUS(status) == (code))
+
+static int has_hostname(const char *name)
+{
+	struct utsname uts;
+
+	if (uname(&uts) < 0)
+		return 0;
+	return strcmp(uts.nodename, name) == 0;
+}
+
+FN_TEST(uts)
+{
+	char long_name[65];
+	pid_t pid;
+
+	memset(long_name, 'a', sizeof(long_name));
+	TEST_ERRNO(sethostname(long_name, sizeof(long_name)), EINVAL);
+
+	pid = CHECK(fork());
+	if (pid == 0) {
+		CHECK(unshare(CLONE_NEWUTS));
+		CHECK(sethostname("ns-test", 7));
+		CHECK(setdomainname("ns-domain", 9));
+		_exit(has_hostname("ns-test") ? 0 : 1);
+	}
+
+	TEST_RES(waitpid(pid, &status, 0), _ret == pid && IS_EXITED_WITH(0));
+	TEST_RES(has_hostname("ns-test"), _ret == 0);
+}
+END_TEST()
+
+FN_TEST(setns)
+{
+	int pipefd[2];
+	char path[64];
+	char link[64];
+	char byte;
+	pid_t holder, pid;
+	int fd;
+
+	CHECK(pipe(pipefd));
+
+	holder = CHECK(fork());
+	if (holder == 0) {
+		CHECK(unshare(CLONE_NEWUTS));
+		CHECK(sethostname("ns-holder", 9));
+		CHECK(write(pipefd[1], "x", 1));
+		pause();
+		_exit(0);
+	}
+	CHECK(read(pipefd[0], &byte, 1));
+
+	snprintf(path, sizeof(path), "/proc/%d/ns/uts", holder);
+	TEST_RES(readlink(path, link, sizeof(link)),
+		 _ret > 6 && strncmp(link, "uts:[", 5) == 0);
+
+	fd = TEST_SUCC(open(path, O_RDONLY));
+	TEST_ERRNO(setns(fd, CLONE_NEWNET), EINVAL);
+
+	pid = CHECK(fork());
+	if (pid == 0) {
+		CHECK(setns(fd, CLONE_NEWUTS));
+		_exit(has_hostname("ns-holder") ? 0 : 1);
+	}
+	TEST_RES(waitpid(pid, &status, 0), _ret == pid && IS_EXITED_WITH(0));
+
+	TEST_SUCC(close(fd));
+	TEST_SUCC(kill(holder, SIGKILL));
+	TEST_RES(waitpid(holder, &status, 0), _ret == holder);
+	TEST_SUCC(close(pipefd[0]));
+	TEST_SUCC(close(pipefd[1]));
+}
+END_TEST()
+
+FN_TEST(pid)
+{
+	pid_t pid, child;
+
+	pid = CHECK(fork());
+	if (pid == 0) {
+		CHECK(unshare(CLONE_NEWPID));
+		if (unshare(CLONE_NEWPID) == 0 || errno != EINVAL)
+			_exit(1);
+
+		child = CHECK(fork());
+		if (child == 0)
+			_exit(getpid() == 1 && getppid() == 0 ? 0 : 1);
+
+		CHECK_WITH(waitpid(child, &status, 0), _ret == child);
+		_exit(IS_EXITED_WITH(0) ? 0 : 1);
+	}
+
+	TEST_RES(waitpid(pid, &status, 0), _ret == pid && IS_EXITED_WITH(0));
+}
+END_TEST()
+
+static volatile pid_t sender_pid = -1;
+
+static void record_sender(int sig, siginfo_t *info, void *ucontext)
+{
+	sender_pid = info->si_pid;
+}
+
+static int check_pid_ns_ids(void)
+{
+	struct sigaction sa = { .sa_sigaction = record_sender,
+				.sa_flags = SA_SIGINFO };
+
+	// The group and the session are led by the processes outside the namespace.
+	if (getpgrp() != 0 || getpgid(0) != 0 || getsid(0) != 0)
+		return 1;
+	if (getpgid(1) != 0 || getsid(1) != 0)
+		return 1;
+
+	if (setpgid(1, 2) == 0 || errno != EPERM)
+		return 1;
+	if (setpgid(0, 0) < 0 || getpgrp() != 1 || getpgid(1) != 1)
+		return 1;
+
+	if (sigaction(SIGUSR1, &sa, NULL) < 0 || kill(1, SIGUSR1) < 0)
+		return 1;
+	if (sender_pid != 1)
+		return 1;
+
+	return 0;
+}
+
+FN_TEST(pid_ids)
+{
+	pid_t pid, child;
+
+	pid = CHECK(fork());
+	if (pid == 0) {
+		CHECK(unshare(CLONE_NEWPID));
+
+		child = CHECK(fork());
+		if (child == 0)
+			_exit(check_pid_ns_ids());
+
+		CHECK_WITH(waitpid(child, &status, 0), _ret == child);
+		_exit(IS_EXITED_WITH(0) ? 0 : 1);
+	}
+
+	TEST_RES(waitpid(pid, &status, 0), _ret == pid && IS_EXITED_WITH(0));
+}
+END_TEST()
+
+FN_TEST(user)
+{
+	pid_t pid;
+	int fd;
+
+	pid = CHECK(fork());
+	if (pid == 0) {
+		CHECK(unshare(CLONE_NEWUSER));
+		if (getuid() != 65534)
+			_exit(1);
+
+		fd = CHECK(open("/proc/self/uid_map", O_WRONLY));
+		CHECK(write(fd, "0 0 1", 5));
+		if (write(fd, "0 0 1", 5) >= 0)
+			_exit(1);
+		CHECK(close(fd));
+
+		_exit(getuid() == 0 ? 0 : 1);
+	}
+
+	TEST_RES(waitpid(pid, &status, 0), _ret == pid && IS_EXITED_WITH(0));
+}
+END_TEST()
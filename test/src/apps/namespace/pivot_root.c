@@ -0,0 +1,67 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <sched.h>
+#include <unistd.h>
+#include <sys/mount.h>
+#include <sys/stat.h>
+#include <sys/syscall.h>
+#include <sys/wait.h>
+
+#include "../test.h"
+
+#define NEW_ROOT "/tmp/pivot_root_test"
+
+static int status;
+
+static int pivot_root(const char *new_root, const char *put_old)
+{
+	return syscall(SYS_pivot_root, new_root, put_old);
+}
+
+FN_SETUP(new_root)
+{
+	CHECK(mkdir(NEW_ROOT, 0755));
+}
+END_SETUP()
+
+FN_TEST(pivot_root)
+{
+	char cwd[16];
+	pid_t pid;
+
+	pid = CHECK(fork());
+	if (pid == 0) {
+		CHECK(unshare(CLONE_NEWNS));
+		// Shared mounts cannot be pivoted.
+		CHECK(mount(NULL, "/", NULL, MS_REC | MS_PRIVATE, NULL));
+
+		CHECK(mount("ramfs", NEW_ROOT, "ramfs", 0, NULL));
+		CHECK(mkdir(NEW_ROOT "/old", 0755));
+
+		CHECK_WITH(pivot_root("/", NEW_ROOT "/old"),
+			   _ret < 0 && errno == EBUSY);
+		CHECK_WITH(pivot_root(NEW_ROOT "/old", NEW_ROOT),
+			   _ret < 0 && errno == EINVAL);
+
+		CHECK(chdir(NEW_ROOT));
+		CHECK(pivot_root(".", "old"));
+
+		CHECK_WITH(getcwd(cwd, sizeof(cwd)),
+			   _ret != NULL && strcmp(cwd, "/") == 0);
+		CHECK(access("/old" NEW_ROOT, F_OK));
+		CHECK_WITH(access(NEW_ROOT, F_OK), _ret < 0 && errno == ENOENT);
+		_exit(0);
+	}
+
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && WIFEXITED(status) && WEXITSTATUS(status) == 0);
+}
+END_TEST()
+
+FN_SETUP(cleanup)
+{
+	CHECK(rmdir(NEW_ROOT));
+}
+END_SETUP()
@@ -0,0 +1,173 @@
+// SPDX-License-Identifier: MPL-2.0
+
+// This test requires the kernel to be booted with `ip=dhcp` on the QEMU
+// user-mode network, whose built-in DHCP server leases the addresses below.
+
+#include <arpa/inet.h>
+#include <fcntl.h>
+#include <linux/netlink.h>
+#include <linux/rtnetlink.h>
+#include <net/if.h>
+#include <sys/socket.h>
+#include <unistd.h>
+
+#include "../test.h"
+
+#define ETHER_NAME "eth0"
+
+#define LEASED_ADDR "10.0.2.15"
+#define LEASED_PREFIX_LEN 24
+#define GATEWAY "10.0.2.2"
+#define NAMESERVER "10.0.2.3"
+
+#define PNP_PATH "/proc/net/pnp"
+#define MAX_WAIT_SECS 30
+
+static char req_buf[256];
+static char resp_buf[8192];
+static char pnp_buf[256];
+
+static int rtnl_fd;
+static int eth0_index;
+
+static int read_pnp(void)
+{
+	ssize_t len;
+	int fd;
+
+	fd = CHECK(open(PNP_PATH, O_RDONLY));
+	len = CHECK(read(fd, pnp_buf, sizeof(pnp_buf) - 1));
+	CHECK(close(fd));
+	pnp_buf[len] = '\0';
+	return len;
+}
+
+FN_SETUP(wait_for_lease)
+{
+	int i;
+
+	for (i = 0; i < MAX_WAIT_SECS; i++) {
+		read_pnp();
+		if (strncmp(pnp_buf, "#PROTO: DHCP\n", 13) == 0)
+			break;
+		sleep(1);
+	}
+
+	rtnl_fd = CHECK(socket(AF_NETLINK, SOCK_RAW, NETLINK_ROUTE));
+	eth0_index = CHECK_WITH(if_nametoindex(ETHER_NAME), _ret != 0);
+}
+END_SETUP()
+
+// Dumps the objects of `type` and returns the number of them that `match`.
+static int dump_and_count(int type, size_t body_len,
+			  int (*match)(struct nlmsghdr *hdr))
+{
+	struct nlmsghdr *hdr = (struct nlmsghdr *)req_buf;
+	ssize_t len;
+	int count = 0;
+
+	memset(req_buf, 0, sizeof(req_buf));
+	hdr->nlmsg_len = NLMSG_LENGTH(body_len);
+	hdr->nlmsg_type = type;
+	hdr->nlmsg_flags = NLM_F_REQUEST | NLM_F_DUMP;
+	*(unsigned char *)NLMSG_DATA(hdr) = AF_INET;
+	if (send(rtnl_fd, req_buf, hdr->nlmsg_len, 0) < 0)
+		return -1;
+
+	for (;;) {
+		len = recv(rtnl_fd, resp_buf, sizeof(resp_buf), 0);
+		if (len < 0)
+			return -1;
+
+		for (hdr = (struct nlmsghdr *)resp_buf; NLMSG_OK(hdr, len);
+		     hdr = NLMSG_NEXT(hdr, len)) {
+			if (hdr->nlmsg_type == NLMSG_DONE)
+				return count;
+			if (hdr->nlmsg_type == NLMSG_ERROR) {
+				errno = EPROTO;
+				return -1;
+			}
+			count += match(hdr);
+		}
+	}
+}
+
+// Returns whether the segment has an attribute of `type` that is `addr`.
+static int has_addr_attr(struct nlmsghdr *hdr, size_t body_len, int type,
+			 const char *addr)
+{
+	struct rtattr *rta;
+	int len;
+
+	rta = (struct rtattr *)((char *)NLMSG_DATA(hdr) +
+				NLMSG_ALIGN(body_len));
+	len = hdr->nlmsg_len - NLMSG_LENGTH(NLMSG_ALIGN(body_len));
+	for (; RTA_OK(rta, len); rta = RTA_NEXT(rta, len))
+		if (rta->rta_type == type)
+			return *(in_addr_t *)RTA_DATA(rta) == inet_addr(addr);
+	return 0;
+}
+
+static int match_leased_addr(struct nlmsghdr *hdr)
+{
+	struct ifaddrmsg *ifa = NLMSG_DATA(hdr);
+
+	return hdr->nlmsg_type == RTM_NEWADDR &&
+	       ifa->ifa_index == eth0_index &&
+	       ifa->ifa_prefixlen == LEASED_PREFIX_LEN &&
+	       has_addr_attr(hdr, sizeof(*ifa), IFA_LOCAL, LEASED_ADDR);
+}
+
+static int match_default_route(struct nlmsghdr *hdr)
+{
+	struct rtmsg *rtm = NLMSG_DATA(hdr);
+
+	return hdr->nlmsg_type == RTM_NEWROUTE && rtm->rtm_dst_len == 0 &&
+	       has_addr_attr(hdr, sizeof(*rtm), RTA_GATEWAY, GATEWAY);
+}
+
+FN_TEST(leased_address)
+{
+	TEST_RES(dump_and_count(RTM_GETADDR, sizeof(struct ifaddrmsg),
+				match_leased_addr),
+		 _ret == 1);
+}
+END_TEST()
+
+FN_TEST(default_route)
+{
+	TEST_RES(dump_and_count(RTM_GETROUTE, sizeof(struct rtmsg),
+				match_default_route),
+		 _ret == 1);
+}
+END_TEST()
+
+FN_TEST(proc_net_pnp)
+{
+	TEST_SUCC(read_pnp());
+	TEST_RES(strcmp(pnp_buf, "#PROTO: DHCP\n"
+				 "nameserver " NAMESERVER "\n"
+				 "bootserver " GATEWAY "\n"),
+		 _ret == 0);
+}
+END_TEST()
+
+FN_TEST(connect_to_gateway)
+{
+	struct sockaddr_in addr = {
+		.sin_family = AF_INET,
+		.sin_port = htons(53),
+	};
+	struct sockaddr_in local;
+	socklen_t len = sizeof(local);
+	int fd;
+
+	// Connecting a UDP socket selects the source address via the routes.
+	addr.sin_addr.s_addr = inet_addr(NAMESERVER);
+	fd = TEST_SUCC(socket(AF_INET, SOCK_DGRAM, 0));
+	TEST_SUCC(connect(fd, (struct sockaddr *)&addr, sizeof(addr)));
+	TEST_RES(getsockname(fd, (struct sockaddr *)&local, &len),
+		 local.sin_addr.s_addr == inet_addr(LEASED_ADDR));
+	TEST_SUCC(close(fd));
+}
+END_TEST()
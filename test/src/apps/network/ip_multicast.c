@@ -0,0 +1,335 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <unistd.h>
+#include <string.h>
+#include <fcntl.h>
+#include <net/if.h>
+#include <sys/socket.h>
+#include <netinet/in.h>
+#include <arpa/inet.h>
+
+#include "../test.h"
+
+#define GROUP_ADDR "239.1.2.3"
+#define OTHER_GROUP_ADDR "239.1.2.4"
+#define MCAST_PORT 8650
+#define UCAST_PORT 8651
+
+static struct sockaddr_in group_addr;
+static struct sockaddr_in any_addr;
+static struct sockaddr_in lo_addr;
+static struct sockaddr_in bcast_addr;
+
+FN_SETUP(general)
+{
+	group_addr.sin_family = AF_INET;
+	group_addr.sin_port = htons(MCAST_PORT);
+	CHECK(inet_aton(GROUP_ADDR, &group_addr.sin_addr));
+
+	any_addr.sin_family = AF_INET;
+	any_addr.sin_port = htons(MCAST_PORT);
+	any_addr.sin_addr.s_addr = htonl(INADDR_ANY);
+
+	lo_addr.sin_family = AF_INET;
+	lo_addr.sin_port = htons(UCAST_PORT);
+	lo_addr.sin_addr.s_addr = htonl(INADDR_LOOPBACK);
+
+	bcast_addr.sin_family = AF_INET;
+	bcast_addr.sin_port = htons(MCAST_PORT);
+	bcast_addr.sin_addr.s_addr = htonl(INADDR_BROADCAST);
+}
+END_SETUP()
+
+static int get_int_option(int sk, int level, int name)
+{
+	int val = -1;
+	socklen_t len = sizeof(val);
+
+	if (getsockopt(sk, level, name, &val, &len) < 0)
+		return -1;
+	if (len != sizeof(val))
+		return -1;
+
+	return val;
+}
+
+FN_TEST(option_defaults)
+{
+	int sk;
+
+	sk = TEST_SUCC(socket(PF_INET, SOCK_DGRAM, 0));
+
+	TEST_RES(get_int_option(sk, IPPROTO_IP, IP_MULTICAST_TTL), _ret == 1);
+	TEST_RES(get_int_option(sk, IPPROTO_IP, IP_MULTICAST_LOOP), _ret == 1);
+	TEST_RES(get_int_option(sk, IPPROTO_IP, IP_PKTINFO), _ret == 0);
+	TEST_RES(get_int_option(sk, IPPROTO_IP, IP_RECVTTL), _ret == 0);
+	TEST_RES(get_int_option(sk, IPPROTO_IP, IP_TOS), _ret == 0);
+	TEST_RES(get_int_option(sk, IPPROTO_IP, IP_TTL), _ret == 64);
+	TEST_RES(get_int_option(sk, SOL_SOCKET, SO_BROADCAST), _ret == 0);
+
+	TEST_SUCC(close(sk));
+}
+END_TEST()
+
+FN_TEST(option_values)
+{
+	int sk, val;
+	unsigned char byte;
+
+	sk = TEST_SUCC(socket(PF_INET, SOCK_DGRAM, 0));
+
+	// Like Linux, the multicast options also accept a single byte.
+	byte = 32;
+	TEST_SUCC(setsockopt(sk, IPPROTO_IP, IP_MULTICAST_TTL, &byte,
+			     sizeof(byte)));
+	TEST_RES(get_int_option(sk, IPPROTO_IP, IP_MULTICAST_TTL), _ret == 32);
+	byte = 0;
+	TEST_SUCC(setsockopt(sk, IPPROTO_IP, IP_MULTICAST_LOOP, &byte,
+			     sizeof(byte)));
+	TEST_RES(get_int_option(sk, IPPROTO_IP, IP_MULTICAST_LOOP), _ret == 0);
+
+	val = 255;
+	TEST_SUCC(setsockopt(sk, IPPROTO_IP, IP_MULTICAST_TTL, &val,
+			     sizeof(val)));
+	TEST_RES(get_int_option(sk, IPPROTO_IP, IP_MULTICAST_TTL), _ret == 255);
+	val = -1;
+	TEST_SUCC(setsockopt(sk, IPPROTO_IP, IP_MULTICAST_TTL, &val,
+			     sizeof(val)));
+	TEST_RES(get_int_option(sk, IPPROTO_IP, IP_MULTICAST_TTL), _ret == 1);
+	val = 256;
+	TEST_ERRNO(setsockopt(sk, IPPROTO_IP, IP_MULTICAST_TTL, &val,
+			      sizeof(val)),
+		   EINVAL);
+
+	val = 0x10;
+	TEST_SUCC(setsockopt(sk, IPPROTO_IP, IP_TOS, &val, sizeof(val)));
+	TEST_RES(get_int_option(sk, IPPROTO_IP, IP_TOS), _ret == 0x10);
+
+	val = 1;
+	TEST_SUCC(setsockopt(sk, IPPROTO_IP, IP_PKTINFO, &val, sizeof(val)));
+	TEST_RES(get_int_option(sk, IPPROTO_IP, IP_PKTINFO), _ret == 1);
+	TEST_SUCC(setsockopt(sk, IPPROTO_IP, IP_RECVTTL, &val, sizeof(val)));
+	TEST_RES(get_int_option(sk, IPPROTO_IP, IP_RECVTTL), _ret == 1);
+	TEST_SUCC(setsockopt(sk, SOL_SOCKET, SO_BROADCAST, &val, sizeof(val)));
+	TEST_RES(get_int_option(sk, SOL_SOCKET, SO_BROADCAST), _ret == 1);
+
+	TEST_SUCC(close(sk));
+}
+END_TEST()
+
+FN_TEST(membership_errors)
+{
+	int sk;
+	struct ip_mreq mreq;
+	struct ip_mreqn mreqn;
+	socklen_t len = sizeof(mreq);
+
+	sk = TEST_SUCC(socket(PF_INET, SOCK_DGRAM, 0));
+
+	mreq.imr_multiaddr.s_addr = htonl(INADDR_LOOPBACK);
+	mreq.imr_interface.s_addr = htonl(INADDR_ANY);
+	TEST_ERRNO(setsockopt(sk, IPPROTO_IP, IP_ADD_MEMBERSHIP, &mreq,
+			      sizeof(mreq)),
+		   EINVAL);
+
+	CHECK(inet_aton(GROUP_ADDR, &mreq.imr_multiaddr));
+	TEST_ERRNO(setsockopt(sk, IPPROTO_IP, IP_ADD_MEMBERSHIP, &mreq,
+			      sizeof(mreq) - 1),
+		   EINVAL);
+	TEST_SUCC(setsockopt(sk, IPPROTO_IP, IP_ADD_MEMBERSHIP, &mreq,
+			     sizeof(mreq)));
+	TEST_ERRNO(setsockopt(sk, IPPROTO_IP, IP_ADD_MEMBERSHIP, &mreq,
+			      sizeof(mreq)),
+		   EADDRINUSE);
+	TEST_ERRNO(getsockopt(sk, IPPROTO_IP, IP_ADD_MEMBERSHIP, &mreq, &len),
+		   ENOPROTOOPT);
+
+	memset(&mreqn, 0, sizeof(mreqn));
+	CHECK(inet_aton(OTHER_GROUP_ADDR, &mreqn.imr_multiaddr));
+	mreqn.imr_ifindex = 12345;
+	TEST_ERRNO(setsockopt(sk, IPPROTO_IP, IP_ADD_MEMBERSHIP, &mreqn,
+			      sizeof(mreqn)),
+		   ENODEV);
+	mreqn.imr_ifindex = 0;
+	TEST_ERRNO(setsockopt(sk, IPPROTO_IP, IP_DROP_MEMBERSHIP, &mreqn,
+			      sizeof(mreqn)),
+		   EADDRNOTAVAIL);
+
+	TEST_SUCC(setsockopt(sk, IPPROTO_IP, IP_DROP_MEMBERSHIP, &mreq,
+			     sizeof(mreq)));
+	TEST_ERRNO(setsockopt(sk, IPPROTO_IP, IP_DROP_MEMBERSHIP, &mreq,
+			      sizeof(mreq)),
+		   EADDRNOTAVAIL);
+
+	TEST_SUCC(close(sk));
+
+	sk = TEST_SUCC(socket(PF_INET, SOCK_STREAM, 0));
+	TEST_ERRNO(setsockopt(sk, IPPROTO_IP, IP_ADD_MEMBERSHIP, &mreq,
+			      sizeof(mreq)),
+		   EPROTO);
+	TEST_SUCC(close(sk));
+}
+END_TEST()
+
+static int recv_with_info(int sk, char *buf, size_t len, int *ttl,
+			  struct in_pktinfo *pktinfo)
+{
+	char control[CMSG_SPACE(sizeof(struct in_pktinfo)) +
+		     CMSG_SPACE(sizeof(int))];
+	struct iovec iov = { .iov_base = buf, .iov_len = len };
+	struct msghdr msg = {
+		.msg_iov = &iov,
+		.msg_iovlen = 1,
+		.msg_control = control,
+		.msg_controllen = sizeof(control),
+	};
+	struct cmsghdr *cmsg;
+	int ret;
+
+	ret = recvmsg(sk, &msg, MSG_DONTWAIT);
+	if (ret < 0)
+		return ret;
+
+	*ttl = -1;
+	memset(pktinfo, 0, sizeof(*pktinfo));
+
+	for (cmsg = CMSG_FIRSTHDR(&msg); cmsg; cmsg = CMSG_NXTHDR(&msg, cmsg)) {
+		if (cmsg->cmsg_level != IPPROTO_IP)
+			return -1;
+
+		if (cmsg->cmsg_type == IP_TTL &&
+		    cmsg->cmsg_len == CMSG_LEN(sizeof(int)))
+			memcpy(ttl, CMSG_DATA(cmsg), sizeof(int));
+		else if (cmsg->cmsg_type == IP_PKTINFO &&
+			 cmsg->cmsg_len == CMSG_LEN(sizeof(*pktinfo)))
+			memcpy(pktinfo, CMSG_DATA(cmsg), sizeof(*pktinfo));
+		else
+			return -1;
+	}
+
+	return ret;
+}
+
+FN_TEST(multicast_loop)
+{
+	int sk_recv, sk_send, ttl, one = 1, zero = 0;
+	struct ip_mreq mreq;
+	struct in_pktinfo pktinfo;
+	char buf[16];
+
+	sk_recv = TEST_SUCC(socket(PF_INET, SOCK_DGRAM, 0));
+	sk_send = TEST_SUCC(socket(PF_INET, SOCK_DGRAM, 0));
+
+	TEST_SUCC(bind(sk_recv, (struct sockaddr *)&any_addr,
+		       sizeof(any_addr)));
+	TEST_SUCC(setsockopt(sk_recv, IPPROTO_IP, IP_PKTINFO, &one,
+			     sizeof(one)));
+	TEST_SUCC(setsockopt(sk_recv, IPPROTO_IP, IP_RECVTTL, &one,
+			     sizeof(one)));
+
+	// The group has not been joined.
+	TEST_RES(sendto(sk_send, "hello", 5, 0, (struct sockaddr *)&group_addr,
+			sizeof(group_addr)),
+		 _ret == 5);
+	TEST_ERRNO(recv_with_info(sk_recv, buf, sizeof(buf), &ttl, &pktinfo),
+		   EAGAIN);
+
+	mreq.imr_multiaddr = group_addr.sin_addr;
+	mreq.imr_interface.s_addr = htonl(INADDR_ANY);
+	TEST_SUCC(setsockopt(sk_recv, IPPROTO_IP, IP_ADD_MEMBERSHIP, &mreq,
+			     sizeof(mreq)));
+
+	TEST_RES(sendto(sk_send, "hello", 5, 0, (struct sockaddr *)&group_addr,
+			sizeof(group_addr)),
+		 _ret == 5);
+	TEST_RES(recv_with_info(sk_recv, buf, sizeof(buf), &ttl, &pktinfo),
+		 _ret == 5 && memcmp(buf, "hello", 5) == 0 && ttl == 1 &&
+			 pktinfo.ipi_ifindex > 0 &&
+			 pktinfo.ipi_addr.s_addr == group_addr.sin_addr.s_addr);
+
+	// The packet is not looped back if `IP_MULTICAST_LOOP` is disabled.
+	TEST_SUCC(setsockopt(sk_send, IPPROTO_IP, IP_MULTICAST_LOOP, &zero,
+			     sizeof(zero)));
+	TEST_RES(sendto(sk_send, "hello", 5, 0, (struct sockaddr *)&group_addr,
+			sizeof(group_addr)),
+		 _ret == 5);
+	TEST_ERRNO(recv_with_info(sk_recv, buf, sizeof(buf), &ttl, &pktinfo),
+		   EAGAIN);
+
+	TEST_SUCC(setsockopt(sk_send, IPPROTO_IP, IP_MULTICAST_LOOP, &one,
+			     sizeof(one)));
+	TEST_SUCC(setsockopt(sk_recv, IPPROTO_IP, IP_DROP_MEMBERSHIP, &mreq,
+			     sizeof(mreq)));
+	TEST_RES(sendto(sk_send, "hello", 5, 0, (struct sockaddr *)&group_addr,
+			sizeof(group_addr)),
+		 _ret == 5);
+	TEST_ERRNO(recv_with_info(sk_recv, buf, sizeof(buf), &ttl, &pktinfo),
+		   EAGAIN);
+
+	TEST_SUCC(close(sk_send));
+	TEST_SUCC(close(sk_recv));
+}
+END_TEST()
+
+FN_TEST(unicast_info)
+{
+	int sk_recv, sk_send, ttl, one = 1;
+	struct in_pktinfo pktinfo;
+	char buf[16];
+
+	sk_recv = TEST_SUCC(socket(PF_INET, SOCK_DGRAM, 0));
+	sk_send = TEST_SUCC(socket(PF_INET, SOCK_DGRAM, 0));
+
+	TEST_SUCC(bind(sk_recv, (struct sockaddr *)&lo_addr, sizeof(lo_addr)));
+
+	// No control messages are received if the options are disabled.
+	TEST_RES(sendto(sk_send, "hello", 5, 0, (struct sockaddr *)&lo_addr,
+			sizeof(lo_addr)),
+		 _ret == 5);
+	TEST_RES(recv_with_info(sk_recv, buf, sizeof(buf), &ttl, &pktinfo),
+		 _ret == 5 && ttl == -1 && pktinfo.ipi_ifindex == 0);
+
+	TEST_SUCC(setsockopt(sk_recv, IPPROTO_IP, IP_PKTINFO, &one,
+			     sizeof(one)));
+	TEST_SUCC(setsockopt(sk_recv, IPPROTO_IP, IP_RECVTTL, &one,
+			     sizeof(one)));
+
+	TEST_RES(sendto(sk_send, "hello", 5, 0, (struct sockaddr *)&lo_addr,
+			sizeof(lo_addr)),
+		 _ret == 5);
+	TEST_RES(recv_with_info(sk_recv, buf, sizeof(buf), &ttl, &pktinfo),
+		 _ret == 5 && ttl == 64 &&
+			 pktinfo.ipi_ifindex == (int)if_nametoindex("lo") &&
+			 pktinfo.ipi_spec_dst.s_addr == lo_addr.sin_addr.s_addr &&
+			 pktinfo.ipi_addr.s_addr == lo_addr.sin_addr.s_addr);
+
+	TEST_SUCC(close(sk_send));
+	TEST_SUCC(close(sk_recv));
+}
+END_TEST()
+
+FN_TEST(broadcast)
+{
+	int sk, one = 1;
+
+	sk = TEST_SUCC(socket(PF_INET, SOCK_DGRAM, 0));
+
+	TEST_ERRNO(sendto(sk, "hello", 5, 0, (struct sockaddr *)&bcast_addr,
+			  sizeof(bcast_addr)),
+		   EACCES);
+	TEST_ERRNO(connect(sk, (struct sockaddr *)&bcast_addr,
+			   sizeof(bcast_addr)),
+		   EACCES);
+
+	TEST_SUCC(setsockopt(sk, SOL_SOCKET, SO_BROADCAST, &one, sizeof(one)));
+	TEST_RES(sendto(sk, "hello", 5, 0, (struct sockaddr *)&bcast_addr,
+			sizeof(bcast_addr)),
+		 _ret == 5);
+	TEST_SUCC(connect(sk, (struct sockaddr *)&bcast_addr,
+			  sizeof(bcast_addr)));
+
+	TEST_SUCC(close(sk));
+}
+END_TEST()
@@ -0,0 +1,293 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#include <unistd.h>
+#include <string.h>
+#include <sys/signal.h>
+#include <sys/socket.h>
+#include <sys/poll.h>
+#include <netinet/in.h>
+#include <arpa/inet.h>
+#include <fcntl.h>
+
+#include "../test.h"
+
+static struct sockaddr_in6 sk_addr;
+static struct sockaddr_in6 sk_mapped_addr;
+static struct sockaddr_in sk_addr4;
+
+#define C_PORT htons(0x2345)
+#define S_PORT htons(0x2346)
+#define U_PORT htons(0x2347)
+#define S4_PORT htons(0x2348)
+
+static int is_loopback6(struct sockaddr_in6 *addr)
+{
+	return addr->sin6_family == AF_INET6 &&
+	       memcmp(&addr->sin6_addr, &in6addr_loopback,
+		      sizeof(in6addr_loopback)) == 0;
+}
+
+static int is_mapped_loopback(struct sockaddr_in6 *addr)
+{
+	return addr->sin6_family == AF_INET6 &&
+	       memcmp(&addr->sin6_addr, &sk_mapped_addr.sin6_addr,
+		      sizeof(sk_mapped_addr.sin6_addr)) == 0;
+}
+
+FN_SETUP(general)
+{
+	sk_addr.sin6_family = AF_INET6;
+	sk_addr.sin6_addr = in6addr_loopback;
+
+	sk_mapped_addr.sin6_family = AF_INET6;
+	CHECK(inet_pton(AF_INET6, "::ffff:127.0.0.1",
+			&sk_mapped_addr.sin6_addr));
+
+	sk_addr4.sin_family = AF_INET;
+	CHECK(inet_aton("127.0.0.1", &sk_addr4.sin_addr));
+
+	signal(SIGPIPE, SIG_IGN);
+}
+END_SETUP()
+
+static int sk_unbound;
+static int sk_listen;
+static int sk_connected;
+static int sk_accepted;
+static int sk_listen4;
+
+FN_SETUP(unbound)
+{
+	sk_unbound = CHECK(socket(PF_INET6, SOCK_STREAM | SOCK_NONBLOCK, 0));
+}
+END_SETUP()
+
+FN_SETUP(listen)
+{
+	sk_listen = CHECK(socket(PF_INET6, SOCK_STREAM, 0));
+
+	sk_addr.sin6_port = S_PORT;
+	CHECK(bind(sk_listen, (struct sockaddr *)&sk_addr, sizeof(sk_addr)));
+
+	CHECK(listen(sk_listen, 2));
+}
+END_SETUP()
+
+FN_SETUP(connected)
+{
+	sk_connected = CHECK(socket(PF_INET6, SOCK_STREAM, 0));
+
+	sk_addr.sin6_port = S_PORT;
+	CHECK(connect(sk_connected, (struct sockaddr *)&sk_addr,
+		      sizeof(sk_addr)));
+}
+END_SETUP()
+
+FN_SETUP(accepted)
+{
+	sk_accepted = CHECK(accept(sk_listen, NULL, NULL));
+}
+END_SETUP()
+
+FN_SETUP(listen4)
+{
+	sk_listen4 = CHECK(socket(PF_INET, SOCK_STREAM, 0));
+
+	sk_addr4.sin_port = S4_PORT;
+	CHECK(bind(sk_listen4, (struct sockaddr *)&sk_addr4,
+		   sizeof(sk_addr4)));
+
+	CHECK(listen(sk_listen4, 2));
+}
+END_SETUP()
+
+FN_TEST(getsockname)
+{
+	struct sockaddr_in6 saddr;
+	struct sockaddr *psaddr = (struct sockaddr *)&saddr;
+	socklen_t addrlen;
+
+	addrlen = sizeof(saddr);
+	TEST_RES(getsockname(sk_unbound, psaddr, &addrlen),
+		 addrlen == sizeof(saddr) && saddr.sin6_family == AF_INET6 &&
+			 saddr.sin6_port == 0 &&
+			 IN6_IS_ADDR_UNSPECIFIED(&saddr.sin6_addr));
+
+	addrlen = sizeof(saddr);
+	TEST_RES(getsockname(sk_listen, psaddr, &addrlen),
+		 addrlen == sizeof(saddr) && is_loopback6(&saddr) &&
+			 saddr.sin6_port == S_PORT);
+
+	addrlen = sizeof(saddr);
+	TEST_RES(getsockname(sk_connected, psaddr, &addrlen),
+		 addrlen == sizeof(saddr) && is_loopback6(&saddr) &&
+			 saddr.sin6_port != 0);
+
+	addrlen = sizeof(saddr);
+	TEST_RES(getsockname(sk_accepted, psaddr, &addrlen),
+		 addrlen == sizeof(saddr) && is_loopback6(&saddr) &&
+			 saddr.sin6_port == S_PORT);
+}
+END_TEST()
+
+FN_TEST(getpeername)
+{
+	struct sockaddr_in6 saddr;
+	struct sockaddr *psaddr = (struct sockaddr *)&saddr;
+	socklen_t addrlen;
+
+	addrlen = sizeof(saddr);
+	TEST_RES(getpeername(sk_connected, psaddr, &addrlen),
+		 addrlen == sizeof(saddr) && is_loopback6(&saddr) &&
+			 saddr.sin6_port == S_PORT);
+
+	addrlen = sizeof(saddr);
+	TEST_RES(getpeername(sk_accepted, psaddr, &addrlen),
+		 addrlen == sizeof(saddr) && is_loopback6(&saddr) &&
+			 saddr.sin6_port != 0);
+}
+END_TEST()
+
+FN_TEST(tcp_send_recv)
+{
+	char buf[6];
+
+	TEST_RES(send(sk_connected, "hello", 6, 0), _ret == 6);
+	TEST_RES(recv(sk_accepted, buf, sizeof(buf), 0),
+		 _ret == 6 && strcmp(buf, "hello") == 0);
+
+	TEST_RES(send(sk_accepted, "world", 6, 0), _ret == 6);
+	TEST_RES(recv(sk_connected, buf, sizeof(buf), 0),
+		 _ret == 6 && strcmp(buf, "world") == 0);
+}
+END_TEST()
+
+FN_TEST(udp_send_recv)
+{
+	int sk_server, sk_client;
+	struct sockaddr_in6 saddr;
+	struct sockaddr *psaddr = (struct sockaddr *)&saddr;
+	socklen_t addrlen = sizeof(saddr);
+	char buf[6];
+
+	sk_server = TEST_SUCC(socket(PF_INET6, SOCK_DGRAM, 0));
+	sk_client = TEST_SUCC(socket(PF_INET6, SOCK_DGRAM, 0));
+
+	sk_addr.sin6_port = U_PORT;
+	TEST_SUCC(bind(sk_server, (struct sockaddr *)&sk_addr,
+		       sizeof(sk_addr)));
+
+	TEST_RES(sendto(sk_client, "hello", 6, 0, (struct sockaddr *)&sk_addr,
+			sizeof(sk_addr)),
+		 _ret == 6);
+	TEST_RES(recvfrom(sk_server, buf, sizeof(buf), 0, psaddr, &addrlen),
+		 _ret == 6 && strcmp(buf, "hello") == 0 &&
+			 addrlen == sizeof(saddr) && is_loopback6(&saddr));
+
+	TEST_RES(sendto(sk_server, "world", 6, 0, psaddr, addrlen), _ret == 6);
+	TEST_RES(recv(sk_client, buf, sizeof(buf), 0),
+		 _ret == 6 && strcmp(buf, "world") == 0);
+
+	TEST_SUCC(close(sk_server));
+	TEST_SUCC(close(sk_client));
+}
+END_TEST()
+
+FN_TEST(v4_mapped)
+{
+	int sk;
+	struct sockaddr_in6 saddr;
+	struct sockaddr *psaddr = (struct sockaddr *)&saddr;
+	socklen_t addrlen = sizeof(saddr);
+
+	sk = TEST_SUCC(socket(PF_INET6, SOCK_STREAM, 0));
+
+	sk_mapped_addr.sin6_port = S4_PORT;
+	TEST_SUCC(connect(sk, (struct sockaddr *)&sk_mapped_addr,
+			  sizeof(sk_mapped_addr)));
+
+	TEST_RES(getsockname(sk, psaddr, &addrlen),
+		 addrlen == sizeof(saddr) && is_mapped_loopback(&saddr));
+	TEST_RES(getpeername(sk, psaddr, &addrlen),
+		 addrlen == sizeof(saddr) && is_mapped_loopback(&saddr) &&
+			 saddr.sin6_port == S4_PORT);
+
+	TEST_SUCC(close(sk));
+}
+END_TEST()
+
+FN_TEST(v6only)
+{
+	int sk, v6only;
+	socklen_t optlen = sizeof(v6only);
+
+	sk = TEST_SUCC(socket(PF_INET6, SOCK_STREAM, 0));
+
+	TEST_RES(getsockopt(sk, IPPROTO_IPV6, IPV6_V6ONLY, &v6only, &optlen),
+		 optlen == sizeof(v6only) && v6only == 0);
+
+	v6only = 1;
+	TEST_SUCC(setsockopt(sk, IPPROTO_IPV6, IPV6_V6ONLY, &v6only,
+			     sizeof(v6only)));
+	TEST_RES(getsockopt(sk, IPPROTO_IPV6, IPV6_V6ONLY, &v6only, &optlen),
+		 optlen == sizeof(v6only) && v6only == 1);
+
+	sk_mapped_addr.sin6_port = S4_PORT;
+	TEST_ERRNO(connect(sk, (struct sockaddr *)&sk_mapped_addr,
+			   sizeof(sk_mapped_addr)),
+		   ENETUNREACH);
+
+	sk_mapped_addr.sin6_port = C_PORT;
+	TEST_ERRNO(bind(sk, (struct sockaddr *)&sk_mapped_addr,
+			sizeof(sk_mapped_addr)),
+		   EINVAL);
+
+	TEST_SUCC(close(sk));
+
+	TEST_ERRNO(getsockopt(sk_listen4, IPPROTO_IPV6, IPV6_V6ONLY, &v6only,
+			      &optlen),
+		   EOPNOTSUPP);
+	TEST_ERRNO(setsockopt(sk_listen4, IPPROTO_IPV6, IPV6_V6ONLY, &v6only,
+			      sizeof(v6only)),
+		   ENOPROTOOPT);
+
+	v6only = 1;
+	TEST_ERRNO(setsockopt(sk_listen, IPPROTO_IPV6, IPV6_V6ONLY, &v6only,
+			      sizeof(v6only)),
+		   EINVAL);
+}
+END_TEST()
+
+FN_TEST(bad_addr)
+{
+	int sk;
+
+	sk = TEST_SUCC(socket(PF_INET6, SOCK_DGRAM, 0));
+
+	sk_addr.sin6_port = C_PORT;
+	TEST_ERRNO(bind(sk, (struct sockaddr *)&sk_addr, 23), EINVAL);
+
+	sk_addr4.sin_port = C_PORT;
+	TEST_ERRNO(bind(sk, (struct sockaddr *)&sk_addr4, sizeof(sk_addr4)),
+		   EINVAL);
+
+	TEST_SUCC(close(sk));
+
+	sk = TEST_SUCC(socket(PF_INET, SOCK_DGRAM, 0));
+
+	TEST_ERRNO(bind(sk, (struct sockaddr *)&sk_addr, sizeof(sk_addr)),
+		   EAFNOSUPPORT);
+
+	TEST_SUCC(close(sk));
+}
+END_TEST()
+
+FN_SETUP(cleanup)
+{
+	CHECK(close(sk_unbound));
+	CHECK(close(sk_listen));
+	CHECK(close(sk_connected));
+	CHECK(close(sk_accepted));
+	CHECK(close(sk_listen4));
+}
+END_SETUP()
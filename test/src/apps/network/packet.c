@@ -0,0 +1,280 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#include <unistd.h>
+#include <string.h>
+#include <sys/socket.h>
+#include <net/if.h>
+#include <net/if_arp.h>
+#include <netinet/in.h>
+#include <netinet/ip.h>
+#include <netinet/udp.h>
+#include <arpa/inet.h>
+#include <linux/if_ether.h>
+#include <linux/if_packet.h>
+
+#include "../test.h"
+
+#define UDP_PORT 0x4321
+#define MESSAGE "hello"
+#define MESSAGE_LEN (sizeof(MESSAGE) - 1)
+#define FRAME_LEN \
+	(ETH_HLEN + sizeof(struct iphdr) + sizeof(struct udphdr) + MESSAGE_LEN)
+
+static int lo_index;
+static int udp_sk;
+static struct sockaddr_in udp_addr;
+static struct sockaddr_ll lo_addr;
+
+FN_SETUP(general)
+{
+	lo_index = CHECK(if_nametoindex("lo"));
+
+	udp_addr.sin_family = AF_INET;
+	udp_addr.sin_port = htons(UDP_PORT);
+	CHECK(inet_aton("127.0.0.1", &udp_addr.sin_addr));
+
+	udp_sk = CHECK(socket(PF_INET, SOCK_DGRAM, 0));
+	CHECK(bind(udp_sk, (struct sockaddr *)&udp_addr, sizeof(udp_addr)));
+
+	lo_addr.sll_family = AF_PACKET;
+	lo_addr.sll_protocol = htons(ETH_P_IP);
+	lo_addr.sll_ifindex = lo_index;
+}
+END_SETUP()
+
+static int send_udp(void)
+{
+	return sendto(udp_sk, MESSAGE, MESSAGE_LEN, 0,
+		      (struct sockaddr *)&udp_addr, sizeof(udp_addr));
+}
+
+static int recv_udp(void)
+{
+	char buf[16];
+
+	return recv(udp_sk, buf, sizeof(buf), 0);
+}
+
+FN_TEST(bind)
+{
+	int sk;
+	struct sockaddr_ll saddr;
+	socklen_t addrlen = sizeof(saddr);
+
+	sk = TEST_SUCC(socket(PF_PACKET, SOCK_RAW, htons(ETH_P_IP)));
+
+	saddr = lo_addr;
+	saddr.sll_ifindex = 0x7fffffff;
+	TEST_ERRNO(bind(sk, (struct sockaddr *)&saddr, sizeof(saddr)), ENODEV);
+
+	TEST_SUCC(bind(sk, (struct sockaddr *)&lo_addr, sizeof(lo_addr)));
+	TEST_RES(getsockname(sk, (struct sockaddr *)&saddr, &addrlen),
+		 saddr.sll_family == AF_PACKET &&
+			 saddr.sll_protocol == htons(ETH_P_IP) &&
+			 saddr.sll_ifindex == lo_index &&
+			 saddr.sll_hatype == ARPHRD_LOOPBACK &&
+			 saddr.sll_halen == ETH_ALEN);
+
+	TEST_SUCC(close(sk));
+}
+END_TEST()
+
+// Receives frames from a packet socket until a frame of the UDP datagram with
+// the packet type arrives, since the frames sent by other sockets can also be
+// received.
+static int recv_udp_frame(int sk, int is_raw, unsigned char pkttype)
+{
+	char buf[128];
+	struct sockaddr_ll saddr;
+	socklen_t addrlen;
+	struct iphdr *ip;
+	struct udphdr *udp;
+	int i, ret;
+
+	for (i = 0; i < 8; ++i) {
+		addrlen = sizeof(saddr);
+		ret = recvfrom(sk, buf, sizeof(buf), MSG_DONTWAIT,
+			       (struct sockaddr *)&saddr, &addrlen);
+		if (ret < 0)
+			return ret;
+
+		if (saddr.sll_family != AF_PACKET ||
+		    saddr.sll_protocol != htons(ETH_P_IP) ||
+		    saddr.sll_ifindex != lo_index ||
+		    saddr.sll_hatype != ARPHRD_LOOPBACK)
+			return -1;
+
+		if (is_raw) {
+			if (ret < ETH_HLEN || buf[12] != 0x08 || buf[13] != 0x00)
+				return -1;
+			ip = (struct iphdr *)(buf + ETH_HLEN);
+			ret -= ETH_HLEN;
+		} else {
+			ip = (struct iphdr *)buf;
+		}
+
+		if (ret < (int)(sizeof(*ip) + sizeof(*udp)) || ip->version != 4)
+			return -1;
+		if (ip->protocol != IPPROTO_UDP)
+			continue;
+
+		udp = (struct udphdr *)(ip + 1);
+		if (udp->dest == htons(UDP_PORT) && saddr.sll_pkttype == pkttype)
+			return is_raw ? ret + ETH_HLEN : ret;
+	}
+
+	return -1;
+}
+
+FN_TEST(recv_raw)
+{
+	int sk;
+
+	sk = TEST_SUCC(socket(PF_PACKET, SOCK_RAW, htons(ETH_P_IP)));
+	TEST_SUCC(bind(sk, (struct sockaddr *)&lo_addr, sizeof(lo_addr)));
+
+	TEST_RES(send_udp(), _ret == MESSAGE_LEN);
+	TEST_RES(recv_udp(), _ret == MESSAGE_LEN);
+
+	// Sockets that are bound to a specific protocol only see incoming frames.
+	TEST_RES(recv_udp_frame(sk, 1, PACKET_HOST), _ret == FRAME_LEN);
+	TEST_ERRNO(recv(sk, NULL, 0, MSG_DONTWAIT), EAGAIN);
+
+	TEST_SUCC(close(sk));
+}
+END_TEST()
+
+FN_TEST(recv_dgram)
+{
+	int sk;
+	struct sockaddr_ll saddr;
+
+	sk = TEST_SUCC(socket(PF_PACKET, SOCK_DGRAM, htons(ETH_P_ALL)));
+	saddr = lo_addr;
+	saddr.sll_protocol = htons(ETH_P_ALL);
+	TEST_SUCC(bind(sk, (struct sockaddr *)&saddr, sizeof(saddr)));
+
+	TEST_RES(send_udp(), _ret == MESSAGE_LEN);
+	TEST_RES(recv_udp(), _ret == MESSAGE_LEN);
+
+	// On the loopback interface, a frame is seen twice: once when it is sent
+	// and once when it is received.
+	TEST_RES(recv_udp_frame(sk, 0, PACKET_OUTGOING),
+		 _ret == FRAME_LEN - ETH_HLEN);
+	TEST_RES(recv_udp_frame(sk, 0, PACKET_HOST),
+		 _ret == FRAME_LEN - ETH_HLEN);
+
+	TEST_SUCC(close(sk));
+}
+END_TEST()
+
+static unsigned short checksum(const void *data, size_t len)
+{
+	const unsigned short *words = data;
+	unsigned int sum = 0;
+
+	for (; len > 1; len -= 2)
+		sum += *words++;
+
+	sum = (sum >> 16) + (sum & 0xffff);
+	sum += sum >> 16;
+
+	return ~sum;
+}
+
+FN_TEST(send_raw)
+{
+	int sk, sniffer;
+	struct sockaddr_ll saddr;
+	struct __attribute__((packed)) {
+		struct ethhdr eth;
+		struct iphdr ip;
+		struct udphdr udp;
+		char message[MESSAGE_LEN];
+	} frame;
+
+	sniffer = TEST_SUCC(socket(PF_PACKET, SOCK_RAW, htons(ETH_P_ALL)));
+	saddr = lo_addr;
+	saddr.sll_protocol = htons(ETH_P_ALL);
+	TEST_SUCC(bind(sniffer, (struct sockaddr *)&saddr, sizeof(saddr)));
+
+	sk = TEST_SUCC(socket(PF_PACKET, SOCK_RAW, 0));
+
+	TEST_ERRNO(send(sk, &frame, sizeof(frame), 0), ENXIO);
+	TEST_ERRNO(sendto(sk, &frame, ETH_HLEN - 1, 0,
+			  (struct sockaddr *)&lo_addr, sizeof(lo_addr)),
+		   EINVAL);
+
+	// The UDP checksum is optional for IPv4, so it is left zero.
+	memset(&frame, 0, sizeof(frame));
+	frame.eth.h_proto = htons(ETH_P_IP);
+	frame.ip.version = 4;
+	frame.ip.ihl = sizeof(frame.ip) / 4;
+	frame.ip.tot_len = htons(sizeof(frame) - sizeof(frame.eth));
+	frame.ip.ttl = 64;
+	frame.ip.protocol = IPPROTO_UDP;
+	frame.ip.saddr = udp_addr.sin_addr.s_addr;
+	frame.ip.daddr = udp_addr.sin_addr.s_addr;
+	frame.ip.check = checksum(&frame.ip, sizeof(frame.ip));
+	frame.udp.source = htons(UDP_PORT);
+	frame.udp.dest = htons(UDP_PORT);
+	frame.udp.len = htons(sizeof(frame.udp) + MESSAGE_LEN);
+	memcpy(frame.message, MESSAGE, MESSAGE_LEN);
+
+	TEST_RES(sendto(sk, &frame, sizeof(frame), 0,
+			(struct sockaddr *)&lo_addr, sizeof(lo_addr)),
+		 _ret == sizeof(frame));
+	TEST_RES(recv_udp_frame(sniffer, 1, PACKET_OUTGOING),
+		 _ret == sizeof(frame));
+	TEST_RES(recv_udp_frame(sniffer, 1, PACKET_HOST),
+		 _ret == sizeof(frame));
+
+	// Sockets with a zero protocol receive no frames.
+	TEST_ERRNO(recv(sk, NULL, 0, MSG_DONTWAIT), EAGAIN);
+
+	TEST_SUCC(close(sk));
+	TEST_SUCC(close(sniffer));
+}
+END_TEST()
+
+FN_TEST(membership)
+{
+	int sk;
+	struct packet_mreq mreq;
+
+	sk = TEST_SUCC(socket(PF_PACKET, SOCK_RAW, 0));
+
+	memset(&mreq, 0, sizeof(mreq));
+	mreq.mr_ifindex = 0x7fffffff;
+	mreq.mr_type = PACKET_MR_PROMISC;
+	TEST_ERRNO(setsockopt(sk, SOL_PACKET, PACKET_ADD_MEMBERSHIP, &mreq,
+			      sizeof(mreq)),
+		   ENODEV);
+
+	mreq.mr_ifindex = lo_index;
+	TEST_ERRNO(setsockopt(sk, SOL_PACKET, PACKET_ADD_MEMBERSHIP, &mreq,
+			      sizeof(mreq) - 1),
+		   EINVAL);
+
+	mreq.mr_alen = sizeof(mreq.mr_address);
+	TEST_ERRNO(setsockopt(sk, SOL_PACKET, PACKET_ADD_MEMBERSHIP, &mreq,
+			      sizeof(mreq)),
+		   EINVAL);
+	mreq.mr_alen = 0;
+
+	TEST_SUCC(setsockopt(sk, SOL_PACKET, PACKET_ADD_MEMBERSHIP, &mreq,
+			     sizeof(mreq)));
+	TEST_SUCC(setsockopt(sk, SOL_PACKET, PACKET_ADD_MEMBERSHIP, &mreq,
+			     sizeof(mreq)));
+
+	TEST_SUCC(setsockopt(sk, SOL_PACKET, PACKET_DROP_MEMBERSHIP, &mreq,
+			     sizeof(mreq)));
+	TEST_SUCC(setsockopt(sk, SOL_PACKET, PACKET_DROP_MEMBERSHIP, &mreq,
+			     sizeof(mreq)));
+	// Dropping a membership that does not exist succeeds silently.
+	TEST_SUCC(setsockopt(sk, SOL_PACKET, PACKET_DROP_MEMBERSHIP, &mreq,
+			     sizeof(mreq)));
+
+	TEST_SUCC(close(sk));
+}
+END_TEST()
@@ -0,0 +1,227 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#include <unistd.h>
+#include <string.h>
+#include <fcntl.h>
+#include <sys/socket.h>
+#include <netinet/in.h>
+#include <netinet/ip.h>
+#include <netinet/ip_icmp.h>
+#include <arpa/inet.h>
+
+#include "../test.h"
+
+#define ICMP_ID 0x1234
+#define ICMP_SEQ 0x5678
+#define PING_GROUP_RANGE "/proc/sys/net/ipv4/ping_group_range"
+
+static struct sockaddr_in sk_addr;
+
+FN_SETUP(general)
+{
+	sk_addr.sin_family = AF_INET;
+	CHECK(inet_aton("127.0.0.1", &sk_addr.sin_addr));
+}
+END_SETUP()
+
+static unsigned short checksum(const void *data, size_t len)
+{
+	const unsigned short *words = data;
+	unsigned int sum = 0;
+
+	for (; len > 1; len -= 2)
+		sum += *words++;
+	if (len == 1)
+		sum += *(const unsigned char *)words;
+
+	sum = (sum >> 16) + (sum & 0xffff);
+	sum += sum >> 16;
+
+	return ~sum;
+}
+
+static void make_echo_request(struct icmphdr *icmp)
+{
+	memset(icmp, 0, sizeof(*icmp));
+	icmp->type = ICMP_ECHO;
+	icmp->un.echo.id = htons(ICMP_ID);
+	icmp->un.echo.sequence = htons(ICMP_SEQ);
+	icmp->checksum = checksum(icmp, sizeof(*icmp));
+}
+
+// Receives packets from a raw socket until an echo reply arrives, since raw
+// sockets also receive the echo requests that are sent to the local machine.
+static int recv_echo_reply(int sk, char *buf, size_t len)
+{
+	struct sockaddr_in saddr;
+	socklen_t addrlen;
+	struct iphdr *ip = (struct iphdr *)buf;
+	struct icmphdr *icmp = (struct icmphdr *)(buf + sizeof(*ip));
+	int i, ret;
+
+	for (i = 0; i < 2; ++i) {
+		addrlen = sizeof(saddr);
+		ret = recvfrom(sk, buf, len, 0, (struct sockaddr *)&saddr,
+			       &addrlen);
+		if (ret < 0)
+			return ret;
+
+		if (addrlen != sizeof(saddr) || saddr.sin_port != 0 ||
+		    saddr.sin_addr.s_addr != sk_addr.sin_addr.s_addr)
+			return -1;
+		if (ret < (int)(sizeof(*ip) + sizeof(*icmp)) ||
+		    ip->version != 4 || ip->protocol != IPPROTO_ICMP ||
+		    ntohs(ip->tot_len) != ret)
+			return -1;
+
+		if (icmp->type == ICMP_ECHOREPLY)
+			return ret;
+	}
+
+	return -1;
+}
+
+FN_TEST(raw_protocol)
+{
+	TEST_ERRNO(socket(PF_INET, SOCK_RAW, 0), EPROTONOSUPPORT);
+	TEST_ERRNO(socket(PF_INET, SOCK_RAW, -1), EINVAL);
+}
+END_TEST()
+
+FN_TEST(raw_icmp)
+{
+	int sk;
+	struct icmphdr icmp;
+	struct sockaddr_in saddr;
+	socklen_t addrlen = sizeof(saddr);
+	char buf[128];
+	struct icmphdr *reply = (struct icmphdr *)(buf + sizeof(struct iphdr));
+
+	sk = TEST_SUCC(socket(PF_INET, SOCK_RAW, IPPROTO_ICMP));
+
+	TEST_ERRNO(send(sk, "", 0, 0), EDESTADDRREQ);
+
+	make_echo_request(&icmp);
+	TEST_RES(sendto(sk, &icmp, sizeof(icmp), 0, (struct sockaddr *)&sk_addr,
+			sizeof(sk_addr)),
+		 _ret == sizeof(icmp));
+	TEST_RES(recv_echo_reply(sk, buf, sizeof(buf)),
+		 _ret == sizeof(struct iphdr) + sizeof(icmp) &&
+			 reply->un.echo.id == htons(ICMP_ID) &&
+			 reply->un.echo.sequence == htons(ICMP_SEQ));
+
+	TEST_SUCC(bind(sk, (struct sockaddr *)&sk_addr, sizeof(sk_addr)));
+	TEST_RES(getsockname(sk, (struct sockaddr *)&saddr, &addrlen),
+		 addrlen == sizeof(saddr) &&
+			 saddr.sin_addr.s_addr == sk_addr.sin_addr.s_addr &&
+			 saddr.sin_port == htons(IPPROTO_ICMP));
+
+	TEST_SUCC(close(sk));
+}
+END_TEST()
+
+FN_TEST(raw_hdrincl)
+{
+	int sk, hdrincl;
+	socklen_t optlen = sizeof(hdrincl);
+	struct {
+		struct iphdr ip;
+		struct icmphdr icmp;
+	} packet;
+	char buf[128];
+
+	sk = TEST_SUCC(socket(PF_INET, SOCK_RAW, IPPROTO_RAW));
+	TEST_RES(getsockopt(sk, IPPROTO_IP, IP_HDRINCL, &hdrincl, &optlen),
+		 optlen == sizeof(hdrincl) && hdrincl == 1);
+	TEST_SUCC(close(sk));
+
+	sk = TEST_SUCC(socket(PF_INET, SOCK_RAW, IPPROTO_ICMP));
+	TEST_RES(getsockopt(sk, IPPROTO_IP, IP_HDRINCL, &hdrincl, &optlen),
+		 optlen == sizeof(hdrincl) && hdrincl == 0);
+
+	hdrincl = 1;
+	TEST_SUCC(setsockopt(sk, IPPROTO_IP, IP_HDRINCL, &hdrincl,
+			     sizeof(hdrincl)));
+
+	TEST_ERRNO(sendto(sk, &packet, sizeof(packet.ip) - 1, 0,
+			  (struct sockaddr *)&sk_addr, sizeof(sk_addr)),
+		   EINVAL);
+
+	// The total length and the source address are filled in by the kernel.
+	memset(&packet.ip, 0, sizeof(packet.ip));
+	packet.ip.version = 4;
+	packet.ip.ihl = sizeof(packet.ip) / 4;
+	packet.ip.ttl = 64;
+	packet.ip.protocol = IPPROTO_ICMP;
+	packet.ip.daddr = sk_addr.sin_addr.s_addr;
+	make_echo_request(&packet.icmp);
+
+	TEST_RES(sendto(sk, &packet, sizeof(packet), 0,
+			(struct sockaddr *)&sk_addr, sizeof(sk_addr)),
+		 _ret == sizeof(packet));
+	TEST_RES(recv_echo_reply(sk, buf, sizeof(buf)),
+		 _ret == sizeof(packet));
+
+	TEST_SUCC(close(sk));
+}
+END_TEST()
+
+static int write_ping_group_range(const char *range)
+{
+	int fd, ret;
+
+	fd = open(PING_GROUP_RANGE, O_WRONLY);
+	if (fd < 0)
+		return fd;
+
+	ret = write(fd, range, strlen(range));
+	close(fd);
+
+	return ret;
+}
+
+FN_TEST(ping)
+{
+	int sk;
+	in_port_t port;
+	struct icmphdr icmp;
+	struct sockaddr_in saddr;
+	socklen_t addrlen = sizeof(saddr);
+	char buf[128];
+
+	TEST_ERRNO(socket(PF_INET, SOCK_DGRAM, IPPROTO_ICMP), EACCES);
+
+	TEST_RES(write_ping_group_range("0 0"), _ret == 3);
+	sk = TEST_SUCC(socket(PF_INET, SOCK_DGRAM, IPPROTO_ICMP));
+	TEST_RES(write_ping_group_range("1 0"), _ret == 3);
+
+	make_echo_request(&icmp);
+	TEST_ERRNO(sendto(sk, &icmp, sizeof(icmp) - 1, 0,
+			  (struct sockaddr *)&sk_addr, sizeof(sk_addr)),
+		   EINVAL);
+
+	icmp.type = ICMP_ECHOREPLY;
+	TEST_ERRNO(sendto(sk, &icmp, sizeof(icmp), 0,
+			  (struct sockaddr *)&sk_addr, sizeof(sk_addr)),
+		   EINVAL);
+
+	// The identifier is overwritten with the port of the socket.
+	make_echo_request(&icmp);
+	TEST_RES(sendto(sk, &icmp, sizeof(icmp), 0, (struct sockaddr *)&sk_addr,
+			sizeof(sk_addr)),
+		 _ret == sizeof(icmp));
+	TEST_RES(getsockname(sk, (struct sockaddr *)&saddr, &addrlen),
+		 addrlen == sizeof(saddr) && saddr.sin_port != 0);
+	port = saddr.sin_port;
+
+	TEST_RES(recvfrom(sk, buf, sizeof(buf), 0, (struct sockaddr *)&saddr,
+			  &addrlen),
+		 _ret == sizeof(icmp) &&
+			 ((struct icmphdr *)buf)->type == ICMP_ECHOREPLY &&
+			 ((struct icmphdr *)buf)->un.echo.id == port &&
+			 saddr.sin_addr.s_addr == sk_addr.sin_addr.s_addr &&
+			 saddr.sin_port == 0);
+
+	TEST_SUCC(close(sk));
+}
+END_TEST()
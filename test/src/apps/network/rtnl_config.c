@@ -0,0 +1,427 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#include <arpa/inet.h>
+#include <linux/netlink.h>
+#include <linux/rtnetlink.h>
+#include <net/if.h>
+#include <sys/socket.h>
+#include <unistd.h>
+
+#include "../test.h"
+
+#define ETHER_NAME "eth0"
+#define LOOPBACK_NAME "lo"
+
+#define TEST_ADDR "10.255.0.1"
+#define TEST_ROUTE_DST "10.254.0.0"
+#define TEST_GATEWAY "10.0.2.2"
+#define TEST_NEIGH "10.0.2.200"
+
+static char req_buf[256];
+static char resp_buf[8192];
+static unsigned int seq;
+
+static int rtnl_fd;
+static int lo_index;
+static int eth0_index;
+
+FN_SETUP(init)
+{
+	rtnl_fd = CHECK(socket(AF_NETLINK, SOCK_RAW, NETLINK_ROUTE));
+	lo_index = CHECK_WITH(if_nametoindex(LOOPBACK_NAME), _ret != 0);
+	eth0_index = CHECK_WITH(if_nametoindex(ETHER_NAME), _ret != 0);
+}
+END_SETUP()
+
+static in_addr_t to_addr(const char *addr)
+{
+	return inet_addr(addr);
+}
+
+static void new_req(int type, int flags, const void *body, size_t len)
+{
+	struct nlmsghdr *hdr = (struct nlmsghdr *)req_buf;
+
+	memset(req_buf, 0, sizeof(req_buf));
+	hdr->nlmsg_len = NLMSG_LENGTH(len);
+	hdr->nlmsg_type = type;
+	hdr->nlmsg_flags = NLM_F_REQUEST | flags;
+	hdr->nlmsg_seq = ++seq;
+	memcpy(NLMSG_DATA(hdr), body, len);
+}
+
+static void add_attr(int type, const void *data, size_t len)
+{
+	struct nlmsghdr *hdr = (struct nlmsghdr *)req_buf;
+	struct rtattr *rta;
+
+	rta = (struct rtattr *)(req_buf + NLMSG_ALIGN(hdr->nlmsg_len));
+	rta->rta_type = type;
+	rta->rta_len = RTA_LENGTH(len);
+	memcpy(RTA_DATA(rta), data, len);
+	hdr->nlmsg_len = NLMSG_ALIGN(hdr->nlmsg_len) + RTA_ALIGN(rta->rta_len);
+}
+
+// Sends the request and returns the error code in the acknowledgment.
+static int transact(void)
+{
+	struct nlmsghdr *hdr = (struct nlmsghdr *)req_buf;
+	struct nlmsghdr *resp = (struct nlmsghdr *)resp_buf;
+	int error;
+
+	hdr->nlmsg_flags |= NLM_F_ACK;
+	if (send(rtnl_fd, req_buf, hdr->nlmsg_len, 0) < 0)
+		return -1;
+	if (recv(rtnl_fd, resp_buf, sizeof(resp_buf), 0) < 0)
+		return -1;
+
+	if (resp->nlmsg_type != NLMSG_ERROR || resp->nlmsg_seq != seq) {
+		errno = EPROTO;
+		return -1;
+	}
+	error = ((struct nlmsgerr *)NLMSG_DATA(resp))->error;
+	if (error != 0) {
+		errno = -error;
+		return -1;
+	}
+	return 0;
+}
+
+// Dumps the objects of `type` and returns the number of them that `match`.
+static int dump_and_count(int type, size_t body_len,
+			  int (*match)(struct nlmsghdr *hdr))
+{
+	struct nlmsghdr *hdr;
+	char body[sizeof(struct ifinfomsg)];
+	ssize_t len;
+	int count = 0;
+
+	memset(body, 0, sizeof(body));
+	body[0] = AF_INET;
+	new_req(type, NLM_F_DUMP, body, body_len);
+	if (send(rtnl_fd, req_buf, NLMSG_LENGTH(body_len), 0) < 0)
+		return -1;
+
+	for (;;) {
+		len = recv(rtnl_fd, resp_buf, sizeof(resp_buf), 0);
+		if (len < 0)
+			return -1;
+
+		for (hdr = (struct nlmsghdr *)resp_buf; NLMSG_OK(hdr, len);
+		     hdr = NLMSG_NEXT(hdr, len)) {
+			if (hdr->nlmsg_type == NLMSG_DONE)
+				return count;
+			if (hdr->nlmsg_type == NLMSG_ERROR) {
+				errno = EPROTO;
+				return -1;
+			}
+			count += match(hdr);
+		}
+	}
+}
+
+// Returns the attribute of `type` in the segment, if any.
+static struct rtattr *find_attr(struct nlmsghdr *hdr, size_t body_len,
+				int type)
+{
+	struct rtattr *rta;
+	int len;
+
+	rta = (struct rtattr *)((char *)NLMSG_DATA(hdr) +
+				NLMSG_ALIGN(body_len));
+	len = hdr->nlmsg_len - NLMSG_LENGTH(NLMSG_ALIGN(body_len));
+	for (; RTA_OK(rta, len); rta = RTA_NEXT(rta, len))
+		if (rta->rta_type == type)
+			return rta;
+	return NULL;
+}
+
+static int attr_is_addr(struct rtattr *rta, const char *addr)
+{
+	return rta != NULL && *(in_addr_t *)RTA_DATA(rta) == to_addr(addr);
+}
+
+// Returns the type of the next message received by the socket.
+static int next_message_type(int fd)
+{
+	struct nlmsghdr *hdr = (struct nlmsghdr *)resp_buf;
+
+	if (recv(fd, resp_buf, sizeof(resp_buf), MSG_DONTWAIT) < 0)
+		return -1;
+	return hdr->nlmsg_type;
+}
+
+/* Links */
+
+static unsigned int link_flags;
+static unsigned int link_mtu;
+
+static int get_link(int index)
+{
+	struct ifinfomsg ifi = { .ifi_family = AF_UNSPEC, .ifi_index = index };
+	struct nlmsghdr *hdr = (struct nlmsghdr *)resp_buf;
+	struct rtattr *rta;
+
+	new_req(RTM_GETLINK, 0, &ifi, sizeof(ifi));
+	if (send(rtnl_fd, req_buf, NLMSG_LENGTH(sizeof(ifi)), 0) < 0)
+		return -1;
+	if (recv(rtnl_fd, resp_buf, sizeof(resp_buf), 0) < 0)
+		return -1;
+	if (hdr->nlmsg_type != RTM_NEWLINK) {
+		errno = EPROTO;
+		return -1;
+	}
+
+	link_flags = ((struct ifinfomsg *)NLMSG_DATA(hdr))->ifi_flags;
+	rta = find_attr(hdr, sizeof(ifi), IFLA_MTU);
+	link_mtu = rta != NULL ? *(unsigned int *)RTA_DATA(rta) : 0;
+	return 0;
+}
+
+static int set_link(int index, unsigned int flags, unsigned int change,
+		    unsigned int mtu)
+{
+	struct ifinfomsg ifi = {
+		.ifi_family = AF_UNSPEC,
+		.ifi_index = index,
+		.ifi_flags = flags,
+		.ifi_change = change,
+	};
+
+	new_req(RTM_NEWLINK, 0, &ifi, sizeof(ifi));
+	if (mtu != 0)
+		add_attr(IFLA_MTU, &mtu, sizeof(mtu));
+	return transact();
+}
+
+FN_TEST(link_up_down)
+{
+	TEST_RES(get_link(lo_index), link_flags & IFF_UP);
+
+	TEST_SUCC(set_link(lo_index, 0, IFF_UP, 0));
+	TEST_RES(get_link(lo_index), !(link_flags & IFF_UP) &&
+					     (link_flags & IFF_LOOPBACK));
+
+	TEST_SUCC(set_link(lo_index, IFF_UP, IFF_UP, 0));
+	TEST_RES(get_link(lo_index), link_flags & IFF_UP);
+
+	TEST_ERRNO(set_link(9999, IFF_UP, IFF_UP, 0), ENODEV);
+}
+END_TEST()
+
+FN_TEST(link_mtu)
+{
+	unsigned int old_mtu;
+
+	TEST_SUCC(get_link(lo_index));
+	old_mtu = link_mtu;
+
+	TEST_SUCC(set_link(lo_index, 0, 0, 1280));
+	TEST_RES(get_link(lo_index), link_mtu == 1280);
+
+	TEST_ERRNO(set_link(lo_index, 0, 0, 10), EINVAL);
+	TEST_RES(get_link(lo_index), link_mtu == 1280);
+
+	TEST_SUCC(set_link(lo_index, 0, 0, old_mtu));
+	TEST_RES(get_link(lo_index), link_mtu == old_mtu);
+}
+END_TEST()
+
+/* Addresses */
+
+static int change_addr(int type, int flags, const char *addr, int prefix_len)
+{
+	struct ifaddrmsg ifa = {
+		.ifa_family = AF_INET,
+		.ifa_prefixlen = prefix_len,
+		.ifa_index = lo_index,
+	};
+	in_addr_t in_addr = to_addr(addr);
+
+	new_req(type, flags, &ifa, sizeof(ifa));
+	add_attr(IFA_LOCAL, &in_addr, sizeof(in_addr));
+	add_attr(IFA_ADDRESS, &in_addr, sizeof(in_addr));
+	return transact();
+}
+
+static int match_test_addr(struct nlmsghdr *hdr)
+{
+	struct ifaddrmsg *ifa = NLMSG_DATA(hdr);
+
+	return hdr->nlmsg_type == RTM_NEWADDR && ifa->ifa_prefixlen == 24 &&
+	       ifa->ifa_index == lo_index &&
+	       attr_is_addr(find_attr(hdr, sizeof(*ifa), IFA_LOCAL),
+			    TEST_ADDR);
+}
+
+FN_TEST(addr)
+{
+	struct sockaddr_nl addr = {
+		.nl_family = AF_NETLINK,
+		.nl_groups = RTMGRP_IPV4_IFADDR,
+	};
+	int mon_fd;
+
+	mon_fd = TEST_SUCC(socket(AF_NETLINK, SOCK_RAW, NETLINK_ROUTE));
+	TEST_SUCC(bind(mon_fd, (struct sockaddr *)&addr, sizeof(addr)));
+
+	TEST_RES(dump_and_count(RTM_GETADDR, sizeof(struct ifaddrmsg),
+				match_test_addr),
+		 _ret == 0);
+
+	TEST_SUCC(change_addr(RTM_NEWADDR, NLM_F_CREATE | NLM_F_EXCL,
+			      TEST_ADDR, 24));
+	TEST_RES(next_message_type(mon_fd), _ret == RTM_NEWADDR);
+	TEST_RES(dump_and_count(RTM_GETADDR, sizeof(struct ifaddrmsg),
+				match_test_addr),
+		 _ret == 1);
+
+	TEST_ERRNO(change_addr(RTM_NEWADDR, NLM_F_CREATE | NLM_F_EXCL,
+			       TEST_ADDR, 24),
+		   EEXIST);
+	TEST_ERRNO(change_addr(RTM_NEWADDR, NLM_F_CREATE, TEST_ADDR, 33),
+		   EINVAL);
+
+	TEST_SUCC(change_addr(RTM_DELADDR, 0, TEST_ADDR, 24));
+	TEST_RES(next_message_type(mon_fd), _ret == RTM_DELADDR);
+	TEST_RES(dump_and_count(RTM_GETADDR, sizeof(struct ifaddrmsg),
+				match_test_addr),
+		 _ret == 0);
+
+	TEST_ERRNO(change_addr(RTM_DELADDR, 0, TEST_ADDR, 24), EADDRNOTAVAIL);
+	TEST_ERRNO(next_message_type(mon_fd), EAGAIN);
+
+	TEST_SUCC(close(mon_fd));
+}
+END_TEST()
+
+/* Routes */
+
+static int change_route(int type, int flags, const char *gateway)
+{
+	struct rtmsg rtm = {
+		.rtm_family = AF_INET,
+		.rtm_dst_len = 16,
+		.rtm_table = RT_TABLE_MAIN,
+		.rtm_protocol = RTPROT_BOOT,
+		.rtm_scope = RT_SCOPE_UNIVERSE,
+		.rtm_type = RTN_UNICAST,
+	};
+	in_addr_t dst = to_addr(TEST_ROUTE_DST);
+	in_addr_t gw = to_addr(gateway);
+
+	new_req(type, flags, &rtm, sizeof(rtm));
+	add_attr(RTA_DST, &dst, sizeof(dst));
+	add_attr(RTA_GATEWAY, &gw, sizeof(gw));
+	return transact();
+}
+
+static int match_test_route(struct nlmsghdr *hdr)
+{
+	struct rtmsg *rtm = NLMSG_DATA(hdr);
+	struct rtattr *oif;
+
+	oif = find_attr(hdr, sizeof(*rtm), RTA_OIF);
+	return hdr->nlmsg_type == RTM_NEWROUTE && rtm->rtm_dst_len == 16 &&
+	       attr_is_addr(find_attr(hdr, sizeof(*rtm), RTA_DST),
+			    TEST_ROUTE_DST) &&
+	       attr_is_addr(find_attr(hdr, sizeof(*rtm), RTA_GATEWAY),
+			    TEST_GATEWAY) &&
+	       oif != NULL && *(int *)RTA_DATA(oif) == eth0_index;
+}
+
+FN_TEST(route)
+{
+	struct sockaddr_nl addr;
+	socklen_t addrlen = sizeof(addr);
+	int group = RTNLGRP_IPV4_ROUTE;
+	int mon_fd;
+
+	mon_fd = TEST_SUCC(socket(AF_NETLINK, SOCK_RAW, NETLINK_ROUTE));
+	TEST_SUCC(setsockopt(mon_fd, SOL_NETLINK, NETLINK_ADD_MEMBERSHIP,
+			     &group, sizeof(group)));
+	TEST_RES(getsockname(mon_fd, (struct sockaddr *)&addr, &addrlen),
+		 addr.nl_groups == RTMGRP_IPV4_ROUTE);
+
+	TEST_RES(dump_and_count(RTM_GETROUTE, sizeof(struct rtmsg),
+				match_test_route),
+		 _ret == 0);
+
+	TEST_SUCC(change_route(RTM_NEWROUTE, NLM_F_CREATE | NLM_F_EXCL,
+			       TEST_GATEWAY));
+	TEST_RES(next_message_type(mon_fd), _ret == RTM_NEWROUTE);
+	TEST_RES(dump_and_count(RTM_GETROUTE, sizeof(struct rtmsg),
+				match_test_route),
+		 _ret == 1);
+
+	TEST_ERRNO(change_route(RTM_NEWROUTE, NLM_F_CREATE | NLM_F_EXCL,
+				TEST_GATEWAY),
+		   EEXIST);
+	TEST_ERRNO(change_route(RTM_NEWROUTE, NLM_F_CREATE, "10.253.0.1"),
+		   ENETUNREACH);
+
+	TEST_SUCC(change_route(RTM_DELROUTE, 0, TEST_GATEWAY));
+	TEST_RES(next_message_type(mon_fd), _ret == RTM_DELROUTE);
+	TEST_RES(dump_and_count(RTM_GETROUTE, sizeof(struct rtmsg),
+				match_test_route),
+		 _ret == 0);
+
+	TEST_ERRNO(change_route(RTM_DELROUTE, 0, TEST_GATEWAY), ESRCH);
+
+	TEST_SUCC(close(mon_fd));
+}
+END_TEST()
+
+/* Neighbors */
+
+static const unsigned char test_lladdr[6] = { 0x52, 0x54, 0x00,
+					      0x12, 0x34, 0x99 };
+
+static int add_neigh(int flags)
+{
+	struct ndmsg ndm = {
+		.ndm_family = AF_INET,
+		.ndm_ifindex = eth0_index,
+		.ndm_state = NUD_PERMANENT,
+	};
+	in_addr_t dst = to_addr(TEST_NEIGH);
+
+	new_req(RTM_NEWNEIGH, flags, &ndm, sizeof(ndm));
+	add_attr(NDA_DST, &dst, sizeof(dst));
+	add_attr(NDA_LLADDR, test_lladdr, sizeof(test_lladdr));
+	return transact();
+}
+
+static int match_test_neigh(struct nlmsghdr *hdr)
+{
+	struct ndmsg *ndm = NLMSG_DATA(hdr);
+	struct rtattr *lladdr;
+
+	lladdr = find_attr(hdr, sizeof(*ndm), NDA_LLADDR);
+	return hdr->nlmsg_type == RTM_NEWNEIGH &&
+	       ndm->ndm_ifindex == eth0_index &&
+	       attr_is_addr(find_attr(hdr, sizeof(*ndm), NDA_DST),
+			    TEST_NEIGH) &&
+	       lladdr != NULL &&
+	       memcmp(RTA_DATA(lladdr), test_lladdr, 6) == 0;
+}
+
+FN_TEST(neigh)
+{
+	TEST_ERRNO(add_neigh(0), ENOENT);
+
+	TEST_SUCC(add_neigh(NLM_F_CREATE | NLM_F_EXCL));
+	TEST_RES(dump_and_count(RTM_GETNEIGH, sizeof(struct ndmsg),
+				match_test_neigh),
+		 _ret == 1);
+
+	TEST_ERRNO(add_neigh(NLM_F_CREATE | NLM_F_EXCL), EEXIST);
+	TEST_SUCC(add_neigh(NLM_F_CREATE | NLM_F_REPLACE));
+}
+END_TEST()
+
+FN_SETUP(cleanup)
+{
+	CHECK(close(rtnl_fd));
+}
+END_SETUP()
@@ -0,0 +1,398 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <sys/socket.h>
+#include <sys/un.h>
+#include <sys/poll.h>
+#include <signal.h>
+#include <unistd.h>
+#include <stddef.h>
+#include <string.h>
+
+#include "../test.h"
+
+#define PATH_OFFSET offsetof(struct sockaddr_un, sun_path)
+
+#define UNIX_ADDR(path) \
+	((struct sockaddr_un){ .sun_family = AF_UNIX, .sun_path = path })
+
+#define BOUND_ADDR UNIX_ADDR("/tmp/D0")
+#define BOUND_ADDRLEN (PATH_OFFSET + 8)
+
+#define BOUND_ADDR2 UNIX_ADDR("\0D1")
+#define BOUND_ADDRLEN2 (PATH_OFFSET + 3)
+
+#define MISSING_ADDR UNIX_ADDR("/tmp/D2")
+#define MISSING_ADDRLEN (PATH_OFFSET + 8)
+
+static int sk_unbound;
+static int sk_bound;
+static int sk_bound2;
+
+FN_SETUP(general)
+{
+	signal(SIGPIPE, SIG_IGN);
+
+	sk_unbound = CHECK(socket(PF_UNIX, SOCK_DGRAM | SOCK_NONBLOCK, 0));
+
+	sk_bound = CHECK(socket(PF_UNIX, SOCK_DGRAM | SOCK_NONBLOCK, 0));
+	CHECK(bind(sk_bound, (struct sockaddr *)&BOUND_ADDR, BOUND_ADDRLEN));
+
+	sk_bound2 = CHECK(socket(PF_UNIX, SOCK_DGRAM | SOCK_NONBLOCK, 0));
+	CHECK(bind(sk_bound2, (struct sockaddr *)&BOUND_ADDR2, BOUND_ADDRLEN2));
+}
+END_SETUP()
+
+FN_TEST(bind)
+{
+	int sk;
+
+	sk = TEST_SUCC(socket(PF_UNIX, SOCK_DGRAM, 0));
+
+	TEST_ERRNO(bind(sk, (struct sockaddr *)&BOUND_ADDR, BOUND_ADDRLEN),
+		   EADDRINUSE);
+	TEST_ERRNO(bind(sk, (struct sockaddr *)&BOUND_ADDR2, BOUND_ADDRLEN2),
+		   EADDRINUSE);
+
+	TEST_SUCC(close(sk));
+
+	TEST_ERRNO(bind(sk_bound, (struct sockaddr *)&BOUND_ADDR2,
+			BOUND_ADDRLEN2),
+		   EINVAL);
+}
+END_TEST()
+
+FN_TEST(getsockname)
+{
+	struct sockaddr_un addr;
+	socklen_t addrlen;
+
+	addrlen = sizeof(addr);
+	TEST_RES(getsockname(sk_unbound, (struct sockaddr *)&addr, &addrlen),
+		 addrlen == PATH_OFFSET);
+
+	addrlen = sizeof(addr);
+	TEST_RES(getsockname(sk_bound, (struct sockaddr *)&addr, &addrlen),
+		 addrlen == BOUND_ADDRLEN &&
+			 memcmp(&addr, &BOUND_ADDR, BOUND_ADDRLEN) == 0);
+
+	addrlen = sizeof(addr);
+	TEST_ERRNO(getpeername(sk_bound, (struct sockaddr *)&addr, &addrlen),
+		   ENOTCONN);
+}
+END_TEST()
+
+FN_TEST(sendto)
+{
+	char buf[1] = { 'z' };
+
+	TEST_ERRNO(send(sk_unbound, buf, 1, 0), ENOTCONN);
+	TEST_ERRNO(sendto(sk_unbound, buf, 1, 0,
+			  (struct sockaddr *)&MISSING_ADDR, MISSING_ADDRLEN),
+		   ENOENT);
+	TEST_ERRNO(sendto(sk_unbound, buf, 1, 0,
+			  (struct sockaddr *)&UNIX_ADDR("\0D2"),
+			  PATH_OFFSET + 3),
+		   ECONNREFUSED);
+
+	TEST_ERRNO(recv(sk_bound, buf, 1, 0), EAGAIN);
+}
+END_TEST()
+
+FN_TEST(message_boundaries)
+{
+	char buf[8];
+	struct sockaddr_un addr;
+	socklen_t addrlen;
+
+	TEST_RES(sendto(sk_unbound, "abc", 3, 0, (struct sockaddr *)&BOUND_ADDR,
+			BOUND_ADDRLEN),
+		 _ret == 3);
+	TEST_RES(sendto(sk_bound2, "defgh", 5, 0,
+			(struct sockaddr *)&BOUND_ADDR, BOUND_ADDRLEN),
+		 _ret == 5);
+	TEST_RES(sendto(sk_bound2, "", 0, 0, (struct sockaddr *)&BOUND_ADDR,
+			BOUND_ADDRLEN),
+		 _ret == 0);
+	TEST_RES(sendto(sk_bound2, "ijklmn", 6, 0,
+			(struct sockaddr *)&BOUND_ADDR, BOUND_ADDRLEN),
+		 _ret == 6);
+
+	// No address is reported if the sender is unnamed.
+	addrlen = sizeof(addr);
+	TEST_RES(recvfrom(sk_bound, buf, sizeof(buf), 0,
+			  (struct sockaddr *)&addr, &addrlen),
+		 _ret == 3 && memcmp(buf, "abc", 3) == 0 && addrlen == 0);
+
+	// The rest of the message is discarded.
+	addrlen = sizeof(addr);
+	TEST_RES(recvfrom(sk_bound, buf, 2, 0, (struct sockaddr *)&addr,
+			  &addrlen),
+		 _ret == 2 && memcmp(buf, "de", 2) == 0 &&
+			 addrlen == BOUND_ADDRLEN2 &&
+			 memcmp(&addr, &BOUND_ADDR2, BOUND_ADDRLEN2) == 0);
+
+	TEST_RES(recv(sk_bound, buf, sizeof(buf), 0), _ret == 0);
+
+	TEST_RES(recv(sk_bound, buf, 2, MSG_PEEK),
+		 _ret == 2 && memcmp(buf, "ij", 2) == 0);
+	TEST_RES(recv(sk_bound, buf, 2, MSG_PEEK | MSG_TRUNC),
+		 _ret == 6 && memcmp(buf, "ij", 2) == 0);
+	TEST_RES(recv(sk_bound, buf, 2, MSG_TRUNC),
+		 _ret == 6 && memcmp(buf, "ij", 2) == 0);
+
+	TEST_ERRNO(recv(sk_bound, buf, sizeof(buf), 0), EAGAIN);
+}
+END_TEST()
+
+FN_TEST(poll)
+{
+	struct pollfd pfd = { .fd = sk_bound, .events = POLLIN | POLLOUT };
+
+	TEST_RES(poll(&pfd, 1, 0), _ret == 1 && pfd.revents == POLLOUT);
+
+	TEST_RES(sendto(sk_unbound, "a", 1, 0, (struct sockaddr *)&BOUND_ADDR,
+			BOUND_ADDRLEN),
+		 _ret == 1);
+	TEST_RES(poll(&pfd, 1, 0),
+		 _ret == 1 && pfd.revents == (POLLIN | POLLOUT));
+
+	TEST_RES(recv(sk_bound, NULL, 0, 0), _ret == 0);
+	TEST_RES(poll(&pfd, 1, 0), _ret == 1 && pfd.revents == POLLOUT);
+}
+END_TEST()
+
+FN_TEST(connect)
+{
+	int sk;
+	char buf[8];
+	struct sockaddr_un addr;
+	socklen_t addrlen;
+
+	sk = TEST_SUCC(socket(PF_UNIX, SOCK_DGRAM | SOCK_NONBLOCK, 0));
+
+	TEST_ERRNO(connect(sk, (struct sockaddr *)&MISSING_ADDR,
+			   MISSING_ADDRLEN),
+		   ENOENT);
+	TEST_SUCC(connect(sk, (struct sockaddr *)&BOUND_ADDR2, BOUND_ADDRLEN2));
+
+	addrlen = sizeof(addr);
+	TEST_RES(getpeername(sk, (struct sockaddr *)&addr, &addrlen),
+		 addrlen == BOUND_ADDRLEN2 &&
+			 memcmp(&addr, &BOUND_ADDR2, BOUND_ADDRLEN2) == 0);
+
+	TEST_RES(send(sk, "abc", 3, 0), _ret == 3);
+	TEST_RES(recv(sk_bound2, buf, sizeof(buf), 0), _ret == 3);
+
+	// An explicit destination overrides the connected one.
+	TEST_RES(sendto(sk, "de", 2, 0, (struct sockaddr *)&BOUND_ADDR,
+			BOUND_ADDRLEN),
+		 _ret == 2);
+	TEST_RES(recv(sk_bound, buf, sizeof(buf), 0), _ret == 2);
+
+	// Once connected, only the peer can send messages to the socket.
+	TEST_SUCC(bind(sk, (struct sockaddr *)&UNIX_ADDR("\0D3"),
+		       PATH_OFFSET + 3));
+	TEST_ERRNO(sendto(sk_unbound, "f", 1, 0,
+			  (struct sockaddr *)&UNIX_ADDR("\0D3"),
+			  PATH_OFFSET + 3),
+		   EPERM);
+	TEST_RES(sendto(sk_bound2, "g", 1, 0,
+			(struct sockaddr *)&UNIX_ADDR("\0D3"), PATH_OFFSET + 3),
+		 _ret == 1);
+	TEST_RES(recv(sk, buf, sizeof(buf), 0), _ret == 1 && buf[0] == 'g');
+
+	TEST_SUCC(close(sk));
+}
+END_TEST()
+
+FN_TEST(peer_closed)
+{
+	int sk, sk_peer;
+
+	sk_peer = TEST_SUCC(socket(PF_UNIX, SOCK_DGRAM, 0));
+	TEST_SUCC(bind(sk_peer, (struct sockaddr *)&UNIX_ADDR("\0D4"),
+		       PATH_OFFSET + 3));
+
+	sk = TEST_SUCC(socket(PF_UNIX, SOCK_DGRAM, 0));
+	TEST_SUCC(connect(sk, (struct sockaddr *)&UNIX_ADDR("\0D4"),
+			  PATH_OFFSET + 3));
+
+	TEST_SUCC(close(sk_peer));
+	TEST_ERRNO(send(sk, "a", 1, 0), ECONNREFUSED);
+
+	TEST_SUCC(close(sk));
+}
+END_TEST()
+
+FN_TEST(queue_full)
+{
+	int sk, i;
+	char buf[1] = { 'z' };
+	static char large_buf[1 << 20];
+
+	sk = TEST_SUCC(socket(PF_UNIX, SOCK_DGRAM | SOCK_NONBLOCK, 0));
+	TEST_SUCC(connect(sk, (struct sockaddr *)&BOUND_ADDR, BOUND_ADDRLEN));
+
+	TEST_ERRNO(send(sk, large_buf, sizeof(large_buf), 0), EMSGSIZE);
+
+	for (i = 0; i < 11; ++i)
+		TEST_RES(send(sk, buf, 1, 0), _ret == 1);
+	TEST_ERRNO(send(sk, buf, 1, 0), EAGAIN);
+
+	for (i = 0; i < 11; ++i)
+		TEST_RES(recv(sk_bound, buf, 1, 0), _ret == 1);
+	TEST_ERRNO(recv(sk_bound, buf, 1, 0), EAGAIN);
+
+	TEST_SUCC(close(sk));
+}
+END_TEST()
+
+FN_TEST(shutdown)
+{
+	int sk, sk_peer;
+	char buf[1] = { 'z' };
+
+	sk_peer = TEST_SUCC(socket(PF_UNIX, SOCK_DGRAM, 0));
+	TEST_SUCC(bind(sk_peer, (struct sockaddr *)&UNIX_ADDR("\0D5"),
+		       PATH_OFFSET + 3));
+
+	sk = TEST_SUCC(socket(PF_UNIX, SOCK_DGRAM, 0));
+	TEST_SUCC(connect(sk, (struct sockaddr *)&UNIX_ADDR("\0D5"),
+			  PATH_OFFSET + 3));
+
+	TEST_SUCC(shutdown(sk_peer, SHUT_RD));
+	TEST_ERRNO(send(sk, buf, 1, 0), EPIPE);
+	TEST_RES(recv(sk_peer, buf, 1, 0), _ret == 0);
+
+	TEST_SUCC(shutdown(sk, SHUT_WR));
+	TEST_ERRNO(send(sk, buf, 1, 0), EPIPE);
+
+	TEST_SUCC(close(sk));
+	TEST_SUCC(close(sk_peer));
+}
+END_TEST()
+
+FN_TEST(socketpair)
+{
+	int sv[2];
+	char buf[8];
+	struct ucred cred;
+	socklen_t len;
+
+	TEST_SUCC(socketpair(PF_UNIX, SOCK_DGRAM | SOCK_NONBLOCK, 0, sv));
+
+	TEST_RES(send(sv[0], "abc", 3, 0), _ret == 3);
+	TEST_RES(send(sv[0], "de", 2, 0), _ret == 2);
+	TEST_RES(send(sv[1], "f", 1, 0), _ret == 1);
+
+	TEST_RES(recv(sv[1], buf, sizeof(buf), 0),
+		 _ret == 3 && memcmp(buf, "abc", 3) == 0);
+	TEST_RES(recv(sv[1], buf, sizeof(buf), 0),
+		 _ret == 2 && memcmp(buf, "de", 2) == 0);
+	TEST_RES(recv(sv[0], buf, sizeof(buf), 0),
+		 _ret == 1 && buf[0] == 'f');
+
+	len = sizeof(cred);
+	TEST_RES(getsockopt(sv[0], SOL_SOCKET, SO_PEERCRED, &cred, &len),
+		 len == sizeof(cred) && cred.pid == getpid() &&
+			 cred.uid == getuid() && cred.gid == getgid());
+
+	TEST_SUCC(close(sv[0]));
+	TEST_SUCC(close(sv[1]));
+}
+END_TEST()
+
+FN_TEST(scm_rights)
+{
+	int sv[2], fds[2];
+	char buf[1] = { 'z' };
+	char cbuf[CMSG_SPACE(sizeof(int))];
+	struct iovec iov = { .iov_base = buf, .iov_len = 1 };
+	struct msghdr mhdr;
+	struct cmsghdr *chdr;
+	int fd;
+
+	TEST_SUCC(socketpair(PF_UNIX, SOCK_DGRAM, 0, sv));
+	TEST_SUCC(pipe(fds));
+
+	memset(&mhdr, 0, sizeof(mhdr));
+	mhdr.msg_iov = &iov;
+	mhdr.msg_iovlen = 1;
+	mhdr.msg_control = cbuf;
+	mhdr.msg_controllen = sizeof(cbuf);
+	chdr = CMSG_FIRSTHDR(&mhdr);
+	chdr->cmsg_level = SOL_SOCKET;
+	chdr->cmsg_type = SCM_RIGHTS;
+	chdr->cmsg_len = CMSG_LEN(sizeof(int));
+	memcpy(CMSG_DATA(chdr), &fds[1], sizeof(int));
+
+	TEST_RES(sendmsg(sv[0], &mhdr, 0), _ret == 1);
+	TEST_SUCC(close(fds[1]));
+
+	memset(cbuf, 0, sizeof(cbuf));
+	mhdr.msg_controllen = sizeof(cbuf);
+	TEST_RES(recvmsg(sv[1], &mhdr, 0),
+		 _ret == 1 && (chdr = CMSG_FIRSTHDR(&mhdr)) &&
+			 chdr->cmsg_level == SOL_SOCKET &&
+			 chdr->cmsg_type == SCM_RIGHTS &&
+			 chdr->cmsg_len == CMSG_LEN(sizeof(int)));
+
+	memcpy(&fd, CMSG_DATA(chdr), sizeof(int));
+	TEST_RES(write(fd, "a", 1), _ret == 1);
+	TEST_RES(read(fds[0], buf, 1), _ret == 1 && buf[0] == 'a');
+
+	TEST_SUCC(close(fd));
+	TEST_SUCC(close(fds[0]));
+	TEST_SUCC(close(sv[0]));
+	TEST_SUCC(close(sv[1]));
+}
+END_TEST()
+
+FN_TEST(scm_credentials)
+{
+	int sv[2];
+	int one = 1;
+	char buf[1] = { 'z' };
+	char cbuf[CMSG_SPACE(sizeof(struct ucred))];
+	struct iovec iov = { .iov_base = buf, .iov_len = 1 };
+	struct msghdr mhdr;
+	struct cmsghdr *chdr;
+	struct ucred cred;
+
+	TEST_SUCC(socketpair(PF_UNIX, SOCK_DGRAM, 0, sv));
+	TEST_SUCC(setsockopt(sv[1], SOL_SOCKET, SO_PASSCRED, &one,
+			     sizeof(one)));
+
+	TEST_RES(send(sv[0], buf, 1, 0), _ret == 1);
+
+	memset(&mhdr, 0, sizeof(mhdr));
+	mhdr.msg_iov = &iov;
+	mhdr.msg_iovlen = 1;
+	mhdr.msg_control = cbuf;
+	mhdr.msg_controllen = sizeof(cbuf);
+	TEST_RES(recvmsg(sv[1], &mhdr, 0),
+		 _ret == 1 && (chdr = CMSG_FIRSTHDR(&mhdr)) &&
+			 chdr->cmsg_level == SOL_SOCKET &&
+			 chdr->cmsg_type == SCM_CREDENTIALS &&
+			 chdr->cmsg_len == CMSG_LEN(sizeof(struct ucred)));
+
+	memcpy(&cred, CMSG_DATA(chdr), sizeof(cred));
+	TEST_RES(0, cred.pid == getpid() && cred.uid == getuid() &&
+			    cred.gid == getgid());
+
+	TEST_SUCC(close(sv[0]));
+	TEST_SUCC(close(sv[1]));
+}
+END_TEST()
+
+FN_SETUP(cleanup)
+{
+	CHECK(close(sk_unbound));
+	CHECK(close(sk_bound));
+	CHECK(close(sk_bound2));
+
+	CHECK(unlink(BOUND_ADDR.sun_path));
+}
+END_SETUP()
@@ -0,0 +1,229 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <fcntl.h>
+#include <string.h>
+#include <unistd.h>
+#include <sys/stat.h>
+#include <sys/uio.h>
+
+#include "../test.h"
+
+#define PAGE_SIZE 4096
+#define FILE_SIZE (3 * PAGE_SIZE + 100)
+
+#define SRC_PATH "/tmp/splice_src"
+#define DST_PATH "/tmp/splice_dst"
+
+static char data[FILE_SIZE];
+static char buf[FILE_SIZE];
+
+static int src_fd, dst_fd;
+
+FN_SETUP(init)
+{
+	int i;
+
+	for (i = 0; i < FILE_SIZE; i++)
+		data[i] = 'a' + i % 26;
+
+	src_fd = CHECK(open(SRC_PATH, O_RDWR | O_CREAT | O_TRUNC, 0644));
+	CHECK_WITH(write(src_fd, data, FILE_SIZE), _ret == FILE_SIZE);
+	dst_fd = CHECK(open(DST_PATH, O_RDWR | O_CREAT | O_TRUNC, 0644));
+}
+END_SETUP()
+
+FN_TEST(invalid_args)
+{
+	struct iovec iov = { .iov_base = buf, .iov_len = 1 };
+	int fds[2], fds2[2];
+	loff_t off = 0;
+
+	TEST_SUCC(pipe(fds));
+	TEST_SUCC(pipe(fds2));
+
+	TEST_ERRNO(splice(src_fd, NULL, dst_fd, NULL, 1, 0), EINVAL);
+	TEST_ERRNO(splice(src_fd, NULL, fds[1], NULL, 1, 0x10), EINVAL);
+	TEST_ERRNO(splice(fds[0], &off, dst_fd, NULL, 1, 0), ESPIPE);
+	TEST_ERRNO(splice(src_fd, NULL, fds[1], &off, 1, 0), ESPIPE);
+	TEST_ERRNO(splice(fds[1], NULL, dst_fd, NULL, 1, 0), EBADF);
+	TEST_ERRNO(splice(fds[0], NULL, fds[1], NULL, 1, 0), EINVAL);
+	TEST_ERRNO(splice(fds[0], NULL, fds2[1], NULL, 1, SPLICE_F_NONBLOCK),
+		   EAGAIN);
+
+	TEST_ERRNO(tee(fds[0], dst_fd, 1, 0), EINVAL);
+	TEST_ERRNO(tee(fds[0], fds[1], 1, 0), EINVAL);
+	TEST_ERRNO(tee(fds[0], fds2[1], 1, SPLICE_F_NONBLOCK), EAGAIN);
+
+	TEST_ERRNO(vmsplice(src_fd, &iov, 1, 0), EBADF);
+
+	// The end-of-file is reached after the write end is closed.
+	TEST_SUCC(close(fds[1]));
+	TEST_RES(splice(fds[0], NULL, dst_fd, NULL, 1, 0), _ret == 0);
+	TEST_RES(tee(fds[0], fds2[1], 1, 0), _ret == 0);
+
+	TEST_SUCC(close(fds[0]));
+	TEST_SUCC(close(fds2[0]));
+	TEST_SUCC(close(fds2[1]));
+}
+END_TEST()
+
+FN_TEST(splice_file)
+{
+	int fds[2];
+	loff_t off_in = 10, off_out = 0;
+
+	TEST_SUCC(pipe(fds));
+
+	// Splice with the given offsets, which do not change the file
+	// offsets.
+	TEST_RES(splice(src_fd, &off_in, fds[1], NULL, FILE_SIZE, 0),
+		 _ret == FILE_SIZE - 10 && off_in == FILE_SIZE);
+	TEST_RES(splice(fds[0], NULL, dst_fd, &off_out, FILE_SIZE, 0),
+		 _ret == FILE_SIZE - 10 && off_out == FILE_SIZE - 10);
+	TEST_RES(pread(dst_fd, buf, FILE_SIZE, 0),
+		 _ret == FILE_SIZE - 10 &&
+			 memcmp(buf, data + 10, FILE_SIZE - 10) == 0);
+	TEST_RES(lseek(src_fd, 0, SEEK_CUR), _ret == FILE_SIZE);
+	TEST_RES(lseek(dst_fd, 0, SEEK_CUR), _ret == 0);
+
+	// Splice with the file offsets.
+	TEST_RES(lseek(src_fd, PAGE_SIZE, SEEK_SET), _ret == PAGE_SIZE);
+	TEST_RES(splice(src_fd, NULL, fds[1], NULL, 100, 0), _ret == 100);
+	TEST_RES(splice(fds[0], NULL, dst_fd, NULL, 100, 0), _ret == 100);
+	TEST_RES(lseek(src_fd, 0, SEEK_CUR), _ret == PAGE_SIZE + 100);
+	TEST_RES(lseek(dst_fd, 0, SEEK_CUR), _ret == 100);
+	TEST_RES(pread(dst_fd, buf, 100, 0),
+		 _ret == 100 && memcmp(buf, data + PAGE_SIZE, 100) == 0);
+
+	// Nothing is spliced at the end of the file.
+	TEST_RES(splice(src_fd, &off_in, fds[1], NULL, 1, 0), _ret == 0);
+
+	TEST_SUCC(close(fds[0]));
+	TEST_SUCC(close(fds[1]));
+}
+END_TEST()
+
+FN_TEST(splice_pipe)
+{
+	int fds[2], fds2[2];
+
+	TEST_SUCC(pipe(fds));
+	TEST_SUCC(pipe(fds2));
+
+	TEST_RES(write(fds[1], data, 100), _ret == 100);
+	TEST_RES(splice(fds[0], NULL, fds2[1], NULL, 30, 0), _ret == 30);
+	TEST_RES(splice(fds[0], NULL, fds2[1], NULL, 100, 0), _ret == 70);
+	TEST_ERRNO(splice(fds[0], NULL, fds2[1], NULL, 1, SPLICE_F_NONBLOCK),
+		   EAGAIN);
+
+	// New data are appended after the spliced data.
+	TEST_RES(write(fds2[1], data + 100, 10), _ret == 10);
+	TEST_RES(read(fds2[0], buf, sizeof(buf)),
+		 _ret == 110 && memcmp(buf, data, 110) == 0);
+
+	TEST_SUCC(close(fds[0]));
+	TEST_SUCC(close(fds[1]));
+	TEST_SUCC(close(fds2[0]));
+	TEST_SUCC(close(fds2[1]));
+}
+END_TEST()
+
+FN_TEST(tee)
+{
+	int fds[2], fds2[2];
+
+	TEST_SUCC(pipe(fds));
+	TEST_SUCC(pipe(fds2));
+
+	TEST_RES(write(fds[1], data, 100), _ret == 100);
+	TEST_RES(tee(fds[0], fds2[1], 200, 0), _ret == 100);
+
+	// The data are duplicated instead of being consumed.
+	TEST_RES(read(fds2[0], buf, sizeof(buf)),
+		 _ret == 100 && memcmp(buf, data, 100) == 0);
+	TEST_RES(read(fds[0], buf, sizeof(buf)),
+		 _ret == 100 && memcmp(buf, data, 100) == 0);
+
+	TEST_SUCC(close(fds[0]));
+	TEST_SUCC(close(fds[1]));
+	TEST_SUCC(close(fds2[0]));
+	TEST_SUCC(close(fds2[1]));
+}
+END_TEST()
+
+FN_TEST(vmsplice)
+{
+	int fds[2];
+	struct iovec iov[2] = {
+		{ .iov_base = data, .iov_len = 10 },
+		{ .iov_base = data + 20, .iov_len = 10 },
+	};
+	struct iovec out_iov = { .iov_base = buf, .iov_len = 15 };
+
+	TEST_SUCC(pipe(fds));
+
+	TEST_RES(vmsplice(fds[1], iov, 2, 0), _ret == 20);
+	TEST_RES(vmsplice(fds[0], &out_iov, 1, 0),
+		 _ret == 15 && memcmp(buf, data, 10) == 0 &&
+			 memcmp(buf + 10, data + 20, 5) == 0);
+	TEST_RES(read(fds[0], buf, sizeof(buf)),
+		 _ret == 5 && memcmp(buf, data + 25, 5) == 0);
+
+	TEST_SUCC(close(fds[0]));
+	TEST_SUCC(close(fds[1]));
+}
+END_TEST()
+
+FN_TEST(copy_file_range)
+{
+	loff_t off_in = 0, off_out = 0;
+	int dir_fd;
+
+	TEST_ERRNO(copy_file_range(src_fd, NULL, dst_fd, NULL, 1, 1), EINVAL);
+	dir_fd = TEST_SUCC(open("/tmp", O_RDONLY | O_DIRECTORY));
+	TEST_ERRNO(copy_file_range(dir_fd, NULL, dst_fd, NULL, 1, 0), EISDIR);
+	TEST_SUCC(close(dir_fd));
+	off_out = 10;
+	TEST_ERRNO(copy_file_range(src_fd, &off_in, src_fd, &off_out, 20, 0),
+		   EINVAL);
+
+	TEST_SUCC(ftruncate(dst_fd, 0));
+
+	// Copy with the given offsets.
+	off_in = 1;
+	off_out = 0;
+	TEST_RES(copy_file_range(src_fd, &off_in, dst_fd, &off_out,
+				 2 * FILE_SIZE, 0),
+		 _ret == FILE_SIZE - 1 && off_in == FILE_SIZE &&
+			 off_out == FILE_SIZE - 1);
+	TEST_RES(pread(dst_fd, buf, FILE_SIZE, 0),
+		 _ret == FILE_SIZE - 1 &&
+			 memcmp(buf, data + 1, FILE_SIZE - 1) == 0);
+
+	// Copy with the file offsets.
+	TEST_RES(lseek(src_fd, PAGE_SIZE - 1, SEEK_SET), _ret == PAGE_SIZE - 1);
+	TEST_RES(lseek(dst_fd, 2, SEEK_SET), _ret == 2);
+	TEST_RES(copy_file_range(src_fd, NULL, dst_fd, NULL, 2, 0), _ret == 2);
+	TEST_RES(lseek(src_fd, 0, SEEK_CUR), _ret == PAGE_SIZE + 1);
+	TEST_RES(lseek(dst_fd, 0, SEEK_CUR), _ret == 4);
+	TEST_RES(pread(dst_fd, buf, 5, 0),
+		 _ret == 5 && buf[0] == data[1] && buf[1] == data[2] &&
+			 buf[2] == data[PAGE_SIZE - 1] &&
+			 buf[3] == data[PAGE_SIZE] && buf[4] == data[5]);
+
+	// Nothing is copied at the end of the file.
+	TEST_RES(copy_file_range(src_fd, &off_in, dst_fd, NULL, 1, 0),
+		 _ret == 0);
+}
+END_TEST()
+
+FN_SETUP(cleanup)
+{
+	CHECK(close(src_fd));
+	CHECK(close(dst_fd));
+	CHECK(unlink(SRC_PATH));
+	CHECK(unlink(DST_PATH));
+}
+END_SETUP()
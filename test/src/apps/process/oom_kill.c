@@ -0,0 +1,129 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <fcntl.h>
+#include <signal.h>
+#include <stdio.h>
+#include <unistd.h>
+#include <sys/mman.h>
+#include <sys/wait.h>
+
+#include "../test.h"
+
+#define OOM_SCORE_ADJ_MIN -1000
+#define OOM_SCORE_ADJ_MAX 1000
+
+// A size that exceeds the memory and the swap space of the test machines.
+#define HUGE_SIZE (1UL << 40)
+
+static char buf[64];
+
+static long read_long(const char *path)
+{
+	ssize_t len;
+	int fd;
+
+	fd = CHECK(open(path, O_RDONLY));
+	len = CHECK(read(fd, buf, sizeof(buf) - 1));
+	CHECK(close(fd));
+	buf[len] = '\0';
+	return atol(buf);
+}
+
+static ssize_t write_file(const char *path, const char *value)
+{
+	ssize_t len;
+	int fd;
+
+	fd = CHECK(open(path, O_WRONLY));
+	len = write(fd, value, strlen(value));
+	CHECK(close(fd));
+	return len;
+}
+
+static void set_oom_score_adj(int oom_score_adj)
+{
+	char value[16];
+
+	snprintf(value, sizeof(value), "%d", oom_score_adj);
+	CHECK_WITH(write_file("/proc/self/oom_score_adj", value),
+		   _ret == strlen(value));
+}
+
+FN_TEST(oom_score_adj)
+{
+	char path[64];
+	int status;
+	pid_t pid;
+
+	TEST_RES(read_long("/proc/self/oom_score_adj"), _ret == 0);
+	TEST_ERRNO(write_file("/proc/self/oom_score_adj", "-1001"), EINVAL);
+	TEST_ERRNO(write_file("/proc/self/oom_score_adj", "1001"), EINVAL);
+	TEST_ERRNO(write_file("/proc/self/oom_score_adj", "foo"), EINVAL);
+
+	// The processes with the maximum adjustment are killed first, and those
+	// with the minimum adjustment are never killed.
+	TEST_RES(write_file("/proc/self/oom_score_adj", "1000"), _ret == 4);
+	TEST_RES(read_long("/proc/self/oom_score_adj"), _ret == 1000);
+	TEST_RES(read_long("/proc/self/oom_score"), _ret >= 1333);
+	TEST_RES(write_file("/proc/self/oom_score_adj", "-1000"), _ret == 5);
+	TEST_RES(read_long("/proc/self/oom_score"), _ret == 0);
+
+	// The adjustment is inherited by the children.
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		if (read_long("/proc/self/oom_score_adj") != OOM_SCORE_ADJ_MIN)
+			_exit(1);
+		_exit(0);
+	}
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && WIFEXITED(status) && WEXITSTATUS(status) == 0);
+
+	snprintf(path, sizeof(path), "/proc/%d/oom_score_adj", getpid());
+	TEST_RES(read_long(path), _ret == OOM_SCORE_ADJ_MIN);
+}
+END_TEST()
+
+FN_TEST(oom_kill)
+{
+	int status;
+	pid_t bystander, victim;
+	char *addr;
+
+	// This process is never killed, since its adjustment is the minimum one.
+	set_oom_score_adj(OOM_SCORE_ADJ_MIN);
+
+	bystander = TEST_SUCC(fork());
+	if (bystander == 0) {
+		set_oom_score_adj(0);
+		pause();
+		_exit(0);
+	}
+
+	victim = TEST_SUCC(fork());
+	if (victim == 0) {
+		set_oom_score_adj(OOM_SCORE_ADJ_MAX);
+		addr = CHECK_WITH(mmap(NULL, HUGE_SIZE, PROT_READ | PROT_WRITE,
+				       MAP_PRIVATE | MAP_ANONYMOUS |
+					       MAP_NORESERVE,
+				       -1, 0),
+				  _ret != MAP_FAILED);
+		// Touches the pages until the memory runs out.
+		for (size_t offset = 0; offset < HUGE_SIZE; offset += 4096)
+			addr[offset] = 1;
+		_exit(1);
+	}
+
+	TEST_RES(waitpid(victim, &status, 0),
+		 _ret == victim && WIFSIGNALED(status) &&
+			 WTERMSIG(status) == SIGKILL);
+
+	// The process with the highest badness score is the only one killed.
+	TEST_RES(waitpid(bystander, &status, WNOHANG), _ret == 0);
+	TEST_SUCC(kill(bystander, SIGKILL));
+	TEST_RES(waitpid(bystander, &status, 0),
+		 _ret == bystander && WIFSIGNALED(status) &&
+			 WTERMSIG(status) == SIGKILL);
+}
+END_TEST()
@@ -0,0 +1,185 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <fcntl.h>
+#include <stdio.h>
+#include <unistd.h>
+#include <sys/mman.h>
+#include <sys/resource.h>
+#include <sys/wait.h>
+
+#include "../test.h"
+
+#define NR_PAGES 4
+#define FILE_NAME "/tmp/procfs_pid_test"
+
+extern char **environ;
+
+static char buf[65536];
+static char line[256];
+static long page_size;
+static void *anon_addr;
+
+static ssize_t read_file(const char *path)
+{
+	ssize_t len = 0, ret;
+	int fd;
+
+	fd = CHECK(open(path, O_RDONLY));
+	do {
+		ret = CHECK(read(fd, buf + len, sizeof(buf) - 1 - len));
+		len += ret;
+	} while (ret > 0);
+	CHECK(close(fd));
+	buf[len] = '\0';
+	return len;
+}
+
+// Returns the line of `buf` that describes the mapping containing `addr`.
+static char *find_mapping(void *addr)
+{
+	unsigned long start, end;
+	char *pos;
+
+	for (pos = buf; pos != NULL && *pos != '\0';) {
+		if (sscanf(pos, "%lx-%lx ", &start, &end) == 2 &&
+		    start <= (unsigned long)addr && (unsigned long)addr < end)
+			return pos;
+		pos = strchr(pos, '\n');
+		if (pos != NULL)
+			pos++;
+	}
+	return NULL;
+}
+
+static int mapping_has(void *addr, const char *text)
+{
+	char *pos, *end;
+
+	pos = find_mapping(addr);
+	if (pos == NULL)
+		return 0;
+	end = strchr(pos, '\n');
+	snprintf(line, sizeof(line), "%.*s",
+		 end == NULL ? (int)strlen(pos) : (int)(end - pos), pos);
+	return strstr(line, text) != NULL;
+}
+
+static long mapping_rss(void *addr)
+{
+	char *pos;
+	long rss;
+
+	pos = find_mapping(addr);
+	if (pos == NULL)
+		return -1;
+	pos = strstr(pos, "\nRss:");
+	if (pos == NULL || sscanf(pos, "\nRss: %ld kB", &rss) != 1)
+		return -1;
+	return rss;
+}
+
+FN_SETUP(mmap)
+{
+	page_size = CHECK(sysconf(_SC_PAGESIZE));
+	anon_addr = CHECK_WITH(mmap(NULL, NR_PAGES * page_size,
+				    PROT_READ | PROT_WRITE,
+				    MAP_PRIVATE | MAP_ANONYMOUS, -1, 0),
+			       _ret != MAP_FAILED);
+	memset(anon_addr, 1, NR_PAGES * page_size);
+}
+END_SETUP()
+
+FN_TEST(maps)
+{
+	void *file_addr;
+	int fd;
+
+	fd = TEST_SUCC(open(FILE_NAME, O_CREAT | O_RDWR, 0644));
+	TEST_SUCC(ftruncate(fd, page_size));
+	file_addr = TEST_SUCC(
+		mmap(NULL, page_size, PROT_READ, MAP_SHARED, fd, 0));
+
+	TEST_SUCC(read_file("/proc/self/maps"));
+	TEST_RES(mapping_has(anon_addr, " rw-p "), _ret);
+	TEST_RES(mapping_has(file_addr, " r--s "), _ret);
+	TEST_RES(mapping_has(file_addr, FILE_NAME), _ret);
+	TEST_RES(strstr(buf, "[stack]"), _ret != NULL);
+
+	TEST_SUCC(munmap(file_addr, page_size));
+	TEST_SUCC(close(fd));
+	TEST_SUCC(unlink(FILE_NAME));
+}
+END_TEST()
+
+FN_TEST(smaps)
+{
+	TEST_SUCC(read_file("/proc/self/smaps"));
+	TEST_RES(mapping_rss(anon_addr), _ret >= NR_PAGES * page_size / 1024);
+}
+END_TEST()
+
+FN_TEST(environ)
+{
+	TEST_SUCC(read_file("/proc/self/environ"));
+	TEST_RES(environ[0] == NULL || strcmp(buf, environ[0]) == 0, _ret);
+}
+END_TEST()
+
+FN_TEST(mounts)
+{
+	TEST_SUCC(read_file("/proc/self/mounts"));
+	TEST_RES(strstr(buf, " /proc proc "), _ret != NULL);
+}
+END_TEST()
+
+FN_TEST(limits)
+{
+	struct rlimit rlimit = { .rlim_cur = 100, .rlim_max = 200 };
+
+	TEST_SUCC(setrlimit(RLIMIT_NOFILE, &rlimit));
+	TEST_SUCC(read_file("/proc/self/limits"));
+	TEST_RES(strstr(buf, "Max open files            100                  "
+			     "200                  files"),
+		 _ret != NULL);
+}
+END_TEST()
+
+FN_TEST(cwd_and_root)
+{
+	char path[64];
+
+	TEST_SUCC(chdir("/tmp"));
+	TEST_RES(readlink("/proc/self/cwd", path, sizeof(path)),
+		 _ret == 4 && strncmp(path, "/tmp", 4) == 0);
+	TEST_RES(readlink("/proc/self/root", path, sizeof(path)),
+		 _ret == 1 && path[0] == '/');
+}
+END_TEST()
+
+FN_TEST(oom_score_adj)
+{
+	int status;
+	pid_t pid;
+	int fd;
+
+	fd = TEST_SUCC(open("/proc/self/oom_score_adj", O_WRONLY));
+	TEST_RES(pwrite(fd, "500", 3, 0), _ret == 3);
+	TEST_RES(read_file("/proc/self/oom_score_adj"),
+		 _ret == 4 && strcmp(buf, "500\n") == 0);
+	TEST_ERRNO(pwrite(fd, "2000", 4, 0), EINVAL);
+
+	// The adjustment is inherited by the child process.
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		read_file("/proc/self/oom_score_adj");
+		_exit(strcmp(buf, "500\n") == 0 ? 0 : 1);
+	}
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && WIFEXITED(status) && WEXITSTATUS(status) == 0);
+
+	TEST_RES(pwrite(fd, "0", 1, 0), _ret == 1);
+	TEST_SUCC(close(fd));
+}
+END_TEST()
@@ -0,0 +1,224 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <dirent.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <sys/mman.h>
+#include <sys/socket.h>
+#include <sys/utsname.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#include "../test.h"
+
+#define HOSTNAME_PATH "/proc/sys/kernel/hostname"
+#define FILE_MAX_PATH "/proc/sys/fs/file-max"
+#define OVERCOMMIT_PATH "/proc/sys/vm/overcommit_memory"
+#define TCP_RMEM_PATH "/proc/sys/net/ipv4/tcp_rmem"
+
+static char buf[4096];
+static char old_value[256];
+
+static ssize_t read_file(const char *path)
+{
+	ssize_t len;
+	int fd;
+
+	fd = CHECK(open(path, O_RDONLY));
+	len = CHECK(read(fd, buf, sizeof(buf) - 1));
+	CHECK(close(fd));
+	buf[len] = '\0';
+	return len;
+}
+
+static ssize_t write_file(const char *path, const char *value)
+{
+	ssize_t len;
+	int fd;
+
+	fd = CHECK(open(path, O_WRONLY));
+	len = write(fd, value, strlen(value));
+	CHECK(close(fd));
+	return len;
+}
+
+// Returns the number of integers in the buffer.
+static int count_ints(void)
+{
+	long values[4];
+
+	return sscanf(buf, "%ld %ld %ld %ld", &values[0], &values[1],
+		      &values[2], &values[3]);
+}
+
+static int has_dir_entry(const char *path, const char *name)
+{
+	struct dirent *entry;
+	DIR *dir;
+	int found = 0;
+
+	dir = opendir(path);
+	if (dir == NULL)
+		return 0;
+	while ((entry = readdir(dir)) != NULL)
+		if (strcmp(entry->d_name, name) == 0)
+			found = 1;
+	closedir(dir);
+	return found;
+}
+
+static int tcp_rcvbuf(void)
+{
+	int fd, val;
+	socklen_t len = sizeof(val);
+
+	fd = CHECK(socket(AF_INET, SOCK_STREAM, 0));
+	CHECK(getsockopt(fd, SOL_SOCKET, SO_RCVBUF, &val, &len));
+	CHECK(close(fd));
+	return val;
+}
+
+FN_TEST(directories)
+{
+	TEST_RES(has_dir_entry("/proc/sys", "kernel"), _ret);
+	TEST_RES(has_dir_entry("/proc/sys", "fs"), _ret);
+	TEST_RES(has_dir_entry("/proc/sys", "net"), _ret);
+	TEST_RES(has_dir_entry("/proc/sys", "vm"), _ret);
+	TEST_RES(has_dir_entry("/proc/sys/net/ipv4", "tcp_rmem"), _ret);
+}
+END_TEST()
+
+FN_TEST(read_ints)
+{
+	TEST_SUCC(read_file("/proc/sys/kernel/pid_max"));
+	TEST_RES(count_ints(), _ret == 1);
+	TEST_SUCC(read_file("/proc/sys/kernel/cap_last_cap"));
+	TEST_RES(count_ints(), _ret == 1);
+	TEST_SUCC(read_file("/proc/sys/kernel/printk"));
+	TEST_RES(count_ints(), _ret == 4);
+	TEST_SUCC(read_file(FILE_MAX_PATH));
+	TEST_RES(atol(buf), _ret > 0);
+}
+END_TEST()
+
+FN_TEST(hostname)
+{
+	struct utsname uts;
+
+	TEST_SUCC(read_file(HOSTNAME_PATH));
+	strcpy(old_value, buf);
+
+	TEST_RES(write_file(HOSTNAME_PATH, "sysctl-test\n"), _ret == 12);
+	TEST_SUCC(uname(&uts));
+	TEST_RES(strcmp(uts.nodename, "sysctl-test"), _ret == 0);
+	TEST_SUCC(read_file(HOSTNAME_PATH));
+	TEST_RES(strcmp(buf, "sysctl-test\n"), _ret == 0);
+
+	TEST_SUCC(write_file(HOSTNAME_PATH, old_value));
+}
+END_TEST()
+
+FN_TEST(overcommit_memory)
+{
+	TEST_SUCC(read_file(OVERCOMMIT_PATH));
+	strcpy(old_value, buf);
+
+	TEST_ERRNO(write_file(OVERCOMMIT_PATH, "3"), EINVAL);
+	TEST_ERRNO(write_file(OVERCOMMIT_PATH, "foo"), EINVAL);
+	TEST_RES(write_file(OVERCOMMIT_PATH, "1"), _ret == 1);
+	TEST_SUCC(read_file(OVERCOMMIT_PATH));
+	TEST_RES(strcmp(buf, "1\n"), _ret == 0);
+
+	TEST_SUCC(write_file(OVERCOMMIT_PATH, old_value));
+}
+END_TEST()
+
+// A size that exceeds the memory and the swap space of the test machines.
+#define HUGE_SIZE (1UL << 42)
+#define SMALL_SIZE 4096
+
+static void *map_huge(int flags)
+{
+	return mmap(NULL, HUGE_SIZE, PROT_READ | PROT_WRITE,
+		    MAP_PRIVATE | MAP_ANONYMOUS | flags, -1, 0);
+}
+
+FN_TEST(overcommit_policy)
+{
+	void *addr;
+
+	TEST_SUCC(read_file(OVERCOMMIT_PATH));
+	strcpy(old_value, buf);
+
+	// Heuristic overcommit
+	TEST_RES(write_file(OVERCOMMIT_PATH, "0"), _ret == 1);
+	TEST_ERRNO(map_huge(0), ENOMEM);
+	addr = TEST_SUCC(map_huge(MAP_NORESERVE));
+	TEST_SUCC(munmap(addr, HUGE_SIZE));
+	addr = TEST_SUCC(mmap(NULL, HUGE_SIZE, PROT_READ,
+			      MAP_PRIVATE | MAP_ANONYMOUS, -1, 0));
+	TEST_SUCC(munmap(addr, HUGE_SIZE));
+
+	// Always overcommit
+	TEST_RES(write_file(OVERCOMMIT_PATH, "1"), _ret == 1);
+	addr = TEST_SUCC(map_huge(0));
+	TEST_SUCC(munmap(addr, HUGE_SIZE));
+
+	// Don't overcommit
+	TEST_RES(write_file(OVERCOMMIT_PATH, "2"), _ret == 1);
+	TEST_ERRNO(map_huge(MAP_NORESERVE), ENOMEM);
+	addr = TEST_SUCC(mmap(NULL, SMALL_SIZE, PROT_READ | PROT_WRITE,
+			      MAP_PRIVATE | MAP_ANONYMOUS, -1, 0));
+	TEST_SUCC(munmap(addr, SMALL_SIZE));
+
+	TEST_SUCC(write_file(OVERCOMMIT_PATH, old_value));
+}
+END_TEST()
+
+FN_TEST(file_max)
+{
+	int status;
+	pid_t pid;
+
+	TEST_SUCC(read_file(FILE_MAX_PATH));
+	strcpy(old_value, buf);
+
+	TEST_RES(write_file(FILE_MAX_PATH, "1"), _ret == 1);
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		// Without `CAP_SYS_ADMIN`, no more files can be opened.
+		CHECK(setuid(65534));
+		_exit(open("/", O_RDONLY) < 0 && errno == ENFILE ? 0 : 1);
+	}
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && WIFEXITED(status) && WEXITSTATUS(status) == 0);
+
+	// With `CAP_SYS_ADMIN`, files can still be opened.
+	TEST_SUCC(read_file(FILE_MAX_PATH));
+	TEST_RES(strcmp(buf, "1\n"), _ret == 0);
+
+	TEST_SUCC(write_file(FILE_MAX_PATH, old_value));
+}
+END_TEST()
+
+FN_TEST(tcp_rmem)
+{
+	TEST_SUCC(read_file(TCP_RMEM_PATH));
+	TEST_RES(count_ints(), _ret == 3);
+	strcpy(old_value, buf);
+
+	TEST_ERRNO(write_file(TCP_RMEM_PATH, "0 65536 6291456"), EINVAL);
+	TEST_ERRNO(write_file(TCP_RMEM_PATH, "65536 4096 6291456"), EINVAL);
+	TEST_ERRNO(write_file(TCP_RMEM_PATH, "4096 65536 8192"), EINVAL);
+	TEST_RES(write_file(TCP_RMEM_PATH, "4096 65536 6291456"), _ret > 0);
+	TEST_SUCC(read_file(TCP_RMEM_PATH));
+	TEST_RES(strcmp(buf, "4096\t65536\t6291456\n"), _ret == 0);
+	TEST_RES(tcp_rcvbuf(), _ret == 65536);
+
+	TEST_SUCC(write_file(TCP_RMEM_PATH, old_value));
+}
+END_TEST()
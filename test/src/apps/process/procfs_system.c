@@ -0,0 +1,160 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <arpa/inet.h>
+#include <fcntl.h>
+#include <netinet/in.h>
+#include <stdio.h>
+#include <sys/socket.h>
+#include <sys/un.h>
+#include <unistd.h>
+
+#include "../test.h"
+
+#define TCP_PORT 0x3039
+#define UDP_PORT 0x303A
+#define UNIX_PATH "/tmp/procfs_system_sock"
+
+static char buf[65536];
+
+static ssize_t read_file(const char *path)
+{
+	ssize_t len = 0, ret;
+	int fd;
+
+	fd = CHECK(open(path, O_RDONLY));
+	do {
+		ret = CHECK(read(fd, buf + len, sizeof(buf) - 1 - len));
+		len += ret;
+	} while (ret > 0);
+	CHECK(close(fd));
+	buf[len] = '\0';
+	return len;
+}
+
+static int bind_inet(int type, int port)
+{
+	struct sockaddr_in addr = {
+		.sin_family = AF_INET,
+		.sin_port = htons(port),
+		.sin_addr = { .s_addr = htonl(INADDR_LOOPBACK) },
+	};
+	int fd;
+
+	fd = CHECK(socket(AF_INET, type, 0));
+	CHECK(bind(fd, (struct sockaddr *)&addr, sizeof(addr)));
+	return fd;
+}
+
+// Returns the total CPU time in the `cpu` line of `/proc/stat`.
+static long total_cpu_time(void)
+{
+	unsigned long user, nice, system, idle;
+
+	if (sscanf(buf, "cpu  %lu %lu %lu %lu", &user, &nice, &system,
+		   &idle) != 4)
+		return -1;
+	return user + nice + system + idle;
+}
+
+// Returns whether `/proc/uptime` contains two valid times.
+static int is_valid_uptime(void)
+{
+	double uptime, idle;
+
+	return sscanf(buf, "%lf %lf", &uptime, &idle) == 2 && uptime > 0 &&
+	       idle >= 0;
+}
+
+FN_TEST(stat)
+{
+	TEST_SUCC(read_file("/proc/stat"));
+	TEST_RES(total_cpu_time(), _ret > 0);
+	TEST_RES(strstr(buf, "\ncpu0 "), _ret != NULL);
+	TEST_RES(strstr(buf, "\nintr "), _ret != NULL);
+	TEST_RES(strstr(buf, "\nbtime "), _ret != NULL);
+	TEST_RES(strstr(buf, "\nprocesses "), _ret != NULL);
+}
+END_TEST()
+
+FN_TEST(uptime)
+{
+	TEST_SUCC(read_file("/proc/uptime"));
+	TEST_RES(is_valid_uptime(), _ret);
+}
+END_TEST()
+
+FN_TEST(interrupts)
+{
+	TEST_SUCC(read_file("/proc/interrupts"));
+	TEST_RES(strstr(buf, "CPU0"), _ret != NULL);
+}
+END_TEST()
+
+FN_TEST(version)
+{
+	TEST_SUCC(read_file("/proc/version"));
+	TEST_RES(strncmp(buf, "Linux version ", 14), _ret == 0);
+}
+END_TEST()
+
+FN_TEST(mounts)
+{
+	char path[64];
+
+	TEST_RES(readlink("/proc/mounts", path, sizeof(path)),
+		 _ret == 11 && strncmp(path, "self/mounts", 11) == 0);
+	TEST_SUCC(read_file("/proc/mounts"));
+	TEST_RES(strstr(buf, " /proc proc "), _ret != NULL);
+}
+END_TEST()
+
+FN_TEST(net_tcp_and_udp)
+{
+	int tcp_fd, udp_fd;
+
+	tcp_fd = TEST_SUCC(bind_inet(SOCK_STREAM, TCP_PORT));
+	TEST_SUCC(listen(tcp_fd, 2));
+	udp_fd = TEST_SUCC(bind_inet(SOCK_DGRAM, UDP_PORT));
+
+	TEST_SUCC(read_file("/proc/net/tcp"));
+	TEST_RES(strstr(buf, "0100007F:3039 00000000:0000 0A "), _ret != NULL);
+	TEST_SUCC(read_file("/proc/net/udp"));
+	TEST_RES(strstr(buf, "0100007F:303A 00000000:0000 07 "), _ret != NULL);
+
+	TEST_SUCC(close(tcp_fd));
+	TEST_SUCC(close(udp_fd));
+
+	TEST_SUCC(read_file("/proc/net/tcp"));
+	TEST_RES(strstr(buf, "0100007F:3039"), _ret == NULL);
+}
+END_TEST()
+
+FN_TEST(net_unix)
+{
+	struct sockaddr_un addr = { .sun_family = AF_UNIX };
+	int fd;
+
+	strcpy(addr.sun_path, UNIX_PATH);
+	fd = TEST_SUCC(socket(AF_UNIX, SOCK_STREAM, 0));
+	TEST_SUCC(bind(fd, (struct sockaddr *)&addr, sizeof(addr)));
+	TEST_SUCC(listen(fd, 2));
+
+	TEST_SUCC(read_file("/proc/net/unix"));
+	TEST_RES(strstr(buf, " 00010000 0001 01 "), _ret != NULL);
+	TEST_RES(strstr(buf, UNIX_PATH "\n"), _ret != NULL);
+
+	TEST_SUCC(close(fd));
+	TEST_SUCC(unlink(UNIX_PATH));
+}
+END_TEST()
+
+FN_TEST(net_dev_and_route)
+{
+	TEST_SUCC(read_file("/proc/net/dev"));
+	TEST_RES(strstr(buf, "    lo:"), _ret != NULL);
+	TEST_SUCC(read_file("/proc/net/route"));
+	TEST_RES(strncmp(buf, "Iface\tDestination\tGateway", 25), _ret == 0);
+}
+END_TEST()
@@ -0,0 +1,360 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <errno.h>
+#include <signal.h>
+#include <stddef.h>
+#include <unistd.h>
+#include <sys/ptrace.h>
+#include <sys/syscall.h>
+#include <sys/user.h>
+#include <sys/wait.h>
+
+#include "../test.h"
+
+static volatile long value = 0x1234;
+
+static int status;
+
+#define IS_STOPPED_BY(sig) (WIFSTOPPED(status) && WSTOPSIG(status) == (sig))
+#define IS_EVENT_STOP(event) ((status >> 8) == (SIGTRAP | ((event) << 8)))
+#define IS_EXITED_WITH(code) \
+	(WIFEXITED(status) && WEXITSTATUS(status) == (code))
+
+// Forks a child that calls `PTRACE_TRACEME` and then stops itself.
+static pid_t fork_traced_child(void)
+{
+	pid_t pid;
+
+	pid = CHECK(fork());
+	if (pid == 0) {
+		CHECK(ptrace(PTRACE_TRACEME, 0, NULL, NULL));
+		CHECK(kill(getpid(), SIGSTOP));
+	}
+
+	return pid;
+}
+
+FN_TEST(invalid_args)
+{
+	pid_t pid;
+
+	TEST_ERRNO(ptrace(PTRACE_ATTACH, getpid(), NULL, NULL), EPERM);
+	TEST_ERRNO(ptrace(PTRACE_CONT, getpid(), NULL, NULL), ESRCH);
+
+	pid = CHECK(fork());
+	if (pid == 0) {
+		pause();
+		_exit(0);
+	}
+
+	// The child is not traced.
+	TEST_ERRNO(ptrace(PTRACE_CONT, pid, NULL, NULL), ESRCH);
+	TEST_ERRNO(ptrace(PTRACE_SEIZE, pid, NULL, (void *)-1), EIO);
+
+	// The child is traced but not stopped.
+	TEST_SUCC(ptrace(PTRACE_SEIZE, pid, NULL, NULL));
+	TEST_ERRNO(ptrace(PTRACE_ATTACH, pid, NULL, NULL), EPERM);
+	TEST_ERRNO(ptrace(PTRACE_CONT, pid, NULL, NULL), ESRCH);
+	TEST_ERRNO(ptrace(PTRACE_PEEKDATA, pid, &value, NULL), ESRCH);
+
+	TEST_SUCC(kill(pid, SIGKILL));
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && WIFSIGNALED(status) &&
+			 WTERMSIG(status) == SIGKILL);
+}
+END_TEST()
+
+FN_TEST(peek_and_poke)
+{
+	pid_t pid;
+
+	pid = fork_traced_child();
+	if (pid == 0)
+		_exit(value == 0x5678 ? 0 : 1);
+
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && IS_STOPPED_BY(SIGSTOP));
+
+	TEST_RES(ptrace(PTRACE_PEEKDATA, pid, &value, NULL), _ret == 0x1234);
+	TEST_SUCC(ptrace(PTRACE_POKEDATA, pid, &value, (void *)0x5678));
+	TEST_ERRNO(ptrace(PTRACE_PEEKDATA, pid, NULL, NULL), EIO);
+	TEST_ERRNO(ptrace(-1, pid, NULL, NULL), EIO);
+	TEST_ERRNO(ptrace(PTRACE_SETOPTIONS, pid, NULL, (void *)-1), EINVAL);
+
+	// The value in the tracer does not change.
+	TEST_RES(value, _ret == 0x1234);
+
+	TEST_SUCC(ptrace(PTRACE_CONT, pid, NULL, NULL));
+	TEST_RES(waitpid(pid, &status, 0), _ret == pid && IS_EXITED_WITH(0));
+}
+END_TEST()
+
+FN_TEST(signal_delivery)
+{
+	pid_t pid;
+	siginfo_t info;
+
+	pid = fork_traced_child();
+	if (pid == 0) {
+		CHECK(raise(SIGUSR1));
+		CHECK(raise(SIGUSR2));
+		_exit(2);
+	}
+
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && IS_STOPPED_BY(SIGSTOP));
+	TEST_SUCC(ptrace(PTRACE_CONT, pid, NULL, NULL));
+
+	// Suppress `SIGUSR1`.
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && IS_STOPPED_BY(SIGUSR1));
+	TEST_RES(ptrace(PTRACE_GETSIGINFO, pid, NULL, &info),
+		 info.si_signo == SIGUSR1 && info.si_pid == pid);
+	TEST_SUCC(ptrace(PTRACE_CONT, pid, NULL, NULL));
+
+	// Deliver `SIGUSR2`, which terminates the child.
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && IS_STOPPED_BY(SIGUSR2));
+	TEST_SUCC(ptrace(PTRACE_CONT, pid, NULL, (void *)SIGUSR2));
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && WIFSIGNALED(status) &&
+			 WTERMSIG(status) == SIGUSR2);
+}
+END_TEST()
+
+FN_TEST(syscall_stops)
+{
+	pid_t pid;
+	int options = PTRACE_O_TRACESYSGOOD | PTRACE_O_TRACEEXIT;
+	unsigned long msg;
+
+	pid = fork_traced_child();
+	if (pid == 0) {
+		syscall(SYS_getppid);
+		_exit(3);
+	}
+
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && IS_STOPPED_BY(SIGSTOP));
+	TEST_SUCC(ptrace(PTRACE_SETOPTIONS, pid, NULL, (void *)(long)options));
+
+	// Stop at the entry and the exit of `getppid`.
+	TEST_SUCC(ptrace(PTRACE_SYSCALL, pid, NULL, NULL));
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && IS_STOPPED_BY(SIGTRAP | 0x80));
+#ifdef __x86_64__
+	TEST_RES(ptrace(PTRACE_PEEKUSER, pid,
+			offsetof(struct user_regs_struct, orig_rax), NULL),
+		 _ret == SYS_getppid);
+#endif
+	TEST_SUCC(ptrace(PTRACE_SYSCALL, pid, NULL, NULL));
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && IS_STOPPED_BY(SIGTRAP | 0x80));
+#ifdef __x86_64__
+	TEST_RES(ptrace(PTRACE_PEEKUSER, pid,
+			offsetof(struct user_regs_struct, rax), NULL),
+		 _ret == getpid());
+#endif
+
+	// Stop at the entry of `exit_group`.
+	TEST_SUCC(ptrace(PTRACE_SYSCALL, pid, NULL, NULL));
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && IS_STOPPED_BY(SIGTRAP | 0x80));
+
+	// Stop before exiting.
+	TEST_SUCC(ptrace(PTRACE_CONT, pid, NULL, NULL));
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && IS_EVENT_STOP(PTRACE_EVENT_EXIT));
+	TEST_RES(ptrace(PTRACE_GETEVENTMSG, pid, NULL, &msg),
+		 msg == (3 << 8));
+
+	TEST_SUCC(ptrace(PTRACE_CONT, pid, NULL, NULL));
+	TEST_RES(waitpid(pid, &status, 0), _ret == pid && IS_EXITED_WITH(3));
+}
+END_TEST()
+
+#ifdef __x86_64__
+FN_TEST(regs)
+{
+	pid_t pid;
+	struct user_regs_struct regs;
+
+	pid = fork_traced_child();
+	if (pid == 0)
+		_exit(syscall(SYS_getppid) == 100 ? 0 : 1);
+
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && IS_STOPPED_BY(SIGSTOP));
+
+	// Replace `getppid` with `getpid` at the entry.
+	TEST_SUCC(ptrace(PTRACE_SYSCALL, pid, NULL, NULL));
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && IS_STOPPED_BY(SIGTRAP));
+	TEST_RES(ptrace(PTRACE_GETREGS, pid, NULL, &regs),
+		 regs.orig_rax == SYS_getppid && regs.cs == 0x33);
+	regs.orig_rax = SYS_getpid;
+	TEST_SUCC(ptrace(PTRACE_SETREGS, pid, NULL, &regs));
+
+	// Replace the return value at the exit.
+	TEST_SUCC(ptrace(PTRACE_SYSCALL, pid, NULL, NULL));
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && IS_STOPPED_BY(SIGTRAP));
+	TEST_RES(ptrace(PTRACE_GETREGS, pid, NULL, &regs), regs.rax == pid);
+	regs.rax = 100;
+	TEST_SUCC(ptrace(PTRACE_SETREGS, pid, NULL, &regs));
+
+	TEST_SUCC(ptrace(PTRACE_CONT, pid, NULL, NULL));
+	TEST_RES(waitpid(pid, &status, 0), _ret == pid && IS_EXITED_WITH(0));
+}
+END_TEST()
+
+FN_TEST(skip_syscall)
+{
+	pid_t pid;
+
+	pid = fork_traced_child();
+	if (pid == 0)
+		_exit(syscall(SYS_getppid) == -1 && errno == ENOSYS ? 0 : 1);
+
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && IS_STOPPED_BY(SIGSTOP));
+
+	// Skip `getppid` at the entry.
+	TEST_SUCC(ptrace(PTRACE_SYSCALL, pid, NULL, NULL));
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && IS_STOPPED_BY(SIGTRAP));
+	TEST_SUCC(ptrace(PTRACE_POKEUSER, pid,
+			 offsetof(struct user_regs_struct, orig_rax),
+			 (void *)-1L));
+
+	// The skipped system call still stops at the exit.
+	TEST_SUCC(ptrace(PTRACE_SYSCALL, pid, NULL, NULL));
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && IS_STOPPED_BY(SIGTRAP));
+	TEST_RES(ptrace(PTRACE_PEEKUSER, pid,
+			offsetof(struct user_regs_struct, rax), NULL),
+		 _ret == -ENOSYS);
+
+	TEST_SUCC(ptrace(PTRACE_CONT, pid, NULL, NULL));
+	TEST_RES(waitpid(pid, &status, 0), _ret == pid && IS_EXITED_WITH(0));
+}
+END_TEST()
+#endif
+
+FN_TEST(seize_and_interrupt)
+{
+	pid_t pid;
+
+	pid = CHECK(fork());
+	if (pid == 0) {
+		for (;;)
+			pause();
+	}
+
+	TEST_SUCC(ptrace(PTRACE_SEIZE, pid, NULL, NULL));
+	TEST_SUCC(ptrace(PTRACE_INTERRUPT, pid, NULL, NULL));
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && IS_EVENT_STOP(PTRACE_EVENT_STOP));
+
+	// The interrupted `pause` is restarted after the child is detached.
+	TEST_SUCC(ptrace(PTRACE_DETACH, pid, NULL, NULL));
+	TEST_ERRNO(ptrace(PTRACE_INTERRUPT, pid, NULL, NULL), ESRCH);
+	TEST_RES(waitpid(pid, &status, WNOHANG), _ret == 0);
+
+	TEST_SUCC(kill(pid, SIGKILL));
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && WIFSIGNALED(status) &&
+			 WTERMSIG(status) == SIGKILL);
+}
+END_TEST()
+
+FN_TEST(trace_fork)
+{
+	pid_t pid, grandchild;
+	unsigned long msg;
+
+	pid = fork_traced_child();
+	if (pid == 0) {
+		sigset_t mask;
+
+		// Block `SIGCHLD` so that it does not cause a stop.
+		CHECK(sigemptyset(&mask));
+		CHECK(sigaddset(&mask, SIGCHLD));
+		CHECK(sigprocmask(SIG_BLOCK, &mask, NULL));
+
+		if (CHECK(fork()) == 0)
+			_exit(4);
+		CHECK(wait(NULL));
+		_exit(5);
+	}
+
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && IS_STOPPED_BY(SIGSTOP));
+	TEST_SUCC(ptrace(PTRACE_SETOPTIONS, pid, NULL,
+			 (void *)PTRACE_O_TRACEFORK));
+	TEST_SUCC(ptrace(PTRACE_CONT, pid, NULL, NULL));
+
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && IS_EVENT_STOP(PTRACE_EVENT_FORK));
+	TEST_SUCC(ptrace(PTRACE_GETEVENTMSG, pid, NULL, &msg));
+	grandchild = msg;
+
+	// The grandchild is traced and starts with `SIGSTOP`.
+	TEST_RES(waitpid(grandchild, &status, 0),
+		 _ret == grandchild && IS_STOPPED_BY(SIGSTOP));
+	TEST_SUCC(ptrace(PTRACE_DETACH, grandchild, NULL, NULL));
+	TEST_SUCC(ptrace(PTRACE_CONT, pid, NULL, NULL));
+
+	TEST_RES(waitpid(pid, &status, 0), _ret == pid && IS_EXITED_WITH(5));
+}
+END_TEST()
+
+FN_TEST(trace_exit_of_non_child)
+{
+	pid_t pid, grandchild;
+	unsigned long msg;
+
+	pid = fork_traced_child();
+	if (pid == 0) {
+		sigset_t mask;
+		pid_t child;
+		int child_status;
+
+		// Block `SIGCHLD` so that it does not cause a stop.
+		CHECK(sigemptyset(&mask));
+		CHECK(sigaddset(&mask, SIGCHLD));
+		CHECK(sigprocmask(SIG_BLOCK, &mask, NULL));
+
+		child = CHECK(fork());
+		if (child == 0)
+			_exit(6);
+		CHECK(waitpid(child, &child_status, 0));
+		_exit(WIFEXITED(child_status) ? WEXITSTATUS(child_status) : 0);
+	}
+
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && IS_STOPPED_BY(SIGSTOP));
+	TEST_SUCC(ptrace(PTRACE_SETOPTIONS, pid, NULL,
+			 (void *)PTRACE_O_TRACEFORK));
+	TEST_SUCC(ptrace(PTRACE_CONT, pid, NULL, NULL));
+
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && IS_EVENT_STOP(PTRACE_EVENT_FORK));
+	TEST_SUCC(ptrace(PTRACE_GETEVENTMSG, pid, NULL, &msg));
+	grandchild = msg;
+	TEST_RES(waitpid(grandchild, &status, 0),
+		 _ret == grandchild && IS_STOPPED_BY(SIGSTOP));
+
+	// The exit of the traced grandchild is reported to the tracer.
+	TEST_SUCC(ptrace(PTRACE_CONT, grandchild, NULL, NULL));
+	TEST_RES(waitpid(grandchild, &status, 0),
+		 _ret == grandchild && IS_EXITED_WITH(6));
+	TEST_ERRNO(waitpid(grandchild, &status, WNOHANG), ECHILD);
+
+	// The exit is also reported to the parent.
+	TEST_SUCC(ptrace(PTRACE_CONT, pid, NULL, NULL));
+	TEST_RES(waitpid(pid, &status, 0), _ret == pid && IS_EXITED_WITH(6));
+}
+END_TEST()
@@ -57,11 +57,22 @@ echo "Start ext2 fs test......"
 test_ext2 "/ext2" "test_file.txt"
 echo "All ext2 fs test passed."
 
+echo "Start ext4 fs test......"
+ext4/ext4
+echo "All ext4 fs test passed."
+
 echo "Start fdatasync test......"
 test_fdatasync
 echo "All fdatasync test passed."
 
+echo "Start writeback test......"
+writeback/writeback
+echo "All writeback test passed."
+
 pipe/pipe_err
 pipe/short_rw
+pipe/splice
 epoll/epoll_err
 epoll/poll_err
+inotify/inotify
+fanotify/fanotify
@@ -34,11 +34,17 @@ sleep 0.2
 ./tcp_poll
 ./tcp_reuseaddr
 ./udp_err
+./raw_ping
+./packet
+./ipv6
 ./unix_stream_err
 ./unix_seqpacket_err
+./unix_datagram_err
+./ip_multicast
 
 ./netlink_route
 ./rtnl_err
+./rtnl_config
 ./uevent_err
 
 echo "All network test passed"
@@ -25,6 +25,8 @@ getcpu/getcpu
 getpid/getpid
 hello_pie/hello
 hello_world/hello_world
+ipc/posix_mqueue
+ipc/sysv_msg
 itimer/setitimer
 itimer/timer_create
 mmap/mmap_and_fork
@@ -33,21 +35,31 @@ mmap/mmap_beyond_the_file
 mmap/mmap_shared_filebacked
 mmap/mmap_readahead
 mmap/mmap_vmrss
+namespace/namespace
+namespace/mount_propagation
+namespace/pivot_root
 process/group_session
 process/job_control
+process/oom_kill
 process/pidfd
+process/procfs_pid
+process/procfs_sysctl
+process/procfs_system
 process/wait4
+ptrace/ptrace
 pthread/pthread_test
 pty/open_pty
 pty/pty_blocking
 sched/sched_attr
 sched/sched_attr_idle
 shm/posix_shm
+shm/sysv_shm
 signal_c/parent_death_signal
 signal_c/sigaltstack
 signal_c/signal_fpu
 signal_c/signal_test
 signal_c/signal_test2
+swap/swap
 "
 
 for testcase in ${tests}
@@ -0,0 +1,15 @@
+#!/bin/sh
+
+# SPDX-License-Identifier: MPL-2.0
+
+# To successfully run the DHCP test, the kernel should be booted with `ip=dhcp`
+# on the QEMU user-mode network (i.e., `make run AUTO_TEST=dhcp NETDEV=user`).
+
+set -e
+
+NETTEST_DIR=/test/network
+cd ${NETTEST_DIR}
+
+echo "Start DHCP test......"
+./dhcp_client
+echo "DHCP test passed."
@@ -0,0 +1,132 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#include "../test.h"
+
+#include <unistd.h>
+#include <sys/ipc.h>
+#include <sys/mman.h>
+#include <sys/shm.h>
+#include <sys/wait.h>
+
+#define SHM_KEY 0x5348
+#define SHM_SIZE 0x2000
+
+static int shm_id;
+static char *shm_addr;
+
+FN_TEST(shmget)
+{
+	shm_id = TEST_SUCC(shmget(SHM_KEY, SHM_SIZE, IPC_CREAT | 0600));
+	TEST_RES(shmget(SHM_KEY, SHM_SIZE, 0600), _ret == shm_id);
+	TEST_ERRNO(shmget(SHM_KEY, SHM_SIZE, IPC_CREAT | IPC_EXCL | 0600),
+		   EEXIST);
+	TEST_ERRNO(shmget(SHM_KEY, SHM_SIZE * 2, 0600), EINVAL);
+	TEST_ERRNO(shmget(SHM_KEY + 1, SHM_SIZE, 0600), ENOENT);
+	TEST_ERRNO(shmget(IPC_PRIVATE, 0, 0600), EINVAL);
+
+	// Keys are not IDs, so keys larger than `SHMMNI` can be used.
+	TEST_RES(shmget(0x7fffffff, SHM_SIZE, IPC_CREAT | 0600),
+		 _ret > 0 && _ret != shm_id &&
+			 shmctl(_ret, IPC_RMID, NULL) == 0);
+}
+END_TEST()
+
+FN_TEST(shmat)
+{
+	shm_addr = TEST_SUCC(shmat(shm_id, NULL, 0));
+	TEST_ERRNO(shmat(shm_id, shm_addr + 1, 0), EINVAL);
+	TEST_ERRNO(shmat(shm_id, shm_addr, 0), EINVAL);
+	TEST_ERRNO(shmat(-1, NULL, 0), EINVAL);
+}
+END_TEST()
+
+FN_TEST(share_with_child)
+{
+	int status;
+	pid_t pid;
+
+	shm_addr[0] = 'a';
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		struct shmid_ds ds;
+		char *addr;
+
+		// The attach of the parent is inherited.
+		if (shmctl(shm_id, IPC_STAT, &ds) < 0 || ds.shm_nattch != 2)
+			exit(EXIT_FAILURE);
+
+		addr = shmat(shm_id, NULL, SHM_RDONLY);
+		if (addr == (void *)-1 || addr[0] != 'a')
+			exit(EXIT_FAILURE);
+		if (shmctl(shm_id, IPC_STAT, &ds) < 0 || ds.shm_nattch != 3)
+			exit(EXIT_FAILURE);
+		shm_addr[SHM_SIZE - 1] = 'b';
+		exit(EXIT_SUCCESS);
+	}
+
+	TEST_RES(wait4(pid, &status, 0, NULL),
+		 _ret == pid && WIFEXITED(status) && WEXITSTATUS(status) == 0);
+	TEST_RES(shm_addr[SHM_SIZE - 1], _ret == 'b');
+}
+END_TEST()
+
+FN_TEST(shmctl_stat_and_set)
+{
+	struct shmid_ds ds;
+
+	TEST_RES(shmctl(shm_id, IPC_STAT, &ds),
+		 ds.shm_segsz == SHM_SIZE && ds.shm_nattch == 1 &&
+			 ds.shm_cpid == getpid() &&
+			 ds.shm_perm.__key == SHM_KEY &&
+			 (ds.shm_perm.mode & 0777) == 0600);
+
+	// Splitting the attached region does not add attaches.
+	TEST_SUCC(mprotect(shm_addr, SHM_SIZE / 2, PROT_READ));
+	TEST_RES(shmctl(shm_id, IPC_STAT, &ds), ds.shm_nattch == 1);
+	TEST_SUCC(mprotect(shm_addr, SHM_SIZE / 2, PROT_READ | PROT_WRITE));
+
+	ds.shm_perm.mode = 0640;
+	TEST_SUCC(shmctl(shm_id, IPC_SET, &ds));
+	TEST_RES(shmctl(shm_id, IPC_STAT, &ds),
+		 (ds.shm_perm.mode & 0777) == 0640);
+}
+END_TEST()
+
+FN_TEST(shmctl_rmid)
+{
+	struct shmid_ds ds;
+	int new_shm_id;
+
+	TEST_SUCC(shmctl(shm_id, IPC_RMID, NULL));
+
+	// The segment remains accessible until it is detached.
+	TEST_RES(shm_addr[0], _ret == 'a');
+	TEST_RES(shmctl(shm_id, IPC_STAT, &ds),
+		 ds.shm_nattch == 1 && ds.shm_perm.__key == IPC_PRIVATE &&
+			 (ds.shm_perm.mode & SHM_DEST) != 0);
+	TEST_ERRNO(shmget(SHM_KEY, SHM_SIZE, 0600), ENOENT);
+
+	// The key can be reused after the removal.
+	new_shm_id = TEST_SUCC(
+		shmget(SHM_KEY, SHM_SIZE, IPC_CREAT | IPC_EXCL | 0600));
+	TEST_SUCC(shmctl(new_shm_id, IPC_RMID, NULL));
+}
+END_TEST()
+
+FN_TEST(shmdt)
+{
+	struct shmid_ds ds;
+
+	// A partially unmapped attach is still attached.
+	TEST_SUCC(munmap(shm_addr + SHM_SIZE / 2, SHM_SIZE / 2));
+	TEST_RES(shmctl(shm_id, IPC_STAT, &ds), ds.shm_nattch == 1);
+
+	TEST_ERRNO(shmdt(shm_addr + 1), EINVAL);
+	TEST_SUCC(shmdt(shm_addr));
+	TEST_ERRNO(shmdt(shm_addr), EINVAL);
+
+	// The removed segment is destroyed on the last detach.
+	TEST_ERRNO(shmctl(shm_id, IPC_STAT, &ds), EINVAL);
+}
+END_TEST()
@@ -0,0 +1,249 @@
+// SPDX-License-Identifier: MPL-2.0
+
+// The test swaps to a file on `/ext2`. The pages are swapped out under the memory
+// pressure made by a child process, which is killed by the OOM killer at last.
+
+#define _GNU_SOURCE
+
+#include <fcntl.h>
+#include <signal.h>
+#include <stdint.h>
+#include <sys/mman.h>
+#include <sys/swap.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#include "../test.h"
+
+#define SWAP_FILE "/ext2/test_swapfile"
+#define SWAP_PAGE_SIZE 4096
+#define SWAP_NR_PAGES 4096
+
+// The layout of the first page of a swap area made by `mkswap`.
+#define SWAP_HEADER_INFO_OFFSET 1024
+#define SWAP_MAGIC "SWAPSPACE2"
+
+// The private pages that are swapped out and checked.
+#define NR_PAGES 1024
+#define REGION_SIZE (NR_PAGES * SWAP_PAGE_SIZE)
+
+// A size that exceeds the memory and the swap space of the test machines.
+#define HUGE_SIZE (1UL << 40)
+
+struct swap_header_info {
+	uint32_t version;
+	uint32_t last_page;
+	uint32_t nr_badpages;
+	uint8_t uuid[16];
+	uint8_t volume_name[16];
+};
+
+static char page[SWAP_PAGE_SIZE];
+static char meminfo[1024];
+
+static char *region_a;
+static char *region_b;
+
+static int make_swap_file(void)
+{
+	struct swap_header_info *info;
+	int fd, i;
+
+	fd = CHECK(open(SWAP_FILE, O_WRONLY | O_CREAT | O_TRUNC, 0600));
+
+	memset(page, 0, sizeof(page));
+	info = (struct swap_header_info *)(page + SWAP_HEADER_INFO_OFFSET);
+	info->version = 1;
+	info->last_page = SWAP_NR_PAGES - 1;
+	memcpy(page + SWAP_PAGE_SIZE - strlen(SWAP_MAGIC), SWAP_MAGIC,
+	       strlen(SWAP_MAGIC));
+	CHECK_WITH(write(fd, page, sizeof(page)), _ret == sizeof(page));
+
+	// The swap file must not have holes.
+	memset(page, 0, sizeof(page));
+	for (i = 1; i < SWAP_NR_PAGES; i++)
+		CHECK_WITH(write(fd, page, sizeof(page)), _ret == sizeof(page));
+
+	CHECK(fsync(fd));
+	return fd;
+}
+
+// Returns the field of `/proc/meminfo` in kB.
+static long meminfo_kb(const char *name)
+{
+	ssize_t len;
+	char *field;
+	int fd;
+
+	fd = CHECK(open("/proc/meminfo", O_RDONLY));
+	len = CHECK(read(fd, meminfo, sizeof(meminfo) - 1));
+	CHECK(close(fd));
+	meminfo[len] = '\0';
+
+	field = strstr(meminfo, name);
+	if (field == NULL)
+		return -1;
+	return atol(field + strlen(name));
+}
+
+static long swap_used_kb(void)
+{
+	return meminfo_kb("SwapTotal:") - meminfo_kb("SwapFree:");
+}
+
+static void set_oom_score_adj(const char *value)
+{
+	int fd;
+
+	fd = CHECK(open("/proc/self/oom_score_adj", O_WRONLY));
+	CHECK_WITH(write(fd, value, strlen(value)), _ret == strlen(value));
+	CHECK(close(fd));
+}
+
+static char *map_region(int seed)
+{
+	char *addr;
+	size_t i;
+
+	addr = CHECK_WITH(mmap(NULL, REGION_SIZE, PROT_READ | PROT_WRITE,
+			       MAP_PRIVATE | MAP_ANONYMOUS, -1, 0),
+			  _ret != MAP_FAILED);
+	for (i = 0; i < REGION_SIZE; i++)
+		addr[i] = (char)(i * 7 + seed);
+	return addr;
+}
+
+static int check_region(const char *addr, int seed)
+{
+	size_t i;
+
+	for (i = 0; i < REGION_SIZE; i++)
+		if (addr[i] != (char)(i * 7 + seed))
+			return -1;
+	return 0;
+}
+
+// Makes the memory pressure, under which the pages are swapped out until the swap
+// area is full.
+static int make_memory_pressure(void)
+{
+	int status;
+	pid_t pid;
+	char *addr;
+
+	pid = CHECK(fork());
+	if (pid == 0) {
+		set_oom_score_adj("1000");
+		addr = CHECK_WITH(mmap(NULL, HUGE_SIZE, PROT_READ | PROT_WRITE,
+				       MAP_PRIVATE | MAP_ANONYMOUS |
+					       MAP_NORESERVE,
+				       -1, 0),
+				  _ret != MAP_FAILED);
+		for (size_t offset = 0; offset < HUGE_SIZE;
+		     offset += SWAP_PAGE_SIZE)
+			addr[offset] = 1;
+		_exit(1);
+	}
+
+	CHECK_WITH(waitpid(pid, &status, 0), _ret == pid);
+	if (!WIFSIGNALED(status) || WTERMSIG(status) != SIGKILL)
+		return -1;
+	return 0;
+}
+
+FN_SETUP(swapon)
+{
+	// This process must survive the memory pressure.
+	set_oom_score_adj("-1000");
+
+	CHECK(close(make_swap_file()));
+	CHECK(swapon(SWAP_FILE, 0));
+
+	region_a = map_region(1);
+	region_b = map_region(2);
+}
+END_SETUP()
+
+FN_TEST(swap_file_is_busy)
+{
+	int fd;
+
+	fd = TEST_SUCC(open(SWAP_FILE, O_WRONLY));
+	TEST_ERRNO(write(fd, page, sizeof(page)), ETXTBSY);
+	TEST_ERRNO(pwrite(fd, page, sizeof(page), SWAP_PAGE_SIZE), ETXTBSY);
+	TEST_ERRNO(ftruncate(fd, 0), ETXTBSY);
+	TEST_ERRNO(fallocate(fd, 0, 0, 2 * SWAP_PAGE_SIZE * SWAP_NR_PAGES),
+		   ETXTBSY);
+	TEST_SUCC(close(fd));
+
+	TEST_ERRNO(truncate(SWAP_FILE, 0), ETXTBSY);
+	TEST_ERRNO(open(SWAP_FILE, O_WRONLY | O_TRUNC), ETXTBSY);
+	TEST_ERRNO(swapon(SWAP_FILE, 0), EBUSY);
+}
+END_TEST()
+
+FN_TEST(swap_out)
+{
+	TEST_SUCC(make_memory_pressure());
+	TEST_RES(swap_used_kb(), _ret > 0);
+}
+END_TEST()
+
+FN_TEST(swap_in_after_fork)
+{
+	int status;
+	pid_t pid;
+
+	// The swap entries are shared with the child, which swaps in its own pages.
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		if (check_region(region_a, 1) < 0 ||
+		    check_region(region_b, 2) < 0)
+			_exit(1);
+		memset(region_a, 0, REGION_SIZE);
+		_exit(0);
+	}
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && WIFEXITED(status) && WEXITSTATUS(status) == 0);
+}
+END_TEST()
+
+FN_TEST(swap_in_after_mremap)
+{
+	char *new_addr;
+
+	// The swap entries are moved with the pages, which are swapped in at
+	// the new address.
+	new_addr = TEST_SUCC(mremap(region_a, REGION_SIZE, 2 * REGION_SIZE,
+				    MREMAP_MAYMOVE));
+	region_a = new_addr;
+	TEST_SUCC(check_region(region_a, 1));
+}
+END_TEST()
+
+FN_TEST(swapoff_with_live_entries)
+{
+	// The pages of `region_b` in the parent have not been swapped in yet.
+	TEST_RES(swap_used_kb(), _ret > 0);
+	TEST_SUCC(swapoff(SWAP_FILE));
+	TEST_RES(meminfo_kb("SwapTotal:"), _ret == 0);
+	TEST_ERRNO(swapoff(SWAP_FILE), EINVAL);
+
+	TEST_SUCC(check_region(region_b, 2));
+	TEST_SUCC(check_region(region_a, 1));
+}
+END_TEST()
+
+FN_TEST(swap_file_is_writable_after_swapoff)
+{
+	int fd;
+
+	fd = TEST_SUCC(open(SWAP_FILE, O_WRONLY));
+	TEST_RES(write(fd, page, sizeof(page)), _ret == sizeof(page));
+	TEST_SUCC(close(fd));
+	TEST_SUCC(unlink(SWAP_FILE));
+
+	TEST_SUCC(munmap(region_a, 2 * REGION_SIZE));
+	TEST_SUCC(munmap(region_b, REGION_SIZE));
+}
+END_TEST()
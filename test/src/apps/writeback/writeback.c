@@ -0,0 +1,264 @@
+// SPDX-License-Identifier: MPL-2.0
+
+// The test writes the files on `/ext2`, whose dirty pages are accounted in the
+// `Dirty` field of `/proc/meminfo`.
+
+#define _GNU_SOURCE
+
+#include <fcntl.h>
+#include <linux/capability.h>
+#include <sys/syscall.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#include "../test.h"
+
+#define BACKGROUND_RATIO_PATH "/proc/sys/vm/dirty_background_ratio"
+#define RATIO_PATH "/proc/sys/vm/dirty_ratio"
+#define EXPIRE_PATH "/proc/sys/vm/dirty_expire_centisecs"
+#define WRITEBACK_PATH "/proc/sys/vm/dirty_writeback_centisecs"
+
+#define TEST_FILE "/ext2/test_writeback"
+#define TEST_FILE_SIZE (256 * 1024)
+#define TEST_FILE_KB (TEST_FILE_SIZE / 1024)
+
+#define MAX_WAIT_MS 5000
+#define WAIT_STEP_MS 10
+
+static char buf[TEST_FILE_SIZE];
+
+static char old_background_ratio[32];
+static char old_ratio[32];
+static char old_expire[32];
+static char old_writeback[32];
+
+static ssize_t read_file(const char *path, char *value, size_t size)
+{
+	ssize_t len;
+	int fd;
+
+	fd = CHECK(open(path, O_RDONLY));
+	len = CHECK(read(fd, value, size - 1));
+	CHECK(close(fd));
+	value[len] = '\0';
+	return len;
+}
+
+static ssize_t write_file(const char *path, const char *value)
+{
+	ssize_t len;
+	int fd;
+
+	fd = CHECK(open(path, O_WRONLY));
+	len = write(fd, value, strlen(value));
+	CHECK(close(fd));
+	return len;
+}
+
+static long read_long(const char *path)
+{
+	char value[32];
+
+	read_file(path, value, sizeof(value));
+	return atol(value);
+}
+
+// Returns the `Dirty` field of `/proc/meminfo` in kB.
+static long dirty_kb(void)
+{
+	static char meminfo[1024];
+	char *field;
+
+	read_file("/proc/meminfo", meminfo, sizeof(meminfo));
+	field = strstr(meminfo, "Dirty:");
+	if (field == NULL)
+		return -1;
+	return atol(field + strlen("Dirty:"));
+}
+
+// Writes the test file without syncing it, and returns its file descriptor.
+static int write_test_file(void)
+{
+	int fd;
+
+	fd = CHECK(open(TEST_FILE, O_RDWR | O_CREAT | O_TRUNC, 0644));
+	memset(buf, 'a', sizeof(buf));
+	CHECK_WITH(write(fd, buf, sizeof(buf)), _ret == sizeof(buf));
+	return fd;
+}
+
+// Waits until the dirty pages of the test file are written back.
+static int wait_for_writeback(void)
+{
+	int ms;
+
+	for (ms = 0; ms < MAX_WAIT_MS; ms += WAIT_STEP_MS) {
+		if (dirty_kb() < TEST_FILE_KB)
+			return 0;
+		usleep(WAIT_STEP_MS * 1000);
+	}
+	return -1;
+}
+
+FN_SETUP(save_knobs)
+{
+	read_file(BACKGROUND_RATIO_PATH, old_background_ratio,
+		  sizeof(old_background_ratio));
+	read_file(RATIO_PATH, old_ratio, sizeof(old_ratio));
+	read_file(EXPIRE_PATH, old_expire, sizeof(old_expire));
+	read_file(WRITEBACK_PATH, old_writeback, sizeof(old_writeback));
+
+	// Starts from a clean state, with neither the periodic writeback nor
+	// the background writeback.
+	CHECK(write_file(WRITEBACK_PATH, "0"));
+	CHECK(write_file(BACKGROUND_RATIO_PATH, "100"));
+	CHECK(write_file(RATIO_PATH, "100"));
+	sync();
+}
+END_SETUP()
+
+FN_TEST(ratio_knobs)
+{
+	TEST_ERRNO(write_file(BACKGROUND_RATIO_PATH, "101"), EINVAL);
+	TEST_ERRNO(write_file(BACKGROUND_RATIO_PATH, "-1"), EINVAL);
+	TEST_ERRNO(write_file(RATIO_PATH, "101"), EINVAL);
+	TEST_ERRNO(write_file(RATIO_PATH, "-1"), EINVAL);
+	TEST_ERRNO(write_file(RATIO_PATH, "foo"), EINVAL);
+
+	TEST_RES(write_file(RATIO_PATH, "0"), _ret == 1);
+	TEST_RES(read_long(RATIO_PATH), _ret == 0);
+	TEST_RES(write_file(RATIO_PATH, "100"), _ret == 3);
+	TEST_RES(read_long(RATIO_PATH), _ret == 100);
+	TEST_RES(read_long(BACKGROUND_RATIO_PATH), _ret == 100);
+}
+END_TEST()
+
+FN_TEST(centisecs_knobs)
+{
+	TEST_ERRNO(write_file(EXPIRE_PATH, "-1"), EINVAL);
+	TEST_ERRNO(write_file(EXPIRE_PATH, "2147483648"), EINVAL);
+	TEST_ERRNO(write_file(WRITEBACK_PATH, "-1"), EINVAL);
+	TEST_ERRNO(write_file(WRITEBACK_PATH, "2147483648"), EINVAL);
+
+	TEST_RES(write_file(EXPIRE_PATH, "2147483647"), _ret == 10);
+	TEST_RES(read_long(EXPIRE_PATH), _ret == 2147483647);
+	TEST_RES(read_long(WRITEBACK_PATH), _ret == 0);
+}
+END_TEST()
+
+// Returns whether writing `value` to `path` is denied.
+static int is_write_denied(const char *path, const char *value)
+{
+	int fd, denied;
+
+	fd = open(path, O_WRONLY);
+	if (fd < 0)
+		return errno == EACCES;
+	denied = write(fd, value, strlen(value)) < 0 && errno == EPERM;
+	close(fd);
+	return denied;
+}
+
+static void drop_sys_admin(void)
+{
+	struct __user_cap_header_struct header = {
+		.version = _LINUX_CAPABILITY_VERSION_3,
+	};
+	struct __user_cap_data_struct data[2];
+
+	CHECK(syscall(SYS_capget, &header, data));
+	data[CAP_SYS_ADMIN / 32].effective &= ~(1U << (CAP_SYS_ADMIN % 32));
+	CHECK(syscall(SYS_capset, &header, data));
+}
+
+FN_TEST(knobs_need_sys_admin)
+{
+	int status;
+	pid_t pid;
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		drop_sys_admin();
+		if (read_long(RATIO_PATH) != 100)
+			_exit(1);
+		if (!is_write_denied(RATIO_PATH, "50") ||
+		    !is_write_denied(BACKGROUND_RATIO_PATH, "50") ||
+		    !is_write_denied(EXPIRE_PATH, "100") ||
+		    !is_write_denied(WRITEBACK_PATH, "100"))
+			_exit(1);
+		_exit(0);
+	}
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && WIFEXITED(status) && WEXITSTATUS(status) == 0);
+	TEST_RES(read_long(RATIO_PATH), _ret == 100);
+	TEST_RES(read_long(WRITEBACK_PATH), _ret == 0);
+}
+END_TEST()
+
+FN_TEST(syncfs)
+{
+	int fd, pipefd[2];
+
+	fd = TEST_SUCC(write_test_file());
+	TEST_RES(dirty_kb(), _ret >= TEST_FILE_KB);
+	TEST_SUCC(syncfs(fd));
+	TEST_RES(dirty_kb(), _ret < TEST_FILE_KB);
+	TEST_SUCC(close(fd));
+
+	// The files without a filesystem have nothing to sync.
+	TEST_SUCC(pipe(pipefd));
+	TEST_SUCC(syncfs(pipefd[0]));
+	TEST_SUCC(close(pipefd[0]));
+	TEST_SUCC(close(pipefd[1]));
+	TEST_ERRNO(syncfs(-1), EBADF);
+
+	TEST_SUCC(unlink(TEST_FILE));
+}
+END_TEST()
+
+FN_TEST(periodic_writeback)
+{
+	int fd;
+
+	// Without the periodic writeback, the dirty pages are kept.
+	TEST_RES(write_file(EXPIRE_PATH, "0"), _ret == 1);
+	fd = TEST_SUCC(write_test_file());
+	usleep(500 * 1000);
+	TEST_RES(dirty_kb(), _ret >= TEST_FILE_KB);
+
+	// The expired dirty pages are written back by the periodic writeback.
+	TEST_RES(write_file(WRITEBACK_PATH, "10"), _ret == 2);
+	TEST_SUCC(wait_for_writeback());
+
+	TEST_RES(write_file(WRITEBACK_PATH, "0"), _ret == 1);
+	TEST_SUCC(close(fd));
+	TEST_SUCC(unlink(TEST_FILE));
+	sync();
+}
+END_TEST()
+
+FN_TEST(background_writeback)
+{
+	int fd;
+
+	// The dirty pages that have not expired are written back only if they exceed
+	// the background threshold.
+	TEST_RES(write_file(EXPIRE_PATH, "2147483647"), _ret == 10);
+	TEST_RES(write_file(BACKGROUND_RATIO_PATH, "0"), _ret == 1);
+	fd = TEST_SUCC(write_test_file());
+	TEST_SUCC(wait_for_writeback());
+
+	TEST_SUCC(close(fd));
+	TEST_SUCC(unlink(TEST_FILE));
+	sync();
+}
+END_TEST()
+
+FN_TEST(restore_knobs)
+{
+	TEST_SUCC(write_file(BACKGROUND_RATIO_PATH, old_background_ratio));
+	TEST_SUCC(write_file(RATIO_PATH, old_ratio));
+	TEST_SUCC(write_file(EXPIRE_PATH, old_expire));
+	TEST_SUCC(write_file(WRITEBACK_PATH, old_writeback));
+}
+END_TEST()